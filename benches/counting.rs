@@ -0,0 +1,91 @@
+/// Benchmarks live in the `benches` directory and run with `cargo bench`.
+///
+/// Criterion plays a similar role to JMH in the Java world: it warms up,
+/// repeats each measurement many times, and reports statistically sound
+/// timings instead of a single stopwatch reading.
+#[macro_use]
+extern crate criterion;
+extern crate rust_for_java_devs;
+
+use criterion::{Criterion, BenchmarkId, Throughput};
+use rust_for_java_devs::counting::{count_characters, CountStrategy};
+
+/// Builds a cart of `size` items cycling through a handful of product codes.
+fn cart_of(size: usize) -> String {
+    "ABBACBBAB".chars().cycle().take(size).collect()
+}
+
+/// Compares the cost of counting the same carts with 32, 64 and 128 bit
+/// tallies. The Supermarket uses `u64`; the other widths show what the
+/// narrower and wider alternatives would cost.
+fn count_widths(c: &mut Criterion) {
+    let mut group = c.benchmark_group("count_characters");
+    for &size in &[1_000usize, 100_000, 1_000_000] {
+        let items = cart_of(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("u32", size), &items,
+                               |b, items| b.iter(|| count_characters::<u32>(items)));
+        group.bench_with_input(BenchmarkId::new("u64", size), &items,
+                               |b, items| b.iter(|| count_characters::<u64>(items)));
+        group.bench_with_input(BenchmarkId::new("u128", size), &items,
+                               |b, items| b.iter(|| count_characters::<u128>(items)));
+    }
+    group.finish();
+}
+
+/// Builds a cart of `size` items cycling through `alphabet`.
+fn cart_over(alphabet: &[char], size: usize) -> String {
+    alphabet.iter().cycle().take(size).collect()
+}
+
+/// Compares the ways of counting a cart, from a typical basket of ten items
+/// up to a hundred thousand, over alphabets of 3 and 26 ASCII codes and of
+/// 200 emoji, where the array strategy must fall back to the tree.
+fn count_strategies(c: &mut Criterion) {
+    // `char::from_u32` rejects surrogates, none of which are in this range.
+    let emoji: Vec<char> = (0x1F400..0x1F4C8).filter_map(char::from_u32).collect();
+    let uppercase: Vec<char> = ('A'..='Z').collect();
+    let alphabets = [("3-ascii", vec!['A', 'B', 'C']), ("26-ascii", uppercase), ("200-emoji", emoji)];
+    let strategies = [("tree", CountStrategy::Tree), ("array", CountStrategy::Array),
+                      ("hash", CountStrategy::Hash)];
+
+    for (alphabet_name, alphabet) in alphabets.iter() {
+        let mut group = c.benchmark_group(format!("count_strategies/{}", alphabet_name));
+        for &size in &[10usize, 30, 100, 1_000, 100_000] {
+            let items = cart_over(alphabet, size);
+            group.throughput(Throughput::Elements(size as u64));
+            for &(name, strategy) in strategies.iter() {
+                group.bench_with_input(BenchmarkId::new(name, size), &items,
+                                       |b, items| b.iter(|| strategy.count_items(items)));
+            }
+        }
+        group.finish();
+    }
+}
+
+/// Counts million-item carts with the `Hash` strategy, whose hasher the
+/// `fast-hash` feature replaces. Criterion compares every run with the one
+/// before, so run this once without the feature and once with it to see
+/// the difference:
+///
+/// ```text
+/// cargo bench --bench counting -- count_hash_million
+/// cargo bench --bench counting --features fast-hash -- count_hash_million
+/// ```
+fn count_hash_million(c: &mut Criterion) {
+    let size = 1_000_000;
+    let emoji: Vec<char> = (0x1F400..0x1F4C8).filter_map(char::from_u32).collect();
+    let uppercase: Vec<char> = ('A'..='Z').collect();
+    let mut group = c.benchmark_group("count_hash_million");
+    group.throughput(Throughput::Elements(size as u64));
+    group.sample_size(20);
+    for (name, alphabet) in [("26-ascii", uppercase), ("200-emoji", emoji)] {
+        let items = cart_over(&alphabet, size);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &items,
+                               |b, items| b.iter(|| CountStrategy::Hash.count_items(items)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, count_widths, count_strategies, count_hash_million);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+/// Compares the trait-object, enum-dispatch and statically dispatched
+/// Supermarkets on the same carts, so the cost of dynamic dispatch can be
+/// seen rather than guessed at.
+///
+/// `market_setup` builds a trait-object Supermarket from scratch for every
+/// short checkout, which is where the `smallvec` feature's rule storage
+/// should show; run it with and without `--features smallvec` to compare.
+///
+/// `reload` builds a thousand demo markets at once, as a chain reloading its
+/// catalog would; with `--features arena` it also builds them in an arena.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rust_for_java_devs::core::rules::FlatPrice;
+use rust_for_java_devs::reasonable_implementation::{self, BoxedRule, Market};
+use rust_for_java_devs::enum_implementation;
+use rust_for_java_devs::generic_implementation;
+use rust_for_java_devs::money::{Cents, Money};
+
+fn cart_of(size: usize) -> String {
+    "ABBACBBAB".chars().cycle().take(size).collect()
+}
+
+fn checkout_dispatch(c: &mut Criterion) {
+    let boxed: reasonable_implementation::Supermarket<Cents> =
+        reasonable_implementation::Supermarket::demo();
+    let closed: enum_implementation::Supermarket<Cents> =
+        enum_implementation::Supermarket::demo();
+    let generic = generic_implementation::Supermarket::<_, Cents>::demo();
+
+    let mut group = c.benchmark_group("checkout");
+    for &size in &[10usize, 1_000, 100_000] {
+        let items = cart_of(size);
+        group.bench_with_input(BenchmarkId::new("trait_objects", size), &items,
+                               |b, items| b.iter(|| boxed.checkout(items)));
+        group.bench_with_input(BenchmarkId::new("enum", size), &items,
+                               |b, items| b.iter(|| closed.checkout(items)));
+        group.bench_with_input(BenchmarkId::new("generic", size), &items,
+                               |b, items| b.iter(|| generic.checkout(items)));
+    }
+    group.finish();
+}
+
+fn market_setup(c: &mut Criterion) {
+    let items = cart_of(10);
+    let mut group = c.benchmark_group("market_setup");
+    for &rules in &[4u8, 8, 16] {
+        group.bench_with_input(BenchmarkId::new("trait_objects", rules), &rules, |b, &rules| {
+            b.iter(|| {
+                let market: reasonable_implementation::Supermarket<Cents> = (0..rules)
+                    .map(|i| {
+                        let rule = FlatPrice::new(char::from(b'A' + i), Cents::from_cents(100))
+                            .expect("prices are valid");
+                        Box::new(rule) as BoxedRule<Cents>
+                    })
+                    .collect();
+                market.checkout(&items)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn reload(c: &mut Criterion) {
+    const MARKETS: usize = 1_000;
+    let mut group = c.benchmark_group("reload");
+    group.bench_function("trait_objects", |b| b.iter(|| {
+        (0..MARKETS).map(|_| reasonable_implementation::Supermarket::<Cents>::demo())
+            .collect::<Vec<_>>()
+    }));
+    #[cfg(feature = "arena")]
+    {
+        use rust_for_java_devs::arena_implementation::{self, Bump};
+        let mut arena = Bump::new();
+        group.bench_function("arena", |b| b.iter(|| {
+            // `reset` keeps the arena's memory for the next round, as a real
+            // reload would; the markets are dropped before it runs.
+            let markets = (0..MARKETS)
+                .map(|_| arena_implementation::Supermarket::<Cents>::demo_in(&arena))
+                .collect::<Vec<_>>();
+            let built = markets.len();
+            drop(markets);
+            arena.reset();
+            built
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, checkout_dispatch, market_setup, reload);
+criterion_main!(benches);
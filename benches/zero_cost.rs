@@ -0,0 +1,47 @@
+/// Times each pair of functions in `zero_cost_abstractions`, the
+/// abstraction against what it abstracts over, on the same inputs.
+///
+/// `subtotal` should show the index loop and the iterator chain within
+/// noise of each other. `dispatch` shows what a vtable call costs when the
+/// work behind it is as small as a flat price: the static version inlines
+/// each rule into the loop, the dynamic one cannot.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rust_for_java_devs::core::counting::Counts;
+use rust_for_java_devs::core::rules::FlatPrice;
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::BoxedRule;
+use rust_for_java_devs::zero_cost_abstractions::*;
+
+fn subtotal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("subtotal");
+    for &lines in &[16usize, 1_024, 65_536] {
+        let prices: Vec<Cents> = (0..lines).map(|i| Cents(i as i64 % 5_000)).collect();
+        let quantities: Vec<u32> = (0..lines).map(|i| i as u32 % 7).collect();
+        group.bench_with_input(BenchmarkId::new("index", lines), &lines, |b, _| {
+            b.iter(|| subtotal_by_index(black_box(&prices), black_box(&quantities)))
+        });
+        group.bench_with_input(BenchmarkId::new("iterator", lines), &lines, |b, _| {
+            b.iter(|| subtotal_by_iterator(black_box(&prices), black_box(&quantities)))
+        });
+    }
+    group.finish();
+}
+
+fn dispatch(c: &mut Criterion) {
+    let products: Vec<char> = ('A'..='Z').collect();
+    let flat: Vec<FlatPrice<Cents>> = products.iter()
+        .map(|&product| FlatPrice::new(product, Cents(100)).expect("prices are valid"))
+        .collect();
+    let boxed: Vec<BoxedRule<Cents>> = flat.iter()
+        .map(|rule| Box::new(rule.clone()) as BoxedRule<Cents>)
+        .collect();
+    let counts: Counts = products.iter().map(|&product| (product, 3)).collect();
+
+    let mut group = c.benchmark_group("dispatch");
+    group.bench_function("static", |b| b.iter(|| total_static(black_box(&flat), black_box(&counts))));
+    group.bench_function("dynamic", |b| b.iter(|| total_dynamic(black_box(&boxed), black_box(&counts))));
+    group.finish();
+}
+
+criterion_group!(benches, subtotal, dispatch);
+criterion_main!(benches);
@@ -0,0 +1,75 @@
+//! Many tills sharing one Supermarket, each on its own thread.
+//!
+//! ```text
+//! $ cargo run --release --example concurrent_checkouts
+//! ```
+//!
+//! In Java, sharing a `Supermarket` between threads is allowed by default,
+//! and whether it is *safe* is up to the programmer: a rule with a mutable
+//! field and no lock compiles fine, and fails only sometimes, in
+//! production. Rust checks it when compiling. A type may be used from
+//! several threads at once only if it is `Sync`, and the compiler works that
+//! out from its fields; the rules in a `Supermarket` are boxed as
+//! `dyn PricingRule + Send + Sync`, so a rule that is not thread safe cannot
+//! be added to one in the first place.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Instant;
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+const TILLS: usize = 8;
+const CARTS_PER_TILL: usize = 100_000;
+
+fn main() {
+    let market: Supermarket = Supermarket::demo();
+
+    // The first way: scoped threads. `thread::scope` waits for every thread
+    // spawned in it before returning, so the threads may borrow `market`
+    // from this stack frame, with no `Arc` and no `final` copies, as Java's
+    // lambdas would need.
+    let started = Instant::now();
+    let takings: Vec<Cents> = thread::scope(|scope| {
+        let tills: Vec<_> = (0..TILLS).map(|till| {
+            let market = &market;
+            scope.spawn(move || {
+                let mut taken = Cents(0);
+                for n in 0..CARTS_PER_TILL {
+                    // Every till sees slightly different carts.
+                    let cart = "ABBACBBAB"[..1 + (till + n) % 9].to_string();
+                    taken.0 += market.try_checkout(cart).expect("the demo carts are small").0;
+                }
+                taken
+            })
+        }).collect();
+        // `join` waits for a thread and returns what its closure returned,
+        // like `Future.get()`. It is an `Err` only if the thread panicked.
+        tills.into_iter().map(|till| till.join().expect("no till panics")).collect()
+    });
+    let total: i64 = takings.iter().map(|taken| taken.0).sum();
+    println!("{} tills took {} in {:?}", TILLS, Cents(total), started.elapsed());
+
+    // The second way: threads that may outlive the function that started
+    // them, which cannot borrow from it. The Supermarket is moved into an
+    // `Arc`, a reference-counted pointer, and each thread gets a clone of the
+    // pointer, not of the Supermarket. A shared counter has to be atomic, as
+    // a Java `AtomicLong` would be; a plain `u64` would not compile here.
+    let market = Arc::new(market);
+    let carts = Arc::new(AtomicU64::new(0));
+    let handles: Vec<_> = (0..TILLS).map(|_| {
+        let market = Arc::clone(&market);
+        let carts = Arc::clone(&carts);
+        thread::spawn(move || {
+            for _ in 0..CARTS_PER_TILL / 10 {
+                market.try_checkout("ABBACBBAB").expect("the demo cart is small");
+                carts.fetch_add(1, Ordering::Relaxed);
+            }
+        })
+    }).collect();
+    for handle in handles {
+        handle.join().expect("no till panics");
+    }
+    println!("{} carts priced through an Arc", carts.load(Ordering::Relaxed));
+}
@@ -0,0 +1,94 @@
+//! Building a catalog in code, with a pricing rule of our own.
+//!
+//! ```text
+//! $ cargo run --example custom_catalog
+//! ```
+//!
+//! The built-in rules cover a price per item and bundles of one product. A
+//! meal deal, taking money off for every sandwich bought with a drink, spans
+//! two products, so we write it ourselves. In Java this would be a class
+//! implementing the `PricingRule` interface, and so `Describable`, which it
+//! extends; in Rust it is a struct with an `impl` block for each of the two
+//! traits, and nothing else changes.
+
+use rust_for_java_devs::core::counting::Counts;
+use rust_for_java_devs::money::{Cents, Money, Overflow, OverflowPolicy};
+use rust_for_java_devs::reasonable_implementation::{
+    BoxedRule, BundlePrice, Describable, FlatPrice, PricingRule, Supermarket
+};
+
+/// Takes `discount` off for every pair of one `main` and one `side`.
+///
+/// A Java class would need a constructor, getters and perhaps `equals`; a
+/// Rust struct whose fields are all public can be built with a literal,
+/// `MealDeal { main: 'S', .. }`, and `derive` writes the rest.
+#[derive(Clone, Debug)]
+struct MealDeal {
+    main: char,
+    side: char,
+    discount: Cents
+}
+
+impl Describable for MealDeal {
+    fn describe(&self) -> String {
+        format!("{} with {}: {} off", self.main, self.side, self.discount)
+    }
+}
+
+impl PricingRule<Cents> for MealDeal {
+
+    /// The price of a meal deal is negative: it is a discount on prices the
+    /// other rules have already charged. The trait allows that, as its
+    /// documentation says.
+    fn price(&self, counts: &Counts, policy: OverflowPolicy) -> Result<Cents, Overflow> {
+        // `copied` turns the `Option<&u64>` that `get` returns into an
+        // `Option<u64>`, and `unwrap_or` supplies the count of a product
+        // that is not in the cart at all.
+        let count = |product| counts.get(&product).copied().unwrap_or(0);
+        let deals = count(self.main).min(count(self.side));
+        policy.times(Cents(-self.discount.0), deals)
+    }
+
+    /// Both products are claimed, so the catalog linter will report each as
+    /// priced by two rules. For a discount stacked on other prices that is
+    /// intended, and the warning can be acknowledged.
+    fn products(&self) -> Vec<char> {
+        vec![self.main, self.side]
+    }
+}
+
+fn main() {
+    // `?` cannot be used in a `main` that returns nothing, so the rule
+    // constructors' `Result`s are unwrapped: the prices below are known to
+    // be valid, and a mistake in them should stop the program at once.
+    //
+    // Each rule is boxed so that rules of different types can share one
+    // `Vec`, as they would share one `List<PricingRule>` in Java.
+    let rules: Vec<BoxedRule<Cents>> = vec![
+        Box::new(FlatPrice::new('S', Cents(450)).unwrap()),
+        Box::new(BundlePrice::new('D', Cents(150), 4, Cents(500)).unwrap()),
+        Box::new(FlatPrice::new('F', Cents(100)).unwrap()),
+        Box::new(MealDeal { main: 'S', side: 'D', discount: Cents(100) }),
+    ];
+    let market = Supermarket::with_rules(rules);
+
+    println!("Catalog:");
+    for rule in market.rules() {
+        println!("  {}", rule.describe());
+    }
+
+    // Unknown items, here `X`, are charged nothing: no rule claims them.
+    for cart in ["S", "SD", "SSDDDD", "FX"] {
+        match market.try_checkout(cart) {
+            Ok(total) => println!("{:>8} costs {}", cart, total),
+            Err(error) => println!("{:>8} cannot be priced: {}", cart, error)
+        }
+    }
+
+    // A plan explains a total rule by rule, with the meal deal's discount
+    // showing as a negative amount.
+    let plan = market.plan("SSDDDD").unwrap();
+    for rule in plan.rules.iter().filter(|rule| rule.amount != Cents::zero()) {
+        println!("{:>34}: {}", rule.description, rule.amount);
+    }
+}
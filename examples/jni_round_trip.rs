@@ -0,0 +1,98 @@
+//! Pricing the same carts in Java and in Rust, from one Rust program.
+//!
+//! ```text
+//! $ javac -d target/java java/reasonable_implementation/*.java
+//! $ cargo run --features jni --example jni_round_trip -- target/java
+//! ```
+//!
+//! The argument is the directory holding the compiled Java classes,
+//! `target/java` if there is none. Running needs a JDK; set `JAVA_HOME` if
+//! `java` on the `PATH` is not the one to use.
+//!
+//! JNI, the Java Native Interface, usually runs the other way: Java loads a
+//! native library and calls into it. The same interface also lets a native
+//! program start a JVM inside its own process and call into Java, which is
+//! what this example does. It creates the Java `Supermarket` from
+//! `java/reasonable_implementation`, the original of this crate's Rust
+//! implementations, hands it carts as Java strings, reads back its `int`
+//! totals, and checks that both languages agree.
+//!
+//! Every Java object reached through JNI is a *reference* that Rust holds
+//! on the JVM's behalf. The `jni` crate ties each one to the lifetime of
+//! the `Env` it came from, so the compiler refuses any attempt to use one
+//! after the JVM may have collected it, a mistake C code using JNI makes
+//! easily and discovers late.
+
+use std::process::ExitCode;
+use jni::objects::{JObject, JValue};
+use jni::{jni_sig, jni_str, Env, InitArgsBuilder, JNIVersion, JavaVM};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+const CARTS: [&str; 5] = ["", "A", "ABBACBBAB", "BBBBBBBBBBB", "CCAXB"];
+
+fn main() -> ExitCode {
+    let classes = std::env::args().nth(1).unwrap_or_else(|| "target/java".to_string());
+    let options = InitArgsBuilder::new()
+        .version(JNIVersion::V1_8)
+        .option(format!("-Djava.class.path={}", classes))
+        .build()
+        .expect("the options are well formed");
+    let jvm = match JavaVM::new(options) {
+        Ok(jvm) => jvm,
+        Err(error) => {
+            eprintln!("jni_round_trip: cannot start a JVM: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // A thread must be attached to the JVM before it can call Java, the way
+    // a thread Java starts itself always is. The closure receives the `Env`,
+    // JNI's handle on the attached thread, through which every call goes.
+    let result = jvm.attach_current_thread(|env| -> jni::errors::Result<bool> {
+        // `new Supermarket()`. Classes are named with slashes, and the
+        // constructor's signature, `()V`, is "no arguments, returns void".
+        let java_market = env.new_object(jni_str!("reasonable_implementation/Supermarket"),
+                                         jni_sig!("()V"), &[])?;
+        let rust_market: Supermarket = Supermarket::demo();
+
+        let mut agreed = true;
+        for cart in CARTS {
+            let java_total = java_checkout(env, &java_market, cart)?;
+            let Cents(rust_total) = rust_market.try_checkout(cart)
+                .expect("the demo carts are small");
+            // The Java version counts in whole dollars, the Rust one in cents.
+            let same = i64::from(java_total) * 100 == rust_total;
+            agreed &= same;
+            println!("{:>13}  Java: ${:<5} Rust: {}  {}", format!("{:?}", cart), java_total,
+                     Cents(rust_total), if same { "agree" } else { "DISAGREE" });
+        }
+        Ok(agreed)
+    });
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        // A Java exception thrown by a call arrives here as an `Err`, with
+        // its class name and message, rather than unwinding through Rust.
+        Err(error) => {
+            eprintln!("jni_round_trip: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// `market.checkout(cart)`, called on the Java `Supermarket`.
+///
+/// The method is found by name and signature, as with Java reflection:
+/// `(Ljava/lang/String;)I` means "takes a `String`, returns an `int`". A
+/// signature matching none of the class's methods is only found out when
+/// the call is made.
+fn java_checkout(env: &mut Env, market: &JObject, cart: &str) -> jni::errors::Result<i32> {
+    // Java strings are UTF-16 and Rust ones UTF-8, so the cart is copied
+    // into a new Java string rather than shared.
+    let cart = env.new_string(cart)?;
+    env.call_method(market, jni_str!("checkout"), jni_sig!("(Ljava/lang/String;)I"),
+                    &[JValue::Object(&cart)])?
+        .i()
+}
@@ -0,0 +1,77 @@
+//! Loading a catalog from a TOML price file, and pricing carts with it.
+//!
+//! ```text
+//! $ cargo run --features toml --example load_config -- pricing-cli/prices.toml ABBACBBAB
+//! ```
+//!
+//! The first argument is the price file, `pricing-cli/prices.toml` if there
+//! is none, and the rest are carts. Where a Java application might bind a
+//! file to objects with Jackson and then validate them with Bean
+//! Validation, here `catalog::file` does both, and hands back either a
+//! working `Supermarket` or every problem it found, each with its line.
+//!
+//! The file may use kinds of rule registered by the application, as well
+//! as the built-in ones. This one registers `three_for_two`, so a file can
+//! say:
+//!
+//! ```toml
+//! [[rule]]
+//! kind = "three_for_two"
+//! product = "E"
+//! cost = 500
+//! ```
+
+use std::process::ExitCode;
+use rust_for_java_devs::catalog::{self, file};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::BestOfferPrice;
+use rust_for_java_devs::registry::RuleRegistry;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| "pricing-cli/prices.toml".to_string());
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // A registry maps each `kind` a file may name to a constructor. The
+    // closure is this application's own kind of rule; a Java application
+    // might add it to a `Map<String, Function<RuleSpec, PricingRule>>` or
+    // list it for a `ServiceLoader`.
+    let mut registry: RuleRegistry<Cents> = RuleRegistry::with_builtins();
+    registry.register("three_for_two", |spec| {
+        let cost: Cents = spec.cost("cost")?;
+        Ok(Box::new(BestOfferPrice::new(spec.product, cost)?.with_offer(3, Cents(cost.0 * 2))?))
+    });
+
+    // `load` returns every problem at once, rather than stopping at the
+    // first the way an exception would.
+    let market = match file::load(&registry, &source) {
+        Ok(market) => market,
+        Err(problems) => {
+            for problem in problems {
+                eprintln!("{}:{}: {}", path, problem.line, problem.message);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // The linter's warnings don't stop the catalog from working, so they
+    // are only printed.
+    for problem in file::validate(&registry, &source) {
+        eprintln!("{}:{}: warning: {}", path, problem.line, problem.message);
+    }
+    print!("{}", catalog::table(&market));
+
+    for cart in args {
+        match market.try_checkout(cart.clone()) {
+            Ok(total) => println!("{}: {}", cart, total),
+            Err(error) => eprintln!("{}: {}", cart, error)
+        }
+    }
+    ExitCode::SUCCESS
+}
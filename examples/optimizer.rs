@@ -0,0 +1,66 @@
+//! Finding the cheapest way to buy a number of items when several offers
+//! overlap.
+//!
+//! ```text
+//! $ cargo run --example optimizer -- 8
+//! ```
+//!
+//! With apples at $1.00 each, 4 for $3.00 or 6 for $4.20, the cheapest 8
+//! apples are two fours for $6.00, not a six and two singles for $6.20.
+//! Taking as many of the best offer as fit is a greedy choice, and greedy
+//! choices miss combinations like that, as they do in the coin change
+//! problem this is. `BestOfferPrice` solves it by dynamic programming,
+//! building the cheapest price for every smaller count first, and
+//! `best_lots` reads the winning combination back out.
+
+use rust_for_java_devs::money::{Cents, OverflowPolicy};
+use rust_for_java_devs::reasonable_implementation::BestOfferPrice;
+
+fn main() {
+    // `parse` works for any type implementing `FromStr`, chosen here by the
+    // `u64` annotation, much as `Long.parseLong` does for one type only.
+    let count: u64 = match std::env::args().nth(1).map(|arg| arg.parse()) {
+        Some(Ok(count)) => count,
+        Some(Err(error)) => {
+            eprintln!("optimizer: {}", error);
+            std::process::exit(2);
+        },
+        None => 8
+    };
+
+    let apples = BestOfferPrice::new('A', Cents(100)).unwrap()
+        .with_offer(4, Cents(300)).unwrap()
+        .with_offer(6, Cents(420)).unwrap();
+
+    let (price, lots) = apples.best_lots(count, OverflowPolicy::Checked).expect("the price fits");
+    println!("{} apples cost {} at best:", count, price);
+    // `offers` and `lots` are in the same order, so `zip` pairs each offer
+    // with the number of times it was chosen.
+    for (&(size, cost), &lots) in apples.offers().iter().zip(lots.iter()) {
+        if lots > 0 {
+            println!("  {} x {} for {}", lots, size, cost);
+        }
+    }
+
+    // The greedy answer, for comparison: as many of the largest offer as
+    // fit, then the next largest, and so on.
+    let mut left = count;
+    let mut greedy = 0;
+    let mut offers = apples.offers().to_vec();
+    // `Reverse` flips the ordering of what it wraps, so this sorts from
+    // the largest size down, like `Comparator.reverseOrder()`.
+    offers.sort_by_key(|&(size, _)| std::cmp::Reverse(size));
+    for (size, cost) in offers {
+        greedy += (left / size) as i64 * cost.0;
+        left %= size;
+    }
+    println!("buying the largest offers first would cost {}", Cents(greedy));
+
+    // The table behind `best_lots` is built once and remembered, so pricing
+    // every count up to a thousand costs little more than pricing one.
+    let cheapest_per_item = (1..=1000u64)
+        .map(|n| (n, apples.best_price(n, OverflowPolicy::Checked).unwrap()))
+        .min_by(|(n, a), (m, b)| (a.0 * *m as i64).cmp(&(b.0 * *n as i64)))
+        .expect("the range is not empty");
+    println!("the lowest price per apple is at {} apples, for {}", cheapest_per_item.0, cheapest_per_item.1);
+}
@@ -0,0 +1,63 @@
+// Prices the cart typed into the page on every keystroke, using the Rust
+// pricing code compiled to WebAssembly by the `pricing-wasm` crate.
+//
+// `pkg/pricing_wasm.js` is written by `wasm-bindgen`, not by hand; see
+// README.md. Its default export fetches and instantiates the `.wasm` module,
+// after which `Register` can be used like any JavaScript class.
+import init, { Register } from "./pkg/pricing_wasm.js";
+
+await init();
+const register = new Register();
+
+const cart = document.getElementById("cart");
+const lines = document.getElementById("lines");
+const total = document.getElementById("total");
+const error = document.getElementById("error");
+
+// The receipt's amounts are whole cents, exactly as the Rust `Cents` type
+// holds them; only here, for display, do they become dollars.
+const dollars = (cents) => (cents < 0 ? "-$" : "$") + (Math.abs(cents) / 100).toFixed(2);
+
+function cell(text, className) {
+  const td = document.createElement("td");
+  td.textContent = text;
+  if (className) {
+    td.className = className;
+  }
+  return td;
+}
+
+function update() {
+  let receipt;
+  try {
+    receipt = JSON.parse(register.receipt(cart.value));
+  } catch (e) {
+    // A `CheckoutError` from Rust arrives as a thrown `Error`.
+    error.textContent = e.message;
+    return;
+  }
+  error.textContent = "";
+  lines.replaceChildren(...receipt.lines.map((line) => {
+    const row = document.createElement("tr");
+    const rule = cell(line.rule);
+    for (const promotion of line.promotions) {
+      const note = document.createElement("div");
+      note.className = "promotion";
+      note.textContent = "bought as " + promotion;
+      rule.append(note);
+    }
+    row.append(cell(line.product ?? ""), rule, cell(line.quantity, "amount"),
+               cell(dollars(line.amount), "amount"));
+    return row;
+  }));
+  for (const adjustment of receipt.adjustments) {
+    const row = document.createElement("tr");
+    row.append(cell(""), cell(adjustment.label), cell(""), cell(dollars(adjustment.amount), "amount"));
+    lines.append(row);
+  }
+  total.textContent = dollars(receipt.total);
+}
+
+document.getElementById("catalog").textContent = register.catalog();
+cart.addEventListener("input", update);
+update();
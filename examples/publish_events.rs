@@ -0,0 +1,43 @@
+//! Prices carts read from standard input, one per line, against the demo
+//! catalog, and writes a `CheckoutCompleted` event for each to standard
+//! output as NDJSON, ready to be piped into a Kafka producer:
+//!
+//! ```text
+//! $ printf 'ABBACBBAB\nAXBC\n' | cargo run -q --features events --example publish_events store-7 \
+//!     | kcat -P -b localhost:9092 -t checkouts
+//! ```
+//!
+//! The store id is the first argument, `store-1` if there is none. Carts that
+//! cannot be priced are reported on standard error and skipped.
+
+use std::io::{self, BufRead};
+use std::process::ExitCode;
+use rust_for_java_devs::events::{self, EventError, NdjsonPublisher};
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+fn main() -> ExitCode {
+    let store_id = std::env::args().nth(1).unwrap_or_else(|| "store-1".to_string());
+    let market: Supermarket = Supermarket::demo();
+    let publisher = NdjsonPublisher::new(io::stdout());
+
+    for line in io::stdin().lock().lines() {
+        let cart = match line {
+            Ok(cart) => cart,
+            Err(error) => {
+                eprintln!("publish_events: {}", error);
+                return ExitCode::FAILURE;
+            }
+        };
+        match events::checkout(&market, &store_id, &cart, &publisher) {
+            Ok(_) => {},
+            Err(EventError::Checkout(error)) => eprintln!("publish_events: {:?}: {}", cart, error),
+            // Standard output has gone away, as when piped into `head`, so
+            // there is nobody left to publish to.
+            Err(error) => {
+                eprintln!("publish_events: {}", error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
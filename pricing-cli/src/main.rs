@@ -0,0 +1,333 @@
+//! `checkout`, a command line register.
+//!
+//! Each argument is priced as one cart against the demo catalog and its
+//! total printed on a line of its own:
+//!
+//! ```text
+//! $ checkout ABBACBBAB AXBC
+//! $240.00
+//! $100.00
+//! ```
+//!
+//! `checkout price` does the same for carts read one per line, from a file
+//! or from standard input, so that it can sit in a shell pipeline working
+//! through a large batch. `--rules` prices them against a price file rather
+//! than the demo catalog, and `--json` prints each cart's receipt as a line
+//! of JSON rather than just its total:
+//!
+//! ```text
+//! $ checkout price --rules prices.toml --input carts.txt
+//! $240.00
+//! $100.00
+//! $ printf 'AB\n' | checkout price --json
+//! {"lines":[{"product":"A",...}],"subtotal":7000,"adjustments":[],"total":7000}
+//! ```
+//!
+//! A cart that cannot be priced is reported on standard error, with its line
+//! number, and skipped; the exit status then says that something failed.
+//!
+//! `checkout catalog` prints the catalog, the demo one or that of `--rules`,
+//! with what an item costs alone, in a bundle and just past one. With
+//! `--up-to`, it prints what an item costs at every quantity up to a limit
+//! instead, with all the rules applied, to check where an offer pays:
+//!
+//! ```text
+//! $ checkout catalog --rules prices.toml
+//! product  rule                             unit price
+//! A        A: $20.00 each                   1: $20.00
+//! B        B: $50.00 each or 5 for $150.00  1: $50.00 5: $30.00 6: $33.33
+//! C        C: $30.00 each                   1: $30.00
+//! $ checkout catalog --up-to 6
+//! product  unit price by quantity
+//! A        1: $20.00 2: $20.00 3: $20.00 4: $20.00 5: $20.00 6: $20.00
+//! B        1: $50.00 2: $50.00 3: $50.00 4: $50.00 5: $30.00 6: $33.33
+//! C        1: $30.00 2: $30.00 3: $30.00 4: $30.00 5: $30.00 6: $30.00
+//! ```
+//!
+//! `checkout tui` opens the same catalog as an interactive register, showing
+//! the running total as items are added. It is only built with the `tui`
+//! feature, as it pulls in a terminal UI library the other commands do not
+//! need.
+//!
+//! `checkout validate` checks price files instead, reporting each problem
+//! with its file and line, and exits with a failure status if there were
+//! any, so that a CI pipeline can refuse a broken price change:
+//!
+//! ```text
+//! $ checkout validate prices.toml
+//! prices.toml:14: no rule kind is named "discount"
+//! prices.toml:21: A: priced by 2 rules
+//! ```
+//!
+//! A binary crate's entry point is `main`, just as in Java, but there is no
+//! enclosing class and no `String[] args` parameter; the arguments are
+//! fetched from `std::env` instead.
+
+use rust_for_java_devs::catalog::{self, file};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+use rust_for_java_devs::registry::RuleRegistry;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+
+/// The terminal interface, in `src/tui.rs`.
+#[cfg(feature = "tui")]
+mod tui;
+
+const USAGE: &str = "\
+usage: checkout CART...
+       checkout price [--rules FILE] [--input FILE] [--json]
+       checkout catalog [--rules FILE] [--up-to QUANTITY]
+       checkout tui [--rules FILE]
+       checkout validate FILE...";
+
+/// Returning `ExitCode` lets `main` choose the process exit status, the
+/// way `System.exit` does, without cutting the program short.
+fn main() -> ExitCode {
+    // `skip(1)` drops the program's own name, which is always the first
+    // argument on Unix-like systems.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Matching on a slice can pick out its first element and bind the rest,
+    // which makes for a small command dispatcher. `as_str` is needed as a
+    // `String` cannot be matched against string literals directly.
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] | ["validate"] => usage(),
+        ["validate", ..] => validate(&args[1..]),
+        ["price", ..] => match PriceOptions::parse(&args[1..]) {
+            Some(options) => price_lines(options),
+            None => usage()
+        },
+        ["catalog", ..] => match CatalogOptions::parse(&args[1..]) {
+            Some(options) => show(options),
+            None => usage()
+        },
+        ["tui"] => interact(Supermarket::demo()),
+        ["tui", "--rules", path] => match load(path) {
+            Some(market) => interact(market),
+            None => ExitCode::FAILURE
+        },
+        ["tui", ..] => usage(),
+        _ => price(args)
+    }
+}
+
+/// Prints the table of the catalog, or its unit prices by quantity.
+fn show(options: CatalogOptions) -> ExitCode {
+    let market = match options.rules {
+        Some(ref path) => match load(path) {
+            Some(market) => market,
+            None => return ExitCode::FAILURE
+        },
+        None => Supermarket::demo()
+    };
+    match options.up_to {
+        Some(max_quantity) => print!("{}", market.unit_price_table(max_quantity)),
+        None => print!("{}", catalog::table(&market))
+    }
+    ExitCode::SUCCESS
+}
+
+/// Runs the terminal interface against `market`.
+#[cfg(feature = "tui")]
+fn interact(market: Supermarket<Cents>) -> ExitCode {
+    match tui::run(market) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("checkout: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Without the `tui` feature there is no interface to run, only advice on
+/// how to get one.
+#[cfg(not(feature = "tui"))]
+fn interact(_market: Supermarket<Cents>) -> ExitCode {
+    eprintln!("checkout: built without the terminal interface; rebuild with `--features tui`");
+    ExitCode::FAILURE
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::FAILURE
+}
+
+/// Prices each cart against the demo catalog.
+fn price(carts: Vec<String>) -> ExitCode {
+    let market: Supermarket = Supermarket::demo();
+    for cart in carts {
+        match market.try_checkout(cart) {
+            Ok(total) => println!("{}", total),
+            Err(error) => {
+                eprintln!("checkout: {}", error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// The options of `checkout price`.
+#[derive(Default)]
+struct PriceOptions {
+    /// The price file to use instead of the demo catalog.
+    rules: Option<String>,
+    /// The file to read carts from instead of standard input.
+    input: Option<String>,
+    /// Whether to print receipts as JSON rather than totals.
+    json: bool
+}
+
+impl PriceOptions {
+
+    /// Reads the options from `args`, or returns `None` if they make no
+    /// sense, such as an unknown option or one missing its value.
+    fn parse(args: &[String]) -> Option<PriceOptions> {
+        let mut options = PriceOptions::default();
+        // An iterator can be advanced inside its own loop, which is how an
+        // option takes the argument after it as its value.
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rules" => options.rules = Some(args.next()?.clone()),
+                "--input" => options.input = Some(args.next()?.clone()),
+                "--json" => options.json = true,
+                _ => return None
+            }
+        }
+        Some(options)
+    }
+}
+
+/// The options of `checkout catalog`.
+#[derive(Default)]
+struct CatalogOptions {
+    /// The price file to use instead of the demo catalog.
+    rules: Option<String>,
+    /// The largest quantity to show unit prices for, if they are wanted
+    /// rather than the table of rules.
+    up_to: Option<u32>
+}
+
+impl CatalogOptions {
+
+    /// Reads the options from `args`, or returns `None` if they make no
+    /// sense, as `PriceOptions::parse` does. `ok()?` also refuses an
+    /// `--up-to` that is not a number.
+    fn parse(args: &[String]) -> Option<CatalogOptions> {
+        let mut options = CatalogOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rules" => options.rules = Some(args.next()?.clone()),
+                "--up-to" => options.up_to = Some(args.next()?.parse().ok()?),
+                _ => return None
+            }
+        }
+        Some(options)
+    }
+}
+
+/// Builds the catalog from a price file, reporting its problems the way
+/// `validate` does.
+fn load(path: &str) -> Option<Supermarket<Cents>> {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            return None;
+        }
+    };
+    match file::load(&RuleRegistry::default(), &source) {
+        Ok(market) => Some(market),
+        Err(problems) => {
+            for problem in problems {
+                eprintln!("{}:{}: {}", path, problem.line, problem.message);
+            }
+            None
+        }
+    }
+}
+
+/// Prices each line of the input as a cart.
+fn price_lines(options: PriceOptions) -> ExitCode {
+    let market: Supermarket = match options.rules {
+        Some(ref path) => match load(path) {
+            Some(market) => market,
+            None => return ExitCode::FAILURE
+        },
+        None => Supermarket::demo()
+    };
+    // A `Box<dyn BufRead>` can hold either kind of reader, much as a Java
+    // `Reader` variable can hold a `FileReader` or an `InputStreamReader`.
+    let input: Box<dyn BufRead> = match options.input {
+        Some(ref path) => match std::fs::File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(io::stdin().lock())
+    };
+    // Standard output is flushed on every line unless buffered, which is
+    // slow for a large batch.
+    let mut output = BufWriter::new(io::stdout().lock());
+    let mut status = ExitCode::SUCCESS;
+    for (number, line) in input.lines().enumerate() {
+        let cart = match line {
+            Ok(cart) => cart,
+            Err(error) => {
+                eprintln!("checkout: {}", error);
+                return ExitCode::FAILURE;
+            }
+        };
+        let priced = if options.json {
+            market.receipt(&cart).map(|receipt| {
+                serde_json::to_string(&receipt).expect("a receipt can always be written as JSON")
+            })
+        } else {
+            market.try_checkout(cart).map(|total| total.to_string())
+        };
+        let written = match priced {
+            Ok(priced) => writeln!(output, "{}", priced),
+            Err(error) => {
+                eprintln!("checkout: line {}: {}", number + 1, error);
+                status = ExitCode::FAILURE;
+                Ok(())
+            }
+        };
+        // Standard output has gone away, as when piped into `head`, so
+        // there is no point pricing the rest.
+        if written.is_err() {
+            return ExitCode::FAILURE;
+        }
+    }
+    if output.flush().is_err() {
+        return ExitCode::FAILURE;
+    }
+    status
+}
+
+/// Checks every price file, reporting problems as `file:line: message`, the
+/// form compilers use and editors and CI tools know how to link to.
+fn validate(paths: &[String]) -> ExitCode {
+    let registry: RuleRegistry<Cents> = RuleRegistry::default();
+    let mut clean = true;
+    for path in paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                clean = false;
+                continue;
+            }
+        };
+        let problems = file::validate(&registry, &source);
+        for problem in problems.iter() {
+            eprintln!("{}:{}: {}", path, problem.line, problem.message);
+        }
+        clean &= problems.is_empty();
+    }
+    if clean { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
@@ -0,0 +1,165 @@
+//! `checkout tui`, a register drawn in the terminal.
+//!
+//! The catalog is listed on the left, the cart and its running total on the
+//! right, along with hints at promotions the cart nearly qualifies for. Type
+//! a product's letter, or pick it with the arrow keys and press Enter, to
+//! add it to the cart; Backspace takes the last item out again, and Esc
+//! quits.
+//!
+//! The screen is drawn by `ratatui` in *immediate mode*: each frame is drawn
+//! from scratch from the application's state, rather than by updating a
+//! tree of long-lived widgets as Swing does. The state is a plain struct,
+//! `App`, and the whole program is a loop of draw, wait for a key, update.
+
+use std::io;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use rust_for_java_devs::catalog::{self, CatalogTable};
+use rust_for_java_devs::core::counting::{count_items, Counts};
+use rust_for_java_devs::money::{Cents, Money, OverflowPolicy};
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+/// Runs the interface against `market` until the user quits.
+pub fn run(market: Supermarket<Cents>) -> io::Result<()> {
+    let mut app = App::new(market);
+    // `ratatui::run` switches the terminal into raw mode, where keys arrive
+    // one at a time and are not echoed, and restores it afterwards, even if
+    // the closure panics.
+    ratatui::run(|terminal| app.run(terminal))
+}
+
+/// Everything the interface shows, and nothing about how it is shown.
+struct App {
+    market: Supermarket<Cents>,
+    table: CatalogTable<Cents>,
+    /// Which catalog row is highlighted, and how far the list is scrolled.
+    catalog: ListState,
+    /// The items added so far, in the form `checkout` takes them.
+    cart: String,
+    done: bool
+}
+
+impl App {
+
+    fn new(market: Supermarket<Cents>) -> App {
+        let table = catalog::table(&market);
+        // `with_selected` is a builder method: it takes `self` by value and
+        // returns it changed, so that calls can be chained.
+        let catalog = ListState::default().with_selected(Some(0));
+        App { market, table, catalog, cart: String::new(), done: false }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.done {
+            terminal.draw(|frame| self.draw(frame))?;
+            self.handle(event::read()?);
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, event: Event) {
+        // Terminals that report key releases would otherwise add every
+        // item twice.
+        let key = match event.as_key_press_event() {
+            Some(key) => key,
+            None => return
+        };
+        match key.code {
+            KeyCode::Esc => self.done = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.done = true,
+            KeyCode::Up => self.catalog.select_previous(),
+            KeyCode::Down => self.catalog.select_next(),
+            KeyCode::Enter => {
+                let selected = self.catalog.selected().and_then(|row| self.table.rows.get(row));
+                if let Some(row) = selected {
+                    self.cart.push(row.product);
+                }
+            },
+            KeyCode::Backspace => { self.cart.pop(); },
+            KeyCode::Char(c) if self.table.rows.iter().any(|row| row.product == c) => self.cart.push(c),
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        // `areas` splits a rectangle into a fixed-size array, which can be
+        // destructured straight into named variables.
+        let [body, help] = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] = Layout::horizontal([Constraint::Percentage(60), Constraint::Fill(1)]).areas(body);
+        let [cart, hints] = Layout::vertical([Constraint::Length(5), Constraint::Fill(1)]).areas(right);
+
+        let rows = self.table.rows.iter().map(|row| {
+            let prices: Vec<String> = row.prices.iter()
+                .map(|point| format!("{}: ${:.2}", point.quantity, point.each()))
+                .collect();
+            format!("{}  {}  [{}]", row.product, row.description, prices.join(" "))
+        });
+        let list = List::new(rows)
+            .block(Block::bordered().title(" Catalog "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, left, &mut self.catalog);
+
+        let total = match self.market.try_checkout(self.cart.clone()) {
+            Ok(total) => total.to_string(),
+            Err(error) => error.to_string()
+        };
+        let summary = Paragraph::new(vec![
+            Line::from(format!("Items: {}", self.cart)),
+            Line::from(format!("Total: {}", total)).style(Style::new().add_modifier(Modifier::BOLD))
+        ]).wrap(Wrap { trim: false }).block(Block::bordered().title(" Cart "));
+        frame.render_widget(summary, cart);
+
+        let promotions = Paragraph::new(self.hints().into_iter().map(Line::from).collect::<Vec<_>>())
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered().title(" Promotions "));
+        frame.render_widget(promotions, hints);
+
+        frame.render_widget(Line::from("type a product or Enter to add  Backspace to remove  Esc to quit"), help);
+    }
+
+    /// A hint for each bundle the cart is a few items short of, where those
+    /// items would cost less than they do bought alone.
+    fn hints(&self) -> Vec<String> {
+        let counts: Counts = count_items(&self.cart);
+        let mut hints = Vec::new();
+        for rule in self.market.rules() {
+            // What `count` of `product` come to under this rule alone.
+            let price = |product: char, count: u64| {
+                rule.price(&Counts::from([(product, count)]), OverflowPolicy::Checked).ok()
+            };
+            for (&product, &count) in counts.iter() {
+                for size in rule.lot_sizes(product) {
+                    let short = size - count % size;
+                    if short == size {
+                        continue;
+                    }
+                    // `zip` pairs two `Option`s into an `Option` of a pair,
+                    // which is `None` if either of them is.
+                    let more = price(product, count.saturating_add(short)).zip(price(product, count))
+                        .and_then(|(after, before)| after.checked_minus(before));
+                    let alone = price(product, 1).and_then(|one| one.checked_times(short));
+                    let hint = match (more, alone) {
+                        // Completing the bundle makes the cart cheaper.
+                        (Some(more), _) if more < Cents::zero() => Cents::zero().checked_minus(more)
+                            .map(|saved| format!("{} more {} would take {} off the total", short, product, saved)),
+                        (Some(more), Some(alone)) if more < alone =>
+                            Some(format!("{} more {} for only {}, not {}", short, product, more, alone)),
+                        _ => None
+                    };
+                    // An `Option` is a collection of at most one element, so
+                    // `extend` adds the hint if there is one.
+                    hints.extend(hint);
+                }
+            }
+        }
+        if hints.is_empty() {
+            hints.push("no promotions within reach".to_string());
+        }
+        hints
+    }
+}
@@ -0,0 +1,193 @@
+//! Strategies for generating random money, rules, catalogs and carts with
+//! `proptest`, available with the `proptest` feature.
+//!
+//! Property testing checks a claim, such as "a plan always adds up to the
+//! checkout total", against hundreds of randomly generated inputs, and on
+//! failure *shrinks* the input to the smallest one still failing. Java has
+//! the same idea in jqwik and junit-quickcheck.
+//!
+//! A `Strategy` describes how to generate, and shrink, values of one type.
+//! `Arbitrary` names the default strategy for a type, so that `any::<Cents>()`
+//! works in a `proptest!` test. The values here are deliberately realistic:
+//! prices up to `MAX_CENTS`, products from `PRODUCTS`, and bundles that
+//! never cost more than their items would alone.
+//!
+//! Rust only lets a crate implement a trait for a type if it owns one of
+//! them, so `Arbitrary` can't be implemented here for `Decimal`. `money`
+//! serves every backend instead.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+use proptest::prelude::*;
+use proptest::sample::{select, subsequence};
+use crate::counting::Counts;
+use crate::lint::LintWarning;
+use crate::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::plan::RuleNode;
+use crate::rules::{Describable, PricingRule, FlatPrice, BundlePrice, BestOfferPrice};
+
+/// The product codes generated catalogs draw from.
+pub const PRODUCTS: [char; 6] = ['A', 'B', 'C', 'D', 'E', 'F'];
+
+/// The largest generated price of a single item: $1,000.00.
+pub const MAX_CENTS: i64 = 100_000;
+
+/// Any amount from zero to `MAX_CENTS`, in any money backend.
+pub fn money<M: Money>() -> impl Strategy<Value = M> {
+    // `prop_map` transforms every generated value, like `Stream.map`.
+    (0..=MAX_CENTS).prop_map(M::from_cents)
+}
+
+impl Arbitrary for Cents {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Cents>;
+
+    fn arbitrary_with(_parameters: ()) -> BoxedStrategy<Cents> {
+        money().boxed()
+    }
+}
+
+impl Arbitrary for OverflowPolicy {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<OverflowPolicy>;
+
+    fn arbitrary_with(_parameters: ()) -> BoxedStrategy<OverflowPolicy> {
+        // `prop_oneof!` picks one of several strategies at random.
+        prop_oneof![
+            Just(OverflowPolicy::Checked),
+            Just(OverflowPolicy::Saturating),
+            Just(OverflowPolicy::Wrapping)
+        ].boxed()
+    }
+}
+
+/// A flat price for `product`.
+pub fn flat_price<M: Money>(product: char) -> impl Strategy<Value = FlatPrice<M>> {
+    money().prop_map(move |cost| FlatPrice::new(product, cost).expect("costs are never negative"))
+}
+
+/// A bundle of 2 to 10 `product`s, costing between half and all of what
+/// its items would alone.
+pub fn bundle_price<M: Money>(product: char) -> impl Strategy<Value = BundlePrice<M>> {
+    (0..=MAX_CENTS, 2..=10u64, 50..=100i64).prop_map(move |(lone, size, percent)| {
+        let bundle = lone * size as i64 * percent / 100;
+        BundlePrice::new(product, M::from_cents(lone), size, M::from_cents(bundle))
+            .expect("bundles are never empty, nor their costs negative")
+    })
+}
+
+/// A product with up to three offers of 2 to 12 items, each costing between
+/// half and all of what its items would alone.
+pub fn best_offer_price<M: Money>(product: char) -> impl Strategy<Value = BestOfferPrice<M>> {
+    let offers = proptest::collection::vec((2..=12u64, 50..=100i64), 0..=3);
+    (0..=MAX_CENTS, offers).prop_map(move |(lone, offers)| {
+        let mut rule = BestOfferPrice::new(product, M::from_cents(lone))
+            .expect("costs are never negative");
+        for (size, percent) in offers {
+            let cost = M::from_cents(lone * size as i64 * percent / 100);
+            rule = rule.with_offer(size, cost).expect("offers are never empty");
+        }
+        rule
+    })
+}
+
+/// Any one of the core's rules.
+///
+/// A catalog generated by a strategy must implement `Debug`, so that a
+/// failing case can be printed, which rules out a `Vec` of trait objects.
+/// This enum stands in for the trait object and forwards every method to
+/// the rule inside.
+#[derive(Clone, PartialEq, Debug)]
+pub enum AnyRule<M: Money> {
+    Flat(FlatPrice<M>),
+    Bundle(BundlePrice<M>),
+    BestOffer(BestOfferPrice<M>)
+}
+
+impl<M: Money> AnyRule<M> {
+    /// The rule inside, as a trait object.
+    fn rule(&self) -> &dyn PricingRule<M> {
+        match self {
+            AnyRule::Flat(rule) => rule,
+            AnyRule::Bundle(rule) => rule,
+            AnyRule::BestOffer(rule) => rule
+        }
+    }
+}
+
+impl<M: Money> Describable for AnyRule<M> {
+    fn describe(&self) -> String {
+        self.rule().describe()
+    }
+}
+
+impl<M: Money> PricingRule<M> for AnyRule<M> {
+    fn price(&self, character_counts: &Counts,
+             policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.rule().price(character_counts, policy)
+    }
+
+    fn products(&self) -> Vec<char> {
+        self.rule().products()
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        self.rule().applies_to(product)
+    }
+
+    fn lint(&self) -> Vec<LintWarning> {
+        self.rule().lint()
+    }
+
+    fn lot_sizes(&self, product: char) -> Vec<u64> {
+        self.rule().lot_sizes(product)
+    }
+
+    fn plan(&self, character_counts: &Counts,
+            policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        self.rule().plan(character_counts, policy)
+    }
+
+    fn list_price(&self, character_counts: &Counts,
+                  policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.rule().list_price(character_counts, policy)
+    }
+}
+
+/// Any kind of rule for `product`.
+pub fn any_rule<M: Money>(product: char) -> impl Strategy<Value = AnyRule<M>> {
+    prop_oneof![
+        flat_price(product).prop_map(AnyRule::Flat),
+        bundle_price(product).prop_map(AnyRule::Bundle),
+        best_offer_price(product).prop_map(AnyRule::BestOffer)
+    ]
+}
+
+impl<M: Money> Arbitrary for AnyRule<M> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<AnyRule<M>>;
+
+    fn arbitrary_with(_parameters: ()) -> BoxedStrategy<AnyRule<M>> {
+        // `prop_flat_map` picks a product first, then a strategy for a rule
+        // on that product.
+        select(&PRODUCTS[..]).prop_flat_map(any_rule).boxed()
+    }
+}
+
+/// A catalog pricing one to six of `PRODUCTS`, each with exactly one rule,
+/// so it never provokes `LintWarning::Conflicting`.
+pub fn catalog<M: Money>() -> impl Strategy<Value = Vec<AnyRule<M>>> {
+    // `subsequence` picks some of the products, in their original order. A
+    // `Vec` of strategies is itself a strategy for a `Vec` of values.
+    subsequence(&PRODUCTS[..], 1..=PRODUCTS.len())
+        .prop_flat_map(|products| products.into_iter().map(any_rule).collect::<Vec<_>>())
+}
+
+/// A cart of `items`, with a length drawn from `lengths`.
+///
+/// Including codes no rule prices, say `X`, checks that they are ignored.
+pub fn cart(items: &[char], lengths: Range<usize>) -> impl Strategy<Value = String> {
+    proptest::collection::vec(select(items.to_vec()), lengths)
+        .prop_map(|items| items.into_iter().collect())
+}
@@ -0,0 +1,228 @@
+//! Limits on the carts a register will accept and on the promotions it will
+//! grant, and the errors checkout may report.
+//!
+//! Counting a cart takes time and memory in proportion to its length, so a
+//! service pricing carts for strangers should refuse absurdly long ones
+//! before doing any work, much as a Java servlet container caps the size of
+//! a request body.
+
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use crate::counting::InvalidItem;
+use crate::money::{Money, Overflow};
+
+/// A stock-keeping unit: the code of one product, such as `'A'`.
+///
+/// The rules and counts of this crate still use plain `char`s; a `Sku`
+/// says at a call site that a `char` means a product. It is one of the
+/// newtypes of `newtypes_and_units`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Sku(pub char);
+
+/// Every `char` is a product code, so this cannot fail.
+///
+/// Write `From` and never `Into`: the standard library implements
+/// `Into<Sku> for char` from this impl, while an `Into` impl would give
+/// nothing back.
+impl From<char> for Sku {
+    fn from(code: char) -> Sku {
+        Sku(code)
+    }
+}
+
+/// The way back, for the rules and counts that take a `char`.
+impl From<Sku> for char {
+    fn from(sku: Sku) -> char {
+        sku.0
+    }
+}
+
+impl fmt::Display for Sku {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The largest cart a register will price.
+///
+/// Both limits apply; a cart must stay within each of them.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CartLimits {
+    /// The longest cart string accepted, in bytes. Checked before anything
+    /// else, so an oversized cart costs next to nothing to refuse.
+    pub max_bytes: usize,
+    /// The most items accepted in one cart, after separators are skipped.
+    pub max_items: u64
+}
+
+impl CartLimits {
+
+    /// No limits at all. This is the default, as a library cannot guess how
+    /// large its users' carts are.
+    pub const UNLIMITED: CartLimits = CartLimits { max_bytes: usize::MAX, max_items: u64::MAX };
+}
+
+impl Default for CartLimits {
+    fn default() -> CartLimits {
+        CartLimits::UNLIMITED
+    }
+}
+
+/// The most a register will give away in promotions on one cart: how far
+/// below its list price, every item at its single-item price, a cart's
+/// subtotal may fall.
+///
+/// When a cart's promotions add up to more, the register charges the
+/// difference back, trimming the promotions of the rules applied last
+/// first, and notes each trim on the receipt as an adjustment.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PromotionBudget<M> {
+    /// No cap at all. This is the default.
+    Unlimited,
+    /// At most this much off a cart.
+    Absolute(M),
+    /// At most this percentage of the cart's list price off, rounded
+    /// toward zero to a whole cent.
+    Percent(u32)
+}
+
+impl<M: Money> PromotionBudget<M> {
+
+    /// The most that may be given away on a cart listed at `list_price`, or
+    /// `None` if there is no cap, or it cannot be represented.
+    pub fn cap(self, list_price: M) -> Option<M> {
+        match self {
+            PromotionBudget::Unlimited => None,
+            PromotionBudget::Absolute(cap) => Some(cap),
+            PromotionBudget::Percent(percent) => list_price.checked_percent(percent)
+        }
+    }
+}
+
+/// Written by hand, as `#[derive(Default)]` on a generic enum would demand
+/// `M: Default` for no reason.
+impl<M> Default for PromotionBudget<M> {
+    fn default() -> PromotionBudget<M> {
+        PromotionBudget::Unlimited
+    }
+}
+
+/// What a register does with a cart holding more of a product than that
+/// product's quantity limit, such as two per customer during a shortage.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum QuantityLimitPolicy {
+    /// Refuse the cart with `CheckoutError::QuantityLimitExceeded`. This is
+    /// the default, as nothing is sold that the shopper did not expect.
+    #[default]
+    Refuse,
+    /// Sell up to the limit and leave the rest out, noting it on the
+    /// receipt.
+    Trim
+}
+
+/// What a register does with a cart whose discounts came to more than its
+/// price, leaving a total below zero.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum NegativeTotalPolicy {
+    /// Charge nothing, noting the discount left unused on the receipt. This
+    /// is the default, as a register should never pay a shopper for
+    /// shopping.
+    #[default]
+    Clamp,
+    /// Leave the total below zero, for a till that settles it as a refund
+    /// or store credit.
+    Allow
+}
+
+/// Which of the `CartLimits` a cart went over.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum CartDimension {
+    Bytes,
+    Items
+}
+
+/// Everything that can stop a cart from being priced.
+///
+/// Java code would typically declare one checked exception per problem and
+/// list them all in a `throws` clause. In Rust the problems become the
+/// variants of a single enum, and callers `match` on it to tell them apart.
+///
+/// It is `Clone` but not `Copy`, as one variant holds a `Vec`, which owns
+/// memory on the heap that a bitwise copy would end up freeing twice.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum CheckoutError {
+    /// The total could not be represented under the `Checked` policy.
+    ///
+    /// A variant may wrap another error, as a Java exception wraps its
+    /// cause.
+    Overflow(Overflow),
+    /// The cart held something that is not a single item code, as
+    /// `counting::check_items` decides.
+    InvalidItem(InvalidItem),
+    /// The cart exceeded one of the register's `CartLimits`.
+    CartTooLarge { dimension: CartDimension, limit: u64 },
+    /// An outside source of prices could not price `product`, even after
+    /// `attempts` tries.
+    PriceSourceUnavailable { product: char, attempts: u32 },
+    /// The cart holds age-restricted products, listed once each in order,
+    /// and the customer's age has not been verified.
+    AgeVerificationRequired(Vec<Sku>),
+    /// The cart holds `quantity` of `product`, more than its limit of
+    /// `limit` per cart, under `QuantityLimitPolicy::Refuse`.
+    QuantityLimitExceeded { product: Sku, limit: u64, quantity: u64 }
+}
+
+impl fmt::Display for CheckoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CheckoutError::Overflow(overflow) => write!(f, "{}", overflow),
+            CheckoutError::InvalidItem(ref invalid) => write!(f, "{}", invalid),
+            CheckoutError::CartTooLarge { dimension: CartDimension::Bytes, limit } =>
+                write!(f, "cart is longer than {} bytes", limit),
+            CheckoutError::CartTooLarge { dimension: CartDimension::Items, limit } =>
+                write!(f, "cart holds more than {} items", limit),
+            CheckoutError::PriceSourceUnavailable { product, attempts } =>
+                write!(f, "no price for {} after {} attempts", product, attempts),
+            CheckoutError::AgeVerificationRequired(ref products) => {
+                write!(f, "verify the customer's age to sell")?;
+                for product in products.iter() {
+                    write!(f, " {}", product)?;
+                }
+                Ok(())
+            },
+            CheckoutError::QuantityLimitExceeded { product, limit, quantity } =>
+                write!(f, "cart holds {} of {}, which is limited to {} per cart", quantity, product, limit)
+        }
+    }
+}
+
+impl Error for CheckoutError {
+    /// `source` is the counterpart of Java's `Throwable.getCause()`.
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            CheckoutError::Overflow(ref overflow) => Some(overflow),
+            CheckoutError::InvalidItem(ref invalid) => Some(invalid),
+            CheckoutError::CartTooLarge { .. }
+            | CheckoutError::PriceSourceUnavailable { .. }
+            | CheckoutError::AgeVerificationRequired(_)
+            | CheckoutError::QuantityLimitExceeded { .. } => None
+        }
+    }
+}
+
+/// `From` tells the `?` operator how to convert an error as it propagates,
+/// so a function returning `Result<_, CheckoutError>` can apply `?` to a
+/// `Result<_, Overflow>` directly. Java gets the same effect by making one
+/// exception class extend another.
+impl From<Overflow> for CheckoutError {
+    fn from(overflow: Overflow) -> CheckoutError {
+        CheckoutError::Overflow(overflow)
+    }
+}
+
+impl From<InvalidItem> for CheckoutError {
+    fn from(invalid: InvalidItem) -> CheckoutError {
+        CheckoutError::InvalidItem(invalid)
+    }
+}
@@ -0,0 +1,433 @@
+//! The `counting` module turns a string of item codes into a tally per code.
+//!
+//! It is generic over the integer width used for the tallies. The
+//! Supermarket counts with `u64`, which no string that fits in memory can
+//! overflow, while the narrower and wider widths stay available so the
+//! benchmarks in `benches/counting.rs` can measure what the extra bits cost.
+//!
+//! The tallies are kept in a `BTreeMap`, a sorted map much like Java's
+//! `TreeMap`. Unlike `HashMap`, it needs no source of random numbers to
+//! seed its hasher, so it is available without the standard library.
+//!
+//! # What counts as one item
+//!
+//! An item code is a single `char`, which in Rust is a Unicode *scalar
+//! value* (any code point except a surrogate), not a UTF-16 code unit as in
+//! Java. A single-code-point emoji such as 🍎 is therefore one item.
+//!
+//! What a person sees as one character may still be several `char`s: an
+//! `e` followed by a combining accent, a flag, or 👍🏽 (a thumb plus a skin
+//! tone). Counting those `char` by `char` would quietly charge for items
+//! nobody scanned, so checkout refuses such carts with `check_items`
+//! before pricing them.
+
+use alloc::collections::btree_map::{BTreeMap, Entry};
+use alloc::string::String;
+use core::error::Error;
+use core::fmt;
+use core::ops::AddAssign;
+
+/// The tallies every pricing rule receives: how many of each item were
+/// scanned.
+///
+/// `type` declares an alias, a second name for an existing type. Java has
+/// nothing similar, which is partly why its generic signatures grow so long.
+pub type Counts = BTreeMap<char, u64>;
+
+/// An unsigned integer type usable as a per-item tally.
+///
+/// Java's primitive types cannot be used as generic arguments at all, which
+/// is why `Map<Character, Integer>` boxes every count. Rust generics work
+/// over primitives directly, and the compiler produces a separate,
+/// fully specialized copy of the code for each width that is used.
+pub trait Count: Copy + Eq + Ord + AddAssign + fmt::Debug {
+    /// The tally of a single item.
+    fn one() -> Self;
+}
+
+/// `impl` blocks for primitive types look just like those for our own
+/// structs. This is only allowed because `Count` is our own trait.
+impl Count for u32 {
+    fn one() -> u32 { 1 }
+}
+
+impl Count for u64 {
+    fn one() -> u64 { 1 }
+}
+
+impl Count for u128 {
+    fn one() -> u128 { 1 }
+}
+
+/// Counts the occurrences of each character in `items`.
+///
+/// The caller chooses the width of the tallies, e.g.
+/// `count_items::<u64>("ABBA")`.
+///
+/// `&str` is a borrowed view of string data, so counting does not require
+/// the caller to hand over ownership of their `String`.
+pub fn count_items<C: Count>(items: &str) -> BTreeMap<char, C> {
+    // `let mut` means that this variable is mutable.
+    let mut count = BTreeMap::new();
+
+    // A `for` loop in Rust makes use of iterators. In this case,
+    // `items.chars()` is producing an iterator, which produces
+    // references to the elements of the thing we're iterating over,
+    // namely the characters from the `items` String.
+    for c in items.chars() {
+
+        // `match` is like a `switch` statement on steroids. It checks
+        // at compile time that we've handled all possible cases.
+        //
+        // Importantly, `match` can be used with complex data types
+        // like `enum` and even `struct`s. Like Java, it can also do
+        // primitive types.
+        //
+        // A BTreeMap's `entry` method returns an `enum` of type `Entry`
+        // so here we get confirmation that both of the possible `enum`
+        // options have cases.
+        match count.entry(c) {
+            // cases are specified with the value that should be matched
+            // followed by an `=>` arrow, then an expression.
+            // This case statement matches when `count.entry(c)` returns
+            // the `Vacant` enum value. The `Vacant` option includes a
+            // wrapped reference to the slot in the BTreeMap in question.
+            Entry::Vacant(slot) => {
+                // We only get here when the slot was empty, meaning no
+                // characters matching this one have been found yet,
+                // so we can insert a count of 1
+                slot.insert(C::one());
+            },
+            // cases are separated by commas. The practice of breaking
+            // out the matching value's type and its component members
+            // (here, the `slot`) is called destructuring.
+            Entry::Occupied(mut slot) => {
+                // This slot is occupied, meaning some previous matches
+                // have already been found for this character. Increment
+                // the count by one.
+                *slot.get_mut() += C::one();
+            }
+        }
+    }
+
+    // Return the BTreeMap. Note the lack of a semicolon. semicolons are
+    // used for to split rust expressions into distinct statements.
+    // Statements produce the unit type `()`, which is a lot like `void`
+    // in Java.
+    //
+    // Because we actually want to return a value here, we want an
+    // expression, not a statement on the last line of the function.
+    count
+}
+
+/// A cart contained something that is not a valid item code.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InvalidItem {
+    /// Where the offending item starts, in bytes from the start of the
+    /// cart. Rust strings are indexed by byte, because UTF-8 characters
+    /// vary in width.
+    pub position: usize,
+    /// The offending item, exactly as it appeared in the cart.
+    pub item: String
+}
+
+impl fmt::Display for InvalidItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // `{:?}` uses the `Debug` format, which quotes and escapes the text,
+        // keeping invisible combining marks visible in the message.
+        write!(f, "{:?} at byte {} is not a single item code", self.item, self.position)
+    }
+}
+
+impl Error for InvalidItem {}
+
+/// Checks that every item in `items` is exactly one `char`.
+///
+/// With the `unicode` feature, the cart is split into grapheme clusters,
+/// the units a person perceives as single characters, and any cluster made
+/// of several `char`s is refused. Emoji SKUs like 🍎 pass; `e` with a
+/// combining accent does not.
+///
+/// Separators are items like any other here; a `ScanPolicy` decides later
+/// whether to skip them. The one cluster of several `char`s that passes is
+/// a Windows line break, `\r\n`, which is two separators.
+#[cfg(feature = "unicode")]
+pub fn check_items(items: &str) -> Result<(), InvalidItem> {
+    // A trait must be in scope for its methods to be callable, so this
+    // brings in `grapheme_indices` for `str`.
+    use unicode_segmentation::UnicodeSegmentation;
+
+    for (position, grapheme) in items.grapheme_indices(true) {
+        if grapheme.chars().count() > 1 && grapheme != "\r\n" {
+            return Err(InvalidItem { position, item: String::from(grapheme) });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every item in `items` is exactly one `char`.
+///
+/// This is the build without the `unicode` feature; see the other
+/// `check_items` for the full story. With no Unicode tables to consult, it
+/// refuses the `char`s that most commonly join the one before them:
+/// combining accents, variation selectors, skin tones, tags, anything
+/// after a zero-width joiner, and the second half of a flag. Emoji SKUs
+/// like 🍎 and precomposed letters like `é` still pass. The marks of
+/// scripts the list in `joins` leaves out go unnoticed; the `unicode`
+/// feature catches those too.
+#[cfg(not(feature = "unicode"))]
+pub fn check_items(items: &str) -> Result<(), InvalidItem> {
+    // `peekable` lets the loop look at the next `char` without taking it.
+    let mut chars = items.char_indices().peekable();
+    while let Some((position, first)) = chars.next() {
+        let mut end = position + first.len_utf8();
+        let mut previous = first;
+        // `next_if` takes the next `char` only if the closure accepts it.
+        while let Some((_, c)) = chars.next_if(|&(_, c)| joins(previous, c)) {
+            end += c.len_utf8();
+            // A flag is exactly two regional indicators; a third starts
+            // the next flag.
+            previous = if is_regional_indicator(previous) && is_regional_indicator(c) { '\0' } else { c };
+        }
+        if end > position + first.len_utf8() {
+            return Err(InvalidItem { position, item: String::from(&items[position..end]) });
+        }
+    }
+    Ok(())
+}
+
+/// Whether `c` belongs to the same perceived character as `previous`.
+#[cfg(not(feature = "unicode"))]
+fn joins(previous: char, c: char) -> bool {
+    // Match arms may be ranges of `char`s, written with `..=`.
+    let extends = matches!(c,
+        '\u{300}'..='\u{36f}'       // combining diacritical marks
+        | '\u{1ab0}'..='\u{1aff}'   // ... extended
+        | '\u{1dc0}'..='\u{1dff}'   // ... supplement
+        | '\u{20d0}'..='\u{20ff}'   // ... for symbols
+        | '\u{fe20}'..='\u{fe2f}'   // combining half marks
+        | '\u{fe00}'..='\u{fe0f}'   // variation selectors
+        | '\u{e0100}'..='\u{e01ef}' // ... supplement
+        | '\u{200d}'                // zero-width joiner
+        | '\u{1f3fb}'..='\u{1f3ff}' // skin tones
+        | '\u{e0020}'..='\u{e007f}' // tags, as in subdivision flags
+    );
+    extends || previous == '\u{200d}' || (is_regional_indicator(previous) && is_regional_indicator(c))
+}
+
+/// Whether `c` is one of the letters that make up flags in pairs, such as
+/// 🇺 and 🇸 in 🇺🇸.
+#[cfg(not(feature = "unicode"))]
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1f1e6}'..='\u{1f1ff}')
+}
+
+/// How a cart string is read before its items are counted.
+///
+/// By default every character is an item code in its own right, so `b` and
+/// `B` are different products and a space is an unknown item priced at
+/// nothing. A lenient policy copes with carts typed by hand instead.
+///
+/// The two settings are independent, so this is a struct of flags rather
+/// than an enum like `OverflowPolicy`. Its fields are public because any
+/// combination of them makes sense.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct ScanPolicy {
+    /// Read lowercase ASCII letters as their uppercase codes, so `b` is `B`.
+    pub ignore_case: bool,
+    /// Skip whitespace and commas between codes rather than counting them.
+    pub skip_separators: bool
+}
+
+impl ScanPolicy {
+
+    /// Every character counts exactly as written. This is the default.
+    ///
+    /// `const` items are compile-time constants, like `static final` fields
+    /// in Java.
+    pub const STRICT: ScanPolicy = ScanPolicy { ignore_case: false, skip_separators: false };
+
+    /// Case-insensitive, with separators skipped.
+    pub const LENIENT: ScanPolicy = ScanPolicy { ignore_case: true, skip_separators: true };
+
+    /// The item code `c` stands for under this policy, or `None` if it is a
+    /// separator to be skipped.
+    ///
+    /// Only ASCII letters change case. Full Unicode case mapping can turn one
+    /// character into several (`ß` uppercases to `SS`), which makes no sense
+    /// for a single item code.
+    pub fn scan(self, c: char) -> Option<char> {
+        if self.skip_separators && (c.is_whitespace() || c == ',') {
+            None
+        } else if self.ignore_case {
+            Some(c.to_ascii_uppercase())
+        } else {
+            Some(c)
+        }
+    }
+
+    /// Counts the items in `items`, read according to this policy.
+    ///
+    /// This does the same job as `count_items`, written more tersely:
+    /// `filter_map` applies `scan` to each character and drops the `None`s,
+    /// and the `entry` API's `and_modify`/`or_insert` pair replaces the
+    /// `match` on `Entry`.
+    pub fn count_items<C: Count>(self, items: &str) -> BTreeMap<char, C> {
+        let mut count = BTreeMap::new();
+        for c in items.chars().filter_map(|c| self.scan(c)) {
+            count.entry(c).and_modify(|n| *n += C::one()).or_insert(C::one());
+        }
+        count
+    }
+}
+
+/// Read-only access to per-item tallies, however they happen to be stored.
+///
+/// Rules written against this trait, rather than against one kind of map,
+/// can be handed whichever storage suits the caller.
+pub trait ItemCounts {
+    /// How many of `item` were scanned; zero if it never appeared.
+    fn count_of(&self, item: char) -> u64;
+}
+
+impl ItemCounts for BTreeMap<char, u64> {
+    fn count_of(&self, item: char) -> u64 {
+        // `copied` turns the `Option<&u64>` from `get` into an `Option<u64>`.
+        self.get(&item).copied().unwrap_or(0)
+    }
+}
+
+/// Tallies for carts made up entirely of ASCII item codes, kept in a
+/// fixed-size array indexed by the code itself.
+///
+/// The array lives wherever the struct does, typically on the stack, so
+/// counting a cart this way never touches the heap. Java has no way to ask
+/// for that: every array is a heap object.
+pub struct AsciiCounts {
+    counts: [u64; 128]
+}
+
+impl AsciiCounts {
+    /// Counts `items`, or returns `None` if any of them is not ASCII.
+    pub fn from_items(items: &str) -> Option<AsciiCounts> {
+        let mut counts = [0u64; 128];
+        // Iterating over bytes is safe here: every byte of a multi-byte
+        // character has its high bit set, so `is_ascii` rejects them all.
+        for byte in items.bytes() {
+            if !byte.is_ascii() {
+                return None;
+            }
+            counts[byte as usize] += 1;
+        }
+        Some(AsciiCounts { counts })
+    }
+
+    /// The same tallies as a `Counts` map, holding only the codes that
+    /// appeared.
+    pub fn to_counts(&self) -> Counts {
+        // `enumerate` pairs each tally with its index, which is the code.
+        self.counts.iter().enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(code, &count)| (code as u8 as char, count))
+            .collect()
+    }
+}
+
+impl ItemCounts for AsciiCounts {
+    fn count_of(&self, item: char) -> u64 {
+        if item.is_ascii() {
+            self.counts[item as usize]
+        } else {
+            0
+        }
+    }
+}
+
+/// How a cart's items are tallied before being handed to the rules as
+/// `Counts`.
+///
+/// Every strategy produces the same `Counts`; they differ only in speed.
+/// `benches/counting.rs` compares them across cart and alphabet sizes, and
+/// the default is the one that wins for the common case of a short cart of
+/// ASCII item codes. The others stay available for carts that look
+/// different, such as long carts of emoji SKUs.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum CountStrategy {
+    /// Tally straight into the `BTreeMap`, one lookup per item.
+    Tree,
+    /// Tally ASCII carts into an `AsciiCounts` array, then copy the codes
+    /// that appeared into the map. Indexing an array is far cheaper than
+    /// searching a tree, but zeroing and then scanning its 128 slots is not
+    /// free, so carts shorter than `ARRAY_THRESHOLD` bytes, and carts that
+    /// aren't ASCII, are counted as `Tree` would. This is the default.
+    ///
+    /// `#[default]` marks the variant `derive(Default)` returns.
+    #[default]
+    Array,
+    /// Tally into a `HashMap`, then copy it into the map. Only available
+    /// with the `std` feature, as `HashMap` is.
+    ///
+    /// By default the `HashMap` uses SipHash, which resists inputs crafted
+    /// to collide, at some cost in speed. Item codes are single `char`s
+    /// that cannot collide in any damaging number, so the `fast-hash`
+    /// feature swaps in the much simpler FxHash, the hasher the Rust
+    /// compiler itself uses for its own tables.
+    #[cfg(feature = "std")]
+    Hash
+}
+
+impl CountStrategy {
+
+    /// The shortest cart, in bytes, that `Array` counts in an array. In the
+    /// benchmarks the tree is still faster for ten items of three codes,
+    /// while the array is already ahead for thirty items of twenty-six
+    /// codes, and for a hundred of either.
+    pub const ARRAY_THRESHOLD: usize = 32;
+
+    /// Counts the items in `items` using this strategy.
+    pub fn count_items(self, items: &str) -> Counts {
+        match self {
+            CountStrategy::Tree => count_items(items),
+            CountStrategy::Array => {
+                if items.len() < CountStrategy::ARRAY_THRESHOLD {
+                    return count_items(items);
+                }
+                match AsciiCounts::from_items(items) {
+                    Some(counts) => counts.to_counts(),
+                    None => count_items(items)
+                }
+            },
+            #[cfg(feature = "std")]
+            CountStrategy::Hash => {
+                // Exactly one of these two lines is compiled. Both make a
+                // `HashMap`; they differ only in its hasher, the third type
+                // parameter, which `FxHashMap` fills in.
+                #[cfg(feature = "fast-hash")]
+                let mut counts = rustc_hash::FxHashMap::default();
+                #[cfg(not(feature = "fast-hash"))]
+                let mut counts = std::collections::HashMap::new();
+                for c in items.chars() {
+                    *counts.entry(c).or_insert(0) += 1;
+                }
+                // `collect` builds the sorted map from the hash map's
+                // entries, in whatever order they come.
+                counts.into_iter().collect()
+            }
+        }
+    }
+}
+
+/// `HashMap` needs the standard library, so this impl only exists when the
+/// `std` feature is on.
+///
+/// It has to live here rather than next to `count_characters` in the
+/// facade crate because of the *orphan rule*: a trait may only be
+/// implemented for a type in the crate defining one or the other. Both
+/// `ItemCounts` and `HashMap` belong to other crates from the facade's
+/// point of view.
+#[cfg(feature = "std")]
+impl ItemCounts for std::collections::HashMap<char, u64> {
+    fn count_of(&self, item: char) -> u64 {
+        self.get(&item).copied().unwrap_or(0)
+    }
+}
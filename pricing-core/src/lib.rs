@@ -0,0 +1,38 @@
+//! The pricing engine's core: money, counting, and rule evaluation.
+//!
+//! This crate is written against `core` and `alloc` only, the two slices
+//! of the standard library that need no operating system. `core` holds the
+//! language essentials (`Option`, `Result`, formatting, iterators) and
+//! `alloc` adds the heap-allocated collections (`Vec`, `Box`, `BTreeMap`).
+//! That is enough to price carts on an embedded point-of-sale terminal.
+//!
+//! Java has no real counterpart. Even Java ME assumes a JVM; there is no
+//! supported way to run "just the collections" on bare metal.
+//!
+//! The `rust_for_java_devs` crate re-exports this one as its `core` module,
+//! and that is the best place to start reading.
+#![no_std]
+
+/// `alloc` is the part of the standard library providing heap allocation:
+/// `Box`, `Vec`, `String` and friends. A `no_std` crate must request it
+/// explicitly.
+extern crate alloc;
+
+/// With the `std` feature on, the full standard library is linked back in
+/// for the few impls, such as those for `HashMap`, that need it.
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod money;
+pub mod counting;
+pub mod rules;
+pub mod lint;
+pub mod checkout;
+pub mod plan;
+pub mod observer;
+/// Values worked out once and remembered, for the engine's own use.
+mod memo;
+/// Random catalogs, carts and amounts for property tests. Only compiled
+/// with the `proptest` feature.
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
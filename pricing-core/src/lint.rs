@@ -0,0 +1,72 @@
+//! Catalog linting: spotting rules that are legal but probably mistaken.
+//!
+//! A rule constructor refuses values that cannot work at all, such as a
+//! bundle of zero items. Linting catches the ones that work but are likely
+//! typos, like a "deal" that costs more than buying the items one by one,
+//! so that they can be fixed before the catalog reaches a register.
+
+use alloc::collections::btree_map::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use crate::money::Money;
+use crate::rules::PricingRule;
+
+/// One suspicious thing about a catalog.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum LintWarning {
+    /// A product that is stocked but that no rule prices, so it is free.
+    Unpriced { product: char },
+    /// A product priced by more than one rule, so it is charged for twice.
+    Conflicting { product: char, rules: usize },
+    /// A bundle that costs more than the same items bought individually.
+    BundleCostsMore { product: char },
+    /// A rule giving a product away for nothing, or paying to take it.
+    NonPositivePrice { product: char }
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LintWarning::Unpriced { product } =>
+                write!(f, "{}: no rule prices this product", product),
+            LintWarning::Conflicting { product, rules } =>
+                write!(f, "{}: priced by {} rules", product, rules),
+            LintWarning::BundleCostsMore { product } =>
+                write!(f, "{}: the bundle costs more than its items bought singly", product),
+            LintWarning::NonPositivePrice { product } =>
+                write!(f, "{}: priced at zero or less", product)
+        }
+    }
+}
+
+/// Lints every rule on its own, then looks for products claimed by more
+/// than one rule and for products in `stocked` that no rule claims.
+///
+/// Pass an empty `stocked` to skip the check for unpriced products.
+pub fn lint_rules<'r, M, R, I>(rules: I, stocked: &[char]) -> Vec<LintWarning>
+    where M: Money,
+          R: PricingRule<M> + ?Sized + 'r,
+          I: IntoIterator<Item = &'r R>
+{
+    let mut warnings = Vec::new();
+    // How many rules claim each product. A `BTreeMap` keeps the warnings
+    // in product order, so the output is the same from one run to the next.
+    let mut claims: BTreeMap<char, usize> = BTreeMap::new();
+    for rule in rules {
+        warnings.extend(rule.lint());
+        for product in rule.products() {
+            *claims.entry(product).or_insert(0) += 1;
+        }
+    }
+    for (&product, &rules) in claims.iter() {
+        if rules > 1 {
+            warnings.push(LintWarning::Conflicting { product, rules });
+        }
+    }
+    for &product in stocked {
+        if !claims.contains_key(&product) {
+            warnings.push(LintWarning::Unpriced { product });
+        }
+    }
+    warnings
+}
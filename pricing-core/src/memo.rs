@@ -0,0 +1,66 @@
+//! A value worked out once, on first use, and remembered afterwards.
+//!
+//! Rules are shared between threads through `&self`, so a rule remembering
+//! something needs a cell that is safe to fill from any of them. The
+//! standard library's `OnceLock` is exactly that, but it needs an operating
+//! system to make waiting threads sleep. Without the `std` feature, a `Memo`
+//! therefore remembers nothing, and the value is worked out every time.
+//!
+//! A `Memo` also stays out of the way of its owner's derived traits: every
+//! `Memo` equals every other, and `Debug` shows none of its contents, so a
+//! rule that has been used compares and prints like one that has not.
+
+use core::fmt;
+
+/// A lazily computed `T`, remembered when the `std` feature is on.
+pub(crate) struct Memo<T> {
+    #[cfg(feature = "std")]
+    cell: std::sync::OnceLock<T>,
+    /// `PhantomData` makes the struct "use" `T`, which the compiler insists
+    /// on, without storing one.
+    #[cfg(not(feature = "std"))]
+    cell: core::marker::PhantomData<T>
+}
+
+impl<T> Memo<T> {
+
+    /// A memo with nothing remembered yet.
+    pub(crate) fn new() -> Memo<T> {
+        Memo { cell: Default::default() }
+    }
+
+    /// Calls `f` with the remembered value, first working it out with
+    /// `init` if there is none.
+    ///
+    /// Handing the value to a closure, rather than returning a reference to
+    /// it, lets the `no_std` version lend out a value it does not keep.
+    #[cfg(feature = "std")]
+    pub(crate) fn with<R>(&self, init: impl FnOnce() -> T, f: impl FnOnce(&T) -> R) -> R {
+        f(self.cell.get_or_init(init))
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn with<R>(&self, init: impl FnOnce() -> T, f: impl FnOnce(&T) -> R) -> R {
+        f(&init())
+    }
+}
+
+/// A clone starts out with nothing remembered, as a copy of a value may go
+/// on to be changed independently of the original.
+impl<T> Clone for Memo<T> {
+    fn clone(&self) -> Memo<T> {
+        Memo::new()
+    }
+}
+
+impl<T> PartialEq for Memo<T> {
+    fn eq(&self, _other: &Memo<T>) -> bool {
+        true
+    }
+}
+
+impl<T> fmt::Debug for Memo<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Memo")
+    }
+}
@@ -0,0 +1,363 @@
+//! The `money` module answers a question the challenge prompt side-stepped:
+//! what type should a price actually be?
+//!
+//! Java developers usually reach for either `int`/`long` cents or
+//! `java.math.BigDecimal`. Rust offers the same two families, and rather than
+//! pick one for everybody, we describe what the pricing code needs from a
+//! monetary amount in a trait and let callers choose the backend.
+
+use core::error::Error;
+use core::fmt;
+use core::iter::Sum;
+
+/// `+`, `+=`, `*` and `sum` for `Cents`, in `src/money/operators.rs`.
+pub mod operators;
+
+/// Optional dependencies are only available when their feature is enabled,
+/// so even the `use` line importing one must be behind the same `cfg`.
+///
+/// Re-exporting `Decimal` with `pub use` spares callers from depending on
+/// `rust_decimal` themselves just to name the type.
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+
+/// The shared behavior every money backend must provide.
+///
+/// A trait used as a *bound* (rather than as a boxed object) is roughly the
+/// Rust equivalent of a bounded Java generic such as `<M extends Money>`.
+/// Pricing rules are written once against this trait and work unchanged
+/// with any backend.
+///
+/// The list after the colon names *supertraits*: anything implementing
+/// `Money` must also implement each of them. `Copy` means values are
+/// duplicated bit-for-bit on assignment, just like a Java `long`. `Send`,
+/// `Sync` and `'static` mean an amount may be shared freely between threads
+/// and never borrows anything, which every sensible money type satisfies.
+pub trait Money: Copy + PartialEq + PartialOrd + fmt::Debug + fmt::Display
+    + Send + Sync + 'static {
+
+    /// The amount representing "no cost at all".
+    ///
+    /// Functions without a `self` parameter are called on the type itself,
+    /// e.g. `Cents::zero()`, much like a Java static factory method.
+    fn zero() -> Self;
+
+    /// Builds an amount from a whole number of cents, which is the common
+    /// denominator every backend can represent exactly.
+    fn from_cents(cents: i64) -> Self;
+
+    /// Adds two amounts together, or returns `None` if the result cannot be
+    /// represented.
+    ///
+    /// `Option<Self>` is Rust's explicit "maybe a value" type. Unlike a Java
+    /// method returning a nullable reference, the caller cannot use the
+    /// result without first handling the `None` case.
+    fn checked_plus(self, other: Self) -> Option<Self>;
+
+    /// Subtracts `other` from an amount, or returns `None` if the result
+    /// cannot be represented. Pricing never subtracts, but reports comparing
+    /// two prices do.
+    fn checked_minus(self, other: Self) -> Option<Self>;
+
+    /// Multiplies an amount by a count of items, or returns `None` if the
+    /// result cannot be represented.
+    fn checked_times(self, quantity: u64) -> Option<Self>;
+
+    /// `percent` percent of an amount, rounded toward zero to a whole cent,
+    /// or `None` if it cannot be represented. A percentage is the one
+    /// fraction pricing needs, as in a `PromotionBudget`, so there is no
+    /// general division.
+    fn checked_percent(self, percent: u32) -> Option<Self>;
+
+    /// Adds two amounts, clamping at the largest or smallest representable
+    /// amount instead of overflowing.
+    fn saturating_plus(self, other: Self) -> Self;
+
+    /// Multiplies an amount by a count of items, clamping at the largest or
+    /// smallest representable amount instead of overflowing.
+    fn saturating_times(self, quantity: u64) -> Self;
+
+    /// Adds two amounts, wrapping around on overflow the way Java's `int`
+    /// arithmetic silently does.
+    fn wrapping_plus(self, other: Self) -> Self;
+
+    /// Multiplies an amount by a count of items, wrapping around on overflow.
+    fn wrapping_times(self, quantity: u64) -> Self;
+
+    /// The amount in whole currency units, such as dollars, as an `f64`.
+    ///
+    /// A floating point number cannot hold every amount exactly, which is
+    /// why no pricing code uses one. This exists for reporting, such as
+    /// metrics histograms, where a close approximation is all that matters.
+    fn to_f64(self) -> f64;
+}
+
+/// How a market should react when a total is too large to represent.
+///
+/// A Java `int` silently wraps from 2147483647 to -2147483648, and Rust only
+/// catches the equivalent mistake with a panic in debug builds. Rather than
+/// leave the outcome to the build profile, a market carries one of these
+/// values and every sum and product made while pricing a cart honors it.
+///
+/// An `enum` in Rust names a fixed set of alternatives, like a Java `enum`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum OverflowPolicy {
+    /// Report overflow as an `Overflow` error. This is the default.
+    Checked,
+    /// Clamp at the largest or smallest representable amount.
+    Saturating,
+    /// Wrap around like Java `int` arithmetic. Totals produced this way are
+    /// meaningless once overflow happens, so this is only suitable for
+    /// benchmarks that want to exclude the cost of overflow checks.
+    Wrapping
+}
+
+/// `Default` is the standard trait for "the value you get when you don't
+/// specify one", similar to a Java no-argument constructor.
+impl Default for OverflowPolicy {
+    fn default() -> OverflowPolicy {
+        OverflowPolicy::Checked
+    }
+}
+
+impl OverflowPolicy {
+    /// Adds two amounts according to this policy.
+    ///
+    /// `Result<T, E>` holds either a success value, `Ok(T)`, or an error,
+    /// `Err(E)`. It fills the role of a Java checked exception, except that
+    /// the error is an ordinary return value.
+    pub fn plus<M: Money>(self, a: M, b: M) -> Result<M, Overflow> {
+        match self {
+            // `ok_or` converts an `Option` into a `Result`, supplying the
+            // error to use in place of `None`.
+            OverflowPolicy::Checked => a.checked_plus(b).ok_or(Overflow),
+            OverflowPolicy::Saturating => Ok(a.saturating_plus(b)),
+            OverflowPolicy::Wrapping => Ok(a.wrapping_plus(b))
+        }
+    }
+
+    /// `amount`, to be added up with others under this policy by `sum`.
+    pub fn charge<M: Money>(self, amount: Result<M, Overflow>) -> Charge<M> {
+        Charge { amount, policy: self }
+    }
+
+    /// Multiplies a price by a count of items according to this policy.
+    pub fn times<M: Money>(self, price: M, quantity: u64) -> Result<M, Overflow> {
+        match self {
+            OverflowPolicy::Checked => price.checked_times(quantity).ok_or(Overflow),
+            OverflowPolicy::Saturating => Ok(price.saturating_times(quantity)),
+            OverflowPolicy::Wrapping => Ok(price.wrapping_times(quantity))
+        }
+    }
+}
+
+/// The error reported by the `Checked` policy when an amount cannot be
+/// represented by the money backend.
+///
+/// A struct with no fields is called a "unit struct". It carries no data;
+/// its type alone says what went wrong.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "monetary amount overflowed")
+    }
+}
+
+/// Implementing `Error` lets `Overflow` travel anywhere a general error is
+/// expected, the way any exception can be caught as a `Throwable` in Java.
+impl Error for Overflow {}
+
+/// An amount to be added to a total under `policy`, or the overflow that
+/// stopped it being worked out. Made by `OverflowPolicy::charge`.
+///
+/// `Iterator::sum` has nowhere to pass a policy, so each charge carries its
+/// own, and a sequence of them adds up with `sum` into a
+/// `Result<M, Overflow>`:
+///
+/// ```
+/// use pricing_core::money::{Cents, Overflow, OverflowPolicy};
+///
+/// let prices = [Ok(Cents(2000)), Ok(Cents(i64::MAX))];
+/// let checked: Result<Cents, Overflow> =
+///     prices.iter().map(|&price| OverflowPolicy::Checked.charge(price)).sum();
+/// assert_eq!(Err(Overflow), checked);
+/// let saturated: Result<Cents, Overflow> =
+///     prices.iter().map(|&price| OverflowPolicy::Saturating.charge(price)).sum();
+/// assert_eq!(Ok(Cents(i64::MAX)), saturated);
+/// ```
+///
+/// This is what `Collectors.summingLong` cannot do: a Java stream of `long`s
+/// sums with silent wrapping, and one of `Optional`s does not sum at all.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Charge<M> {
+    pub amount: Result<M, Overflow>,
+    pub policy: OverflowPolicy
+}
+
+/// The total of some charges, stopping at the first overflow, whether in a
+/// charge or in adding it. No charges at all total zero.
+///
+/// The orphan rule allows this impl of a foreign trait for the foreign
+/// `Result`, because the trait's type argument, `Charge`, is defined here.
+impl<M: Money> Sum<Charge<M>> for Result<M, Overflow> {
+    fn sum<I: Iterator<Item = Charge<M>>>(mut charges: I) -> Result<M, Overflow> {
+        charges.try_fold(M::zero(), |total, charge| charge.policy.plus(total, charge.amount?))
+    }
+}
+
+/// The default backend: a whole number of cents.
+///
+/// This is a "tuple struct", a struct whose single field is unnamed and
+/// accessed as `.0`. Wrapping a primitive like this costs nothing at runtime
+/// but prevents a price from being accidentally mixed up with a count.
+///
+/// `#[derive(...)]` asks the compiler to write the listed trait
+/// implementations for us, similar to Lombok generating `equals`/`hashCode`.
+///
+/// `cfg_attr` applies an attribute only when its condition holds, so serde's
+/// derives are added with the `serde` feature alone. Serde writes a tuple
+/// struct of one field as that field, so `Cents(2000)` becomes `2000`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cents(pub i64);
+
+impl Money for Cents {
+    fn zero() -> Cents {
+        Cents(0)
+    }
+
+    fn from_cents(cents: i64) -> Cents {
+        Cents(cents)
+    }
+
+    // Each primitive integer type comes with checked, saturating and
+    // wrapping flavors of its arithmetic, so these are mostly one-liners.
+
+    fn checked_plus(self, other: Cents) -> Option<Cents> {
+        // `map` transforms the value inside a `Some`, leaving `None` alone.
+        self.0.checked_add(other.0).map(Cents)
+    }
+
+    fn checked_minus(self, other: Cents) -> Option<Cents> {
+        self.0.checked_sub(other.0).map(Cents)
+    }
+
+    fn checked_times(self, quantity: u64) -> Option<Cents> {
+        // A quantity too large for an `i64` cannot multiply into an `i64`
+        // either, unless the price is zero.
+        if quantity > i64::MAX as u64 {
+            return if self.0 == 0 { Some(self) } else { None };
+        }
+        self.0.checked_mul(quantity as i64).map(Cents)
+    }
+
+    fn checked_percent(self, percent: u32) -> Option<Cents> {
+        // Integer division rounds toward zero, as Java's `/` does.
+        self.0.checked_mul(i64::from(percent)).map(|hundredths| Cents(hundredths / 100))
+    }
+
+    fn saturating_plus(self, other: Cents) -> Cents {
+        Cents(self.0.saturating_add(other.0))
+    }
+
+    fn saturating_times(self, quantity: u64) -> Cents {
+        match self.checked_times(quantity) {
+            Some(total) => total,
+            None if self.0 < 0 => Cents(i64::MIN),
+            None => Cents(i64::MAX)
+        }
+    }
+
+    fn wrapping_plus(self, other: Cents) -> Cents {
+        Cents(self.0.wrapping_add(other.0))
+    }
+
+    fn wrapping_times(self, quantity: u64) -> Cents {
+        Cents(self.0.wrapping_mul(quantity as i64))
+    }
+
+    fn to_f64(self) -> f64 {
+        // `as` between integers and floats rounds to the nearest `f64`,
+        // like a Java `(double)` cast.
+        self.0 as f64 / 100.0
+    }
+}
+
+/// `Display` is the user-facing text form of a value, used by `{}` in
+/// `format!` and `println!`. It plays the role of a carefully written Java
+/// `toString()`.
+impl fmt::Display for Cents {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        // `abs` would overflow for `i64::MIN`, which has no positive
+        // counterpart; `unsigned_abs` returns the magnitude as a `u64`.
+        let magnitude = self.0.unsigned_abs();
+        write!(f, "{}${}.{:02}", sign, magnitude / 100, magnitude % 100)
+    }
+}
+
+/// The exact decimal backend, for goods priced in fractions of a cent such
+/// as fuel or produce sold by weight.
+///
+/// The `#[cfg(feature = "decimal")]` attribute means this code only exists
+/// when the crate is built with `--features decimal`, so users who don't need
+/// it never download or compile the `rust_decimal` dependency. Java has no
+/// direct equivalent; the closest is an optional Maven dependency plus
+/// reflection to discover whether it is present.
+///
+/// Note that we are implementing our own trait for a type from *another*
+/// crate. Rust allows this as long as either the trait or the type is local.
+#[cfg(feature = "decimal")]
+impl Money for Decimal {
+    fn zero() -> Decimal {
+        Decimal::ZERO
+    }
+
+    fn from_cents(cents: i64) -> Decimal {
+        Decimal::new(cents, 2)
+    }
+
+    fn checked_plus(self, other: Decimal) -> Option<Decimal> {
+        self.checked_add(other)
+    }
+
+    fn checked_minus(self, other: Decimal) -> Option<Decimal> {
+        self.checked_sub(other)
+    }
+
+    fn checked_times(self, quantity: u64) -> Option<Decimal> {
+        self.checked_mul(Decimal::from(quantity))
+    }
+
+    fn checked_percent(self, percent: u32) -> Option<Decimal> {
+        let exact = self.checked_mul(Decimal::from(percent))?.checked_div(Decimal::ONE_HUNDRED)?;
+        Some(exact.round_dp_with_strategy(2, rust_decimal::RoundingStrategy::ToZero))
+    }
+
+    fn saturating_plus(self, other: Decimal) -> Decimal {
+        self.saturating_add(other)
+    }
+
+    fn saturating_times(self, quantity: u64) -> Decimal {
+        self.saturating_mul(Decimal::from(quantity))
+    }
+
+    // A decimal has no fixed bit pattern to wrap around, so the `Wrapping`
+    // policy saturates instead.
+
+    fn wrapping_plus(self, other: Decimal) -> Decimal {
+        self.saturating_plus(other)
+    }
+
+    fn wrapping_times(self, quantity: u64) -> Decimal {
+        self.saturating_times(quantity)
+    }
+
+    fn to_f64(self) -> f64 {
+        // Every `Decimal` is within range of an `f64`, so the conversion
+        // never actually falls back to zero.
+        rust_decimal::prelude::ToPrimitive::to_f64(&self).unwrap_or(0.0)
+    }
+}
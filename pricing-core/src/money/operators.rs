@@ -0,0 +1,99 @@
+//! Arithmetic operators for `Cents`, so that prices can be added with `+`.
+//!
+//! Java has no operator overloading. `+` works on primitives and strings
+//! and nothing else, so a `long` of cents can be added with `+`, silently
+//! wrapping around on overflow, while a `BigDecimal` has to be added with
+//! `a.add(b)`, and a sum of several becomes a chain of calls. Rust defines
+//! each operator as a trait in `core::ops`: `a + b` is `Add::add(a, b)`,
+//! and any type implementing `Add` may be added with `+`.
+//!
+//! This is not free-for-all overloading, as in C++:
+//!
+//! * Each operator maps to exactly one trait with a fixed meaning, and
+//!   `==` and `<` come from `PartialEq` and `PartialOrd`, which have
+//!   contracts of their own.
+//! * The *orphan rule* says an operator may only be implemented where
+//!   either the trait or the type is defined. Only this crate can teach
+//!   `Cents` to add, and nobody can teach `i64` new tricks. That is why
+//!   these impls live here, next to `Cents`, and not in an educational
+//!   module of the main crate. It is also why `Decimal` gets no impls
+//!   here: it comes with its own, from `rust_decimal`.
+//!
+//! `Ord`, the ordering `<` and `sort` use, is already derived on `Cents`.
+//! A derived ordering compares fields in order, here the single `i64`,
+//! which is the ordering of amounts. Java needs `Comparable` written by
+//! hand, or a `Comparator.comparingLong(Cents::value)`.
+//!
+//! The operators panic on overflow, in every build, where a Java `long`
+//! wraps and Rust's own `i64` panics only in debug builds. A wrong total is
+//! worse than none. Pricing code, which must honor a market's
+//! `OverflowPolicy`, keeps using the `Money` methods; the operators are for
+//! code where an overflow would be a bug, such as tests and reports.
+
+use core::iter::Sum;
+use core::ops::{Add, AddAssign, Mul};
+use super::Cents;
+
+/// `a + b`.
+///
+/// `type Output` says what the sum is. It need not be the operands' type:
+/// adding two `Instant`s is not allowed at all, and an `Instant` plus a
+/// `Duration` is an `Instant`.
+impl Add for Cents {
+    type Output = Cents;
+
+    fn add(self, other: Cents) -> Cents {
+        match self.0.checked_add(other.0) {
+            Some(total) => Cents(total),
+            None => panic!("{} + {} overflowed", self, other)
+        }
+    }
+}
+
+/// `total += price`, Java's `total += price` on a `long`, but checked.
+///
+/// A separate trait from `Add`, as a type may update itself in place more
+/// cheaply than it makes a new value; for a `Copy` type like `Cents` the two
+/// are the same, and this one simply builds on `Add`.
+impl AddAssign for Cents {
+    fn add_assign(&mut self, other: Cents) {
+        *self = *self + other;
+    }
+}
+
+/// `price * quantity`, for a quantity of items.
+///
+/// Only `Cents * u32` is defined, not `Cents * Cents`, which would be
+/// square money. The operand types are part of the impl, so multiplying by
+/// an `i64`, or the wrong way round, does not compile, where Java's `long`
+/// arithmetic accepts any pair of numbers.
+impl Mul<u32> for Cents {
+    type Output = Cents;
+
+    fn mul(self, quantity: u32) -> Cents {
+        match self.0.checked_mul(i64::from(quantity)) {
+            Some(total) => Cents(total),
+            None => panic!("{} * {} overflowed", self, quantity)
+        }
+    }
+}
+
+/// `iter.sum()` over amounts, the `Collectors.summingLong` of `Cents`.
+///
+/// `sum` is defined for any type implementing `Sum`, and this one builds on
+/// `Add`, so it panics on overflow too. An empty iterator sums to zero.
+impl Sum for Cents {
+    fn sum<I: Iterator<Item = Cents>>(amounts: I) -> Cents {
+        amounts.fold(Cents(0), Add::add)
+    }
+}
+
+/// `iter.sum()` over borrowed amounts, as from `prices.iter()`, which
+/// yields `&Cents` rather than `Cents`.
+///
+/// The `'a` says only that each reference lives long enough to be read.
+impl<'a> Sum<&'a Cents> for Cents {
+    fn sum<I: Iterator<Item = &'a Cents>>(amounts: I) -> Cents {
+        amounts.copied().sum()
+    }
+}
@@ -0,0 +1,63 @@
+//! Hooks for watching a checkout as it happens.
+//!
+//! This is Rust's take on Java's listener interfaces, such as
+//! `ActionListener`. A `CheckoutObserver` is registered on a Supermarket,
+//! which calls it back at each step of pricing a cart, so metrics, logging
+//! or a till's display can follow along without touching the engine.
+//!
+//! Two differences from the Java pattern are worth noticing:
+//!
+//! * Every callback has a default, empty body, so an observer implements
+//!   only the ones it cares about. Java needs a separate adapter class
+//!   (`MouseAdapter` for `MouseListener`) or `default` methods for that.
+//! * Callbacks receive `&self`, not `&mut self`, because a Supermarket may
+//!   be pricing carts on several threads at once. An observer that records
+//!   something must use a thread-safe container for it, such as an atomic
+//!   counter or a `Mutex`, the same caution a Java listener on a shared
+//!   object needs but which the compiler here enforces.
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use crate::money::Money;
+use crate::rules::PricingRule;
+
+/// Callbacks made while a cart is priced, in this order: every item
+/// counted, every rule applied, then the total.
+pub trait CheckoutObserver<M: Money> {
+
+    /// `count` of `item` were found in the cart. Called once per distinct
+    /// item, in item order.
+    ///
+    /// The leading underscores tell the compiler that the unused
+    /// parameters of these empty default bodies are unused on purpose.
+    fn on_item_counted(&self, _item: char, _count: u64) {}
+
+    /// `rule` charged `amount` for the cart.
+    ///
+    /// The rule itself is passed, rather than its description, so that an
+    /// observer which does not need the text does not pay to format it.
+    fn on_rule_applied(&self, _rule: &dyn PricingRule<M>, _amount: M) {}
+
+    /// The cart came to `total`. Not called if pricing failed.
+    fn on_total(&self, _total: M) {}
+}
+
+/// An `Arc`-wrapped observer is an observer too, so the code that registers
+/// one can keep its own handle and read what the observer recorded.
+///
+/// `Arc` needs atomic instructions, which a few small microcontrollers
+/// lack, hence the `cfg`.
+#[cfg(target_has_atomic = "ptr")]
+impl<M: Money, O: CheckoutObserver<M> + ?Sized> CheckoutObserver<M> for Arc<O> {
+    fn on_item_counted(&self, item: char, count: u64) {
+        (**self).on_item_counted(item, count)
+    }
+
+    fn on_rule_applied(&self, rule: &dyn PricingRule<M>, amount: M) {
+        (**self).on_rule_applied(rule, amount)
+    }
+
+    fn on_total(&self, total: M) {
+        (**self).on_total(total)
+    }
+}
@@ -0,0 +1,89 @@
+//! A structured account of how a cart's total was reached.
+//!
+//! `total` answers "how much?". A `PricingPlan` also answers "why?": which
+//! rule charged for which items, under which offer, and how much each part
+//! came to. Systems that audit prices, print itemized receipts or search
+//! for better offers can walk the tree rather than reverse engineer a
+//! single number.
+//!
+//! The tree has three levels, each holding the sum of the one below:
+//!
+//! ```text
+//! PricingPlan           total: $240.00
+//! ├── RuleNode          A: $20.00 each                 $60.00
+//! │   └── OfferNode     3 lots of 1 A at $20.00         $60.00
+//! └── RuleNode          B: $50.00 each or 5 for $150.00 $150.00 ...
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::counting::Counts;
+use crate::money::{Money, OverflowPolicy, Overflow};
+use crate::rules::PricingRule;
+
+/// The root of the tree: the whole cart.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PricingPlan<M: Money> {
+    /// One node per rule, in the order the rules were applied.
+    pub rules: Vec<RuleNode<M>>,
+    /// The sum of every rule's amount, as `total` would report it.
+    pub total: M
+}
+
+/// What one rule charged, and for what.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RuleNode<M: Money> {
+    /// The rule's own `describe` text.
+    pub description: String,
+    /// The offers the rule applied. Rules that don't explain themselves
+    /// leave this empty and report only an `amount`.
+    pub offers: Vec<OfferNode<M>>,
+    /// Everything this rule charged.
+    pub amount: M
+}
+
+/// Some items bought under a single offer: `lots` lots of `lot_size`
+/// items, at `lot_cost` per lot.
+///
+/// Single items are lots of one.
+#[derive(Clone, PartialEq, Debug)]
+pub struct OfferNode<M: Money> {
+    pub product: char,
+    pub lot_size: u64,
+    pub lots: u64,
+    pub lot_cost: M,
+    /// `lot_cost` times `lots`.
+    pub amount: M
+}
+
+impl<M: Money> OfferNode<M> {
+
+    /// An offer node, with its amount worked out under `policy`.
+    pub fn new(product: char, lot_size: u64, lots: u64, lot_cost: M,
+               policy: OverflowPolicy) -> Result<OfferNode<M>, Overflow> {
+        let amount = policy.times(lot_cost, lots)?;
+        Ok(OfferNode { product, lot_size, lots, lot_cost, amount })
+    }
+
+    /// How many items this node accounts for.
+    pub fn items(&self) -> u64 {
+        self.lot_size.saturating_mul(self.lots)
+    }
+}
+
+/// Builds the plan for `counts`, the explaining counterpart of
+/// `rules::total`. Its `total` always equals what `total` returns.
+pub fn plan<'r, M, R, I>(rules: I, counts: &Counts, policy: OverflowPolicy) -> Result<PricingPlan<M>, Overflow>
+    where M: Money,
+          R: PricingRule<M> + ?Sized + 'r,
+          I: IntoIterator<Item = &'r R>
+{
+    let mut nodes = Vec::new();
+    let mut total = M::zero();
+    for rule in rules {
+        let node = rule.plan(counts, policy)?;
+        total = policy.plus(total, node.amount)?;
+        nodes.push(node);
+    }
+    Ok(PricingPlan { rules: nodes, total })
+}
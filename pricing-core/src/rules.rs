@@ -0,0 +1,901 @@
+//! Pricing rules and the code that adds their prices up.
+//!
+//! Everything here needs only `core` and `alloc`, so it runs wherever the
+//! rest of the `core` module does.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use crate::counting::Counts;
+use crate::lint::LintWarning;
+use crate::memo::Memo;
+use crate::plan::{RuleNode, OfferNode};
+use crate::money::{Money, OverflowPolicy, Overflow};
+
+/// The reasons a rule's constructor may refuse its arguments.
+///
+/// Where a Java constructor would throw an `IllegalArgumentException`, a
+/// Rust constructor that can fail returns a `Result`, and the error type
+/// lists every way it can fail. The compiler then insists that callers
+/// deal with the possibility.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum RuleError {
+    /// A cost was below zero. Discounts belong in a rule of their own.
+    NegativeCost,
+    /// A bundle of zero items, which would mean dividing by zero.
+    EmptyBundle,
+    /// A substitute's rule does not price the product it stands in for.
+    UnpricedOriginal,
+    /// A percentage off over 100, which would pay the customer to shop.
+    PercentOutOfRange
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RuleError::NegativeCost => write!(f, "costs may not be negative"),
+            RuleError::EmptyBundle => write!(f, "bundles must hold at least one item"),
+            RuleError::UnpricedOriginal => write!(f, "the rule does not price the product substituted for"),
+            RuleError::PercentOutOfRange => write!(f, "percentages off must be from 0 to 100")
+        }
+    }
+}
+
+impl Error for RuleError {}
+
+/// Checks that `cost` is zero or more.
+fn check_cost<M: Money>(cost: M) -> Result<M, RuleError> {
+    if cost < M::zero() {
+        Err(RuleError::NegativeCost)
+    } else {
+        Ok(cost)
+    }
+}
+
+/// Something that can sum itself up in a line of text, for catalogs and
+/// receipts.
+///
+/// Rules need this, but so might much else, such as the offers of a loyalty
+/// scheme, so it is a trait of its own rather than part of `PricingRule`.
+/// `PricingRule` then requires it, as a *supertrait*; see there.
+pub trait Describable {
+
+    /// A one-line, human readable summary, such as
+    /// "B: $50.00 each or 5 for $150.00".
+    ///
+    /// This plays the part `toString()` does in Java, but unlike
+    /// `toString()` there is no inherited default: every rule must say
+    /// something meaningful about itself.
+    fn describe(&self) -> String;
+}
+
+/// Provides a means of attaching a price to some subset of the items.
+///
+/// From a design perspective, a more complicated and robust solution
+/// might include additional functions or return values to specify
+/// which of the input items were actually accounted-for by this rule
+/// in order to discover un-priced items. For the sake of simplicity,
+/// these have been omitted.
+///
+/// Rules are generic over the money backend `M`, so a single rule
+/// definition serves integer cents and exact decimals alike.
+///
+/// The trait is public so that applications, including embedded ones, can
+/// write rules of their own.
+///
+/// `: Describable` makes `Describable` a *supertrait*: every type
+/// implementing `PricingRule` must implement `Describable` too, and code
+/// holding any rule, even a `dyn PricingRule<M>`, may call `describe` on
+/// it. This is Java's `interface PricingRule extends Describable`, with one
+/// difference: a Java class implements both interfaces' methods in one
+/// body, while a Rust type has a separate `impl` block for each trait.
+///
+/// # Order does not matter
+///
+/// A Supermarket adds up the prices of its rules, so a rule's price must
+/// depend on its arguments alone: never on which rules were registered
+/// before it, nor on how often it has been asked. Rules keeping to this, as
+/// every rule in this crate does, price a cart the same whatever order they
+/// are registered in. A Java catalog loaded by iterating a `HashMap` would
+/// otherwise price the same carts differently from one run to the next,
+/// and nobody would notice until two registers disagreed.
+///
+/// The one exception is overflow. Prices below zero can make a sum
+/// overflow in one order and not in another, under the `Checked` and
+/// `Saturating` policies; with prices of zero or more, an overflow is
+/// reported, or saturated, in every order alike.
+pub trait PricingRule<M: Money>: Describable {
+    ///
+    /// # Arguments
+    /// 
+    /// * `character_counts` - the number of instances of each character
+    ///   found in the `items` input String to the `Market.checkout` function.
+    ///   Note that because this input is a simple map of counts, any ordering
+    ///   of characters found in the original string has been lost, so
+    ///   sequence-order-dependent pricing rules are not expressable with
+    ///   this interface formulation.
+    ///
+    /// * `policy` - the owning market's overflow policy, to be used for
+    ///   all arithmetic on money.
+    ///
+    /// # Returns
+    /// The price of the items that this rule is accounting for.
+    /// This number may be negative, possibly useful for indicating some
+    /// discount, coupon, or combo deal. An error is returned only when
+    /// the `Checked` policy detects an overflow.
+    fn price(&self, character_counts:&Counts,
+             policy: OverflowPolicy) -> Result<M, Overflow>;
+
+    /// The products this rule puts a price on, used to spot products
+    /// priced by several rules at once.
+    ///
+    /// Unlike `price`, this method has a body in the trait itself: a
+    /// *provided* method, like a Java `default` method. Rules that don't
+    /// override it claim no products.
+    fn products(&self) -> Vec<char> {
+        Vec::new()
+    }
+
+    /// Whether this rule puts a price on `product`.
+    ///
+    /// The provided version asks `products`, so every rule answers
+    /// correctly without writing anything. As with a Java 8 `default`
+    /// method, a rule may still override it, as the rules of this module
+    /// do to answer without building a `Vec`. Two differences from Java:
+    /// a provided method can call any other method of the trait, including
+    /// the supertrait's, just as a `default` method can, but there is no
+    /// `PricingRule.super.applies_to(...)` to call the provided version
+    /// from an override; and when two traits provide methods of the same
+    /// name, Rust makes the caller choose, where Java makes the implementor.
+    fn applies_to(&self, product: char) -> bool {
+        self.products().contains(&product)
+    }
+
+    /// Anything suspicious about this rule taken on its own, such as a
+    /// bundle costing more than its items bought one at a time.
+    fn lint(&self) -> Vec<LintWarning> {
+        Vec::new()
+    }
+
+    /// The sizes of the multi-item offers this rule makes on `product`,
+    /// such as `[5]` for "5 for $150.00", so that a catalog can show what
+    /// a bundle comes to. Rules that make none need not override it.
+    fn lot_sizes(&self, _product: char) -> Vec<u64> {
+        Vec::new()
+    }
+
+    /// Explains the price: the same amount `price` returns, broken down
+    /// into the offers that make it up.
+    ///
+    /// The provided version only knows the amount, so it lists no offers.
+    /// Rules that can explain themselves should override it.
+    fn plan(&self, character_counts: &Counts,
+            policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        Ok(RuleNode {
+            description: self.describe(),
+            offers: Vec::new(),
+            amount: self.price(character_counts, policy)?
+        })
+    }
+
+    /// What the items this rule accounts for would cost with no offers at
+    /// all, every one of them bought alone. Comparing it with `price`
+    /// shows how much the offers saved.
+    ///
+    /// The provided version assumes the rule makes no offers and returns
+    /// `price` itself.
+    fn list_price(&self, character_counts: &Counts,
+                  policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.price(character_counts, policy)
+    }
+}
+
+/// The count of `product`, or zero.
+fn count_of(character_counts: &Counts, product: char) -> u64 {
+    character_counts.get(&product).copied().unwrap_or(0)
+}
+
+/// Adds up the amounts of `offers` into a `RuleNode` for `rule`, leaving out
+/// offers that were not used at all.
+fn rule_node<M: Money, R: PricingRule<M> + ?Sized>(rule: &R, offers: Vec<OfferNode<M>>,
+                                                   policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+    // `retain` keeps only the elements for which the closure returns true,
+    // like Java's `removeIf` with the condition flipped.
+    let mut offers = offers;
+    offers.retain(|offer| offer.lots > 0);
+    let mut amount = M::zero();
+    for offer in offers.iter() {
+        amount = policy.plus(amount, offer.amount)?;
+    }
+    Ok(RuleNode { description: rule.describe(), offers, amount })
+}
+
+/// Represents a simple flat price. For every item matching the product,
+/// the cost is added to the price.
+///
+/// The fields are private, so the only way to get a `FlatPrice` from
+/// outside this module is through `FlatPrice::new`, which checks them.
+/// Deriving `Clone` and `Debug` does not change that: a clone is a copy of
+/// a rule that was already checked.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FlatPrice<M: Money> {
+    product: char,
+    cost: M
+}
+
+impl<M: Money> FlatPrice<M> {
+
+    /// A flat price of `cost` for every `product`, refused if the cost is
+    /// negative.
+    pub fn new(product: char, cost: M) -> Result<FlatPrice<M>, RuleError> {
+        // `?` works on any `Result`, not just `Overflow` ones.
+        let cost = check_cost(cost)?;
+        Ok(FlatPrice { product, cost })
+    }
+
+    /// The product this rule prices.
+    ///
+    /// Rust has no `get` prefix convention: a getter is simply named after
+    /// the field it reads.
+    pub fn product(&self) -> char {
+        self.product
+    }
+
+    /// The price of a single item.
+    pub fn cost(&self) -> M {
+        self.cost
+    }
+}
+
+impl<M: Money> Describable for FlatPrice<M> {
+    fn describe(&self) -> String {
+        // `format!` is `String.format`, with `{}` placeholders filled in
+        // by each argument's `Display` implementation.
+        format!("{}: {} each", self.product, self.cost)
+    }
+}
+
+/// A rule displays as its description, so `println!("{}", rule)` and
+/// `rule.to_string()` work as they would on a Java object whose
+/// `toString` was overridden. `pad` honors a width and alignment, as in
+/// `{:<30}`, which `write!` alone would ignore.
+impl<M: Money> fmt::Display for FlatPrice<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.describe())
+    }
+}
+
+impl<M: Money> PricingRule<M> for FlatPrice<M> {
+    fn price(&self, character_counts:&Counts,
+             policy: OverflowPolicy) -> Result<M, Overflow> {
+        // The `get` method of a map returns an Option<T>, which is
+        // an enum with two possibilities, either None or Some(x),
+        // where x is a reference to a value of type T.
+        //
+        // In this case, T is the count for that character.  
+        match character_counts.get(&self.product) {
+            Some(&count) => policy.times(self.cost, count),
+            // No key was found that matched the product character code,
+            // so there's no cost.
+            None => Ok(M::zero())
+        }
+        // `match` produces the value of the selected case's expression.
+        // We could store that value in a `let` variable, or, if the match
+        // is the last thing in the function, it gets returned.
+    }
+
+    fn products(&self) -> Vec<char> {
+        // `vec!` works in `no_std` code too, once imported from `alloc`.
+        vec![self.product]
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        product == self.product
+    }
+
+    fn plan(&self, character_counts: &Counts,
+            policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        let count = count_of(character_counts, self.product);
+        let single = OfferNode::new(self.product, 1, count, self.cost, policy)?;
+        rule_node(self, vec![single], policy)
+    }
+
+    fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        if self.cost <= M::zero() {
+            warnings.push(LintWarning::NonPositivePrice { product: self.product });
+        }
+        warnings
+    }
+}
+
+/// Represents a price for a product where you can buy it in bundles of 
+/// a set size for one cost, but you have a number of items less than a
+/// bundle, there is a different cost.  Allows for unlimited bundles.
+///
+/// Equivalent to "X cost apiece, or Y cost when you buy N of them"
+#[derive(Clone, PartialEq, Debug)]
+pub struct BundlePrice<M: Money> {
+    product: char,
+    lone_cost: M,
+    bundle_size: u64,
+    bundle_cost: M
+}
+
+impl<M: Money> BundlePrice<M> {
+
+    /// "`lone_cost` apiece, or `bundle_cost` for `bundle_size` of them",
+    /// refused if either cost is negative or the bundle is empty.
+    pub fn new(product: char, lone_cost: M, bundle_size: u64,
+               bundle_cost: M) -> Result<BundlePrice<M>, RuleError> {
+        if bundle_size == 0 {
+            return Err(RuleError::EmptyBundle);
+        }
+        Ok(BundlePrice {
+            product,
+            lone_cost: check_cost(lone_cost)?,
+            bundle_size,
+            bundle_cost: check_cost(bundle_cost)?
+        })
+    }
+
+    /// The product this rule prices.
+    pub fn product(&self) -> char {
+        self.product
+    }
+
+    /// The price of an item bought outside of a full bundle.
+    pub fn lone_cost(&self) -> M {
+        self.lone_cost
+    }
+
+    /// How many items make up a bundle; never zero.
+    pub fn bundle_size(&self) -> u64 {
+        self.bundle_size
+    }
+
+    /// The price of a full bundle.
+    pub fn bundle_cost(&self) -> M {
+        self.bundle_cost
+    }
+}
+
+impl<M: Money> Describable for BundlePrice<M> {
+    fn describe(&self) -> String {
+        format!("{}: {} each or {} for {}",
+                self.product, self.lone_cost, self.bundle_size, self.bundle_cost)
+    }
+}
+
+impl<M: Money> fmt::Display for BundlePrice<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.describe())
+    }
+}
+
+impl<M: Money> PricingRule<M> for BundlePrice<M> {
+    fn price(&self, character_counts:&Counts,
+             policy: OverflowPolicy) -> Result<M, Overflow> {
+        match character_counts.get(&self.product) {
+            // Here we match on an exact value, 0, rather than capturing
+            // the integer into a variable name (as is done in the 2nd case)
+            Some(&0) => Ok(M::zero()),
+            Some(&non_zero_count) => {
+                let bundles = non_zero_count / self.bundle_size;
+                let leftovers = non_zero_count % self.bundle_size;
+                let bundled = policy.times(self.bundle_cost, bundles)?;
+                let loose = policy.times(self.lone_cost, leftovers)?;
+                policy.plus(bundled, loose)
+            },
+            None => Ok(M::zero())
+        }
+    }
+
+    fn products(&self) -> Vec<char> {
+        vec![self.product]
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        product == self.product
+    }
+
+    fn lot_sizes(&self, product: char) -> Vec<u64> {
+        if product == self.product && self.bundle_size > 1 { vec![self.bundle_size] } else { Vec::new() }
+    }
+
+    fn plan(&self, character_counts: &Counts,
+            policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        let count = count_of(character_counts, self.product);
+        let bundles = OfferNode::new(self.product, self.bundle_size, count / self.bundle_size,
+                                     self.bundle_cost, policy)?;
+        let singles = OfferNode::new(self.product, 1, count % self.bundle_size,
+                                     self.lone_cost, policy)?;
+        rule_node(self, vec![bundles, singles], policy)
+    }
+
+    fn list_price(&self, character_counts: &Counts,
+                  policy: OverflowPolicy) -> Result<M, Overflow> {
+        policy.times(self.lone_cost, count_of(character_counts, self.product))
+    }
+
+    fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        if self.lone_cost <= M::zero() || self.bundle_cost <= M::zero() {
+            warnings.push(LintWarning::NonPositivePrice { product: self.product });
+        }
+        // A bundle too large to price without overflowing is certainly
+        // more expensive than the singles would be, so saturate.
+        if self.bundle_cost > self.lone_cost.saturating_times(self.bundle_size) {
+            warnings.push(LintWarning::BundleCostsMore { product: self.product });
+        }
+        warnings
+    }
+}
+
+/// Several offers on one product, of which the customer always gets the
+/// cheapest combination.
+///
+/// With "$50 each", "3 for $130" and "10 for $400", thirteen items cost
+/// $530 (ten plus three), while twelve cost $490 (ten plus two singles),
+/// and six cost $260 (two threes). Registering the offers as separate
+/// `BundlePrice` rules would instead charge for every item once per rule.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BestOfferPrice<M: Money> {
+    product: char,
+    /// Every offer as a `(size, cost)` tuple, starting with `(1, lone_cost)`
+    /// so that any number of items can be priced.
+    offers: Vec<(u64, M)>,
+    /// The table `best_lots` works out, remembered between calls. `None`
+    /// means the table was too large to keep, or overflowed.
+    table: Memo<Option<OfferTable<M>>>
+}
+
+/// The largest table of cheapest prices a `BestOfferPrice` remembers; any
+/// larger is worked out afresh, and only as far as each count needs.
+const MEMO_LIMIT: u64 = 4096;
+
+/// The dynamic programming table behind `BestOfferPrice::best_lots`.
+///
+/// `cheapest[n]` is the lowest price for `n` items, and `last[n]` the index
+/// of the offer bought last to reach it. Neither depends on the cart, so
+/// one table serves every count, every checkout, and `plan` as well as
+/// `price`.
+#[derive(Debug)]
+struct OfferTable<M> {
+    cheapest: Vec<M>,
+    last: Vec<usize>
+}
+
+impl<M: Money> BestOfferPrice<M> {
+
+    /// A product costing `lone_cost` apiece, with no offers yet.
+    pub fn new(product: char, lone_cost: M) -> Result<BestOfferPrice<M>, RuleError> {
+        Ok(BestOfferPrice {
+            product,
+            offers: vec![(1, check_cost(lone_cost)?)],
+            table: Memo::new()
+        })
+    }
+
+    /// Adds an offer of `size` items for `cost`, returning the updated
+    /// rule, so offers chain as `BestOfferPrice::new(..)?.with_offer(..)?`.
+    pub fn with_offer(mut self, size: u64, cost: M) -> Result<BestOfferPrice<M>, RuleError> {
+        if size == 0 {
+            return Err(RuleError::EmptyBundle);
+        }
+        self.offers.push((size, check_cost(cost)?));
+        // A table remembered for the old offers would now be wrong.
+        self.table = Memo::new();
+        Ok(self)
+    }
+
+    /// The product this rule prices.
+    pub fn product(&self) -> char {
+        self.product
+    }
+
+    /// Every offer as a `(size, cost)` tuple, the single item first.
+    ///
+    /// Returning a slice, `&[...]`, lends out a read-only view of the
+    /// offers, like `Collections.unmodifiableList` but without a wrapper
+    /// object.
+    pub fn offers(&self) -> &[(u64, M)] {
+        &self.offers
+    }
+
+    /// The lowest price at which exactly `count` items can be bought.
+    ///
+    /// This is the "coin change" problem, solved by dynamic programming:
+    /// `cheapest[n]` is built from the cheapest ways to buy fewer items.
+    /// Running that all the way up to `count` would take time and memory in
+    /// proportion to `count`, so it stops early, relying on this fact:
+    ///
+    /// Let `best` be the offer with the lowest cost per item, of size `s`.
+    /// Among any `s` other offers taken together, some of them always add up
+    /// to a multiple of `s` items, and swapping those for `best` bundles
+    /// never costs more. So some cheapest combination uses fewer than `s`
+    /// other offers, covering at most `(s - 1) * largest offer size` items,
+    /// and `best` bundles cover the rest.
+    pub fn best_price(&self, count: u64, policy: OverflowPolicy) -> Result<M, Overflow> {
+        // `.0` is the first element of the returned tuple.
+        Ok(self.best_lots(count, policy)?.0)
+    }
+
+    /// The lowest price for exactly `count` items, along with how many lots
+    /// of each offer make it up, in the same order as `offers`.
+    ///
+    /// Remembering which offer produced each entry of the table lets the
+    /// winning combination be read back out of it, one offer at a time.
+    pub fn best_lots(&self, count: u64,
+                     policy: OverflowPolicy) -> Result<(M, Vec<u64>), Overflow> {
+        let (best, best_size, best_cost) = self.best_offer();
+        let bound = self.table_bound().min(count) as usize;
+
+        // The remembered table is used where there is one; otherwise a
+        // table just large enough for `count` is built for this call alone.
+        // Both hold the same prices as far as `bound`, as each entry only
+        // depends on those before it.
+        self.table.with(|| self.remembered_table(), |remembered| {
+            let built;
+            let table = match remembered {
+                Some(table) => table,
+                None => {
+                    built = self.build_table(bound, policy)?;
+                    &built
+                }
+            };
+
+            // Try every split between `best` bundles and the table that
+            // leaves a whole number of bundles.
+            let mut lowest: Option<(M, u64)> = None;
+            let mut rest = count % best_size;
+            while rest as usize <= bound {
+                let bundles = policy.times(best_cost, (count - rest) / best_size)?;
+                let candidate = policy.plus(table.cheapest[rest as usize], bundles)?;
+                if lowest.is_none_or(|(low, _)| candidate < low) {
+                    lowest = Some((candidate, rest));
+                }
+                rest += best_size;
+            }
+            let (price, rest) = lowest.expect("the remainder modulo the best size is always in the table");
+
+            // Walk back through the table from `rest`, counting the offers.
+            let mut lots = vec![0; self.offers.len()];
+            lots[best] = (count - rest) / best_size;
+            let mut n = rest as usize;
+            while n > 0 {
+                let index = table.last[n];
+                lots[index] += 1;
+                n -= self.offers[index].0 as usize;
+            }
+            Ok((price, lots))
+        })
+    }
+
+    /// The index, size and cost of the offer with the lowest cost per item.
+    fn best_offer(&self) -> (usize, u64, M) {
+        // `enumerate` pairs each offer with its index, and `min_by` picks
+        // the lowest cost per item, comparing `a / b` with `c / d` as
+        // `a * d` with `c * b` to avoid dividing money.
+        let (best, &(best_size, best_cost)) = self.offers.iter().enumerate()
+            .min_by(|&(_, &(size_a, cost_a)), &(_, &(size_b, cost_b))| {
+                cost_a.saturating_times(size_b)
+                    .partial_cmp(&cost_b.saturating_times(size_a))
+                    .unwrap_or(core::cmp::Ordering::Equal)
+            })
+            .expect("there is always the single-item offer");
+        (best, best_size, best_cost)
+    }
+
+    /// How many items the table must reach to price any count at all.
+    fn table_bound(&self) -> u64 {
+        let (_, best_size, _) = self.best_offer();
+        let largest = self.offers.iter().map(|&(size, _)| size).max().unwrap_or(1);
+        (best_size - 1).saturating_mul(largest)
+    }
+
+    /// The whole table, if it is small enough to keep and fits the money
+    /// backend. Without an overflow, every policy builds the same table, so
+    /// building it `Checked` makes it good for all of them.
+    fn remembered_table(&self) -> Option<OfferTable<M>> {
+        let bound = self.table_bound();
+        if bound > MEMO_LIMIT {
+            return None;
+        }
+        self.build_table(bound as usize, OverflowPolicy::Checked).ok()
+    }
+
+    /// The table of cheapest prices for up to `bound` items.
+    fn build_table(&self, bound: usize, policy: OverflowPolicy) -> Result<OfferTable<M>, Overflow> {
+        let mut cheapest: Vec<M> = Vec::with_capacity(bound + 1);
+        let mut last: Vec<usize> = Vec::with_capacity(bound + 1);
+        cheapest.push(M::zero());
+        last.push(0);
+        for n in 1..=bound {
+            let mut lowest: Option<(M, usize)> = None;
+            for (index, &(size, cost)) in self.offers.iter().enumerate() {
+                if let Some(before) = n.checked_sub(size as usize) {
+                    let candidate = policy.plus(cheapest[before], cost)?;
+                    // `is_none_or` is true for `None`, and otherwise asks
+                    // the closure about the value inside the `Some`.
+                    if lowest.is_none_or(|(low, _)| candidate < low) {
+                        lowest = Some((candidate, index));
+                    }
+                }
+            }
+            let (price, index) = lowest.expect("the single-item offer always fits");
+            cheapest.push(price);
+            last.push(index);
+        }
+        Ok(OfferTable { cheapest, last })
+    }
+}
+
+impl<M: Money> Describable for BestOfferPrice<M> {
+    fn describe(&self) -> String {
+        let (_, lone_cost) = self.offers[0];
+        let mut description = format!("{}: {} each", self.product, lone_cost);
+        for &(size, cost) in &self.offers[1..] {
+            description.push_str(&format!(", or {} for {}", size, cost));
+        }
+        description
+    }
+}
+
+impl<M: Money> fmt::Display for BestOfferPrice<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.describe())
+    }
+}
+
+impl<M: Money> PricingRule<M> for BestOfferPrice<M> {
+    fn price(&self, character_counts:&Counts,
+             policy: OverflowPolicy) -> Result<M, Overflow> {
+        match character_counts.get(&self.product) {
+            Some(&count) => self.best_price(count, policy),
+            None => Ok(M::zero())
+        }
+    }
+
+    fn plan(&self, character_counts: &Counts,
+            policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        let count = count_of(character_counts, self.product);
+        let (_, lots) = self.best_lots(count, policy)?;
+        // `zip` walks two iterators in step, like a pair of Java iterators
+        // advanced together.
+        let mut offers = Vec::new();
+        for (&(size, cost), &lots) in self.offers.iter().zip(lots.iter()) {
+            offers.push(OfferNode::new(self.product, size, lots, cost, policy)?);
+        }
+        rule_node(self, offers, policy)
+    }
+
+    fn list_price(&self, character_counts: &Counts,
+                  policy: OverflowPolicy) -> Result<M, Overflow> {
+        let (_, lone_cost) = self.offers[0];
+        policy.times(lone_cost, count_of(character_counts, self.product))
+    }
+
+    fn products(&self) -> Vec<char> {
+        vec![self.product]
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        product == self.product
+    }
+
+    fn lot_sizes(&self, product: char) -> Vec<u64> {
+        if product != self.product {
+            return Vec::new();
+        }
+        self.offers.iter().map(|&(size, _)| size).filter(|&size| size > 1).collect()
+    }
+
+    fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        let (_, lone_cost) = self.offers[0];
+        if self.offers.iter().any(|&(_, cost)| cost <= M::zero()) {
+            warnings.push(LintWarning::NonPositivePrice { product: self.product });
+        }
+        // Such an offer is harmless here, as it is never chosen, but it is
+        // still likely to be a typo.
+        if self.offers.iter().any(|&(size, cost)| cost > lone_cost.saturating_times(size)) {
+            warnings.push(LintWarning::BundleCostsMore { product: self.product });
+        }
+        warnings
+    }
+}
+
+/// Prices one product at another's price: a substitute for a product out of
+/// stock, sold at the price of what the shopper came for.
+///
+/// `rule` is the rule of the `original` product, and every `product` in a
+/// cart is priced as an `original` would be under it, offers and all. The
+/// substitutes are priced on their own, though: a substitute and an
+/// original in one cart do not make up a bundle together.
+///
+/// The rule is held by value, as `R`, so a `SubstitutePrice<FlatPrice<M>>`
+/// needs no allocation; a `Box<dyn PricingRule<M>>` is an `R` too.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SubstitutePrice<R> {
+    product: char,
+    original: char,
+    rule: R
+}
+
+impl<R> SubstitutePrice<R> {
+
+    /// `product`, priced as `original` is by `rule`, refused if `rule` does
+    /// not price `original`.
+    pub fn new<M: Money>(product: char, original: char, rule: R) -> Result<SubstitutePrice<R>, RuleError>
+        where R: PricingRule<M>
+    {
+        if !rule.applies_to(original) {
+            return Err(RuleError::UnpricedOriginal);
+        }
+        Ok(SubstitutePrice { product, original, rule })
+    }
+
+    /// The product this rule prices.
+    pub fn product(&self) -> char {
+        self.product
+    }
+
+    /// The product whose price it is sold at.
+    pub fn original(&self) -> char {
+        self.original
+    }
+
+    /// `character_counts` as the rule of the original sees them: the
+    /// substitutes, counted as originals, and nothing else.
+    fn as_original(&self, character_counts: &Counts) -> Counts {
+        character_counts.get(&self.product).map(|&count| (self.original, count)).into_iter().collect()
+    }
+}
+
+impl<R: Describable> Describable for SubstitutePrice<R> {
+    fn describe(&self) -> String {
+        format!("{}: priced as {} ({})", self.product, self.original, self.rule.describe())
+    }
+}
+
+impl<R: Describable> fmt::Display for SubstitutePrice<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.describe())
+    }
+}
+
+impl<M: Money, R: PricingRule<M>> PricingRule<M> for SubstitutePrice<R> {
+    fn price(&self, character_counts: &Counts,
+             policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.rule.price(&self.as_original(character_counts), policy)
+    }
+
+    /// The original's plan, with the substitutes named in its offers.
+    fn plan(&self, character_counts: &Counts,
+            policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        let mut node = self.rule.plan(&self.as_original(character_counts), policy)?;
+        for offer in node.offers.iter_mut().filter(|offer| offer.product == self.original) {
+            offer.product = self.product;
+        }
+        node.description = self.describe();
+        Ok(node)
+    }
+
+    fn list_price(&self, character_counts: &Counts,
+                  policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.rule.list_price(&self.as_original(character_counts), policy)
+    }
+
+    fn products(&self) -> Vec<char> {
+        vec![self.product]
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        product == self.product
+    }
+
+    fn lot_sizes(&self, product: char) -> Vec<u64> {
+        if product != self.product {
+            return Vec::new();
+        }
+        self.rule.lot_sizes(self.original)
+    }
+}
+
+/// Any rule displays as its description, even one known only as a trait
+/// object, so that the boxed rules of a Supermarket can be printed with
+/// `{}`. The standard library's `impl Display for Box<T>` forwards to this.
+///
+/// A trait cannot require `Display` of its implementors without forcing
+/// every rule to write one, and `Describable` is already that promise, so
+/// the impl is written once, for the trait object, instead.
+impl<M: Money> fmt::Display for dyn PricingRule<M> + Send + Sync + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.describe())
+    }
+}
+
+/// A `Box` holding something describable is describable too, and a boxed
+/// rule needs to be, to be a rule itself.
+impl<R: Describable + ?Sized> Describable for Box<R> {
+    fn describe(&self) -> String {
+        (**self).describe()
+    }
+}
+
+/// A `Box` holding a rule is itself a rule, forwarding to the rule inside.
+///
+/// In Java every object is reached through a reference, so a `List` of rules
+/// and the rules themselves are interchangeable. In Rust a `Box<R>` is a
+/// distinct type from `R`, and this *blanket impl* (one impl covering every
+/// `R`) is how we tell the compiler they behave the same.
+///
+/// `?Sized` relaxes the default assumption that `R` has a size known at
+/// compile time, which a trait object such as `dyn PricingRule<M>` lacks.
+impl<M: Money, R: PricingRule<M> + ?Sized> PricingRule<M> for Box<R> {
+    fn price(&self, character_counts:&Counts,
+             policy: OverflowPolicy) -> Result<M, Overflow> {
+        // `**self` looks through the `&` and then through the `Box`.
+        (**self).price(character_counts, policy)
+    }
+
+    fn products(&self) -> Vec<char> {
+        (**self).products()
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        (**self).applies_to(product)
+    }
+
+    fn lint(&self) -> Vec<LintWarning> {
+        (**self).lint()
+    }
+
+    fn lot_sizes(&self, product: char) -> Vec<u64> {
+        (**self).lot_sizes(product)
+    }
+
+    fn plan(&self, character_counts: &Counts,
+            policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        (**self).plan(character_counts, policy)
+    }
+
+    fn list_price(&self, character_counts: &Counts,
+                  policy: OverflowPolicy) -> Result<M, Overflow> {
+        (**self).list_price(character_counts, policy)
+    }
+}
+
+/// Adds up the prices every rule assigns to the counted items, following
+/// `policy` for all of the arithmetic.
+///
+/// `I: IntoIterator<Item = &'r R>` accepts anything that can be iterated
+/// over to yield references to rules: a `Vec`, a slice, an array, or an
+/// iterator proper. It is the equivalent of a Java parameter typed as
+/// `Iterable<? extends PricingRule>`.
+pub fn total<'r, M, R, I>(rules: I, counts: &Counts, policy: OverflowPolicy) -> Result<M, Overflow>
+    where M: Money,
+          R: PricingRule<M> + ?Sized + 'r,
+          I: IntoIterator<Item = &'r R>
+{
+    // Here we see a hint at the functional-style terseness possible
+    // in Rust. The next expression iterates through the price rules,
+    // runs a fresh function (defined inline) on each of the rules,
+    // and adds up the individual results, like Java's
+    // `stream.mapToLong(..).sum()`.
+    //
+    // The inline (a.k.a "anonymous") function definition syntax used
+    // is simply `|parameter_name| expression`.
+    //
+    // `sum` can add up anything implementing `Sum`. Each price is
+    // wrapped in a `Charge` saying which policy to add it under, and
+    // charges add up into a `Result`, stopping at the first `Overflow`;
+    // see `money::Charge`. The type to sum into comes from this
+    // function's return type.
+    rules.into_iter().map(|p| policy.charge(p.price(counts, policy))).sum()
+}
@@ -0,0 +1,212 @@
+//! Derive macros writing the `PricingRule` impl for simple rules, so that
+//! a struct holding a product and its prices needs no impl of its own.
+//!
+//! This is Rust's answer to Java annotation processing, as used by Lombok or
+//! AutoValue. A derive macro is a function the compiler calls with the
+//! tokens of the struct it is attached to, and the tokens it returns, here
+//! an `impl` block, are compiled alongside it. Unlike most annotation
+//! processors, the macro never sees anything but tokens: it cannot ask what
+//! a type is, so it relies on the field attributes to know which is which.
+//!
+//! Use the macros through `rust_for_java_devs`, with its `derive` feature,
+//! rather than from this crate directly. The code they generate names items
+//! by their paths in `rust_for_java_devs`, which is why they cannot be used
+//! inside that crate itself.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, Type};
+
+/// Implements `PricingRule` for a struct with a flat price, "`cost` for
+/// every `product`", like `core::rules::FlatPrice`.
+///
+/// Mark the fields with `#[rule(product)]`, a `char`, and `#[rule(cost)]`,
+/// of the money backend to price in.
+#[proc_macro_derive(FlatRule, attributes(rule))]
+pub fn derive_flat_rule(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    // `unwrap_or_else` turns a problem into a compile error pointing at the
+    // offending code, rather than a panic inside the compiler.
+    flat_rule(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Implements `PricingRule` for a struct with a bundle price, "`lone_cost`
+/// apiece, or `bundle_cost` for `bundle_size`", like
+/// `core::rules::BundlePrice`.
+///
+/// Mark the fields with `#[rule(product)]`, a `char`, `#[rule(bundle_size)]`,
+/// a `u64`, and `#[rule(lone_cost)]` and `#[rule(bundle_cost)]`, both of the
+/// money backend to price in. An empty bundle prices every item singly.
+#[proc_macro_derive(BundleRule, attributes(rule))]
+pub fn derive_bundle_rule(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    bundle_rule(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// The fields of a struct marked with `#[rule(...)]`, by role.
+struct RuleFields<'a> {
+    input: &'a DeriveInput,
+    marked: Vec<(Ident, &'a syn::Field)>
+}
+
+impl<'a> RuleFields<'a> {
+
+    /// Collects the marked fields of `input`, refusing anything but a
+    /// struct with named fields.
+    fn of(input: &'a DeriveInput) -> syn::Result<RuleFields<'a>> {
+        let fields = match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => &fields.named,
+                _ => return Err(Error::new_spanned(&input.ident, "rules need named fields"))
+            },
+            _ => return Err(Error::new_spanned(&input.ident, "only structs can derive rules"))
+        };
+        let mut marked = Vec::new();
+        for field in fields {
+            for attribute in field.attrs.iter().filter(|a| a.path().is_ident("rule")) {
+                marked.push((attribute.parse_args::<Ident>()?, field));
+            }
+        }
+        Ok(RuleFields { input, marked })
+    }
+
+    /// The field marked `#[rule(role)]`, as its name and type.
+    fn get(&self, role: &str) -> syn::Result<(&'a Ident, &'a Type)> {
+        let mut found = self.marked.iter().filter(|(marked, _)| marked == role);
+        match (found.next(), found.next()) {
+            (Some((_, field)), None) => {
+                Ok((field.ident.as_ref().expect("named fields have names"), &field.ty))
+            },
+            (None, _) => Err(Error::new(Span::call_site(),
+                                        format!("one field must be marked `#[rule({})]`", role))),
+            (Some(_), Some((attribute, _))) => Err(Error::new_spanned(
+                attribute, format!("only one field may be marked `#[rule({})]`", role)))
+        }
+    }
+
+    /// Refuses roles this derive does not know, which are most likely typos.
+    fn only(&self, roles: &[&str]) -> syn::Result<()> {
+        match self.marked.iter().find(|(marked, _)| !roles.iter().any(|role| marked == role)) {
+            Some((marked, _)) => Err(Error::new_spanned(
+                marked, format!("expected one of `{}`", roles.join("`, `")))),
+            None => Ok(())
+        }
+    }
+}
+
+fn flat_rule(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = RuleFields::of(input)?;
+    fields.only(&["product", "cost"])?;
+    let (product, _) = fields.get("product")?;
+    let (cost, money) = fields.get("cost")?;
+
+    let name = &input.ident;
+    // Copies the struct's own generics, if any, onto the impl, so that a
+    // `struct Apples<M>` gets an `impl<M> PricingRule<M> for Apples<M>`.
+    let (impl_generics, type_generics, where_clause) = fields.input.generics.split_for_impl();
+    // `quote!` builds code from a template, and `#name` splices in a value,
+    // much like a JavaPoet `$T`.
+    Ok(quote! {
+        impl #impl_generics ::rust_for_java_devs::core::rules::Describable
+            for #name #type_generics #where_clause {
+            fn describe(&self) -> ::std::string::String {
+                ::std::format!("{}: {} each", self.#product, self.#cost)
+            }
+        }
+
+        impl #impl_generics ::rust_for_java_devs::core::rules::PricingRule<#money>
+            for #name #type_generics #where_clause {
+            fn price(&self, character_counts: &::rust_for_java_devs::core::counting::Counts,
+                     policy: ::rust_for_java_devs::core::money::OverflowPolicy)
+                     -> ::std::result::Result<#money, ::rust_for_java_devs::core::money::Overflow> {
+                let count = character_counts.get(&self.#product).copied().unwrap_or(0);
+                policy.times(self.#cost, count)
+            }
+
+            fn products(&self) -> ::std::vec::Vec<char> {
+                ::std::vec![self.#product]
+            }
+
+            fn lint(&self) -> ::std::vec::Vec<::rust_for_java_devs::core::lint::LintWarning> {
+                use ::rust_for_java_devs::core::money::Money;
+                let mut warnings = ::std::vec::Vec::new();
+                if self.#cost <= <#money as Money>::zero() {
+                    warnings.push(::rust_for_java_devs::core::lint::LintWarning::NonPositivePrice {
+                        product: self.#product
+                    });
+                }
+                warnings
+            }
+        }
+    })
+}
+
+fn bundle_rule(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = RuleFields::of(input)?;
+    fields.only(&["product", "lone_cost", "bundle_size", "bundle_cost"])?;
+    let (product, _) = fields.get("product")?;
+    let (lone_cost, money) = fields.get("lone_cost")?;
+    let (bundle_size, _) = fields.get("bundle_size")?;
+    let (bundle_cost, _) = fields.get("bundle_cost")?;
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = fields.input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::rust_for_java_devs::core::rules::Describable
+            for #name #type_generics #where_clause {
+            fn describe(&self) -> ::std::string::String {
+                ::std::format!("{}: {} each or {} for {}", self.#product, self.#lone_cost,
+                               self.#bundle_size, self.#bundle_cost)
+            }
+        }
+
+        impl #impl_generics ::rust_for_java_devs::core::rules::PricingRule<#money>
+            for #name #type_generics #where_clause {
+            fn price(&self, character_counts: &::rust_for_java_devs::core::counting::Counts,
+                     policy: ::rust_for_java_devs::core::money::OverflowPolicy)
+                     -> ::std::result::Result<#money, ::rust_for_java_devs::core::money::Overflow> {
+                let count = character_counts.get(&self.#product).copied().unwrap_or(0);
+                if self.#bundle_size == 0 {
+                    return policy.times(self.#lone_cost, count);
+                }
+                let bundled = policy.times(self.#bundle_cost, count / self.#bundle_size)?;
+                let loose = policy.times(self.#lone_cost, count % self.#bundle_size)?;
+                policy.plus(bundled, loose)
+            }
+
+            fn products(&self) -> ::std::vec::Vec<char> {
+                ::std::vec![self.#product]
+            }
+
+            fn lot_sizes(&self, product: char) -> ::std::vec::Vec<u64> {
+                if product == self.#product && self.#bundle_size > 1 {
+                    ::std::vec![self.#bundle_size]
+                } else {
+                    ::std::vec::Vec::new()
+                }
+            }
+
+            fn list_price(&self, character_counts: &::rust_for_java_devs::core::counting::Counts,
+                          policy: ::rust_for_java_devs::core::money::OverflowPolicy)
+                          -> ::std::result::Result<#money, ::rust_for_java_devs::core::money::Overflow> {
+                let count = character_counts.get(&self.#product).copied().unwrap_or(0);
+                policy.times(self.#lone_cost, count)
+            }
+
+            fn lint(&self) -> ::std::vec::Vec<::rust_for_java_devs::core::lint::LintWarning> {
+                use ::rust_for_java_devs::core::lint::LintWarning;
+                use ::rust_for_java_devs::core::money::Money;
+                let mut warnings = ::std::vec::Vec::new();
+                let zero = <#money as Money>::zero();
+                if self.#lone_cost <= zero || self.#bundle_cost <= zero {
+                    warnings.push(LintWarning::NonPositivePrice { product: self.#product });
+                }
+                if self.#bundle_cost > self.#lone_cost.saturating_times(self.#bundle_size) {
+                    warnings.push(LintWarning::BundleCostsMore { product: self.#product });
+                }
+                warnings
+            }
+        }
+    })
+}
@@ -0,0 +1,37 @@
+/*
+ * C declarations for the pricing-ffi crate. Kept by hand; see
+ * pricing-ffi/src/lib.rs for the full documentation of each function.
+ */
+#ifndef PRICING_H
+#define PRICING_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opaque handle; only ever used through a pointer. */
+typedef struct pricing_supermarket pricing_supermarket;
+
+#define PRICING_OK 0
+#define PRICING_NULL_ARGUMENT 1
+#define PRICING_INVALID_UTF8 2
+#define PRICING_OVERFLOW 3
+#define PRICING_CART_TOO_LARGE 4
+#define PRICING_PRICE_SOURCE_UNAVAILABLE 5
+#define PRICING_AGE_VERIFICATION_REQUIRED 6
+#define PRICING_QUANTITY_LIMIT_EXCEEDED 7
+#define PRICING_INVALID_ITEM 8
+
+pricing_supermarket *pricing_supermarket_new(void);
+void pricing_supermarket_free(pricing_supermarket *market);
+int pricing_supermarket_checkout(const pricing_supermarket *market,
+                                 const char *items,
+                                 int64_t *total_cents);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PRICING_H */
@@ -0,0 +1,145 @@
+//! A C ABI for the demo Supermarket, callable from C, or from Java through
+//! JNI, JNA or the Foreign Function & Memory API.
+//!
+//! The matching declarations are in `include/pricing.h`. Building this crate
+//! produces a shared library (`libpricing_ffi.so`, `.dylib` or `.dll`) thanks
+//! to the `cdylib` crate type in its `Cargo.toml`.
+//!
+//! Nothing that crosses the boundary is a Rust type with a Rust layout: the
+//! Supermarket is handed out as an opaque pointer, carts come in as
+//! NUL-terminated C strings and totals go out as plain `int64_t` cents.
+//! Failures are reported through status codes rather than panics, because
+//! unwinding a panic into C code is undefined behavior.
+//!
+//! # Why the `unsafe` code is sound
+//!
+//! JNI code is unsafe from end to end, and nothing says so: a C function
+//! reached through `native` may keep a `jobject` past its local frame,
+//! forget `ReleaseStringUTFChars` or write past an array, and the JVM
+//! finds out by crashing, some time later. Java's safety stops at the
+//! `native` keyword.
+//!
+//! Rust draws the line more finely. Everything here is safe Rust except
+//! the few `unsafe` blocks, each of which does something the compiler
+//! cannot check, and each is preceded by a `SAFETY:` comment arguing why
+//! it is fine anyway. (`clippy::undocumented_unsafe_blocks`, turned on
+//! below, refuses a block without one.) The arguments rest on two kinds
+//! of promise:
+//!
+//! * *Pointer validity.* A pointer from C may be null, dangling, misaligned
+//!   or pointing at the wrong type. Null is checked; the rest cannot be,
+//!   so the functions are `unsafe fn`, and their `# Safety` sections are
+//!   the promises a caller makes. The Rust side then relies only on those.
+//! * *Ownership across the boundary.* `Box::into_raw` hands C the only
+//!   pointer to a Supermarket and tells Rust to forget it, so nothing is
+//!   dropped when the function returns. `Box::from_raw` takes it back,
+//!   exactly once, in `pricing_supermarket_free`. In between, C may lend
+//!   it to `pricing_supermarket_checkout`, which only reads through it.
+//!
+//! Marking a function `unsafe fn` only says that *calling* it is unsafe.
+//! `unsafe_op_in_unsafe_fn`, denied below, still requires its body to
+//! wrap each unsafe operation in a block of its own, so that each can be
+//! argued for separately. The tests in `tests/ffi.rs` call the functions
+//! from Rust, as C would, keeping the callers' side of the promises.
+#![deny(unsafe_op_in_unsafe_fn)]
+#![warn(clippy::undocumented_unsafe_blocks)]
+
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::{CheckoutError, Supermarket};
+use std::ffi::{c_char, c_int, CStr};
+
+/// The cart was priced and the total written out.
+pub const PRICING_OK: c_int = 0;
+/// A pointer argument was null.
+pub const PRICING_NULL_ARGUMENT: c_int = 1;
+/// The cart was not valid UTF-8.
+pub const PRICING_INVALID_UTF8: c_int = 2;
+/// The total did not fit in an `int64_t`.
+pub const PRICING_OVERFLOW: c_int = 3;
+/// The cart exceeded the Supermarket's cart limits.
+pub const PRICING_CART_TOO_LARGE: c_int = 4;
+/// An outside source of prices could not be reached.
+pub const PRICING_PRICE_SOURCE_UNAVAILABLE: c_int = 5;
+/// The cart holds age-restricted products and the customer's age was not
+/// verified.
+pub const PRICING_AGE_VERIFICATION_REQUIRED: c_int = 6;
+/// The cart holds more of a product than its quantity limit.
+pub const PRICING_QUANTITY_LIMIT_EXCEEDED: c_int = 7;
+/// The cart holds something that is not a single item code.
+pub const PRICING_INVALID_ITEM: c_int = 8;
+
+/// Allocates a Supermarket stocked with the demo catalog.
+///
+/// `#[no_mangle]` keeps the symbol's name exactly as written so C can find
+/// it, and `extern "C"` uses the platform's C calling convention. The
+/// returned pointer owns the Supermarket and must eventually be passed to
+/// `pricing_supermarket_free`, much like a `close()` in Java that the
+/// garbage collector will not call for you.
+#[no_mangle]
+pub extern "C" fn pricing_supermarket_new() -> *mut Supermarket<Cents> {
+    Box::into_raw(Box::new(Supermarket::demo()))
+}
+
+/// Frees a Supermarket allocated by `pricing_supermarket_new`.
+///
+/// # Safety
+///
+/// `market` must be null or a pointer returned by `pricing_supermarket_new`
+/// that has not already been freed. It must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pricing_supermarket_free(market: *mut Supermarket<Cents>) {
+    if !market.is_null() {
+        // Rebuilding the Box hands ownership back to Rust, which drops it.
+        // SAFETY: the caller promises `market` came from `Box::into_raw` in
+        // `pricing_supermarket_new` and has not been freed, so this is the
+        // one and only `Box` owning it, and the Supermarket is dropped once.
+        drop(unsafe { Box::from_raw(market) });
+    }
+}
+
+/// Prices `items`, writing the total in cents to `*total_cents`.
+///
+/// Returns `PRICING_OK` on success, or one of the other `PRICING_*` codes,
+/// in which case `*total_cents` is left untouched.
+///
+/// # Safety
+///
+/// `market` must be a live pointer from `pricing_supermarket_new`, `items`
+/// must point to a NUL-terminated string, and `total_cents` must point to
+/// writable memory for one `int64_t`. Null pointers are reported rather
+/// than dereferenced.
+#[no_mangle]
+pub unsafe extern "C" fn pricing_supermarket_checkout(market: *const Supermarket<Cents>,
+                                                      items: *const c_char,
+                                                      total_cents: *mut i64) -> c_int {
+    if market.is_null() || items.is_null() || total_cents.is_null() {
+        return PRICING_NULL_ARGUMENT;
+    }
+    // SAFETY: `items` is not null, and the caller promises it points to a
+    // NUL-terminated string that stays unchanged for the duration of the
+    // call. The `CStr` borrows it for no longer than that.
+    let items = match unsafe { CStr::from_ptr(items) }.to_str() {
+        Ok(items) => items,
+        Err(_) => return PRICING_INVALID_UTF8
+    };
+    // SAFETY: `market` is not null, and the caller promises it is a live
+    // Supermarket from `pricing_supermarket_new`. Only a shared reference
+    // is made, and `try_checkout` takes `&self`, so C may price carts on
+    // several threads at once, as a Supermarket is `Sync`.
+    let market = unsafe { &*market };
+    match market.try_checkout(items) {
+        Ok(Cents(cents)) => {
+            // SAFETY: `total_cents` is not null, and the caller promises it
+            // is aligned and writable. An `i64` has no destructor, so
+            // overwriting whatever was there leaks nothing.
+            unsafe { *total_cents = cents };
+            PRICING_OK
+        },
+        Err(CheckoutError::Overflow(_)) => PRICING_OVERFLOW,
+        Err(CheckoutError::CartTooLarge { .. }) => PRICING_CART_TOO_LARGE,
+        Err(CheckoutError::PriceSourceUnavailable { .. }) => PRICING_PRICE_SOURCE_UNAVAILABLE,
+        Err(CheckoutError::AgeVerificationRequired(_)) => PRICING_AGE_VERIFICATION_REQUIRED,
+        Err(CheckoutError::QuantityLimitExceeded { .. }) => PRICING_QUANTITY_LIMIT_EXCEEDED,
+        Err(CheckoutError::InvalidItem(_)) => PRICING_INVALID_ITEM
+    }
+}
@@ -0,0 +1,83 @@
+/// Calls the C functions from Rust, as C would, keeping the promises in
+/// their `# Safety` sections: live pointers, NUL-terminated strings, and
+/// each Supermarket freed exactly once.
+use pricing_ffi::*;
+use std::ffi::{c_char, CString};
+use std::ptr;
+
+/// Prices `items` with a fresh Supermarket, returning the status and the
+/// total, or `-1` if no total was written.
+fn checkout(items: &[u8]) -> (i32, i64) {
+    let items = CString::new(items).unwrap();
+    let market = pricing_supermarket_new();
+    let mut total = -1;
+    // SAFETY: `market` is live until freed below, `items` is NUL-terminated
+    // and outlives the call, and `total` is a local `i64`.
+    let status = unsafe { pricing_supermarket_checkout(market, items.as_ptr(), &mut total) };
+    // SAFETY: `market` came from `pricing_supermarket_new` and is freed once.
+    unsafe { pricing_supermarket_free(market) };
+    (status, total)
+}
+
+#[test]
+fn prices_carts_in_cents() {
+    assert_eq!((PRICING_OK, 24000), checkout(b"ABBACBBAB"));
+    assert_eq!((PRICING_OK, 0), checkout(b""));
+    assert_eq!((PRICING_OK, 10000), checkout(b"AXBC"));
+}
+
+#[test]
+fn refuses_invalid_utf8_without_writing_a_total() {
+    assert_eq!((PRICING_INVALID_UTF8, -1), checkout(b"AB\xffC"));
+}
+
+#[test]
+fn refuses_combining_marks_without_writing_a_total() {
+    // An `e` followed by a combining acute accent: one letter, two `char`s.
+    assert_eq!((PRICING_INVALID_ITEM, -1), checkout("Ae\u{301}".as_bytes()));
+}
+
+#[test]
+fn reports_null_arguments_rather_than_reading_them() {
+    let market = pricing_supermarket_new();
+    let items = CString::new("A").unwrap();
+    let mut total = -1;
+    // SAFETY: every pointer is either null, which must be reported, or valid.
+    unsafe {
+        assert_eq!(PRICING_NULL_ARGUMENT, pricing_supermarket_checkout(ptr::null(), items.as_ptr(), &mut total));
+        assert_eq!(PRICING_NULL_ARGUMENT, pricing_supermarket_checkout(market, ptr::null::<c_char>(), &mut total));
+        assert_eq!(PRICING_NULL_ARGUMENT, pricing_supermarket_checkout(market, items.as_ptr(), ptr::null_mut()));
+        pricing_supermarket_free(market);
+    }
+    assert_eq!(-1, total);
+}
+
+#[test]
+fn freeing_null_does_nothing() {
+    // SAFETY: null is explicitly allowed, like `free(NULL)` in C.
+    unsafe { pricing_supermarket_free(ptr::null_mut()) };
+}
+
+#[test]
+fn one_market_serves_several_threads() {
+    let market = pricing_supermarket_new();
+    // A raw pointer is neither `Send` nor `Sync`, so it crosses to the
+    // threads as an address, as it would between C threads.
+    let address = market as usize;
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(move || {
+                let items = CString::new("ABBACBBAB").unwrap();
+                let mut total = 0;
+                // SAFETY: the market is freed only after every thread is
+                // joined, at the end of the scope, and is only read here.
+                let status = unsafe {
+                    pricing_supermarket_checkout(address as *const _, items.as_ptr(), &mut total)
+                };
+                assert_eq!((PRICING_OK, 24000), (status, total));
+            });
+        }
+    });
+    // SAFETY: every thread has finished with the market.
+    unsafe { pricing_supermarket_free(market) };
+}
@@ -0,0 +1,51 @@
+//! A sample pricing plugin, loadable with `plugins::Plugin::load`.
+//!
+//! Its promotion, every third `D` free at $10.00 each, is an ordinary
+//! `PricingRule`. The only plugin-specific code is the exported factory at
+//! the bottom, which hands a rule to the host through `plugins::export`.
+//!
+//! Build it with `cargo build -p pricing-plugin-sample`, which leaves
+//! `libpricing_plugin_sample.so` (or `.dylib`, or `.dll`) in `target/debug`.
+
+use std::ffi::c_int;
+use rust_for_java_devs::core::counting::Counts;
+use rust_for_java_devs::core::money::{Cents, OverflowPolicy, Overflow};
+use rust_for_java_devs::core::rules::{Describable, PricingRule};
+use rust_for_java_devs::plugins::{self, RawRule, PLUGIN_OK};
+
+/// `cost` for every `product`, except that every third one is free.
+pub struct ThirdFree {
+    product: char,
+    cost: Cents
+}
+
+impl Describable for ThirdFree {
+    fn describe(&self) -> String {
+        format!("{}: {} each, every third free", self.product, self.cost)
+    }
+}
+
+impl PricingRule<Cents> for ThirdFree {
+    fn price(&self, character_counts: &Counts,
+             policy: OverflowPolicy) -> Result<Cents, Overflow> {
+        let count = character_counts.get(&self.product).copied().unwrap_or(0);
+        policy.times(self.cost, count - count / 3)
+    }
+
+    fn products(&self) -> Vec<char> {
+        vec![self.product]
+    }
+}
+
+/// The function every plugin exports. `#[no_mangle]` keeps its name as
+/// written, rather than the mangled name Rust would otherwise give it, so
+/// that the host can find it.
+///
+/// # Safety
+///
+/// `rule` must point to memory for one `RawRule`, which the host provides.
+#[no_mangle]
+pub unsafe extern "C" fn pricing_plugin_create(rule: *mut RawRule) -> c_int {
+    rule.write(plugins::export(ThirdFree { product: 'D', cost: Cents(1000) }));
+    PLUGIN_OK
+}
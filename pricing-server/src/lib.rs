@@ -0,0 +1,132 @@
+//! A small TCP service that prices carts against the demo catalog.
+//!
+//! The protocol is line based: a client writes one cart per line and reads
+//! back one line per cart, holding either the total, such as `$240.00`, or
+//! `error: ` followed by a description. A cart longer than the market's
+//! byte limit ends the connection after its error reply.
+//!
+//! The server is built on `tokio`, an asynchronous runtime. An `async fn`
+//! returns a future, a value describing work that has not happened yet,
+//! and `.await` suspends the current task until that work completes. Where
+//! a Java server might dedicate a thread to every connection, tokio parks
+//! waiting tasks and runs many of them on a small pool of threads, much like
+//! virtual threads do since Java 21.
+//!
+//! Every connection and every cart is wrapped in a `tracing` span carrying
+//! the store's id, so that a tracing subscriber, such as an OpenTelemetry
+//! layer exporting over OTLP, can line the pricing up with the traces of
+//! the front-end that sent the cart. Spans are `tracing`'s take on the
+//! spans of the OpenTelemetry Java API: named, timed units of work with
+//! structured fields, nested inside one another.
+
+use rust_for_java_devs::reasonable_implementation::{CartDimension, CartLimits, CheckoutError,
+                                                     Supermarket};
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::Instrument;
+
+/// Where the binary listens unless told otherwise.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+/// The store id the binary reports in its spans unless the
+/// `PRICING_STORE_ID` environment variable gives another.
+pub const DEFAULT_STORE_ID: &str = "demo";
+
+/// Where the binary serves its metrics, in Prometheus's text format, at
+/// `http://127.0.0.1:9878/metrics`.
+pub const DEFAULT_METRICS_ADDRESS: &str = "127.0.0.1:9878";
+
+/// The cart limits the binary applies: generous for a person at a till,
+/// far too small to exhaust the server's memory.
+pub const DEFAULT_CART_LIMITS: CartLimits = CartLimits { max_bytes: 64 * 1024, max_items: 10_000 };
+
+/// Accepts connections on `listener` forever, serving each one on its own
+/// task, with `store_id` recorded in every span.
+///
+/// The Supermarket is shared through an `Arc` rather than copied, which is
+/// possible because every rule inside it is `Send + Sync`. Its cart limits
+/// also bound how much of any one line is read into memory.
+pub async fn serve(listener: TcpListener, store_id: &str,
+                   market: Arc<Supermarket>) -> io::Result<()> {
+    // An `Arc<str>` shares one copy of the id between every task, where
+    // cloning a `String` would copy its bytes each time.
+    let store_id: Arc<str> = Arc::from(store_id);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let market = Arc::clone(&market);
+        // `%` records a field through its `Display` impl.
+        let span = tracing::info_span!("connection", store_id = %store_id, %peer);
+        let store_id = Arc::clone(&store_id);
+        // `tokio::spawn` is the asynchronous counterpart of starting a
+        // thread. A failing connection only ends its own task.
+        //
+        // `instrument` enters the span every time the task is resumed and
+        // leaves it every time the task is suspended. A Java `ThreadLocal`
+        // holding the current span would go wrong here, as one thread runs
+        // many tasks by turns.
+        tokio::spawn(async move {
+            if let Err(error) = handle_connection(stream, &store_id, &market).await {
+                tracing::debug!(%error, "connection failed");
+            }
+        }.instrument(span));
+    }
+}
+
+/// Answers every cart sent over one connection until the client hangs up.
+async fn handle_connection(stream: TcpStream, store_id: &str,
+                           market: &Supermarket) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    // One byte more than the limit, plus the newline, is enough to tell a
+    // cart that is too long from one that fits exactly.
+    let max_line = market.cart_limits().max_bytes.saturating_add(2) as u64;
+    let mut line = Vec::new();
+    loop {
+        // `take` caps how much this one read may consume, so a client that
+        // never sends a newline cannot make the buffer grow without bound.
+        let read = (&mut reader).take(max_line).read_until(b'\n', &mut line).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        // A line cut short by `take` means the rest of an oversized cart is
+        // still on its way. Rather than read it all just to skip it, reply
+        // and hang up.
+        if !line.ends_with(b"\n") && read as u64 == max_line {
+            let error = CheckoutError::CartTooLarge {
+                dimension: CartDimension::Bytes,
+                limit: market.cart_limits().max_bytes as u64
+            };
+            writer.write_all(format!("error: {}\n", error).as_bytes()).await?;
+            return Ok(());
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
+        }
+        // `from_utf8` takes the bytes by value, and `mem::take` hands them
+        // over while leaving an empty `Vec` behind for the next line.
+        let reply = match String::from_utf8(std::mem::take(&mut line)) {
+            Ok(cart) => {
+                // `Empty` fields are declared now and recorded once known.
+                let span = tracing::info_span!("checkout", store_id = %store_id, cart_bytes = cart.len(),
+                                               total = tracing::field::Empty);
+                // Pricing never waits on anything, so rather than
+                // instrumenting a future, the span is simply entered for
+                // the length of the closure.
+                span.in_scope(|| match market.try_checkout(cart) {
+                    Ok(total) => {
+                        span.record("total", tracing::field::display(total));
+                        format!("{}\n", total)
+                    },
+                    Err(error) => {
+                        tracing::warn!(%error, "checkout refused");
+                        format!("error: {}\n", error)
+                    }
+                })
+            },
+            Err(_) => "error: cart is not valid UTF-8\n".to_string()
+        };
+        writer.write_all(reply.as_bytes()).await?;
+    }
+}
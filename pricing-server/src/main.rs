@@ -0,0 +1,49 @@
+//! Runs the pricing server, on the address given as the first argument or
+//! on `DEFAULT_ADDRESS`, with its metrics on the address given as the second
+//! argument or on `DEFAULT_METRICS_ADDRESS`.
+//!
+//! Spans and events are printed to standard output, filtered by the
+//! `RUST_LOG` environment variable (`info` unless set), and tagged with the
+//! store id in `PRICING_STORE_ID` (`DEFAULT_STORE_ID` unless set).
+
+use metrics_exporter_prometheus::PrometheusBuilder;
+use pricing_server::{serve, DEFAULT_ADDRESS, DEFAULT_CART_LIMITS, DEFAULT_METRICS_ADDRESS,
+                     DEFAULT_STORE_ID};
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// `#[tokio::main]` rewrites this `async fn main` into an ordinary `main`
+/// that starts the runtime and blocks on the body.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let address = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let metrics_address: SocketAddr = std::env::args().nth(2)
+        .unwrap_or_else(|| DEFAULT_METRICS_ADDRESS.to_string())
+        .parse()?;
+    let store_id = std::env::var("PRICING_STORE_ID").unwrap_or_else(|_| DEFAULT_STORE_ID.to_string());
+
+    // The subscriber decides what happens to spans, as a logging backend
+    // does for log records. This one prints them; a deployment feeding an
+    // OpenTelemetry collector would add a `tracing-opentelemetry` layer.
+    // `FmtSpan::CLOSE` prints every span as it ends, with its timings.
+    tracing_subscriber::fmt()
+        .with_span_events(FmtSpan::CLOSE)
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+
+    // The Supermarket records its metrics through the `metrics` facade;
+    // installing the Prometheus exporter makes it the recorder collecting
+    // them, and serves them over HTTP for Prometheus to scrape.
+    PrometheusBuilder::new().with_http_listener(metrics_address).install()?;
+
+    let listener = TcpListener::bind(&address).await?;
+    tracing::info!(store_id, "pricing carts on {}, metrics on http://{}/metrics",
+                   listener.local_addr()?, metrics_address);
+    let market = Supermarket::demo().with_cart_limits(DEFAULT_CART_LIMITS);
+    serve(listener, &store_id, Arc::new(market)).await?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+/// Starts the server on an ephemeral port and talks to it like a client.
+///
+/// `#[tokio::test]` is `#[test]` for async functions: it starts a runtime
+/// for the duration of the test.
+use pricing_server::serve;
+use rust_for_java_devs::reasonable_implementation::{CartLimits, Supermarket};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+#[tokio::test]
+async fn prices_one_cart_per_line() {
+    // Port 0 asks the operating system for any free port.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, "test", Arc::new(Supermarket::demo())));
+
+    let stream = TcpStream::connect(address).await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(b"ABBACBBAB\nAXBC\n\n").await.unwrap();
+    writer.shutdown().await.unwrap();
+
+    let mut lines = BufReader::new(reader).lines();
+    assert_eq!(Some("$240.00".to_string()), lines.next_line().await.unwrap());
+    assert_eq!(Some("$100.00".to_string()), lines.next_line().await.unwrap());
+    assert_eq!(Some("$0.00".to_string()), lines.next_line().await.unwrap());
+    assert_eq!(None, lines.next_line().await.unwrap());
+}
+
+#[tokio::test]
+async fn hangs_up_on_carts_beyond_the_byte_limit() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let limits = CartLimits { max_bytes: 4, max_items: 4 };
+    tokio::spawn(serve(listener, "test", Arc::new(Supermarket::demo().with_cart_limits(limits))));
+
+    let stream = TcpStream::connect(address).await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(b"ABBA\nA B C\nABBACBBAB").await.unwrap();
+
+    let mut lines = BufReader::new(reader).lines();
+    assert_eq!(Some("$140.00".to_string()), lines.next_line().await.unwrap());
+    assert_eq!(Some("error: cart is longer than 4 bytes".to_string()),
+               lines.next_line().await.unwrap());
+    assert_eq!(Some("error: cart is longer than 4 bytes".to_string()),
+               lines.next_line().await.unwrap());
+    assert_eq!(None, lines.next_line().await.unwrap());
+}
@@ -0,0 +1,71 @@
+//! WebAssembly bindings to the demo Supermarket, for JavaScript in a browser.
+//!
+//! Built for the `wasm32-unknown-unknown` target, this crate is a `.wasm`
+//! module; the `wasm-bindgen` command line tool then writes the JavaScript
+//! glue that loads it and converts strings at the boundary:
+//!
+//! ```text
+//! cargo build -p pricing-wasm --release --target wasm32-unknown-unknown
+//! wasm-bindgen --target web --out-dir examples/playground/pkg \
+//!     target/wasm32-unknown-unknown/release/pricing_wasm.wasm
+//! ```
+//!
+//! `examples/playground` is a page built on it.
+//!
+//! Where `pricing-ffi` hands C an opaque pointer and status codes,
+//! `#[wasm_bindgen]` does that bookkeeping itself: a Rust struct becomes a
+//! JavaScript class wrapping a pointer into the module's memory, and an
+//! `Err` becomes a thrown JavaScript `Error`. The one thing JavaScript must
+//! do by hand is call `free()` on a `Register` it is done with, as the
+//! garbage collector knows nothing of the Rust memory behind it, much like
+//! a Java object holding a native handle.
+
+use rust_for_java_devs::catalog;
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+use wasm_bindgen::prelude::*;
+
+/// A Supermarket stocked with the demo catalog, seen from JavaScript as
+/// `new Register()`.
+#[wasm_bindgen]
+pub struct Register {
+    market: Supermarket<Cents>
+}
+
+#[wasm_bindgen]
+impl Register {
+
+    /// `constructor` makes this the class's JavaScript constructor, rather
+    /// than a static method named `new`.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Register {
+        Register { market: Supermarket::demo() }
+    }
+
+    /// The total for `cart`, formatted as on a receipt, such as `"$240.00"`.
+    pub fn total(&self, cart: &str) -> Result<String, JsError> {
+        // `JsError` can be built from any Rust error, and is thrown as a
+        // JavaScript `Error` carrying its `Display` text.
+        Ok(self.market.try_checkout(cart.to_string())?.to_string())
+    }
+
+    /// The itemized receipt for `cart`, as JSON in the form
+    /// `receipt::Receipt` serializes to: amounts are whole cents.
+    pub fn receipt(&self, cart: &str) -> Result<String, JsError> {
+        let receipt = self.market.receipt(cart)?;
+        Ok(serde_json::to_string(&receipt)?)
+    }
+
+    /// The catalog, as `checkout catalog` prints it.
+    pub fn catalog(&self) -> String {
+        catalog::table(&self.market).to_string()
+    }
+}
+
+/// `Default` mirrors the constructor, as Clippy expects of a `new` taking
+/// no arguments.
+impl Default for Register {
+    fn default() -> Register {
+        Register::new()
+    }
+}
@@ -0,0 +1,117 @@
+//! A Supermarket whose rules, and the list holding them, live in an arena
+//! that somebody else owns, available with the `arena` feature.
+//!
+//! Building a `reasonable_implementation::Supermarket` allocates every rule
+//! in a `Box` of its own, plus the `Vec` holding them. That is nothing for
+//! one market, but a chain reloading its catalog may rebuild thousands of
+//! markets at once, and then the allocator is most of the work.
+//!
+//! An arena, or bump allocator, hands out memory from large chunks by
+//! moving a pointer along, and gives all of it back at once when the arena
+//! is dropped or `reset`. Java's garbage collector does something similar
+//! for short-lived objects in its young generation; in Rust we opt in.
+//!
+//! The price is a lifetime, as in `borrowed_rules_implementation`: a market
+//! borrows its arena, so it cannot outlive it, and since an arena may only
+//! be used from one thread at a time, neither can the market be sent to
+//! another thread.
+
+use crate::core::counting::count_items;
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{PricingRule, total};
+#[cfg(feature = "demo-catalog")]
+use crate::core::rules::{FlatPrice, BundlePrice};
+use crate::reasonable_implementation::{CheckoutError, Market};
+
+/// The arena itself, re-exported so that callers need not depend on
+/// `bumpalo` directly.
+pub use bumpalo::Bump;
+
+/// A rule allocated in an arena. Dropping it runs the rule's own `Drop`,
+/// such as freeing the offers of a `BestOfferPrice`, but its memory is only
+/// reclaimed along with the rest of the arena.
+pub type ArenaRule<'a, M> = bumpalo::boxed::Box<'a, dyn PricingRule<M> + 'a>;
+
+/// A Supermarket whose rules live in the arena `'a`.
+pub struct Supermarket<'a, M: Money = Cents> {
+
+    /// The arena new rules are allocated in.
+    arena: &'a Bump,
+
+    /// `bumpalo`'s own `Vec`, which grows within the arena too.
+    price_rules: bumpalo::collections::Vec<'a, ArenaRule<'a, M>>,
+
+    /// What to do when a total grows too large for the money backend.
+    overflow_policy: OverflowPolicy
+}
+
+impl<'a, M: Money> Supermarket<'a, M> {
+
+    /// A Supermarket with an empty catalog, allocating in `arena`.
+    ///
+    /// The `_in` suffix is the convention for constructors taking the
+    /// allocator to use, as in `bumpalo::collections::Vec::new_in`.
+    pub fn new_in(arena: &'a Bump) -> Supermarket<'a, M> {
+        Supermarket {
+            arena,
+            price_rules: bumpalo::collections::Vec::new_in(arena),
+            overflow_policy: Default::default()
+        }
+    }
+
+    /// A Supermarket stocked with the same demo catalog as
+    /// `reasonable_implementation::Supermarket::demo`, allocated in `arena`.
+    #[cfg(feature = "demo-catalog")]
+    pub fn demo_in(arena: &'a Bump) -> Supermarket<'a, M> {
+        let valid = "demo prices are valid";
+        Supermarket::new_in(arena)
+            .with_rule(FlatPrice::new('A', M::from_cents(2000)).expect(valid))
+            .with_rule(BundlePrice::new('B', M::from_cents(5000), 5, M::from_cents(15000))
+                           .expect(valid))
+            .with_rule(FlatPrice::new('C', M::from_cents(3000)).expect(valid))
+    }
+
+    /// Moves `rule` into the arena and adds it to the catalog, returning the
+    /// updated Supermarket.
+    ///
+    /// Unlike `with_rules` elsewhere, this takes each rule by value, with
+    /// its concrete type, so that the rule is never boxed on the heap first.
+    pub fn with_rule<R: PricingRule<M> + 'a>(mut self, rule: R) -> Supermarket<'a, M> {
+        let rule = bumpalo::boxed::Box::new_in(rule, self.arena);
+        // A standard `Box<R>` turns into a `Box<dyn PricingRule<M>>` by
+        // itself, but that conversion is not yet stable for boxes defined
+        // outside the standard library. Going through a raw pointer does the
+        // same thing by hand.
+        //
+        // SAFETY: the pointer comes straight from `into_raw`, and points at
+        // the same rule, allocated in the same arena; only the type through
+        // which it is seen changes, from `R` to `dyn PricingRule<M>`.
+        let rule: ArenaRule<'a, M> = unsafe {
+            let raw: *mut R = bumpalo::boxed::Box::into_raw(rule);
+            bumpalo::boxed::Box::from_raw(raw as *mut (dyn PricingRule<M> + 'a))
+        };
+        self.price_rules.push(rule);
+        self
+    }
+
+    /// Replaces the overflow policy, returning the updated Supermarket.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Supermarket<'a, M> {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Prices a sequence of items, reporting an overflow under the
+    /// `Checked` policy as an error.
+    pub fn try_checkout(&self, items: String) -> Result<M, Overflow> {
+        let counts = count_items::<u64>(&items);
+        // `total` wants references to rules, and `&**rule` looks through
+        // both the reference and the arena's box to reach one.
+        total(self.price_rules.iter().map(|rule| &**rule), &counts, self.overflow_policy)
+    }
+}
+
+impl<'a, M: Money> Market<M> for Supermarket<'a, M> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        Ok(self.try_checkout(items.to_string())?)
+    }
+}
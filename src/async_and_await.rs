@@ -0,0 +1,185 @@
+//! `async` and `.await`, compared with Java's `CompletableFuture`.
+//!
+//! Pricing a cart against a remote `AsyncPriceSource` means waiting for a
+//! lookup per product. Java would fire them all off and combine the
+//! results:
+//!
+//! ```text
+//! List<CompletableFuture<Price>> lookups = products.stream()
+//!     .map(product -> source.priceOf(product))
+//!     .toList();
+//! CompletableFuture.allOf(lookups.toArray(CompletableFuture[]::new))
+//!     .thenApply(ignored -> total(lookups.stream().map(CompletableFuture::join)));
+//! ```
+//!
+//! An `async fn` in Rust returns a `Future`, much as that `priceOf` returns
+//! a `CompletableFuture`, and `.await` is `join` without blocking a thread:
+//! the function is suspended, and resumed once the value is ready. Code
+//! reads top to bottom, with `?` and `match` and loops, rather than as a
+//! chain of `thenApply` and `thenCompose` callbacks.
+//!
+//! The biggest difference is that a Rust future is *lazy*. A
+//! `CompletableFuture` is already running when it is returned, on some
+//! thread pool; a Rust future does nothing at all until it is awaited, or
+//! polled by a runtime such as Tokio. Calling `source.price_of('A')` and
+//! dropping the result never asks the source anything. So the standard
+//! library has futures but no runtime, and this module needs none either:
+//! whoever calls it chooses one.
+//!
+//! Combining futures is done by the `futures` crate:
+//!
+//! | Java                               | Rust                                      |
+//! |------------------------------------|-------------------------------------------|
+//! | `a.thenCompose(x -> b(x))`         | `b(a.await).await`                        |
+//! | `CompletableFuture.allOf(...)`     | `join_all`, or `try_join_all` to stop at the first error |
+//! | `CompletableFuture.anyOf(...)`     | `select`, or `select_ok` for the first success |
+//!
+//! `join_all` runs its futures *concurrently* on the current task, taking
+//! turns whenever one of them waits, rather than in parallel on several
+//! threads; `spawn`ing each on a multi-threaded runtime would do that.
+//! Only with the `async` feature.
+
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+use futures::future::{self, Either};
+use crate::core::checkout::CheckoutError;
+use crate::core::counting::count_items;
+use crate::core::money::{Money, OverflowPolicy};
+use crate::remote::{AsyncPriceSource, SourceError};
+
+/// The price of `count` items, once their unit price is known. A product
+/// the source does not sell costs nothing, as with `RemotePrice`.
+fn line<M: Money>(count: u64, price: Option<M>) -> Result<M, CheckoutError> {
+    Ok(OverflowPolicy::Checked.times(price.unwrap_or_else(M::zero), count)?)
+}
+
+/// A failed lookup, as the error a checkout reports.
+fn unavailable(product: char) -> impl FnOnce(SourceError) -> CheckoutError {
+    move |_| CheckoutError::PriceSourceUnavailable { product, attempts: 1 }
+}
+
+/// Prices `cart`, looking its products up one after another.
+///
+/// Each `.await` waits for one lookup before the next is even started, as
+/// a chain of `thenCompose` calls would. Simple, and slow when the source
+/// is far away.
+pub async fn price_one_by_one<M, A>(source: &A, cart: &str) -> Result<M, CheckoutError>
+    where M: Money, A: AsyncPriceSource<M>
+{
+    let mut total = M::zero();
+    for (product, count) in count_items::<u64>(cart) {
+        let price = source.price_of(product).await.map_err(unavailable(product))?;
+        total = OverflowPolicy::Checked.plus(total, line(count, price)?)?;
+    }
+    Ok(total)
+}
+
+/// Prices `cart`, looking all its products up at once: `allOf`.
+///
+/// The `async move` blocks are futures that have not started yet, and
+/// `try_join_all` polls them all, in turns, until every one has finished
+/// or one has failed. After a failure the rest are dropped, which cancels
+/// them, something a `CompletableFuture` can only ask politely.
+pub async fn price_all_at_once<M, A>(source: &A, cart: &str) -> Result<M, CheckoutError>
+    where M: Money, A: AsyncPriceSource<M>
+{
+    let lookups = count_items::<u64>(cart).into_iter().map(|(product, count)| async move {
+        let price = source.price_of(product).await.map_err(unavailable(product))?;
+        line(count, price)
+    });
+    let lines = future::try_join_all(lookups).await?;
+    lines.into_iter().try_fold(M::zero(), |total, amount| Ok(OverflowPolicy::Checked.plus(total, amount)?))
+}
+
+/// Prices every cart in `carts`, all at once, each with its own result.
+///
+/// `join_all`, unlike `try_join_all`, waits for everything whether it
+/// succeeds or not, like `allOf` followed by inspecting each future.
+pub async fn price_carts<M, A>(source: &A, carts: &[&str]) -> Vec<Result<M, CheckoutError>>
+    where M: Money, A: AsyncPriceSource<M>
+{
+    future::join_all(carts.iter().map(|cart| price_all_at_once(source, cart))).await
+}
+
+/// The price of `product` from whichever source answers first, success or
+/// failure: `anyOf`. The slower lookup is dropped, and so cancelled.
+///
+/// `select` needs futures it can poll from behind a plain `&mut`, which an
+/// `async` block may not allow, as it can hold references into itself.
+/// `pin!` fixes each future in place on the stack, after which it can be
+/// polled safely. Pinning is the price of futures that need no allocation
+/// of their own, where every `CompletableFuture` is an object on the heap.
+pub async fn first_answer<M, A, B>(first: &A, second: &B, product: char)
+    -> Result<Option<M>, SourceError>
+    where M: Money, A: AsyncPriceSource<M>, B: AsyncPriceSource<M>
+{
+    let first = pin!(first.price_of(product));
+    let second = pin!(second.price_of(product));
+    // `Either` says which future won, along with the other, unfinished one.
+    match future::select(first, second).await {
+        Either::Left((price, _)) | Either::Right((price, _)) => price
+    }
+}
+
+/// The price of `product` from whichever of `sources` first answers
+/// successfully, or the last failure if they all fail.
+///
+/// This is what `anyOf` is usually wanted for, but does not do: an
+/// `anyOf` completes with the first future to finish, even exceptionally.
+pub async fn first_success<M, A>(sources: &[A], product: char) -> Result<Option<M>, SourceError>
+    where M: Money, A: AsyncPriceSource<M>
+{
+    // `select_ok` refuses an empty list, with a panic.
+    if sources.is_empty() {
+        return Err(SourceError::Transport("no sources to ask".to_string()));
+    }
+    let lookups = sources.iter().map(|source| Box::pin(source.price_of(product)));
+    future::select_ok(lookups).await.map(|(price, _)| price)
+}
+
+/// A source that answers only after giving way to other work `delay` times,
+/// to stand in for a slow network in examples and tests.
+pub struct Slow<S> {
+    source: S,
+    delay: u32
+}
+
+impl<S> Slow<S> {
+
+    /// `source`, answering after `delay` turns.
+    pub fn new(source: S, delay: u32) -> Slow<S> {
+        Slow { source, delay }
+    }
+}
+
+impl<M: Money, S: AsyncPriceSource<M>> AsyncPriceSource<M> for Slow<S> {
+    async fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        for _ in 0..self.delay {
+            YieldNow(false).await;
+        }
+        self.source.price_of(product).await
+    }
+}
+
+/// A future that is not ready the first time it is polled, and is the
+/// second.
+///
+/// This is all a future is: a `poll` method saying `Ready` with a value, or
+/// `Pending`. A future returning `Pending` must arrange to be polled again,
+/// through the `Waker` in its `Context`; a network future would hand the
+/// waker to the socket, and this one wakes itself at once.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        context.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
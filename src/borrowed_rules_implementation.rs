@@ -0,0 +1,108 @@
+//! This module preserves the original, lifetime-parameterized Supermarket
+//! as a teaching aid.
+//!
+//! It prices carts exactly like `reasonable_implementation`, and shares its
+//! `Market` trait and pricing rules, but holds its rules under a lifetime
+//! `'s` instead of requiring them to be `'static`. Comparing the two shows
+//! what that lifetime buys (rules may borrow data owned by somebody else)
+//! and what it costs (every type that holds a Supermarket must mention `'s`).
+//! `lifetimes_explained` sets it beside the owned market and a third one
+//! borrowing its rules as a slice.
+
+use crate::core::counting::count_items;
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{PricingRule, total};
+#[cfg(feature = "demo-catalog")]
+use crate::core::rules::{FlatPrice, BundlePrice};
+use crate::reasonable_implementation::{CheckoutError, Market};
+
+/// A Supermarket whose rules need only live as long as `'s`.
+///
+/// The `'s` in angle brackets after the struct's name is a lifetime name.
+/// Lifetimes are used to track how long given objects are valid and in-use.
+/// Here, we define a new lifetime, named "s" for terseness.  This lifetime
+/// will be used to help clarify to the compiler that other instances are
+/// expected to stay alive during the same parts of the code as a
+/// Supermarket instance.
+///
+/// Java has no counterpart: the garbage collector keeps anything reachable
+/// alive, so the question of "how long" never has to be asked up front.
+pub struct Supermarket<'s, M: Money = Cents> {
+
+    /// The `+ 's` portion below makes sure the PricingRules held in this
+    /// Vec must be alive while this Supermarket is alive.
+    price_rules: Vec<Box<dyn PricingRule<M> + 's>>,
+
+    /// What to do when a total grows too large for the money backend.
+    overflow_policy: OverflowPolicy
+}
+
+/// Note that we're chaining through a lifetime definition, `'s`, so that
+/// the functions below may talk about the same lifetime as the struct.
+impl<'s, M: Money> Supermarket<'s, M> {
+
+    /// A Supermarket with an empty catalog.
+    pub fn new() -> Supermarket<'s, M> {
+        Supermarket {
+            price_rules: Vec::new(),
+            overflow_policy: Default::default()
+        }
+    }
+
+    /// A Supermarket stocked with the same demo catalog as
+    /// `reasonable_implementation::Supermarket::demo`.
+    #[cfg(feature = "demo-catalog")]
+    pub fn demo() -> Supermarket<'s, M> {
+        let valid = "demo prices are valid";
+        let a = Box::new(FlatPrice::new('A', M::from_cents(2000)).expect(valid))
+            as Box<dyn PricingRule<M>>;
+        let b = Box::new(BundlePrice::new('B', M::from_cents(5000), 5,
+                                          M::from_cents(15000)).expect(valid))
+            as Box<dyn PricingRule<M>>;
+        let c = Box::new(FlatPrice::new('C', M::from_cents(3000)).expect(valid))
+            as Box<dyn PricingRule<M>>;
+
+        // The rules above are `Box<dyn PricingRule<M>>`, which is shorthand
+        // for `Box<dyn PricingRule<M> + 'static>`. The compiler happily
+        // accepts them where only `+ 's` is demanded, because something that
+        // lives forever certainly lives as long as `'s`.
+        Supermarket {
+            price_rules: vec!(a, b, c),
+            overflow_policy: Default::default()
+        }
+    }
+
+    /// Adds `rule` to the catalog, returning the updated Supermarket.
+    ///
+    /// The rule need only live as long as `'s`, so it may borrow, as
+    /// `lifetimes_explained::PriceTable` borrows its prices.
+    pub fn with_rule(mut self, rule: Box<dyn PricingRule<M> + 's>) -> Supermarket<'s, M> {
+        self.price_rules.push(rule);
+        self
+    }
+
+    /// Replaces the overflow policy, returning the updated Supermarket.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Supermarket<'s, M> {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Prices a sequence of items, reporting an overflow under the
+    /// `Checked` policy as an error.
+    pub fn try_checkout(&self, items:String) -> Result<M, Overflow> {
+        let counts = count_items::<u64>(&items);
+        total(&self.price_rules, &counts, self.overflow_policy)
+    }
+}
+
+impl<'s, M: Money> Default for Supermarket<'s, M> {
+    fn default() -> Supermarket<'s, M> {
+        Supermarket::new()
+    }
+}
+
+impl<'s, M: Money> Market<M> for Supermarket<'s, M> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        Ok(self.try_checkout(items.to_string())?)
+    }
+}
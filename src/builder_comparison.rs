@@ -0,0 +1,127 @@
+//! Builders in Java and Rust, from `SupermarketBuilder` to a builder whose
+//! type rules out building a Supermarket with no rules at all.
+//!
+//! Java builders come in two flavours. The hand-written one is a static
+//! nested `Builder` class with a setter per field, each returning `this`,
+//! and a `build()` checking that everything needed was set. Lombok's
+//! `@Builder` writes the same class for you, from the fields of the class it
+//! annotates. Either way, a forgotten setting is found when `build()` runs,
+//! as an `IllegalStateException`, or not at all.
+//!
+//! `reasonable_implementation::SupermarketBuilder` is the Rust counterpart
+//! of the hand-written kind, with two differences worth noticing:
+//!
+//! * Its methods take the builder by value, `self`, and hand it back, where
+//!   Java's return `this`. The chain reads the same, but once `build` has
+//!   consumed the builder, the compiler stops it being used again, where a
+//!   Java builder may go on to build a second object sharing the first's
+//!   mutable list of rules.
+//! * The standard library has nothing like Lombok. Derive macros can play
+//!   its part, as `#[derive(FlatRule)]` does for rules in this crate, and
+//!   crates such as `derive_builder` and `typed-builder` write builders.
+//!
+//! Rust's type system also allows a kind of builder Java's cannot express:
+//! a *typestate* builder, whose type records what has been set so far, so
+//! that a method may only be called in the right state. `TypestateBuilder`
+//! offers `build` only once at least one rule has been added:
+//!
+//! ```
+//! use rust_for_java_devs::builder_comparison::TypestateBuilder;
+//! use rust_for_java_devs::money::Cents;
+//! use rust_for_java_devs::reasonable_implementation::{FlatPrice, Market};
+//!
+//! let market = TypestateBuilder::<_, Cents>::new()
+//!     .rule(FlatPrice::new('A', Cents(2000)).unwrap())
+//!     .build();
+//! assert_eq!(Ok(Cents(4000)), market.checkout("AA"));
+//! ```
+//!
+//! Leaving the rule out is a compile error, "no method named `build` found
+//! for struct `TypestateBuilder<NoRules>`":
+//!
+//! ```compile_fail
+//! use rust_for_java_devs::builder_comparison::TypestateBuilder;
+//! use rust_for_java_devs::money::Cents;
+//!
+//! let market = TypestateBuilder::<_, Cents>::new().build();
+//! ```
+//!
+//! A Java builder could imitate this with one builder class per state,
+//! each returning the next, but generics cannot choose which methods a
+//! class has, so every state needs a class of its own. Here there is one
+//! struct, and the states are zero-sized types that exist only while
+//! compiling: the typestate builder costs exactly what the plain one does.
+
+use std::marker::PhantomData;
+use crate::core::checkout::CartLimits;
+use crate::core::money::{Money, Cents, OverflowPolicy};
+use crate::core::rules::PricingRule;
+use crate::reasonable_implementation::{Supermarket, SupermarketBuilder};
+
+/// The state of a `TypestateBuilder` before any rule has been added.
+///
+/// A struct with no fields has exactly one value and takes no memory, so
+/// it can serve as a label in a type, and nothing more.
+pub struct NoRules;
+
+/// The state of a `TypestateBuilder` once at least one rule has been added.
+pub struct HasRules;
+
+/// A `SupermarketBuilder` that will only `build` once it has a rule.
+///
+/// `S`, one of `NoRules` and `HasRules`, says which state the builder is
+/// in. No field holds an `S`, as there is nothing to hold, so the
+/// `PhantomData<S>` marker tells the compiler the type uses it anyway.
+pub struct TypestateBuilder<S, M: Money = Cents> {
+    builder: SupermarketBuilder<M>,
+    state: PhantomData<S>
+}
+
+impl<M: Money> TypestateBuilder<NoRules, M> {
+
+    /// A builder with no rules, which cannot build anything yet.
+    pub fn new() -> TypestateBuilder<NoRules, M> {
+        TypestateBuilder { builder: SupermarketBuilder::new(), state: PhantomData }
+    }
+}
+
+impl<M: Money> Default for TypestateBuilder<NoRules, M> {
+    fn default() -> TypestateBuilder<NoRules, M> {
+        TypestateBuilder::new()
+    }
+}
+
+/// Methods available in every state, as the `S` here is any type at all.
+impl<S, M: Money> TypestateBuilder<S, M> {
+
+    /// Adds `rule` to the catalog.
+    ///
+    /// Whatever the state before, the builder returned is in `HasRules`, a
+    /// different type from the one passed in. That is why the builder has to
+    /// be taken by value: a Java setter returning `this` cannot change the
+    /// type of its object.
+    pub fn rule(self, rule: impl PricingRule<M> + Send + Sync + 'static) -> TypestateBuilder<HasRules, M> {
+        TypestateBuilder { builder: self.builder.rule(rule), state: PhantomData }
+    }
+
+    /// Sets the overflow policy, leaving the state as it was.
+    pub fn overflow_policy(self, policy: OverflowPolicy) -> TypestateBuilder<S, M> {
+        TypestateBuilder { builder: self.builder.overflow_policy(policy), state: PhantomData }
+    }
+
+    /// Sets the cart limits, leaving the state as it was.
+    pub fn cart_limits(self, limits: CartLimits) -> TypestateBuilder<S, M> {
+        TypestateBuilder { builder: self.builder.cart_limits(limits), state: PhantomData }
+    }
+}
+
+/// Methods available only once there is a rule.
+impl<M: Money> TypestateBuilder<HasRules, M> {
+
+    /// The Supermarket with every rule and setting added so far.
+    ///
+    /// There is nothing left to check: the type says there is a rule.
+    pub fn build(self) -> Supermarket<M> {
+        self.builder.build()
+    }
+}
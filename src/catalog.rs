@@ -0,0 +1,305 @@
+//! Comparing two catalogs, to review a price change before it ships.
+//!
+//! A price change is usually deployed as a new catalog: a new set of rules,
+//! perhaps built from a file by `registry::RuleRegistry`. `diff` lists what
+//! the new rules add, remove and change compared to the old ones, with the
+//! price of a single item of each product before and after, so that the
+//! deployment can attach a changelog a person can check at a glance:
+//!
+//! ```
+//! use rust_for_java_devs::catalog;
+//! use rust_for_java_devs::money::Cents;
+//! use rust_for_java_devs::reasonable_implementation::{BundlePrice, FlatPrice, Supermarket};
+//!
+//! let old: Supermarket = Supermarket::demo();
+//! let new: Supermarket = Supermarket::with_rules(vec![
+//!     Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+//!     Box::new(BundlePrice::new('B', Cents(4500), 5, Cents(15000)).unwrap()),
+//!     Box::new(FlatPrice::new('D', Cents(1000)).unwrap()),
+//! ]);
+//! assert_eq!("\
+//! ~ B: $50.00 each or 5 for $150.00
+//!   -> B: $45.00 each or 5 for $150.00
+//!   one B: $50.00 -> $45.00 (-$5.00)
+//! - C: $30.00 each
+//! + D: $10.00 each
+//! ", catalog::diff(&old, &new).to_string());
+//! ```
+//!
+//! `table` lays a catalog out for a person browsing it instead: what an item
+//! of each product costs when bought alone, and in and just past each of its
+//! bundles, which is where mistakes in a bundle's price show.
+//! `Supermarket::unit_price_table` goes further, pricing every quantity up
+//! to a limit with all the rules at once, which shows where an offer starts
+//! to pay.
+
+use std::fmt;
+use crate::core::counting::Counts;
+use crate::core::money::{Money, Cents, OverflowPolicy};
+use crate::core::rules::PricingRule;
+use crate::reasonable_implementation::{PriceQueryError, Supermarket};
+use crate::receipt::{changes, moved, Change};
+
+/// Reading catalogs from TOML files, in `src/catalog/file.rs`. Only
+/// compiled with the `toml` feature.
+#[cfg(feature = "toml")]
+pub mod file;
+
+/// One rule of a catalog, as a reviewer sees it.
+///
+/// `Eq` and `Hash` are derived, like `equals` and `hashCode` generated from
+/// every field. The derived impls only exist for a money type that has them
+/// too, as `Cents` and `Decimal` do: `derive` adds `M: Eq` and `M: Hash`
+/// to its impls by itself.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CatalogEntry<M: Money = Cents> {
+    /// The rule's own `describe` text.
+    pub description: String,
+    /// The products the rule prices, in order.
+    pub products: Vec<char>,
+    /// What a single item of each product costs under this rule. A product
+    /// whose price overflows is left out.
+    pub unit_prices: Vec<(char, M)>
+}
+
+impl<M: Money> CatalogEntry<M> {
+
+    /// The entry for `rule`.
+    pub fn of<R: PricingRule<M> + ?Sized>(rule: &R) -> CatalogEntry<M> {
+        let mut products = rule.products();
+        products.sort_unstable();
+        products.dedup();
+        let unit_prices = products.iter()
+            .filter_map(|&product| {
+                let counts = Counts::from([(product, 1)]);
+                // `ok()` turns the `Result` into an `Option`, which
+                // `filter_map` then drops if it is `None`.
+                rule.price(&counts, OverflowPolicy::Checked).ok().map(|price| (product, price))
+            })
+            .collect();
+        CatalogEntry { description: rule.describe(), products, unit_prices }
+    }
+}
+
+/// Every rule of `market`, in the order they are applied.
+pub fn entries<M: Money>(market: &Supermarket<M>) -> Vec<CatalogEntry<M>> {
+    market.rules().map(CatalogEntry::of).collect()
+}
+
+/// What some items of one product cost together, and so each.
+///
+/// The derived `Ord` compares fields in the order they are declared, so
+/// price points sort by quantity first and total second, like a
+/// `Comparator.comparing(PricePoint::quantity).thenComparing(...)`. That
+/// makes declaration order part of the type's behavior: swapping the two
+/// fields would change how a table sorts.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct PricePoint<M: Money = Cents> {
+    pub quantity: u64,
+    pub total: M
+}
+
+impl<M: Money> PricePoint<M> {
+
+    /// The effective price of one item, in whole currency units. This is an
+    /// approximation, fit for display only, as a price divided by three
+    /// cannot always be written in cents.
+    pub fn each(&self) -> f64 {
+        self.total.to_f64() / self.quantity as f64
+    }
+}
+
+/// One product of a catalog, with the rule that prices it.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CatalogRow<M: Money = Cents> {
+    pub product: char,
+    /// The rule's own `describe` text.
+    pub description: String,
+    /// The price of one item, then of each bundle size and one more, in
+    /// order of quantity. A quantity whose price overflows is left out.
+    pub prices: Vec<PricePoint<M>>
+}
+
+/// A catalog as a table, one row per product and rule.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CatalogTable<M: Money = Cents> {
+    /// In the order the rules are applied.
+    pub rows: Vec<CatalogRow<M>>
+}
+
+/// The quantities worth showing for `product` under `rule`: one, and each of
+/// the rule's bundle sizes along with one more than it.
+fn quantities<M: Money, R: PricingRule<M> + ?Sized>(rule: &R, product: char) -> Vec<u64> {
+    let mut quantities = vec![1];
+    for size in rule.lot_sizes(product) {
+        quantities.push(size);
+        quantities.push(size.saturating_add(1));
+    }
+    quantities.sort_unstable();
+    quantities.dedup();
+    quantities
+}
+
+/// The table of `market`'s catalog. Rules that name no products, such as
+/// `remote::RemotePrice`, have no rows.
+pub fn table<M: Money>(market: &Supermarket<M>) -> CatalogTable<M> {
+    let mut rows = Vec::new();
+    for rule in market.rules() {
+        let entry = CatalogEntry::of(rule);
+        for &product in entry.products.iter() {
+            let prices = quantities(rule, product).into_iter()
+                .filter_map(|quantity| {
+                    let counts = Counts::from([(product, quantity)]);
+                    rule.price(&counts, OverflowPolicy::Checked).ok().map(|total| PricePoint { quantity, total })
+                })
+                .collect();
+            rows.push(CatalogRow { product, description: entry.description.clone(), prices });
+        }
+    }
+    CatalogTable { rows }
+}
+
+/// `amount`, a number of whole currency units, as dollars and cents.
+fn dollars(amount: f64) -> String {
+    let sign = if amount < 0.0 { "-" } else { "" };
+    format!("{}${:.2}", sign, amount.abs())
+}
+
+/// Aligned columns of product, rule and unit prices, such as
+/// `B        B: $50.00 each or 5 for $150.00  1: $50.00 5: $30.00 6: $33.33`.
+impl<M: Money> fmt::Display for CatalogTable<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const RULE: &str = "rule";
+        // `{:width$}` pads to a width given at run time, counted in
+        // `char`s rather than bytes.
+        let width = self.rows.iter()
+            .map(|row| row.description.chars().count())
+            .chain(Some(RULE.len()))
+            .max()
+            .unwrap_or(0);
+        writeln!(f, "{:8} {:width$}  unit price", "product", RULE, width = width)?;
+        for row in self.rows.iter() {
+            write!(f, "{:8} {:width$} ", row.product, row.description, width = width)?;
+            for point in row.prices.iter() {
+                write!(f, " {}: {}", point.quantity, dollars(point.each()))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// What one product costs at every quantity from one item up.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UnitPriceRow<M: Money = Cents> {
+    pub product: char,
+    /// One entry per quantity, in order, so `prices[4]` is always five
+    /// items. A quantity the market will not price, as it overflows or is
+    /// beyond a cart or quantity limit, is the error saying why.
+    pub prices: Vec<Result<PricePoint<M>, PriceQueryError>>
+}
+
+impl<M: Money> UnitPriceRow<M> {
+
+    /// The first quantity at which an item costs less than it does alone:
+    /// where an offer breaks even for the shopper and starts to save. `None`
+    /// if no quantity in the row does.
+    ///
+    /// The comparison is of exact totals, `total < quantity * price of one`,
+    /// not of the rounded `each`, so a saving of a fraction of a cent still
+    /// counts.
+    pub fn first_saving(&self) -> Option<&PricePoint<M>> {
+        // `flatten` passes over the refused quantities: a `&Result` is
+        // iterable, yielding its `Ok` value, if any.
+        let one = self.prices.iter().flatten().find(|point| point.quantity == 1)?.total;
+        self.prices.iter().flatten().find(|point| {
+            one.checked_times(point.quantity).is_some_and(|undiscounted| point.total < undiscounted)
+        })
+    }
+}
+
+/// What one to some number of items of each product cost at a market, with
+/// every rule applied, as made by `Supermarket::unit_price_table`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UnitPriceTable<M: Money = Cents> {
+    /// In order of product.
+    pub rows: Vec<UnitPriceRow<M>>
+}
+
+/// A row per product, with the effective price of an item at each quantity,
+/// such as `B        1: $50.00 2: $50.00 3: $50.00 4: $50.00 5: $30.00`.
+/// A quantity the market would not price shows as `refused`.
+impl<M: Money> fmt::Display for UnitPriceTable<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:8} unit price by quantity", "product")?;
+        for row in self.rows.iter() {
+            write!(f, "{:8}", row.product)?;
+            // `zip` numbers the entries from one, as a refused quantity
+            // carries no `PricePoint` to say which it was.
+            for (quantity, price) in (1..).zip(row.prices.iter()) {
+                match price {
+                    Ok(point) => write!(f, " {}: {}", quantity, dollars(point.each()))?,
+                    Err(_) => write!(f, " {}: refused", quantity)?
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// What changed between two catalogs, rule by rule.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CatalogDiff<M: Money = Cents> {
+    /// Rules are paired up by the products they price, or by description
+    /// for rules that do not say, such as `remote::RemotePrice`.
+    pub rules: Vec<Change<CatalogEntry<M>>>
+}
+
+impl<M: Money> CatalogDiff<M> {
+
+    /// Whether the catalogs were the same.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Compares the catalog of `old` with that of `new`.
+pub fn diff<M: Money>(old: &Supermarket<M>, new: &Supermarket<M>) -> CatalogDiff<M> {
+    let key = |entry: &CatalogEntry<M>| if entry.products.is_empty() {
+        Err(entry.description.clone())
+    } else {
+        Ok(entry.products.clone())
+    };
+    CatalogDiff { rules: changes(&entries(old), &entries(new), key) }
+}
+
+/// A changelog, one rule per paragraph: `+` for an added rule, `-` for a
+/// removed one, and `~` for a changed one, followed by its new description
+/// and the unit prices that moved.
+impl<M: Money> fmt::Display for CatalogDiff<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in self.rules.iter() {
+            match *change {
+                Change::Added(ref entry) => writeln!(f, "+ {}", entry.description)?,
+                Change::Removed(ref entry) => writeln!(f, "- {}", entry.description)?,
+                Change::Changed { ref before, ref after } => {
+                    writeln!(f, "~ {}", before.description)?;
+                    if after.description != before.description {
+                        writeln!(f, "  -> {}", after.description)?;
+                    }
+                    for &(product, new_price) in after.unit_prices.iter() {
+                        let old_price = before.unit_prices.iter()
+                            .find(|&&(old_product, _)| old_product == product)
+                            .map(|&(_, price)| price);
+                        if let Some(old_price) = old_price.filter(|&old_price| old_price != new_price) {
+                            writeln!(f, "  one {}: {}", product, moved(old_price, new_price))?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
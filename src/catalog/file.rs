@@ -0,0 +1,203 @@
+//! Catalogs kept in TOML files, such as `prices.toml`.
+//!
+//! Each rule is a `[[rule]]` table naming its kind and product, with its
+//! numbers, costs in cents, as the remaining keys:
+//!
+//! ```toml
+//! [[rule]]
+//! kind = "flat"
+//! product = "A"
+//! cost = 2000
+//!
+//! [[rule]]
+//! kind = "bundle"
+//! product = "B"
+//! cost = 5000
+//! size = 5
+//! bundle_cost = 15000
+//! ```
+//!
+//! The kinds are those of a `RuleRegistry`, so a kind registered at startup
+//! may be used here like a built-in one. Every problem is reported with the
+//! line it was found on, like a compiler error, so that a CI job checking a
+//! price change can point straight at the mistake.
+//!
+//! Parsing is done by the `toml` crate, through serde: the file is read into
+//! plain Rust types, much as Jackson reads JSON into a bean, and only then
+//! checked. `toml::Spanned` wraps a value with where it was found in the
+//! file, which is how each rule knows its line.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::Deserialize;
+use toml::Spanned;
+use crate::core::money::Money;
+use crate::core::lint::lint_rules;
+use crate::reasonable_implementation::{LintWarning, Supermarket};
+use crate::registry::{RegistryError, RuleRegistry, RuleSpec};
+
+/// One problem with a catalog file.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    /// The line the problem was found on, counting from one.
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A rule read from a file, and where each of its parts was written.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SpecAt {
+    /// The line of the rule's `[[rule]]` header.
+    pub line: usize,
+    pub spec: RuleSpec,
+    /// The line of each key in the rule's table.
+    key_lines: BTreeMap<String, usize>
+}
+
+impl SpecAt {
+
+    /// The line of the key `name`, or of the rule's header if the rule has
+    /// no such key.
+    pub fn line_of(&self, name: &str) -> usize {
+        self.key_lines.get(name).copied().unwrap_or(self.line)
+    }
+}
+
+/// The shape of the whole file. `deny_unknown_fields` turns a misspelt
+/// `[[rules]]` into an error, rather than a silently empty catalog.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CatalogFile {
+    #[serde(default)]
+    rule: Vec<Spanned<BTreeMap<String, Spanned<toml::Value>>>>
+}
+
+/// The line holding the byte at `offset` of `source`.
+fn line_at(source: &str, offset: usize) -> usize {
+    source.as_bytes()[..offset.min(source.len())].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+/// Reads the rules of a catalog file, in the order they are written.
+///
+/// Stops at a syntax error, of which there can be only one, but otherwise
+/// reports every malformed rule at once.
+pub fn parse(source: &str) -> Result<Vec<SpecAt>, Vec<Diagnostic>> {
+    let file: CatalogFile = toml::from_str(source).map_err(|e| {
+        let line = e.span().map_or(1, |span| line_at(source, span.start));
+        vec![Diagnostic { line, message: e.message().trim().to_string() }]
+    })?;
+
+    let mut specs = Vec::new();
+    let mut problems = Vec::new();
+    for table in file.rule {
+        let line = line_at(source, table.span().start);
+        let table = table.into_inner();
+        let key_lines = table.iter()
+            .map(|(key, value)| (key.clone(), line_at(source, value.span().start)))
+            .collect();
+        let mut problem = |at: usize, message: String| problems.push(Diagnostic { line: at, message });
+
+        let kind = match table.get("kind").map(|kind| kind.get_ref()) {
+            Some(toml::Value::String(kind)) => Some(kind.clone()),
+            Some(_) => { problem(line, "`kind` must be a string".to_string()); None },
+            None => { problem(line, "the rule has no `kind`".to_string()); None }
+        };
+        let product = match table.get("product") {
+            Some(product) => {
+                let mut chars = product.get_ref().as_str().unwrap_or("").chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => {
+                        let at = line_at(source, product.span().start);
+                        problem(at, "`product` must be a string of one character".to_string());
+                        None
+                    }
+                }
+            },
+            None => { problem(line, "the rule has no `product`".to_string()); None }
+        };
+        let mut parameters = BTreeMap::new();
+        for (key, value) in table.iter().filter(|(key, _)| *key != "kind" && *key != "product") {
+            match value.get_ref().as_integer() {
+                Some(number) => { parameters.insert(key.clone(), number); },
+                None => problem(line_at(source, value.span().start),
+                                format!("`{}` must be a whole number", key))
+            }
+        }
+        // `if let` with a tuple only matches when both halves were found.
+        if let (Some(kind), Some(product)) = (kind, product) {
+            specs.push(SpecAt { line, spec: RuleSpec { kind, product, parameters }, key_lines });
+        }
+    }
+    if problems.is_empty() { Ok(specs) } else { Err(problems) }
+}
+
+/// Where in `spec` the registry's complaint about it belongs.
+fn registry_problem(spec: &SpecAt, e: RegistryError) -> Diagnostic {
+    let line = match e {
+        RegistryError::UnknownKind(_) => spec.line_of("kind"),
+        RegistryError::InvalidParameter { ref name, .. } => spec.line_of(name),
+        _ => spec.line
+    };
+    Diagnostic { line, message: e.to_string() }
+}
+
+/// Builds a Supermarket from a catalog file, with the kinds `registry`
+/// knows, reporting every rule that cannot be built.
+pub fn load<M: Money>(registry: &RuleRegistry<M>, source: &str) -> Result<Supermarket<M>, Vec<Diagnostic>> {
+    let specs = parse(source)?;
+    let mut rules = Vec::new();
+    let mut problems = Vec::new();
+    for spec in specs.iter() {
+        match registry.build(&spec.spec) {
+            Ok(rule) => rules.push(rule),
+            Err(e) => problems.push(registry_problem(spec, e))
+        }
+    }
+    if problems.is_empty() { Ok(Supermarket::with_rules(rules)) } else { Err(problems) }
+}
+
+/// Everything wrong with a catalog file: syntax errors, rules that cannot
+/// be built, and whatever the catalog linter finds, each at the line of the
+/// rule concerned. An empty `Vec` means the file is fine.
+pub fn validate<M: Money>(registry: &RuleRegistry<M>, source: &str) -> Vec<Diagnostic> {
+    let specs = match parse(source) {
+        Ok(specs) => specs,
+        Err(problems) => return problems
+    };
+    let mut problems = Vec::new();
+    let mut built = Vec::new();
+    for spec in specs.iter() {
+        match registry.build(&spec.spec) {
+            Ok(rule) => {
+                // Each rule is linted on its own, so that its warnings are
+                // reported at its own line.
+                for warning in rule.lint() {
+                    problems.push(Diagnostic { line: spec.line, message: warning.to_string() });
+                }
+                built.push((spec.line, rule));
+            },
+            Err(e) => problems.push(registry_problem(spec, e))
+        }
+    }
+    // Only the whole catalog shows which products are priced twice. Such a
+    // conflict is reported at the second rule pricing the product, as that
+    // is usually the one added by mistake.
+    let rules = built.iter().map(|(_, rule)| &**rule);
+    for warning in lint_rules(rules, &[]) {
+        if let LintWarning::Conflicting { product, .. } = warning {
+            let line = built.iter()
+                .filter(|(_, rule)| rule.applies_to(product))
+                .nth(1)
+                .map_or(1, |&(line, _)| line);
+            problems.push(Diagnostic { line, message: warning.to_string() });
+        }
+    }
+    problems
+}
@@ -0,0 +1,184 @@
+//! `Copy` and `Clone`, compared with Java's `clone()` and defensive copies.
+//!
+//! Java copies objects in whatever way each class chose. `Object.clone()`
+//! is protected, works only if the class implements the `Cloneable` marker
+//! interface, which itself declares no `clone` method, throws a checked
+//! `CloneNotSupportedException` otherwise, and copies fields *shallowly*:
+//! a cloned market would share its `List` of rules with the original, and
+//! adding a rule to one would add it to both. So Java code mostly avoids it,
+//! writing copy constructors instead, and copies mutable arguments and
+//! results "defensively", `new ArrayList<>(rules)`, in case somebody else
+//! changes them later.
+//!
+//! Rust has two traits, and no defensive copying:
+//!
+//! * `Copy` types are duplicated bit for bit on every assignment, like a
+//!   Java `long`, and the original stays usable. Only small types that own
+//!   nothing on the heap may be `Copy`, and it is a promise never to own
+//!   anything in future: `Cents`, `Decimal`, `Sku`, `OverflowPolicy`,
+//!   `Takings` and the `PriceTag` below.
+//! * `Clone` types are duplicated only when `clone` is called. A derived
+//!   `clone` clones every field, so a `Receipt`'s clone has its own `Vec`
+//!   of lines, each with its own `String`s: deep, as far as the fields own
+//!   their data. A type holding an `Rc` or `Arc` shares what it points to
+//!   instead, as `shared_rules_implementation` does on purpose.
+//!
+//! Defensive copies are unneeded because nobody can change a value they
+//! only borrowed. `Supermarket::rules` in `shared_rules_implementation`
+//! hands out `&[SharedRule]`, and the caller can read the rules but not
+//! push to the `Vec`; a `List` returned from a Java getter needs wrapping
+//! in `Collections.unmodifiableList` for that.
+//!
+//! The rules themselves are `Clone` but not `Copy`, although `FlatPrice`
+//! and `BundlePrice` hold only `Copy` fields today: making them `Copy`
+//! would forbid ever giving them a `String` or a `Vec`, as `BestOfferPrice`
+//! has. A `reasonable_implementation::Supermarket` is neither, as a
+//! `Box<dyn PricingRule>` cannot be cloned without knowing the type inside.
+//! `DeepMarket` shows how that is done, and `ShallowMarket` the alternative.
+
+use std::sync::Arc;
+use crate::conversions::Sku;
+use crate::core::counting::count_items;
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{PricingRule, total};
+
+/// The price on a shelf label.
+///
+/// Both fields are `Copy`, so the whole struct can be, and `derive` needs
+/// `Clone` alongside, as every `Copy` type is also `Clone`. Passing a tag to
+/// a function copies its few bytes, and the caller keeps the original.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PriceTag<M: Money = Cents> {
+    pub sku: Sku,
+    pub price: M
+}
+
+/// A pricing rule that can clone itself into a new `Box`.
+///
+/// `Clone` cannot be a supertrait of a trait used as `dyn`, because
+/// `clone` returns `Self`, whose size a `dyn` caller does not know. The
+/// usual way round is a method returning a `Box` instead, implemented once
+/// for every rule that is `Clone`, by the blanket impl below. Java's
+/// `clone()` returns `Object` for much the same reason, and leaves the
+/// cast to the caller.
+pub trait CloneRule<M: Money>: PricingRule<M> + Send + Sync {
+    fn clone_box(&self) -> Box<dyn CloneRule<M>>;
+}
+
+impl<M: Money, R: PricingRule<M> + Clone + Send + Sync + 'static> CloneRule<M> for R {
+    fn clone_box(&self) -> Box<dyn CloneRule<M>> {
+        Box::new(self.clone())
+    }
+}
+
+/// With this, a `Box<dyn CloneRule>` is `Clone`, and so is anything
+/// holding some, such as a `Vec` of them or a `DeepMarket`.
+impl<M: Money> Clone for Box<dyn CloneRule<M>> {
+    fn clone(&self) -> Box<dyn CloneRule<M>> {
+        // `(**self)` is the rule itself; `self.clone_box()` would find this
+        // very method first, through the `Box`, and recurse forever.
+        (**self).clone_box()
+    }
+}
+
+/// A market whose clones copy every rule: a deep copy.
+///
+/// `derive(Clone)` works because each field is `Clone`. A clone can have
+/// its rules changed without the original noticing, as a Java copy
+/// constructor calling `rule.copy()` on every rule would guarantee.
+#[derive(Clone)]
+pub struct DeepMarket<M: Money = Cents> {
+    rules: Vec<Box<dyn CloneRule<M>>>,
+    policy: OverflowPolicy
+}
+
+/// A market whose clones share their rules: a shallow copy.
+///
+/// Cloning copies the `Vec`, so each clone has a list of its own, as
+/// `new ArrayList<>(rules)` would, but the rules in both lists are the same
+/// rules, counted by their `Arc`s. That is safe here, and cheap, because a
+/// rule cannot be changed through an `Arc`; in Java, sharing a mutable
+/// rule this way would be a bug waiting to happen.
+#[derive(Clone)]
+pub struct ShallowMarket<M: Money = Cents> {
+    rules: Vec<Arc<dyn PricingRule<M> + Send + Sync>>,
+    policy: OverflowPolicy
+}
+
+impl<M: Money> DeepMarket<M> {
+
+    /// A market with no rules.
+    pub fn new() -> DeepMarket<M> {
+        DeepMarket { rules: Vec::new(), policy: OverflowPolicy::Checked }
+    }
+
+    /// Adds `rule`, returning the updated market.
+    pub fn with_rule(mut self, rule: impl CloneRule<M> + 'static) -> DeepMarket<M> {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Replaces every rule for `product` with `rule`.
+    pub fn reprice(&mut self, product: char, rule: impl CloneRule<M> + 'static) {
+        self.rules.retain(|existing| !existing.products().contains(&product));
+        self.rules.push(Box::new(rule));
+    }
+
+    /// Whether any rule of this market is the very same rule, at the same
+    /// address, as one of `other`'s. Never true of a clone.
+    pub fn shares_rules_with(&self, other: &DeepMarket<M>) -> bool {
+        self.rules.iter().any(|mine| other.rules.iter().any(|theirs| {
+            std::ptr::addr_eq(&**mine as *const dyn CloneRule<M>, &**theirs as *const dyn CloneRule<M>)
+        }))
+    }
+
+    /// The price of `items`.
+    pub fn try_checkout(&self, items: &str) -> Result<M, Overflow> {
+        total(self.rules.iter().map(|rule| &**rule), &count_items::<u64>(items), self.policy)
+    }
+}
+
+impl<M: Money> Default for DeepMarket<M> {
+    fn default() -> DeepMarket<M> {
+        DeepMarket::new()
+    }
+}
+
+impl<M: Money> ShallowMarket<M> {
+
+    /// A market with no rules.
+    pub fn new() -> ShallowMarket<M> {
+        ShallowMarket { rules: Vec::new(), policy: OverflowPolicy::Checked }
+    }
+
+    /// Adds `rule`, returning the updated market.
+    pub fn with_rule(mut self, rule: impl PricingRule<M> + Send + Sync + 'static) -> ShallowMarket<M> {
+        self.rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Replaces every rule for `product` with `rule`. Only this market's
+    /// list changes; clones sharing the old rules keep them.
+    pub fn reprice(&mut self, product: char, rule: impl PricingRule<M> + Send + Sync + 'static) {
+        self.rules.retain(|existing| !existing.products().contains(&product));
+        self.rules.push(Arc::new(rule));
+    }
+
+    /// Whether any rule of this market is the very same rule as one of
+    /// `other`'s, as is every rule of a clone until it is repriced.
+    /// `Arc::ptr_eq` is Java's `==` on references.
+    pub fn shares_rules_with(&self, other: &ShallowMarket<M>) -> bool {
+        self.rules.iter().any(|mine| other.rules.iter().any(|theirs| Arc::ptr_eq(mine, theirs)))
+    }
+
+    /// The price of `items`.
+    pub fn try_checkout(&self, items: &str) -> Result<M, Overflow> {
+        total(self.rules.iter().map(|rule| &**rule), &count_items::<u64>(items), self.policy)
+    }
+}
+
+impl<M: Money> Default for ShallowMarket<M> {
+    fn default() -> ShallowMarket<M> {
+        ShallowMarket::new()
+    }
+}
@@ -0,0 +1,183 @@
+//! The demo catalog as constant data, priced at compile time.
+//!
+//! A `const fn` is a function the compiler is able to run while compiling,
+//! whenever its arguments are themselves known by then. Its result is baked
+//! into the binary as if it had been typed in by hand, and a `panic!` along
+//! the way becomes a compile error rather than a crash. The closest Java
+//! gets is `static final` fields of primitives and strings, folded by
+//! `javac`; anything that calls a method waits for the class initializer at
+//! runtime.
+//!
+//! The price is a smaller language. A `const fn` cannot allocate, call trait
+//! methods, or use `for` loops (which are built on the `Iterator` trait), so
+//! the rules here are a plain `Copy` enum over `Cents`, priced with `while`
+//! loops over bytes. The rest of the crate is none the wiser: `supermarket`
+//! turns the same data into an ordinary `Supermarket`.
+//!
+//! ```
+//! use rust_for_java_devs::const_catalog::{self, DEMO};
+//! use rust_for_java_devs::money::Cents;
+//!
+//! // Worked out by the compiler; the program only ever sees the answer.
+//! const TOTAL: Cents = match const_catalog::checkout(&DEMO, "ABBACBBAB") {
+//!     Ok(total) => total,
+//!     Err(_) => panic!("the canonical cart overflowed"),
+//! };
+//! assert_eq!(Cents(24000), TOTAL);
+//! ```
+
+use crate::core::money::{Cents, Overflow};
+use crate::core::rules::{FlatPrice, BundlePrice};
+use crate::reasonable_implementation::{BoxedRule, Supermarket};
+
+/// A rule simple enough to be priced at compile time.
+///
+/// It derives `Copy` so that the `const fn`s below may read rules out of a
+/// slice by value, which is all they are allowed to do with them.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConstRule {
+    /// `cost` cents for every `product`.
+    Flat { product: char, cost: i64 },
+    /// `lone_cost` cents apiece, or `bundle_cost` cents for `bundle_size`.
+    Bundle { product: char, lone_cost: i64, bundle_size: u64, bundle_cost: i64 }
+}
+
+impl ConstRule {
+
+    /// A flat price, refused at compile time if the product is not ASCII
+    /// or the cost is negative.
+    ///
+    /// In a `const` context, a failed `assert!` stops the build, much as
+    /// `RuleError` stops a runtime constructor.
+    pub const fn flat(product: char, cost: i64) -> ConstRule {
+        assert!(product.is_ascii(), "products must be ASCII to be priced at compile time");
+        assert!(cost >= 0, "costs may not be negative");
+        ConstRule::Flat { product, cost }
+    }
+
+    /// A bundle price, refused at compile time if the product is not ASCII,
+    /// a cost is negative, or the bundle is empty.
+    pub const fn bundle(product: char, lone_cost: i64, bundle_size: u64,
+                        bundle_cost: i64) -> ConstRule {
+        assert!(product.is_ascii(), "products must be ASCII to be priced at compile time");
+        assert!(lone_cost >= 0 && bundle_cost >= 0, "costs may not be negative");
+        assert!(bundle_size > 0, "bundles may not be empty");
+        ConstRule::Bundle { product, lone_cost, bundle_size, bundle_cost }
+    }
+
+    /// The product this rule prices.
+    pub const fn product(&self) -> char {
+        match *self {
+            ConstRule::Flat { product, .. } | ConstRule::Bundle { product, .. } => product
+        }
+    }
+
+    /// What `count` of this rule's product cost, or `Overflow` if that is
+    /// too large for `Cents`.
+    ///
+    /// `?` is not allowed in a `const fn`, as it converts errors through a
+    /// trait, so each checked step is matched by hand.
+    pub const fn price(&self, count: u64) -> Result<Cents, Overflow> {
+        let cents = match *self {
+            ConstRule::Flat { cost, .. } => times(cost, count),
+            ConstRule::Bundle { lone_cost, bundle_size, bundle_cost, .. } => {
+                match (times(bundle_cost, count / bundle_size), times(lone_cost, count % bundle_size)) {
+                    (Some(bundles), Some(singles)) => bundles.checked_add(singles),
+                    _ => None
+                }
+            }
+        };
+        match cents {
+            Some(cents) => Ok(Cents(cents)),
+            None => Err(Overflow)
+        }
+    }
+
+    /// The same rule, as one of the runtime rules in `core::rules`.
+    pub fn to_rule(&self) -> BoxedRule<Cents> {
+        match *self {
+            ConstRule::Flat { product, cost } => Box::new(
+                FlatPrice::new(product, Cents(cost)).expect("checked by `flat`")
+            ),
+            ConstRule::Bundle { product, lone_cost, bundle_size, bundle_cost } => Box::new(
+                BundlePrice::new(product, Cents(lone_cost), bundle_size, Cents(bundle_cost))
+                    .expect("checked by `bundle`")
+            )
+        }
+    }
+}
+
+/// `cents * count`, or `None` if that does not fit in an `i64`.
+const fn times(cents: i64, count: u64) -> Option<i64> {
+    if count > i64::MAX as u64 {
+        return None;
+    }
+    cents.checked_mul(count as i64)
+}
+
+/// The demo catalog: A is $20, B is $50 or five for $150, and C is $30.
+///
+/// Being `const`, the array is copied into every place that uses it, and
+/// each `ConstRule::flat` or `ConstRule::bundle` call, checks included, runs
+/// while compiling.
+pub const DEMO: [ConstRule; 3] = [
+    ConstRule::flat('A', 2000),
+    ConstRule::bundle('B', 5000, 5, 15000),
+    ConstRule::flat('C', 3000)
+];
+
+/// How many times `product` appears in `items`.
+///
+/// A `const fn` cannot call `str::chars`, but it may look at the bytes. In
+/// UTF-8, every byte of a non-ASCII character is 128 or more, so counting an
+/// ASCII product byte by byte never mistakes part of another character for
+/// it.
+pub const fn count(items: &str, product: char) -> u64 {
+    let bytes = items.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] as u32 == product as u32 {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Prices `items` with `rules`, as `Supermarket::try_checkout` would, but
+/// in a `const fn`. Items no rule prices are ignored.
+pub const fn checkout(rules: &[ConstRule], items: &str) -> Result<Cents, Overflow> {
+    let mut total: i64 = 0;
+    let mut i = 0;
+    while i < rules.len() {
+        let rule = rules[i];
+        let price = match rule.price(count(items, rule.product())) {
+            Ok(Cents(price)) => price,
+            Err(overflow) => return Err(overflow)
+        };
+        total = match total.checked_add(price) {
+            Some(total) => total,
+            None => return Err(Overflow)
+        };
+        i += 1;
+    }
+    Ok(Cents(total))
+}
+
+/// The canonical cart's total, worked out by the compiler.
+pub const CANONICAL_TOTAL: Cents = match checkout(&DEMO, "ABBACBBAB") {
+    Ok(total) => total,
+    Err(_) => panic!("the canonical cart overflowed")
+};
+
+/// A test that runs during compilation: if the demo prices ever changed so
+/// that the canonical cart no longer came to $240, the crate would not
+/// build. `const _` evaluates the expression without naming the result.
+const _: () = assert!(CANONICAL_TOTAL.0 == 24000);
+
+/// An ordinary Supermarket with the same rules, for carts only known at
+/// runtime.
+pub fn supermarket(rules: &[ConstRule]) -> Supermarket<Cents> {
+    rules.iter().map(ConstRule::to_rule).collect()
+}
@@ -0,0 +1,126 @@
+//! Conversions with `From`, `Into` and `TryFrom`, compared with Java's
+//! constructors, `valueOf` and converter classes.
+//!
+//! Java converts in whatever way each class chose: `new Sku(c)`,
+//! `Sku.valueOf(c)`, `Sku.of(c)`, a Spring `Converter<Character, Sku>`, or
+//! an `asChar()` going the other way. A caller has to look each one up, and
+//! generic code cannot ask for "anything that converts to a `Sku`" at all.
+//!
+//! Rust has one pair of traits for it:
+//!
+//! * `From<T> for U` is the conversion that cannot fail, written once by
+//!   the type's author. `Sku::from('A')` is `Sku.valueOf('A')`.
+//! * `Into<U> for T` comes free with every `From`, pointing the other way,
+//!   so `'A'.into()` is a `Sku` wherever a `Sku` is expected. A parameter
+//!   of type `impl Into<Sku>` accepts a `Sku` or a `char`, with no
+//!   overloads to write: that is how `Cart::add` and
+//!   `Supermarket::try_checkout` take what their callers have.
+//! * `TryFrom<T> for U` is the conversion that can fail, returning a
+//!   `Result` instead of throwing, and `TryInto` is its mirror image.
+//!   `Cart::try_from("ABBA")` is `Integer.parseInt` for carts.
+//!
+//! An impl may live in any module of the crate that defines the trait or
+//! the type, and those below are gathered here so that they can be read
+//! together. The *orphan rule*, explained in `money::operators`, is why
+//! `From<Receipt<Cents>> for Cents` is allowed although both `From` and
+//! `Cents` come from other crates: `Receipt` is this crate's own.
+//!
+//! ```
+//! use rust_for_java_devs::conversions::Sku;
+//! use rust_for_java_devs::money::Cents;
+//! use rust_for_java_devs::ownership_and_borrowing::Cart;
+//! use rust_for_java_devs::reasonable_implementation::Supermarket;
+//!
+//! let market: Supermarket = Supermarket::demo();
+//! let mut cart = Cart::try_from("AB").unwrap();
+//! cart.add(Sku::from('C'));
+//! cart.add('B');
+//! // `try_checkout` takes anything `Into<String>`, a `Cart` among them.
+//! assert_eq!(Ok(Cents(15000)), market.try_checkout(cart.clone()));
+//! let total: Cents = market.receipt(cart.items()).unwrap().into();
+//! assert_eq!(Cents(15000), total);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use crate::core::money::Cents;
+#[cfg(feature = "decimal")]
+use crate::core::money::Decimal;
+use crate::ownership_and_borrowing::Cart;
+use crate::receipt::Receipt;
+
+/// `Sku` is defined in `pricing-core`, as `CheckoutError` names products
+/// with it, and its `From` impls are written there with it. Neither `char`
+/// nor `From` belongs to this crate, so here they could not be: the orphan
+/// rule wants the trait or the type to be local.
+pub use crate::core::checkout::Sku;
+
+/// Why some text is not a cart.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NotACart {
+    /// The character that is no product code.
+    pub found: char,
+    /// Where it was, counted in `char`s from zero.
+    pub position: usize
+}
+
+impl fmt::Display for NotACart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at position {} is not a product", self.found, self.position)
+    }
+}
+
+impl Error for NotACart {}
+
+/// Reads a cart from text, one product per character.
+///
+/// Text is checked, where `From<char> for Sku` is not: a space or a line
+/// break read from a file is far more likely a mistake than a product. This
+/// is the difference between Java's `Integer.valueOf(int)`, which cannot
+/// fail, and `Integer.parseInt(String)`, which throws a
+/// `NumberFormatException`; here the failure is the `Err` of a `Result`,
+/// whose type, `Error`, the impl names.
+impl<'a> TryFrom<&'a str> for Cart {
+    type Error = NotACart;
+
+    fn try_from(items: &'a str) -> Result<Cart, NotACart> {
+        let mut cart = Cart::new();
+        for (position, found) in items.chars().enumerate() {
+            if found.is_whitespace() || found.is_control() {
+                return Err(NotACart { found, position });
+            }
+            cart.add(found);
+        }
+        Ok(cart)
+    }
+}
+
+/// A cart's items, as a `String`, the type a market prices.
+///
+/// This moves the `String` out of the cart, as `Cart::into_items` does,
+/// so no copy is made.
+impl From<Cart> for String {
+    fn from(cart: Cart) -> String {
+        cart.into_items()
+    }
+}
+
+/// The amount due on a receipt.
+///
+/// This has to be written once per money type. A single
+/// `impl<M: Money> From<Receipt<M>> for M` is refused, as error E0210:
+/// `M` could be a type from another crate, and that crate might then write
+/// a conflicting impl of its own.
+impl From<Receipt<Cents>> for Cents {
+    fn from(receipt: Receipt<Cents>) -> Cents {
+        receipt.total
+    }
+}
+
+/// The amount due on a receipt in `Decimal`s.
+#[cfg(feature = "decimal")]
+impl From<Receipt<Decimal>> for Decimal {
+    fn from(receipt: Receipt<Decimal>) -> Decimal {
+        receipt.total
+    }
+}
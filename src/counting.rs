@@ -0,0 +1,32 @@
+//! Counting conveniences that depend on the standard library.
+//!
+//! `HashMap` picks a random seed for its hasher when it is created, to defend
+//! against inputs crafted to collide, and getting randomness requires an
+//! operating system. That makes it unavailable to `no_std` builds, so the
+//! `HashMap`-based counting lives here, behind the `std` feature, while the
+//! portable counting lives in `core::counting`. (The `ItemCounts` impl for
+//! `HashMap` is in `core::counting` too, for reasons explained there.)
+
+use std::collections::hash_map::{HashMap, Entry};
+
+/// `pub use` re-exports items from elsewhere, so that callers can find them
+/// through this module too.
+pub use crate::core::counting::{Count, ItemCounts, AsciiCounts, Counts, CountStrategy, count_items};
+
+/// Counts the occurrences of each character in `items` into a `HashMap`.
+///
+/// This mirrors `count_items`, which explains the code in detail.
+pub fn count_characters<C: Count>(items: &str) -> HashMap<char, C> {
+    let mut count = HashMap::new();
+    for c in items.chars() {
+        match count.entry(c) {
+            Entry::Vacant(slot) => {
+                slot.insert(C::one());
+            },
+            Entry::Occupied(mut slot) => {
+                *slot.get_mut() += C::one();
+            }
+        }
+    }
+    count
+}
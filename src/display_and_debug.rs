@@ -0,0 +1,90 @@
+//! `Display` and `Debug`, compared with Java's `toString`.
+//!
+//! A Java object has one `toString`, and it is asked to do two jobs: show a
+//! value to a user, in a message or a report, and show it to a developer,
+//! in a log or a debugger. A format good for one is rarely good for the
+//! other, and an object that never overrides it prints `Receipt@1b6d3586`,
+//! which is good for neither.
+//!
+//! Rust gives each job its own trait:
+//!
+//! * `Display`, printed by `{}`, is for users. It is never derived, since
+//!   only the author knows what a user should see, and a type without one
+//!   simply cannot be printed with `{}`. `to_string` comes free with it.
+//! * `Debug`, printed by `{:?}`, is for developers, and may be derived,
+//!   showing every field. `{:#?}` prints the same over several indented
+//!   lines. `assert_eq!` and `unwrap` print values with it when they fail.
+//!
+//! Across the crate:
+//!
+//! * Every error has both, as `std::error::Error` requires.
+//! * The rules display as their descriptions, `B: $50.00 each or 5 for
+//!   $150.00`, even boxed as trait objects.
+//! * A `Receipt` displays as a shopper's receipt, line by line, and so do
+//!   its `ReceiptLine`s and `Adjustment`s on their own.
+//! * A `Supermarket` and a `RuleRegistry` hold trait objects and closures,
+//!   which have no `Debug` to derive from, so they write theirs by hand,
+//!   and have no `Display` at all.
+//!
+//! A type may offer more than one user-facing format, too. `Display` can
+//! only be implemented once, so other formats come from small adapter types
+//! borrowing the value, as `Path::display` does in the standard library;
+//! `summary` below is one. And a written `Debug` can leave things out, as
+//! `LoyaltyCard` does with its number, where a Java `toString` generated by
+//! an IDE or Lombok would happily log it.
+
+use std::fmt;
+use crate::core::money::Money;
+use crate::receipt::Receipt;
+
+/// A receipt in one line, such as `7 items: $200.00`.
+///
+/// Made by `summary`; this type exists only to be formatted.
+pub struct Summary<'r, M: Money>(&'r Receipt<M>);
+
+/// The one-line form of `receipt`, for `{}`.
+///
+/// Nothing is formatted until the `Summary` is: making one only borrows the
+/// receipt, so `println!("{}", summary(&receipt))` builds no `String`
+/// along the way.
+pub fn summary<M: Money>(receipt: &Receipt<M>) -> Summary<'_, M> {
+    Summary(receipt)
+}
+
+/// Honors width and alignment, as in `{:>20}`, by handing the finished
+/// text to `pad`. A `write!` straight to the formatter would ignore them,
+/// which is what most hand-written `Display`s do, and fine for text never
+/// laid out in columns.
+impl<M: Money> fmt::Display for Summary<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items: u64 = self.0.lines.iter().map(|line| line.quantity).sum();
+        let noun = if items == 1 { "item" } else { "items" };
+        f.pad(&format!("{} {}: {}", items, noun, self.0.total))
+    }
+}
+
+/// A shopper's loyalty card.
+///
+/// Its number must not end up in logs, yet anything `Debug` will sooner or
+/// later be logged with `{:?}`, or printed by a failing `assert_eq!`. So
+/// `Debug` is written by hand, to show only the last four digits, and
+/// `Display`, which has no business showing the number at all, is left out.
+#[derive(Clone, PartialEq, Eq)]
+pub struct LoyaltyCard {
+    pub holder: String,
+    pub number: String
+}
+
+/// `LoyaltyCard { holder: "Ada", number: "****1234" }`.
+impl fmt::Debug for LoyaltyCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `skip` counts `char`s, so a number with fewer than four digits
+        // is shown whole, rather than slicing off part of a character.
+        let shown = self.number.chars().count().saturating_sub(4);
+        let last: String = self.number.chars().skip(shown).collect();
+        f.debug_struct("LoyaltyCard")
+            .field("holder", &self.holder)
+            .field("number", &format!("****{}", last))
+            .finish()
+    }
+}
@@ -0,0 +1,167 @@
+//! Cleaning up with `Drop`, compared with Java's `try`-with-resources and
+//! finalizers.
+//!
+//! A `CheckoutSession` is a till's shift: it prices carts, keeps a running
+//! total, and must write an audit record of the shift when it ends, however
+//! it ends. In Java that is a job for `AutoCloseable`:
+//!
+//! ```text
+//! try (CheckoutSession session = new CheckoutSession(market, "till 1", log)) {
+//!     session.scan("ABBA");
+//! }   // close() runs here, even if scan threw
+//! ```
+//!
+//! The `try` is what makes it work: a session used without one, or stored
+//! in a field, is closed only if somebody remembers to. The last resort was
+//! `finalize()`, run by the garbage collector at some unknown time, if at
+//! all, and now deprecated for removal.
+//!
+//! Rust has neither. A type implementing `Drop` has its `drop` method run
+//! by the compiler exactly when its owner goes out of scope, whether the
+//! scope ends normally, by `return` or `?`, or by a panic unwinding through
+//! it. Every variable is its own `try`-with-resources, so there is no
+//! `close` to forget. The order is fixed, too:
+//!
+//! * Local variables are dropped in reverse order of declaration, as a
+//!   `try` with several resources closes them last to first.
+//! * A struct's fields, and a `Vec`'s elements, are dropped front to back,
+//!   after the value holding them.
+//! * A value moved away is dropped by its new owner instead, and
+//!   `std::mem::drop(value)` is nothing more than a function taking
+//!   ownership, to end a value's life early.
+//!
+//! Two things skip `drop`: `std::mem::forget`, which gives a value up
+//! without dropping it, and values kept alive forever, such as `Rc`s in a
+//! cycle. Neither is unsafe, so `Drop` is the right place for cleanup, but
+//! not for anything soundness depends on.
+
+use std::sync::{Arc, Mutex};
+use std::vec::Vec;
+use crate::core::checkout::CheckoutError;
+use crate::core::money::{Money, Cents};
+use crate::reasonable_implementation::Supermarket;
+
+/// What a session writes to the audit log when it ends.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AuditRecord<M: Money = Cents> {
+    /// The session's name, such as the till it ran on.
+    pub session: String,
+    /// The carts priced successfully.
+    pub carts: u64,
+    /// What they came to, saturating at the largest amount.
+    pub total: M,
+    /// Whether the session was closed explicitly, as opposed to dropped.
+    pub closed: bool
+}
+
+/// Where sessions write their audit records, in the order they end.
+///
+/// Clones share one log, so that any number of sessions, on any threads,
+/// can write to it.
+#[derive(Clone, Debug)]
+pub struct AuditLog<M: Money = Cents> {
+    records: Arc<Mutex<Vec<AuditRecord<M>>>>
+}
+
+impl<M: Money> Default for AuditLog<M> {
+    fn default() -> AuditLog<M> {
+        AuditLog { records: Arc::new(Mutex::new(Vec::new())) }
+    }
+}
+
+impl<M: Money> AuditLog<M> {
+
+    /// An empty log.
+    pub fn new() -> AuditLog<M> {
+        AuditLog::default()
+    }
+
+    /// Every record written so far, oldest first.
+    pub fn records(&self) -> Vec<AuditRecord<M>> {
+        self.lock().clone()
+    }
+
+    /// The records, locked.
+    ///
+    /// A session dropped during a panic writes its record while unwinding,
+    /// and a panic while the lock is held marks the lock *poisoned*. Writing
+    /// a record cannot leave the list half changed, so `into_inner` takes
+    /// the list from a poisoned lock anyway rather than failing too.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<AuditRecord<M>>> {
+        self.records.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A till's shift, writing its `AuditRecord` to the log when it ends.
+///
+/// The session borrows its market, `'m`, so it cannot outlive it, which the
+/// Java version could only promise in its documentation.
+pub struct CheckoutSession<'m, M: Money = Cents> {
+    market: &'m Supermarket<M>,
+    log: AuditLog<M>,
+    /// The record so far. `None` once it has been written, so that it is
+    /// never written twice.
+    record: Option<AuditRecord<M>>
+}
+
+impl<'m, M: Money> CheckoutSession<'m, M> {
+
+    /// Opens a session called `name`, pricing carts at `market`.
+    ///
+    /// There is no separate `open` or `close` bracket to get right: the
+    /// session is ready once made, and ends when dropped.
+    pub fn open(market: &'m Supermarket<M>, name: impl Into<String>, log: &AuditLog<M>)
+                -> CheckoutSession<'m, M> {
+        CheckoutSession {
+            market,
+            log: log.clone(),
+            record: Some(AuditRecord { session: name.into(), carts: 0, total: M::zero(), closed: false })
+        }
+    }
+
+    /// Prices `cart`, adding it to the session's takings.
+    pub fn scan(&mut self, cart: &str) -> Result<M, CheckoutError> {
+        let price = self.market.try_checkout(cart)?;
+        if let Some(record) = self.record.as_mut() {
+            record.carts += 1;
+            record.total = record.total.saturating_plus(price);
+        }
+        Ok(price)
+    }
+
+    /// The session's takings so far.
+    pub fn total(&self) -> M {
+        self.record.as_ref().map_or_else(M::zero, |record| record.total)
+    }
+
+    /// Ends the session now, writing its record and returning a copy.
+    ///
+    /// This is `close()` for callers who want the record, or want the
+    /// session's end to stand out in the code. Taking `self` by value means
+    /// a closed session cannot be used again, nor closed twice: the
+    /// compiler forbids both, where a Java `close` has to check a flag.
+    /// `drop` runs anyway once this returns, and finds nothing left to do.
+    pub fn close(mut self) -> AuditRecord<M> {
+        let mut record = self.record.take().expect("the record is only taken once");
+        record.closed = true;
+        self.log.lock().push(record.clone());
+        record
+    }
+}
+
+/// Writes the audit record of a session that ends without `close`.
+///
+/// `drop` takes `&mut self`, not `self`, as the value is still being torn
+/// down; its fields are dropped, front to back, right after this returns.
+/// It cannot fail, either, so there is no `Result` to return, much as
+/// Java's `close` exceptions become suppressed ones once another exception
+/// is already on its way out.
+impl<'m, M: Money> Drop for CheckoutSession<'m, M> {
+    fn drop(&mut self) {
+        // `take` leaves `None` behind, so a session already closed writes
+        // nothing more.
+        if let Some(record) = self.record.take() {
+            self.log.lock().push(record);
+        }
+    }
+}
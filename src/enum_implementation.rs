@@ -0,0 +1,149 @@
+//! This module solves the same challenge as `reasonable_implementation`,
+//! but models the pricing rules as an `enum` instead of as trait objects.
+//!
+//! A Java programmer would reach for an interface with one class per kind of
+//! rule, and `reasonable_implementation` follows that habit: each rule is a
+//! `Box<dyn PricingRule>` and every call to `price` is dispatched at runtime
+//! through a table of function pointers.
+//!
+//! Here the set of rule kinds is *closed*. All of them are listed in one
+//! place, the rules are stored inline in the Vec without boxing, and pricing
+//! is a plain `match`. The trade-off is the classic one:
+//!
+//! * Adding a new kind of rule means editing this enum and every `match` on
+//!   it, and downstream crates cannot add kinds of their own.
+//! * In exchange, the compiler checks every `match` handles every kind, and
+//!   the calls can be inlined rather than dispatched through a pointer.
+
+use crate::core::counting::{count_items, Counts};
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::reasonable_implementation::{CheckoutError, Market};
+
+/// Every kind of rule this market understands, in one type.
+///
+/// Unlike a Java `enum`, whose constants are all instances of the same
+/// class, each variant of a Rust enum may carry its own fields. The closest
+/// Java analogue is a sealed interface whose permitted subclasses are
+/// records.
+///
+/// One cost of the enum approach: a variant's fields are exactly as visible
+/// as the enum itself, so, unlike `core::rules::BundlePrice`, nothing stops
+/// a caller from writing `bundle_size: 0`. `price` therefore treats an empty
+/// bundle as pricing every item individually rather than dividing by zero.
+pub enum PricingRule<M: Money> {
+    /// For every item matching the product, the cost is added to the price.
+    Flat {
+        product: char,
+        cost: M
+    },
+    /// "X cost apiece, or Y cost when you buy N of them", allowing
+    /// unlimited bundles.
+    Bundle {
+        product: char,
+        lone_cost: M,
+        bundle_size: u64,
+        bundle_cost: M
+    }
+}
+
+impl<M: Money> PricingRule<M> {
+
+    /// Prices the items this rule accounts for.
+    ///
+    /// Where `reasonable_implementation` has one `impl` block per rule type,
+    /// here a single `match` covers every variant. Remove an arm and the
+    /// program no longer compiles.
+    pub fn price(&self, character_counts: &Counts,
+                 policy: OverflowPolicy) -> Result<M, Overflow> {
+        // Matching on `*self` rather than `self` names the rule itself
+        // rather than a reference to it. Every field is `Copy`, so each one
+        // is simply copied into the variables named in the pattern.
+        match *self {
+            PricingRule::Flat { product, cost } => {
+                match character_counts.get(&product) {
+                    Some(&count) => policy.times(cost, count),
+                    None => Ok(M::zero())
+                }
+            },
+            PricingRule::Bundle { product, lone_cost, bundle_size, bundle_cost } => {
+                match character_counts.get(&product) {
+                    Some(&0) | None => Ok(M::zero()),
+                    Some(&count) if bundle_size == 0 => policy.times(lone_cost, count),
+                    Some(&count) => {
+                        let bundled = policy.times(bundle_cost, count / bundle_size)?;
+                        let loose = policy.times(lone_cost, count % bundle_size)?;
+                        policy.plus(bundled, loose)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A Supermarket holding its rules directly, with no `Box` in sight.
+pub struct Supermarket<M: Money = Cents> {
+    /// Each element is as large as the largest variant, so the rules sit
+    /// side by side in one contiguous allocation.
+    price_rules: Vec<PricingRule<M>>,
+    overflow_policy: OverflowPolicy
+}
+
+impl<M: Money> Supermarket<M> {
+
+    /// A Supermarket with an empty catalog.
+    pub fn new() -> Supermarket<M> {
+        Supermarket {
+            price_rules: Vec::new(),
+            overflow_policy: Default::default()
+        }
+    }
+
+    /// A Supermarket stocked with the demo catalog.
+    #[cfg(feature = "demo-catalog")]
+    pub fn demo() -> Supermarket<M> {
+        Supermarket {
+            // No casts are needed this time: every element already has the
+            // same type, `PricingRule<M>`.
+            price_rules: vec!(
+                PricingRule::Flat { product: 'A', cost: M::from_cents(2000) },
+                PricingRule::Bundle {
+                    product: 'B',
+                    lone_cost: M::from_cents(5000),
+                    bundle_size: 5,
+                    bundle_cost: M::from_cents(15000)
+                },
+                PricingRule::Flat { product: 'C', cost: M::from_cents(3000) }
+            ),
+            overflow_policy: Default::default()
+        }
+    }
+
+    /// Replaces the overflow policy, returning the updated Supermarket.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Supermarket<M> {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Prices a sequence of items, reporting an overflow under the
+    /// `Checked` policy as an error.
+    pub fn try_checkout(&self, items:String) -> Result<M, Overflow> {
+        let counts = count_items::<u64>(&items);
+        let policy = self.overflow_policy;
+        self.price_rules.iter().try_fold(M::zero(), |total, p| {
+            let price = p.price(&counts, policy)?;
+            policy.plus(total, price)
+        })
+    }
+}
+
+impl<M: Money> Default for Supermarket<M> {
+    fn default() -> Supermarket<M> {
+        Supermarket::new()
+    }
+}
+
+impl<M: Money> Market<M> for Supermarket<M> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        Ok(self.try_checkout(items.to_string())?)
+    }
+}
@@ -0,0 +1,238 @@
+//! Enums carrying data, compared with Java 17's sealed interfaces and
+//! records.
+//!
+//! A promotion knocks something off a cart's total once the rules have
+//! priced it. Modern Java would model the kinds of promotion like this:
+//!
+//! ```java
+//! sealed interface Promotion permits MultiBuy, SpendAndSave, Coupon {}
+//! record MultiBuy(char product, long every, long cost) implements Promotion {}
+//! record SpendAndSave(long threshold, long saving) implements Promotion {}
+//! record Coupon(String code, long saving) implements Promotion {}
+//! ```
+//!
+//! and price them with a `switch` over the sealed type, which since Java 21
+//! the compiler checks covers every permitted record.
+//!
+//! Rust offers both halves of that design, and this module writes each:
+//!
+//! * The records become structs, `MultiBuy`, `SpendAndSave` and `Coupon`,
+//!   and the interface a trait, `Discount`. Each struct implements it, and
+//!   a `Vec<Box<dyn Discount>>` holds any mix of them. The set is *open*:
+//!   another crate may add a kind of its own, just by implementing the
+//!   trait, as a non-sealed Java interface allows.
+//! * The sealed interface becomes the enum `Promotion`, with one variant
+//!   per struct. Its set is *closed*: every kind is listed in one place,
+//!   and a `match` missing one does not compile. Values are stored inline,
+//!   with no `Box`, and each `saving` is a jump rather than a virtual call.
+//!
+//! The two make opposite things easy, a trade-off known as the *expression
+//! problem*:
+//!
+//! | Change                  | `dyn Discount`                  | `enum Promotion`                   |
+//! |-------------------------|---------------------------------|------------------------------------|
+//! | add a kind of promotion | a new struct and impl, anywhere | a new variant, and every `match` updated, in this crate only |
+//! | add an operation        | a new trait method, and every impl updated | a new function with a `match`, anywhere |
+//!
+//! `tests/rust/enums_and_sealed_classes.rs` makes both kinds of change from
+//! outside the crate.
+//!
+//! `Promotion` is also `#[non_exhaustive]`, a promise that variants may be
+//! added in future. Within this crate it changes nothing. Other crates must
+//! end each `match` on it with a `_` arm, so adding a variant does not
+//! break them, at the price of the compiler no longer telling them what
+//! they have missed; `tests/ui/promotion_match_not_exhaustive.rs` shows the
+//! error without one. Java has no counterpart: a `switch` over a sealed
+//! interface breaks whenever a record is permitted in.
+
+use crate::core::counting::Counts;
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::reasonable_implementation::{CheckoutError, Supermarket};
+use crate::reasonable_implementation::pipeline::{Stage, Ticket};
+
+/// Something taken off a total after the rules have priced a cart: the
+/// interface half of the design.
+pub trait Discount<M: Money = Cents>: Send + Sync {
+
+    /// What the receipt calls this discount.
+    fn label(&self) -> String;
+
+    /// How much to take off a cart with these `counts` and this `subtotal`;
+    /// zero if the discount does not apply.
+    fn saving(&self, counts: &Counts, subtotal: M, policy: OverflowPolicy) -> Result<M, Overflow>;
+}
+
+/// Every `every`th item of `product` is free, as in "buy two, get a third
+/// free" with `every` of three. `cost` is the price refunded per free item.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MultiBuy<M: Money = Cents> {
+    pub product: char,
+    pub every: u64,
+    pub cost: M
+}
+
+/// `saving` off any cart whose subtotal reaches `threshold`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SpendAndSave<M: Money = Cents> {
+    pub threshold: M,
+    pub saving: M
+}
+
+/// `saving` off every cart, for the shopper who brought the coupon `code`.
+///
+/// The `String` keeps this one from being `Copy`, and so keeps `Promotion`
+/// from being `Copy` too: an enum is only as `Copy` as its least `Copy`
+/// variant.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Coupon<M: Money = Cents> {
+    pub code: String,
+    pub saving: M
+}
+
+impl<M: Money> Discount<M> for MultiBuy<M> {
+    fn label(&self) -> String {
+        format!("every {} {} free", ordinal(self.every), self.product)
+    }
+
+    fn saving(&self, counts: &Counts, _subtotal: M, policy: OverflowPolicy) -> Result<M, Overflow> {
+        // As with an empty bundle in `enum_implementation`, nothing stops a
+        // caller writing `every: 0`, so it is taken to mean no free items
+        // rather than dividing by zero.
+        match counts.get(&self.product) {
+            Some(&count) if self.every > 0 => policy.times(self.cost, count / self.every),
+            _ => Ok(M::zero())
+        }
+    }
+}
+
+impl<M: Money> Discount<M> for SpendAndSave<M> {
+    fn label(&self) -> String {
+        format!("{} off over {}", self.saving, self.threshold)
+    }
+
+    fn saving(&self, _counts: &Counts, subtotal: M, _policy: OverflowPolicy) -> Result<M, Overflow> {
+        Ok(if subtotal >= self.threshold { self.saving } else { M::zero() })
+    }
+}
+
+impl<M: Money> Discount<M> for Coupon<M> {
+    fn label(&self) -> String {
+        format!("coupon {}", self.code)
+    }
+
+    fn saving(&self, _counts: &Counts, _subtotal: M, _policy: OverflowPolicy) -> Result<M, Overflow> {
+        Ok(self.saving)
+    }
+}
+
+/// Every kind of promotion, in one type: the sealed half of the design.
+///
+/// Each variant wraps one of the structs above, as each permitted subclass
+/// of a sealed Java interface is a record of its own. Variants could hold
+/// their fields directly, `MultiBuy { product: char, .. }`, as
+/// `enum_implementation::PricingRule` does; wrapping structs lets the same
+/// types serve both designs, and lets a function take a `MultiBuy` alone,
+/// which a variant, not being a type, cannot be.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum Promotion<M: Money = Cents> {
+    MultiBuy(MultiBuy<M>),
+    SpendAndSave(SpendAndSave<M>),
+    Coupon(Coupon<M>)
+}
+
+impl<M: Money> Promotion<M> {
+
+    /// Whether this promotion depends on what is in the cart, rather than
+    /// on its total or on nothing at all.
+    ///
+    /// A new operation on the enum is just a function with a `match`, and
+    /// no variant needed changing to add it. On the trait, this would be a
+    /// new method, to be written for every implementation, including those
+    /// in crates this one has never heard of.
+    pub fn depends_on_items(&self) -> bool {
+        matches!(self, Promotion::MultiBuy(_))
+    }
+}
+
+/// The enum is a `Discount` too, delegating to whichever struct it holds,
+/// so a stage can take either design.
+///
+/// With no `_` arm, each `match` here must name every variant. Adding a
+/// fourth stops this impl compiling until it is handled, which is the
+/// whole point of a closed set.
+impl<M: Money> Discount<M> for Promotion<M> {
+    fn label(&self) -> String {
+        match self {
+            Promotion::MultiBuy(promotion) => promotion.label(),
+            Promotion::SpendAndSave(promotion) => promotion.label(),
+            Promotion::Coupon(promotion) => promotion.label()
+        }
+    }
+
+    fn saving(&self, counts: &Counts, subtotal: M, policy: OverflowPolicy) -> Result<M, Overflow> {
+        match self {
+            Promotion::MultiBuy(promotion) => promotion.saving(counts, subtotal, policy),
+            Promotion::SpendAndSave(promotion) => promotion.saving(counts, subtotal, policy),
+            Promotion::Coupon(promotion) => promotion.saving(counts, subtotal, policy)
+        }
+    }
+}
+
+/// A boxed discount is a discount, so a `Vec<Box<dyn Discount>>` can go
+/// wherever a `Vec<Promotion>` can. `?Sized` admits `dyn Discount` itself.
+impl<M: Money, D: Discount<M> + ?Sized> Discount<M> for Box<D> {
+    fn label(&self) -> String {
+        (**self).label()
+    }
+
+    fn saving(&self, counts: &Counts, subtotal: M, policy: OverflowPolicy) -> Result<M, Overflow> {
+        (**self).saving(counts, subtotal, policy)
+    }
+}
+
+/// A checkout stage taking each of its discounts off the total, as an
+/// adjustment of its own, in order. Insert it after `promotions`.
+///
+/// `D` is `Promotion` for the enum design, or `Box<dyn Discount>` for the
+/// trait objects; the stage is written once for both.
+pub struct Discounts<D> {
+    discounts: Vec<D>
+}
+
+impl<D> Discounts<D> {
+
+    /// A stage applying `discounts`.
+    pub fn new(discounts: Vec<D>) -> Discounts<D> {
+        Discounts { discounts }
+    }
+}
+
+impl<M: Money, D: Discount<M>> Stage<M> for Discounts<D> {
+    fn name(&self) -> &str {
+        "discounts"
+    }
+
+    fn run(&self, _market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+        for discount in self.discounts.iter() {
+            let saving = discount.saving(&ticket.counts, ticket.subtotal, ticket.policy)?;
+            if saving != M::zero() {
+                let amount = M::zero().checked_minus(saving).ok_or(Overflow)?;
+                ticket.adjust(discount.label(), amount)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `2nd`, `3rd`, `4th` and so on, for labels.
+fn ordinal(n: u64) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th"
+    };
+    format!("{}{}", n, suffix)
+}
@@ -0,0 +1,107 @@
+//! Equality, hashing and ordering, compared with Java's `equals`,
+//! `hashCode` and `compareTo`.
+//!
+//! Every Java object has `equals` and `hashCode`, inherited from `Object`
+//! and comparing identity until overridden, and the two must be overridden
+//! together. Forgetting `hashCode`, or computing it from a field `equals`
+//! ignores, gives a class whose equal objects land in different buckets of
+//! a `HashMap`, and `get` then fails to find a key that is plainly there.
+//! Nothing but a linter warns about it.
+//!
+//! Rust splits the contract into traits, and a type has none of them until
+//! it asks:
+//!
+//! * `PartialEq` is `==`. Without it, comparing two values does not compile,
+//!   so there is no identity comparison to fall back on by mistake.
+//! * `Eq` adds no methods. It promises that `==` is reflexive, that every
+//!   value equals itself, which `f64` cannot promise, as `NaN != NaN`. That
+//!   is why a float cannot be a `HashMap` key, and why `PricePoint::each`
+//!   returns an `f64` rather than being a field.
+//! * `Hash` feeds the fields to a `Hasher`, instead of combining them into
+//!   an `int` by hand, as `Objects.hash` does. Its contract is `hashCode`'s:
+//!   `a == b` must imply that `a` and `b` hash the same.
+//! * `PartialOrd` and `Ord` are `compareTo`, and must agree with `==`.
+//!
+//! `#[derive(...)]` writes any of them from the fields, in declaration
+//! order, like an IDE generating `equals` and `hashCode`, except that it is
+//! regenerated on every build and so cannot fall out of date when a field
+//! is added. The catalog and receipt types derive what they can:
+//! `CatalogRow`, `ReceiptLine` and `Adjustment` derive `Eq` and `Hash`, and
+//! `PricePoint` derives `Ord` too, sorting by quantity first. `ReceiptLine`
+//! writes its `Ord` by hand, since the derived one would sort lines without
+//! a product first.
+//!
+//! A type whose equality is not field by field must write `PartialEq` and
+//! `Hash` by hand, and keep them consistent itself, as in Java. `ProductCode`
+//! below is such a type: a product code that ignores case, as a till
+//! scanning with `ScanPolicy { ignore_case: true, .. }` does.
+//!
+//! Java's other pitfall, changing a key while it is in a map so that it sits
+//! in the wrong bucket, cannot happen: a `HashMap` owns its keys and lends
+//! them out only through shared references, so they cannot be changed
+//! without first being removed.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A product code, such as `'A'`, equal to the same letter in either case.
+///
+/// The code keeps the case it was made with, for display, so the impls
+/// cannot be derived: a derived `PartialEq` would compare the stored `char`s
+/// and find `'a'` and `'A'` different.
+#[derive(Clone, Copy, Debug)]
+pub struct ProductCode(pub char);
+
+impl ProductCode {
+
+    /// The code as a till would read it, in upper case.
+    ///
+    /// Equality, hashing and ordering all go through this one method, which
+    /// is the easiest way to make sure they agree.
+    pub fn normalized(self) -> char {
+        self.0.to_ascii_uppercase()
+    }
+}
+
+/// Codes are equal when they name the same product, whatever their case.
+impl PartialEq for ProductCode {
+    fn eq(&self, other: &ProductCode) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+/// `==` compares upper-case `char`s, so it is reflexive.
+impl Eq for ProductCode {}
+
+/// Hashes the normalized code, so that codes equal under `eq` hash the
+/// same. Hashing `self.0` instead would compile, and would break every
+/// `HashMap` keyed by codes of mixed case; Clippy's
+/// `derived_hash_with_manual_eq` lint catches the commonest form of that
+/// mistake, a derived `Hash` next to a written `PartialEq`.
+impl Hash for ProductCode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
+    }
+}
+
+/// Codes in alphabetical order, ignoring case, so that `cmp` returns
+/// `Equal` exactly when `eq` returns `true`.
+impl Ord for ProductCode {
+    fn cmp(&self, other: &ProductCode) -> Ordering {
+        self.normalized().cmp(&other.normalized())
+    }
+}
+
+impl PartialOrd for ProductCode {
+    fn partial_cmp(&self, other: &ProductCode) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The code as it was written, case and all.
+impl fmt::Display for ProductCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,287 @@
+//! Error context and chains: one failure to load a market's settings,
+//! reported three ways, compared with Java's exception chaining.
+//!
+//! A Java loader would wrap each low-level exception in one of its own,
+//! `throw new ConfigException("could not load " + path, e)`, and the
+//! resulting chain of causes is what `printStackTrace` prints: each
+//! exception's message, then `Caused by:` and the next, each with the stack
+//! trace of where it was thrown.
+//!
+//! Rust's `std::error::Error` has the same chain: `source()` is
+//! `getCause()`, and `chain` below walks it, as a loop over `getCause()`
+//! would. What Rust lacks is the automatic stack trace, which Java captures
+//! on every `new Exception()`, at a price paid whether anybody looks at it
+//! or not. A Rust error carries what its author put in it, and context has
+//! to be added on purpose. The three submodules add it with increasing
+//! help:
+//!
+//! * `bare` writes the error enum by hand, with its `Display`, `Error` and
+//!   `From` impls. Nothing is hidden, but it is a lot of code, and it is
+//!   easy to let `?` convert an `io::Error` with no word of which file it
+//!   was about; an `io::Error`, unlike Java's `FileNotFoundException`, does
+//!   not know its path.
+//! * `derived` writes the same enum with `thiserror`, whose
+//!   `#[derive(Error)]` writes those impls from attributes: `#[error(..)]`
+//!   for the message, `#[source]` for the cause. This is the usual choice
+//!   for a library, whose callers may want to `match` on what went wrong.
+//! * `with_anyhow` gives up on a type of its own. `anyhow::Error` holds any
+//!   error, `.context(..)` wraps it in a message, as Java's wrapping
+//!   constructor does, and `{:#}` prints the whole chain on one line. It
+//!   also captures a backtrace, if `RUST_BACKTRACE=1` asks for one. This is
+//!   the usual choice for an application, which only reports errors.
+//!
+//! Each loads the same settings, from a file of `key = value` lines:
+//!
+//! ```text
+//! # Limits for the express lane.
+//! max_items = 10
+//! max_bytes = 64
+//! overflow = saturating
+//! ```
+
+use std::error::Error;
+use std::iter;
+use crate::core::checkout::CartLimits;
+use crate::core::money::{Money, OverflowPolicy};
+use crate::reasonable_implementation::Supermarket;
+
+/// The settings a file may change, with the rest left at their defaults.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MarketConfig {
+    pub cart_limits: CartLimits,
+    pub overflow_policy: OverflowPolicy
+}
+
+impl MarketConfig {
+
+    /// `market`, with these settings.
+    pub fn apply<M: Money>(self, market: Supermarket<M>) -> Supermarket<M> {
+        market.with_cart_limits(self.cart_limits).with_overflow_policy(self.overflow_policy)
+    }
+}
+
+/// The messages of `error` and of each of its causes, outermost first.
+///
+/// `iter::successors` yields `error`, then whatever `source()` returns for
+/// it, and so on until there is none: Java's
+/// `for (Throwable t = e; t != null; t = t.getCause())`.
+pub fn chain(error: &(dyn Error + 'static)) -> Vec<String> {
+    iter::successors(Some(error), |&error| error.source()).map(|error| error.to_string()).collect()
+}
+
+/// The lines of a settings file worth reading, numbered from one, each
+/// split into a key and a value if it can be.
+fn settings(text: &str) -> impl Iterator<Item = (usize, Option<(&str, &str)>)> {
+    text.lines().enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| (number, line.split_once('=').map(|(key, value)| (key.trim(), value.trim()))))
+}
+
+/// The policy named `value`, if any.
+fn policy_named(value: &str) -> Option<OverflowPolicy> {
+    match value {
+        "checked" => Some(OverflowPolicy::Checked),
+        "saturating" => Some(OverflowPolicy::Saturating),
+        "wrapping" => Some(OverflowPolicy::Wrapping),
+        _ => None
+    }
+}
+
+/// The error enum written by hand.
+pub mod bare {
+    use std::error::Error;
+    use std::fmt;
+    use std::fs;
+    use std::io;
+    use std::num::ParseIntError;
+    use std::path::Path;
+    use super::{MarketConfig, policy_named, settings};
+
+    /// Everything that can go wrong loading settings.
+    #[derive(Debug)]
+    pub enum ConfigError {
+        /// The file could not be read, though which file is not recorded.
+        Read(io::Error),
+        /// A line is not `key = value`.
+        Syntax { line: usize },
+        /// A line sets something there is no setting for.
+        UnknownKey { line: usize, key: String },
+        /// A limit is not a number.
+        BadNumber { line: usize, key: String, source: ParseIntError },
+        /// `overflow` names no policy.
+        UnknownPolicy { line: usize, value: String }
+    }
+
+    impl fmt::Display for ConfigError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                // The cause's message is left to `source`, not repeated
+                // here, or a printed chain would say it twice.
+                ConfigError::Read(_) => write!(f, "could not read the settings"),
+                ConfigError::Syntax { line } => write!(f, "line {}: expected `key = value`", line),
+                ConfigError::UnknownKey { line, key } => write!(f, "line {}: no setting called {:?}", line, key),
+                ConfigError::BadNumber { line, key, .. } => write!(f, "line {}: {} is not a number", line, key),
+                ConfigError::UnknownPolicy { line, value } => {
+                    write!(f, "line {}: no overflow policy called {:?}", line, value)
+                }
+            }
+        }
+    }
+
+    /// `source` is the cause, `getCause()`. The provided version says
+    /// there is none, so each variant with a cause has to be listed.
+    impl Error for ConfigError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                ConfigError::Read(source) => Some(source),
+                ConfigError::BadNumber { source, .. } => Some(source),
+                _ => None
+            }
+        }
+    }
+
+    /// Lets `?` wrap an `io::Error`, losing the chance to say which file.
+    impl From<io::Error> for ConfigError {
+        fn from(source: io::Error) -> ConfigError {
+            ConfigError::Read(source)
+        }
+    }
+
+    /// The settings in `text`.
+    pub fn parse(text: &str) -> Result<MarketConfig, ConfigError> {
+        let mut config = MarketConfig::default();
+        for (line, setting) in settings(text) {
+            let (key, value) = setting.ok_or(ConfigError::Syntax { line })?;
+            let bad_number = |source| ConfigError::BadNumber { line, key: key.to_string(), source };
+            match key {
+                "max_items" => config.cart_limits.max_items = value.parse().map_err(bad_number)?,
+                "max_bytes" => config.cart_limits.max_bytes = value.parse().map_err(bad_number)?,
+                "overflow" => {
+                    config.overflow_policy = policy_named(value)
+                        .ok_or_else(|| ConfigError::UnknownPolicy { line, value: value.to_string() })?
+                },
+                _ => return Err(ConfigError::UnknownKey { line, key: key.to_string() })
+            }
+        }
+        Ok(config)
+    }
+
+    /// The settings in the file at `path`.
+    pub fn load(path: &Path) -> Result<MarketConfig, ConfigError> {
+        parse(&fs::read_to_string(path)?)
+    }
+}
+
+/// The same enum, derived with `thiserror`.
+pub mod derived {
+    use std::fs;
+    use std::io;
+    use std::num::ParseIntError;
+    use std::path::{Path, PathBuf};
+    use thiserror::Error;
+    use super::{MarketConfig, policy_named, settings};
+
+    /// Everything that can go wrong loading settings.
+    ///
+    /// The `derive` writes `Display` from each `#[error]`, whose fields may
+    /// be named in the message as in `format!`, and `Error::source` from
+    /// each `#[source]`. A field called `source` would be taken as the
+    /// cause even without the attribute; it is written out here to be seen.
+    #[derive(Debug, Error)]
+    pub enum ConfigError {
+        /// The file at `path` could not be read. With a path field, the
+        /// error can say which file, as the hand-written one did not.
+        #[error("could not read {}", path.display())]
+        Read { path: PathBuf, #[source] source: io::Error },
+        /// A line is not `key = value`.
+        #[error("line {line}: expected `key = value`")]
+        Syntax { line: usize },
+        /// A line sets something there is no setting for.
+        #[error("line {line}: no setting called {key:?}")]
+        UnknownKey { line: usize, key: String },
+        /// A limit is not a number.
+        #[error("line {line}: {key} is not a number")]
+        BadNumber { line: usize, key: String, #[source] source: ParseIntError },
+        /// `overflow` names no policy.
+        #[error("line {line}: no overflow policy called {value:?}")]
+        UnknownPolicy { line: usize, value: String }
+    }
+
+    /// The settings in `text`.
+    pub fn parse(text: &str) -> Result<MarketConfig, ConfigError> {
+        let mut config = MarketConfig::default();
+        for (line, setting) in settings(text) {
+            let (key, value) = setting.ok_or(ConfigError::Syntax { line })?;
+            let bad_number = |source| ConfigError::BadNumber { line, key: key.to_string(), source };
+            match key {
+                "max_items" => config.cart_limits.max_items = value.parse().map_err(bad_number)?,
+                "max_bytes" => config.cart_limits.max_bytes = value.parse().map_err(bad_number)?,
+                "overflow" => {
+                    config.overflow_policy = policy_named(value)
+                        .ok_or_else(|| ConfigError::UnknownPolicy { line, value: value.to_string() })?
+                },
+                _ => return Err(ConfigError::UnknownKey { line, key: key.to_string() })
+            }
+        }
+        Ok(config)
+    }
+
+    /// The settings in the file at `path`.
+    ///
+    /// There is deliberately no `From<io::Error>`: `#[from]` on the `Read`
+    /// variant would write one, and `?` would then lose the path again.
+    pub fn load(path: &Path) -> Result<MarketConfig, ConfigError> {
+        let text = fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        parse(&text)
+    }
+}
+
+/// No error type at all, only `anyhow::Error` and context.
+pub mod with_anyhow {
+    use std::fs;
+    use std::path::Path;
+    use anyhow::{bail, Context, Result};
+    use super::{MarketConfig, policy_named, settings};
+
+    /// The settings in `text`.
+    ///
+    /// `anyhow::Result<T>` is `Result<T, anyhow::Error>`. `bail!` returns
+    /// an error made from a message, `throw new RuntimeException(..)`, and
+    /// `with_context` wraps the error before it, keeping it as the cause.
+    /// The closure only runs on failure, so the message costs nothing on
+    /// the happy path.
+    pub fn parse(text: &str) -> Result<MarketConfig> {
+        let mut config = MarketConfig::default();
+        for (line, setting) in settings(text) {
+            let Some((key, value)) = setting else {
+                bail!("line {}: expected `key = value`", line);
+            };
+            let not_a_number = || format!("line {}: {} is not a number", line, key);
+            match key {
+                "max_items" => config.cart_limits.max_items = value.parse().with_context(not_a_number)?,
+                "max_bytes" => config.cart_limits.max_bytes = value.parse().with_context(not_a_number)?,
+                "overflow" => {
+                    // `context` works on an `Option` too, making `None` an
+                    // error with that message.
+                    config.overflow_policy = policy_named(value)
+                        .with_context(|| format!("line {}: no overflow policy called {:?}", line, value))?
+                },
+                _ => bail!("line {}: no setting called {:?}", line, key)
+            }
+        }
+        Ok(config)
+    }
+
+    /// The settings in the file at `path`.
+    ///
+    /// Both steps add a layer of context, so a bad number renders, with
+    /// `{:#}`, as "could not load express.cfg: line 2: max_items is not a
+    /// number: invalid digit found in string", a whole `Caused by:` chain on
+    /// one line.
+    pub fn load(path: &Path) -> Result<MarketConfig> {
+        let text = fs::read_to_string(path).with_context(|| format!("could not read {}", path.display()))?;
+        parse(&text).with_context(|| format!("could not load {}", path.display()))
+    }
+}
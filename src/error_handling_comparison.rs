@@ -0,0 +1,162 @@
+//! The same fallible checkout written twice: once returning `Result`, once
+//! panicking, to compare Rust's two kinds of failure with Java's checked
+//! and unchecked exceptions.
+//!
+//! The checkout prices a cart against a list of unit prices, and can fail
+//! in two ways: the cart holds a product with no price, or the total is too
+//! large for `Cents`.
+//!
+//! In Java, the first would likely be a checked exception, declared with
+//! `throws` so that every caller must catch it or pass it on, and the
+//! second an unchecked `ArithmeticException` from `Math.addExact`, which any
+//! caller may ignore. Rust has the same two camps, drawn more sharply:
+//!
+//! * `Result<T, E>` is for failures a caller is expected to handle. It is
+//!   an ordinary return value, part of the signature like `throws`, and the
+//!   compiler warns if one is silently dropped. `?` passes it on to the
+//!   caller, as leaving an exception uncaught does, but visibly, at every
+//!   call that may fail.
+//! * `panic!` is for bugs: a broken invariant, an index out of bounds. It
+//!   unwinds the stack like an unchecked exception, but it is not meant to
+//!   be caught. A program may even be built to abort on the spot instead.
+//!
+//! `with_result` is how this crate writes every fallible operation.
+//! `with_panics` is what the same code looks like with exceptions as the
+//! model, and what it costs callers who need to recover.
+
+use crate::core::money::{Cents, Money, Overflow};
+
+/// The checkout written with `Result` and `?`.
+pub mod with_result {
+    use std::error::Error;
+    use std::fmt;
+    use super::{Cents, Money, Overflow};
+
+    /// Everything that can go wrong, as one enum.
+    ///
+    /// Where Java would declare `throws UnknownProductException,
+    /// PriceOverflowException`, or one checked superclass of both, Rust
+    /// lists the cases as variants. A caller's `match` must handle every
+    /// one, and will stop compiling if a variant is added, which no
+    /// `catch` block does.
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub enum OrderError {
+        /// The cart holds `product`, at `position` counting from zero, for
+        /// which there is no price.
+        UnknownProduct { product: char, position: usize },
+        /// The total is too large to represent.
+        Overflow
+    }
+
+    impl fmt::Display for OrderError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                OrderError::UnknownProduct { product, position } =>
+                    write!(f, "no price for {:?} at position {}", product, position),
+                OrderError::Overflow => write!(f, "the total is too large")
+            }
+        }
+    }
+
+    impl Error for OrderError {}
+
+    /// Lets `?` turn an `Overflow` into an `OrderError` on its way out of a
+    /// function, the way Java code wraps a low-level exception in one of
+    /// its own, `throw new OrderException(e)`, but with no code at the call.
+    impl From<Overflow> for OrderError {
+        fn from(_: Overflow) -> OrderError {
+            OrderError::Overflow
+        }
+    }
+
+    /// The price of one `product`, or `None` if it has none.
+    ///
+    /// Absence is not an error here, so the return type is `Option`, not
+    /// `Result`: it is up to the caller to decide whether a missing price
+    /// is a failure. Java would return `null`, or an `Optional`.
+    pub fn unit_price(prices: &[(char, Cents)], product: char) -> Option<Cents> {
+        prices.iter().find(|&&(p, _)| p == product).map(|&(_, price)| price)
+    }
+
+    /// Adds two amounts, or fails with `Overflow`.
+    pub fn add(a: Cents, b: Cents) -> Result<Cents, Overflow> {
+        a.checked_plus(b).ok_or(Overflow)
+    }
+
+    /// The total for `cart`, priced item by item against `prices`.
+    ///
+    /// Each `?` is a point where the function may return early, so a reader
+    /// sees every way out of it without looking up what the callees throw.
+    pub fn checkout(prices: &[(char, Cents)], cart: &str) -> Result<Cents, OrderError> {
+        let mut total = Cents::zero();
+        for (position, product) in cart.chars().enumerate() {
+            // `ok_or` turns the `Option` into a `Result`, supplying the
+            // error for `None`; `?` then returns it if it is an `Err`.
+            let price = unit_price(prices, product)
+                .ok_or(OrderError::UnknownProduct { product, position })?;
+            // `add` fails with an `Overflow`, which `?` converts with the
+            // `From` impl above.
+            total = add(total, price)?;
+        }
+        Ok(total)
+    }
+}
+
+/// The checkout written as if Rust had only unchecked exceptions.
+pub mod with_panics {
+    use std::panic;
+    use super::{Cents, Money};
+
+    /// The price of one `product`, panicking if it has none.
+    pub fn unit_price(prices: &[(char, Cents)], product: char) -> Cents {
+        match prices.iter().find(|&&(p, _)| p == product) {
+            Some(&(_, price)) => price,
+            // `panic!` is `throw new IllegalArgumentException(...)`, except
+            // that nothing in the signature says it may happen.
+            None => panic!("no price for {:?}", product)
+        }
+    }
+
+    /// The total for `cart`, panicking on an unknown product or overflow.
+    ///
+    /// The happy path reads well, with no `?` anywhere. The signature,
+    /// `-> Cents`, claims the function always succeeds, though, and only
+    /// its documentation, this paragraph, says otherwise.
+    pub fn checkout(prices: &[(char, Cents)], cart: &str) -> Cents {
+        let mut total = Cents::zero();
+        for product in cart.chars() {
+            // `expect` panics with its message if there is no value. It is
+            // the right call where `None` would be a bug, as an `assert` is.
+            total = total.checked_plus(unit_price(prices, product)).expect("the total is too large");
+        }
+        total
+    }
+
+    /// `checkout`, with its panic caught and turned back into an error, as a
+    /// Java caller would `catch (RuntimeException e)`.
+    ///
+    /// This works, but shows why panics are a poor way to report expected
+    /// failures:
+    ///
+    /// * The error is whatever was passed to `panic!`, so all that can be
+    ///   recovered is a message, not a value a caller could `match` on.
+    /// * The panic hook still runs first, and prints the message to
+    ///   standard error, as if the program were crashing.
+    /// * A program built with `panic = "abort"` in its `Cargo.toml` never
+    ///   gets here: the process ends at the `panic!`.
+    pub fn try_checkout(prices: &[(char, Cents)], cart: &str) -> Result<Cents, String> {
+        // `catch_unwind` runs the closure, stopping any panic unwinding out
+        // of it. `AssertUnwindSafe` is our promise that nothing the closure
+        // borrows is left half-updated by a panic, which is true of
+        // read-only borrows like these.
+        panic::catch_unwind(panic::AssertUnwindSafe(|| checkout(prices, cart))).map_err(|payload| {
+            // The payload is a `Box<dyn Any>`, as a panic can carry any
+            // value; a formatted message is a `String`, a literal a `&str`.
+            match payload.downcast::<String>() {
+                Ok(message) => *message,
+                Err(payload) => payload.downcast_ref::<&str>().map_or_else(
+                    || "checkout panicked".to_string(), |message| message.to_string())
+            }
+        })
+    }
+}
@@ -0,0 +1,182 @@
+//! Events announcing finished checkouts, for message queues such as Kafka.
+//!
+//! Java shops commonly publish a message for every sale, which stock
+//! keeping, analytics and fraud detection each consume at their own pace. A
+//! `CheckoutCompleted` is that message: which store, which cart, when, and
+//! the itemized receipt. It is `Serialize`, so any serde format will do; the
+//! `publish_events` example writes it as NDJSON, one JSON object per line,
+//! which `jq`, `kcat -P` and Kafka Connect's file source all read directly.
+//!
+//! The `Publish` trait is the hook: `checkout` prices a cart and hands the
+//! event to any `Publish` implementation. This crate only ships
+//! `NdjsonPublisher`; one for a real broker would wrap its client, such as
+//! `rdkafka`'s producer, behind the same trait.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::core::money::{Money, Cents};
+use crate::reasonable_implementation::{CheckoutError, Supermarket};
+use crate::receipt::Receipt;
+
+/// A cart was priced.
+///
+/// The `serde` attributes only tell the derives how to name the fields in
+/// JSON, in the camel case Java consumers expect; in Rust they remain in
+/// snake case.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutCompleted<M: Money = Cents> {
+    /// The store the register belongs to.
+    pub store_id: String,
+    /// `cart_hash` of the cart as scanned, identifying its contents without
+    /// publishing the cart itself.
+    ///
+    /// It identifies the cart, not the sale: two customers buying `AB` at
+    /// the same store produce the same hash, so it is no key for dropping
+    /// repeated deliveries of one event.
+    pub cart_hash: String,
+    /// When the cart was priced, in milliseconds since the Unix epoch, as
+    /// Java's `System.currentTimeMillis()` counts them.
+    pub timestamp: u64,
+    pub receipt: Receipt<M>
+}
+
+impl<M: Money> CheckoutCompleted<M> {
+
+    /// The event for `items`, priced as `receipt` in `store_id` at `at`.
+    pub fn new(store_id: impl Into<String>, items: &str, receipt: Receipt<M>,
+               at: SystemTime) -> CheckoutCompleted<M> {
+        // A time before 1970 can only come from a badly set clock, and is
+        // recorded as the epoch itself rather than refused.
+        let since_epoch = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+        CheckoutCompleted {
+            store_id: store_id.into(),
+            cart_hash: cart_hash(items),
+            timestamp: u64::try_from(since_epoch.as_millis()).unwrap_or(u64::MAX),
+            receipt
+        }
+    }
+}
+
+/// A 64-bit FNV-1a hash of `items`, as 16 hexadecimal digits.
+///
+/// The standard library's `DefaultHasher` is deliberately allowed to change
+/// between Rust releases, which would break any consumer comparing hashes
+/// made by different builds. FNV-1a is fixed, and short enough to write
+/// out here, or in Java.
+pub fn cart_hash(items: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in items.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Somewhere to send events, such as a message queue.
+///
+/// Like a Supermarket, a publisher may be shared between the threads of a
+/// busy register, hence `Send + Sync` and `&self`.
+pub trait Publish<M: Money = Cents>: Send + Sync {
+    fn publish(&self, event: &CheckoutCompleted<M>) -> io::Result<()>;
+}
+
+/// Any suitable closure is a publisher, as a Java lambda may implement a
+/// functional interface.
+impl<M, F> Publish<M> for F
+    where M: Money, F: Fn(&CheckoutCompleted<M>) -> io::Result<()> + Send + Sync
+{
+    fn publish(&self, event: &CheckoutCompleted<M>) -> io::Result<()> {
+        self(event)
+    }
+}
+
+/// Writes each event as one line of JSON.
+pub struct NdjsonPublisher<W> {
+    /// The `Mutex` keeps the lines of events published at the same time
+    /// from being interleaved.
+    writer: Mutex<W>
+}
+
+impl<W: Write + Send> NdjsonPublisher<W> {
+
+    /// A publisher writing to `writer`, such as standard output or a file.
+    pub fn new(writer: W) -> NdjsonPublisher<W> {
+        NdjsonPublisher { writer: Mutex::new(writer) }
+    }
+
+    /// Gives the writer back, once publishing is done.
+    pub fn into_inner(self) -> W {
+        // The lock is only poisoned if a thread panicked while writing, and
+        // the writer is still worth having then.
+        self.writer.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<W: Write + Send, M: Money + Serialize> Publish<M> for NdjsonPublisher<W> {
+    fn publish(&self, event: &CheckoutCompleted<M>) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        serde_json::to_writer(&mut *writer, event)?;
+        // Flushing each line hands it to whoever reads the output at once,
+        // rather than whenever a buffer happens to fill up.
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
+}
+
+/// The reasons `checkout` may fail.
+#[derive(Debug)]
+pub enum EventError {
+    /// The cart could not be priced, so there was nothing to publish.
+    Checkout(CheckoutError),
+    /// The cart was priced, but its event could not be published.
+    Publish(io::Error)
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EventError::Checkout(ref e) => write!(f, "{}", e),
+            EventError::Publish(ref e) => write!(f, "could not publish the checkout: {}", e)
+        }
+    }
+}
+
+impl Error for EventError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            EventError::Checkout(ref e) => Some(e),
+            EventError::Publish(ref e) => Some(e)
+        }
+    }
+}
+
+impl From<CheckoutError> for EventError {
+    fn from(e: CheckoutError) -> EventError {
+        EventError::Checkout(e)
+    }
+}
+
+impl From<io::Error> for EventError {
+    fn from(e: io::Error) -> EventError {
+        EventError::Publish(e)
+    }
+}
+
+/// Prices `items` in `market`, and publishes a `CheckoutCompleted` for them
+/// from `store_id` to `publisher`, returning the event.
+///
+/// Nothing is published for a cart that cannot be priced.
+pub fn checkout<M, P>(market: &Supermarket<M>, store_id: &str, items: &str,
+                      publisher: &P) -> Result<CheckoutCompleted<M>, EventError>
+    where M: Money, P: Publish<M> + ?Sized
+{
+    let receipt = market.receipt(items)?;
+    let event = CheckoutCompleted::new(store_id, items, receipt, SystemTime::now());
+    publisher.publish(&event)?;
+    Ok(event)
+}
@@ -0,0 +1,221 @@
+//! This module solves the challenge a third way: the market is generic over
+//! the exact types of its rules, so nothing is boxed and no call goes
+//! through a vtable.
+//!
+//! In Java, `List<PricingRule>` erases to a plain `List` at runtime and
+//! every element is reached through a reference and a virtual call. Rust
+//! generics work the other way around. Each distinct combination of type
+//! arguments is *monomorphized*: the compiler writes out a dedicated copy of
+//! the generic code for it, with every call resolved ahead of time and free
+//! to be inlined.
+//!
+//! The rules are held in a tuple, such as `(FlatPrice, BundlePrice,
+//! FlatPrice)`, whose type records the type of every element. Combined with
+//! the stack-allocated `AsciiCounts`, pricing an ASCII cart through
+//! `try_checkout_str` performs no heap allocation at all.
+
+use crate::core::counting::{count_items, AsciiCounts, ItemCounts};
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{self as core_rules, RuleError};
+use crate::reasonable_implementation::{CheckoutError, Market};
+use std::marker::PhantomData;
+
+/// A rule that can price the items counted in any `ItemCounts`.
+///
+/// Because `price` is itself generic over `C`, this trait cannot be turned
+/// into a `dyn PricingRule` trait object: there is no single function to put
+/// in a vtable, only a template for one per `C`. That is fine here, as this
+/// module never boxes its rules.
+pub trait PricingRule<M: Money> {
+    fn price<C: ItemCounts>(&self, counts: &C, policy: OverflowPolicy) -> Result<M, Overflow>;
+}
+
+/// For every item matching the product, the cost is added to the price.
+///
+/// As with `core::rules::FlatPrice`, the fields are private and checked by
+/// `new`.
+#[derive(Copy, Clone, Debug)]
+pub struct FlatPrice<M: Money> {
+    product: char,
+    cost: M
+}
+
+impl<M: Money> FlatPrice<M> {
+    /// A flat price of `cost` for every `product`, refused if the cost is
+    /// negative.
+    pub fn new(product: char, cost: M) -> Result<FlatPrice<M>, RuleError> {
+        // Rather than repeat its checks, build the equivalent core rule and
+        // copy its fields out of it.
+        let checked = core_rules::FlatPrice::new(product, cost)?;
+        Ok(FlatPrice { product: checked.product(), cost: checked.cost() })
+    }
+}
+
+impl<M: Money> PricingRule<M> for FlatPrice<M> {
+    fn price<C: ItemCounts>(&self, counts: &C, policy: OverflowPolicy) -> Result<M, Overflow> {
+        policy.times(self.cost, counts.count_of(self.product))
+    }
+}
+
+/// "X cost apiece, or Y cost when you buy N of them", allowing unlimited
+/// bundles.
+#[derive(Copy, Clone, Debug)]
+pub struct BundlePrice<M: Money> {
+    product: char,
+    lone_cost: M,
+    bundle_size: u64,
+    bundle_cost: M
+}
+
+impl<M: Money> BundlePrice<M> {
+    /// "`lone_cost` apiece, or `bundle_cost` for `bundle_size` of them",
+    /// refused if either cost is negative or the bundle is empty.
+    pub fn new(product: char, lone_cost: M, bundle_size: u64,
+               bundle_cost: M) -> Result<BundlePrice<M>, RuleError> {
+        let checked = core_rules::BundlePrice::new(product, lone_cost, bundle_size, bundle_cost)?;
+        Ok(BundlePrice {
+            product: checked.product(),
+            lone_cost: checked.lone_cost(),
+            bundle_size: checked.bundle_size(),
+            bundle_cost: checked.bundle_cost()
+        })
+    }
+}
+
+impl<M: Money> PricingRule<M> for BundlePrice<M> {
+    fn price<C: ItemCounts>(&self, counts: &C, policy: OverflowPolicy) -> Result<M, Overflow> {
+        let count = counts.count_of(self.product);
+        let bundled = policy.times(self.bundle_cost, count / self.bundle_size)?;
+        let loose = policy.times(self.lone_cost, count % self.bundle_size)?;
+        policy.plus(bundled, loose)
+    }
+}
+
+/// A fixed collection of rules whose types are all known at compile time.
+///
+/// This is implemented below for tuples of rules, so `(a, b, c)` is a
+/// `RuleList` whenever `a`, `b` and `c` are each `PricingRule`s.
+pub trait RuleList<M: Money> {
+    /// Adds the price of every rule in the list to `running_total`.
+    fn total<C: ItemCounts>(&self, counts: &C, policy: OverflowPolicy,
+                            running_total: M) -> Result<M, Overflow>;
+}
+
+/// A declarative macro writing the `RuleList` impl for one tuple size.
+///
+/// Rust has no way to be generic over "tuples of any length", so, like the
+/// standard library does for its own tuple impls, we stamp out one impl per
+/// arity. Java's equivalent would be writing `Tuple1` through `Tuple8` by
+/// hand or with an annotation processor.
+macro_rules! impl_rule_list_for_tuple {
+    ($($rule:ident),+) => {
+        impl<M: Money, $($rule: PricingRule<M>),+> RuleList<M> for ($($rule,)+) {
+            // Reusing the type parameter names as variable names lets the
+            // pattern `let (R1, R2, ...) = *self` destructure the tuple.
+            #[allow(non_snake_case)]
+            fn total<C: ItemCounts>(&self, counts: &C, policy: OverflowPolicy,
+                                    running_total: M) -> Result<M, Overflow> {
+                let ($(ref $rule,)+) = *self;
+                let mut total = running_total;
+                $(
+                    total = policy.plus(total, $rule.price(counts, policy)?)?;
+                )+
+                Ok(total)
+            }
+        }
+    };
+}
+
+/// The empty tuple, `()`, is the empty catalog: it adds nothing at all.
+///
+/// `()` is Rust's "unit" type, the value-level counterpart of Java's `void`.
+impl<M: Money> RuleList<M> for () {
+    fn total<C: ItemCounts>(&self, _counts: &C, _policy: OverflowPolicy,
+                            running_total: M) -> Result<M, Overflow> {
+        Ok(running_total)
+    }
+}
+
+impl_rule_list_for_tuple!(R1);
+impl_rule_list_for_tuple!(R1, R2);
+impl_rule_list_for_tuple!(R1, R2, R3);
+impl_rule_list_for_tuple!(R1, R2, R3, R4);
+impl_rule_list_for_tuple!(R1, R2, R3, R4, R5);
+impl_rule_list_for_tuple!(R1, R2, R3, R4, R5, R6);
+impl_rule_list_for_tuple!(R1, R2, R3, R4, R5, R6, R7);
+impl_rule_list_for_tuple!(R1, R2, R3, R4, R5, R6, R7, R8);
+
+/// The rule types of the demo catalog, spelled out in full.
+pub type DemoCatalog<M> = (FlatPrice<M>, BundlePrice<M>, FlatPrice<M>);
+
+/// A Supermarket whose rules are stored by value in `R`.
+///
+/// `PhantomData<M>` is a zero-sized marker telling the compiler that the
+/// struct is logically tied to `M` even though no field stores an `M`
+/// directly; without it, the unused type parameter would be an error.
+pub struct Supermarket<R: RuleList<M>, M: Money = Cents> {
+    price_rules: R,
+    overflow_policy: OverflowPolicy,
+    money: PhantomData<M>
+}
+
+impl<R: RuleList<M>, M: Money> Supermarket<R, M> {
+
+    /// A Supermarket using the given rules, e.g. a tuple of them, or `()`
+    /// for an empty catalog.
+    pub fn new(price_rules: R) -> Supermarket<R, M> {
+        Supermarket {
+            price_rules,
+            overflow_policy: Default::default(),
+            money: PhantomData
+        }
+    }
+
+    /// Replaces the overflow policy, returning the updated Supermarket.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Supermarket<R, M> {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Prices a borrowed sequence of items.
+    ///
+    /// Carts made only of ASCII item codes are counted into a stack array,
+    /// making this path allocation-free. Anything else falls back to the
+    /// usual `BTreeMap` counts.
+    pub fn try_checkout_str(&self, items: &str) -> Result<M, Overflow> {
+        let policy = self.overflow_policy;
+        // Each call to `total` below is a different monomorphized copy,
+        // one specialized for `AsciiCounts` and one for `BTreeMap`.
+        match AsciiCounts::from_items(items) {
+            Some(counts) => self.price_rules.total(&counts, policy, M::zero()),
+            None => {
+                let counts = count_items::<u64>(items);
+                self.price_rules.total(&counts, policy, M::zero())
+            }
+        }
+    }
+}
+
+impl<M: Money> Supermarket<DemoCatalog<M>, M> {
+
+    /// A Supermarket stocked with the demo catalog.
+    #[cfg(feature = "demo-catalog")]
+    pub fn demo() -> Supermarket<DemoCatalog<M>, M> {
+        Supermarket::new((
+            FlatPrice { product: 'A', cost: M::from_cents(2000) },
+            BundlePrice {
+                product: 'B',
+                lone_cost: M::from_cents(5000),
+                bundle_size: 5,
+                bundle_cost: M::from_cents(15000)
+            },
+            FlatPrice { product: 'C', cost: M::from_cents(3000) }
+        ))
+    }
+}
+
+impl<R: RuleList<M>, M: Money> Market<M> for Supermarket<R, M> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        Ok(self.try_checkout_str(items)?)
+    }
+}
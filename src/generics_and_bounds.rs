@@ -0,0 +1,208 @@
+//! Generics and trait bounds, compared with Java's bounded types and
+//! wildcards.
+//!
+//! Every market elsewhere in this crate sells products named by a `char`.
+//! `GenericMarket` sells items of any type `I`: `char`s, `conversions::Sku`s,
+//! barcodes as `&str`s or numeric PLU codes. All it asks of `I` is what it
+//! needs to count them, which it states as bounds: `I: Eq + Hash`, to be a
+//! `HashMap` key. Java would write `<I>` and trust that `equals` and
+//! `hashCode` had been overridden; here a type without them is refused at
+//! compile time.
+//!
+//! Java's generics need wildcards because of *variance*: a
+//! `List<FlatPrice>` is not a `List<PricingRule>`, so a method wanting
+//! either must take `List<? extends PricingRule>`, and one feeding values
+//! to a consumer takes a `Consumer<? super Money>`. Rust's generic types
+//! have no subtypes to be variant over, so there is nothing to wildcard.
+//! Where Java abstracts over the argument's type with `? extends`, Rust
+//! makes the function generic instead, and bounds the type parameter:
+//!
+//! | Java                                                       | Rust                                                |
+//! |------------------------------------------------------------|-----------------------------------------------------|
+//! | `void addAll(Collection<? extends Rule<I, M>> rules)`      | `fn with_rules<R: Rule<I, M>>(rules: impl IntoIterator<Item = R>)` |
+//! | `Map<I, Long> count(Iterable<? extends I> items)`          | `fn count<I: Eq + Hash>(items: impl IntoIterator<Item = I>)` |
+//! | `void each(Iterable<I> items, BiConsumer<? super I, ? super M> sink)` | `fn each(&self, items, sink: impl FnMut(&I, M))` |
+//! | `<T extends Comparable<? super T>> T min(Collection<? extends T> c)` | `fn cheapest<M: PartialOrd>(totals: impl IntoIterator<Item = M>)` |
+//!
+//! That last one is `Collections.min`, perhaps the best-known wildcard
+//! puzzle in Java. The Rust bound says only what `cheapest` does with its
+//! argument, compare it, with nothing about the direction of subtyping.
+//!
+//! The price of the Rust way is that a generic function is compiled once
+//! per type it is used with, while Java compiles it once and erases the
+//! types. The gain is that the types are never erased: `I` is still known
+//! inside `count`, and `HashMap<I, u64>` is a map of exactly that.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::reasonable_implementation::{CheckoutError, Market};
+
+/// A rule pricing items of type `I` in money `M`.
+///
+/// The trait puts no bounds on `I`: a rule that never looks an item up
+/// would not need them. Each impl adds the ones it uses.
+pub trait Rule<I, M: Money> {
+    fn price(&self, counts: &HashMap<I, u64>, policy: OverflowPolicy) -> Result<M, Overflow>;
+}
+
+/// `cost` for every `item`.
+///
+/// The struct has no bounds at all. Bounds on a struct must be repeated on
+/// every `impl` and every function mentioning it, so Rust code puts them
+/// only on the impls that need them, where a Java class states a bound
+/// such as `<I extends Comparable<I>>` once, on the class.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UnitPrice<I, M: Money = Cents> {
+    pub item: I,
+    pub cost: M
+}
+
+impl<I: Eq + Hash, M: Money> Rule<I, M> for UnitPrice<I, M> {
+    fn price(&self, counts: &HashMap<I, u64>, policy: OverflowPolicy) -> Result<M, Overflow> {
+        policy.times(self.cost, counts.get(&self.item).copied().unwrap_or(0))
+    }
+}
+
+/// `each` apiece, or `cost` for every `size` of `item`. A `size` of zero
+/// prices every item at `each`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MultiBuy<I, M: Money = Cents> {
+    pub item: I,
+    pub each: M,
+    pub size: u64,
+    pub cost: M
+}
+
+impl<I: Eq + Hash, M: Money> Rule<I, M> for MultiBuy<I, M> {
+    fn price(&self, counts: &HashMap<I, u64>, policy: OverflowPolicy) -> Result<M, Overflow> {
+        let count = counts.get(&self.item).copied().unwrap_or(0);
+        let (bundles, singles) = match self.size {
+            0 => (0, count),
+            size => (count / size, count % size)
+        };
+        policy.plus(policy.times(self.cost, bundles)?, policy.times(self.each, singles)?)
+    }
+}
+
+/// How many of each item there are in `items`.
+///
+/// `impl IntoIterator<Item = I>` accepts a `Vec<I>`, an array, a
+/// `str::chars()` or any other source of `I`s, as an `Iterable<? extends I>`
+/// would. Unlike Java's, it can hand over the items themselves rather than
+/// references to them, so the map owns its keys.
+pub fn count<I: Eq + Hash>(items: impl IntoIterator<Item = I>) -> HashMap<I, u64> {
+    let mut counts = HashMap::new();
+    for item in items {
+        *counts.entry(item).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// The least of `totals`, or `None` if there are none.
+///
+/// `Money` is only `PartialOrd`, as a `Decimal` could in principle be
+/// incomparable; the bound here asks for no more than that, and keeps the
+/// first of any totals that do not compare.
+pub fn cheapest<M: PartialOrd>(totals: impl IntoIterator<Item = M>) -> Option<M> {
+    totals.into_iter().fold(None, |least, total| match least {
+        Some(least) if total.partial_cmp(&least) != Some(Ordering::Less) => Some(least),
+        _ => Some(total)
+    })
+}
+
+/// A market selling items of any type `I`, priced in `M`.
+///
+/// The rules are trait objects, so rules of different types can share the
+/// `Vec`, as they do in `reasonable_implementation`. `Send + Sync` keeps the
+/// market shareable between threads.
+pub struct GenericMarket<I, M: Money = Cents> {
+    rules: Vec<Box<dyn Rule<I, M> + Send + Sync>>,
+    policy: OverflowPolicy
+}
+
+impl<I, M: Money> GenericMarket<I, M> {
+
+    /// A market with no rules, charging nothing for anything.
+    pub fn new() -> GenericMarket<I, M> {
+        GenericMarket { rules: Vec::new(), policy: OverflowPolicy::Checked }
+    }
+
+    /// Adds one rule, of any type pricing `I`s in `M`.
+    ///
+    /// The `'static` bound says the rule borrows nothing that could be
+    /// freed while the market holds it.
+    pub fn with_rule(mut self, rule: impl Rule<I, M> + Send + Sync + 'static) -> GenericMarket<I, M> {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Adds every rule in `rules`, all of one type `R`: Java's
+    /// `addAll(Collection<? extends Rule<I, M>>)`.
+    ///
+    /// A `Vec<UnitPrice<I, M>>` is not a `Vec<Box<dyn Rule<I, M>>>`, in Rust
+    /// or in Java, but the function needs neither: it is generic over `R`,
+    /// and boxes each rule as it goes.
+    pub fn with_rules<R>(mut self, rules: impl IntoIterator<Item = R>) -> GenericMarket<I, M>
+        where R: Rule<I, M> + Send + Sync + 'static {
+        for rule in rules {
+            self.rules.push(Box::new(rule));
+        }
+        self
+    }
+}
+
+impl<I, M: Money> Default for GenericMarket<I, M> {
+    fn default() -> GenericMarket<I, M> {
+        GenericMarket::new()
+    }
+}
+
+/// Pricing needs to count, so only this block asks for `I: Eq + Hash`. A
+/// market of items without them can be built, but has no `try_checkout`.
+impl<I: Eq + Hash, M: Money> GenericMarket<I, M> {
+
+    /// The price of `items`, or `Overflow` if it is too large.
+    pub fn try_checkout(&self, items: impl IntoIterator<Item = I>) -> Result<M, Overflow> {
+        let counts = count(items);
+        self.rules.iter().try_fold(M::zero(), |total, rule| {
+            self.policy.plus(total, rule.price(&counts, self.policy)?)
+        })
+    }
+
+    /// Calls `sink` with every item of `items` and the price the market
+    /// would charge for it alone.
+    ///
+    /// In Java `sink` would be a `BiConsumer<? super I, ? super M>`, so that
+    /// a `BiConsumer<Object, Object>` could be passed too. A Rust closure is
+    /// generic over what it does with its arguments, not over their types,
+    /// so there is nothing to widen: any closure accepting an `&I` and an
+    /// `M` will do.
+    ///
+    /// Pricing one item on its own means counting a copy of it, so this
+    /// method alone also needs `I: Clone`. A `where` clause on a method adds
+    /// to the bounds of its `impl` block, for that method only.
+    pub fn each(&self, items: impl IntoIterator<Item = I>,
+                mut sink: impl FnMut(&I, M)) -> Result<(), Overflow>
+        where I: Clone {
+        for item in items {
+            let price = self.try_checkout(std::iter::once(item.clone()))?;
+            sink(&item, price);
+        }
+        Ok(())
+    }
+}
+
+/// `char` items can be sold through the `Market` trait, whose carts are
+/// `String`s.
+///
+/// This impl exists only for `GenericMarket<char, M>`. Java cannot do that:
+/// a class implements an interface for every type argument or for none.
+/// Here a `GenericMarket<u32>` is still a market, with `try_checkout`, but not
+/// a `Market`.
+impl<M: Money> Market<M> for GenericMarket<char, M> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        Ok(self.try_checkout(items.chars())?)
+    }
+}
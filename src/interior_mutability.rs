@@ -0,0 +1,234 @@
+//! Interior mutability: changing a value through a shared reference, and
+//! only where it was planned for.
+//!
+//! A Java object can be changed through any reference to it, unless every
+//! field is `final`, and even then whatever the fields point to can be
+//! changed. A rule keeping statistics would simply increment a field inside
+//! `price`, and nothing in the language would say whether that is safe with
+//! two checkouts on two threads. It is not, unless the field is an
+//! `AtomicLong` or the method `synchronized`, which the author has to
+//! remember and the compiler does not check.
+//!
+//! In Rust, `&self` is a shared reference, and a shared reference forbids
+//! changes: `self.carts += 1` in `price` does not compile. Making `price`
+//! take `&mut self` would allow it, and would also stop a market pricing
+//! two carts at once, and demand exclusive access to read a rule at all.
+//! Rust's answer is to make mutability part of a *field's type*, not of
+//! the reference. A few types may be changed through `&`, each with a
+//! guarantee of its own:
+//!
+//! * `Cell<T>` holds a `Copy` value, read with `get` and replaced with
+//!   `set`. No reference to the inside is ever handed out, so no borrow can
+//!   be invalidated, and there is nothing to check at runtime. A `Cell` is
+//!   not `Sync`, so the compiler will not let two threads share one.
+//! * `AtomicU64` and its siblings are `Cell`s made for threads, Java's
+//!   `AtomicLong` under another name. They are `Sync`.
+//! * `RefCell<T>` and `Mutex<T>`, seen in `shared_rules_implementation`,
+//!   hand out references to a value of any type, and check at runtime, or
+//!   wait, so that only one is mutable at a time.
+//!
+//! `CellCountedRule` and `CountedRule` below both wrap any rule and count
+//! how often it was used, from `&self`. The first, with `Cell`s, fits a
+//! single thread, such as a `lifetimes_explained::SliceMarket` built for
+//! one request. Putting it into a `Supermarket`, which prices carts on any
+//! number of threads, does not compile:
+//! `tests/ui/cell_counted_rule_not_sync.rs` shows the error. The second,
+//! with atomics, goes anywhere. Java would have accepted either in either
+//! place, and the first would have miscounted in production.
+//!
+//! Neither wrapper makes the rule itself mutable: the counts are the only
+//! fields that can change, and their types say so.
+
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::core::counting::Counts;
+use crate::core::money::{Money, OverflowPolicy, Overflow};
+use crate::core::rules::{Describable, PricingRule};
+use crate::core::lint::LintWarning;
+use crate::core::plan::RuleNode;
+
+/// How often a rule was used.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Usage {
+    /// Carts holding at least one of the rule's products.
+    pub carts: u64,
+    /// The rule's products across those carts.
+    pub items: u64
+}
+
+/// How many of `rule`'s products are in `counts`.
+fn items_for<M: Money>(rule: &impl PricingRule<M>, counts: &Counts) -> u64 {
+    counts.iter().filter(|&(&product, _)| rule.applies_to(product)).map(|(_, &count)| count).sum()
+}
+
+/// A rule counting its uses in `Cell`s, for a single thread.
+///
+/// ```
+/// use rust_for_java_devs::core::rules::FlatPrice;
+/// use rust_for_java_devs::interior_mutability::{CellCountedRule, Usage};
+/// use rust_for_java_devs::lifetimes_explained::SliceMarket;
+/// use rust_for_java_devs::money::Cents;
+///
+/// let rules = [CellCountedRule::new(FlatPrice::new('A', Cents(2000)).unwrap())];
+/// let market = SliceMarket::new(&rules);
+/// assert_eq!(Ok(Cents(4000)), market.try_checkout("AA"));
+/// assert_eq!(Usage { carts: 1, items: 2 }, rules[0].usage());
+/// ```
+pub struct CellCountedRule<R> {
+    rule: R,
+    carts: Cell<u64>,
+    items: Cell<u64>
+}
+
+impl<R> CellCountedRule<R> {
+
+    /// Counts the uses of `rule`, starting from none.
+    pub fn new(rule: R) -> CellCountedRule<R> {
+        CellCountedRule { rule, carts: Cell::new(0), items: Cell::new(0) }
+    }
+
+    /// How often the rule has been used so far.
+    pub fn usage(&self) -> Usage {
+        Usage { carts: self.carts.get(), items: self.items.get() }
+    }
+}
+
+impl<R: Describable> Describable for CellCountedRule<R> {
+    fn describe(&self) -> String {
+        self.rule.describe()
+    }
+}
+
+impl<M: Money, R: PricingRule<M>> PricingRule<M> for CellCountedRule<R> {
+    fn price(&self, character_counts: &Counts, policy: OverflowPolicy) -> Result<M, Overflow> {
+        let items = items_for(&self.rule, character_counts);
+        if items > 0 {
+            // `&self`, and yet the counts change: `Cell::set` needs only a
+            // shared reference. Wrapping on overflow, after 2^64 items, is
+            // as good as never.
+            self.carts.set(self.carts.get().wrapping_add(1));
+            self.items.set(self.items.get().wrapping_add(items));
+        }
+        self.rule.price(character_counts, policy)
+    }
+
+    fn products(&self) -> Vec<char> {
+        self.rule.products()
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        self.rule.applies_to(product)
+    }
+
+    fn lint(&self) -> Vec<LintWarning> {
+        self.rule.lint()
+    }
+
+    fn lot_sizes(&self, product: char) -> Vec<u64> {
+        self.rule.lot_sizes(product)
+    }
+
+    /// Explaining a price, as `Supermarket::receipt` does after checkout,
+    /// is not a use, so it is passed on uncounted.
+    fn plan(&self, character_counts: &Counts, policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        self.rule.plan(character_counts, policy)
+    }
+
+    fn list_price(&self, character_counts: &Counts, policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.rule.list_price(character_counts, policy)
+    }
+}
+
+/// The counts behind a `CountedRule`, which may be read while the rule
+/// itself is boxed away in a market.
+#[derive(Debug, Default)]
+pub struct UsageCounter {
+    carts: AtomicU64,
+    items: AtomicU64
+}
+
+impl UsageCounter {
+
+    /// How often the rule has been used so far.
+    ///
+    /// The two counts are read one after the other, so a cart being priced
+    /// meanwhile may show up in one and not yet the other. A consistent
+    /// pair would need a `Mutex<Usage>` instead, as it would need
+    /// `synchronized` in Java.
+    pub fn usage(&self) -> Usage {
+        Usage { carts: self.carts.load(Ordering::Relaxed), items: self.items.load(Ordering::Relaxed) }
+    }
+
+    /// `Relaxed` orders nothing but the count itself, which is all a
+    /// statistic needs; `AtomicLong.incrementAndGet` always pays for the
+    /// strongest ordering, `SeqCst`.
+    fn record(&self, items: u64) {
+        self.carts.fetch_add(1, Ordering::Relaxed);
+        self.items.fetch_add(items, Ordering::Relaxed);
+    }
+}
+
+/// A rule counting its uses atomically, for any number of threads.
+///
+/// The counts live behind an `Arc`, so `counter` can hand out a handle to
+/// them before the rule is boxed and given to a `Supermarket`, which would
+/// otherwise keep them out of reach.
+pub struct CountedRule<R> {
+    rule: R,
+    counter: Arc<UsageCounter>
+}
+
+impl<R> CountedRule<R> {
+
+    /// Counts the uses of `rule`, starting from none.
+    pub fn new(rule: R) -> CountedRule<R> {
+        CountedRule { rule, counter: Arc::new(UsageCounter::default()) }
+    }
+
+    /// A handle to the counts, which stays readable after the rule has
+    /// been moved into a market.
+    pub fn counter(&self) -> Arc<UsageCounter> {
+        Arc::clone(&self.counter)
+    }
+}
+
+impl<R: Describable> Describable for CountedRule<R> {
+    fn describe(&self) -> String {
+        self.rule.describe()
+    }
+}
+
+impl<M: Money, R: PricingRule<M>> PricingRule<M> for CountedRule<R> {
+    fn price(&self, character_counts: &Counts, policy: OverflowPolicy) -> Result<M, Overflow> {
+        let items = items_for(&self.rule, character_counts);
+        if items > 0 {
+            self.counter.record(items);
+        }
+        self.rule.price(character_counts, policy)
+    }
+
+    fn products(&self) -> Vec<char> {
+        self.rule.products()
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        self.rule.applies_to(product)
+    }
+
+    fn lint(&self) -> Vec<LintWarning> {
+        self.rule.lint()
+    }
+
+    fn lot_sizes(&self, product: char) -> Vec<u64> {
+        self.rule.lot_sizes(product)
+    }
+
+    fn plan(&self, character_counts: &Counts, policy: OverflowPolicy) -> Result<RuleNode<M>, Overflow> {
+        self.rule.plan(character_counts, policy)
+    }
+
+    fn list_price(&self, character_counts: &Counts, policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.rule.list_price(character_counts, policy)
+    }
+}
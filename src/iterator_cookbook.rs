@@ -0,0 +1,158 @@
+//! Checkout totals computed with iterators, recipe by recipe, next to the
+//! Java Streams code each one corresponds to.
+//!
+//! Rust's iterators and Java's streams are close relatives: both are lazy
+//! pipelines of `map`, `filter` and friends, run only when something at the
+//! end, a `sum` or a `collect`, asks for the values. The main differences:
+//!
+//! * An iterator is a plain value with a `next` method, and the pipeline is
+//!   compiled into one loop, as if written by hand, with no objects or
+//!   virtual calls in between.
+//! * There are no separate `IntStream` or `LongStream`; `sum` works on any
+//!   type implementing `Sum`.
+//! * Errors travel through a pipeline as `Result`s, where a lambda in a
+//!   stream cannot throw a checked exception at all.
+//!
+//! The recipes start from unit prices, `prices`, and grow to the full
+//! rules a `Supermarket` applies. Products without a price are charged
+//! nothing, as in the rest of the crate.
+
+use std::collections::BTreeMap;
+use crate::core::counting::Counts;
+use crate::core::money::{Cents, Money, Overflow, OverflowPolicy};
+use crate::core::rules::PricingRule;
+
+/// The unit price of `product`, if it has one.
+fn unit_price(prices: &[(char, Cents)], product: char) -> Option<Cents> {
+    prices.iter().find(|&&(p, _)| p == product).map(|&(_, price)| price)
+}
+
+/// The starting point: a `for` loop, with no iterator adapters at all.
+///
+/// ```text
+/// long total = 0;
+/// for (char c : cart.toCharArray()) {
+///     Long price = prices.get(c);
+///     if (price != null) total += price;
+/// }
+/// ```
+pub fn with_loop(prices: &[(char, Cents)], cart: &str) -> Cents {
+    let mut total = 0;
+    for product in cart.chars() {
+        if let Some(Cents(price)) = unit_price(prices, product) {
+            total += price;
+        }
+    }
+    Cents(total)
+}
+
+/// `filter_map` and `sum`: look each item up, drop those with no price, add
+/// up the rest.
+///
+/// ```text
+/// cart.chars()
+///     .mapToObj(c -> prices.get((char) c))
+///     .filter(Objects::nonNull)
+///     .mapToLong(Long::longValue)
+///     .sum();
+/// ```
+///
+/// `filter_map` is the `map` and `filter(Objects::nonNull)` in one step: the
+/// closure returns an `Option`, and the `None`s are dropped.
+pub fn with_filter_map(prices: &[(char, Cents)], cart: &str) -> Cents {
+    let total: i64 = cart.chars()
+        .filter_map(|product| unit_price(prices, product))
+        .map(|Cents(price)| price)
+        .sum();
+    Cents(total)
+}
+
+/// `fold`: the same, but adding up the `Cents` themselves, starting from
+/// zero.
+///
+/// ```text
+/// ....reduce(Money.ZERO, Money::plus);
+/// ```
+///
+/// `fold` is `Stream.reduce(identity, accumulator)`. Unlike `reduce`, it may
+/// produce a type other than the items', which Java needs the three
+/// argument `reduce` for.
+pub fn with_fold(prices: &[(char, Cents)], cart: &str) -> Cents {
+    cart.chars()
+        .filter_map(|product| unit_price(prices, product))
+        .fold(Cents::zero(), |total, price| total.saturating_plus(price))
+}
+
+/// `try_fold`: a fold that stops at the first error, here an overflow.
+///
+/// There is no Java equivalent. `Math.addExact` inside `reduce` can only
+/// throw an unchecked `ArithmeticException`; to return an error as a value,
+/// the stream has to be given up for a loop. `try_fold` returns early with
+/// the first `Err` the closure gives, without looking at the rest.
+pub fn with_try_fold(prices: &[(char, Cents)], cart: &str) -> Result<Cents, Overflow> {
+    cart.chars()
+        .filter_map(|product| unit_price(prices, product))
+        .try_fold(Cents::zero(), |total, price| total.checked_plus(price).ok_or(Overflow))
+}
+
+/// Counting the items of each product: a `groupingBy`.
+///
+/// ```text
+/// Map<Character, Long> counts = cart.chars()
+///     .mapToObj(c -> (char) c)
+///     .collect(Collectors.groupingBy(Function.identity(), TreeMap::new,
+///                                    Collectors.counting()));
+/// ```
+///
+/// The standard library has no `groupingBy`, but a `fold` into a map does
+/// the same, with the map's `entry` API standing in for `merge`:
+/// `counts.merge(c, 1L, Long::sum)`.
+pub fn group_counts(cart: &str) -> Counts {
+    cart.chars().fold(BTreeMap::new(), |mut counts, product| {
+        *counts.entry(product).or_insert(0) += 1;
+        counts
+    })
+}
+
+/// Unit prices applied per product, after grouping: the shape rules with
+/// bundles need, as they price a count rather than single items.
+///
+/// ```text
+/// counts.entrySet().stream()
+///     .filter(e -> prices.containsKey(e.getKey()))
+///     .mapToLong(e -> prices.get(e.getKey()) * e.getValue())
+///     .sum();
+/// ```
+///
+/// `map` turns each group into a `Result`, and `sum` over `Result`s adds up
+/// the values inside, or stops at the first `Err` and returns that: a
+/// `Result` can be summed whenever what it holds can be.
+pub fn with_groups(prices: &[(char, Cents)], cart: &str) -> Result<Cents, Overflow> {
+    let total: Result<i64, Overflow> = group_counts(cart).into_iter()
+        .filter_map(|(product, count)| unit_price(prices, product).map(|price| (price, count)))
+        .map(|(price, count)| price.checked_times(count).map(|Cents(amount)| amount).ok_or(Overflow))
+        .sum();
+    // `sum` on `i64`s panics in a debug build if it overflows, and wraps in
+    // a release one; the prices here are far too small for that.
+    total.map(Cents)
+}
+
+/// The whole checkout: group the items, then ask every rule for its price
+/// and add those up, as `Supermarket` does.
+///
+/// ```text
+/// Map<Character, Long> counts = groupCounts(cart);
+/// rules.stream().mapToLong(rule -> rule.price(counts)).sum();
+/// ```
+///
+/// The rules are generic, `R`, with `?Sized` so that they may also be
+/// trait objects, such as those `Supermarket::rules` hands out.
+pub fn with_rules<'r, R, I>(rules: I, cart: &str) -> Result<Cents, Overflow>
+    where R: PricingRule<Cents> + ?Sized + 'r,
+          I: IntoIterator<Item = &'r R>
+{
+    let counts = group_counts(cart);
+    rules.into_iter()
+        .map(|rule| rule.price(&counts, OverflowPolicy::Checked))
+        .try_fold(Cents::zero(), |total, price| OverflowPolicy::Checked.plus(total, price?))
+}
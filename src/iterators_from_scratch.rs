@@ -0,0 +1,192 @@
+//! Writing an `Iterator` by hand, compared with Java's `Iterator`,
+//! `Iterable` and `Spliterator`.
+//!
+//! Java's `Iterator` has two methods, `hasNext()` and `next()`, which must
+//! be called in the right order, and `next()` throws
+//! `NoSuchElementException` past the end. Rust's has one required method,
+//! `next`, returning an `Option`: `Some(item)` while there are items, then
+//! `None`. Asking and taking are one call, so they cannot disagree.
+//!
+//! Everything else is provided. Implementing `next` alone gives a type
+//! every adapter in the standard library, `map`, `filter`, `zip`, `rev`
+//! and the rest, where a Java iterator has to be wrapped with
+//! `StreamSupport.stream(spliterator, false)` before it can join a stream.
+//! A few provided methods are worth overriding when the type knows better:
+//!
+//! | Java                                   | Rust                               |
+//! |----------------------------------------|------------------------------------|
+//! | `hasNext()` and `next()`               | `next()`                           |
+//! | `Iterable.iterator()`, for `for (x : xs)` | `IntoIterator::into_iter`, for `for x in xs` |
+//! | `Spliterator.estimateSize()`           | `size_hint()`                      |
+//! | `Spliterator.SIZED`                    | `ExactSizeIterator`, and `len()`   |
+//! | `Spliterator.ORDERED`                  | always: iterators are ordered      |
+//! | `ListIterator.previous()`, roughly     | `DoubleEndedIterator::next_back()` |
+//! | `Spliterator.trySplit()`, for parallel streams | no counterpart in `std`; the `rayon` crate |
+//! | "keeps returning `false` from `hasNext()`" | `FusedIterator`                |
+//!
+//! `ReceiptLines` walks a receipt's lines from both ends, as `iter` on a
+//! slice does. `BundleChunks` produces the chunks a number of items falls
+//! into, bundles first and then the remainder, and `price_by_chunks`
+//! prices a bundle offer by adding up chunk prices, as a shopper would.
+
+use std::iter::FusedIterator;
+use crate::core::money::{Money, Overflow, OverflowPolicy};
+use crate::receipt::{Receipt, ReceiptLine};
+
+/// The lines of a `Receipt`, borrowed from it, front to back or back to
+/// front. Made by `Receipt::iter`, or by `for line in &receipt`.
+///
+/// The iterator holds the part of the lines not yet visited, and shrinks
+/// it from either end; it never copies a line.
+#[derive(Clone, Debug)]
+pub struct ReceiptLines<'r, M: Money> {
+    remaining: &'r [ReceiptLine<M>]
+}
+
+impl<'r, M: Money> ReceiptLines<'r, M> {
+
+    /// The lines of `receipt`, from the first.
+    pub fn new(receipt: &'r Receipt<M>) -> ReceiptLines<'r, M> {
+        ReceiptLines { remaining: &receipt.lines }
+    }
+}
+
+impl<'r, M: Money> Iterator for ReceiptLines<'r, M> {
+    /// What `next` returns, inside a `Some`: `Iterator<ReceiptLine>` in
+    /// Java, written as an associated type rather than a type parameter, as
+    /// a type can only be one kind of iterator.
+    type Item = &'r ReceiptLine<M>;
+
+    fn next(&mut self) -> Option<&'r ReceiptLine<M>> {
+        // `split_first` returns the first line and the rest, or `None` if
+        // there are no lines left, and `?` passes that `None` on.
+        let (first, rest) = self.remaining.split_first()?;
+        self.remaining = rest;
+        Some(first)
+    }
+
+    /// The exact number left, as both bounds. The provided version says
+    /// "anywhere from zero to unknown", which makes `collect` guess how
+    /// much to allocate.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining.len(), Some(self.remaining.len()))
+    }
+}
+
+impl<M: Money> DoubleEndedIterator for ReceiptLines<'_, M> {
+    /// Taking from the back, which is what `rev` calls.
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (last, rest) = self.remaining.split_last()?;
+        self.remaining = rest;
+        Some(last)
+    }
+}
+
+/// A promise that `size_hint` is exact, and so `len` is right.
+impl<M: Money> ExactSizeIterator for ReceiptLines<'_, M> {}
+
+/// A promise that after the first `None`, every `next` is `None` too,
+/// which lets `fuse` skip its own bookkeeping.
+impl<M: Money> FusedIterator for ReceiptLines<'_, M> {}
+
+/// The sizes of the chunks `count` items fall into, in bundles of
+/// `bundle_size`: as many full bundles as they make, then whatever is
+/// left over, if anything.
+///
+/// ```
+/// use rust_for_java_devs::iterators_from_scratch::BundleChunks;
+///
+/// let chunks: Vec<u64> = BundleChunks::new(12, 5).collect();
+/// assert_eq!(vec!(5, 5, 2), chunks);
+/// ```
+///
+/// The iterator is a few counters, not a list, so it takes the same memory
+/// for a dozen items as for a billion; `nth` and `count` are overridden to
+/// jump straight to the answer rather than step through every chunk.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BundleChunks {
+    bundle_size: u64,
+    bundles: u64,
+    remainder: u64
+}
+
+impl BundleChunks {
+
+    /// The chunks of `count` items. A `bundle_size` of zero bundles
+    /// nothing, and puts every item in one chunk of leftovers.
+    pub fn new(count: u64, bundle_size: u64) -> BundleChunks {
+        match count.checked_div(bundle_size) {
+            Some(bundles) => BundleChunks { bundle_size, bundles, remainder: count % bundle_size },
+            None => BundleChunks { bundle_size, bundles: 0, remainder: count }
+        }
+    }
+
+    /// How many chunks are left, which may be more than a `usize` holds on
+    /// a 32-bit target. That is why this type is not `ExactSizeIterator`,
+    /// whose `len` is a `usize`.
+    fn remaining(&self) -> u64 {
+        self.bundles + u64::from(self.remainder > 0)
+    }
+}
+
+impl Iterator for BundleChunks {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.bundles > 0 {
+            self.bundles -= 1;
+            Some(self.bundle_size)
+        } else if self.remainder > 0 {
+            // `take` leaves zero behind, so the next call finds nothing.
+            Some(std::mem::take(&mut self.remainder))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match usize::try_from(self.remaining()) {
+            Ok(remaining) => (remaining, Some(remaining)),
+            Err(_) => (usize::MAX, None)
+        }
+    }
+
+    /// Skips `n` chunks at once. The provided version calls `next` `n`
+    /// times, which for a billion bundles is a billion calls; this is what
+    /// `skip` and `nth` rely on.
+    fn nth(&mut self, n: usize) -> Option<u64> {
+        let n = n as u64;
+        if n < self.bundles {
+            self.bundles -= n;
+        } else {
+            let past_bundles = n - self.bundles;
+            self.bundles = 0;
+            if past_bundles > 0 {
+                self.remainder = 0;
+            }
+        }
+        self.next()
+    }
+
+    /// Counts what is left without walking through it.
+    fn count(self) -> usize {
+        self.remaining() as usize
+    }
+}
+
+impl FusedIterator for BundleChunks {}
+
+/// What `count` items cost, with every full bundle of `bundle_size` at
+/// `bundle_cost` and every other item at `lone_cost`: a price worked out
+/// chunk by chunk, as a shopper would add it up.
+///
+/// This is the price `core::rules::BundlePrice` charges. That rule divides
+/// instead, taking the same time for any count, as a cart may hold more
+/// bundles than anybody would want to step through; the chunks are for
+/// showing the adapters on an iterator of our own.
+pub fn price_by_chunks<M: Money>(count: u64, lone_cost: M, bundle_size: u64, bundle_cost: M,
+                                 policy: OverflowPolicy) -> Result<M, Overflow> {
+    BundleChunks::new(count, bundle_size)
+        .map(|chunk| if chunk == bundle_size { Ok(bundle_cost) } else { policy.times(lone_cost, chunk) })
+        .try_fold(M::zero(), |total, price| policy.plus(total, price?))
+}
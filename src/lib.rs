@@ -1,3 +1,242 @@
+//! Lines beginning with `//!` document the enclosing item, here the whole
+//! crate, while `///` documents the item that follows.
+//!
+//! The attribute below is applied to the whole crate. `cfg_attr` applies
+//! `no_std` only when its condition holds, so building with
+//! `--no-default-features` (and therefore without the `std` feature) opts
+//! out of the standard library entirely.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// The portable pricing engine lives in its own crate, `pricing-core`, in
+/// the `pricing-core` directory of this Cargo workspace. Re-exporting it as
+/// `core` makes it part of this crate's public API, as if it were one of
+/// our own modules.
+///
+/// A workspace groups several crates that are built together, somewhat like
+/// a multi-module Maven project. Keeping the command line tool, the C
+/// bindings and the network server in crates of their own means that code
+/// depending on this library never compiles their dependencies.
+///
+/// This shares its name with the built-in `core` crate. Within this crate
+/// we always reach it as `crate::core`, which is unambiguous.
+pub use pricing_core as core;
+
+/// `pub use` re-exports an item under a new path, so `money` may be reached
+/// as `rust_for_java_devs::money` as well as `rust_for_java_devs::core::money`.
+pub use crate::core::money;
+
+/// Facades gathering the crate's main items by topic, whichever module
+/// defines them: `market`, `rules`, `money` above and `receipt` below, and a
+/// `prelude` of the names most programs need. See `modules_and_visibility`.
+#[cfg(feature = "std")]
+pub mod market;
+#[cfg(feature = "std")]
+pub mod rules;
+#[cfg(feature = "std")]
+pub mod prelude;
+
+/// Derive macros implementing `PricingRule` for a struct, given which of its
+/// fields holds the product and which the prices. Only available with the
+/// `derive` feature.
+///
+/// ```
+/// use rust_for_java_devs::FlatRule;
+/// use rust_for_java_devs::money::Cents;
+/// use rust_for_java_devs::reasonable_implementation::{Market, Supermarket, BoxedRule};
+///
+/// #[derive(FlatRule)]
+/// struct Apples {
+///     #[rule(product)]
+///     code: char,
+///     #[rule(cost)]
+///     each: Cents,
+/// }
+///
+/// let apples = Box::new(Apples { code: 'A', each: Cents(40) }) as BoxedRule<Cents>;
+/// let market: Supermarket = Supermarket::with_rules(vec![apples]);
+/// assert_eq!(Ok(Cents(120)), market.checkout("AAA"));
+/// ```
+///
+/// A mistake in the markings is reported by the compiler, at the struct:
+///
+/// ```compile_fail
+/// use rust_for_java_devs::FlatRule;
+/// use rust_for_java_devs::money::Cents;
+///
+/// #[derive(FlatRule)]
+/// struct Apples {
+///     #[rule(product)]
+///     code: char,
+///     each: Cents, // one field must be marked `#[rule(cost)]`
+/// }
+/// ```
+///
+/// Derive macros live in a namespace of their own, so `FlatRule` could even
+/// share its name with a trait, as serde's `Serialize` does.
+#[cfg(feature = "derive")]
+pub use pricing_derive::{FlatRule, BundleRule};
+
+/// `#[cfg(feature = "std")]` includes the item that follows only when the
+/// `std` feature is enabled, which it is by default. Each of these modules
+/// relies on the standard library, if only for the `Vec`, `Box` and `String`
+/// it makes available everywhere without a `use`.
+#[cfg(feature = "std")]
+pub mod counting;
+#[cfg(feature = "std")]
+pub mod borrowed_rules_implementation;
+#[cfg(feature = "std")]
+pub mod enum_implementation;
+#[cfg(feature = "std")]
+pub mod generic_implementation;
+/// Markets sharing rules and takings through `Rc`, `RefCell`, `Arc` and
+/// `Mutex`, compared with Java's references.
+#[cfg(feature = "std")]
+pub mod shared_rules_implementation;
+/// The demo catalog as constant data, priced at compile time.
+#[cfg(feature = "demo-catalog")]
+pub mod const_catalog;
+/// Pricing rules loaded from shared libraries at runtime. Only compiled with
+/// the `plugins` feature.
+#[cfg(feature = "plugins")]
+pub mod plugins;
+/// Markets allocated wholesale in an arena, for rebuilding many at once.
+/// Only compiled with the `arena` feature.
+#[cfg(feature = "arena")]
+pub mod arena_implementation;
+/// Rules built by kind name, for catalogs kept as data.
+#[cfg(feature = "std")]
+pub mod registry;
+/// Prices looked up in a remote service at checkout. The HTTP clients need
+/// the `http` feature.
+#[cfg(feature = "std")]
+pub mod remote;
+/// Comparing two catalogs, for reviewing price changes.
+#[cfg(feature = "std")]
+pub mod catalog;
+/// Itemized receipts for priced carts.
+#[cfg(feature = "std")]
+pub mod receipt;
+/// `CheckoutCompleted` events, for message queues. Only compiled with the
+/// `events` feature.
+#[cfg(feature = "events")]
+pub mod events;
+/// A fallible checkout written with `Result` and again with panics, to
+/// compare them with Java's checked and unchecked exceptions.
+#[cfg(feature = "std")]
+pub mod error_handling_comparison;
+/// Handling an `Option` five ways, compared with `java.util.Optional`.
+#[cfg(feature = "std")]
+pub mod option_patterns;
+/// Checkout totals as iterator pipelines, compared with Java Streams.
+#[cfg(feature = "std")]
+pub mod iterator_cookbook;
+/// Pricing carts on several threads with channels and scoped threads,
+/// compared with Java's `ExecutorService`.
+#[cfg(feature = "std")]
+pub mod threads_and_channels;
+/// Moves, borrows and the borrow checker's errors, around a cart being
+/// filled.
+#[cfg(feature = "std")]
+pub mod ownership_and_borrowing;
+/// `SupermarketBuilder` set against Java's builders, with a typestate
+/// builder that cannot build a market without rules.
+#[cfg(feature = "std")]
+pub mod builder_comparison;
+/// Visitors over receipts, compared with Java's visitor pattern and with a
+/// plain `match`.
+#[cfg(feature = "std")]
+pub mod visitor_pattern;
+/// Cleanup in `Drop`, compared with `AutoCloseable` and finalizers.
+#[cfg(feature = "std")]
+pub mod drop_and_cleanup;
+/// `PartialEq`, `Eq`, `Hash` and `Ord`, compared with `equals`, `hashCode`
+/// and `compareTo`.
+#[cfg(feature = "std")]
+pub mod equality_and_hashing;
+/// `From`, `Into` and `TryFrom`, compared with constructors, `valueOf` and
+/// converters.
+#[cfg(feature = "std")]
+pub mod conversions;
+/// `Display` and `Debug`, compared with `toString`.
+#[cfg(feature = "std")]
+pub mod display_and_debug;
+/// Generic items and money with trait bounds, compared with wildcards.
+#[cfg(feature = "std")]
+pub mod generics_and_bounds;
+/// The `price_rules!` macro, and macros compared with reflection and
+/// annotation processing.
+#[cfg(feature = "std")]
+pub mod macros_and_metaprogramming;
+/// `async` and `.await`, compared with `CompletableFuture`. Only compiled
+/// with the `async` feature.
+#[cfg(feature = "async")]
+pub mod async_and_await;
+/// Match guards, `@` bindings, nested patterns and exhaustiveness,
+/// compared with `switch` over sealed interfaces.
+#[cfg(feature = "std")]
+pub mod pattern_matching;
+/// Serializing a catalog with serde, compared with Jackson. Only compiled
+/// with the `serde` feature.
+#[cfg(feature = "serde")]
+pub mod serde_and_jackson;
+/// `Copy` and `Clone`, deep and shallow, compared with `clone()` and
+/// defensive copies.
+#[cfg(feature = "std")]
+pub mod clone_and_copy;
+/// Nested modules, `pub(crate)`, `pub(super)` and re-exports, compared
+/// with Java's packages and imports.
+#[cfg(feature = "std")]
+pub mod modules_and_visibility;
+/// Three markets owning or borrowing their rules, showing when lifetimes
+/// are needed.
+#[cfg(feature = "std")]
+pub mod lifetimes_explained;
+/// Unit, integration and doc tests, tables of cases and fluent assertions,
+/// compared with JUnit 5 and AssertJ.
+#[cfg(feature = "std")]
+pub mod testing_idioms;
+/// Newtypes giving units to plain numbers, and the argument-swapping bugs
+/// they rule out.
+#[cfg(feature = "std")]
+pub mod newtypes_and_units;
+/// Promotions as an enum carrying data and as trait objects, compared with
+/// Java's sealed interfaces and records.
+#[cfg(feature = "std")]
+pub mod enums_and_sealed_classes;
+/// Rules keeping statistics through `&self` with `Cell` and `AtomicU64`,
+/// compared with Java's mutable fields.
+#[cfg(feature = "std")]
+pub mod interior_mutability;
+/// A checkout workflow whose steps the compiler keeps in order, with the
+/// cart's state in its type.
+#[cfg(feature = "std")]
+pub mod typestate_checkout;
+/// Pairs of abstractions and hand-written code, and what each compiles
+/// to, benchmarked in `benches/zero_cost.rs`.
+#[cfg(feature = "std")]
+pub mod zero_cost_abstractions;
+/// `Iterator` written by hand for receipt lines and bundle chunks,
+/// compared with Java's `Iterator` and `Spliterator`.
+#[cfg(feature = "std")]
+pub mod iterators_from_scratch;
+/// One failure to load settings, reported with a hand-written error, with
+/// `thiserror` and with `anyhow`. Only compiled with the `error-chains`
+/// feature.
+#[cfg(feature = "error-chains")]
+pub mod error_chains;
+/// A market that cannot cross threads, and the `Arc` and the `Send` and
+/// `Sync` bounds that let one that can.
+#[cfg(feature = "std")]
+pub mod send_and_sync;
+/// Random carts for property tests, for this crate's tests and anybody
+/// else's. Only compiled with the `testkit` feature.
+#[cfg(feature = "testkit")]
+pub mod testkit;
+/// Fake markets and rules for testing code built on this crate. Only
+/// compiled with the `test-support` feature.
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
 /// `mod` is similar to `package` in Java, it declares a module namespace.
 ///
 /// `pub` is short for "public" and means that this module may have contents
@@ -5,19 +244,74 @@
 ///
 /// This particular module represents what I would consider a reasonably
 /// balanced approach to the posed challenge.
+#[cfg(feature = "std")]
 pub mod reasonable_implementation {
 
     /// The `use` keyword is for importing functionality from other modules.
     /// Note the double-colon `::` style of separating out namespace trees.
     use std::vec::Vec;
-    /// Here we import multiple types from a module
-    use std::collections::hash_map::{HashMap, Entry};
-    use std::iter::AdditiveIterator;
+    use std::collections::{BTreeMap, BTreeSet};
+    /// Here we import multiple types from a module.
+    ///
+    /// Paths beginning with `crate::` start from the root of this crate, so
+    /// these reach into the portable `core` module.
+    use crate::core::money::{Money, Cents, Overflow, OverflowPolicy};
+    pub use crate::core::checkout::{CartLimits, CartDimension, CheckoutError, PromotionBudget, QuantityLimitPolicy,
+                                    NegativeTotalPolicy};
+    pub use crate::core::counting::{ScanPolicy, CountStrategy};
+    /// The pricing rules themselves, and the code adding up their prices,
+    /// live in `pricing-core/src/rules.rs`, so that they are available even
+    /// without the standard library. Continue reading there once you reach
+    /// the end of this module.
+    ///
+    /// `pub use` makes them available from this module too.
+    pub use crate::core::rules::{Describable, PricingRule, FlatPrice, BundlePrice, BestOfferPrice, SubstitutePrice,
+                                 RuleError};
+    pub use crate::core::observer::CheckoutObserver;
+    use crate::core::counting::{check_items, Counts};
+    use crate::core::rules::total;
+    use crate::conversions::Sku;
+    use crate::newtypes_and_units::Quantity;
+    pub use crate::core::plan::{PricingPlan, RuleNode, OfferNode};
+    use crate::core::plan::plan;
+    pub use crate::core::lint::LintWarning;
+    use crate::core::lint::lint_rules;
+
+    /// A module declared without a body, `mod name;`, is read from a file.
+    /// As this one is nested in `reasonable_implementation`, cargo looks for
+    /// it in `src/reasonable_implementation/pipeline.rs`.
+    pub mod pipeline;
+    use self::pipeline::{Pipeline, Ticket};
+    /// Only compiled with the `metrics` feature, in
+    /// `src/reasonable_implementation/telemetry.rs`.
+    #[cfg(feature = "metrics")]
+    pub mod telemetry;
+    /// `SupermarketBuilder`, in `src/reasonable_implementation/builder.rs`.
+    pub mod builder;
+    pub use self::builder::SupermarketBuilder;
+    /// Price tiers and the customers they are chosen by, in
+    /// `src/reasonable_implementation/tiers.rs`.
+    pub mod tiers;
+    pub use self::tiers::{CustomerContext, PriceTier};
+    /// Age-restricted products and `checkout_strict`, in
+    /// `src/reasonable_implementation/restricted.rs`.
+    pub mod restricted;
+    /// Per-product quantity limits, in
+    /// `src/reasonable_implementation/quantity_limits.rs`.
+    pub mod quantity_limits;
+    /// Discounts on whole carts, in
+    /// `src/reasonable_implementation/cart_rules.rs`.
+    pub mod cart_rules;
+    pub use self::cart_rules::{BoxedCartRule, CartRule, SpendThresholds, Voucher};
 
     /// A trait resembles a Java `interface` in so far as it is composed
     /// of a series of function signatures that may be implemented
     /// elsewhere by other types.
-    pub trait Market {
+    ///
+    /// The `<M: Money = Cents>` part makes the trait generic over the money
+    /// backend, like `Market<M extends Money>` in Java. The `= Cents` is a
+    /// default, so plain `Market` means `Market<Cents>`.
+    pub trait Market<M: Money = Cents> {
 
         /// Calculates the price of a sequence of items encoded in a string.
         /// 
@@ -25,20 +319,121 @@ pub mod reasonable_implementation {
         /// 
         /// * `self` - a reference to the implementing type's instance
         /// * `items` - a product list, where each item purchased is
-        /// assumed to be represented by a single character.
+        ///   assumed to be represented by a single character.
         ///
         /// # Returns
-        /// A value in whole dollars.
-        ///
-        /// The size of the integer chosen matches the default integer size
-        /// in the Java prompt, and correspondingly limits the absolute quantity
-        /// calculable. Luckily, a limit of around 2 billion is a
-        /// reasonable match for the domain. A still better match
-        /// would have been to use cents, but that would violate the
-        /// challenge prompt's constraints.
-        fn checkout(&self, items:String) -> i32;
+        /// The total price, expressed in the market's money backend, or why
+        /// the cart could not be priced.
+        ///
+        /// The challenge prompt asks for an `int` of whole dollars. That is
+        /// a poor fit for real prices, so by default we return `Cents`, and
+        /// callers who need fractional cents may pick an exact decimal type.
+        ///
+        /// The items are borrowed, as `&str`, so that a caller keeps its
+        /// `String`; and problems are returned, where a Java `int
+        /// checkout(String)` would throw whatever unchecked exception it
+        /// liked, with nothing in the signature to say so.
+        fn checkout(&self, items: &str) -> Result<M, CheckoutError>;
     }
 
+    /// `Market` is *object safe*: `checkout` takes `&self`, and has no type
+    /// parameters of its own and no `Self` in its signature, so the compiler
+    /// can build a vtable for it, and `Box<dyn Market>` and
+    /// `Arc<dyn Market + Send + Sync>` are types. That is how an
+    /// application injects its pricing engine, as it would a Java service
+    /// behind an interface:
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use rust_for_java_devs::prelude::*;
+    ///
+    /// struct Till {
+    ///     market: Arc<dyn Market + Send + Sync>
+    /// }
+    ///
+    /// let market: Supermarket = Supermarket::with_rules(vec![
+    ///     Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+    /// ]);
+    /// let till = Till { market: Arc::new(market) };
+    /// assert_eq!(Ok(Cents(4000)), till.market.checkout("AA"));
+    /// ```
+    ///
+    /// These impls make references and those smart pointers markets too,
+    /// for any market inside them, `dyn` or not, so generic code taking a
+    /// `K: Market` accepts all of them. `?Sized` admits `dyn Market`, whose
+    /// size is not known while compiling.
+    impl<M: Money, K: Market<M> + ?Sized> Market<M> for &K {
+        fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+            (**self).checkout(items)
+        }
+    }
+
+    impl<M: Money, K: Market<M> + ?Sized> Market<M> for Box<K> {
+        fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+            (**self).checkout(items)
+        }
+    }
+
+    impl<M: Money, K: Market<M> + ?Sized> Market<M> for std::sync::Arc<K> {
+        fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+            (**self).checkout(items)
+        }
+    }
+
+    /// A `Market` made from a closure, for stubbing one in a test or a demo
+    /// without building a catalog:
+    ///
+    /// ```
+    /// use rust_for_java_devs::market::FnMarket;
+    /// use rust_for_java_devs::prelude::*;
+    ///
+    /// let market = FnMarket(|items: &str| Cents(100 * items.len() as i64));
+    /// assert_eq!(Ok(Cents(300)), market.checkout("ABC"));
+    /// ```
+    ///
+    /// Java turns a lambda into any interface with a single abstract
+    /// method, so `Market market = items -> 240;` compiles as it stands. A
+    /// Rust closure only implements the `Fn` traits, and a blanket
+    /// `impl Market for F where F: Fn(&str) -> M` is not allowed next to the
+    /// impl for `&K` above: a reference to a closure is itself a closure, so
+    /// `&F` would be a `Market` twice over. Wrapping the closure in a type of
+    /// our own settles which impl applies.
+    ///
+    /// The closure answers with a plain amount, as the lambda would; a stub
+    /// that has to fail is better written as a type implementing `Market`.
+    #[derive(Clone, Copy, Debug)]
+    pub struct FnMarket<F>(pub F);
+
+    impl<M: Money, F: Fn(&str) -> M> Market<M> for FnMarket<F> {
+        fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+            Ok((self.0)(items))
+        }
+    }
+
+    /// A `type` alias gives a long type a shorter name, with no runtime
+    /// cost: `BoxedRule<M>` and the type it stands for are interchangeable.
+    /// Java has nothing similar; the closest is a subclass that adds
+    /// nothing, which is a new type rather than a new name.
+    ///
+    /// The parts of this type are explained on `Supermarket::price_rules`
+    /// below.
+    pub type BoxedRule<M> = Box<dyn PricingRule<M> + Send + Sync + 'static>;
+
+    /// Where a Supermarket keeps its rules.
+    ///
+    /// Most catalogs hold a handful of rules, so with the `smallvec` feature
+    /// the first eight are stored inside the Supermarket itself rather than
+    /// in a separate heap allocation; only a ninth rule moves them all to
+    /// the heap. A `SmallVec` dereferences to a slice just as a `Vec` does,
+    /// so no other code needs to know which one it is dealing with.
+    ///
+    /// Two definitions, with opposite `cfg` conditions, mean exactly one of
+    /// them exists in any build.
+    #[cfg(feature = "smallvec")]
+    type RuleStore<M> = smallvec::SmallVec<[BoxedRule<M>; 8]>;
+    #[cfg(not(feature = "smallvec"))]
+    type RuleStore<M> = Vec<BoxedRule<M>>;
+
     /// A `struct` is the datatype most similar to a Java class,
     /// as it is a data structure with named member fields
     /// and associated  associated functions.
@@ -50,30 +445,130 @@ pub mod reasonable_implementation {
     ///
     /// The Supermarket struct is the sole visible structure in this module.
     ///
-    /// The `'s` in angle brackets after the struct's name is a lifetime name.
-    /// Lifetimes are used to track how long given objects are valid and in-use.
-    /// Here, we define a new lifetime, named "s" for terseness.  This lifetime
-    /// will be used to help clarify to the compiler that other instances are
-    /// expected to stay alive during the same parts of the code as a
-    /// Supermarket instance.
-    pub struct Supermarket<'s> {
+    /// `M` is the money backend, defaulting to `Cents` just as in `Market`.
+    ///
+    /// An earlier version of this struct carried a lifetime parameter, `'s`,
+    /// so that its rules could borrow data owned elsewhere. That version now
+    /// lives on in the `borrowed_rules_implementation` module; this one owns
+    /// its rules outright, so code holding a Supermarket never has to
+    /// mention a lifetime.
+    pub struct Supermarket<M: Money = Cents> {
 
-        /// `price_rules` is a Vector of `Box<PricingRule>` instances. `Vector`
-        /// is a simple resizable linear collection, akin to a Java `List`.
+        /// `price_rules` is a Vector of `Box<dyn PricingRule>` instances.
+        /// `Vector` is a simple resizable linear collection, akin to a Java
+        /// `List`.
+        ///
+        /// The `dyn` keyword marks `PricingRule` as a *trait object*: the
+        /// concrete type of each rule is only known at runtime, and calls
+        /// are dispatched through a table of function pointers, exactly as
+        /// calls through a Java interface are.
         ///
         /// Like a Java list, Vec is generic, meaning it can be used with
         /// a user-specified particular type. Its element type is 
         /// specified using angle-bracket notation, e.g. Vec<ElementType>
         ///
-        /// The `+ 's` portion below makes sure the PricingRules held in this
-        /// Vec must be alive while this Supermarket is alive.
+        /// The `+ Send + Sync + 'static` portion below adds requirements on
+        /// every rule stored here:
+        ///
+        /// * `Send` - the rule may be moved to another thread.
+        /// * `Sync` - the rule may be used from several threads at once.
+        /// * `'static` - the rule borrows nothing that could disappear
+        ///   before the Supermarket does, i.e. it owns all of its data.
+        ///
+        /// In exchange, the Supermarket itself becomes `Send + Sync` and
+        /// free of lifetime parameters, so it can be stored anywhere and
+        /// shared between threads, as any immutable Java object could be.
         ///
         /// This `price_rules` member does not have a `pub` prefix, and thus
         /// is not visible to or editable by  code outside of this module.
         ///
         /// We gained access to Vec thanks to the `use std::vec::Vec;` import
         /// statement up at the top of this module.
-        price_rules: Vec<Box<PricingRule + 's>>
+        ///
+        /// Strictly speaking, the type is `RuleStore<M>`, which is exactly
+        /// `Vec<BoxedRule<M>>` unless the `smallvec` feature is on.
+        price_rules: RuleStore<M>,
+
+        /// What to do when a total grows too large for the money backend.
+        /// Storing the choice as an enum field, rather than as a comment or
+        /// a convention, means every Supermarket states its policy.
+        overflow_policy: OverflowPolicy,
+
+        /// How cart strings are read: strictly by default, or ignoring case
+        /// and separators.
+        scan_policy: ScanPolicy,
+
+        /// How the scanned items are tallied. Every strategy gives the same
+        /// counts, so this only affects speed.
+        count_strategy: CountStrategy,
+
+        /// The largest cart this Supermarket will price.
+        cart_limits: CartLimits,
+
+        /// The most this Supermarket gives away in promotions on one cart.
+        promotion_budget: PromotionBudget<M>,
+
+        /// The rules of each price tier, replacing retail rules for their
+        /// products. See `tiers`.
+        tier_rules: BTreeMap<PriceTier, Vec<BoxedRule<M>>>,
+
+        /// The products `checkout_strict` will only sell to a customer
+        /// whose age has been verified.
+        age_restricted: BTreeSet<char>,
+
+        /// The most of each limited product one cart may hold.
+        quantity_limits: BTreeMap<char, u64>,
+
+        /// What to do with a cart holding more than that.
+        quantity_limit_policy: QuantityLimitPolicy,
+
+        /// Discounts on the cart as a whole, applied in order once the
+        /// promotions are done. See `cart_rules`.
+        cart_rules: Vec<BoxedCartRule<M>>,
+
+        /// Whether a total may end up below zero.
+        negative_total_policy: NegativeTotalPolicy,
+
+        /// Everyone to notify as carts are priced. The same `Send + Sync`
+        /// requirements as on the rules keep the Supermarket shareable
+        /// between threads.
+        observers: Vec<Box<dyn CheckoutObserver<M> + Send + Sync + 'static>>,
+
+        /// The stages every cart passes through on its way to a total.
+        pipeline: Pipeline<M>
+    }
+
+    /// `Debug`, for `{:?}` and `{:#?}`, written by hand.
+    ///
+    /// `#[derive(Debug)]` would need every field to be `Debug`, and the
+    /// boxed rules and observers are trait objects, which are not. A
+    /// written impl picks what a developer wants to see: the rules by their
+    /// descriptions, the observers by their number, and the pipeline by its
+    /// stage names.
+    ///
+    /// There is deliberately no `Display`: a Supermarket has no single
+    /// form a shopper would want to read, where Java would inherit a
+    /// `toString` of `Supermarket@1b6d3586` from `Object` regardless.
+    impl<M: Money> std::fmt::Debug for Supermarket<M> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let rules: Vec<String> = self.price_rules.iter().map(|rule| rule.to_string()).collect();
+            f.debug_struct("Supermarket")
+                .field("rules", &rules)
+                .field("overflow_policy", &self.overflow_policy)
+                .field("scan_policy", &self.scan_policy)
+                .field("count_strategy", &self.count_strategy)
+                .field("cart_limits", &self.cart_limits)
+                .field("promotion_budget", &self.promotion_budget)
+                .field("tiers", &self.tier_rules.keys().collect::<Vec<_>>())
+                .field("age_restricted", &self.age_restricted)
+                .field("quantity_limits", &self.quantity_limits)
+                .field("quantity_limit_policy", &self.quantity_limit_policy)
+                .field("cart_rules", &self.cart_rules.iter().map(|rule| rule.describe()).collect::<Vec<_>>())
+                .field("negative_total_policy", &self.negative_total_policy)
+                .field("observers", &self.observers.len())
+                .field("pipeline", &self.pipeline.names())
+                .finish()
+        }
     }
 
     /// Implementation of general-purpose functions for the Supermarket type.
@@ -85,22 +580,98 @@ pub mod reasonable_implementation {
     /// This is different from Java, where methods for a class must be
     /// defined in the main body of the class.
     ///
-    /// Note also that we're chaining through a lifetime definition, `'s`.
-    impl<'s> Supermarket<'s> {
+    /// Note also that we're chaining through the money type, `M`, so that
+    /// the functions below work for any backend.
+    impl<M: Money> Supermarket<M> {
 
         /// A public constructor for the `Supermarket` struct.
         /// This allows external code to create and use a `Supermarket`
         /// even though it lacks access to its private `price_rules` field.
-        pub fn new() -> Supermarket<'s> {
+        ///
+        /// The catalog starts out empty, so every cart costs nothing until
+        /// rules are added.
+        pub fn new() -> Supermarket<M> {
+            // `Vec::new()` allocates nothing until the first push.
+            Supermarket::with_rules(Vec::new())
+        }
+
+        /// A Supermarket pricing carts with exactly the given rules.
+        ///
+        /// Rust has no constructor overloading, so where Java would offer
+        /// `new Supermarket()` and `new Supermarket(rules)`, Rust names each
+        /// variant, and `with_` is the customary prefix for the ones taking
+        /// extra arguments, as in `Vec::with_capacity`.
+        ///
+        /// The rules may come in any order: totals do not depend on it, as
+        /// the `PricingRule` contract explains. Order only decides what is
+        /// listed first, and which rules a `PromotionBudget` trims, which
+        /// changes the labels on a receipt but not its total. Cart rules
+        /// are different, each applying to the total the ones added before
+        /// it left; see `cart_rules`.
+        pub fn with_rules(price_rules: Vec<BoxedRule<M>>) -> Supermarket<M> {
+            // Collecting moves the rules into a `RuleStore`. When that is a
+            // `Vec` too, the standard library reuses the original allocation
+            // rather than copying anything; a `SmallVec` moves up to eight
+            // rules into itself.
+            Supermarket::from_store(price_rules.into_iter().collect())
+        }
+
+        /// A builder, for collecting rules and settings before making a
+        /// Supermarket of them. See `SupermarketBuilder`.
+        pub fn builder() -> SupermarketBuilder<M> {
+            SupermarketBuilder::new()
+        }
+
+        /// A Supermarket with the given rules and every other setting at its
+        /// default.
+        fn from_store(price_rules: RuleStore<M>) -> Supermarket<M> {
+            Supermarket {
+                price_rules,
+                // `Default::default()` asks the field's type for its default
+                // value, which for `OverflowPolicy` is `Checked`.
+                overflow_policy: Default::default(),
+                scan_policy: ScanPolicy::STRICT,
+                count_strategy: CountStrategy::default(),
+                cart_limits: CartLimits::UNLIMITED,
+                promotion_budget: PromotionBudget::Unlimited,
+                tier_rules: BTreeMap::new(),
+                age_restricted: BTreeSet::new(),
+                quantity_limits: BTreeMap::new(),
+                quantity_limit_policy: QuantityLimitPolicy::Refuse,
+                cart_rules: Vec::new(),
+                negative_total_policy: NegativeTotalPolicy::Clamp,
+                observers: Vec::new(),
+                pipeline: Pipeline::standard()
+            }
+        }
+
+        /// A Supermarket stocked with the challenge's demo catalog: A for
+        /// $20, B for $50 or 5 for $150, and C for $30.
+        ///
+        /// `#[cfg(feature = "demo-catalog")]` compiles this function only
+        /// when the `demo-catalog` feature is enabled. It is on by default,
+        /// for the sake of the tutorial, but a real shop can turn it off so
+        /// that these made-up prices cannot end up in production by
+        /// accident. Java would need a separate artifact to achieve the same.
+        ///
+        /// Prices are written in cents, which every money backend can
+        /// represent exactly.
+        #[cfg(feature = "demo-catalog")]
+        pub fn demo() -> Supermarket<M> {
             
             // Here we instantiate the members-to-be of the pricing rules.
             // The use of a `Box::new` call wrapping the individual structs
             // clarifies that those structures should be allocated on the
             // heap, and a reference provided to those structures.
-            let a = Box::new(FlatPrice { 
-                product: 'A',
-                cost: 20
-            }) as Box<PricingRule>;
+            //
+            // The rule constructors return a `Result`, refusing nonsense
+            // like negative prices. `expect` unwraps the `Ok` value, or
+            // panics with the given message on an `Err`. That is only
+            // appropriate because these prices are fixed and known to be
+            // valid; a panic here would be a bug in this very function.
+            let a = Box::new(
+                FlatPrice::new('A', M::from_cents(2000)).expect("demo prices are valid")
+            ) as Box<dyn PricingRule<M> + Send + Sync>;
 
             // `as MyType` is a cast in Rust, equivalent to `(MyType) obj`
             // in Java-land. The reason for the casting here is to
@@ -108,82 +679,453 @@ pub mod reasonable_implementation {
             // right type of collection to make. If we weren't interested
             // in demonstrating casting for educational reasons, we might
             // use a different formulation that was less explicit.
-            let b = Box::new(BundlePrice {
-                product: 'B',
-                lone_cost: 50,
-                bundle_size: 5,
-                bundle_cost: 150
-            }) as Box<PricingRule>;
+            let b = Box::new(
+                BundlePrice::new('B', M::from_cents(5000), 5, M::from_cents(15000))
+                    .expect("demo prices are valid")
+            ) as Box<dyn PricingRule<M> + Send + Sync>;
             
             // Another important difference between Java and Rust is
             // that these `let value_name` variables are immutable by default.
             // This is a lot like having `final` variables everywhere.
-            let c = Box::new(FlatPrice {
-                product: 'C',
-                cost: 30
-            }) as Box<PricingRule>;
+            let c = Box::new(
+                FlatPrice::new('C', M::from_cents(3000)).expect("demo prices are valid")
+            ) as Box<dyn PricingRule<M> + Send + Sync>;
 
             // The last expression in a function is returned automatically
             // without requiring a `return` keyword.
-            Supermarket {
-                // `vec!` is a macro which generates a Vec of a type
-                // matching the input list.
-                price_rules: vec!(a, b, c)
+            //
+            // `vec!` is a macro which generates a Vec of a type matching
+            // the input list.
+            Supermarket::with_rules(vec!(a, b, c))
+        }
+
+        /// Replaces the overflow policy, returning the updated Supermarket.
+        ///
+        /// Taking `mut self` by value consumes the old instance and hands
+        /// back a modified one, which allows chaining calls fluently:
+        /// `Supermarket::demo().with_overflow_policy(OverflowPolicy::Saturating)`
+        pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Supermarket<M> {
+            self.overflow_policy = policy;
+            self
+        }
+
+        /// Replaces the scan policy, returning the updated Supermarket.
+        pub fn with_scan_policy(mut self, policy: ScanPolicy) -> Supermarket<M> {
+            self.scan_policy = policy;
+            self
+        }
+
+        /// Replaces the count strategy, returning the updated Supermarket.
+        pub fn with_count_strategy(mut self, strategy: CountStrategy) -> Supermarket<M> {
+            self.count_strategy = strategy;
+            self
+        }
+
+        /// Replaces the cart limits, returning the updated Supermarket.
+        ///
+        /// Anything pricing carts sent by strangers, such as a network
+        /// service, should set these, so that nobody can tie it up with a
+        /// multi-gigabyte cart.
+        pub fn with_cart_limits(mut self, limits: CartLimits) -> Supermarket<M> {
+            self.cart_limits = limits;
+            self
+        }
+
+        /// Replaces the promotion budget, returning the updated Supermarket.
+        ///
+        /// ```
+        /// use rust_for_java_devs::market::{PromotionBudget, Supermarket};
+        /// use rust_for_java_devs::money::Cents;
+        ///
+        /// // Five Bs list at $250.00 and sell for $150.00, $100.00 off, of
+        /// // which only $60.00 is allowed.
+        /// let market: Supermarket = Supermarket::demo()
+        ///     .with_promotion_budget(PromotionBudget::Absolute(Cents(6000)));
+        /// let receipt = market.receipt("BBBBB").unwrap();
+        /// assert_eq!(Cents(19000), receipt.total);
+        /// assert_eq!("promotion budget: B: $50.00 each or 5 for $150.00  $40.00",
+        ///            receipt.adjustments[0].to_string());
+        /// ```
+        pub fn with_promotion_budget(mut self, budget: PromotionBudget<M>) -> Supermarket<M> {
+            self.promotion_budget = budget;
+            self
+        }
+
+        /// Registers an observer to be told about every cart priced,
+        /// returning the updated Supermarket. Observers are called in the
+        /// order they were registered.
+        ///
+        /// `impl CheckoutObserver<M> + ...` in argument position accepts any
+        /// type implementing the trait, and is shorthand for declaring a
+        /// generic parameter. The observer is boxed here, so callers don't
+        /// have to.
+        pub fn with_observer(mut self, observer: impl CheckoutObserver<M> + Send + Sync + 'static)
+                             -> Supermarket<M> {
+            self.observers.push(Box::new(observer));
+            self
+        }
+
+        /// The checkout pipeline, for replacing, wrapping or adding stages.
+        ///
+        /// Returning `&mut` lends out write access to the pipeline for as
+        /// long as the caller holds on to it, during which nothing else may
+        /// touch the Supermarket at all. That is how Rust rules out the
+        /// `ConcurrentModificationException`s Java detects only at runtime.
+        pub fn pipeline_mut(&mut self) -> &mut Pipeline<M> {
+            &mut self.pipeline
+        }
+
+        /// The largest cart this Supermarket will price.
+        pub fn cart_limits(&self) -> CartLimits {
+            self.cart_limits
+        }
+
+        /// The most this Supermarket gives away in promotions on one cart.
+        pub fn promotion_budget(&self) -> PromotionBudget<M> {
+            self.promotion_budget
+        }
+
+        /// Replaces the policy for totals below zero, returning the updated
+        /// Supermarket.
+        ///
+        /// By default a cart never costs less than nothing, however its
+        /// rules, cart rules and stages combine: a total below zero is
+        /// brought up to zero by a `discount not used` adjustment, for the
+        /// amount the customer could not be given. The standard rules never
+        /// go below zero; a custom cart rule or pipeline stage might.
+        pub fn with_negative_total_policy(mut self, policy: NegativeTotalPolicy) -> Supermarket<M> {
+            self.negative_total_policy = policy;
+            self
+        }
+
+        /// Prices a sequence of items like `Market::checkout`, reporting an
+        /// overflow under the `Checked` policy, or a cart beyond the cart
+        /// limits, as an error.
+        ///
+        /// `items` may be anything `Into<String>`: a `String`, a `&str` or
+        /// an `ownership_and_borrowing::Cart`. See `conversions`.
+        pub fn try_checkout(&self, items: impl Into<String>) -> Result<M, CheckoutError> {
+            Ok(self.checkout_ticket(items.into(), &CustomerContext::default())?.total)
+        }
+
+        /// Prices a sequence of items like `try_checkout`, but returns the
+        /// itemized `Receipt` rather than the total alone.
+        pub fn receipt(&self, items: &str) -> Result<crate::receipt::Receipt<M>, CheckoutError> {
+            let ticket = self.checkout_ticket(items.to_string(), &CustomerContext::default())?;
+            self.itemize(ticket)
+        }
+
+        /// Prices a cart like `receipt`, without it counting as a checkout:
+        /// the observers are not told, and no metrics are recorded. For
+        /// pricing a cart already sold once again, as `receipt::reprice`
+        /// does.
+        pub(crate) fn requote(&self, items: &str) -> Result<crate::receipt::Receipt<M>, CheckoutError> {
+            let ticket = self.run_pipeline(items.to_string(), &CustomerContext::default())?;
+            self.itemize(ticket)
+        }
+
+        /// The receipt for a cart the pipeline left as `ticket`.
+        fn itemize(&self, ticket: Ticket<M>) -> Result<crate::receipt::Receipt<M>, CheckoutError> {
+            // The plan is worked out from the ticket's counts and tier, so
+            // that it sees the cart exactly as the pipeline did.
+            let plan = plan(self.rules_for(ticket.tier), &ticket.counts, self.overflow_policy)?;
+            Ok(crate::receipt::Receipt::new(plan, ticket)?)
+        }
+
+        /// Runs a cart through the pipeline as a checkout for `customer`,
+        /// recording it and telling the observers, and returns the finished
+        /// ticket.
+        fn checkout_ticket(&self, items: String, customer: &CustomerContext<M>) -> Result<Ticket<M>, CheckoutError> {
+            // With the `metrics` feature, every checkout is timed and its
+            // outcome recorded; see `telemetry.rs`. `#[cfg]` on a `let`
+            // removes the variable entirely from builds without it.
+            #[cfg(feature = "metrics")]
+            let started = std::time::Instant::now();
+            let outcome = self.run_pipeline(items, customer);
+            #[cfg(feature = "metrics")]
+            telemetry::record(self, &outcome, started);
+            let ticket = outcome?;
+            #[cfg(feature = "logging")]
+            log::debug!("priced {} items at {} ({} before adjustments)",
+                        ticket.counts.values().sum::<u64>(), ticket.total, ticket.subtotal);
+            for observer in self.observers.iter() {
+                observer.on_total(ticket.total);
             }
+            Ok(ticket)
         }
 
-        fn count_characters(items:String) -> HashMap<char, i32> {
-            // `let mut` means that this variable is mutable.
-            let mut count = HashMap::new();
-
-            // A `for` loop in Rust makes use of iterators. In this case,
-            // `items.chars()` is producing an iterator, which produces
-            // references to the elements of the thing we're iterating over,
-            // namely the characters from the `items` String.
-            for c in items.chars() {
-
-                // `match` is like a `switch` statement on steroids. It checks
-                // at compile time that we've handled all possible cases.
-                //
-                // Importantly, `match` can be used with complex data types
-                // like `enum` and even `struct`s. Like Java, it can also do
-                // primitive types.
-                //
-                // A HashMap's `entry` method returns an `enum` of type `Entry`
-                // so here we get confirmation that both of the possible `enum` 
-                // options have cases.  
-                match count.entry(c) {
-                    // cases are specified with the value that should be matched
-                    // followed by an `=>` arrow, then an expression.
-                    // This case statement matches when `count.entry(c)` returns
-                    // the `Vacant` enum value. The `Vacant` option includes a
-                    // wrapped reference to the slot in the HashMap in question.
-                    Entry::Vacant(slot) => {
-                        // We only get here when the slot was empty, meaning no
-                        // characters matching this one have been found yet,
-                        // so we can insert a count of 1
-                        slot.insert(1);
-                    },
-                    // cases are separated by commas. The practice of breaking
-                    // out the matching value's type and its component members
-                    // (here, the `slot`) is called destructuring.
-                    Entry::Occupied(mut slot) => {
-                        // This slot is occupied, meaning some previous matches
-                        // have already been found for this character. Increment
-                        // the count by one.
-                        *slot.get_mut() += 1;
-                    }
-                }
+        /// Runs a cart through the pipeline for `customer`, returning the
+        /// finished ticket.
+        fn run_pipeline(&self, items: String, customer: &CustomerContext<M>) -> Result<Ticket<M>, CheckoutError> {
+            // `&items` lends the function a view of our String rather than
+            // giving the String away.
+            self.check_byte_limit(&items)?;
+
+            // The pipeline does the real work, one stage at a time; see
+            // `pipeline.rs`. The ticket is moved in, as nobody else needs it.
+            let mut ticket = Ticket::new(items, self.overflow_policy);
+            ticket.tier = customer.tier;
+            ticket.competitor_prices = customer.competitor_prices.iter()
+                .map(|(&product, &price)| (char::from(product), price))
+                .collect();
+            self.pipeline.run(self, &mut ticket)?;
+
+            // Whatever the stages did, and an integrator may have added any,
+            // the total is held to the negative total policy last of all.
+            if self.negative_total_policy == NegativeTotalPolicy::Clamp && ticket.total < M::zero() {
+                let unused = M::zero().checked_minus(ticket.total).ok_or(Overflow)?;
+                ticket.take_back("discount not used", unused)?;
             }
+            Ok(ticket)
+        }
+
+        /// Prices a sequence of items like `try_checkout`, but returns the
+        /// whole `PricingPlan` behind the total: which rule charged for
+        /// which items, and under which offer.
+        ///
+        /// The plan covers the rules alone. Stages after `promotions`, such
+        /// as tax, are not part of it.
+        pub fn plan(&self, items: &str) -> Result<PricingPlan<M>, CheckoutError> {
+            self.check_byte_limit(items)?;
+            check_items(items)?;
 
-            // Return the HashMap. Note the lack of a semicolon. semicolons are
-            // used for to split rust expressions into distinct statements.
-            // Statements produce the unit type `()`, which is a lot like `void`
-            // in Java.
+            // The scan policy reads the cart and counts its items. The
+            // counting itself is explained in `core::counting::count_items`.
             //
-            // Because we actually want to return a value here, we want an
-            // expression, not a statement on the last line of the function.
-            count
+            // It is generic over the width of the counts. The `::<u64>`
+            // (affectionately called the "turbofish") picks 64-bit counts,
+            // wide enough that no cart which fits in memory can overflow them.
+            let mut counts = self.scan_policy.count_items::<u64>(items);
+            self.check_item_limit(&counts)?;
+            self.limit_quantities(&mut counts)?;
+            Ok(plan(&self.price_rules, &counts, self.overflow_policy)?)
+        }
+
+        /// Refuses a cart longer than the byte limit.
+        ///
+        /// Without `pub`, this helper is private to the module, like a
+        /// `private` method in Java. The `pipeline` module may still call
+        /// it, being a child of this one.
+        fn check_byte_limit(&self, items: &str) -> Result<(), CheckoutError> {
+            // `len` is the length in bytes, known without reading the
+            // string, so an oversized cart is turned away before any work.
+            //
+            // `as u64` widens the `usize`; it cannot lose information on
+            // any platform Rust supports.
+            let limits = self.cart_limits;
+            if items.len() > limits.max_bytes {
+                // `return` leaves the function early, as in Java.
+                return Err(CheckoutError::CartTooLarge {
+                    dimension: CartDimension::Bytes,
+                    limit: limits.max_bytes as u64
+                });
+            }
+            Ok(())
+        }
+
+        /// Refuses a cart holding more items than the item limit.
+        ///
+        /// The number of items is only known once separators have been
+        /// skipped, which is why this limit is checked after counting.
+        fn check_item_limit(&self, counts: &Counts) -> Result<(), CheckoutError> {
+            let item_count: u64 = counts.values().sum();
+            if item_count > self.cart_limits.max_items {
+                return Err(CheckoutError::CartTooLarge {
+                    dimension: CartDimension::Items,
+                    limit: self.cart_limits.max_items
+                });
+            }
+            Ok(())
+        }
+    }
+
+    /// Why `Supermarket::price_of` has no price to show.
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    pub enum PriceQueryError {
+        /// No rule prices the product. A cart would get it for nothing, but
+        /// a shelf label saying $0.00 would be a mistake.
+        UnknownProduct(Sku),
+        /// The quantity could not be priced, for the reason a cart of it
+        /// could not: too many items, or a total too large.
+        Checkout(CheckoutError)
+    }
+
+    impl std::fmt::Display for PriceQueryError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                PriceQueryError::UnknownProduct(sku) => write!(f, "no rule prices {}", sku),
+                PriceQueryError::Checkout(error) => write!(f, "{}", error)
+            }
+        }
+    }
+
+    impl std::error::Error for PriceQueryError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                PriceQueryError::UnknownProduct(_) => None,
+                PriceQueryError::Checkout(error) => Some(error)
+            }
+        }
+    }
+
+    /// Lets `?` pass on a `CheckoutError`, and, through it, an `Overflow`.
+    impl From<CheckoutError> for PriceQueryError {
+        fn from(error: CheckoutError) -> PriceQueryError {
+            PriceQueryError::Checkout(error)
+        }
+    }
+
+    /// Inspecting the catalog, rather than using it to price carts.
+    ///
+    /// A type may have any number of `impl` blocks. Splitting them up by
+    /// purpose is purely for the reader's benefit.
+    impl<M: Money> Supermarket<M> {
+
+        /// Warns about rules that are probably mistakes: products priced by
+        /// more than one rule, bundles costing more than their items bought
+        /// singly, and prices of zero or less.
+        ///
+        /// An empty `Vec` means nothing looked wrong.
+        pub fn lint(&self) -> Vec<LintWarning> {
+            lint_rules(&self.price_rules, &[])
+        }
+
+        /// Like `lint`, but also warns about every product in `stocked`,
+        /// written like a cart, that no rule puts a price on.
+        pub fn lint_stock(&self, stocked: &str) -> Vec<LintWarning> {
+            let stocked: Vec<char> = stocked.chars().collect();
+            lint_rules(&self.price_rules, &stocked)
+        }
+
+        /// The price of `quantity` items of `sku`, as the rules charge
+        /// for them alone, with no other items in the cart: a shelf price
+        /// for a quantity of one, or the price behind a "5 for $150.00"
+        /// label for five.
+        ///
+        /// ```
+        /// use rust_for_java_devs::conversions::Sku;
+        /// use rust_for_java_devs::newtypes_and_units::Quantity;
+        /// use rust_for_java_devs::reasonable_implementation::{PriceQueryError, Supermarket};
+        /// use rust_for_java_devs::money::Cents;
+        ///
+        /// let market: Supermarket = Supermarket::demo();
+        /// assert_eq!(Ok(Cents(15000)), market.price_of(Sku('B'), Quantity(5)));
+        /// assert_eq!(Err(PriceQueryError::UnknownProduct(Sku('X'))),
+        ///            market.price_of(Sku('X'), Quantity::ONE));
+        /// ```
+        ///
+        /// No cart string is made: the counts go straight to the rules,
+        /// and are added up by `core::rules::total`, as the `promotions`
+        /// stage of a checkout adds them. Like `plan`, this covers the
+        /// rules, not the later pipeline stages.
+        ///
+        /// A quantity over the product's quantity limit is refused whatever
+        /// the `QuantityLimitPolicy`: trimmed, it would be the price of
+        /// fewer items than were asked about.
+        ///
+        /// The arguments are newtypes, so swapping them does not compile,
+        /// where a Java `priceOf(char, int)` accepts `priceOf('A', 'B')`.
+        /// See `newtypes_and_units`.
+        pub fn price_of(&self, sku: Sku, quantity: Quantity) -> Result<M, PriceQueryError> {
+            let product = char::from(sku);
+            if !self.price_rules.iter().any(|rule| rule.applies_to(product)) {
+                return Err(PriceQueryError::UnknownProduct(sku));
+            }
+            let counts = Counts::from([(product, u64::from(quantity))]);
+            self.check_item_limit(&counts)?;
+            if let Some(limit) = self.quantity_limit(sku).filter(|&limit| u64::from(quantity) > limit) {
+                return Err(CheckoutError::QuantityLimitExceeded { product: sku, limit, quantity: u64::from(quantity) }.into());
+            }
+            total(&self.price_rules, &counts, self.overflow_policy)
+                .map_err(|overflow| PriceQueryError::Checkout(overflow.into()))
+        }
+
+        /// What one to `max_quantity` items of each product in the catalog
+        /// cost, the total and the effective price of one, each worked out
+        /// by `price_of`, so with every rule applied as at a checkout. A
+        /// quantity `price_of` refuses keeps its place in the row, as the
+        /// error.
+        ///
+        /// ```
+        /// use rust_for_java_devs::reasonable_implementation::Supermarket;
+        /// use rust_for_java_devs::money::Cents;
+        ///
+        /// let market: Supermarket = Supermarket::demo();
+        /// let table = market.unit_price_table(6);
+        /// let b = &table.rows[1];
+        /// let five = b.prices[4].as_ref().unwrap();
+        /// assert_eq!((5, Cents(15000)), (five.quantity, five.total));
+        /// assert_eq!(30.0, five.each());
+        /// assert_eq!(Some(5), b.first_saving().map(|point| point.quantity));
+        /// ```
+        ///
+        /// This is what `checkout catalog --up-to N` prints.
+        pub fn unit_price_table(&self, max_quantity: u32) -> crate::catalog::UnitPriceTable<M> {
+            use crate::catalog::{PricePoint, UnitPriceRow, UnitPriceTable};
+            let mut products: Vec<char> = self.price_rules.iter().flat_map(|rule| rule.products()).collect();
+            products.sort_unstable();
+            products.dedup();
+            let rows = products.into_iter().map(|product| {
+                let prices = (1..=max_quantity)
+                    .map(|quantity| {
+                        let total = self.price_of(Sku(product), Quantity(quantity))?;
+                        Ok(PricePoint { quantity: u64::from(quantity), total })
+                    })
+                    .collect();
+                UnitPriceRow { product, prices }
+            }).collect();
+            UnitPriceTable { rows }
+        }
+
+        /// The rules, in the order they are applied.
+        ///
+        /// `&**rule` lends out each rule itself, rather than the `Box`
+        /// holding it, so callers need not know how rules are stored.
+        pub fn rules(&self) -> impl Iterator<Item = &(dyn PricingRule<M> + Send + Sync)> {
+            self.price_rules.iter().map(|rule| &**rule)
+        }
+    }
+
+    /// `Default` is the standard trait for "construct one with no
+    /// arguments". Rust does not treat a function named `new` specially, so
+    /// implementing `Default` lets generic code that knows nothing about
+    /// Supermarkets create one anyway.
+    impl<M: Money> Default for Supermarket<M> {
+        fn default() -> Supermarket<M> {
+            Supermarket::new()
+        }
+    }
+
+    /// `FromIterator` is what `Iterator::collect` builds on, so with this
+    /// impl a pipeline of rules can end in a Supermarket the way a Java
+    /// stream ends in `collect(Collectors.toList())`:
+    ///
+    /// ```
+    /// # use rust_for_java_devs::reasonable_implementation::{BoxedRule, FlatPrice, Supermarket};
+    /// # use rust_for_java_devs::money::Cents;
+    /// // Each rule constructor may fail, so the pipeline yields `Result`s.
+    /// // Collecting those into a single `Result` stops at the first error.
+    /// let s: Result<Supermarket, _> = "XYZ".chars()
+    ///     .map(|product| FlatPrice::new(product, Cents(100)))
+    ///     .map(|rule| rule.map(|r| Box::new(r) as BoxedRule<Cents>))
+    ///     .collect();
+    /// assert!(s.is_ok());
+    /// ```
+    impl<M: Money> FromIterator<BoxedRule<M>> for Supermarket<M> {
+        fn from_iter<I: IntoIterator<Item = BoxedRule<M>>>(rules: I) -> Supermarket<M> {
+            // Collecting straight into the `RuleStore`, rather than into a
+            // `Vec` for `with_rules`, spares a small catalog the heap
+            // allocation when the `smallvec` feature is on.
+            Supermarket::from_store(rules.into_iter().collect())
+        }
+    }
+
+    /// `Extend` adds every rule of an iterator to an existing catalog, like
+    /// Java's `Collection.addAll`. It takes `&mut self`, so the Supermarket
+    /// must be declared `let mut`.
+    impl<M: Money> Extend<BoxedRule<M>> for Supermarket<M> {
+        fn extend<I: IntoIterator<Item = BoxedRule<M>>>(&mut self, rules: I) {
+            self.price_rules.extend(rules)
         }
     }
 
@@ -192,117 +1134,22 @@ pub mod reasonable_implementation {
     /// Unlike the preceding `impl` block where any function could be added,
     /// we are constrained to defining *just* the functions relevant to the
     /// Market trait.
-    impl<'s> Market for Supermarket<'s> { 
+    impl<M: Money> Market<M> for Supermarket<M> {
         /// The `&` preceding the `self` parameter clarifies that we're being
         /// provided a reference to the Supermarket struct instance, not
         /// the direct value.
         ///
         /// References, and more broadly, pointers, are deep topics
         /// that deserve more of an explanation than you'll presently get here.
-        /// Check out http://doc.rust-lang.org/book/pointers.html for more.
+        /// Check out https://doc.rust-lang.org/book/ch04-02-references-and-borrowing.html for more.
         ///
         /// You can think of functions with the `&self` parameter as being like
         /// methods, whereas functions without it are more like static functions
         /// in Java.
-        fn checkout(&self, items:String) -> i32 {
-            // Note that we can make use of private functions from the
-            // Supermarket `impl` block because we are in the same module.
-            //
-            // The count_characters function was defined without a `&self` param
-            // so we call it with the following TypeName::function_name(args)
-            // syntax.
-            let counts = Supermarket::count_characters(items);
-
-            // Here we see a hint at the functional-style terseness possible
-            // in Rust. The next expression iterates through the price rules,
-            // runs a fresh function (defined inline) on each of the rules,
-            // and sums up the individual results.
-            //
-            // The inline (a.k.a "anonymous") function definition syntax used
-            // is simply `|parameter_name| expression`
-            //
-            // If multiple lines were needed, it could have also been written
-            // `|parameter_name| { ... multiple lines ... }`
-            self.price_rules.iter().map(|p| p.price(&counts) ).sum()
-        }
-    }
-    
-    /// Provides a means of attaching a price to some subset of the items.
-    ///
-    /// From a design perspective, a more complicated and robust solution
-    /// might include additional functions or return values to specify
-    /// which of the input items were actually accounted-for by this rule
-    /// in order to discover un-priced items. For the sake of simplicity,
-    /// these have been omitted.
-    trait PricingRule {
-        ///
-        /// # Arguments
-        /// 
-        /// * `character_counts` - the number of instances of each character
-        /// found in the `items` input String to the `Market.checkout` function.
-        /// Note that because this input is a simple map of counts, any ordering
-        /// of characters found in the original string has been lost, so
-        /// sequence-order-dependent pricing rules are not expressable with
-        /// this interface formulation.
-        ///
-        /// # Returns
-        /// The price of the items that this rule is accounting for.
-        /// This number may be negative, possibly useful for indicating some
-        /// discount, coupon, or combo deal.
-        fn price(&self, character_counts:&HashMap<char, i32>) -> i32;
-    }
-    
-    /// Represents a simple flat price. For every item matching the product,
-    /// the cost is added to the price.
-    struct FlatPrice {
-        product: char,
-        cost: i32
-    }
-
-    impl PricingRule for FlatPrice {
-        fn price(&self, character_counts:&HashMap<char, i32>) -> i32 {
-            // The `get` method of a HashMap returns an Option<T>, which is
-            // an enum with two possibilities, either None or Some(x),
-            // where x is a reference to a value of type T.
-            //
-            // In this case, T is the count for that character.  
-            match character_counts.get(&self.product) {
-                Some(&count) => count * self.cost,
-                // No key was found that matched the product character code,
-                // so there's no cost.
-                None => 0
-            }
-            // `match` produces the value of the selected case's expression.
-            // We could store that value in a `let` variable, or, if the match
-            // is the last thing in the function, it gets returned.
-        }
-    }
-
-    /// Represents a price for a product where you can buy it in bundles of 
-    /// a set size for one cost, but you have a number of items less than a
-    /// bundle, there is a different cost.  Allows for unlimited bundles.
-    ///
-    /// Equivalent to "X cost apiece, or Y cost when you buy N of them"
-    struct BundlePrice {
-        product: char,
-        lone_cost: i32,
-        bundle_size: i32,
-        bundle_cost: i32
-    }
-
-    impl PricingRule for BundlePrice {
-        fn price(&self, character_counts:&HashMap<char, i32>) -> i32 {
-            match character_counts.get(&self.product) {
-                // Here we match on an exact value, 0, rather than capturing
-                // the integer into a variable name (as is done in the 2nd case)
-                Some(&0) => 0,
-                Some(&non_zero_count) => {
-                    let bundles = non_zero_count / self.bundle_size;
-                    let leftovers = non_zero_count % self.bundle_size;
-                    bundles * self.bundle_cost + leftovers * self.lone_cost
-                },
-                None => 0
-            }
+        fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+            // Note that we can make use of the public functions from the
+            // Supermarket `impl` block like any other method.
+            self.try_checkout(items)
         }
     }
 }
@@ -0,0 +1,161 @@
+//! Lifetimes, explained by taking them away.
+//!
+//! The crate has three markets that differ in little else than who owns
+//! the rules, and so in which lifetimes they must write down:
+//!
+//! 1. `reasonable_implementation::Supermarket` owns its rules, each in a
+//!    `Box<dyn PricingRule<M> + Send + Sync + 'static>`. `'static` says the
+//!    rules borrow nothing, so they can live as long as the market does,
+//!    whatever that turns out to be. No lifetime appears on the struct, nor
+//!    on any code holding one. This is the Java-like one: a Java market's
+//!    rules would live as long as anything refers to them.
+//! 2. `borrowed_rules_implementation::Supermarket<'s>` also owns its rules
+//!    in `Box`es, but a rule may borrow data owned elsewhere, such as the
+//!    `PriceTable` below, which borrows a map of prices instead of copying
+//!    it. The market is then only valid while that data is, so its type
+//!    carries the lifetime `'s`, and so must every struct or function
+//!    signature mentioning it.
+//! 3. `SliceMarket<'r, R>`, here, owns nothing: it borrows a slice of rules
+//!    owned by its caller. Making one allocates nothing, which suits a
+//!    market built afresh for every request, but it cannot outlive the
+//!    slice, and says so with `'r`.
+//!
+//! The rule of thumb the three illustrate: a lifetime parameter is needed
+//! exactly when a struct holds a reference, `&'r T`, or something that may
+//! contain one, `dyn Trait + 's`. Owning everything removes the need, at
+//! the price of copying or boxing; borrowing saves that price, at the cost
+//! of a parameter the compiler checks everywhere the type is used.
+//!
+//! Functions need lifetimes written less often than structs, as *elision*
+//! fills them in. A method taking `&self` and returning a reference is
+//! assumed to return something borrowed from `self`, and a function with a
+//! single reference argument something borrowed from that. Only when there
+//! are several candidates, as in `SliceMarket::rule_for` and `dearer`, does
+//! the signature have to say which.
+
+use std::collections::BTreeMap;
+use crate::core::counting::{count_items, Counts};
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{Describable, PricingRule, total};
+use crate::reasonable_implementation::{CheckoutError, Market};
+
+/// A rule charging the price listed in a table for each product in it.
+///
+/// The table is borrowed, not owned: a store's price file may be loaded
+/// once and read by many rules. `'t` says how long the borrow lasts, and
+/// any `PriceTable<'t>` is only usable within it. Putting one in a market
+/// needs `borrowed_rules_implementation`, as a `PriceTable` is not
+/// `'static` unless its table is.
+pub struct PriceTable<'t, M: Money = Cents> {
+    prices: &'t BTreeMap<char, M>
+}
+
+impl<'t, M: Money> PriceTable<'t, M> {
+
+    /// A rule reading its prices from `prices`.
+    pub fn new(prices: &'t BTreeMap<char, M>) -> PriceTable<'t, M> {
+        PriceTable { prices }
+    }
+}
+
+/// `'_` is an elided lifetime: the impl holds for a `PriceTable` borrowing
+/// for any lifetime, with no need to name it, as nothing else here uses it.
+impl<M: Money> Describable for PriceTable<'_, M> {
+    fn describe(&self) -> String {
+        let prices: Vec<String> = self.prices.iter()
+            .map(|(product, cost)| format!("{} {}", product, cost))
+            .collect();
+        format!("table: {}", prices.join(", "))
+    }
+}
+
+impl<M: Money> PricingRule<M> for PriceTable<'_, M> {
+    fn price(&self, character_counts: &Counts, policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.prices.iter().try_fold(M::zero(), |sum, (product, &cost)| {
+            let count = character_counts.get(product).copied().unwrap_or(0);
+            policy.plus(sum, policy.times(cost, count)?)
+        })
+    }
+
+    fn products(&self) -> Vec<char> {
+        self.prices.keys().copied().collect()
+    }
+
+    fn applies_to(&self, product: char) -> bool {
+        self.prices.contains_key(&product)
+    }
+}
+
+/// A market borrowing its rules, of any one type `R`, from its caller.
+///
+/// ```
+/// use rust_for_java_devs::core::rules::FlatPrice;
+/// use rust_for_java_devs::lifetimes_explained::SliceMarket;
+/// use rust_for_java_devs::money::Cents;
+///
+/// let rules = [FlatPrice::new('A', Cents(2000)).unwrap(), FlatPrice::new('C', Cents(3000)).unwrap()];
+/// let market = SliceMarket::new(&rules);
+/// assert_eq!(Ok(Cents(7000)), market.try_checkout("AAC"));
+/// ```
+///
+/// Dropping `rules` while `market` still exists would not compile: the
+/// borrow checker sees that the slice must live for `'r`, and `'r` for as
+/// long as the market is used.
+pub struct SliceMarket<'r, R> {
+    rules: &'r [R],
+    policy: OverflowPolicy
+}
+
+impl<'r, R> SliceMarket<'r, R> {
+
+    /// A market pricing with `rules`.
+    pub fn new(rules: &'r [R]) -> SliceMarket<'r, R> {
+        SliceMarket { rules, policy: OverflowPolicy::Checked }
+    }
+
+    /// The rules, for as long as they are borrowed, which may be longer
+    /// than the market is kept.
+    ///
+    /// Elision would make the result borrow from `&self`, and so not outlive
+    /// this particular `SliceMarket`. Writing `'r` promises more: the slice
+    /// is the caller's, and stays valid after the market is dropped.
+    pub fn rules(&self) -> &'r [R] {
+        self.rules
+    }
+
+    /// The price of `items`.
+    ///
+    /// `M` is a parameter of the method rather than of the struct, as a
+    /// slice of rules could in principle price in several money types.
+    pub fn try_checkout<M: Money>(&self, items: &str) -> Result<M, Overflow> where R: PricingRule<M> {
+        total(self.rules, &count_items::<u64>(items), self.policy)
+    }
+
+    /// The first rule pricing `product`.
+    ///
+    /// Both `&self` and the rules are references, so the signature must say
+    /// which the result borrows from; `'r`, as with `rules`.
+    pub fn rule_for<M: Money>(&self, product: char) -> Option<&'r R> where R: PricingRule<M> {
+        self.rules.iter().find(|rule| rule.applies_to(product))
+    }
+}
+
+impl<M: Money, R: PricingRule<M>> Market<M> for SliceMarket<'_, R> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        Ok(self.try_checkout(items)?)
+    }
+}
+
+/// Whichever of two rules charges more for a single `product`, preferring
+/// `first` on a tie.
+///
+/// Two reference arguments, and no `self`, so elision gives up, and the
+/// signature names one lifetime `'a` for both: the result is valid for as
+/// long as *both* arguments are. Java's garbage collector answers that
+/// question at runtime instead, by keeping whichever is returned alive.
+pub fn dearer<'a, M: Money>(first: &'a dyn PricingRule<M>, second: &'a dyn PricingRule<M>,
+                            product: char) -> &'a dyn PricingRule<M> {
+    let counts = Counts::from([(product, 1)]);
+    let price = |rule: &dyn PricingRule<M>| rule.price(&counts, OverflowPolicy::Saturating).ok();
+    if price(second) > price(first) { second } else { first }
+}
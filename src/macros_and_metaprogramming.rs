@@ -0,0 +1,133 @@
+//! Macros, compared with Java's reflection and annotation processing.
+//!
+//! Java writes code about code in two ways. Reflection inspects classes
+//! while the program runs: a framework finds every `@Rule`-annotated class
+//! on the classpath and calls its constructor through a `Method` handle,
+//! and a typo in a name is an exception in production. Annotation
+//! processors, such as Lombok, AutoValue or Dagger, run inside `javac` and
+//! write new source files, which is safer but needs a build plugin and an
+//! API of its own.
+//!
+//! Rust has no runtime reflection at all. Everything is done by macros, at
+//! compile time, in two kinds:
+//!
+//! * *Declarative* macros, `macro_rules!`, match their input against
+//!   patterns of tokens and expand to a template, as `price_rules!` below
+//!   does. They live in ordinary source files and need no build step.
+//! * *Procedural* macros are Rust functions from tokens to tokens, compiled
+//!   in a crate of their own and run by the compiler. `#[derive(FlatRule)]`,
+//!   in `pricing-derive`, is one; see that crate for its internals.
+//!
+//! Either way, the expanded code is compiled and type checked like any
+//! other, so a mistake is a compile error at the macro's call site, never a
+//! `NoSuchMethodException`. `cargo expand`, a separate cargo subcommand,
+//! prints the code a macro expanded to.
+//!
+//! # `#[derive(FlatRule)]`
+//!
+//! This struct:
+//!
+//! ```text
+//! #[derive(FlatRule)]
+//! struct Apples {
+//!     #[rule(product)] code: char,
+//!     #[rule(cost)] each: Cents
+//! }
+//! ```
+//!
+//! is handed to `pricing_derive::derive_flat_rule` as a stream of tokens.
+//! `syn` parses them into a `DeriveInput`, a syntax tree, and the macro
+//! looks for the fields marked `#[rule(...)]`. It cannot ask the type of
+//! `Cents`, or whether it is money at all: a macro sees spelling, not
+//! meaning, which is why the roles are marked rather than guessed. `quote!`
+//! then writes the impls, roughly:
+//!
+//! ```text
+//! impl ::rust_for_java_devs::core::rules::Describable for Apples {
+//!     fn describe(&self) -> String { format!("{}: {} each", self.code, self.each) }
+//! }
+//! impl ::rust_for_java_devs::core::rules::PricingRule<Cents> for Apples {
+//!     fn price(&self, character_counts: &Counts, policy: OverflowPolicy) -> Result<Cents, Overflow> {
+//!         policy.times(self.each, character_counts.get(&self.code).copied().unwrap_or(0))
+//!     }
+//!     // products and lint likewise
+//! }
+//! ```
+//!
+//! Where the struct is wrong, say a field is marked `#[rule(price)]`, the
+//! macro returns a `compile_error!` instead, pointing at `price`, much as an
+//! annotation processor reports through `Messager.printMessage`. The
+//! `tests/ui` directory holds such mistakes, with the errors they must give.
+
+/// A list of rules, written as a table:
+///
+/// ```
+/// use rust_for_java_devs::price_rules;
+/// use rust_for_java_devs::money::Cents;
+/// use rust_for_java_devs::reasonable_implementation::Supermarket;
+///
+/// let rules = price_rules! {
+///     'A' => Cents(2000);
+///     'B' => Cents(5000), 5 for Cents(15000);
+///     'C' => Cents(3000), 3 for Cents(8000), 10 for Cents(25000);
+/// }.unwrap();
+/// let market = Supermarket::with_rules(rules);
+/// assert_eq!(Ok(Cents(25000)), market.try_checkout("ABBBBBCCC"));
+/// ```
+///
+/// A product with no offers gets a `FlatPrice`, one offer a `BundlePrice`,
+/// and several a `BestOfferPrice`. The rule constructors check the prices,
+/// so the whole list is a `Result`, failing with the first `RuleError`.
+///
+/// `#[macro_export]` puts the macro at the root of the crate, as
+/// `rust_for_java_devs::price_rules`, whichever module defines it.
+#[macro_export]
+macro_rules! price_rules {
+    // Arms are tried in order, top to bottom, and the first whose pattern
+    // matches is expanded. The `@rule` arms are for the macro's own use:
+    // no caller's table starts with `@`, so they never match one.
+    //
+    // `$product:literal` matches a literal token, such as `'A'`, and
+    // `$each:expr` any expression. Only a few tokens, such as `,`, `;` and
+    // `=>`, may follow an `expr`, so that the macro always knows where an
+    // expression ends; that is why an offer is `, 5 for cost` rather than
+    // `cost 5 for cost`.
+    (@rule $product:literal => $each:expr) => {
+        ::std::boxed::Box::new($crate::core::rules::FlatPrice::new($product, $each)?)
+            as $crate::reasonable_implementation::BoxedRule<_>
+    };
+    (@rule $product:literal => $each:expr, $size:literal for $cost:expr) => {
+        ::std::boxed::Box::new($crate::core::rules::BundlePrice::new($product, $each, $size, $cost)?)
+            as $crate::reasonable_implementation::BoxedRule<_>
+    };
+    // `$( ... )+` repeats its contents once or more, and the same `$( )+`
+    // in the template writes them out once per match, here as a chain of
+    // `with_offer` calls.
+    (@rule $product:literal => $each:expr $(, $size:literal for $cost:expr)+) => {
+        ::std::boxed::Box::new($crate::core::rules::BestOfferPrice::new($product, $each)?
+                                   $(.with_offer($size, $cost)?)+)
+            as $crate::reasonable_implementation::BoxedRule<_>
+    };
+    // The table itself: rules separated by `;`, with an optional `;` at the
+    // end. Each row is handed back to the macro, as `@rule`, and the rows
+    // are collected into a `Vec` inside a closure, so that `?` returns the
+    // first error from the closure rather than from the caller's function.
+    //
+    // The paths are written in full, `::std::vec::Vec` and `$crate::...`,
+    // because a macro expands where it is called, and the caller may have
+    // a `Vec` or a `core` of their own. `$crate` always means this crate.
+    ($($product:literal => $each:expr $(, $size:literal for $cost:expr)*);* $(;)?) => {
+        (|| -> ::std::result::Result<::std::vec::Vec<$crate::reasonable_implementation::BoxedRule<_>>,
+                                     $crate::core::rules::RuleError> {
+            ::std::result::Result::Ok(::std::vec![
+                $($crate::price_rules!(@rule $product => $each $(, $size for $cost)*)),*
+            ])
+        })()
+    };
+    // Anything else is a mistake. Without this arm the compiler would say
+    // only "no rules expected this token"; `compile_error!` says what was
+    // expected instead.
+    ($($anything:tt)*) => {
+        ::std::compile_error!("expected rules such as `'A' => Cents(2000); 'B' => Cents(5000), 5 for Cents(15000)`")
+    };
+}
@@ -0,0 +1,14 @@
+//! Markets and checking out: the `Market` trait, the `Supermarket` and
+//! what can go wrong at the register.
+//!
+//! Nothing is defined here. Each item is re-exported from the module that
+//! defines it, so `rust_for_java_devs::market::Supermarket` and
+//! `rust_for_java_devs::reasonable_implementation::Supermarket` are the same
+//! type under two paths. See `modules_and_visibility` for why.
+
+pub use crate::core::checkout::{CartDimension, CartLimits, CheckoutError, PromotionBudget, QuantityLimitPolicy,
+                                NegativeTotalPolicy};
+pub use crate::core::money::OverflowPolicy;
+pub use crate::reasonable_implementation::{BoxedRule, CustomerContext, FnMarket, Market, PriceQueryError, PriceTier,
+                                          Supermarket, SupermarketBuilder};
+pub use crate::reasonable_implementation::pipeline::Adjustment;
@@ -0,0 +1,138 @@
+//! Modules, visibility and re-exports, compared with Java's packages and
+//! imports.
+//!
+//! A Java package is a directory, and a class is visible either to
+//! everybody, `public`, or only to its own package, by leaving the modifier
+//! out. Packages do not nest: `com.shop` has no special access to
+//! `com.shop.register`, however close their directories are. And the only
+//! name a class can be imported under is the one it was declared with, in
+//! the package it was declared in.
+//!
+//! Rust modules differ on every point:
+//!
+//! * Modules nest, and are declared rather than discovered: `mod register;`
+//!   in `lib.rs` makes cargo read `src/register.rs`, and nothing in `src`
+//!   is compiled unless some module declares it.
+//! * Everything is private unless marked otherwise, and private means
+//!   visible within the module that declares it *and its descendants*. A
+//!   child module sees its parent's private items, as `drawer` below sees
+//!   the private fields of a `Till`; a parent does not see its children's.
+//! * Between private and `pub` there is a scale: `pub(super)`, visible to
+//!   the parent module as well, `pub(crate)`, visible anywhere in this crate
+//!   but not to its users, and `pub(in path)` for any ancestor in between.
+//!   `pub(crate)` is closest to package-private, with the whole crate as
+//!   the package.
+//! * `pub use` re-exports an item under another path, and the new path is
+//!   as good as the old one.
+//!
+//! That last point lets a crate present a different layout from the one its
+//! files have. The modules of this crate grew one tutorial at a time, so a
+//! `Supermarket` is in `reasonable_implementation`, a `FlatPrice` in
+//! `core::rules` and the `CheckoutError` in `core::checkout`. Users are
+//! offered four facades by topic instead, `market`, `rules`, `money` and
+//! `receipt`, and a `prelude` for importing the common names all at once,
+//! `use rust_for_java_devs::prelude::*`. The old paths still work, so no
+//! code using them had to change, where moving a Java class to another
+//! package breaks every `import` of it.
+//!
+//! Paths start from somewhere:
+//!
+//! | Java                                 | Rust                                 |
+//! |--------------------------------------|--------------------------------------|
+//! | `import com.shop.money.Cents;`       | `use crate::money::Cents;`, from the crate root |
+//! | a class in the same package          | `use self::register::Till;`, from this module |
+//! | no counterpart                       | `use super::Till;`, from the parent module |
+//! | `import com.shop.rules.*;`           | `use crate::rules::*;`               |
+//! | `import static ...Cents.ZERO;`       | `use` works on functions and constants too |
+//!
+//! `use` only makes a name available in the module it is written in; it is
+//! private, like any other item, unless it is a `pub use`.
+
+use crate::core::money::{Money, Cents};
+
+/// A cash register.
+///
+/// A module may be written inline, in braces, as here, rather than in a
+/// file of its own. Inline modules suit small ones and examples; the crate's
+/// own `reasonable_implementation` is written this way too.
+pub mod register {
+    use crate::core::money::Cents;
+
+    /// A till: the cash a register holds.
+    ///
+    /// Each field has its own visibility. `store` is public, `float` can be
+    /// read anywhere in this crate, and `takings` only here and in `drawer`.
+    /// A struct with any private field cannot be built with `Till { .. }`
+    /// outside the module, so `open` is the only way to make one, as a Java
+    /// class with only private constructors and a factory method.
+    pub struct Till {
+        pub store: String,
+        pub(crate) float: Cents,
+        takings: Cents
+    }
+
+    impl Till {
+
+        /// A till in `store`, starting with `float` in change.
+        pub fn open(store: impl Into<String>, float: Cents) -> Till {
+            Till { store: store.into(), float, takings: Cents(0) }
+        }
+
+        /// Takes `amount` for a sale.
+        pub fn ring_up(&mut self, amount: Cents) {
+            self.takings += amount;
+        }
+
+        /// What the till has taken so far.
+        pub fn takings(&self) -> Cents {
+            self.takings
+        }
+    }
+
+    /// Whether the till holds what it should, given the `counted` cash.
+    ///
+    /// `pub(super)` makes this visible to `modules_and_visibility`, the
+    /// parent module, which calls it in `close`, but not to the rest of the
+    /// crate, nor to users of it.
+    pub(super) fn balances(till: &Till, counted: Cents) -> bool {
+        drawer::expected(till) == counted
+    }
+
+    /// The cash drawer, a module nested in `register`.
+    pub mod drawer {
+        use super::Till;
+        use crate::core::money::Cents;
+
+        /// The cash a till should hold.
+        ///
+        /// `takings` is private to `register`, and `drawer` is inside
+        /// `register`, so it may read it. `pub(in crate::modules_and_visibility)`
+        /// lets `register` call this, and its parent too, and no one else.
+        pub(in crate::modules_and_visibility) fn expected(till: &Till) -> Cents {
+            till.float + till.takings
+        }
+    }
+}
+
+/// Closes `till`, returning its takings if `counted` is what it should
+/// hold, or the shortfall or surplus as an `Err` if not.
+///
+/// `register::balances` is `pub(super)`, and this module is its `super`, so
+/// it may be called from here. From `market` or `rules`, the compiler
+/// would report it as private.
+pub fn close(till: register::Till, counted: Cents) -> Result<Cents, Cents> {
+    if register::balances(&till, counted) {
+        Ok(till.takings())
+    } else {
+        let expected = register::drawer::expected(&till);
+        Err(counted.checked_minus(expected).unwrap_or(Cents(i64::MIN)))
+    }
+}
+
+/// The float a till was opened with.
+///
+/// `float` is `pub(crate)`, so any module of this crate may read it, but
+/// users of the crate may not; this function is how they can.
+pub fn float_of(till: &register::Till) -> Cents {
+    till.float
+}
@@ -0,0 +1,89 @@
+//! Newtypes, and the units they give to plain numbers.
+//!
+//! A Java method pricing some items might be declared
+//! `long priceOf(char sku, int quantity)`, and a bundle rule
+//! `BundlePrice(char sku, int bundleSize, long bundleCost)`. Nothing stops
+//! a caller writing `priceOf('A', 'B')`, as a `char` widens to an `int`
+//! without complaint, nor `new BundlePrice('B', cost, size)` once the
+//! cost fits in an `int`. The compiler sees numbers, and numbers of any
+//! kind will do. The usual Java cure, a small class per unit, costs an
+//! object on the heap per value and an `equals`, `hashCode` and
+//! `toString` each, so it is mostly reserved for money, and often not even
+//! that.
+//!
+//! A *newtype* is a struct with a single field, `struct Quantity(u32)`. It
+//! has exactly the same size and layout as the field, so it costs nothing
+//! at runtime, and `derive` writes the `equals`, `hashCode` and `toString`
+//! equivalents. But to the compiler it is a different type from `u32`, and
+//! from every other newtype over `u32`. Passing a `Quantity` where a `Sku`
+//! is expected, or adding `Cents` to a `Quantity`, no longer compiles;
+//! `tests/ui/newtype_swapped_arguments.rs` and
+//! `tests/ui/newtype_mixed_units.rs` show the errors. A newtype only does
+//! what it is given impls for, so the arithmetic that makes sense for a
+//! unit is all it gets: `Cents * Quantity` is `Cents`, below, while
+//! `Cents * Cents`, square money, is not defined at all.
+//!
+//! The crate has three:
+//!
+//! * `conversions::Sku(char)`, a product code.
+//! * `Quantity(u32)`, here, a number of items.
+//! * `Cents(i64)`, an amount of money. It plays the part of a `Money(i64)`
+//!   newtype; the name `Money` is taken by the trait that `Cents` and
+//!   `Decimal` both implement.
+//!
+//! The engine takes them at its typed entry points:
+//! `Supermarket::price_of(sku, quantity)` and the cart's `add_many` and
+//! `quantity_of`. Beneath those, rules and counts keep their `char`s and
+//! `u64`s, which `pricing-core` shares with the C and WebAssembly
+//! bindings; the newtypes are unwrapped at the boundary, where the
+//! argument order no longer depends on the caller's care.
+
+use std::fmt;
+use std::ops::Mul;
+use crate::core::money::Cents;
+
+/// A number of items.
+///
+/// `u32` is plenty for one line of a cart. The field is public, as with
+/// `Sku` and `Cents`, so `Quantity(3)` builds one and `quantity.0` reads
+/// it; making it private would let `Quantity::new` check a range, which a
+/// count of items does not need.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct Quantity(pub u32);
+
+impl Quantity {
+
+    /// A single item.
+    pub const ONE: Quantity = Quantity(1);
+
+    /// The sum of two quantities, or `None` if it does not fit.
+    pub fn checked_add(self, other: Quantity) -> Option<Quantity> {
+        self.0.checked_add(other.0).map(Quantity)
+    }
+}
+
+/// Counts are `u64`s, and every `u32` fits in one.
+impl From<Quantity> for u64 {
+    fn from(quantity: Quantity) -> u64 {
+        u64::from(quantity.0)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// `price * quantity`: the cost of `quantity` items at `price` each.
+///
+/// `Cents` belongs to `pricing-core`, and `Mul` to the standard library,
+/// but the orphan rule allows this impl here, as `Quantity` is this
+/// crate's own. It panics on overflow, as `Cents * u32` does.
+impl Mul<Quantity> for Cents {
+    type Output = Cents;
+
+    fn mul(self, quantity: Quantity) -> Cents {
+        self * quantity.0
+    }
+}
@@ -0,0 +1,94 @@
+//! Five ways to handle an `Option`, for readers who know `java.util.Optional`.
+//!
+//! `FlatPrice::price` looks its product up in the cart's counts. A map's
+//! `get` returns an `Option`, `None` when the product is not in the cart,
+//! and `FlatPrice` handles it with a `match`. Each function below prices
+//! the same rule, "`cost` per `product`", handling that `Option` another
+//! way; the tests check that all five always agree.
+//!
+//! Java's `Map.get` returns `null` for a missing key, and `Optional` is
+//! opt-in, so it is easy to forget the check. Rust has no `null`: `get`
+//! returns an `Option<&u64>`, and the count inside cannot be used until the
+//! `None` case has been dealt with somehow, which is what these are.
+//!
+//! Which to use is a matter of taste. This crate mostly writes `match` for
+//! anything longer than a line and the combinators for one-liners.
+
+use crate::core::counting::Counts;
+use crate::core::money::{Money, Overflow, OverflowPolicy};
+
+/// A `match`, naming both cases, as `FlatPrice` does.
+///
+/// The nearest Java is an `if (count != null) ... else ...`, but `match`
+/// is an expression, so both arms produce the function's result, and the
+/// compiler checks that neither case is left out.
+pub fn with_match<M: Money>(counts: &Counts, product: char, cost: M,
+                            policy: OverflowPolicy) -> Result<M, Overflow> {
+    match counts.get(&product) {
+        // `&count` matches the `&u64` inside the `Some` and copies the
+        // number out of it.
+        Some(&count) => policy.times(cost, count),
+        None => Ok(M::zero())
+    }
+}
+
+/// `if let`, for when only one case needs anything done.
+///
+/// This is a `match` with one arm and an `else`. It reads like Java's
+/// `optional.ifPresent(count -> ...)`, but, not being a lambda, it can
+/// `return` from the enclosing function, as here.
+pub fn with_if_let<M: Money>(counts: &Counts, product: char, cost: M,
+                             policy: OverflowPolicy) -> Result<M, Overflow> {
+    if let Some(&count) = counts.get(&product) {
+        return policy.times(cost, count);
+    }
+    Ok(M::zero())
+}
+
+/// `unwrap_or`, supplying a default in place of `None`.
+///
+/// This is Java's `map.getOrDefault(product, 0L)`, or
+/// `optional.orElse(0L)`. It suits this rule because a missing product and
+/// a count of zero cost the same, so the two cases need not be told apart.
+///
+/// `copied` first turns the `Option<&u64>` into an `Option<u64>`, as the
+/// default, `0`, is a number and not a reference to one.
+pub fn with_unwrap_or<M: Money>(counts: &Counts, product: char, cost: M,
+                                policy: OverflowPolicy) -> Result<M, Overflow> {
+    let count = counts.get(&product).copied().unwrap_or(0);
+    policy.times(cost, count)
+}
+
+/// `map_or`, turning the value into a result, or using a default for `None`.
+///
+/// The Java is `optional.map(count -> ...).orElse(...)`, in one call. As
+/// with `orElse`, the default is worked out even when it is not used, which
+/// costs nothing for a zero. For a default that takes work to make there is
+/// `map_or_else`, taking a closure instead, like `orElseGet`.
+pub fn with_map_or<M: Money>(counts: &Counts, product: char, cost: M,
+                             policy: OverflowPolicy) -> Result<M, Overflow> {
+    counts.get(&product).map_or(Ok(M::zero()), |&count| policy.times(cost, count))
+}
+
+/// A chain of combinators, each doing one small step, as in a Java
+/// `Optional` pipeline.
+///
+/// * `copied` unwraps the reference, as in `with_unwrap_or`.
+/// * `filter` keeps only a positive count, so an empty line is `None` too,
+///   like `Optional.filter`.
+/// * `map` prices the count. The result is an `Option<Result<M, Overflow>>`:
+///   maybe a price, which may have overflowed.
+/// * `transpose` swaps the two wrappers, giving a `Result<Option<M>, _>`,
+///   so that `?` can return an overflow at once. Java's `Optional` cannot
+///   carry a checked exception through `map` at all, which is why such code
+///   there ends up as an `if`.
+/// * `unwrap_or_else` turns the remaining `None` into zero.
+pub fn with_combinators<M: Money>(counts: &Counts, product: char, cost: M,
+                                  policy: OverflowPolicy) -> Result<M, Overflow> {
+    let price = counts.get(&product)
+        .copied()
+        .filter(|&count| count > 0)
+        .map(|count| policy.times(cost, count))
+        .transpose()?;
+    Ok(price.unwrap_or_else(M::zero))
+}
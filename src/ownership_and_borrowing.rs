@@ -0,0 +1,240 @@
+//! Ownership and borrowing, worked through with a shopping cart that
+//! changes as it is filled.
+//!
+//! In Java every object lives on the heap, any number of variables may
+//! refer to it, and the garbage collector frees it once none do. Rust has
+//! no garbage collector. Instead every value has exactly one *owner*, the
+//! variable or field holding it, and is freed when its owner goes out of
+//! scope. Other code reaches the value by *borrowing* it:
+//!
+//! * `&Cart`, a shared borrow, lets any number of readers look at the cart
+//!   at once, but none may change it.
+//! * `&mut Cart`, a mutable borrow, lets exactly one writer change it, and
+//!   nobody else may even look while it does.
+//! * `Cart`, passed by value, *moves* the cart: the new owner has it, and
+//!   the old variable may not be used again.
+//!
+//! The compiler's *borrow checker* enforces these rules, and rejects code
+//! that breaks them. Code that would not compile is shown below in
+//! comments, each with the error the compiler gives and why. Every one of
+//! them is a bug Java would have let through, or caught only at runtime.
+
+use crate::conversions::Sku;
+use crate::core::checkout::CheckoutError;
+use crate::core::money::Money;
+use crate::newtypes_and_units::Quantity;
+use crate::reasonable_implementation::Supermarket;
+
+/// A cart being filled, one item at a time.
+///
+/// The cart owns its `String` of items, as a Java object would hold a
+/// reference to one. The difference is that nothing else can hold that
+/// `String` too: it is freed when the `Cart` is.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Cart {
+    items: String
+}
+
+impl Cart {
+
+    /// An empty cart.
+    pub fn new() -> Cart {
+        Cart { items: String::new() }
+    }
+
+    /// Puts `product` in the cart.
+    ///
+    /// `&mut self` borrows the cart mutably for the length of the call, so
+    /// the caller's variable must be declared `let mut`:
+    ///
+    /// ```text
+    /// let cart = Cart::new();
+    /// cart.add('A');
+    /// // error[E0596]: cannot borrow `cart` as mutable, as it is not
+    /// // declared as mutable
+    /// ```
+    ///
+    /// A binding without `mut` is like a `final` variable whose object is
+    /// immutable as well, a guarantee Java's `final` does not give.
+    ///
+    /// `product` may be a `Sku` or a plain `char`; see `conversions`.
+    pub fn add(&mut self, product: impl Into<Sku>) {
+        self.items.push(product.into().into());
+    }
+
+    /// Takes one `product` out of the cart, returning whether there was one.
+    pub fn remove(&mut self, product: impl Into<Sku>) -> bool {
+        let product: char = product.into().into();
+        match self.items.rfind(product) {
+            Some(position) => {
+                self.items.remove(position);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Puts `quantity` items of `sku` in the cart.
+    ///
+    /// Both arguments are newtypes, so they cannot be given the wrong way
+    /// round. See `newtypes_and_units`.
+    pub fn add_many(&mut self, sku: Sku, quantity: Quantity) {
+        self.items.extend((0..quantity.0).map(|_| char::from(sku)));
+    }
+
+    /// How many items of `sku` the cart holds.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `u32::MAX` of them, more than any
+    /// `Quantity` can say.
+    pub fn quantity_of(&self, sku: Sku) -> Quantity {
+        let count = self.items.chars().filter(|&item| item == sku.0).count();
+        Quantity(u32::try_from(count).expect("too many items to count"))
+    }
+
+    /// The items in the cart, borrowed from it.
+    ///
+    /// The `&str` returned points into the cart's own `String`, with no
+    /// copy made, so the cart stays borrowed for as long as the `&str` is
+    /// in use. Changing the cart meanwhile would leave the `&str` pointing
+    /// at memory the `String` may have moved away from as it grew:
+    ///
+    /// ```text
+    /// let items = cart.items();
+    /// cart.add('A');
+    /// // error[E0502]: cannot borrow `cart` as mutable because it is also
+    /// // borrowed as immutable
+    /// println!("{}", items);
+    /// ```
+    ///
+    /// The same rule stops a cart changing while it is being read, which is
+    /// Java's `ConcurrentModificationException`, found when compiling:
+    ///
+    /// ```text
+    /// for product in cart.items().chars() {
+    ///     cart.add(product);  // error[E0502], as above
+    /// }
+    /// ```
+    ///
+    /// Nor may a borrow outlive the cart itself, where Java would simply
+    /// keep the object alive for as long as anything refers to it:
+    ///
+    /// ```text
+    /// let items;
+    /// {
+    ///     let cart = Cart::new();
+    ///     items = cart.items();
+    ///     // error[E0597]: `cart` does not live long enough
+    /// }
+    /// println!("{}", items);
+    /// ```
+    pub fn items(&self) -> &str {
+        &self.items
+    }
+
+    /// The number of items in the cart.
+    pub fn len(&self) -> usize {
+        self.items.chars().count()
+    }
+
+    /// Whether the cart holds nothing at all.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Empties the cart, handing its items over to the caller.
+    ///
+    /// `self`, by value, moves the cart into the call, so the `String` can
+    /// be moved out of it without a copy. The cart is gone afterwards.
+    pub fn into_items(self) -> String {
+        self.items
+    }
+}
+
+/// Puts every item of `items` into `cart`.
+///
+/// The cart is borrowed mutably: the caller keeps owning it, and gets it
+/// back, changed, when this returns. Only one mutable borrow may exist at a
+/// time, so two pieces of code can never change the same cart at once:
+///
+/// ```text
+/// let first = &mut cart;
+/// let second = &mut cart;
+/// // error[E0499]: cannot borrow `cart` as mutable more than once at a time
+/// fill(first, "A");
+/// fill(second, "B");
+/// ```
+///
+/// In Java, two threads could each be handed the same cart and fill it at
+/// once, and the result would depend on timing. Here it does not compile,
+/// whether or not threads are involved.
+pub fn fill(cart: &mut Cart, items: &str) {
+    for product in items.chars() {
+        cart.add(product);
+    }
+}
+
+/// The price of `cart` so far, leaving it to be filled further.
+///
+/// A shared borrow is enough to read the cart. `Supermarket::try_checkout`
+/// wants a `String` of its own, though, and turning the borrowed `&str`
+/// `Into` one copies the items into a new `String`: borrowing does not let
+/// this function take the cart's `String` away from it.
+pub fn subtotal<M: Money>(market: &Supermarket<M>, cart: &Cart) -> Result<M, CheckoutError> {
+    market.try_checkout(cart.items())
+}
+
+/// The price of `cart`, which is used up in paying for it.
+///
+/// Taking the cart by value moves it into this function, so it can be
+/// passed on to the market as it is, and turned `Into` its `String` of
+/// items with no copy. The caller has no
+/// cart left afterwards, and the compiler holds them to that:
+///
+/// ```text
+/// let total = pay(&market, cart);
+/// cart.add('A');
+/// // error[E0382]: borrow of moved value: `cart`
+/// ```
+///
+/// A caller who wants to keep the cart, say to pay for the same things
+/// again, must say so with `pay(&market, cart.clone())`, where Java would
+/// share one object between both and leave the copying to be remembered.
+pub fn pay<M: Money>(market: &Supermarket<M>, cart: Cart) -> Result<M, CheckoutError> {
+    market.try_checkout(cart)
+}
+
+/// A whole shopping trip: fill a cart, check the subtotal, change it, pay,
+/// and return the subtotal and the total.
+///
+/// Each step shows which kind of access it needs. The cart is borrowed
+/// mutably to change it, shared to price it, and finally moved to pay for
+/// it, after which it may not be touched.
+pub fn shopping_trip<M: Money>(market: &Supermarket<M>, items: &str,
+                               put_back: char) -> Result<(M, M), CheckoutError> {
+    let mut cart = Cart::new();
+    fill(&mut cart, items);
+    let subtotal = subtotal(market, &cart)?;
+
+    // A closure that changes the cart borrows it mutably for as long as the
+    // closure exists, so it is used and dropped before the cart is read
+    // again. A Java lambda may only capture variables that are effectively
+    // final, and cannot change them at all.
+    let mut put_one_back = || cart.remove(put_back);
+    put_one_back();
+
+    // Threads get the same treatment. A thread might run for longer than
+    // this function, so it may not borrow the cart at all:
+    //
+    //     std::thread::spawn(|| cart.add('A'));
+    //     // error[E0373]: closure may outlive the current function, but it
+    //     // borrows `cart`, which is owned by the current function
+    //
+    // `move ||` would move the cart into the thread instead, or
+    // `std::thread::scope` would let the thread borrow it, as in the
+    // `threads_and_channels` module.
+
+    let total = pay(market, cart)?;
+    Ok((subtotal, total))
+}
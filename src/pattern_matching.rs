@@ -0,0 +1,192 @@
+//! Pattern matching, compared with Java's `switch` and sealed interfaces.
+//!
+//! Java 21 caught up with much of what `match` does. A sealed interface
+//! with record subclasses is a closed set of shapes, a `switch` expression
+//! over it can take records apart, and the compiler checks that every
+//! permitted subclass has a `case`:
+//!
+//! ```text
+//! sealed interface Event permits Scanned, Voided, Paid, Refused {}
+//! record Paid(Receipt receipt, long tendered) implements Event {}
+//!
+//! String headline = switch (event) {
+//!     case Scanned(char product) -> "scanned " + product;
+//!     case Paid(Receipt(var lines, var subtotal, var adjustments, var total), long tendered)
+//!         when tendered >= total -> "paid";
+//!     ...
+//! };
+//! ```
+//!
+//! A Rust `enum` is the sealed interface and its records in one
+//! declaration, and `match` is that `switch`, with a few more tools:
+//!
+//! | Java                                   | Rust                                   |
+//! |----------------------------------------|----------------------------------------|
+//! | `case Paid p when p.tendered() > 0`    | `Event::Paid { tendered, .. } if ...`  |
+//! | `case Scanned s` (naming the whole)    | `scanned @ Event::Scanned(_)`          |
+//! | `case Paid(Receipt(var lines, ...), ...)` | `Event::Paid { receipt: Receipt { lines, .. }, .. }` |
+//! | `case 'A', 'B', 'C'`                   | `'A' \| 'B' \| 'C'`, or `'A'..='C'`    |
+//! | no counterpart                         | `[first, .., last]`, matching slices   |
+//! | `default ->`                           | `_ =>`                                 |
+//!
+//! Record patterns must list every component, where `..` skips the fields
+//! a Rust pattern does not care about. Patterns also appear outside
+//! `match`, in `let`, `if let`, `let ... else` and function parameters, as
+//! the functions below show.
+//!
+//! Exhaustiveness works the same way in both languages, and for the same
+//! reason: add a variant to the enum, or a class to the `permits` list,
+//! and every `match` without a catch-all stops compiling until it says
+//! what to do with the newcomer. A `_` arm gives that check up, so
+//! `rule_kind` below deliberately has none.
+
+use crate::core::checkout::{CartDimension, CheckoutError};
+use crate::core::money::{Money, Cents};
+use crate::enum_implementation::PricingRule;
+use crate::receipt::{Receipt, ReceiptLine};
+
+/// Something that happened at a register.
+///
+/// The variants come in each of the three shapes a Rust enum allows: like
+/// a tuple, `Scanned('A')`, like a struct, `Paid { .. }`, and, in
+/// `Opened`, with no data at all, like a Java `enum` constant.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Event<M: Money = Cents> {
+    /// The register was opened for a new cart.
+    Opened,
+    /// One item was scanned.
+    Scanned(char),
+    /// One item was taken back out of the cart.
+    Voided(char),
+    /// The cart was priced and paid for with `tendered`.
+    Paid { receipt: Receipt<M>, tendered: M },
+    /// The cart could not be priced.
+    Refused(CheckoutError)
+}
+
+/// A receipt line in words, such as `3 of B, 1 deal`.
+///
+/// Match guards, the `if` after a pattern, test what a pattern alone
+/// cannot, here whether the `Vec` of promotions is empty. The arms are
+/// tried from the top, so the arm for a single item must come before those
+/// for any quantity, and the guarded arm before the one without a guard.
+pub fn describe_line<M: Money>(line: &ReceiptLine<M>) -> String {
+    match *line {
+        // `ref` borrows `rule` from the line, rather than moving the
+        // `String` out of it, which matching through `*line` cannot do.
+        ReceiptLine { product: None, ref rule, .. } => format!("charged by {}", rule),
+        ReceiptLine { product: Some(product), quantity: 1, .. } => format!("1 of {}", product),
+        ReceiptLine { product: Some(product), quantity, ref promotions, .. } if promotions.is_empty() =>
+            format!("{} of {}", quantity, product),
+        ReceiptLine { product: Some(product), quantity, ref promotions, .. } =>
+            format!("{} of {}, {} {}", quantity, product, promotions.len(),
+                    if promotions.len() == 1 { "deal" } else { "deals" })
+    }
+}
+
+/// How a register should treat a cart of `items` items.
+///
+/// `@` binds a name to a value while also matching it against a pattern,
+/// here a range. Without it the arm would have to match `2..=10` and then
+/// use `items` again, which works for a number but not for the insides of
+/// an enum, as in `replay` below.
+pub fn lane(items: u64) -> String {
+    match items {
+        0 => "empty".to_string(),
+        1 => "express, 1 item".to_string(),
+        count @ 2..=10 => format!("express, {} items", count),
+        count => format!("staffed, {} items", count)
+    }
+}
+
+/// A one-line headline for `event`.
+///
+/// Patterns nest to any depth. The `Paid` arms reach through the event,
+/// into its receipt and then into the receipt's `Vec` of lines, matching
+/// it as a slice: `[]` is no lines, `[only]` exactly one, and
+/// `[first, .., last]` two or more, naming the ends and skipping the
+/// middle. Java's record patterns stop at the `List`.
+pub fn headline<M: Money>(event: &Event<M>) -> String {
+    // Matching on a reference, `event`, rather than `*event`, makes every
+    // name bound below a reference too, without writing `ref` each time.
+    match event {
+        Event::Opened => "register opened".to_string(),
+        // Or-patterns share an arm; every alternative must bind the same
+        // names, here `product`.
+        Event::Scanned(product) | Event::Voided(product) if !product.is_ascii_uppercase() =>
+            format!("unknown product {:?}", product),
+        Event::Scanned(product) => format!("scanned {}", product),
+        Event::Voided(product) => format!("voided {}", product),
+        Event::Paid { receipt: Receipt { lines, total, .. }, tendered } => match lines.as_slice() {
+            [] => format!("paid {} for nothing", tendered),
+            [only] => format!("paid {} for {}", total, describe_line(only)),
+            [first, .., last] => format!("paid {} for {} lines, {} to {}", total, lines.len(),
+                                         describe_line(first), describe_line(last))
+        },
+        // Matching an enum inside an enum: the dimension is a pattern too.
+        Event::Refused(CheckoutError::CartTooLarge { dimension: CartDimension::Items, limit }) =>
+            format!("refused, over {} items", limit),
+        Event::Refused(error) => format!("refused, {}", error)
+    }
+}
+
+/// The change due for a `Paid` event, or `None` for any other event, or
+/// for a payment that fell short.
+///
+/// `let ... else` destructures with a pattern that might not match, and
+/// runs the `else` block, which must leave the function, when it does not.
+/// It keeps the happy path unindented, as an early `return` after an
+/// `instanceof` check does in Java.
+pub fn change_due<M: Money>(event: &Event<M>) -> Option<M> {
+    let Event::Paid { receipt, tendered } = event else {
+        return None;
+    };
+    match tendered.partial_cmp(&receipt.total) {
+        Some(std::cmp::Ordering::Less) | None => None,
+        Some(_) => tendered.checked_minus(receipt.total)
+    }
+}
+
+/// The cart the scans and voids in `events` add up to, since the register
+/// was last opened.
+///
+/// The `@` here binds the whole `Voided` event while also taking its
+/// product out, so the event itself can be reported when there is nothing
+/// to void.
+pub fn replay<M: Money>(events: &[Event<M>]) -> Result<String, String> {
+    let mut cart = String::new();
+    for event in events {
+        match event {
+            Event::Opened => cart.clear(),
+            Event::Scanned(product) => cart.push(*product),
+            voided @ Event::Voided(product) => {
+                let Some(at) = cart.rfind(*product) else {
+                    return Err(format!("{:?} with no {} in the cart", voided, product));
+                };
+                cart.remove(at);
+            },
+            Event::Paid { .. } | Event::Refused(_) => {}
+        }
+    }
+    Ok(cart)
+}
+
+/// The name of the kind of `rule`.
+///
+/// There is no `_` arm. If `enum_implementation::PricingRule` gains a
+/// variant, say `Percentage`, this function stops compiling with "pattern
+/// `PricingRule::Percentage { .. }` not covered", and so does every other
+/// `match` like it, which is the point: the compiler lists everything that
+/// must decide what the new kind of rule means. A `_ => "other"` arm would
+/// compile, and quietly call every new rule "other".
+///
+/// Java checks a `switch` over a sealed interface the same way, but only a
+/// `switch` expression or a pattern `switch`; an old-style statement
+/// `switch` with no `default` is silently incomplete.
+pub fn rule_kind<M: Money>(rule: &PricingRule<M>) -> &'static str {
+    match rule {
+        PricingRule::Flat { .. } => "flat",
+        PricingRule::Bundle { bundle_size: 0 | 1, .. } => "flat, as a bundle",
+        PricingRule::Bundle { .. } => "bundle"
+    }
+}
@@ -0,0 +1,384 @@
+//! Pricing rules loaded at runtime from shared libraries, available with
+//! the `plugins` feature.
+//!
+//! A Java application would load a third party's promotion from a jar with
+//! a `ServiceLoader` or a `URLClassLoader`, and the JVM would check that the
+//! classes in it fit. Rust has no such runtime: a compiled library is just
+//! machine code, and nothing checks that it agrees with the host about the
+//! shape of anything. Two Rust libraries built by different compiler
+//! versions may not even lay out a `Vec` the same way.
+//!
+//! So plugins speak the C ABI, which every compiler agrees on. A plugin is a
+//! `cdylib` exporting a single function, `pricing_plugin_create`, which
+//! fills in a `RawRule`: a pointer to the plugin's own state, and a table of
+//! `extern "C"` functions to call with it. That is exactly how a C++ virtual
+//! call works under the hood, written out by hand. `PluginRule` wraps the
+//! table back up as an ordinary `PricingRule`, and `export` builds one from
+//! any `PricingRule`, so neither side needs to write unsafe code of its own.
+//! The `pricing-plugin-sample` crate in this workspace is a complete plugin.
+//!
+//! # Safety
+//!
+//! Loading a plugin runs its code inside this process, with all of this
+//! process's privileges, and nothing can check that the code keeps its side
+//! of the bargain. `Plugin::load` is therefore `unsafe`: by calling it, the
+//! caller vouches that the library
+//!
+//! * really is a pricing plugin, built against this version of `RawRule`
+//!   (`PLUGIN_ABI_VERSION` catches honest mismatches, not lies);
+//! * stays well-behaved: its functions never unwind into the host, never
+//!   write outside the buffers they are given, and may be called from any
+//!   thread at once, since `PluginRule` is `Send` and `Sync`.
+//!
+//! `export` takes care of the second point for plugins written in Rust.
+
+use std::ffi::{c_int, c_void, OsStr};
+use std::fmt;
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use libloading::Library;
+use crate::core::counting::Counts;
+use crate::core::money::{Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{Describable, PricingRule};
+
+/// The version of `RawRule` this crate understands. It changes whenever the
+/// table does, so that an old plugin is refused rather than misread.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// The name of the function every plugin exports, NUL-terminated as C
+/// expects.
+pub const FACTORY_SYMBOL: &[u8] = b"pricing_plugin_create\0";
+
+/// The call succeeded.
+pub const PLUGIN_OK: c_int = 0;
+/// The price did not fit in an `int64_t` under the given policy.
+pub const PLUGIN_OVERFLOW: c_int = 1;
+/// The plugin failed in some way of its own, such as a Rust panic.
+pub const PLUGIN_FAILED: c_int = 2;
+
+/// The signature of `pricing_plugin_create`: fill in `*rule` and return
+/// `PLUGIN_OK`, or return something else to refuse.
+pub type Factory = unsafe extern "C" fn(rule: *mut RawRule) -> c_int;
+
+/// How many of one product are in the cart, as handed to a plugin.
+///
+/// `#[repr(C)]` lays the struct out as a C compiler would, field by field
+/// in order; without it, Rust is free to reorder fields.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ItemCount {
+    /// The product, as a Unicode code point.
+    pub product: u32,
+    pub count: u64
+}
+
+/// One rule, as a plugin hands it over: its state, and the functions to
+/// call with that state.
+///
+/// Every function takes `state` back as its first argument, the way `this`
+/// is passed to a Java method. Text and lists are returned C style: the
+/// caller provides a buffer with its capacity, and the function fills in as
+/// much as fits and returns how much it would need in all.
+#[repr(C)]
+pub struct RawRule {
+    /// Must be `PLUGIN_ABI_VERSION`.
+    pub abi_version: u32,
+    /// The plugin's own data, never looked at by the host.
+    pub state: *mut c_void,
+    /// Prices `len` counts under `policy` (0 `Checked`, 1 `Saturating`,
+    /// 2 `Wrapping`), writing the price in cents to `*cents`.
+    pub price: unsafe extern "C" fn(state: *const c_void, counts: *const ItemCount, len: usize,
+                                    policy: u32, cents: *mut i64) -> c_int,
+    /// Writes the rule's description as UTF-8, without a terminating NUL.
+    pub describe: unsafe extern "C" fn(state: *const c_void, text: *mut u8,
+                                       capacity: usize) -> usize,
+    /// Writes the products the rule prices, as code points.
+    pub products: unsafe extern "C" fn(state: *const c_void, products: *mut u32,
+                                       capacity: usize) -> usize,
+    /// Frees `state`. Called exactly once, after which nothing else is.
+    pub free: unsafe extern "C" fn(state: *mut c_void)
+}
+
+/// The reasons a plugin may fail to load or to produce a rule.
+#[derive(Debug)]
+pub enum PluginError {
+    /// The library could not be loaded at all.
+    Load(libloading::Error),
+    /// The library does not export `pricing_plugin_create`.
+    MissingFactory(libloading::Error),
+    /// The plugin was built for a different version of `RawRule`.
+    AbiVersion { expected: u32, found: u32 },
+    /// `pricing_plugin_create` returned this status rather than `PLUGIN_OK`.
+    Refused(c_int)
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PluginError::Load(ref e) => write!(f, "cannot load the plugin: {}", e),
+            PluginError::MissingFactory(ref e) => write!(f, "not a pricing plugin: {}", e),
+            PluginError::AbiVersion { expected, found } =>
+                write!(f, "plugin ABI version {} is not the expected {}", found, expected),
+            PluginError::Refused(status) => write!(f, "the plugin refused with status {}", status)
+        }
+    }
+}
+
+impl Error for PluginError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            PluginError::Load(ref e) | PluginError::MissingFactory(ref e) => Some(e),
+            PluginError::AbiVersion { .. } | PluginError::Refused(_) => None
+        }
+    }
+}
+
+/// A loaded plugin library.
+///
+/// The library stays loaded for as long as this, or any rule it produced,
+/// is alive: unloading it would leave the rules' function pointers pointing
+/// at nothing. `Arc` counts the owners, like a reference count in C++.
+pub struct Plugin {
+    library: Arc<Library>,
+    factory: Factory
+}
+
+impl Plugin {
+
+    /// Loads the plugin at `path`, such as `libpromo.so` on Linux. See
+    /// `libloading::library_filename` for the name on every platform.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization code, and every later call
+    /// trusts it completely; see the module documentation for what the
+    /// library must guarantee.
+    pub unsafe fn load(path: impl AsRef<OsStr>) -> Result<Plugin, PluginError> {
+        let library = Library::new(path).map_err(PluginError::Load)?;
+        // `*symbol` copies the function pointer out of the `Symbol`, which
+        // borrows the library; `Arc` keeps the library alive instead.
+        let factory = *library.get::<Factory>(FACTORY_SYMBOL).map_err(PluginError::MissingFactory)?;
+        Ok(Plugin { library: Arc::new(library), factory })
+    }
+
+    /// Asks the plugin for a new rule.
+    pub fn rule(&self) -> Result<PluginRule, PluginError> {
+        // `MaybeUninit` is memory the plugin is about to fill in, which Rust
+        // would otherwise insist on initializing first.
+        let mut raw = std::mem::MaybeUninit::<RawRule>::uninit();
+        // SAFETY: `load` was trusted with a real plugin, whose factory fills
+        // in `*raw` whenever it returns `PLUGIN_OK`.
+        let raw = unsafe {
+            let status = (self.factory)(raw.as_mut_ptr());
+            if status != PLUGIN_OK {
+                return Err(PluginError::Refused(status));
+            }
+            raw.assume_init()
+        };
+        // SAFETY: as above, the rule comes from a trusted plugin.
+        let mut rule = unsafe { PluginRule::from_raw(raw)? };
+        rule.library = Some(Arc::clone(&self.library));
+        Ok(rule)
+    }
+}
+
+/// A rule provided by a plugin, usable wherever a `PricingRule<Cents>` is.
+pub struct PluginRule {
+    raw: RawRule,
+    /// The library providing `raw`'s functions, if it was loaded from one.
+    library: Option<Arc<Library>>
+}
+
+impl PluginRule {
+
+    /// Wraps a rule handed over through the C ABI, refusing one built for a
+    /// different `PLUGIN_ABI_VERSION`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must keep the promises in the module documentation, and its
+    /// functions must stay loaded for as long as the `PluginRule` lives.
+    pub unsafe fn from_raw(raw: RawRule) -> Result<PluginRule, PluginError> {
+        if raw.abi_version != PLUGIN_ABI_VERSION {
+            // Dropping a `PluginRule` would call `free`, which may not be
+            // there in a table of another version, so the state is leaked.
+            return Err(PluginError::AbiVersion { expected: PLUGIN_ABI_VERSION,
+                                                 found: raw.abi_version });
+        }
+        Ok(PluginRule { raw, library: None })
+    }
+}
+
+/// SAFETY: whoever built the `PluginRule` promised that its functions may be
+/// called from any thread, at the same time.
+unsafe impl Send for PluginRule {}
+unsafe impl Sync for PluginRule {}
+
+impl Drop for PluginRule {
+    fn drop(&mut self) {
+        // SAFETY: `free` is called exactly once, here, and the library is
+        // still loaded, as `self.library` is only dropped after this runs.
+        unsafe { (self.raw.free)(self.raw.state) }
+    }
+}
+
+/// Reads a C-style list from `fill`, calling it again with a larger buffer
+/// if the first one was too small.
+fn read_list<T: Copy + Default>(fill: impl Fn(*mut T, usize) -> usize) -> Vec<T> {
+    let mut buffer = vec![T::default(); 64];
+    let needed = fill(buffer.as_mut_ptr(), buffer.len());
+    if needed > buffer.len() {
+        buffer.resize(needed, T::default());
+        fill(buffer.as_mut_ptr(), buffer.len());
+    }
+    buffer.truncate(needed);
+    buffer
+}
+
+impl Describable for PluginRule {
+    fn describe(&self) -> String {
+        // SAFETY: `read_list` passes a buffer valid for `capacity` items.
+        let text = read_list(|text, capacity| unsafe {
+            (self.raw.describe)(self.raw.state, text, capacity)
+        });
+        String::from_utf8_lossy(&text).into_owned()
+    }
+}
+
+impl PricingRule<Cents> for PluginRule {
+    fn price(&self, character_counts: &Counts,
+             policy: OverflowPolicy) -> Result<Cents, Overflow> {
+        let counts: Vec<ItemCount> = character_counts.iter()
+            .map(|(&product, &count)| ItemCount { product: product as u32, count })
+            .collect();
+        let mut cents = 0;
+        // SAFETY: the buffers are valid for the lengths given, and the
+        // plugin's promises cover the rest.
+        let status = unsafe {
+            (self.raw.price)(self.raw.state, counts.as_ptr(), counts.len(),
+                             policy_code(policy), &mut cents)
+        };
+        match status {
+            PLUGIN_OK => Ok(Cents(cents)),
+            PLUGIN_OVERFLOW => Err(Overflow),
+            other => panic!("a plugin rule failed with status {}", other)
+        }
+    }
+
+    fn products(&self) -> Vec<char> {
+        // SAFETY: as in `describe`.
+        let products = read_list(|products, capacity| unsafe {
+            (self.raw.products)(self.raw.state, products, capacity)
+        });
+        products.into_iter().filter_map(char::from_u32).collect()
+    }
+}
+
+/// The number standing for `policy` in `RawRule::price`.
+fn policy_code(policy: OverflowPolicy) -> u32 {
+    match policy {
+        OverflowPolicy::Checked => 0,
+        OverflowPolicy::Saturating => 1,
+        OverflowPolicy::Wrapping => 2
+    }
+}
+
+/// Hands `rule` over through the C ABI, for a plugin to return from its
+/// `pricing_plugin_create`:
+///
+/// ```
+/// use std::ffi::c_int;
+/// use rust_for_java_devs::core::rules::FlatPrice;
+/// use rust_for_java_devs::money::Cents;
+/// use rust_for_java_devs::plugins::{self, RawRule, PLUGIN_OK};
+///
+/// /// # Safety
+/// ///
+/// /// `rule` must point to memory for one `RawRule`.
+/// #[no_mangle]
+/// pub unsafe extern "C" fn pricing_plugin_create(rule: *mut RawRule) -> c_int {
+///     let flat = FlatPrice::new('D', Cents(1000)).expect("prices are valid");
+///     rule.write(plugins::export(flat));
+///     PLUGIN_OK
+/// }
+/// ```
+///
+/// Every function in the table catches panics, which must never unwind into
+/// the host, and reports them as `PLUGIN_FAILED`, or as an empty answer.
+pub fn export<R: PricingRule<Cents> + Send + Sync + 'static>(rule: R) -> RawRule {
+    RawRule {
+        abi_version: PLUGIN_ABI_VERSION,
+        // `Box::into_raw` gives up ownership of the box for a raw pointer;
+        // `free_rule` takes it back.
+        state: Box::into_raw(Box::new(rule)) as *mut c_void,
+        price: price_rule::<R>,
+        describe: describe_rule::<R>,
+        products: products_rule::<R>,
+        free: free_rule::<R>
+    }
+}
+
+/// Copies as much of `items` as fits into a C-style buffer, returning how
+/// many there are in all.
+///
+/// # Safety
+///
+/// `buffer` must be valid for writing `capacity` items.
+unsafe fn write_list<T: Copy>(items: &[T], buffer: *mut T, capacity: usize) -> usize {
+    std::ptr::copy_nonoverlapping(items.as_ptr(), buffer, items.len().min(capacity));
+    items.len()
+}
+
+unsafe extern "C" fn price_rule<R: PricingRule<Cents>>(state: *const c_void, counts: *const ItemCount,
+                                                       len: usize, policy: u32,
+                                                       cents: *mut i64) -> c_int {
+    let rule = &*(state as *const R);
+    let counts = std::slice::from_raw_parts(counts, len);
+    // `AssertUnwindSafe` promises that nothing left half-updated by a panic
+    // will be looked at afterwards, which holds as the rule is only read.
+    let priced = panic::catch_unwind(AssertUnwindSafe(|| {
+        let counts: Counts = counts.iter()
+            .filter_map(|c| char::from_u32(c.product).map(|product| (product, c.count)))
+            .collect();
+        let policy = match policy {
+            1 => OverflowPolicy::Saturating,
+            2 => OverflowPolicy::Wrapping,
+            _ => OverflowPolicy::Checked
+        };
+        rule.price(&counts, policy)
+    }));
+    match priced {
+        Ok(Ok(Cents(price))) => {
+            *cents = price;
+            PLUGIN_OK
+        },
+        Ok(Err(Overflow)) => PLUGIN_OVERFLOW,
+        Err(_) => PLUGIN_FAILED
+    }
+}
+
+unsafe extern "C" fn describe_rule<R: PricingRule<Cents>>(state: *const c_void, text: *mut u8,
+                                                          capacity: usize) -> usize {
+    let rule = &*(state as *const R);
+    let description = panic::catch_unwind(AssertUnwindSafe(|| rule.describe())).unwrap_or_default();
+    write_list(description.as_bytes(), text, capacity)
+}
+
+unsafe extern "C" fn products_rule<R: PricingRule<Cents>>(state: *const c_void, buffer: *mut u32,
+                                                          capacity: usize) -> usize {
+    let rule = &*(state as *const R);
+    let products: Vec<u32> = panic::catch_unwind(AssertUnwindSafe(|| rule.products()))
+        .unwrap_or_default()
+        .into_iter()
+        .map(u32::from)
+        .collect();
+    write_list(&products, buffer, capacity)
+}
+
+unsafe extern "C" fn free_rule<R>(state: *mut c_void) {
+    // `Box::from_raw` takes back ownership from `export`, and dropping the
+    // box drops the rule. A panicking `Drop` is abandoned rather than let
+    // unwind into the host.
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(state as *mut R))));
+}
@@ -0,0 +1,33 @@
+//! The names most programs using this crate need, for importing all at
+//! once:
+//!
+//! ```
+//! use rust_for_java_devs::prelude::*;
+//!
+//! let market: Supermarket = Supermarket::with_rules(vec![
+//!     Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+//! ]);
+//! let mut cart = Cart::new();
+//! cart.add('A');
+//! cart.add('A');
+//! assert_eq!(Ok(Cents(4000)), market.checkout(cart.items()));
+//! ```
+//!
+//! The traits are here so that their methods can be called: `checkout`
+//! above is `Market::checkout`, which cannot be called unless `Market` is
+//! in scope. Java has no such rule, as a method belongs to its class.
+//!
+//! `Cart` is `ownership_and_borrowing::Cart`, filled through `&mut self`.
+//! `typestate_checkout` has a `Cart` of its own, which would clash with it
+//! here; it stays at its own path.
+//!
+//! This is the path to rely on. The modules behind it may be split or moved
+//! as the crate grows, and the prelude will re-export the same names from
+//! wherever they end up, as it already does from `pricing-core`.
+
+pub use crate::market::{BoxedRule, CheckoutError, Market, Supermarket};
+pub use crate::money::{Cents, Money};
+pub use crate::ownership_and_borrowing::Cart;
+pub use crate::receipt::Receipt;
+pub use crate::rules::{BestOfferPrice, BundlePrice, Describable, FlatPrice, PricingRule, RuleError,
+                        SubstitutePrice};
@@ -0,0 +1,164 @@
+//! `SupermarketBuilder`, collecting a Supermarket's rules and settings
+//! before building it in one go.
+//!
+//! `Supermarket::with_rules(rules).with_overflow_policy(...)` already
+//! chains, but every call there builds on a working Supermarket. A builder
+//! suits code that gathers the pieces bit by bit, say from a configuration
+//! file, and wants nothing usable until all of them are in.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::vec::Vec;
+use crate::core::money::{Money, Cents, OverflowPolicy};
+use crate::core::checkout::{CartLimits, NegativeTotalPolicy, PromotionBudget, QuantityLimitPolicy, Sku};
+use crate::core::counting::{ScanPolicy, CountStrategy};
+use crate::core::rules::PricingRule;
+use super::{BoxedCartRule, BoxedRule, CartRule, PriceTier, Supermarket};
+
+/// The rules and settings of a Supermarket still to be built.
+///
+/// Each method takes the builder by value and returns it, so calls chain as
+/// they would on a Java builder, and a builder cannot be used again once
+/// `build` has consumed it.
+pub struct SupermarketBuilder<M: Money = Cents> {
+    rules: Vec<BoxedRule<M>>,
+    overflow_policy: OverflowPolicy,
+    scan_policy: ScanPolicy,
+    count_strategy: CountStrategy,
+    cart_limits: CartLimits,
+    promotion_budget: PromotionBudget<M>,
+    tier_rules: BTreeMap<PriceTier, Vec<BoxedRule<M>>>,
+    age_restricted: BTreeSet<Sku>,
+    quantity_limits: BTreeMap<Sku, u64>,
+    quantity_limit_policy: QuantityLimitPolicy,
+    cart_rules: Vec<BoxedCartRule<M>>,
+    negative_total_policy: NegativeTotalPolicy
+}
+
+impl<M: Money> Default for SupermarketBuilder<M> {
+    fn default() -> SupermarketBuilder<M> {
+        SupermarketBuilder::new()
+    }
+}
+
+impl<M: Money> SupermarketBuilder<M> {
+
+    /// A builder with no rules and every setting at the same default as
+    /// `Supermarket::new`.
+    pub fn new() -> SupermarketBuilder<M> {
+        SupermarketBuilder {
+            rules: Vec::new(),
+            overflow_policy: Default::default(),
+            scan_policy: ScanPolicy::STRICT,
+            count_strategy: CountStrategy::default(),
+            cart_limits: CartLimits::UNLIMITED,
+            promotion_budget: PromotionBudget::Unlimited,
+            tier_rules: BTreeMap::new(),
+            age_restricted: BTreeSet::new(),
+            quantity_limits: BTreeMap::new(),
+            quantity_limit_policy: QuantityLimitPolicy::Refuse,
+            cart_rules: Vec::new(),
+            negative_total_policy: NegativeTotalPolicy::Clamp
+        }
+    }
+
+    /// Adds `rule` to the catalog. Rules are applied in the order added.
+    pub fn rule(mut self, rule: impl PricingRule<M> + Send + Sync + 'static) -> SupermarketBuilder<M> {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Adds every rule in `rules`, already boxed, to the catalog.
+    pub fn rules(mut self, rules: impl IntoIterator<Item = BoxedRule<M>>) -> SupermarketBuilder<M> {
+        self.rules.extend(rules);
+        self
+    }
+
+    /// Adds `rule` to `tier`'s own rules. See `Supermarket::with_tier_rules`.
+    pub fn tier_rule(mut self, tier: PriceTier, rule: impl PricingRule<M> + Send + Sync + 'static)
+                     -> SupermarketBuilder<M> {
+        self.tier_rules.entry(tier).or_default().push(Box::new(rule));
+        self
+    }
+
+    /// Marks `products` as age-restricted. See `Supermarket::checkout_strict`.
+    pub fn age_restricted(mut self, products: impl IntoIterator<Item = Sku>) -> SupermarketBuilder<M> {
+        self.age_restricted.extend(products);
+        self
+    }
+
+    /// Limits carts to `limit` of `product`. See
+    /// `Supermarket::with_quantity_limit`.
+    pub fn quantity_limit(mut self, product: Sku, limit: u64) -> SupermarketBuilder<M> {
+        self.quantity_limits.insert(product, limit);
+        self
+    }
+
+    /// Sets what is done with a cart over a quantity limit.
+    pub fn quantity_limit_policy(mut self, policy: QuantityLimitPolicy) -> SupermarketBuilder<M> {
+        self.quantity_limit_policy = policy;
+        self
+    }
+
+    /// Sets the policy for totals below zero.
+    pub fn negative_total_policy(mut self, policy: NegativeTotalPolicy) -> SupermarketBuilder<M> {
+        self.negative_total_policy = policy;
+        self
+    }
+
+    /// Sets the overflow policy.
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> SupermarketBuilder<M> {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Sets the scan policy.
+    pub fn scan_policy(mut self, policy: ScanPolicy) -> SupermarketBuilder<M> {
+        self.scan_policy = policy;
+        self
+    }
+
+    /// Sets the count strategy.
+    pub fn count_strategy(mut self, strategy: CountStrategy) -> SupermarketBuilder<M> {
+        self.count_strategy = strategy;
+        self
+    }
+
+    /// Sets the cart limits.
+    pub fn cart_limits(mut self, limits: CartLimits) -> SupermarketBuilder<M> {
+        self.cart_limits = limits;
+        self
+    }
+
+    /// Sets the promotion budget.
+    pub fn promotion_budget(mut self, budget: PromotionBudget<M>) -> SupermarketBuilder<M> {
+        self.promotion_budget = budget;
+        self
+    }
+
+    /// Adds a cart rule. Cart rules are applied in the order added.
+    pub fn cart_rule(mut self, rule: impl CartRule<M> + Send + Sync + 'static) -> SupermarketBuilder<M> {
+        self.cart_rules.push(Box::new(rule));
+        self
+    }
+
+    /// The Supermarket with everything added so far.
+    pub fn build(self) -> Supermarket<M> {
+        let market = Supermarket::with_rules(self.rules)
+            .with_overflow_policy(self.overflow_policy)
+            .with_scan_policy(self.scan_policy)
+            .with_count_strategy(self.count_strategy)
+            .with_cart_limits(self.cart_limits)
+            .with_promotion_budget(self.promotion_budget)
+            .with_age_restricted(self.age_restricted)
+            .with_quantity_limit_policy(self.quantity_limit_policy)
+            .with_negative_total_policy(self.negative_total_policy);
+        let market = self.quantity_limits.into_iter()
+            .fold(market, |market, (product, limit)| market.with_quantity_limit(product, limit));
+        let mut market = self.tier_rules.into_iter()
+            .fold(market, |market, (tier, rules)| market.with_tier_rules(tier, rules));
+        // The rules are boxed already, so they go straight in. The builder
+        // is a child module, and may use the Supermarket's private fields.
+        market.cart_rules.extend(self.cart_rules);
+        market
+    }
+}
@@ -0,0 +1,223 @@
+//! Cart-level rules: discounts on a cart as a whole, such as 5% off a
+//! spend over $100, rather than on the products in it.
+//!
+//! A `PricingRule` prices products from their counts, and never sees the
+//! total. A `CartRule` is the opposite: it sees only the total, once the
+//! pricing rules and promotions have made it, and answers with an
+//! `Adjustment` for the receipt. The `cart rules` stage of the pipeline
+//! applies them, after `promotions` and before `tax`, so tax is charged on
+//! the discounted total:
+//!
+//! ```
+//! use rust_for_java_devs::market::Supermarket;
+//! use rust_for_java_devs::reasonable_implementation::cart_rules::SpendThresholds;
+//! use rust_for_java_devs::money::Cents;
+//!
+//! let thresholds = SpendThresholds::new([(Cents(10000), 5), (Cents(20000), 10)]).unwrap();
+//! let market: Supermarket = Supermarket::demo().with_cart_rule(thresholds);
+//! assert_eq!(Ok(Cents(21600)), market.try_checkout("ABBACBBAB"));
+//! let receipt = market.receipt("BBBBBA").unwrap();
+//! assert_eq!("5% off over $100.00  -$8.50", receipt.adjustments[0].to_string());
+//! ```
+//!
+//! A `Voucher` takes a fixed amount off instead, and shows on the receipt
+//! as a negative line of its own:
+//!
+//! ```
+//! use rust_for_java_devs::market::Supermarket;
+//! use rust_for_java_devs::reasonable_implementation::cart_rules::Voucher;
+//! use rust_for_java_devs::money::Cents;
+//!
+//! let market: Supermarket = Supermarket::demo().with_cart_rule(Voucher::new(Cents(1000), Cents(5000)).unwrap());
+//! let receipt = market.receipt("BBA").unwrap();
+//! assert_eq!(Cents(11000), receipt.total);
+//! assert_eq!("voucher: $10.00 off over $50.00  -$10.00", receipt.adjustments[0].to_string());
+//! assert_eq!(Ok(Cents(5000)), market.try_checkout("B"));
+//! ```
+//!
+//! Java would likely make both kinds of rule implement one `Rule`
+//! interface, with a `getPhase()` saying when each runs. Two traits let
+//! the compiler keep them apart instead: a cart rule cannot be added to the
+//! catalog by mistake, nor a pricing rule run on a total.
+
+use std::fmt;
+use std::vec::Vec;
+use crate::core::money::{Money, Cents, Overflow};
+use crate::core::rules::{Describable, RuleError};
+use super::pipeline::{Adjustment, Stage, Ticket};
+use super::{CheckoutError, Supermarket};
+
+/// A discount on a cart as a whole, worked out from its total.
+pub trait CartRule<M: Money>: Describable {
+
+    /// The adjustment to make to a cart whose total so far is `total`, or
+    /// `None` if the rule gives nothing. A discount has a negative amount.
+    fn adjust(&self, total: M) -> Result<Option<Adjustment<M>>, Overflow>;
+}
+
+/// A cart rule of any type, owned, as the Supermarket keeps them. See
+/// `BoxedRule` for the parts of the type.
+pub type BoxedCartRule<M = Cents> = Box<dyn CartRule<M> + Send + Sync + 'static>;
+
+/// Percentages off a spend over each of several thresholds, the highest
+/// threshold passed deciding: 5% off over $100, 10% off over $200.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpendThresholds<M: Money> {
+    /// Thresholds and their percentages off, in order of threshold.
+    steps: Vec<(M, u32)>
+}
+
+impl<M: Money> SpendThresholds<M> {
+
+    /// Percentages off over the given thresholds, in any order. Refused if a
+    /// threshold is negative or a percentage is over 100.
+    pub fn new(steps: impl IntoIterator<Item = (M, u32)>) -> Result<SpendThresholds<M>, RuleError> {
+        let mut steps: Vec<(M, u32)> = steps.into_iter().collect();
+        for &(threshold, percent) in steps.iter() {
+            if threshold < M::zero() {
+                return Err(RuleError::NegativeCost);
+            }
+            if percent > 100 {
+                return Err(RuleError::PercentOutOfRange);
+            }
+        }
+        // Money is only `PartialOrd`, so there is no `sort_by_key`; every
+        // threshold has just been compared with zero, so none is
+        // incomparable.
+        steps.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(SpendThresholds { steps })
+    }
+
+    /// The highest threshold `total` is over, and its percentage off.
+    fn step_for(&self, total: M) -> Option<(M, u32)> {
+        self.steps.iter().rev().find(|&&(threshold, _)| total > threshold).copied()
+    }
+}
+
+/// One step, such as `5% off over $100.00`.
+fn describe_step<M: Money>(threshold: M, percent: u32) -> String {
+    format!("{}% off over {}", percent, threshold)
+}
+
+impl<M: Money> Describable for SpendThresholds<M> {
+    fn describe(&self) -> String {
+        let steps: Vec<String> = self.steps.iter()
+            .map(|&(threshold, percent)| describe_step(threshold, percent))
+            .collect();
+        steps.join(", ")
+    }
+}
+
+impl<M: Money> fmt::Display for SpendThresholds<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.describe())
+    }
+}
+
+impl<M: Money> CartRule<M> for SpendThresholds<M> {
+    fn adjust(&self, total: M) -> Result<Option<Adjustment<M>>, Overflow> {
+        let Some((threshold, percent)) = self.step_for(total) else {
+            return Ok(None);
+        };
+        let off = total.checked_percent(percent).ok_or(Overflow)?;
+        Ok(Some(Adjustment {
+            label: describe_step(threshold, percent),
+            amount: M::zero().checked_minus(off).ok_or(Overflow)?
+        }))
+    }
+}
+
+/// A voucher for a fixed amount off a spend over a minimum: $10 off orders
+/// over $50.
+///
+/// A voucher never takes off more than the total it is applied to, so one
+/// worth more than a cart makes it free rather than owing the customer.
+/// With a minimum spend of at least the voucher's value that cannot
+/// happen, but an earlier cart rule may have brought the total down, and
+/// the minimum may be zero.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Voucher<M: Money> {
+    value: M,
+    minimum_spend: M
+}
+
+impl<M: Money> Voucher<M> {
+
+    /// `value` off any total over `minimum_spend`, refused if either is
+    /// negative.
+    pub fn new(value: M, minimum_spend: M) -> Result<Voucher<M>, RuleError> {
+        if value < M::zero() || minimum_spend < M::zero() {
+            return Err(RuleError::NegativeCost);
+        }
+        Ok(Voucher { value, minimum_spend })
+    }
+
+    /// How much the voucher is worth.
+    pub fn value(&self) -> M {
+        self.value
+    }
+
+    /// The total it must be spent over.
+    pub fn minimum_spend(&self) -> M {
+        self.minimum_spend
+    }
+}
+
+impl<M: Money> Describable for Voucher<M> {
+    fn describe(&self) -> String {
+        format!("voucher: {} off over {}", self.value, self.minimum_spend)
+    }
+}
+
+impl<M: Money> fmt::Display for Voucher<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.describe())
+    }
+}
+
+impl<M: Money> CartRule<M> for Voucher<M> {
+    fn adjust(&self, total: M) -> Result<Option<Adjustment<M>>, Overflow> {
+        if total <= self.minimum_spend || self.value == M::zero() {
+            return Ok(None);
+        }
+        let off = if self.value < total { self.value } else { total };
+        Ok(Some(Adjustment {
+            label: self.describe(),
+            amount: M::zero().checked_minus(off).ok_or(Overflow)?
+        }))
+    }
+}
+
+/// Applies every cart rule of the Supermarket, in the order they were
+/// added, each to the total the ones before it left.
+pub struct CartRules;
+
+impl<M: Money> Stage<M> for CartRules {
+    fn name(&self) -> &str {
+        "cart rules"
+    }
+
+    fn run(&self, market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+        for rule in market.cart_rules.iter() {
+            if let Some(adjustment) = rule.adjust(ticket.total)? {
+                ticket.adjust(adjustment.label, adjustment.amount)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<M: Money> Supermarket<M> {
+
+    /// Adds a cart rule, returning the updated Supermarket. Cart rules are
+    /// applied in the order added.
+    pub fn with_cart_rule(mut self, rule: impl CartRule<M> + Send + Sync + 'static) -> Supermarket<M> {
+        self.cart_rules.push(Box::new(rule));
+        self
+    }
+
+    /// The cart rules, in the order they are applied.
+    pub fn cart_rules(&self) -> impl Iterator<Item = &(dyn CartRule<M> + Send + Sync)> {
+        self.cart_rules.iter().map(|rule| &**rule)
+    }
+}
@@ -0,0 +1,422 @@
+//! The stages a cart passes through on its way to a total.
+//!
+//! `Supermarket::try_checkout` hands every cart to a `Pipeline`, which runs
+//! its stages in order, each one working on a shared `Ticket`:
+//!
+//! 1. `normalize` checks the cart's item codes and reads it according to
+//!    the scan policy;
+//! 2. `count` tallies the items and enforces the item and quantity limits;
+//! 3. `promotions` applies the pricing rules, within the promotion budget,
+//!    and matches the customer's competitor prices;
+//! 4. `cart rules` applies the discounts on the cart as a whole;
+//! 5. `tax` adds any tax, none by default;
+//! 6. `rounding` rounds the total, not at all by default.
+//!
+//! Every stage is a trait object, so an integrator can replace one, wrap
+//! one in extra behavior, or insert new ones, such as an employee discount,
+//! without forking the checkout code. A Java developer might recognize the
+//! shape from servlet filters or Spring's interceptor chains.
+//!
+//! This module lives in its own file, `src/reasonable_implementation/
+//! pipeline.rs`, yet is a child of `reasonable_implementation`. Unlike a
+//! Java subpackage, a child module may use its parent's private items, so
+//! the standard stages read the Supermarket's private fields directly.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use crate::core::checkout::PromotionBudget;
+use crate::core::counting::check_items;
+use crate::core::rules::total;
+use crate::core::money::{Money, OverflowPolicy, Overflow};
+use super::{Supermarket, CheckoutError, PriceTier};
+
+/// A change to the running total made after the rules have been applied,
+/// such as a tax or a discount, kept for the receipt.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Adjustment<M: Money> {
+    pub label: String,
+    /// Positive for charges, negative for discounts.
+    pub amount: M
+}
+
+/// An adjustment as a receipt shows it, such as `sales tax  $1.70`.
+impl<M: Money> fmt::Display for Adjustment<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}  {}", self.label, self.amount)
+    }
+}
+
+/// Everything the stages know about the cart being priced. Each stage reads
+/// what the earlier ones left and adds its own part.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Ticket<M: Money> {
+    /// The cart's items, as written until `normalize` has tidied them.
+    pub items: String,
+    /// How many of each item, once `count` has run.
+    pub counts: super::Counts,
+    /// The sum of the rules' prices, once `promotions` has run.
+    pub subtotal: M,
+    /// Every later change to the total, in the order it was made.
+    pub adjustments: Vec<Adjustment<M>>,
+    /// The running total: the subtotal plus every adjustment.
+    pub total: M,
+    /// How much of the savings went back on the total through `take_back`,
+    /// so that reporting can tell it apart from a charge such as tax.
+    pub taken_back: M,
+    /// The Supermarket's overflow policy, for stages doing arithmetic.
+    pub policy: OverflowPolicy,
+    /// The price tier the cart is priced at: `Retail`, unless it was
+    /// checked out with `Supermarket::checkout_as`.
+    pub tier: PriceTier,
+    /// A competitor's price for one of each product, to be matched: none,
+    /// unless the cart was checked out with `Supermarket::checkout_as`.
+    pub competitor_prices: BTreeMap<char, M>
+}
+
+impl<M: Money> Ticket<M> {
+
+    /// A fresh ticket for `items`, with nothing counted or priced yet.
+    pub fn new(items: String, policy: OverflowPolicy) -> Ticket<M> {
+        Ticket {
+            items,
+            counts: super::Counts::new(),
+            subtotal: M::zero(),
+            adjustments: Vec::new(),
+            total: M::zero(),
+            taken_back: M::zero(),
+            policy,
+            tier: PriceTier::Retail,
+            competitor_prices: BTreeMap::new()
+        }
+    }
+
+    /// Adds `amount` to the total and records it as an adjustment.
+    ///
+    /// `impl Into<String>` accepts a `String` or a `&str` alike.
+    pub fn adjust(&mut self, label: impl Into<String>, amount: M) -> Result<(), Overflow> {
+        self.total = self.policy.plus(self.total, amount)?;
+        self.adjustments.push(Adjustment { label: label.into(), amount });
+        Ok(())
+    }
+
+    /// Adds `amount` to the total like `adjust`, but as savings taken back,
+    /// such as a promotion beyond the budget, rather than as a new charge.
+    pub fn take_back(&mut self, label: impl Into<String>, amount: M) -> Result<(), Overflow> {
+        self.adjust(label, amount)?;
+        self.taken_back = self.policy.plus(self.taken_back, amount)?;
+        Ok(())
+    }
+}
+
+/// One step of checkout.
+///
+/// A stage receives the Supermarket running it, for its configuration, and
+/// the ticket to work on. It must be `Send + Sync`, like everything else a
+/// Supermarket holds, so that carts can be priced on several threads.
+pub trait Stage<M: Money>: Send + Sync {
+
+    /// The name by which the stage is found in a `Pipeline`.
+    fn name(&self) -> &str;
+
+    /// Does this stage's part of pricing the cart.
+    fn run(&self, market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError>;
+}
+
+/// A boxed stage, as stored in a `Pipeline`.
+pub type BoxedStage<M> = Box<dyn Stage<M> + 'static>;
+
+/// Refuses a cart holding anything but single item codes, as
+/// `counting::check_items` decides, then reads it through the Supermarket's
+/// scan policy, dropping skipped separators and fixing the case of item
+/// codes.
+pub struct Normalize;
+
+impl<M: Money> Stage<M> for Normalize {
+    fn name(&self) -> &str {
+        "normalize"
+    }
+
+    fn run(&self, market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+        // The check comes first, on the cart exactly as written, so that the
+        // error gives the offending item's position in it.
+        check_items(&ticket.items)?;
+        let policy = market.scan_policy;
+        // `collect` can build a `String` straight from an iterator of chars.
+        ticket.items = ticket.items.chars().filter_map(|c| policy.scan(c)).collect();
+        Ok(())
+    }
+}
+
+/// Counts the items with the Supermarket's count strategy, enforcing its
+/// item limit and quantity limits, and tells its observers what was found.
+///
+/// A product trimmed to its quantity limit is noted as an adjustment of
+/// nothing, so that the receipt shows it.
+pub struct Count;
+
+impl<M: Money> Stage<M> for Count {
+    fn name(&self) -> &str {
+        "count"
+    }
+
+    fn run(&self, market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+        let mut counts = market.count_strategy.count_items(&ticket.items);
+        market.check_item_limit(&counts)?;
+        for trimmed in market.limit_quantities(&mut counts)? {
+            ticket.adjust(trimmed.note(), M::zero())?;
+        }
+        for observer in market.observers.iter() {
+            for (&item, &count) in counts.iter() {
+                observer.on_item_counted(item, count);
+            }
+        }
+        ticket.counts = counts;
+        Ok(())
+    }
+}
+
+/// Applies every pricing rule of the ticket's tier, telling the
+/// Supermarket's observers about each one, and sets both the subtotal and
+/// the total. Promotions beyond the Supermarket's `PromotionBudget` are then
+/// charged back, and competitor prices matched, as adjustments.
+pub struct Promotions;
+
+impl<M: Money> Stage<M> for Promotions {
+    fn name(&self) -> &str {
+        "promotions"
+    }
+
+    fn run(&self, market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+        // This adds up the rules' prices just as `core::rules::total`
+        // does, explained in detail there, but also tells the observers
+        // about each rule along the way.
+        //
+        // `sum` stops at the first overflow, and `map` is lazy, so no
+        // observer hears of a rule after it. The error is an `Overflow`,
+        // which `?` converts into a `CheckoutError` on the way out, thanks
+        // to a `From` impl.
+        let policy = ticket.policy;
+        let counts = &ticket.counts;
+        // The budget needs every rule's price again. Rules may count their
+        // uses, as `interior_mutability` shows, so they are kept rather than
+        // asked twice; with no budget, nothing is pushed, and a `Vec` that
+        // is never pushed to never allocates.
+        let budgeted = market.promotion_budget != PromotionBudget::Unlimited;
+        let mut prices = Vec::new();
+        let subtotal: Result<M, Overflow> = market.rules_for(ticket.tier).map(|rule| {
+            let amount = rule.price(counts, policy);
+            if let Ok(amount) = amount {
+                if budgeted {
+                    prices.push(amount);
+                }
+                // `log` is a *facade*, like SLF4J: the library only emits
+                // records, and the application picks the logger that prints
+                // them. `#[cfg]` on a statement compiles it only with the
+                // `logging` feature, so without it there is no trace at all.
+                #[cfg(feature = "logging")]
+                log::trace!("rule {:?} charged {}", rule.describe(), amount);
+                for observer in market.observers.iter() {
+                    observer.on_rule_applied(rule, amount);
+                }
+            }
+            policy.charge(amount)
+        }).sum();
+        let subtotal = subtotal?;
+        ticket.subtotal = subtotal;
+        ticket.total = subtotal;
+        if budgeted {
+            trim_to_budget(market, ticket, &prices)?;
+        }
+        match_prices(market, ticket)
+    }
+}
+
+/// Discounts each product the ticket has a lower competitor price for down
+/// to that price.
+///
+/// A product's own price is what its rules charge for it alone, as
+/// `Supermarket::price_of` works it out, offers included: a competitor
+/// selling B at $45.00 each does not beat five for $150.00. The rules see
+/// only counts, not customers, so the match is an adjustment rather than a
+/// rule.
+fn match_prices<M: Money>(market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+    let policy = ticket.policy;
+    let mut matches = Vec::new();
+    for (&product, &competitor) in ticket.competitor_prices.iter() {
+        let Some(&count) = ticket.counts.get(&product) else {
+            continue;
+        };
+        let alone = super::Counts::from([(product, count)]);
+        let own = total(market.rules_for(ticket.tier).filter(|rule| rule.applies_to(product)), &alone, policy)?;
+        let matched = policy.times(competitor, count)?;
+        if matched < own {
+            let saving = M::zero().checked_minus(own.checked_minus(matched).ok_or(Overflow)?).ok_or(Overflow)?;
+            matches.push((format!("price match: {} at {} each", product, competitor), saving));
+        }
+    }
+    for (label, saving) in matches {
+        ticket.adjust(label, saving)?;
+    }
+    Ok(())
+}
+
+/// Charges back whatever the rules gave away beyond the promotion budget,
+/// the rules having charged `prices`, in order.
+///
+/// The promotions of the rules applied last are trimmed first, each by as
+/// much of the excess as it gave away, so the catalog's order decides,
+/// whatever the cart. Each trim is an adjustment naming its rule, which
+/// shows on the receipt.
+fn trim_to_budget<M: Money>(market: &Supermarket<M>, ticket: &mut Ticket<M>, prices: &[M])
+                            -> Result<(), CheckoutError> {
+    let policy = ticket.policy;
+    let counts = &ticket.counts;
+    let rules: Vec<_> = market.rules_for(ticket.tier).collect();
+    let list_prices = rules.iter()
+        .map(|rule| rule.list_price(counts, policy))
+        .collect::<Result<Vec<M>, Overflow>>()?;
+    let list_price: Result<M, Overflow> = list_prices.iter().map(|&amount| policy.charge(Ok(amount))).sum();
+    let list_price = list_price?;
+    let Some(cap) = market.promotion_budget.cap(list_price) else {
+        return Ok(());
+    };
+    let given = list_price.checked_minus(ticket.subtotal).ok_or(Overflow)?;
+    let mut excess = given.checked_minus(cap).ok_or(Overflow)?;
+    let mut trims = Vec::new();
+    for ((rule, &list), &price) in rules.iter().zip(&list_prices).zip(prices).rev() {
+        if excess <= M::zero() {
+            break;
+        }
+        let discount = list.checked_minus(price).ok_or(Overflow)?;
+        let trim = if discount < excess { discount } else { excess };
+        if trim > M::zero() {
+            excess = excess.checked_minus(trim).ok_or(Overflow)?;
+            trims.push((format!("promotion budget: {}", rule.describe()), trim));
+        }
+    }
+    // Found last rule first, but listed in catalog order, as receipt lines
+    // are.
+    for (label, trim) in trims.into_iter().rev() {
+        ticket.take_back(label, trim)?;
+    }
+    Ok(())
+}
+
+/// A stage that does nothing but hold a place in the pipeline under the
+/// given name. The standard `tax` and `rounding` stages are placeholders,
+/// waiting to be replaced by an integrator who knows the local rules.
+pub struct Placeholder(pub &'static str);
+
+impl<M: Money> Stage<M> for Placeholder {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    fn run(&self, _market: &Supermarket<M>, _ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+        Ok(())
+    }
+}
+
+/// A pipeline had no stage of the given name.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UnknownStage(pub String);
+
+impl fmt::Display for UnknownStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no stage named {:?}", self.0)
+    }
+}
+
+impl Error for UnknownStage {}
+
+/// The stages of checkout, in the order they run.
+pub struct Pipeline<M: Money> {
+    stages: Vec<BoxedStage<M>>
+}
+
+impl<M: Money> Pipeline<M> {
+
+    /// The six standard stages: `normalize`, `count`, `promotions`,
+    /// `cart rules`, `tax` and `rounding`.
+    pub fn standard() -> Pipeline<M> {
+        Pipeline {
+            stages: vec!(
+                Box::new(Normalize),
+                Box::new(Count),
+                Box::new(Promotions),
+                Box::new(super::cart_rules::CartRules),
+                Box::new(Placeholder("tax")),
+                Box::new(Placeholder("rounding"))
+            )
+        }
+    }
+
+    /// The names of the stages, in the order they run.
+    pub fn names(&self) -> Vec<&str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    /// Runs every stage in turn on `ticket`, stopping at the first error.
+    pub fn run(&self, market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+        for stage in self.stages.iter() {
+            stage.run(market, ticket)?;
+        }
+        Ok(())
+    }
+
+    /// Where the stage called `name` is, or an `UnknownStage` error.
+    ///
+    /// `ok_or_else` is `ok_or` with the error built by a closure, so the
+    /// `String` is only allocated when the stage is missing.
+    fn position(&self, name: &str) -> Result<usize, UnknownStage> {
+        self.stages.iter()
+            .position(|stage| stage.name() == name)
+            .ok_or_else(|| UnknownStage(name.to_string()))
+    }
+
+    /// Replaces the stage called `name`.
+    pub fn replace(&mut self, name: &str, stage: impl Stage<M> + 'static) -> Result<(), UnknownStage> {
+        let index = self.position(name)?;
+        self.stages[index] = Box::new(stage);
+        Ok(())
+    }
+
+    /// Replaces the stage called `name` with whatever `wrapper` makes of
+    /// it, typically a stage of its own holding the original and running
+    /// extra code around it.
+    ///
+    /// `wrapper` is an `FnOnce`, a closure that may be called at most once.
+    /// That lets it take ownership of the original stage.
+    pub fn wrap<F>(&mut self, name: &str, wrapper: F) -> Result<(), UnknownStage>
+        where F: FnOnce(BoxedStage<M>) -> BoxedStage<M>
+    {
+        let index = self.position(name)?;
+        // `remove` and `insert` move the stage out and back, because a Vec
+        // cannot be left with a hole while the wrapper is running.
+        let original = self.stages.remove(index);
+        self.stages.insert(index, wrapper(original));
+        Ok(())
+    }
+
+    /// Adds `stage` to run just before the stage called `name`.
+    pub fn insert_before(&mut self, name: &str, stage: impl Stage<M> + 'static) -> Result<(), UnknownStage> {
+        let index = self.position(name)?;
+        self.stages.insert(index, Box::new(stage));
+        Ok(())
+    }
+
+    /// Adds `stage` to run just after the stage called `name`.
+    pub fn insert_after(&mut self, name: &str, stage: impl Stage<M> + 'static) -> Result<(), UnknownStage> {
+        let index = self.position(name)?;
+        self.stages.insert(index + 1, Box::new(stage));
+        Ok(())
+    }
+}
+
+impl<M: Money> Default for Pipeline<M> {
+    fn default() -> Pipeline<M> {
+        Pipeline::standard()
+    }
+}
@@ -0,0 +1,102 @@
+//! Limits on how many of a product one cart may hold, such as two per
+//! customer during a shortage, and what to do with a cart holding more.
+//!
+//! The `QuantityLimitPolicy` decides: `Refuse` fails the checkout with
+//! `CheckoutError::QuantityLimitExceeded`, saying which product and how
+//! many, and `Trim` sells up to the limit, leaving the rest out and noting
+//! it on the receipt as an adjustment of nothing:
+//!
+//! ```
+//! use rust_for_java_devs::conversions::Sku;
+//! use rust_for_java_devs::market::{CheckoutError, QuantityLimitPolicy, Supermarket};
+//! use rust_for_java_devs::money::Cents;
+//!
+//! let market: Supermarket = Supermarket::demo().with_quantity_limit(Sku('C'), 2);
+//! assert_eq!(Err(CheckoutError::QuantityLimitExceeded { product: Sku('C'), limit: 2, quantity: 3 }),
+//!            market.try_checkout("CCC"));
+//!
+//! let market = market.with_quantity_limit_policy(QuantityLimitPolicy::Trim);
+//! let receipt = market.receipt("CCC").unwrap();
+//! assert_eq!(Cents(6000), receipt.total);
+//! assert_eq!("C: limited to 2 per cart, 1 left out  $0.00", receipt.adjustments[0].to_string());
+//! ```
+//!
+//! The limits are applied by the `count` stage, as soon as the cart has
+//! been counted, so every later stage sees the trimmed counts.
+
+use std::vec::Vec;
+use crate::core::checkout::{QuantityLimitPolicy, Sku};
+use crate::core::money::Money;
+use super::{CheckoutError, Counts, Supermarket};
+
+/// A product a cart held too many of, trimmed to its limit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) struct Trimmed {
+    pub product: char,
+    pub limit: u64,
+    /// How many were left out.
+    pub removed: u64
+}
+
+impl Trimmed {
+
+    /// The note on the receipt, such as `C: limited to 2 per cart, 1 left
+    /// out`.
+    pub fn note(&self) -> String {
+        format!("{}: limited to {} per cart, {} left out", self.product, self.limit, self.removed)
+    }
+}
+
+impl<M: Money> Supermarket<M> {
+
+    /// Limits carts to `limit` of `product`, returning the updated
+    /// Supermarket. A second limit for the same product replaces the first.
+    pub fn with_quantity_limit(mut self, product: Sku, limit: u64) -> Supermarket<M> {
+        self.quantity_limits.insert(char::from(product), limit);
+        self
+    }
+
+    /// Replaces what is done with a cart over a quantity limit, returning
+    /// the updated Supermarket.
+    pub fn with_quantity_limit_policy(mut self, policy: QuantityLimitPolicy) -> Supermarket<M> {
+        self.quantity_limit_policy = policy;
+        self
+    }
+
+    /// The most of `product` one cart may hold, if it is limited.
+    pub fn quantity_limit(&self, product: Sku) -> Option<u64> {
+        self.quantity_limits.get(&char::from(product)).copied()
+    }
+
+    /// Holds `counts` to the quantity limits: refuses them, under `Refuse`,
+    /// or trims them, under `Trim`, returning what was trimmed, in order of
+    /// product.
+    pub(super) fn limit_quantities(&self, counts: &mut Counts) -> Result<Vec<Trimmed>, CheckoutError> {
+        let mut trimmed = Vec::new();
+        for (&product, &limit) in self.quantity_limits.iter() {
+            let Some(count) = counts.get_mut(&product) else {
+                continue;
+            };
+            if *count <= limit {
+                continue;
+            }
+            match self.quantity_limit_policy {
+                QuantityLimitPolicy::Refuse => {
+                    return Err(CheckoutError::QuantityLimitExceeded {
+                        product: Sku(product),
+                        limit,
+                        quantity: *count
+                    });
+                },
+                QuantityLimitPolicy::Trim => {
+                    trimmed.push(Trimmed { product, limit, removed: *count - limit });
+                    *count = limit;
+                }
+            }
+        }
+        // A limit of zero trims a product away entirely, and the counts of
+        // a cart only ever hold products it has.
+        counts.retain(|_, &mut count| count > 0);
+        Ok(trimmed)
+    }
+}
@@ -0,0 +1,72 @@
+//! Age-restricted products, and the strict checkout refusing to sell them
+//! to a customer whose age has not been verified.
+//!
+//! A Java register would likely throw an `AgeVerificationException` from
+//! somewhere deep in its checkout, and count on every caller to catch it.
+//! Here the refusal is a `CheckoutError` like any other, which a caller
+//! matching on the error must handle or deliberately pass over with `_`,
+//! and it names every restricted product in the cart, so the cashier can
+//! ask once rather than once per item.
+//!
+//! ```
+//! use rust_for_java_devs::conversions::Sku;
+//! use rust_for_java_devs::market::{CheckoutError, CustomerContext, Supermarket};
+//! use rust_for_java_devs::money::Cents;
+//!
+//! let market: Supermarket = Supermarket::demo().with_age_restricted([Sku('C')]);
+//! let shopper = CustomerContext::default();
+//! assert_eq!(Err(CheckoutError::AgeVerificationRequired(vec![Sku('C')])),
+//!            market.checkout_strict("ABC", &shopper));
+//! assert_eq!(Ok(Cents(10000)), market.checkout_strict("ABC", &shopper.with_verified_age()));
+//! ```
+//!
+//! Only `checkout_strict` checks. `Market::checkout` knows nothing of the
+//! customer, and so cannot tell whether a sale is allowed; a register
+//! selling restricted products should check out through `checkout_strict`
+//! alone.
+
+use std::vec::Vec;
+use crate::core::checkout::Sku;
+use crate::core::counting::check_items;
+use crate::core::money::Money;
+use super::{CheckoutError, CustomerContext, Supermarket};
+
+impl<M: Money> Supermarket<M> {
+
+    /// Marks `products` as age-restricted, returning the updated
+    /// Supermarket.
+    pub fn with_age_restricted(mut self, products: impl IntoIterator<Item = Sku>) -> Supermarket<M> {
+        self.age_restricted.extend(products.into_iter().map(char::from));
+        self
+    }
+
+    /// Whether `product` may only be sold once the customer's age has been
+    /// verified.
+    pub fn is_age_restricted(&self, product: Sku) -> bool {
+        self.age_restricted.contains(&char::from(product))
+    }
+
+    /// Prices a cart like `checkout_as`, unless it holds age-restricted
+    /// products and `customer`'s age has not been verified, in which case
+    /// it fails with `CheckoutError::AgeVerificationRequired`, naming them.
+    ///
+    /// The cart is read with the scan policy before anything is priced, so
+    /// a refused sale is not a checkout: the observers are not told of it.
+    pub fn checkout_strict(&self, items: &str, customer: &CustomerContext<M>) -> Result<M, CheckoutError> {
+        if !customer.age_verified && !self.age_restricted.is_empty() {
+            self.check_byte_limit(items)?;
+            check_items(items)?;
+            let counts = self.scan_policy.count_items::<u64>(items);
+            // The counts are a `BTreeMap`, so the products come out sorted,
+            // each once.
+            let restricted: Vec<Sku> = counts.keys()
+                .filter(|product| self.age_restricted.contains(product))
+                .map(|&product| Sku(product))
+                .collect();
+            if !restricted.is_empty() {
+                return Err(CheckoutError::AgeVerificationRequired(restricted));
+            }
+        }
+        self.checkout_as(items, customer)
+    }
+}
@@ -0,0 +1,80 @@
+//! Checkout metrics, recorded through the `metrics` facade when the crate is
+//! built with the `metrics` feature.
+//!
+//! Like `log`, `metrics` is only a facade: the library records values under
+//! well-known names, and the application installs a *recorder*, such as a
+//! Prometheus exporter, to collect them. Without one, every call below is a
+//! cheap no-op. The arrangement resembles Micrometer's global registry in
+//! the Java world.
+//!
+//! The names are public constants so that dashboards and tests can refer to
+//! them without copying strings around.
+
+use std::time::Instant;
+use crate::core::money::Money;
+use super::{Supermarket, CheckoutError};
+use super::pipeline::Ticket;
+
+/// A counter of checkouts, labelled `outcome` = `ok` or `error`.
+pub const CHECKOUTS: &str = "pricing_checkouts_total";
+
+/// A counter of items no rule in the catalog puts a price on.
+pub const UNKNOWN_ITEMS: &str = "pricing_unknown_items_total";
+
+/// A histogram of how long each checkout took, in seconds.
+pub const CHECKOUT_SECONDS: &str = "pricing_checkout_seconds";
+
+/// A histogram of how much each receipt saved, in whole currency units: the
+/// offers' savings over buying every item alone, plus any discount applied
+/// by a later stage, less whatever was taken back, such as promotions
+/// beyond the promotion budget.
+pub const DISCOUNT: &str = "pricing_receipt_discount";
+
+/// A counter of `remote::CachedPriceSource` lookups, labelled `outcome` =
+/// `hit` or `miss`.
+pub const PRICE_CACHE: &str = "pricing_price_cache_total";
+
+/// Records the outcome of one checkout that began at `started`.
+///
+/// Failed checkouts are counted and timed, but their tickets are incomplete,
+/// so neither their items nor their discounts are recorded.
+pub(super) fn record<M: Money>(market: &Supermarket<M>, outcome: &Result<Ticket<M>, CheckoutError>,
+                               started: Instant) {
+    metrics::histogram!(CHECKOUT_SECONDS).record(started.elapsed().as_secs_f64());
+    let ticket = match outcome {
+        Ok(ticket) => ticket,
+        Err(_) => {
+            metrics::counter!(CHECKOUTS, "outcome" => "error").increment(1);
+            return;
+        }
+    };
+    metrics::counter!(CHECKOUTS, "outcome" => "ok").increment(1);
+
+    // `flat_map` concatenates the Vec each rule returns, like Java's
+    // `Stream.flatMap`.
+    let priced: Vec<char> = market.rules_for(ticket.tier).flat_map(|rule| rule.products()).collect();
+    let unknown: u64 = ticket.counts.iter()
+        .filter(|(item, _)| !priced.contains(item))
+        .map(|(_, &count)| count)
+        .sum();
+    if unknown > 0 {
+        metrics::counter!(UNKNOWN_ITEMS).increment(unknown);
+    }
+
+    // The discount is reporting rather than pricing, so it is worked out in
+    // `f64`, where a list price too large for the money backend is simply
+    // left out.
+    let mut discount = -ticket.subtotal.to_f64();
+    for rule in market.rules_for(ticket.tier) {
+        if let Ok(list_price) = rule.list_price(&ticket.counts, ticket.policy) {
+            discount += list_price.to_f64();
+        }
+    }
+    for adjustment in ticket.adjustments.iter().filter(|a| a.amount < M::zero()) {
+        discount -= adjustment.amount.to_f64();
+    }
+    // Positive adjustments are charges, such as tax, which are no part of
+    // the discount, except those taking savings back.
+    discount -= ticket.taken_back.to_f64();
+    metrics::histogram!(DISCOUNT).record(discount);
+}
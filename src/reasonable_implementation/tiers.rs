@@ -0,0 +1,170 @@
+//! Price tiers: retail, member, employee and wholesale prices from one
+//! catalog, chosen per customer at checkout.
+//!
+//! A Java shop would likely keep one `Supermarket` per tier in a
+//! `Map<Tier, Supermarket>`, four catalogs to edit for every price change,
+//! drifting apart with each one somebody forgot. Here a tier holds only
+//! the rules it changes. A product it has no rule for keeps its retail
+//! rule, so a member discount on B leaves A and C to the retail catalog.
+//!
+//! ```
+//! use rust_for_java_devs::market::{CustomerContext, PriceTier, Supermarket};
+//! use rust_for_java_devs::reasonable_implementation::FlatPrice;
+//! use rust_for_java_devs::money::Cents;
+//!
+//! let market: Supermarket = Supermarket::demo()
+//!     .with_tier_rules(PriceTier::Member, vec![Box::new(FlatPrice::new('B', Cents(4000)).unwrap())]);
+//! assert_eq!(Ok(Cents(24000)), market.checkout_as("ABBACBBAB", &CustomerContext::default()));
+//! assert_eq!(Ok(Cents(29000)), market.checkout_as("ABBACBBAB", &CustomerContext::new(PriceTier::Member)));
+//! ```
+//!
+//! `Market::checkout` knows nothing of customers and prices at retail, so
+//! code written against the trait keeps working unchanged.
+//!
+//! A customer may also bring a competitor's prices, which the store
+//! guarantees to match: a product priced lower elsewhere is discounted to
+//! that price, as a `price match` adjustment on the receipt.
+//!
+//! ```
+//! use rust_for_java_devs::conversions::Sku;
+//! use rust_for_java_devs::market::{CustomerContext, PriceTier, Supermarket};
+//! use rust_for_java_devs::money::Cents;
+//!
+//! let market: Supermarket = Supermarket::demo();
+//! let shopper = CustomerContext::new(PriceTier::Retail).with_competitor_price(Sku('C'), Cents(2500));
+//! let receipt = market.receipt_as("ACC", &shopper).unwrap();
+//! assert_eq!(Cents(7000), receipt.total);
+//! assert_eq!("price match: C at $25.00 each  -$10.00", receipt.adjustments[0].to_string());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::vec::Vec;
+use crate::core::checkout::Sku;
+use crate::core::money::{Cents, Money};
+use crate::receipt::Receipt;
+use super::{BoxedRule, CheckoutError, PricingRule, Supermarket};
+
+/// The prices a customer pays.
+///
+/// The derived `Ord` orders tiers as they are declared, which is the order
+/// a `Supermarket` lists them in.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum PriceTier {
+    /// The catalog's own prices, for everybody else. This is the default.
+    #[default]
+    Retail,
+    Member,
+    Employee,
+    Wholesale
+}
+
+/// A tier by its name, such as `member`.
+impl fmt::Display for PriceTier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            PriceTier::Retail => "retail",
+            PriceTier::Member => "member",
+            PriceTier::Employee => "employee",
+            PriceTier::Wholesale => "wholesale"
+        })
+    }
+}
+
+/// Who a cart is being priced for, as far as the register cares.
+///
+/// It is generic over the money of the competitor prices, defaulting to
+/// `Cents` like the Supermarket it is checked out at.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CustomerContext<M: Money = Cents> {
+    /// Which prices the customer pays.
+    pub tier: PriceTier,
+    /// Whether the cashier has verified the customer's age, allowing the
+    /// sale of age-restricted products. See `restricted`.
+    pub age_verified: bool,
+    /// A competitor's price for one of each product, to be matched.
+    pub competitor_prices: BTreeMap<Sku, M>
+}
+
+/// A retail customer with nothing verified and no prices to match.
+///
+/// Written by hand, as `#[derive(Default)]` would demand `M: Default`,
+/// which an empty map of prices does not need.
+impl<M: Money> Default for CustomerContext<M> {
+    fn default() -> CustomerContext<M> {
+        CustomerContext::new(PriceTier::Retail)
+    }
+}
+
+impl<M: Money> CustomerContext<M> {
+
+    /// A customer paying `tier` prices, whose age has not been verified.
+    pub fn new(tier: PriceTier) -> CustomerContext<M> {
+        CustomerContext { tier, age_verified: false, competitor_prices: BTreeMap::new() }
+    }
+
+    /// The same customer, with their age verified.
+    pub fn with_verified_age(mut self) -> CustomerContext<M> {
+        self.age_verified = true;
+        self
+    }
+
+    /// The same customer, asking for `product` at `price` each, as a
+    /// competitor sells it. A second price for the same product replaces
+    /// the first.
+    pub fn with_competitor_price(mut self, product: Sku, price: M) -> CustomerContext<M> {
+        self.competitor_prices.insert(product, price);
+        self
+    }
+}
+
+/// The tiers of a Supermarket, in `src/reasonable_implementation/tiers.rs`.
+///
+/// A type may have any number of `impl` blocks, in any module of its crate,
+/// where a Java class is written in one file. Being a child module, this
+/// one may use the Supermarket's private fields.
+impl<M: Money> Supermarket<M> {
+
+    /// Adds `rules` to `tier`, returning the updated Supermarket.
+    ///
+    /// At that tier, a retail rule is set aside when any of its products
+    /// has a rule of the tier's own, so a retail rule for several products
+    /// gives way entirely to a tier rule for any one of them.
+    pub fn with_tier_rules(mut self, tier: PriceTier, rules: Vec<BoxedRule<M>>) -> Supermarket<M> {
+        self.tier_rules.entry(tier).or_default().extend(rules);
+        self
+    }
+
+    /// The tiers with rules of their own, in order.
+    pub fn tiers(&self) -> impl Iterator<Item = PriceTier> + '_ {
+        self.tier_rules.keys().copied()
+    }
+
+    /// The rules a cart is priced with at `tier`: the retail rules not set
+    /// aside for the tier, in order, then the tier's own.
+    ///
+    /// With no tier rules, nothing is filtered: the products of a rule are
+    /// only asked for when there is a tier rule to compare them with.
+    pub fn rules_for(&self, tier: PriceTier) -> impl Iterator<Item = &(dyn PricingRule<M> + Send + Sync)> {
+        let tiered: &[BoxedRule<M>] = self.tier_rules.get(&tier).map_or(&[], Vec::as_slice);
+        self.price_rules.iter()
+            .filter(move |rule| {
+                !tiered.iter().any(|own| rule.products().into_iter().any(|product| own.applies_to(product)))
+            })
+            .chain(tiered)
+            .map(|rule| &**rule)
+    }
+
+    /// Prices a cart like `try_checkout`, at the prices of `customer`'s
+    /// tier, matching their competitor prices.
+    pub fn checkout_as(&self, items: &str, customer: &CustomerContext<M>) -> Result<M, CheckoutError> {
+        Ok(self.checkout_ticket(items.to_string(), customer)?.total)
+    }
+
+    /// Prices a cart like `receipt`, at the prices of `customer`'s tier,
+    /// matching their competitor prices.
+    pub fn receipt_as(&self, items: &str, customer: &CustomerContext<M>) -> Result<Receipt<M>, CheckoutError> {
+        let ticket = self.checkout_ticket(items.to_string(), customer)?;
+        self.itemize(ticket)
+    }
+}
@@ -0,0 +1,510 @@
+//! The itemized receipt for a priced cart.
+//!
+//! A `PricingPlan` explains a total rule by rule, as a tree. A `Receipt`
+//! flattens that into what a shopper would see: one line per product, with
+//! its quantity, what it came to and any promotion it was bought under,
+//! followed by the adjustments later stages made, such as tax.
+//!
+//! With the `serde` feature, a receipt is `Serialize` and `Deserialize`, so
+//! it can be written out as JSON, or in any other format serde supports,
+//! much as Jackson maps a Java bean.
+//!
+//! `diff` compares two receipts for the same cart, say before and after a
+//! price change, and explains why the total moved:
+//!
+//! ```
+//! use rust_for_java_devs::money::Cents;
+//! use rust_for_java_devs::reasonable_implementation::{BundlePrice, FlatPrice, Supermarket};
+//! use rust_for_java_devs::receipt;
+//!
+//! let before: Supermarket = Supermarket::demo();
+//! let after: Supermarket = Supermarket::with_rules(vec![
+//!     Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+//!     Box::new(BundlePrice::new('B', Cents(5000), 3, Cents(12000)).unwrap()),
+//! ]);
+//! let changes = receipt::diff(&before.receipt("ABBBBBC").unwrap(),
+//!                             &after.receipt("ABBBBBC").unwrap());
+//! assert_eq!("\
+//! B: 5 for $150.00 -> $220.00 (+$70.00); lost 5 for $150.00; gained 3 for $120.00
+//! C: removed, 1 for $30.00
+//! total: $200.00 -> $240.00 (+$40.00)
+//! ", changes.to_string());
+//! ```
+//!
+//! `reprice` prices a whole batch of stored receipts again at a new catalog,
+//! on as many threads as the machine has, and flags those whose totals
+//! change: the receipts a catalog migration would have priced differently,
+//! or the one a shopper is disputing.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter;
+use std::num::NonZeroUsize;
+use std::thread;
+use crate::core::checkout::CheckoutError;
+use crate::core::money::{Money, Cents, Overflow};
+use crate::reasonable_implementation::{PricingPlan, Supermarket};
+use crate::reasonable_implementation::pipeline::{Adjustment, Ticket};
+use crate::iterators_from_scratch::ReceiptLines;
+
+/// What one rule charged for one product.
+///
+/// Equality and hashing are derived; ordering is written by hand, below,
+/// as the derived one would not sort lines the way a receipt needs.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReceiptLine<M: Money = Cents> {
+    /// The product charged for, or `None` for a rule that does not say
+    /// which items it charged for, such as `remote::RemotePrice`.
+    pub product: Option<char>,
+    /// The rule's own `describe` text.
+    pub rule: String,
+    /// How many of the product were charged for.
+    pub quantity: u64,
+    /// What they came to.
+    pub amount: M,
+    /// The multi-item offers they were bought under, such as
+    /// `"5 for $150.00"`. Empty when every item was charged singly.
+    pub promotions: Vec<String>
+}
+
+/// Lines in the order a receipt sorted by product shows them: by product,
+/// with the lines no product was named for, such as a `RemotePrice`'s, at
+/// the end; then by rule, quantity, amount and promotions.
+///
+/// A derived `Ord` would compare `product` first too, but it orders `None`
+/// before every `Some`, which would put the odd lines at the top. Writing
+/// `Ord` by hand means keeping its contract, as Java's `compareTo` must:
+/// it must be a total order, and it must agree with `==`, returning
+/// `Equal` exactly when the lines are equal. Comparing every field that
+/// `PartialEq` compares, as this does, is the simple way to make sure.
+/// `BTreeMap` and `sort` rely on the contract and misbehave if it is
+/// broken, as a `TreeMap` does with an inconsistent `compareTo`.
+///
+/// The impls only exist for a money type with an order of its own, `Ord`.
+impl<M: Money + Ord> Ord for ReceiptLine<M> {
+    fn cmp(&self, other: &ReceiptLine<M>) -> Ordering {
+        // `is_none` is `false` for a product, and `false` sorts before
+        // `true`, so named products come first. `then_with` is
+        // `thenComparing`: it only looks further if the first comparison
+        // found a tie, and only then runs its closure.
+        self.product.is_none().cmp(&other.product.is_none())
+            .then_with(|| self.product.cmp(&other.product))
+            .then_with(|| self.rule.cmp(&other.rule))
+            .then_with(|| self.quantity.cmp(&other.quantity))
+            .then_with(|| self.amount.cmp(&other.amount))
+            .then_with(|| self.promotions.cmp(&other.promotions))
+    }
+}
+
+/// `PartialOrd` is required alongside `Ord`, for types such as `f64` whose
+/// values are not always comparable. When there is an `Ord`, it should
+/// simply defer to it, so that the two can never disagree.
+impl<M: Money + Ord> PartialOrd for ReceiptLine<M> {
+    fn partial_cmp(&self, other: &ReceiptLine<M>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A priced cart, line by line.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Receipt<M: Money = Cents> {
+    /// One line per product and rule, in the order the rules were applied.
+    /// Rules that charged nothing are left out.
+    pub lines: Vec<ReceiptLine<M>>,
+    /// The sum of the rules' prices.
+    pub subtotal: M,
+    /// Every change later stages made to the total, in order.
+    pub adjustments: Vec<Adjustment<M>>,
+    /// The amount due.
+    pub total: M
+}
+
+impl<M: Money> Receipt<M> {
+
+    /// The receipt for a cart whose rules were explained by `plan`, and
+    /// which the checkout pipeline left as `ticket`.
+    ///
+    /// A stage other than `promotions` adding to the subtotal, such as a
+    /// `RemotePrice` stage, is included in `subtotal` but has no line.
+    pub fn new(plan: PricingPlan<M>, ticket: Ticket<M>) -> Result<Receipt<M>, Overflow> {
+        let mut lines: Vec<ReceiptLine<M>> = Vec::new();
+        for rule in plan.rules {
+            if rule.offers.is_empty() {
+                if rule.amount != M::zero() {
+                    lines.push(ReceiptLine {
+                        product: None,
+                        rule: rule.description,
+                        quantity: 0,
+                        amount: rule.amount,
+                        promotions: Vec::new()
+                    });
+                }
+                continue;
+            }
+            // A rule may charge for several products, and a product may be
+            // bought under several of its offers; each product of each
+            // rule gets one line.
+            let first = lines.len();
+            for offer in rule.offers {
+                let line = match lines[first..].iter_mut().find(|line| line.product == Some(offer.product)) {
+                    Some(line) => line,
+                    None => {
+                        lines.push(ReceiptLine {
+                            product: Some(offer.product),
+                            rule: rule.description.clone(),
+                            quantity: 0,
+                            amount: M::zero(),
+                            promotions: Vec::new()
+                        });
+                        lines.last_mut().expect("a line was just pushed")
+                    }
+                };
+                line.quantity = line.quantity.saturating_add(offer.items());
+                line.amount = ticket.policy.plus(line.amount, offer.amount)?;
+                if offer.lot_size > 1 {
+                    line.promotions.push(format!("{} for {}", offer.lot_size, offer.lot_cost));
+                }
+            }
+        }
+        Ok(Receipt {
+            lines,
+            subtotal: ticket.subtotal,
+            adjustments: ticket.adjustments,
+            total: ticket.total
+        })
+    }
+
+    /// The lines, borrowed, front to back; `.rev()` for back to front.
+    /// See `iterators_from_scratch`.
+    pub fn iter(&self) -> ReceiptLines<'_, M> {
+        ReceiptLines::new(self)
+    }
+
+    /// The cart the receipt was for, rebuilt from its lines: each product
+    /// as many times as its line says, in order of product.
+    ///
+    /// Only what the lines record comes back. A product charged by two
+    /// rules has a line from each, of the same quantity, and is counted
+    /// once. Items without a line, such as those no rule charged for, or
+    /// priced by a `RemotePrice` stage, are lost.
+    pub fn items(&self) -> String {
+        let mut quantities: BTreeMap<char, u64> = BTreeMap::new();
+        for line in self.lines.iter() {
+            if let Some(product) = line.product {
+                let quantity = quantities.entry(product).or_default();
+                *quantity = (*quantity).max(line.quantity);
+            }
+        }
+        quantities.into_iter()
+            .flat_map(|(product, quantity)| iter::repeat_n(product, quantity as usize))
+            .collect()
+    }
+}
+
+/// `for line in &receipt`, as a Java `Receipt implements
+/// Iterable<ReceiptLine>` would allow. `for` calls `into_iter` on whatever
+/// it is given, and `&Receipt` gives its lines.
+impl<'r, M: Money> IntoIterator for &'r Receipt<M> {
+    type Item = &'r ReceiptLine<M>;
+    type IntoIter = ReceiptLines<'r, M>;
+
+    fn into_iter(self) -> ReceiptLines<'r, M> {
+        self.iter()
+    }
+}
+
+/// One line as a shopper reads it, such as `B x5  $150.00  (5 for $150.00)`.
+/// A line without a product is named by its rule instead.
+///
+/// `Display` is the user-facing format, printed by `{}`; the derived
+/// `Debug`, printed by `{:?}`, shows every field for a developer instead.
+/// Java has only `toString` for both, so it usually ends up serving neither
+/// well.
+impl<M: Money> fmt::Display for ReceiptLine<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.product {
+            Some(product) => write!(f, "{}", product)?,
+            None => write!(f, "{}", self.rule)?
+        }
+        write!(f, " x{}  {}", self.quantity, self.amount)?;
+        if !self.promotions.is_empty() {
+            write!(f, "  ({})", self.promotions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The whole receipt, one part per line, as `visitor_pattern::TextRenderer`
+/// renders it:
+///
+/// ```text
+/// A x1  $20.00
+/// B x5  $150.00  (5 for $150.00)
+/// subtotal  $170.00
+/// total  $170.00
+/// ```
+impl<M: Money> fmt::Display for Receipt<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f, "subtotal  {}", self.subtotal)?;
+        for adjustment in self.adjustments.iter() {
+            writeln!(f, "{}", adjustment)?;
+        }
+        writeln!(f, "total  {}", self.total)
+    }
+}
+
+/// One difference between two lists: an entry only in the second, only in
+/// the first, or in both but not the same.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Change<T> {
+    Added(T),
+    Removed(T),
+    Changed { before: T, after: T }
+}
+
+impl<T> Change<T> {
+
+    /// The entry as it was, unless it was added.
+    pub fn before(&self) -> Option<&T> {
+        match *self {
+            Change::Added(_) => None,
+            Change::Removed(ref before) | Change::Changed { ref before, .. } => Some(before)
+        }
+    }
+
+    /// The entry as it is now, unless it was removed.
+    pub fn after(&self) -> Option<&T> {
+        match *self {
+            Change::Removed(_) => None,
+            Change::Added(ref after) | Change::Changed { ref after, .. } => Some(after)
+        }
+    }
+}
+
+/// How much more is charged, the amounts being zero where missing, or
+/// `None` if the difference is too large to represent.
+pub(crate) fn delta<M: Money>(before: Option<M>, after: Option<M>) -> Option<M> {
+    after.unwrap_or_else(M::zero).checked_minus(before.unwrap_or_else(M::zero))
+}
+
+impl<M: Money> Change<ReceiptLine<M>> {
+
+    /// How much more this line charges than before.
+    pub fn delta(&self) -> Option<M> {
+        delta(self.before().map(|line| line.amount), self.after().map(|line| line.amount))
+    }
+
+    /// The promotions the line is bought under now, but was not before.
+    pub fn promotions_gained(&self) -> Vec<&str> {
+        missing_from(self.after(), self.before())
+    }
+
+    /// The promotions the line was bought under before, but is not now.
+    pub fn promotions_lost(&self) -> Vec<&str> {
+        missing_from(self.before(), self.after())
+    }
+}
+
+/// The promotions of `line` that `other` lacks.
+fn missing_from<'a, M: Money>(line: Option<&'a ReceiptLine<M>>,
+                              other: Option<&ReceiptLine<M>>) -> Vec<&'a str> {
+    let others: &[String] = other.map(|other| &other.promotions[..]).unwrap_or(&[]);
+    line.map(|line| &line.promotions[..]).unwrap_or(&[]).iter()
+        .filter(|promotion| !others.contains(promotion))
+        .map(String::as_str)
+        .collect()
+}
+
+impl<M: Money> Change<Adjustment<M>> {
+
+    /// How much more this adjustment charges than before.
+    pub fn delta(&self) -> Option<M> {
+        delta(self.before().map(|a| a.amount), self.after().map(|a| a.amount))
+    }
+}
+
+/// The changes turning `before` into `after`, pairing up entries with the
+/// same key, and leaving out those that did not change at all.
+///
+/// Changed and removed entries come in the order of `before`, followed by
+/// the added ones in the order of `after`.
+pub(crate) fn changes<T, K, F>(before: &[T], after: &[T], key: F) -> Vec<Change<T>>
+    where T: Clone + PartialEq, K: PartialEq, F: Fn(&T) -> K
+{
+    let mut paired = vec![false; after.len()];
+    let mut changes = Vec::new();
+    for old in before {
+        let found = after.iter().enumerate().position(|(i, new)| !paired[i] && key(new) == key(old));
+        match found {
+            Some(i) => {
+                paired[i] = true;
+                if after[i] != *old {
+                    changes.push(Change::Changed { before: old.clone(), after: after[i].clone() });
+                }
+            },
+            None => changes.push(Change::Removed(old.clone()))
+        }
+    }
+    // `zip` walks both in step, like a pair of Java iterators.
+    for (new, paired) in after.iter().zip(paired) {
+        if !paired {
+            changes.push(Change::Added(new.clone()));
+        }
+    }
+    changes
+}
+
+/// What changed between two receipts, line by line.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReceiptDiff<M: Money = Cents> {
+    /// Lines are paired up by product, or by rule for the lines of rules
+    /// that do not say which products they charged for.
+    pub lines: Vec<Change<ReceiptLine<M>>>,
+    /// Adjustments are paired up by label.
+    pub adjustments: Vec<Change<Adjustment<M>>>,
+    pub total_before: M,
+    pub total_after: M
+}
+
+impl<M: Money> ReceiptDiff<M> {
+
+    /// Whether the receipts were the same.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.adjustments.is_empty() && self.total_before == self.total_after
+    }
+
+    /// How much more the cart costs than before.
+    pub fn total_delta(&self) -> Option<M> {
+        delta(Some(self.total_before), Some(self.total_after))
+    }
+}
+
+/// Compares two receipts, typically for the same cart priced two ways.
+pub fn diff<M: Money>(before: &Receipt<M>, after: &Receipt<M>) -> ReceiptDiff<M> {
+    ReceiptDiff {
+        // A line without a product is keyed by its rule instead. Being
+        // `Err`s, those keys never equal a product's, which are `Ok`s.
+        lines: changes(&before.lines, &after.lines, |line| line.product.ok_or_else(|| line.rule.clone())),
+        adjustments: changes(&before.adjustments, &after.adjustments, |a| a.label.clone()),
+        total_before: before.total,
+        total_after: after.total
+    }
+}
+
+/// A stored receipt, priced again at another catalog.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RepriceResult<M: Money = Cents> {
+    /// The cart, as `Receipt::items` reads it back from the stored receipt.
+    pub items: String,
+    /// The total the stored receipt was issued with.
+    pub before: M,
+    /// The receipt the catalog gives the cart now, or why it cannot price
+    /// it at all.
+    pub after: Result<Receipt<M>, CheckoutError>
+}
+
+impl<M: Money> RepriceResult<M> {
+
+    /// Whether the cart would not be charged what it was: its total moved,
+    /// or the catalog can no longer price it.
+    pub fn changed(&self) -> bool {
+        self.after.as_ref().map_or(true, |after| after.total != self.before)
+    }
+
+    /// How much more the cart costs now, or `None` if it cannot be priced,
+    /// or the difference is too large to represent.
+    pub fn delta(&self) -> Option<M> {
+        let after = self.after.as_ref().ok()?;
+        delta(Some(self.before), Some(after.total))
+    }
+}
+
+/// Prices the cart behind every one of `receipts` again at `catalog`, in
+/// the receipts' order, to find the totals that change.
+///
+/// The receipts are split into one run per available thread, each priced on
+/// a scoped thread of its own, as `send_and_sync::checkout_in_scope` prices
+/// carts; a thread per receipt would spend more on starting threads than on
+/// pricing. Repricing is not a sale, so the catalog's observers and metrics
+/// do not see it.
+pub fn reprice<M: Money>(receipts: &[Receipt<M>], catalog: &Supermarket<M>) -> Vec<RepriceResult<M>> {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let run = receipts.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = receipts.chunks(run)
+            .map(|run| scope.spawn(move || run.iter().map(|receipt| {
+                let items = receipt.items();
+                let after = catalog.requote(&items);
+                RepriceResult { items, before: receipt.total, after }
+            }).collect::<Vec<_>>()))
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().expect("no repricing panics")).collect()
+    })
+}
+
+/// `amount`, with a `+` in front if it is positive.
+fn signed<M: Money>(amount: Option<M>) -> String {
+    match amount {
+        Some(amount) if amount > M::zero() => format!("+{}", amount),
+        Some(amount) => amount.to_string(),
+        None => "overflow".to_string()
+    }
+}
+
+/// `before -> after (delta)`.
+pub(crate) fn moved<M: Money>(before: M, after: M) -> String {
+    format!("{} -> {} ({})", before, after, signed(delta(Some(before), Some(after))))
+}
+
+/// A line's quantity and amount, such as `5 for $150.00`.
+fn bought<M: Money>(line: &ReceiptLine<M>) -> String {
+    format!("{} for {}", line.quantity, line.amount)
+}
+
+/// One change per line, followed by the change in total, in a form meant
+/// for people reviewing a price change rather than for machines.
+impl<M: Money> fmt::Display for ReceiptDiff<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in self.lines.iter() {
+            let line = change.after().or(change.before()).expect("every change has a side");
+            match line.product {
+                Some(product) => write!(f, "{}: ", product)?,
+                None => write!(f, "{}: ", line.rule)?
+            }
+            match *change {
+                Change::Added(ref line) => write!(f, "added, {}", bought(line))?,
+                Change::Removed(ref line) => write!(f, "removed, {}", bought(line))?,
+                Change::Changed { ref before, ref after } => {
+                    if before.quantity != after.quantity {
+                        write!(f, "{} -> {} items, ", before.quantity, after.quantity)?;
+                    } else {
+                        write!(f, "{} for ", before.quantity)?;
+                    }
+                    write!(f, "{}", moved(before.amount, after.amount))?;
+                    for promotion in change.promotions_lost() {
+                        write!(f, "; lost {}", promotion)?;
+                    }
+                    for promotion in change.promotions_gained() {
+                        write!(f, "; gained {}", promotion)?;
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+        for change in self.adjustments.iter() {
+            match *change {
+                Change::Added(ref a) => writeln!(f, "{}: added, {}", a.label, a.amount)?,
+                Change::Removed(ref a) => writeln!(f, "{}: removed, {}", a.label, a.amount)?,
+                Change::Changed { ref before, ref after } =>
+                    writeln!(f, "{}: {}", after.label, moved(before.amount, after.amount))?
+            }
+        }
+        writeln!(f, "total: {}", moved(self.total_before, self.total_after))
+    }
+}
@@ -0,0 +1,203 @@
+//! Building rules by name, for catalogs kept as data rather than code.
+//!
+//! A catalog stored in a file can only say *which kind* of rule it wants,
+//! as a string such as `"bundle"`, along with the numbers that go with it.
+//! A `RuleRegistry` maps each kind name to a constructor turning those
+//! numbers into a rule. Downstream crates register kinds of their own at
+//! startup, so that a file may name them exactly like the built-in ones.
+//!
+//! In Java this would be a `Map<String, Function<RuleSpec, PricingRule>>`,
+//! perhaps filled in by a `ServiceLoader`. Rust writes much the same thing;
+//! the constructors are closures, boxed because each one is its own type.
+//!
+//! The registry knows nothing about file formats. A loader for JSON, TOML or
+//! anything else only has to turn each entry into a `RuleSpec`.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use crate::core::money::{Money, Cents};
+use crate::core::rules::{FlatPrice, BundlePrice, RuleError};
+use crate::reasonable_implementation::{BoxedRule, Supermarket};
+
+/// One rule as a catalog file describes it: its kind, its product, and its
+/// numbers by name. Costs are given in cents.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RuleSpec {
+    pub kind: String,
+    pub product: char,
+    pub parameters: BTreeMap<String, i64>
+}
+
+impl RuleSpec {
+
+    /// A rule of `kind` for `product`, with no parameters yet.
+    pub fn new(kind: impl Into<String>, product: char) -> RuleSpec {
+        RuleSpec { kind: kind.into(), product, parameters: BTreeMap::new() }
+    }
+
+    /// Sets the parameter `name` to `value`, returning the updated spec.
+    pub fn with(mut self, name: impl Into<String>, value: i64) -> RuleSpec {
+        self.parameters.insert(name.into(), value);
+        self
+    }
+
+    /// The parameter `name`, or `MissingParameter` if there is none.
+    pub fn parameter(&self, name: &str) -> Result<i64, RegistryError> {
+        self.parameters.get(name).copied().ok_or_else(|| RegistryError::MissingParameter {
+            kind: self.kind.clone(),
+            name: name.to_string()
+        })
+    }
+
+    /// The parameter `name`, as an amount of cents in any money backend.
+    pub fn cost<M: Money>(&self, name: &str) -> Result<M, RegistryError> {
+        Ok(M::from_cents(self.parameter(name)?))
+    }
+
+    /// The parameter `name`, as a number of items, which may not be
+    /// negative.
+    pub fn size(&self, name: &str) -> Result<u64, RegistryError> {
+        let value = self.parameter(name)?;
+        // `try_from` is the checked conversion, failing for negative values
+        // where `as` would silently wrap them around.
+        u64::try_from(value).map_err(|_| RegistryError::InvalidParameter {
+            name: name.to_string(),
+            value
+        })
+    }
+}
+
+/// The reasons a `RuleSpec` may fail to become a rule.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RegistryError {
+    /// No constructor is registered under this kind name.
+    UnknownKind(String),
+    /// The spec lacks a parameter its kind needs.
+    MissingParameter { kind: String, name: String },
+    /// A parameter's value makes no sense, such as a negative size.
+    InvalidParameter { name: String, value: i64 },
+    /// The rule's own constructor refused the parameters.
+    Rule(RuleError)
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegistryError::UnknownKind(ref kind) => write!(f, "no rule kind is named {:?}", kind),
+            RegistryError::MissingParameter { ref kind, ref name } =>
+                write!(f, "a {:?} rule needs a {:?} parameter", kind, name),
+            RegistryError::InvalidParameter { ref name, value } =>
+                write!(f, "{} is not a valid {:?}", value, name),
+            RegistryError::Rule(ref e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl Error for RegistryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RegistryError::Rule(ref e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<RuleError> for RegistryError {
+    fn from(e: RuleError) -> RegistryError {
+        RegistryError::Rule(e)
+    }
+}
+
+/// A constructor for one kind of rule.
+///
+/// `Send + Sync` lets a registry built at startup be shared between threads,
+/// as every Supermarket may be.
+pub type Constructor<M> = Box<dyn Fn(&RuleSpec) -> Result<BoxedRule<M>, RegistryError>
+                              + Send + Sync>;
+
+/// Constructors for rules, by kind name.
+pub struct RuleRegistry<M: Money = Cents> {
+    constructors: BTreeMap<String, Constructor<M>>
+}
+
+impl<M: Money> RuleRegistry<M> {
+
+    /// A registry knowing no kinds at all.
+    pub fn new() -> RuleRegistry<M> {
+        RuleRegistry { constructors: BTreeMap::new() }
+    }
+
+    /// A registry knowing the built-in kinds:
+    ///
+    /// * `"flat"`: `cost` for every item;
+    /// * `"bundle"`: `cost` apiece, or `bundle_cost` for `size` items;
+    /// * `"bogo"`: buy one, get one free, at `cost` apiece.
+    pub fn with_builtins() -> RuleRegistry<M> {
+        let mut registry = RuleRegistry::new();
+        registry.register("flat", |spec| {
+            Ok(Box::new(FlatPrice::new(spec.product, spec.cost("cost")?)?))
+        });
+        registry.register("bundle", |spec| {
+            Ok(Box::new(BundlePrice::new(spec.product, spec.cost("cost")?, spec.size("size")?,
+                                         spec.cost("bundle_cost")?)?))
+        });
+        registry.register("bogo", |spec| {
+            let cost = spec.cost("cost")?;
+            Ok(Box::new(BundlePrice::new(spec.product, cost, 2, cost)?))
+        });
+        registry
+    }
+
+    /// Registers `constructor` under `kind`, replacing any constructor
+    /// already registered under that name, built-in or not.
+    pub fn register<F>(&mut self, kind: impl Into<String>, constructor: F)
+        where F: Fn(&RuleSpec) -> Result<BoxedRule<M>, RegistryError> + Send + Sync + 'static
+    {
+        self.constructors.insert(kind.into(), Box::new(constructor));
+    }
+
+    /// Every registered kind name, in alphabetical order.
+    pub fn kinds(&self) -> impl Iterator<Item = &str> {
+        self.constructors.keys().map(String::as_str)
+    }
+
+    /// Builds the rule `spec` describes.
+    pub fn build(&self, spec: &RuleSpec) -> Result<BoxedRule<M>, RegistryError> {
+        match self.constructors.get(&spec.kind) {
+            Some(constructor) => constructor(spec),
+            None => Err(RegistryError::UnknownKind(spec.kind.clone()))
+        }
+    }
+
+    /// Builds a Supermarket from a whole catalog of specs, stopping at the
+    /// first that fails.
+    pub fn supermarket(&self, specs: &[RuleSpec]) -> Result<Supermarket<M>, RegistryError> {
+        // Collecting an iterator of `Result`s into a `Result` stops at the
+        // first `Err`, much like a Java stream rethrowing an exception.
+        specs.iter().map(|spec| self.build(spec)).collect()
+    }
+}
+
+/// The default registry is the one with the built-in kinds, as a registry
+/// without them is rarely what anybody wants.
+impl<M: Money> Default for RuleRegistry<M> {
+    fn default() -> RuleRegistry<M> {
+        RuleRegistry::with_builtins()
+    }
+}
+
+/// Shows the kinds the registry knows, as in
+/// `RuleRegistry { kinds: ["bogo", "bundle", "flat"] }`.
+///
+/// The constructors are closures, which have no `Debug` to derive from, and
+/// their names are all a developer needs. `debug_list` and `debug_struct`
+/// lay the output out on one line for `{:?}` and across several for
+/// `{:#?}`, with no extra work.
+impl<M: Money> fmt::Debug for RuleRegistry<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuleRegistry")
+            .field("kinds", &self.kinds().collect::<Vec<_>>())
+            .finish()
+    }
+}
@@ -0,0 +1,730 @@
+//! Prices looked up from somewhere else, such as a pricing microservice,
+//! for products the local catalog does not know.
+//!
+//! `RemotePrice` is an ordinary `PricingRule`, but rather than holding its
+//! prices it asks a `PriceSource` for them at checkout. The source is a
+//! trait, so that the same rule works against the HTTP service in
+//! production, `InMemoryPriceSource` in tests, or anything else.
+//!
+//! `PricingRule::price` is synchronous, like a Java method returning a plain
+//! value rather than a `CompletableFuture`. An `AsyncPriceSource` therefore
+//! cannot be asked during checkout itself; instead, `Prefetched::fetch`
+//! awaits the prices of a cart's products first, and then serves them to the
+//! rule synchronously.
+//!
+//! `CachedPriceSource` wraps any source to remember its answers for a
+//! while, so that the service is not asked about every item of every cart.
+//! `RetryingPriceSource` wraps one to try again after a failure, and to give
+//! up on a lookup that takes too long.
+//!
+//! A rule can only report an `Overflow`, so a `RemotePrice` used as a rule
+//! may only ignore a failed lookup or panic. Inserted into the checkout
+//! pipeline as a stage instead, it turns a failure into
+//! `CheckoutError::PriceSourceUnavailable`:
+//!
+//! ```
+//! use std::time::Duration;
+//! use rust_for_java_devs::money::Cents;
+//! use rust_for_java_devs::reasonable_implementation::{CheckoutError, Supermarket};
+//! use rust_for_java_devs::remote::{InMemoryPriceSource, RemotePrice, RetryPolicy,
+//!                                  RetryingPriceSource};
+//!
+//! let source = InMemoryPriceSource::new().with_price('D', Cents(1000)).failing_for('E');
+//! let policy = RetryPolicy::default().with_backoff(Duration::from_millis(1));
+//! let remote = RemotePrice::new(RetryingPriceSource::new(source, policy))
+//!     .excluding(&['A', 'B', 'C']);
+//!
+//! let mut market: Supermarket = Supermarket::demo();
+//! market.pipeline_mut().insert_after("promotions", remote).unwrap();
+//! assert_eq!(Ok(Cents(3000)), market.try_checkout("AD"));
+//! assert_eq!(Err(CheckoutError::PriceSourceUnavailable { product: 'E', attempts: 3 }),
+//!            market.try_checkout("AE"));
+//! ```
+//!
+//! With the `http` feature, `HttpPriceSource` and `AsyncHttpPriceSource`
+//! ask a service answering `GET {base}/prices/{product}` with a price in
+//! cents, or `404 Not Found` for a product it does not sell either.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::core::counting::Counts;
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{Describable, PricingRule};
+use crate::reasonable_implementation::{CheckoutError, Supermarket};
+use crate::reasonable_implementation::pipeline::{Stage, Ticket};
+
+/// The reasons a price lookup may fail, as opposed to finding no price.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SourceError {
+    /// The source could not be reached, or did not answer in time.
+    Transport(String),
+    /// The source answered with an unexpected HTTP status.
+    Status(u16),
+    /// The source answered with something other than a price.
+    BadResponse(String),
+    /// `Prefetched` was asked about a product it did not fetch.
+    NotFetched(char),
+    /// The source gave no answer within the given time.
+    TimedOut(Duration),
+    /// `RetryingPriceSource` gave up after `attempts` tries, the last of
+    /// which failed with `last`.
+    ///
+    /// The `Box` gives the variant a fixed size, as an enum may not hold
+    /// itself directly; Java has no such concern, every field being a
+    /// reference already.
+    Exhausted { attempts: u32, last: Box<SourceError> }
+}
+
+impl SourceError {
+
+    /// Whether trying again might succeed: a source that could not be
+    /// reached, that took too long, or that answered with a server error.
+    /// A nonsensical answer or a client error will only be repeated.
+    pub fn is_transient(&self) -> bool {
+        match *self {
+            SourceError::Transport(_) | SourceError::TimedOut(_) => true,
+            SourceError::Status(status) => status >= 500,
+            _ => false
+        }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SourceError::Transport(ref message) => write!(f, "price source unreachable: {}", message),
+            SourceError::Status(status) => write!(f, "price source answered {}", status),
+            SourceError::BadResponse(ref body) => write!(f, "not a price: {:?}", body),
+            SourceError::NotFetched(product) => write!(f, "{}: not fetched in advance", product),
+            SourceError::TimedOut(timeout) => write!(f, "price source gave no answer within {:?}", timeout),
+            SourceError::Exhausted { attempts, ref last } =>
+                write!(f, "gave up after {} attempts: {}", attempts, last)
+        }
+    }
+}
+
+impl Error for SourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SourceError::Exhausted { ref last, .. } => Some(&**last),
+            _ => None
+        }
+    }
+}
+
+/// Somewhere to look prices up, one product at a time, blocking until the
+/// answer arrives.
+///
+/// `Ok(None)` means the source knows of no price for the product, which is
+/// not an error: the item is then ignored, like any other unknown item.
+pub trait PriceSource<M: Money = Cents>: Send + Sync {
+    fn price_of(&self, product: char) -> Result<Option<M>, SourceError>;
+}
+
+/// A shared source is a source too, so that one `Arc<S>` may serve several
+/// rules, or be kept around to inspect afterwards.
+impl<M: Money, S: PriceSource<M> + ?Sized> PriceSource<M> for std::sync::Arc<S> {
+    fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        (**self).price_of(product)
+    }
+}
+
+/// The asynchronous counterpart of `PriceSource`.
+///
+/// Writing the return type as `impl Future<...> + Send`, rather than making
+/// this an `async fn`, promises callers that the future may move between
+/// threads, as a multi-threaded runtime such as Tokio's requires.
+/// Implementations may still be written as `async fn`.
+pub trait AsyncPriceSource<M: Money = Cents>: Send + Sync {
+    fn price_of(&self, product: char)
+        -> impl Future<Output = Result<Option<M>, SourceError>> + Send;
+}
+
+/// What `RemotePrice` does when a lookup fails.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum LookupFailure {
+    /// Leaves the item out of the total, as if the source knew no price.
+    #[default]
+    Ignore,
+    /// Panics, abandoning the checkout.
+    Panic
+}
+
+/// Prices every product the catalog's other rules leave alone by looking it
+/// up in a `PriceSource`.
+pub struct RemotePrice<S, M: Money = Cents> {
+    source: S,
+    /// Products priced by other rules, which this one never looks up.
+    excluded: Vec<char>,
+    on_failure: LookupFailure,
+    /// `fn() -> M` records which money backend this rule prices in without
+    /// storing one; see `PhantomData`.
+    money: core::marker::PhantomData<fn() -> M>
+}
+
+impl<S: PriceSource<M>, M: Money> RemotePrice<S, M> {
+
+    /// A rule looking up every product in `source`.
+    pub fn new(source: S) -> RemotePrice<S, M> {
+        RemotePrice {
+            source,
+            excluded: Vec::new(),
+            on_failure: LookupFailure::default(),
+            money: core::marker::PhantomData
+        }
+    }
+
+    /// Never looks up `products`, typically those priced by other rules,
+    /// returning the updated rule.
+    pub fn excluding(mut self, products: &[char]) -> RemotePrice<S, M> {
+        self.excluded.extend_from_slice(products);
+        self
+    }
+
+    /// Replaces what happens when a lookup fails, returning the updated rule.
+    pub fn on_failure(mut self, on_failure: LookupFailure) -> RemotePrice<S, M> {
+        self.on_failure = on_failure;
+        self
+    }
+
+    /// The source prices are looked up in.
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Prices every product in `character_counts` that is not excluded,
+    /// handing each failed lookup to `on_failed`, which either gives up on
+    /// the whole pricing or lets the product go unpriced.
+    fn price_with<E, F>(&self, character_counts: &Counts, policy: OverflowPolicy,
+                        mut on_failed: F) -> Result<M, E>
+        where E: From<Overflow>, F: FnMut(char, SourceError) -> Result<(), E>
+    {
+        let mut total = M::zero();
+        for (&product, &count) in character_counts.iter() {
+            if self.excluded.contains(&product) {
+                continue;
+            }
+            match self.source.price_of(product) {
+                Ok(Some(cost)) => total = policy.plus(total, policy.times(cost, count)?)?,
+                Ok(None) => {},
+                Err(e) => on_failed(product, e)?
+            }
+        }
+        Ok(total)
+    }
+}
+
+impl<S: PriceSource<M>, M: Money> Describable for RemotePrice<S, M> {
+    fn describe(&self) -> String {
+        "any other product: its remote price".to_string()
+    }
+}
+
+impl<S: PriceSource<M>, M: Money> PricingRule<M> for RemotePrice<S, M> {
+    fn price(&self, character_counts: &Counts,
+             policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.price_with(character_counts, policy, |product, e| match self.on_failure {
+            LookupFailure::Ignore => Ok(()),
+            LookupFailure::Panic => panic!("cannot price {}: {}", product, e)
+        })
+    }
+
+    /// Which products the source prices is only known by asking, so this
+    /// claims none.
+    fn products(&self) -> Vec<char> {
+        Vec::new()
+    }
+}
+
+/// As a pipeline stage, run after `promotions`, a `RemotePrice` adds the
+/// remote prices to the subtotal, and any failed lookup stops the checkout
+/// with `CheckoutError::PriceSourceUnavailable`; `on_failure` is not
+/// consulted. It does not know which products the catalog's rules price, so
+/// those must be excluded, as when it is used as a rule.
+impl<S: PriceSource<M>, M: Money> Stage<M> for RemotePrice<S, M> {
+    fn name(&self) -> &str {
+        "remote"
+    }
+
+    fn run(&self, _market: &Supermarket<M>, ticket: &mut Ticket<M>) -> Result<(), CheckoutError> {
+        let amount = self.price_with(&ticket.counts, ticket.policy, |product, e| {
+            let attempts = match e {
+                SourceError::Exhausted { attempts, .. } => attempts,
+                _ => 1
+            };
+            Err(CheckoutError::PriceSourceUnavailable { product, attempts })
+        })?;
+        ticket.subtotal = ticket.policy.plus(ticket.subtotal, amount)?;
+        ticket.total = ticket.policy.plus(ticket.total, amount)?;
+        Ok(())
+    }
+}
+
+/// Prices fetched from an `AsyncPriceSource` ahead of checkout, and served
+/// from memory to a `RemotePrice`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Prefetched<M: Money = Cents> {
+    prices: BTreeMap<char, Option<M>>
+}
+
+impl<M: Money> Prefetched<M> {
+
+    /// Looks up each of `products` in `source`, one after another, stopping
+    /// at the first failure.
+    pub async fn fetch<A>(source: &A, products: impl IntoIterator<Item = char>)
+        -> Result<Prefetched<M>, SourceError>
+        where A: AsyncPriceSource<M>
+    {
+        let mut prices = BTreeMap::new();
+        for product in products {
+            // `.await` suspends this function until the lookup completes,
+            // letting the runtime get on with other work meanwhile.
+            prices.insert(product, source.price_of(product).await?);
+        }
+        Ok(Prefetched { prices })
+    }
+}
+
+impl<M: Money> PriceSource<M> for Prefetched<M> {
+    fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        self.prices.get(&product).copied().ok_or(SourceError::NotFetched(product))
+    }
+}
+
+/// A `PriceSource` answering from a map, for tests, and counting how often
+/// it was asked.
+#[derive(Debug, Default)]
+pub struct InMemoryPriceSource<M: Money = Cents> {
+    prices: BTreeMap<char, M>,
+    failing: Vec<char>,
+    lookups: AtomicUsize
+}
+
+impl<M: Money> InMemoryPriceSource<M> {
+
+    /// A source knowing no prices at all.
+    pub fn new() -> InMemoryPriceSource<M> {
+        InMemoryPriceSource { prices: BTreeMap::new(), failing: Vec::new(), lookups: AtomicUsize::new(0) }
+    }
+
+    /// Prices `product` at `cost`, returning the updated source.
+    pub fn with_price(mut self, product: char, cost: M) -> InMemoryPriceSource<M> {
+        self.prices.insert(product, cost);
+        self
+    }
+
+    /// Fails every lookup of `product`, as if the service were down,
+    /// returning the updated source.
+    pub fn failing_for(mut self, product: char) -> InMemoryPriceSource<M> {
+        self.failing.push(product);
+        self
+    }
+
+    /// How many lookups have been made so far.
+    pub fn lookups(&self) -> usize {
+        self.lookups.load(Ordering::Relaxed)
+    }
+
+    fn look_up(&self, product: char) -> Result<Option<M>, SourceError> {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        if self.failing.contains(&product) {
+            return Err(SourceError::Transport(format!("lookups of {} are set to fail", product)));
+        }
+        Ok(self.prices.get(&product).copied())
+    }
+}
+
+impl<M: Money> PriceSource<M> for InMemoryPriceSource<M> {
+    fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        self.look_up(product)
+    }
+}
+
+impl<M: Money> AsyncPriceSource<M> for InMemoryPriceSource<M> {
+    async fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        self.look_up(product)
+    }
+}
+
+/// One remembered answer, and when it stops being trusted.
+#[derive(Copy, Clone, Debug)]
+struct CacheEntry<M> {
+    price: Option<M>,
+    expires: Instant
+}
+
+/// A `PriceSource` remembering the answers of another for a while, so that
+/// a busy register does not ask the upstream service about every item of
+/// every cart.
+///
+/// Each answer, including "no such price", is trusted for `ttl` after it
+/// arrived. Failures are never remembered, so the next checkout tries again.
+/// When `max_entries` answers are held, the one that would expire soonest
+/// makes way for the next. Like Java's Caffeine or Guava caches, but small
+/// enough to read in one sitting.
+///
+/// With the `metrics` feature, every lookup is counted under
+/// `telemetry::PRICE_CACHE`, labelled `outcome` = `hit` or `miss`.
+pub struct CachedPriceSource<S, M: Money = Cents> {
+    source: S,
+    ttl: Duration,
+    max_entries: usize,
+    /// A `Mutex` guards the map, as `price_of` only gets `&self`. It is
+    /// never held while the upstream source is asked, so one slow lookup
+    /// does not hold up every other.
+    entries: Mutex<BTreeMap<char, CacheEntry<M>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    /// Where the time comes from; `Instant::now` unless a test says
+    /// otherwise.
+    clock: Box<dyn Fn() -> Instant + Send + Sync>
+}
+
+impl<S: PriceSource<M>, M: Money> CachedPriceSource<S, M> {
+
+    /// Remembers each of `source`'s answers for `ttl`, holding at most
+    /// `max_entries` of them.
+    pub fn new(source: S, ttl: Duration, max_entries: usize) -> CachedPriceSource<S, M> {
+        CachedPriceSource {
+            source,
+            ttl,
+            max_entries,
+            entries: Mutex::new(BTreeMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            clock: Box::new(Instant::now)
+        }
+    }
+
+    /// Replaces the clock, returning the updated cache. Tests use this to
+    /// let time pass without waiting for it.
+    pub fn with_clock(mut self,
+                      clock: impl Fn() -> Instant + Send + Sync + 'static) -> CachedPriceSource<S, M> {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// How many lookups were answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// How many lookups had to ask the upstream source.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// How many answers are held, fresh or not.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no answers are held at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every answer, as after a price change upstream.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Counts one lookup, in this cache and in the metrics.
+    fn count(&self, hit: bool) {
+        let (counter, _outcome) = if hit { (&self.hits, "hit") } else { (&self.misses, "miss") };
+        counter.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::counter!(crate::reasonable_implementation::telemetry::PRICE_CACHE,
+                          "outcome" => _outcome).increment(1);
+    }
+}
+
+impl<S: PriceSource<M>, M: Money> PriceSource<M> for CachedPriceSource<S, M> {
+    fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        let now = (self.clock)();
+        // The lock is released at the end of this block, when the guard
+        // returned by `lock` goes out of scope.
+        {
+            let entries = self.entries.lock().unwrap();
+            if let Some(entry) = entries.get(&product).filter(|entry| entry.expires > now) {
+                self.count(true);
+                return Ok(entry.price);
+            }
+        }
+        self.count(false);
+        let price = self.source.price_of(product)?;
+
+        let mut entries = self.entries.lock().unwrap();
+        if self.max_entries == 0 {
+            return Ok(price);
+        }
+        if entries.len() >= self.max_entries && !entries.contains_key(&product) {
+            entries.retain(|_, entry| entry.expires > now);
+        }
+        if entries.len() >= self.max_entries && !entries.contains_key(&product) {
+            let soonest = entries.iter()
+                .min_by_key(|(_, entry)| entry.expires)
+                .map(|(&product, _)| product);
+            if let Some(soonest) = soonest {
+                entries.remove(&soonest);
+            }
+        }
+        entries.insert(product, CacheEntry { price, expires: now + self.ttl });
+        Ok(price)
+    }
+}
+
+/// How often, and how patiently, `RetryingPriceSource` asks its source.
+///
+/// After the first failed attempt it waits `backoff`, and twice as long
+/// after each further one, but never more than `max_backoff`: the usual
+/// exponential backoff, as in Resilience4j's `RetryConfig`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RetryPolicy {
+    /// How many times to ask in all, at least once.
+    pub attempts: u32,
+    /// How long to wait before the second attempt.
+    pub backoff: Duration,
+    /// The longest wait between two attempts.
+    pub max_backoff: Duration,
+    /// How long one attempt may take, or `None` to wait as long as the
+    /// source does.
+    pub timeout: Option<Duration>
+}
+
+impl RetryPolicy {
+
+    /// A single attempt, with no timeout: the source's own behavior.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        attempts: 1,
+        backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        timeout: None
+    };
+
+    /// Replaces the number of attempts, returning the updated policy.
+    pub fn with_attempts(mut self, attempts: u32) -> RetryPolicy {
+        self.attempts = attempts;
+        self
+    }
+
+    /// Replaces the first wait, returning the updated policy.
+    pub fn with_backoff(mut self, backoff: Duration) -> RetryPolicy {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Replaces the longest wait, returning the updated policy.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> RetryPolicy {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Replaces the per-attempt timeout, returning the updated policy.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> RetryPolicy {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How long to wait after the `failed`th failed attempt, counting from
+    /// one.
+    pub fn delay(&self, failed: u32) -> Duration {
+        // `checked_pow` and `checked_mul` give `None` rather than wrapping
+        // around, which here simply means "a very long time".
+        let factor = 2u32.checked_pow(failed.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.backoff.checked_mul(factor).unwrap_or(Duration::MAX).min(self.max_backoff)
+    }
+}
+
+/// Three attempts of at most two seconds each, waiting 100ms, then 200ms
+/// in between.
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            attempts: 3,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            timeout: Some(Duration::from_secs(2))
+        }
+    }
+}
+
+/// A `PriceSource` asking another again when it fails, under a
+/// `RetryPolicy`.
+///
+/// Only transient failures, as judged by `SourceError::is_transient`, are
+/// retried. Once the attempts run out, the lookup fails with
+/// `SourceError::Exhausted`.
+///
+/// A blocking call cannot be interrupted from outside, so each attempt with
+/// a timeout runs on a thread of its own, and is abandoned, still running,
+/// if it takes too long, just as Java's `Future.get(timeout, unit)` leaves
+/// its task running. That is why the source is kept in an `Arc`: the
+/// abandoned thread may outlive the lookup, and must share the source
+/// rather than borrow it.
+pub struct RetryingPriceSource<S, M: Money = Cents> {
+    source: Arc<S>,
+    policy: RetryPolicy,
+    money: core::marker::PhantomData<fn() -> M>
+}
+
+impl<S: PriceSource<M> + 'static, M: Money + Send + 'static> RetryingPriceSource<S, M> {
+
+    /// Asks `source` according to `policy`.
+    pub fn new(source: S, policy: RetryPolicy) -> RetryingPriceSource<S, M> {
+        RetryingPriceSource { source: Arc::new(source), policy, money: core::marker::PhantomData }
+    }
+
+    /// The policy lookups follow.
+    pub fn policy(&self) -> RetryPolicy {
+        self.policy
+    }
+
+    /// The source asked.
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Asks the source once, giving up after the policy's timeout.
+    fn attempt(&self, product: char) -> Result<Option<M>, SourceError> {
+        let timeout = match self.policy.timeout {
+            Some(timeout) => timeout,
+            None => return self.source.price_of(product)
+        };
+        // A channel carries the answer back; `recv_timeout` waits for it,
+        // but no longer than the timeout. If nobody is listening any more
+        // when the answer arrives, `send` fails, and the answer is dropped.
+        let (sender, receiver) = mpsc::channel();
+        let source = Arc::clone(&self.source);
+        thread::spawn(move || {
+            let _ = sender.send(source.price_of(product));
+        });
+        match receiver.recv_timeout(timeout) {
+            Ok(answer) => answer,
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(SourceError::TimedOut(timeout)),
+            Err(mpsc::RecvTimeoutError::Disconnected) =>
+                Err(SourceError::Transport(format!("the lookup of {} panicked", product)))
+        }
+    }
+}
+
+impl<S: PriceSource<M> + 'static, M: Money + Send + 'static> PriceSource<M> for RetryingPriceSource<S, M> {
+    fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        let attempts = self.policy.attempts.max(1);
+        let mut failed = 0;
+        loop {
+            let e = match self.attempt(product) {
+                Ok(price) => return Ok(price),
+                Err(e) => e
+            };
+            failed += 1;
+            if !e.is_transient() {
+                return Err(e);
+            }
+            if failed == attempts {
+                return Err(SourceError::Exhausted { attempts, last: Box::new(e) });
+            }
+            thread::sleep(self.policy.delay(failed));
+        }
+    }
+}
+
+/// The path of `product`'s price below the service's base URL, with the
+/// product percent-encoded as UTF-8 unless it is a plain letter or digit.
+#[cfg(feature = "http")]
+fn price_url(base: &str, product: char) -> String {
+    let mut url = format!("{}/prices/", base.trim_end_matches('/'));
+    if product.is_ascii_alphanumeric() {
+        url.push(product);
+    } else {
+        let mut bytes = [0; 4];
+        for byte in product.encode_utf8(&mut bytes).bytes() {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+/// Turns a service's answer into a price: a number of cents for `200 OK`,
+/// or no price at all for `404 Not Found`.
+#[cfg(feature = "http")]
+fn read_price<M: Money>(status: u16, body: &str) -> Result<Option<M>, SourceError> {
+    match status {
+        200 => body.trim().parse::<i64>()
+            .map(|cents| Some(M::from_cents(cents)))
+            .map_err(|_| SourceError::BadResponse(body.to_string())),
+        404 => Ok(None),
+        other => Err(SourceError::Status(other))
+    }
+}
+
+#[cfg(feature = "http")]
+fn transport(e: reqwest::Error) -> SourceError {
+    SourceError::Transport(e.to_string())
+}
+
+/// A `PriceSource` asking a pricing service over HTTP, blocking the calling
+/// thread until it answers.
+///
+/// `reqwest`'s blocking client runs a small async runtime of its own, and
+/// panics if created or used from inside another one; async code should use
+/// `AsyncHttpPriceSource` instead.
+#[cfg(feature = "http")]
+pub struct HttpPriceSource {
+    base: String,
+    client: reqwest::blocking::Client
+}
+
+#[cfg(feature = "http")]
+impl HttpPriceSource {
+
+    /// A source asking the service at `base`, such as
+    /// `http://prices.internal:8080`, and giving up after `timeout`.
+    pub fn new(base: impl Into<String>,
+               timeout: std::time::Duration) -> Result<HttpPriceSource, SourceError> {
+        let client = reqwest::blocking::Client::builder().timeout(timeout).build().map_err(transport)?;
+        Ok(HttpPriceSource { base: base.into(), client })
+    }
+}
+
+#[cfg(feature = "http")]
+impl<M: Money> PriceSource<M> for HttpPriceSource {
+    fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        let response = self.client.get(price_url(&self.base, product)).send().map_err(transport)?;
+        let status = response.status().as_u16();
+        read_price(status, &response.text().map_err(transport)?)
+    }
+}
+
+/// An `AsyncPriceSource` asking a pricing service over HTTP.
+#[cfg(feature = "http")]
+pub struct AsyncHttpPriceSource {
+    base: String,
+    client: reqwest::Client
+}
+
+#[cfg(feature = "http")]
+impl AsyncHttpPriceSource {
+
+    /// A source asking the service at `base`, and giving up after `timeout`.
+    pub fn new(base: impl Into<String>,
+               timeout: std::time::Duration) -> Result<AsyncHttpPriceSource, SourceError> {
+        let client = reqwest::Client::builder().timeout(timeout).build().map_err(transport)?;
+        Ok(AsyncHttpPriceSource { base: base.into(), client })
+    }
+}
+
+#[cfg(feature = "http")]
+impl<M: Money> AsyncPriceSource<M> for AsyncHttpPriceSource {
+    async fn price_of(&self, product: char) -> Result<Option<M>, SourceError> {
+        let response = self.client.get(price_url(&self.base, product)).send().await.map_err(transport)?;
+        let status = response.status().as_u16();
+        read_price(status, &response.text().await.map_err(transport)?)
+    }
+}
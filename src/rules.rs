@@ -0,0 +1,10 @@
+//! Pricing rules: the `PricingRule` trait, the rules this crate ships, and
+//! the registry building them by name.
+//!
+//! A facade, like `market`: the rules themselves live in `pricing-core`,
+//! where they work without the standard library.
+
+pub use crate::core::lint::LintWarning;
+pub use crate::core::rules::{BestOfferPrice, BundlePrice, Describable, FlatPrice, PricingRule, RuleError, SubstitutePrice,
+                             total};
+pub use crate::registry::{RegistryError, RuleRegistry, RuleSpec};
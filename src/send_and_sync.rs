@@ -0,0 +1,127 @@
+//! `Send` and `Sync`: how the compiler rules out data races, compared with
+//! Java's culture of `synchronized`.
+//!
+//! Any Java object can be reached from any thread that has a reference to
+//! it. Whether that is safe is written in the Javadoc, if anywhere: "this
+//! class is not thread-safe", or `@ThreadSafe` from *Java Concurrency in
+//! Practice*, which nothing checks. The cure is `synchronized` on every
+//! method that touches shared state, or a `ConcurrentHashMap` for the
+//! `HashMap`, applied by convention and review, and a forgotten one shows
+//! up as a total that is occasionally wrong under load.
+//!
+//! Rust makes thread safety part of the type, with two marker traits that
+//! have no methods:
+//!
+//! * `Send`: a value may be *moved* to another thread. Nearly everything is
+//!   `Send`; `Rc` is not, as two threads could change its count at once.
+//! * `Sync`: a value may be *shared*, through `&T`, by several threads at
+//!   once. `T` is `Sync` exactly when `&T` is `Send`. `Cell` and `RefCell`
+//!   are not, as their contents change behind a shared reference with no
+//!   lock; `Mutex` and the atomics are.
+//!
+//! Nobody writes `impl Send`. The compiler works both out from a type's
+//! fields, as it would a `derive`, so a struct with an `Rc` inside is not
+//! `Send` either, however deep the `Rc` is buried. `thread::spawn` demands
+//! `Send` of what its closure captures, and that is the whole mechanism: a
+//! type that is not safe to share never gets to another thread at all.
+//!
+//! # The attempt
+//!
+//! `shared_rules_implementation::Supermarket` shares its rules through `Rc`
+//! and its takings through `Rc<RefCell<..>>`. Handing one to a thread, as
+//! Java would without a second thought,
+//!
+//! ```text
+//! let (mut markets, _takings) = shared_rules_implementation::Supermarket::demo_chain(1);
+//! let market = markets.remove(0);
+//! thread::spawn(move || market.checkout("AB"));
+//! ```
+//!
+//! does not compile, and the error names the culprits and the path to
+//! them:
+//!
+//! ```text
+//! error[E0277]: `Rc<RefCell<Takings<_>>>` cannot be sent between threads safely
+//!    |
+//!    = help: within `{closure}`, the trait `Send` is not implemented for `Rc<RefCell<Takings<_>>>`
+//! note: required because it appears within the type `shared_rules_implementation::Supermarket<_>`
+//!
+//! error[E0277]: `Rc<(dyn PricingRule<_> + 'static)>` cannot be sent between threads safely
+//! ```
+//!
+//! The first fix is the type: `SyncSupermarket` is the same market with
+//! `Arc` for `Rc` and `Mutex` for `RefCell`, and is both `Send` and `Sync`.
+//!
+//! The second fix is the bounds. Code generic over any `Market` knows
+//! nothing about whether the market can be shared, so this does not compile
+//! either, even when called with a market that could be:
+//!
+//! ```text
+//! fn checkout_in_scope<K: Market>(market: &K, carts: Vec<String>) {
+//!     thread::scope(|scope| for cart in carts {
+//!         scope.spawn(move || market.checkout(&cart));
+//!     });
+//! }
+//!
+//! error[E0277]: `K` cannot be shared between threads safely
+//!   = note: required for `&K` to implement `Send`
+//! help: consider further restricting type parameter `K` with trait `Sync`
+//! ```
+//!
+//! The bound is a promise in the signature, checked at every call, where
+//! Java's equivalent is a sentence in the Javadoc. The two functions below
+//! carry the bounds each needs, and no more: `checkout_in_scope` borrows
+//! the market, so `Sync` is enough, while `checkout_on_threads` hands it to
+//! threads that may outlive the call, so the market must be in an `Arc`,
+//! and be `Send` and `'static` as well.
+//!
+//! None of this makes a program free of every concurrency bug. A deadlock,
+//! or two locks taken in an order that loses an update, still compiles. What
+//! cannot compile is a *data race*: two threads touching the same memory at
+//! once, at least one of them writing, with nothing ordering them.
+
+use std::sync::Arc;
+use std::thread;
+use crate::core::money::{Money, Cents};
+use crate::reasonable_implementation::{CheckoutError, Market, Supermarket};
+use crate::shared_rules_implementation::SyncSupermarket;
+
+// Checked while compiling: the call only type-checks for a `T` that is both
+// `Send` and `Sync`, so dropping an `Arc` for an `Rc` in either market
+// would fail the build here, not in a test.
+const _: () = {
+    const fn shareable<T: Send + Sync>() {}
+    shareable::<Supermarket<Cents>>();
+    shareable::<SyncSupermarket<Cents>>();
+};
+
+/// The price of every cart, each on a scoped thread of its own, in the
+/// carts' order.
+///
+/// The threads borrow `market`, and `thread::scope` joins them all before
+/// returning, so `K` need only be `Sync`: shareable through `&K`.
+pub fn checkout_in_scope<K, M>(market: &K, carts: &[String]) -> Vec<Result<M, CheckoutError>>
+where K: Market<M> + Sync, M: Money + Send {
+    thread::scope(|scope| {
+        let handles: Vec<_> = carts.iter()
+            .map(|cart| scope.spawn(move || market.checkout(cart)))
+            .collect();
+        handles.into_iter().map(|handle| handle.join().expect("no checkout panics")).collect()
+    })
+}
+
+/// The price of every cart, each on a thread of its own, in the carts'
+/// order.
+///
+/// `thread::spawn` cannot know the threads will be joined before this
+/// function returns, so they may borrow nothing: each gets a clone of the
+/// `Arc`, and `K` must be `Send + Sync + 'static` for an `Arc<K>` to cross
+/// to another thread. `M` comes back across, so it must be `Send` too.
+pub fn checkout_on_threads<K, M>(market: Arc<K>, carts: Vec<String>) -> Vec<Result<M, CheckoutError>>
+where K: Market<M> + Send + Sync + 'static, M: Money + Send + 'static {
+    let handles: Vec<_> = carts.into_iter().map(|cart| {
+        let market = Arc::clone(&market);
+        thread::spawn(move || market.checkout(&cart))
+    }).collect();
+    handles.into_iter().map(|handle| handle.join().expect("no checkout panics")).collect()
+}
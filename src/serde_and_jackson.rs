@@ -0,0 +1,205 @@
+//! Serialization with serde, compared with Jackson.
+//!
+//! Jackson finds out how to map a Java bean by reflection when the program
+//! runs, steered by annotations on its fields. Serde does the same job at
+//! compile time: `#[derive(Serialize, Deserialize)]` writes the mapping code
+//! for a type, steered by `#[serde(...)]` attributes, and the format, JSON
+//! through `serde_json` or TOML through `toml`, is a separate crate that
+//! never needs to know the type. Most annotations have a counterpart:
+//!
+//! | Jackson                                          | serde                                  |
+//! |--------------------------------------------------|----------------------------------------|
+//! | `@JsonProperty("each")`                          | `#[serde(rename = "each")]`            |
+//! | `@JsonNaming(LowerCamelCaseStrategy.class)`      | `#[serde(rename_all = "camelCase")]`   |
+//! | `@JsonAlias("price")`                            | `#[serde(alias = "price")]`            |
+//! | `@JsonInclude(Include.NON_EMPTY)`                | `#[serde(skip_serializing_if = "Vec::is_empty")]` |
+//! | a field initializer, kept when the key is absent | `#[serde(default)]`                    |
+//! | `@JsonSerialize(using = DollarsSerializer.class)` | `#[serde(with = "dollars")]`, or an `impl Serialize` |
+//! | `FAIL_ON_UNKNOWN_PROPERTIES`                     | `#[serde(deny_unknown_fields)]`        |
+//!
+//! The last row is the wrong way round: Jackson fails on unknown properties
+//! unless told not to, and serde ignores them unless told not to.
+//!
+//! Elsewhere in the crate, `Receipt`, `Adjustment` and `CatalogEntry` derive
+//! both traits with the `serde` feature, and `Cents` is written as a plain
+//! number of cents. The `PriceList` here is a catalog kept as a document,
+//! which has been through two versions:
+//!
+//! ```text
+//! {"items": [{"product": "A", "price": 2000}]}
+//!
+//! {"version": 2, "items": [{"product": "A", "each": "$20.00"},
+//!                          {"product": "B", "each": "$50.00",
+//!                           "offers": [{"size": 5, "cost": "$150.00"}]}]}
+//! ```
+//!
+//! Version 1 had no `version` key, named the price `price` and wrote it in
+//! cents. Version 2 renamed it `each`, writes money as people do, and added
+//! offers. The attributes below read both, and always write version 2.
+//! Only with the `serde` feature.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::core::money::Cents;
+use crate::core::rules::{BestOfferPrice, BundlePrice, FlatPrice, RuleError};
+use crate::reasonable_implementation::BoxedRule;
+
+/// The version `PriceList`s are written in.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// A catalog as a document, for JSON or any other serde format.
+///
+/// Like a Java bean, its fields are all public and it has no invariants:
+/// `to_rules` checks the prices, just as the rule constructors would after
+/// a Jackson mapping.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct PriceList {
+    /// Documents from before the key existed are version 1. `default`
+    /// names a function to call for a missing key, where plain `default`
+    /// would call `Default::default` and make it 0.
+    #[serde(default = "first_version")]
+    pub version: u32,
+    pub items: Vec<PriceListItem>
+}
+
+/// The price of one product.
+///
+/// `deny_unknown_fields` makes a misspelt `ofers` an error, rather than a
+/// product quietly sold without its offers.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriceListItem {
+    /// Serde writes a `char` as a string of one character.
+    pub product: char,
+    /// Read from `each`, or from version 1's `price`; always written as
+    /// `each`. `with` hands the field to the functions in `dollars`.
+    #[serde(alias = "price", with = "dollars")]
+    pub each: Cents,
+    /// Absent from version 1, so `default` starts it empty, and left out
+    /// of the output when empty, to keep flat prices short.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offers: Vec<Offer>
+}
+
+/// `cost` for every `size` items.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Offer {
+    pub size: u64,
+    #[serde(with = "dollars")]
+    pub cost: Cents
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+impl PriceList {
+
+    /// A current price list of `items`.
+    pub fn new(items: Vec<PriceListItem>) -> PriceList {
+        PriceList { version: CURRENT_VERSION, items }
+    }
+
+    /// The rules the list describes, as `price_rules!` would build them: a
+    /// `FlatPrice` for an item without offers, a `BundlePrice` for one
+    /// offer, and a `BestOfferPrice` for more.
+    pub fn to_rules(&self) -> Result<Vec<BoxedRule<Cents>>, RuleError> {
+        self.items.iter().map(|item| -> Result<BoxedRule<Cents>, RuleError> {
+            Ok(match item.offers.as_slice() {
+                [] => Box::new(FlatPrice::new(item.product, item.each)?),
+                [offer] => Box::new(BundlePrice::new(item.product, item.each, offer.size, offer.cost)?),
+                offers => Box::new(offers.iter().try_fold(
+                    BestOfferPrice::new(item.product, item.each)?,
+                    |rule, offer| rule.with_offer(offer.size, offer.cost))?)
+            })
+        }).collect()
+    }
+}
+
+/// `Cents` as people write them, `"$20.00"`, for `#[serde(with = "dollars")]`.
+///
+/// `with` expects a module holding a `serialize` and a `deserialize`
+/// function, and uses them for that one field only: `Cents` itself still
+/// derives its traits, and is written as a number everywhere else. Jackson
+/// does the same with `@JsonSerialize(using = ...)` on a field.
+pub mod dollars {
+    use serde::{Deserializer, Serializer};
+    use crate::core::money::Cents;
+    use super::Dollars;
+
+    pub fn serialize<S: Serializer>(cents: &Cents, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&Dollars(*cents), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cents, D::Error> {
+        let Dollars(cents) = serde::Deserialize::deserialize(deserializer)?;
+        Ok(cents)
+    }
+}
+
+/// An amount written as `"$20.00"`, and read either that way or as a
+/// whole number of cents.
+///
+/// Its `Serialize` and `Deserialize` are written by hand, the counterparts
+/// of a Jackson `JsonSerializer<Dollars>` and `JsonDeserializer<Dollars>`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Dollars(pub Cents);
+
+/// Serializing is telling the `Serializer` what the value is, here a
+/// string. The serializer decides what a string looks like in its format.
+impl Serialize for Dollars {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+/// Deserializing goes the other way round. The `Deserializer` reads
+/// whatever the input holds and calls the `Visitor` method for it, a
+/// string or an integer here; the methods left out refuse anything else,
+/// with an error saying what was `expecting`.
+impl<'de> Deserialize<'de> for Dollars {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Dollars, D::Error> {
+        struct DollarsVisitor;
+
+        impl serde::de::Visitor<'_> for DollarsVisitor {
+            type Value = Dollars;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an amount such as \"$20.00\", or a whole number of cents")
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, cents: i64) -> Result<Dollars, E> {
+                Ok(Dollars(Cents(cents)))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, cents: u64) -> Result<Dollars, E> {
+                i64::try_from(cents).map(|cents| Dollars(Cents(cents)))
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(cents), &self))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, text: &str) -> Result<Dollars, E> {
+                parse_dollars(text).map(Dollars)
+                    .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(text), &self))
+            }
+        }
+
+        deserializer.deserialize_any(DollarsVisitor)
+    }
+}
+
+/// `"$20.00"` or `"-$1.50"` as `Cents`, or `None` for anything else,
+/// including amounts without exactly two digits of cents.
+fn parse_dollars(text: &str) -> Option<Cents> {
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text)
+    };
+    let (whole, fraction) = unsigned.strip_prefix('$')?.split_once('.')?;
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if !digits(whole) || !digits(fraction) || fraction.len() != 2 {
+        return None;
+    }
+    let cents = whole.parse::<i64>().ok()?.checked_mul(100)?.checked_add(fraction.parse::<i64>().ok()?)?;
+    Some(Cents(sign * cents))
+}
@@ -0,0 +1,274 @@
+//! Supermarkets sharing their rules, and a running tally of their takings,
+//! through reference-counted smart pointers.
+//!
+//! In Java, sharing is the default: every object variable is a reference,
+//! and two markets built from one list of rules simply refer to the same
+//! rule objects, which live until the garbage collector finds nothing
+//! referring to them. A `reasonable_implementation::Supermarket` cannot do
+//! that, as it owns its rules, each in a `Box`, and a `Box` has exactly one
+//! owner. Sharing has to be asked for, and which pointer to ask for depends
+//! on what the sharing needs:
+//!
+//! * `Rc<T>`, *reference counted*, is the closest thing to a Java
+//!   reference. Cloning an `Rc` copies the pointer, not the value, and adds
+//!   one to a count; dropping one takes one away, and the value is freed as
+//!   soon as the count reaches zero, not at some later collection. Unlike a
+//!   Java reference, an `Rc` only gives read access, and two values
+//!   pointing at each other keep each other alive forever, where the
+//!   garbage collector would free both. `Weak` breaks such cycles.
+//! * `RefCell<T>` lets the value inside be changed through a shared
+//!   pointer, which `Rc` alone does not allow. It checks the borrowing
+//!   rules when the program runs instead of when it compiles, and panics if
+//!   they are broken, as Java throws `ConcurrentModificationException`.
+//! * `Arc<T>` and `Mutex<T>` are the same pair made safe for threads. `Arc`
+//!   counts its references atomically, which costs a little more, and a
+//!   `Mutex` makes threads take turns, as `synchronized` does. Unlike
+//!   `synchronized`, which guards code, a `Mutex` owns the data it guards,
+//!   so it cannot be reached without the lock.
+//!
+//! The compiler knows the difference: `Rc` and `RefCell` are not `Send`,
+//! so a `Supermarket` here cannot be moved to another thread, where the
+//! counts could be corrupted. A `SyncSupermarket` can.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::vec::Vec;
+use crate::core::counting::count_items;
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{PricingRule, total};
+#[cfg(feature = "demo-catalog")]
+use crate::core::rules::{FlatPrice, BundlePrice};
+use crate::reasonable_implementation::{CheckoutError, Market};
+
+/// A rule that any number of single-threaded markets may share.
+pub type SharedRule<M> = Rc<dyn PricingRule<M>>;
+
+/// A rule that any number of markets, on any threads, may share.
+pub type SyncRule<M> = Arc<dyn PricingRule<M> + Send + Sync>;
+
+/// How many carts a group of markets has priced, and for how much.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Takings<M: Money = Cents> {
+    /// The number of carts priced successfully.
+    pub carts: u64,
+    /// What those carts came to, saturating at the largest amount.
+    pub total: M
+}
+
+impl<M: Money> Default for Takings<M> {
+    fn default() -> Takings<M> {
+        Takings { carts: 0, total: M::zero() }
+    }
+}
+
+impl<M: Money> Takings<M> {
+
+    /// Counts one more cart, priced at `price`.
+    fn record(&mut self, price: M) {
+        self.carts += 1;
+        self.total = self.total.saturating_plus(price);
+    }
+}
+
+/// The demo catalog's rules, ready to be shared between markets.
+///
+/// `Rc::new` moves each rule to the heap, next to its reference count, and
+/// the `Rc<FlatPrice>` turns into an `Rc<dyn PricingRule>` by itself, as a
+/// `Box` would.
+#[cfg(feature = "demo-catalog")]
+pub fn demo_rules<M: Money>() -> Vec<SharedRule<M>> {
+    let valid = "demo prices are valid";
+    vec![
+        Rc::new(FlatPrice::new('A', M::from_cents(2000)).expect(valid)),
+        Rc::new(BundlePrice::new('B', M::from_cents(5000), 5, M::from_cents(15000)).expect(valid)),
+        Rc::new(FlatPrice::new('C', M::from_cents(3000)).expect(valid)),
+    ]
+}
+
+/// A Supermarket sharing its rules, and its takings, with other markets on
+/// the same thread.
+pub struct Supermarket<M: Money = Cents> {
+
+    /// The rules, each of which other markets may hold too.
+    price_rules: Vec<SharedRule<M>>,
+
+    /// The takings of every market sharing this `Rc`.
+    ///
+    /// In Java this would be a plain field of a shared `Takings` object,
+    /// changed with `takings.carts++`. Here `checkout` only gets `&self`,
+    /// and an `Rc` only hands out shared references, so the `RefCell` is
+    /// needed to change it at all.
+    takings: Rc<RefCell<Takings<M>>>,
+
+    /// What to do when a total grows too large for the money backend.
+    overflow_policy: OverflowPolicy
+}
+
+impl<M: Money> Supermarket<M> {
+
+    /// A Supermarket with no rules, adding to `takings`.
+    pub fn new(takings: Rc<RefCell<Takings<M>>>) -> Supermarket<M> {
+        Supermarket { price_rules: Vec::new(), takings, overflow_policy: Default::default() }
+    }
+
+    /// A chain of `stores` markets, all sharing the demo rules and one tally
+    /// of takings, which is returned alongside them.
+    ///
+    /// `Rc::clone(&rule)` copies the pointer, so each rule exists once, no
+    /// matter how many stores there are; `rule.clone()` would do the same,
+    /// but the longer form makes plain that no rule is being copied.
+    #[cfg(feature = "demo-catalog")]
+    pub fn demo_chain(stores: usize) -> (Vec<Supermarket<M>>, Rc<RefCell<Takings<M>>>) {
+        let rules = demo_rules();
+        let takings = Rc::new(RefCell::new(Takings::default()));
+        let markets = (0..stores).map(|_| {
+            rules.iter().fold(Supermarket::new(Rc::clone(&takings)),
+                              |market, rule| market.with_rule(Rc::clone(rule)))
+        }).collect();
+        (markets, takings)
+    }
+
+    /// Adds a shared `rule` to the catalog, returning the updated
+    /// Supermarket.
+    pub fn with_rule(mut self, rule: SharedRule<M>) -> Supermarket<M> {
+        self.price_rules.push(rule);
+        self
+    }
+
+    /// Replaces the overflow policy, returning the updated Supermarket.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Supermarket<M> {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// The rules in the catalog, for checking which are shared.
+    ///
+    /// `Rc::ptr_eq` tells whether two `Rc`s point at the same rule, which is
+    /// Java's `==` on references; `==` on `Rc`s compares the values, like
+    /// Java's `equals`.
+    pub fn rules(&self) -> &[SharedRule<M>] {
+        &self.price_rules
+    }
+
+    /// Prices a sequence of items, reporting an overflow under the
+    /// `Checked` policy as an error, and adds a successful price to the
+    /// takings.
+    pub fn try_checkout(&self, items: String) -> Result<M, Overflow> {
+        let counts = count_items::<u64>(&items);
+        let price = total(self.price_rules.iter().map(|rule| &**rule), &counts,
+                          self.overflow_policy)?;
+        // `borrow_mut` checks, at runtime, that nobody else is reading or
+        // changing the takings, and panics if somebody is. The borrow ends
+        // with this statement, when the guard it returns is dropped.
+        self.takings.borrow_mut().record(price);
+        Ok(price)
+    }
+
+    /// The takings so far, of this market and every other sharing them.
+    ///
+    /// `borrow` gives shared access, as long as nobody holds `borrow_mut`.
+    /// The `Takings` are copied out, rather than a reference handed back,
+    /// so that the caller never holds the borrow open.
+    pub fn takings(&self) -> Takings<M> {
+        *self.takings.borrow()
+    }
+}
+
+impl<M: Money> Market<M> for Supermarket<M> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        Ok(self.try_checkout(items.to_string())?)
+    }
+}
+
+/// A Supermarket sharing its rules, and its takings, with other markets on
+/// any thread.
+///
+/// This is `Supermarket` with `Arc` for `Rc` and `Mutex` for `RefCell`, and
+/// nothing else changed. Both could have been written once, generic over
+/// the pointer types, but the two side by side show the difference better.
+pub struct SyncSupermarket<M: Money = Cents> {
+
+    /// The rules, each of which other markets may hold too.
+    price_rules: Vec<SyncRule<M>>,
+
+    /// The takings of every market sharing this `Arc`.
+    takings: Arc<Mutex<Takings<M>>>,
+
+    /// What to do when a total grows too large for the money backend.
+    overflow_policy: OverflowPolicy
+}
+
+impl<M: Money> SyncSupermarket<M> {
+
+    /// A Supermarket with no rules, adding to `takings`.
+    pub fn new(takings: Arc<Mutex<Takings<M>>>) -> SyncSupermarket<M> {
+        SyncSupermarket { price_rules: Vec::new(), takings, overflow_policy: Default::default() }
+    }
+
+    /// A chain of `stores` markets, all sharing the demo rules and one tally
+    /// of takings, which is returned alongside them.
+    #[cfg(feature = "demo-catalog")]
+    pub fn demo_chain(stores: usize) -> (Vec<SyncSupermarket<M>>, Arc<Mutex<Takings<M>>>) {
+        let valid = "demo prices are valid";
+        let rules: [SyncRule<M>; 3] = [
+            Arc::new(FlatPrice::new('A', M::from_cents(2000)).expect(valid)),
+            Arc::new(BundlePrice::new('B', M::from_cents(5000), 5, M::from_cents(15000))
+                         .expect(valid)),
+            Arc::new(FlatPrice::new('C', M::from_cents(3000)).expect(valid)),
+        ];
+        let takings = Arc::new(Mutex::new(Takings::default()));
+        let markets = (0..stores).map(|_| {
+            rules.iter().fold(SyncSupermarket::new(Arc::clone(&takings)),
+                              |market, rule| market.with_rule(Arc::clone(rule)))
+        }).collect();
+        (markets, takings)
+    }
+
+    /// Adds a shared `rule` to the catalog, returning the updated
+    /// Supermarket.
+    pub fn with_rule(mut self, rule: SyncRule<M>) -> SyncSupermarket<M> {
+        self.price_rules.push(rule);
+        self
+    }
+
+    /// Replaces the overflow policy, returning the updated Supermarket.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> SyncSupermarket<M> {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// The rules in the catalog, for checking which are shared.
+    pub fn rules(&self) -> &[SyncRule<M>] {
+        &self.price_rules
+    }
+
+    /// Prices a sequence of items, reporting an overflow under the
+    /// `Checked` policy as an error, and adds a successful price to the
+    /// takings.
+    ///
+    /// Only the update of the takings holds the lock; the pricing itself
+    /// reads nothing but the rules, which are never changed, so it needs
+    /// none, and markets on different threads price their carts in
+    /// parallel.
+    pub fn try_checkout(&self, items: String) -> Result<M, Overflow> {
+        let counts = count_items::<u64>(&items);
+        let price = total(self.price_rules.iter().map(|rule| &**rule), &counts,
+                          self.overflow_policy)?;
+        // `lock` fails only if another thread panicked while holding the
+        // lock, which `record` cannot do, so the tally is still sound.
+        self.takings.lock().expect("recording takings never panics").record(price);
+        Ok(price)
+    }
+
+    /// The takings so far, of this market and every other sharing them.
+    pub fn takings(&self) -> Takings<M> {
+        *self.takings.lock().expect("recording takings never panics")
+    }
+}
+
+impl<M: Money> Market<M> for SyncSupermarket<M> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        Ok(self.try_checkout(items.to_string())?)
+    }
+}
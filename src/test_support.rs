@@ -0,0 +1,128 @@
+//! Stand-ins for `Market` and `PricingRule`, available with the
+//! `test-support` feature, for applications that want to test their own
+//! checkout flows without building a real catalog.
+//!
+//! A Java developer would reach for Mockito here. Rust has mocking crates
+//! too, but a hand-written *fake* is usually simpler: an ordinary type
+//! implementing the trait, scripted through builder methods and remembering
+//! how it was used.
+//!
+//! Both fakes remember their calls through `&self`, as the traits demand,
+//! so they keep their records behind a `Mutex` or an atomic. That also makes
+//! them `Send + Sync`, so a `FakeRule` fits in a `BoxedRule`.
+//!
+//! Enable the feature only for tests, through `[dev-dependencies]`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::core::counting::Counts;
+use crate::core::money::{Money, Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{Describable, PricingRule};
+use crate::reasonable_implementation::{CheckoutError, Market};
+
+/// A `Market` answering with scripted totals, and remembering every cart it
+/// was asked to price.
+pub struct FakeMarket<M: Money = Cents> {
+    default_total: M,
+    totals: HashMap<String, M>,
+    carts: Mutex<Vec<String>>
+}
+
+impl<M: Money> FakeMarket<M> {
+
+    /// A market pricing every cart at `total`.
+    pub fn returning(total: M) -> FakeMarket<M> {
+        FakeMarket { default_total: total, totals: HashMap::new(), carts: Mutex::new(Vec::new()) }
+    }
+
+    /// Prices `cart`, exactly as written, at `total` instead, returning the
+    /// updated market.
+    pub fn with_total(mut self, cart: impl Into<String>, total: M) -> FakeMarket<M> {
+        self.totals.insert(cart.into(), total);
+        self
+    }
+
+    /// Every cart priced so far, in order.
+    pub fn carts(&self) -> Vec<String> {
+        self.carts.lock().unwrap().clone()
+    }
+}
+
+impl<M: Money> Market<M> for FakeMarket<M> {
+    fn checkout(&self, items: &str) -> Result<M, CheckoutError> {
+        let total = self.totals.get(items).copied().unwrap_or(self.default_total);
+        self.carts.lock().unwrap().push(items.to_string());
+        Ok(total)
+    }
+}
+
+/// A `PricingRule` charging a scripted amount, or overflowing, however many
+/// items it is shown, and counting how often it was asked.
+pub struct FakeRule<M: Money = Cents> {
+    /// `None` stands for "report an overflow".
+    amount: Option<M>,
+    products: Vec<char>,
+    description: String,
+    calls: AtomicUsize
+}
+
+impl<M: Money> FakeRule<M> {
+
+    /// A rule charging `amount` for every cart.
+    pub fn charging(amount: M) -> FakeRule<M> {
+        FakeRule {
+            amount: Some(amount),
+            products: Vec::new(),
+            description: format!("fake rule charging {}", amount),
+            calls: AtomicUsize::new(0)
+        }
+    }
+
+    /// A rule reporting an `Overflow` for every cart, to exercise error
+    /// handling.
+    pub fn overflowing() -> FakeRule<M> {
+        FakeRule {
+            amount: None,
+            products: Vec::new(),
+            description: "fake rule overflowing".to_string(),
+            calls: AtomicUsize::new(0)
+        }
+    }
+
+    /// Claims `products`, as returned by `PricingRule::products`, returning
+    /// the updated rule.
+    pub fn for_products(mut self, products: &[char]) -> FakeRule<M> {
+        self.products = products.to_vec();
+        self
+    }
+
+    /// Replaces the description, returning the updated rule.
+    pub fn describing(mut self, description: impl Into<String>) -> FakeRule<M> {
+        self.description = description.into();
+        self
+    }
+
+    /// How many times `price` has been called.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+}
+
+impl<M: Money> Describable for FakeRule<M> {
+    fn describe(&self) -> String {
+        self.description.clone()
+    }
+}
+
+impl<M: Money> PricingRule<M> for FakeRule<M> {
+    fn price(&self, _character_counts: &Counts,
+             _policy: OverflowPolicy) -> Result<M, Overflow> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.amount.ok_or(Overflow)
+    }
+
+    fn products(&self) -> Vec<char> {
+        self.products.clone()
+    }
+}
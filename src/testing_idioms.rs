@@ -0,0 +1,158 @@
+//! Testing idioms, compared with JUnit 5 and AssertJ.
+//!
+//! Rust's test harness is built in: `cargo test` finds and runs every
+//! function marked `#[test]`, with no framework to add. A test passes if it
+//! returns, and fails if it panics, which is what `assert!`, `assert_eq!`
+//! and `unwrap` do when unhappy. Tests live in three places, each with a
+//! JUnit counterpart:
+//!
+//! * *Unit tests* sit in the file they test, in a `#[cfg(test)] mod tests`,
+//!   as at the bottom of this one. `cfg(test)` compiles the module only for
+//!   `cargo test`, and being a child module it may call private functions,
+//!   which a JUnit test in `src/test/java` can only do for package-private
+//!   ones, and only by mirroring the package.
+//! * *Integration tests* are separate crates, in `tests/rust` here, that
+//!   see only the public API, as a user would. This crate keeps almost all
+//!   of its tests there, beside the Java ones they were ported from.
+//! * *Doctests* are the examples in documentation comments, such as the one
+//!   on `split` below. `cargo test` compiles and runs each, so an example
+//!   cannot quietly go stale, which no Javadoc `{@code}` block guarantees.
+//!
+//! Most JUnit and AssertJ features map onto plain code:
+//!
+//! | JUnit 5 / AssertJ                         | Rust                                  |
+//! |-------------------------------------------|---------------------------------------|
+//! | `@Test`                                   | `#[test]`                             |
+//! | `@ParameterizedTest` with `@CsvSource`    | a loop over an array of cases         |
+//! | `assertThrows(IllegalArgumentException.class, ...)` | `#[should_panic(expected = "...")]` |
+//! | `assertEquals(expected, actual, message)` | `assert_eq!(expected, actual, "{}", message)` |
+//! | `assertThat(receipt).hasTotal(...)`       | `assert_that(&receipt).has_total(...)`, below |
+//! | `@Disabled`                               | `#[ignore]`, run with `--ignored`     |
+//! | an abstract test class per implementation | a macro, `market_suite!` in `tests/rust/common` |
+//! | `@BeforeEach`                             | a function each test calls            |
+//!
+//! `tests/rust/testing_idioms.rs` puts each of these to work.
+
+use crate::core::money::{Money, Cents};
+use crate::receipt::{Receipt, ReceiptLine};
+
+/// How `count` items divide into bundles of `bundle_size`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Split {
+    pub bundles: u64,
+    pub singles: u64
+}
+
+/// Divides `count` items into as many bundles of `bundle_size` as they make,
+/// and the singles left over.
+///
+/// ```
+/// use rust_for_java_devs::testing_idioms::{split, Split};
+///
+/// assert_eq!(Split { bundles: 2, singles: 1 }, split(11, 5));
+/// ```
+///
+/// # Panics
+///
+/// If `bundle_size` is zero, as a bundle of nothing cannot be priced. The
+/// `# Panics` heading is the convention for saying so, where Java would
+/// write `@throws IllegalArgumentException`.
+pub fn split(count: u64, bundle_size: u64) -> Split {
+    assert!(bundle_size > 0, "bundles must hold at least one item");
+    Split { bundles: count / bundle_size, singles: remainder(count, bundle_size) }
+}
+
+/// Private, and so only reachable from the unit tests below.
+fn remainder(count: u64, bundle_size: u64) -> u64 {
+    count % bundle_size
+}
+
+/// Starts a fluent assertion about `receipt`, AssertJ's `assertThat`.
+///
+/// Each check panics with a message listing the whole receipt, so a
+/// failure says more than which two numbers differed.
+pub fn assert_that<M: Money>(receipt: &Receipt<M>) -> ReceiptAssert<'_, M> {
+    ReceiptAssert { receipt }
+}
+
+/// Checks on one receipt, chained as in AssertJ.
+///
+/// The methods are `#[track_caller]`, so a failed check reports the line of
+/// the test calling it, rather than a line in this file. That is what lets
+/// helpers like these stand in for assertion methods.
+pub struct ReceiptAssert<'r, M: Money> {
+    receipt: &'r Receipt<M>
+}
+
+impl<'r, M: Money> ReceiptAssert<'r, M> {
+
+    /// Checks the amount due.
+    #[track_caller]
+    pub fn has_total(self, total: M) -> ReceiptAssert<'r, M> {
+        if self.receipt.total != total {
+            self.fail(&format!("expected a total of {}, but it was {}", total, self.receipt.total));
+        }
+        self
+    }
+
+    /// Checks that `product` has a line of `quantity` items.
+    #[track_caller]
+    pub fn has_line(self, product: char, quantity: u64) -> ReceiptAssert<'r, M> {
+        match self.line_for(product) {
+            Some(line) if line.quantity == quantity => {},
+            Some(line) => self.fail(&format!("expected {} x{}, but found x{}", product, quantity,
+                                             line.quantity)),
+            None => self.fail(&format!("expected a line for {}, but there was none", product))
+        }
+        self
+    }
+
+    /// Checks that `product` was sold under at least one promotion.
+    #[track_caller]
+    pub fn has_promotion_for(self, product: char) -> ReceiptAssert<'r, M> {
+        if self.line_for(product).is_none_or(|line| line.promotions.is_empty()) {
+            self.fail(&format!("expected a promotion for {}", product));
+        }
+        self
+    }
+
+    /// Checks that no line is for `product`.
+    #[track_caller]
+    pub fn has_no_line_for(self, product: char) -> ReceiptAssert<'r, M> {
+        if self.line_for(product).is_some() {
+            self.fail(&format!("expected no line for {}", product));
+        }
+        self
+    }
+
+    fn line_for(&self, product: char) -> Option<&'r ReceiptLine<M>> {
+        self.receipt.lines.iter().find(|line| line.product == Some(product))
+    }
+
+    #[track_caller]
+    fn fail(&self, problem: &str) -> ! {
+        panic!("{}, in the receipt:\n{}", problem, self.receipt)
+    }
+}
+
+/// A receipt with no lines and nothing to pay, for tests to fill in.
+///
+/// A plain function called at the top of each test does the work of
+/// JUnit's `@BeforeEach`, and, returning its fixture rather than setting a
+/// field, cannot leak state from one test to the next.
+pub fn empty_receipt() -> Receipt<Cents> {
+    Receipt { lines: Vec::new(), subtotal: Cents(0), adjustments: Vec::new(), total: Cents(0) }
+}
+
+#[cfg(test)]
+mod tests {
+    // `super::*` brings in everything from the module under test, private
+    // items included.
+    use super::*;
+
+    #[test]
+    fn remainders_are_what_bundles_leave() {
+        assert_eq!(0, remainder(10, 5));
+        assert_eq!(4, remainder(4, 5));
+    }
+}
@@ -0,0 +1,219 @@
+//! Random carts for property tests, available with the `testkit` feature.
+//!
+//! These started life as helpers in this crate's own integration tests.
+//! They are public so that crates writing their own `PricingRule`s can test
+//! them the same way: generate many random carts, price each one, and
+//! compare against a total worked out the dumbest possible way.
+//!
+//! Every generator takes the random number generator to draw from rather
+//! than creating one, and `seeded_rng` builds one from a fixed seed. A
+//! failing test therefore fails the same way on every run, and on every
+//! machine, much like a JUnit test seeding `new Random(42)`.
+//!
+//! Enable the feature only for tests, through `[dev-dependencies]`:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! rust_for_java_devs = { version = "0.0.1", features = ["testkit"] }
+//! ```
+
+use core::ops::Range;
+use std::fs;
+use std::path::Path;
+use rand::{Rng, SeedableRng};
+use rand::seq::IndexedRandom;
+use crate::core::counting::Counts;
+use crate::core::money::Money;
+use crate::reasonable_implementation::{Market, Supermarket};
+use crate::receipt;
+
+/// The random number generator the testkit recommends. `StdRng` produces
+/// the same sequence for the same seed wherever it runs, though not
+/// necessarily across versions of the `rand` crate.
+pub type TestRng = rand::rngs::StdRng;
+
+/// A generator that always produces the same sequence for `seed`.
+pub fn seeded_rng(seed: u64) -> TestRng {
+    TestRng::seed_from_u64(seed)
+}
+
+/// Builds a cart holding nothing but `item`, with a length drawn from
+/// `lengths`, and returns it along with that length.
+///
+/// `R: Rng + ?Sized` accepts any generator, including a `&mut dyn RngCore`
+/// someone else handed us.
+pub fn generate_char_sequence<R: Rng + ?Sized>(rng: &mut R, item: char,
+                                               lengths: Range<usize>) -> (String, u64) {
+    let n = rng.random_range(lengths);
+    // `repeat` builds the `String` in one go, without a loop.
+    (item.to_string().repeat(n), n as u64)
+}
+
+/// Builds a cart of items drawn from `items`, with a length drawn from
+/// `lengths`, along with the number of times each item was chosen.
+///
+/// The counts are the same `Counts` map that `core::counting::count_items`
+/// produces, so they can be handed straight to a `PricingRule`.
+pub fn generate_mixed_char_sequence<R: Rng + ?Sized>(rng: &mut R, items: &[char],
+                                                     lengths: Range<usize>) -> (String, Counts) {
+    let n = rng.random_range(lengths);
+    let mut cart = String::with_capacity(n);
+    let mut counts = Counts::new();
+    for _i in 0..n {
+        // `if let` is shorthand for a `match` that only cares about one
+        // case; `choose` returns `None` when `items` is empty.
+        if let Some(&item) = items.choose(rng) {
+            cart.push(item);
+            *counts.entry(item).or_insert(0) += 1;
+        }
+    }
+    (cart, counts)
+}
+
+/// Prices every cart in a recorded corpus, one cart per line, and returns
+/// the totals as a snapshot: one `total<TAB>cart` line per cart.
+///
+/// Blank lines and lines starting with `#` are skipped, so a corpus may
+/// explain where its carts came from. The total comes first because carts,
+/// unlike totals, may themselves contain tabs. A cart the market cannot
+/// price is recorded with its error in place of the total, so that a
+/// snapshot can pin failures down as well.
+pub fn golden_totals<M: Money, T: Market<M> + ?Sized>(market: &T, corpus: &str) -> String {
+    let mut snapshot = String::new();
+    for cart in carts(corpus) {
+        let total = match market.checkout(cart) {
+            Ok(total) => total.to_string(),
+            Err(error) => format!("error: {}", error)
+        };
+        snapshot.push_str(&format!("{}\t{}\n", total, cart));
+    }
+    snapshot
+}
+
+/// The carts of a recorded corpus, skipping blank lines and comments.
+fn carts(corpus: &str) -> impl Iterator<Item = &str> {
+    corpus.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+}
+
+/// Prices every cart in a recorded corpus in two markets, typically the
+/// catalog before and after a price change, and explains each cart whose
+/// receipt differs with a `receipt::diff`.
+///
+/// `assert_golden` says *which* totals moved; this says *why*, which is what
+/// a reviewer of the new snapshot wants to know. An empty report means no
+/// cart in the corpus noticed the change.
+pub fn explain_changes<M: Money>(before: &Supermarket<M>, after: &Supermarket<M>,
+                                 corpus: &str) -> String {
+    let mut report = String::new();
+    for cart in carts(corpus) {
+        let explanation = match (before.receipt(cart), after.receipt(cart)) {
+            (Ok(old), Ok(new)) => {
+                let changes = receipt::diff(&old, &new);
+                if changes.is_empty() {
+                    continue;
+                }
+                changes.to_string()
+            },
+            (old, new) if old == new => continue,
+            (old, new) => format!("before: {:?}\nafter: {:?}\n",
+                                  old.map(|r| r.total), new.map(|r| r.total))
+        };
+        report.push_str(cart);
+        report.push('\n');
+        for line in explanation.lines() {
+            report.push_str(&format!("  {}\n", line));
+        }
+    }
+    report
+}
+
+/// Compares a snapshot from `golden_totals` with the one recorded at `path`,
+/// panicking with every cart whose total has changed.
+///
+/// This is the "golden master" technique: rather than state the expected
+/// total of each cart by hand, record what the code produced once, and
+/// from then on treat any difference as a failure until a person has looked
+/// at it. When a change of total is intended, such as after a price change,
+/// run the tests with the environment variable `UPDATE_GOLDEN` set to write
+/// the new snapshot, and review the change to it like any other diff.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(path, actual).unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+        return;
+    }
+    let expected = fs::read_to_string(path).unwrap_or_else(|e| {
+        panic!("cannot read {}: {}; run with UPDATE_GOLDEN=1 to record it", path.display(), e)
+    });
+    if expected == actual {
+        return;
+    }
+    // `zip` stops at the shorter of the two, so a difference in length is
+    // reported separately.
+    let mut changes = Vec::new();
+    for (old, new) in expected.lines().zip(actual.lines()) {
+        if old != new {
+            changes.push(format!("  was {:?}\n  now {:?}", old, new));
+        }
+    }
+    let (old_carts, new_carts) = (expected.lines().count(), actual.lines().count());
+    if old_carts != new_carts {
+        changes.push(format!("  {} carts were recorded, {} were priced", old_carts, new_carts));
+    }
+    panic!("totals differ from {}; run with UPDATE_GOLDEN=1 if this is intended\n{}",
+           path.display(), changes.join("\n"));
+}
+
+/// `cents` in the same money backend as `_total`, a checkout's result.
+///
+/// `assert_price!` needs this to build its expected amount, as the compiler
+/// cannot otherwise tell which backend's `from_cents` to call. It is public
+/// only because the macro expands in other crates; `#[doc(hidden)]` keeps it
+/// out of the documentation.
+#[doc(hidden)]
+pub fn amount_like<M: Money, E>(_total: &Result<M, E>, cents: i64) -> M {
+    M::from_cents(cents)
+}
+
+/// Asserts that a market prices a cart at a given amount, in whole currency
+/// units, so `assert_price!(market, "ABBACBBAB" => 240)` expects $240.00
+/// from the demo catalog. A fractional amount, such as `20.5`, is rounded to
+/// the nearest cent.
+///
+/// A table of carts may be checked against the same market at once, each
+/// one reported by itself if it fails:
+///
+/// ```
+/// use rust_for_java_devs::assert_price;
+/// use rust_for_java_devs::reasonable_implementation::Supermarket;
+///
+/// let market: Supermarket = Supermarket::demo();
+/// assert_price!(market, "ABBACBBAB" => 240);
+/// assert_price!(market, {
+///     "" => 0,
+///     "BBBBB" => 150,
+///     "AXBC" => 100,
+/// });
+/// ```
+///
+/// The market may be any `Market`, from any of this crate's implementations
+/// or from outside it. `#[macro_export]` places the macro at the root of the
+/// crate, as `rust_for_java_devs::assert_price`, whichever module defines
+/// it; and `$crate` inside it always names this crate, however the caller
+/// refers to it.
+#[macro_export]
+macro_rules! assert_price {
+    // Macro rules are tried in order, so the table form, which starts with
+    // a brace, must come before the single cart form.
+    ($market:expr, { $($cart:expr => $amount:expr),+ $(,)? }) => {{
+        let market = &$market;
+        $( $crate::assert_price!(*market, $cart => $amount); )+
+    }};
+    ($market:expr, $cart:expr => $amount:expr) => {{
+        let cart: &str = $cart;
+        let total = $crate::reasonable_implementation::Market::checkout(&$market, cart);
+        let cents = (($amount) as f64 * 100.0).round() as i64;
+        assert_eq!(Ok($crate::testkit::amount_like(&total, cents)), total,
+                   "pricing the cart {:?}", cart);
+    }};
+}
@@ -0,0 +1,133 @@
+//! Pricing a batch of carts on several threads, first with channels, then
+//! with scoped threads, compared with Java's `ExecutorService`.
+//!
+//! Java hands work to threads through an `ExecutorService`: `submit` puts a
+//! task on the pool's queue and returns a `Future`, and `Future.get` waits
+//! for its result. The standard library has no thread pool, but it has the
+//! parts to build one:
+//!
+//! * `std::thread::spawn` starts a thread, like `new Thread(task).start()`,
+//!   and returns a `JoinHandle`, whose `join` waits for the thread to end and
+//!   returns what its closure returned.
+//! * `std::sync::mpsc` channels pass values from thread to thread, as a
+//!   `BlockingQueue` does. The name means *multiple producer, single
+//!   consumer*: a `Sender` may be cloned for every thread, but there is only
+//!   ever one `Receiver`.
+//! * `std::thread::scope` runs threads that are all joined before it
+//!   returns, so that they may borrow from the function that started them.
+//!
+//! Each function below prices the same carts, in the same order, as
+//! `serial` does; only the way the work is spread out differs. Which worker
+//! prices which cart is left to chance, as it is with an `ExecutorService`.
+
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use crate::core::money::Money;
+use crate::reasonable_implementation::{CheckoutError, Supermarket};
+
+/// The price of every cart, one after another, on the calling thread: the
+/// answer the concurrent versions must match.
+pub fn serial<M: Money>(market: &Supermarket<M>, carts: &[String]) -> Vec<Result<M, CheckoutError>> {
+    carts.iter().map(|cart| market.try_checkout(cart.clone())).collect()
+}
+
+/// The price of every cart, worked out by `workers` threads taking carts
+/// from a shared queue, as `Executors.newFixedThreadPool(workers)` would.
+///
+/// ```text
+/// ExecutorService pool = Executors.newFixedThreadPool(workers);
+/// List<Future<Long>> totals = new ArrayList<>();
+/// for (String cart : carts) totals.add(pool.submit(() -> market.checkout(cart)));
+/// pool.shutdown();
+/// ```
+///
+/// The threads `spawn` starts may outlive this function, so they cannot
+/// borrow anything from it: the market comes in an `Arc`, shared by
+/// reference counting, and the carts are moved into the queue. With
+/// `workers` at zero, one thread is used.
+pub fn with_channels<M: Money>(market: Arc<Supermarket<M>>, carts: Vec<String>,
+                               workers: usize) -> Vec<Result<M, CheckoutError>> {
+    let count = carts.len();
+
+    // The queue of work. Every cart is sent, with its position, before any
+    // worker starts; an unbounded channel never blocks a sender.
+    let (jobs, queue) = mpsc::channel::<(usize, String)>();
+    for job in carts.into_iter().enumerate() {
+        jobs.send(job).expect("the queue is still open");
+    }
+    // Dropping the last `Sender` closes the channel, so that `recv` fails
+    // once the queue is empty instead of waiting for more, which is the
+    // pool's `shutdown`.
+    drop(jobs);
+
+    // The single `Receiver` is shared by locking it, as a `BlockingQueue`
+    // locks internally. Each worker holds the lock only while taking one
+    // cart off the queue, not while pricing it.
+    let queue = Arc::new(Mutex::new(queue));
+    // The results come back on a second channel, in whatever order the
+    // workers finish, each tagged with the position of its cart.
+    let (results, finished) = mpsc::channel();
+
+    let handles: Vec<_> = (0..workers.max(1)).map(|_| {
+        let market = Arc::clone(&market);
+        let queue = Arc::clone(&queue);
+        let results = results.clone();
+        thread::spawn(move || loop {
+            // The lock is released at the end of this statement, when the
+            // guard `lock` returned is dropped.
+            let job = queue.lock().expect("no worker panics holding the lock").recv();
+            match job {
+                Ok((position, cart)) => {
+                    results.send((position, market.try_checkout(cart)))
+                        .expect("the results are still being read");
+                },
+                Err(_) => break
+            }
+        })
+    }).collect();
+    // The workers hold clones of the `Sender`; this one must go too, or the
+    // loop below would wait forever for a result nobody will send.
+    drop(results);
+
+    // Putting each result back at its cart's position does what iterating
+    // over the list of `Future`s in submission order does in Java.
+    let mut totals: Vec<Option<Result<M, CheckoutError>>> = (0..count).map(|_| None).collect();
+    for (position, total) in finished {
+        totals[position] = Some(total);
+    }
+    for handle in handles {
+        handle.join().expect("no worker panics");
+    }
+    totals.into_iter().map(|total| total.expect("every cart was priced")).collect()
+}
+
+/// The price of every cart, worked out by `workers` scoped threads each
+/// pricing an equal share of the carts, like `ExecutorService.invokeAll`
+/// with one task per share.
+///
+/// ```text
+/// List<Callable<List<Long>>> tasks = ...;  // one per share of the carts
+/// for (Future<List<Long>> share : pool.invokeAll(tasks)) totals.addAll(share.get());
+/// ```
+///
+/// `thread::scope` joins every thread spawned in it before returning, so,
+/// unlike `with_channels`, the threads may borrow the market and the carts,
+/// with no `Arc`, no copying and no channels. Each `join` hands back one
+/// thread's results, the way `Future.get` does, and joining the threads in
+/// the order they were spawned keeps the results in the carts' order. With
+/// `workers` at zero, one thread is used.
+pub fn with_scoped_threads<M: Money>(market: &Supermarket<M>, carts: &[String],
+                                     workers: usize) -> Vec<Result<M, CheckoutError>> {
+    // The share of each thread, rounded up so that `workers` shares cover
+    // every cart; `max(1)` because `chunks` refuses a size of zero.
+    let share = carts.len().div_ceil(workers.max(1)).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = carts.chunks(share)
+            .map(|carts| scope.spawn(move || serial(market, carts)))
+            .collect();
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("no thread panics"))
+            .collect()
+    })
+}
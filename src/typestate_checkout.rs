@@ -0,0 +1,166 @@
+//! A checkout workflow whose order is checked by the compiler: a cart is
+//! filled, then priced, then paid for, and no step can be skipped.
+//!
+//! A Java `Cart` would have a `status` field, `OPEN`, `PRICED` or `PAID`,
+//! and every method would begin by checking it:
+//!
+//! ```java
+//! public Change pay(long tendered) {
+//!     if (status != Status.PRICED) {
+//!         throw new IllegalStateException("price the cart before paying");
+//!     }
+//!     ...
+//! }
+//! ```
+//!
+//! Calling `pay` on an open cart compiles, and fails when it runs, if a test
+//! happens to try it. Here the state is part of the cart's *type*:
+//! `Cart<Open>`, `Cart<Priced>` and `Cart<Paid>` are three types, each with
+//! only the methods that make sense in that state. `Cart<Open>` has no
+//! `pay` method at all, so calling one is a compile error, as
+//! `tests/ui/typestate_pay_before_price.rs` shows. Each step takes the cart
+//! by value and returns it in its next state, and the old value cannot be
+//! used afterwards, so putting an item in a cart already priced does not
+//! compile either, whether through the priced cart,
+//! `tests/ui/typestate_put_after_price.rs`, or through the open one it was
+//! made from, `tests/ui/typestate_cart_moved_by_price.rs`.
+//!
+//! Java has no equivalent. Its generics are erased, and cannot decide which
+//! methods an object has; one class per state, each returning the next,
+//! comes closest, but nothing stops code keeping hold of the old object and
+//! calling it again, which is exactly what moving the cart rules out.
+//!
+//! `builder_comparison::TypestateBuilder` uses the same technique with
+//! states that are empty labels. The states here carry data: a `Priced`
+//! cart holds its receipt and a `Paid` one the change given, so a total can
+//! only be asked of a cart that has one, where a Java `getTotal()` would
+//! have to return `null` or throw while the cart is open.
+
+use crate::conversions::Sku;
+use crate::core::checkout::CheckoutError;
+use crate::core::money::{Money, Cents};
+use crate::reasonable_implementation::Supermarket;
+use crate::receipt::Receipt;
+
+/// The state of a cart still being filled.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Open;
+
+/// The state of a cart that has been priced, holding its receipt.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Priced<M: Money = Cents> {
+    receipt: Receipt<M>
+}
+
+/// The state of a cart that has been paid for.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Paid<M: Money = Cents> {
+    receipt: Receipt<M>,
+    change: M
+}
+
+/// A cart in state `S`: `Open`, `Priced` or `Paid`.
+///
+/// The state is a real field, not `PhantomData`, as each state has data of
+/// its own. Being private, it cannot be set from outside, so the only way
+/// to a `Cart<Paid>` is through `price` and `pay`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Cart<S = Open> {
+    items: String,
+    state: S
+}
+
+/// Methods of a cart in every state.
+impl<S> Cart<S> {
+
+    /// The items in the cart.
+    pub fn items(&self) -> &str {
+        &self.items
+    }
+}
+
+impl Cart<Open> {
+
+    /// An empty cart.
+    pub fn new() -> Cart<Open> {
+        Cart { items: String::new(), state: Open }
+    }
+
+    /// Puts `product` in the cart, returning the cart to go on filling.
+    ///
+    /// Not `add`, which clippy would mistake for the `+` operator's method,
+    /// as it takes `self` and returns `Self`.
+    pub fn put(mut self, product: impl Into<Sku>) -> Cart<Open> {
+        self.items.push(product.into().into());
+        self
+    }
+
+    /// Prices the cart at `market`, moving it on to `Priced`, or reports
+    /// why it cannot be priced.
+    ///
+    /// The cart is lost with an error, as an open cart has nothing in it
+    /// worth keeping that the caller does not know already.
+    pub fn price<M: Money>(self, market: &Supermarket<M>) -> Result<Cart<Priced<M>>, CheckoutError> {
+        let receipt = market.receipt(&self.items)?;
+        Ok(Cart { items: self.items, state: Priced { receipt } })
+    }
+}
+
+impl Default for Cart<Open> {
+    fn default() -> Cart<Open> {
+        Cart::new()
+    }
+}
+
+impl<M: Money> Cart<Priced<M>> {
+
+    /// The amount due. Only a priced cart has one, so this returns an `M`,
+    /// not an `Option<M>`.
+    pub fn total(&self) -> M {
+        self.state.receipt.total
+    }
+
+    /// The receipt the cart was priced with.
+    pub fn receipt(&self) -> &Receipt<M> {
+        &self.state.receipt
+    }
+
+    /// Takes `tendered` for the cart, moving it on to `Paid`.
+    ///
+    /// Too little money gives the priced cart back as the `Err`, to be paid
+    /// for again, rather than losing it; `Result`'s two sides may hold
+    /// values of any types, including `Self`.
+    pub fn pay(self, tendered: M) -> Result<Cart<Paid<M>>, Cart<Priced<M>>> {
+        match tendered.checked_minus(self.total()) {
+            Some(change) if change >= M::zero() => {
+                Ok(Cart { items: self.items, state: Paid { receipt: self.state.receipt, change } })
+            },
+            _ => Err(self)
+        }
+    }
+
+    /// Goes back to filling the cart, dropping the price, as it would no
+    /// longer be right once more items were added.
+    pub fn reopen(self) -> Cart<Open> {
+        Cart { items: self.items, state: Open }
+    }
+}
+
+impl<M: Money> Cart<Paid<M>> {
+
+    /// The amount that was due.
+    pub fn total(&self) -> M {
+        self.state.receipt.total
+    }
+
+    /// The change given back.
+    pub fn change(&self) -> M {
+        self.state.change
+    }
+
+    /// The receipt, to hand to the shopper. No method moves a paid cart on
+    /// to another state: there is nothing left to do with it.
+    pub fn into_receipt(self) -> Receipt<M> {
+        self.state.receipt
+    }
+}
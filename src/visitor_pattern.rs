@@ -0,0 +1,268 @@
+//! The visitor pattern over receipts, and the `match` that usually replaces
+//! it in Rust.
+//!
+//! In Java, the visitor pattern adds operations to a fixed family of
+//! classes without touching them. Each class gets an
+//! `accept(Visitor v)` that calls back `v.visit(this)`, and overloading on
+//! the type of `this` picks the right `visit` method: a *double dispatch*,
+//! once on the element and once on the visitor. Every new operation, be it
+//! rendering, exporting or totalling, is one more `Visitor` class.
+//!
+//! A `Receipt` is such a family: lines, a subtotal, adjustments and a
+//! total. `ReceiptVisitor` has one method per part, and `walk` plays
+//! `accept`, calling them in order. Three visitors follow, rendering plain
+//! text, rendering JSON, and extracting the total.
+//!
+//! Rust makes the pattern less necessary than Java does. When the family
+//! is closed, it can be an `enum`, and a `match` on it dispatches on the
+//! kind of part just as the overloads do, with the compiler checking that
+//! every kind is handled, as a Java `switch` over a sealed interface does
+//! since Java 21. `ReceiptPart` and `render_text_with_match` show that
+//! version of the text renderer. A visitor still earns its keep when its
+//! methods have useful defaults, as here, where most visitors care about
+//! only some of the parts, or when it has state to build up between calls.
+
+use std::fmt::Write;
+use crate::core::money::{Money, Cents};
+use crate::receipt::{Receipt, ReceiptLine};
+use crate::reasonable_implementation::pipeline::Adjustment;
+
+/// An operation over the parts of a receipt, called by `walk`.
+///
+/// Every `visit_` method does nothing by default, so a visitor implements
+/// only those it needs, as a Java visitor extending an adapter class with
+/// empty methods would. Whatever the visitor produces comes out of
+/// `finish`, which takes it by value: once finished, it cannot be visited
+/// again.
+pub trait ReceiptVisitor<M: Money> {
+
+    /// What the visitor produces, chosen by each implementation, as the
+    /// `R` of a Java `Visitor<R>`.
+    type Output;
+
+    /// Called for each line, in order.
+    fn visit_line(&mut self, _line: &ReceiptLine<M>) {}
+
+    /// Called once, after the lines.
+    fn visit_subtotal(&mut self, _subtotal: M) {}
+
+    /// Called for each adjustment, in order, after the subtotal.
+    fn visit_adjustment(&mut self, _adjustment: &Adjustment<M>) {}
+
+    /// Called once, last.
+    fn visit_total(&mut self, _total: M) {}
+
+    /// The result of the visit.
+    fn finish(self) -> Self::Output;
+}
+
+/// Takes `visitor` through every part of `receipt`, in order, and returns
+/// what it produced.
+///
+/// This is the `accept` of the Java pattern, written once as a function
+/// rather than once per element class. The visitor is generic, `V`, so each
+/// call is compiled for the visitor's own type, and no call is virtual.
+pub fn walk<M: Money, V: ReceiptVisitor<M>>(receipt: &Receipt<M>, mut visitor: V) -> V::Output {
+    for line in receipt.lines.iter() {
+        visitor.visit_line(line);
+    }
+    visitor.visit_subtotal(receipt.subtotal);
+    for adjustment in receipt.adjustments.iter() {
+        visitor.visit_adjustment(adjustment);
+    }
+    visitor.visit_total(receipt.total);
+    visitor.finish()
+}
+
+/// What a line is for: its product, or its rule for a line of a rule that
+/// does not say which items it charged for.
+fn line_name<M: Money>(line: &ReceiptLine<M>) -> String {
+    match line.product {
+        Some(product) => product.to_string(),
+        None => line.rule.clone()
+    }
+}
+
+/// Renders a receipt as text, one part per line:
+///
+/// ```text
+/// A x1  $20.00
+/// B x5  $150.00  (5 for $150.00)
+/// subtotal  $170.00
+/// total  $170.00
+/// ```
+#[derive(Default)]
+pub struct TextRenderer {
+    text: String
+}
+
+impl<M: Money> ReceiptVisitor<M> for TextRenderer {
+    type Output = String;
+
+    fn visit_line(&mut self, line: &ReceiptLine<M>) {
+        // `write!` to a `String` cannot fail, so its `Result` is ignored.
+        let _ = write!(self.text, "{} x{}  {}", line_name(line), line.quantity, line.amount);
+        if !line.promotions.is_empty() {
+            let _ = write!(self.text, "  ({})", line.promotions.join(", "));
+        }
+        self.text.push('\n');
+    }
+
+    fn visit_subtotal(&mut self, subtotal: M) {
+        let _ = writeln!(self.text, "subtotal  {}", subtotal);
+    }
+
+    fn visit_adjustment(&mut self, adjustment: &Adjustment<M>) {
+        let _ = writeln!(self.text, "{}  {}", adjustment.label, adjustment.amount);
+    }
+
+    fn visit_total(&mut self, total: M) {
+        let _ = writeln!(self.text, "total  {}", total);
+    }
+
+    fn finish(self) -> String {
+        self.text
+    }
+}
+
+/// `text` as a JSON string, quotes included.
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => { let _ = write!(json, "\\u{:04x}", c as u32); },
+            c => json.push(c)
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Renders a receipt as one line of JSON, with every amount as its display
+/// string, such as `"$20.00"`:
+///
+/// ```text
+/// {"lines":[{"product":"A","rule":"A: $20.00 each","quantity":1,"amount":"$20.00",
+/// "promotions":[]}],"subtotal":"$20.00","adjustments":[],"total":"$20.00"}
+/// ```
+///
+/// With the `serde` feature, `serde_json` does this from the `Receipt`
+/// alone, as Jackson would in Java. Written by hand, it shows a visitor
+/// keeping state between calls: it must know whether a comma is due, and
+/// when the lines end and the adjustments begin.
+#[derive(Default)]
+pub struct JsonRenderer {
+    json: String,
+    adjustments: usize
+}
+
+impl<M: Money> ReceiptVisitor<M> for JsonRenderer {
+    type Output = String;
+
+    fn visit_line(&mut self, line: &ReceiptLine<M>) {
+        self.json.push_str(if self.json.is_empty() { "{\"lines\":[" } else { "," });
+        let product = line.product.map_or("null".to_string(), |product| json_string(&product.to_string()));
+        let promotions: Vec<String> = line.promotions.iter().map(|promotion| json_string(promotion)).collect();
+        let _ = write!(self.json, "{{\"product\":{},\"rule\":{},\"quantity\":{},\"amount\":{},\"promotions\":[{}]}}",
+                       product, json_string(&line.rule), line.quantity,
+                       json_string(&line.amount.to_string()), promotions.join(","));
+    }
+
+    fn visit_subtotal(&mut self, subtotal: M) {
+        if self.json.is_empty() {
+            self.json.push_str("{\"lines\":[");
+        }
+        let _ = write!(self.json, "],\"subtotal\":{}", json_string(&subtotal.to_string()));
+    }
+
+    fn visit_adjustment(&mut self, adjustment: &Adjustment<M>) {
+        self.json.push_str(if self.adjustments == 0 { ",\"adjustments\":[" } else { "," });
+        self.adjustments += 1;
+        let _ = write!(self.json, "{{\"label\":{},\"amount\":{}}}", json_string(&adjustment.label),
+                       json_string(&adjustment.amount.to_string()));
+    }
+
+    fn visit_total(&mut self, total: M) {
+        self.json.push_str(if self.adjustments == 0 { ",\"adjustments\":[" } else { "" });
+        let _ = write!(self.json, "],\"total\":{}}}", json_string(&total.to_string()));
+    }
+
+    fn finish(self) -> String {
+        self.json
+    }
+}
+
+/// Picks the total out of a receipt, ignoring everything else.
+///
+/// Thanks to the default methods, this visitor is a single method long. A
+/// Java visitor interface would force it to implement every `visit`, unless
+/// the interface gave them `default` bodies as this trait does.
+pub struct TotalExtractor<M: Money = Cents> {
+    total: Option<M>
+}
+
+impl<M: Money> Default for TotalExtractor<M> {
+    fn default() -> TotalExtractor<M> {
+        TotalExtractor { total: None }
+    }
+}
+
+impl<M: Money> ReceiptVisitor<M> for TotalExtractor<M> {
+    type Output = M;
+
+    fn visit_total(&mut self, total: M) {
+        self.total = Some(total);
+    }
+
+    fn finish(self) -> M {
+        self.total.expect("`walk` always visits the total")
+    }
+}
+
+/// One part of a receipt, as an `enum` rather than as calls to a visitor.
+///
+/// The variants borrow from the receipt, so listing its parts copies
+/// nothing bigger than an amount.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReceiptPart<'r, M: Money> {
+    Line(&'r ReceiptLine<M>),
+    Subtotal(M),
+    Adjustment(&'r Adjustment<M>),
+    Total(M)
+}
+
+/// The parts of `receipt`, in the order `walk` visits them.
+pub fn parts<M: Money>(receipt: &Receipt<M>) -> impl Iterator<Item = ReceiptPart<'_, M>> {
+    receipt.lines.iter().map(ReceiptPart::Line)
+        .chain(std::iter::once(ReceiptPart::Subtotal(receipt.subtotal)))
+        .chain(receipt.adjustments.iter().map(ReceiptPart::Adjustment))
+        .chain(std::iter::once(ReceiptPart::Total(receipt.total)))
+}
+
+/// The same text as `TextRenderer`, from a `match` over the parts.
+///
+/// There is no trait, no `accept` and no renderer type: the operation is an
+/// ordinary function, and adding another is writing another function. The
+/// cost is the other side of the same coin. A new kind of part means a new
+/// variant, and every `match` on `ReceiptPart` must then handle it, which
+/// the compiler will insist on, where a visitor trait would give it a
+/// default method and carry on.
+pub fn render_text_with_match<M: Money>(receipt: &Receipt<M>) -> String {
+    let mut text = String::new();
+    for part in parts(receipt) {
+        let _ = match part {
+            ReceiptPart::Line(line) if line.promotions.is_empty() =>
+                writeln!(text, "{} x{}  {}", line_name(line), line.quantity, line.amount),
+            ReceiptPart::Line(line) =>
+                writeln!(text, "{} x{}  {}  ({})", line_name(line), line.quantity, line.amount,
+                         line.promotions.join(", ")),
+            ReceiptPart::Subtotal(subtotal) => writeln!(text, "subtotal  {}", subtotal),
+            ReceiptPart::Adjustment(adjustment) =>
+                writeln!(text, "{}  {}", adjustment.label, adjustment.amount),
+            ReceiptPart::Total(total) => writeln!(text, "total  {}", total)
+        };
+    }
+    text
+}
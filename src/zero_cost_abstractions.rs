@@ -0,0 +1,123 @@
+//! What "zero-cost abstraction" means, and where the costs do show up.
+//!
+//! The phrase comes from C++, and says two things: you don't pay for what
+//! you don't use, and what you do use could not be hand-written any
+//! faster. It does not say an abstraction is free; it says it costs no more
+//! than the code it replaces. Java's abstractions mostly come with a price
+//! tag the JIT may or may not remove at runtime: a `Cents` class is a heap
+//! object with a header, an `Iterator` an allocation unless escape analysis
+//! catches it, and an interface call a virtual one unless the call site
+//! turns out to see only one class. Rust makes the decision while
+//! compiling, so each of the pairs below can be checked, in the generated
+//! code and with `benches/zero_cost.rs`, rather than taken on trust.
+//!
+//! # Newtypes
+//!
+//! `Cents` is a struct around an `i64`, and is exactly an `i64` in memory:
+//! the `const` assertions below fail to compile if not. A `Vec<Cents>` is
+//! an array of `i64`s, with no header or pointer per element, where Java's
+//! `List<Cents>` is an array of references to objects scattered on the
+//! heap. `Option<Box<T>>` is no larger than a `Box<T>`, as a `Box` is
+//! never null and `None` can use the null pointer; Java's `Optional` is
+//! another object.
+//!
+//! # Iterators
+//!
+//! `subtotal_by_index` and `subtotal_by_iterator` compute the same sum, one
+//! as a C programmer would and one as a `Stream` pipeline would be written.
+//! After inlining, `zip`, `map` and `sum` disappear, and both compile to
+//! the same loop. The iterator one can be *faster*: `zip` knows both
+//! lengths up front, so there is no per-element bounds check for the
+//! optimizer to try to remove, where `prices[i]` must check `i`.
+//!
+//! # Static and dynamic dispatch
+//!
+//! `total_static` takes a slice of `FlatPrice`s, and `total_dynamic` a
+//! slice of `BoxedRule`s. Both call the very same generic `core::rules::total`,
+//! and this is where the difference lies:
+//!
+//! * For `total_static`, the compiler *monomorphizes* `total`, writing a
+//!   copy of it for `R = FlatPrice<Cents>`. Every `rule.price(..)` in that
+//!   copy is a direct call to `FlatPrice::price`, which is then inlined
+//!   into the loop. The rules are stored inline in the slice, one after
+//!   another.
+//! * For `total_dynamic`, `R` is `Box<dyn PricingRule>`, and each
+//!   `rule.price(..)` loads a function pointer from the rule's *vtable* and
+//!   calls through it, an indirect call the compiler cannot inline, as it
+//!   cannot know what it will call. Each rule is a separate allocation,
+//!   reached through a two-word *fat pointer*: one word to the rule, one to
+//!   its vtable. This is what every Java interface call looks like before
+//!   the JIT has profiled it.
+//!
+//! Both functions are `#[inline(never)]`, so each keeps a symbol of its own
+//! to find in the generated assembly:
+//!
+//! ```text
+//! cargo rustc --release --lib -- --emit asm
+//! grep -A40 total_static target/release/deps/rust_for_java_devs-*.s
+//! ```
+//!
+//! `cargo install cargo-show-asm` offers `cargo asm` to do the same for one
+//! function at a time. The static one has no `call` instruction at all,
+//! the flat-price arithmetic sitting in the loop; the dynamic one calls
+//! through the vtable, as `callq *32(%rax)` on x86-64, once per rule.
+//!
+//! # What is not free
+//!
+//! Monomorphization copies code. Every distinct `R` gets its own `total`,
+//! so heavy use of generics grows the binary and the compile time, where
+//! one `dyn` version serves every rule type. `generic_implementation`'s
+//! market cannot even hold a rule type chosen at runtime, such as one
+//! loaded from a catalog file. Checked arithmetic costs a branch per
+//! operation, a cost chosen deliberately, and the `OverflowPolicy` can
+//! drop it. The zero-cost claim is only ever that the abstraction costs no
+//! more than writing the same thing out by hand; `benches/dispatch.rs`
+//! compares whole markets, where the choice also decides how rules are
+//! stored and counted.
+
+use std::mem::size_of;
+use crate::core::counting::Counts;
+use crate::core::money::{Cents, OverflowPolicy, Overflow};
+use crate::core::rules::{FlatPrice, total};
+use crate::reasonable_implementation::BoxedRule;
+
+// Checked while compiling, at no cost at runtime: a failed `assert!` in a
+// `const` is a compile error.
+const _: () = assert!(size_of::<Cents>() == size_of::<i64>());
+const _: () = assert!(size_of::<Option<BoxedRule<Cents>>>() == size_of::<BoxedRule<Cents>>());
+
+/// The sum of `prices[i] * quantities[i]`, written with indexes.
+///
+/// Only as many lines as both slices have are summed.
+#[inline(never)]
+pub fn subtotal_by_index(prices: &[Cents], quantities: &[u32]) -> Cents {
+    let lines = prices.len().min(quantities.len());
+    let mut subtotal = Cents(0);
+    let mut i = 0;
+    while i < lines {
+        subtotal += prices[i] * quantities[i];
+        i += 1;
+    }
+    subtotal
+}
+
+/// The sum of `prices[i] * quantities[i]`, written with iterators.
+///
+/// `zip` stops at the end of the shorter slice, as the loop in
+/// `subtotal_by_index` has to be told to.
+#[inline(never)]
+pub fn subtotal_by_iterator(prices: &[Cents], quantities: &[u32]) -> Cents {
+    prices.iter().zip(quantities).map(|(&price, &quantity)| price * quantity).sum()
+}
+
+/// The total of `rules`, every call resolved while compiling.
+#[inline(never)]
+pub fn total_static(rules: &[FlatPrice<Cents>], counts: &Counts) -> Result<Cents, Overflow> {
+    total(rules, counts, OverflowPolicy::Checked)
+}
+
+/// The total of `rules`, every call made through a vtable.
+#[inline(never)]
+pub fn total_dynamic(rules: &[BoxedRule<Cents>], counts: &Counts) -> Result<Cents, Overflow> {
+    total(rules, counts, OverflowPolicy::Checked)
+}
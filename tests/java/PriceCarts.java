@@ -0,0 +1,22 @@
+import reasonable_implementation.Supermarket;
+
+import java.io.BufferedReader;
+import java.io.IOException;
+import java.io.InputStreamReader;
+
+/**
+ * Prices one cart per line of standard input with the Java Supermarket,
+ * printing one total per line. Driven by tests/rust/differential.rs.
+ */
+public class PriceCarts {
+    public static void main(String[] args) throws IOException {
+        Supermarket s = new Supermarket();
+        BufferedReader in = new BufferedReader(new InputStreamReader(System.in));
+        StringBuilder out = new StringBuilder();
+        String cart;
+        while ((cart = in.readLine()) != null) {
+            out.append(s.checkout(cart)).append('\n');
+        }
+        System.out.print(out);
+    }
+}
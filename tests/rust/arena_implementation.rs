@@ -0,0 +1,47 @@
+/// The arena-allocated Supermarket should price carts exactly like its
+/// owned counterpart, so it runs the shared suite from `common`.
+mod common;
+
+use rust_for_java_devs::arena_implementation::{Bump, Supermarket};
+use rust_for_java_devs::core::rules::BestOfferPrice;
+use rust_for_java_devs::money::OverflowPolicy;
+
+/// The suite builds a fresh market in every test from a single expression,
+/// so each one gets an arena of its own that is simply never freed.
+/// `Box::leak` gives up ownership in exchange for a `'static` reference,
+/// which is fine for a test and rarely anywhere else.
+fn leaked_arena() -> &'static Bump {
+    Box::leak(Box::new(Bump::new()))
+}
+
+market_suite!(Supermarket::<Cents>::demo_in(leaked_arena()));
+
+#[test]
+fn honors_the_overflow_policy() {
+    let arena = Bump::new();
+    let s = Supermarket::demo_in(&arena).with_overflow_policy(OverflowPolicy::Saturating);
+    assert_eq!(Ok(Cents(24000)), s.try_checkout("ABBACBBAB".to_string()))
+}
+
+#[test]
+fn many_markets_share_one_arena() {
+    let mut arena = Bump::new();
+    for _reload in 0..3 {
+        {
+            let markets: Vec<Supermarket> = (0..100).map(|_| Supermarket::demo_in(&arena)).collect();
+            assert!(markets.iter().all(|s| s.checkout("ABBACBBAB") == Ok(Cents(24000))));
+        }
+        // Every market borrowing the arena is gone, so it may be emptied
+        // for the next reload; the compiler refuses this while any remain.
+        arena.reset();
+    }
+}
+
+#[test]
+fn rules_owning_heap_memory_live_in_the_arena_too() {
+    let arena = Bump::new();
+    let offers = BestOfferPrice::new('D', Cents(100)).unwrap()
+        .with_offer(3, Cents(250)).unwrap();
+    let s = Supermarket::new_in(&arena).with_rule(offers);
+    assert_eq!(Ok(Cents(350)), s.checkout("DDDD"))
+}
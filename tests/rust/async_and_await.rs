@@ -0,0 +1,96 @@
+/// Carts priced against an `AsyncPriceSource` should cost the same however
+/// the lookups are combined, and `select` should take the faster answer.
+use tokio_test::{assert_pending, assert_ready, block_on, task};
+use rust_for_java_devs::async_and_await::{first_answer, first_success, price_all_at_once, price_carts,
+                                          price_one_by_one, Slow};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::CheckoutError;
+use rust_for_java_devs::remote::{AsyncPriceSource, InMemoryPriceSource, SourceError};
+
+fn prices() -> InMemoryPriceSource {
+    InMemoryPriceSource::new()
+        .with_price('A', Cents(2000))
+        .with_price('C', Cents(3000))
+        .failing_for('E')
+}
+
+#[test]
+fn lookups_combine_to_the_same_total() {
+    let source = prices();
+    assert_eq!(Ok(Cents(9000)), block_on(price_one_by_one(&source, "AACAX")));
+    assert_eq!(Ok(Cents(9000)), block_on(price_all_at_once(&source, "AACAX")));
+    assert_eq!(Ok(Cents(0)), block_on(price_all_at_once(&source, "")));
+}
+
+#[test]
+fn failed_lookups_fail_the_checkout() {
+    let source = prices();
+    let unavailable = Err(CheckoutError::PriceSourceUnavailable { product: 'E', attempts: 1 });
+    assert_eq!(unavailable, block_on(price_one_by_one::<Cents, _>(&source, "AE")));
+    assert_eq!(unavailable, block_on(price_all_at_once::<Cents, _>(&source, "AE")));
+}
+
+#[test]
+fn each_cart_gets_its_own_result() {
+    let source = prices();
+    let totals = block_on(price_carts(&source, &["A", "AE", "CC"]));
+    assert_eq!(vec![Ok(Cents(2000)),
+                    Err(CheckoutError::PriceSourceUnavailable { product: 'E', attempts: 1 }),
+                    Ok(Cents(6000))], totals);
+}
+
+#[test]
+fn futures_do_nothing_until_polled() {
+    let source = prices();
+    let lookup = AsyncPriceSource::<Cents>::price_of(&source, 'A');
+    assert_eq!(0, source.lookups());
+    assert_eq!(Ok(Some(Cents(2000))), block_on(lookup));
+    assert_eq!(1, source.lookups());
+}
+
+#[test]
+fn slow_sources_keep_the_caller_waiting() {
+    let slow = Slow::new(prices(), 2);
+    let mut lookup = task::spawn(AsyncPriceSource::<Cents>::price_of(&slow, 'A'));
+    assert_pending!(lookup.poll());
+    assert_pending!(lookup.poll());
+    assert_eq!(Ok(Some(Cents(2000))), assert_ready!(lookup.poll()));
+}
+
+#[test]
+fn all_at_once_waits_for_the_slowest_lookup_only() {
+    // Three lookups taking three turns each take three turns together,
+    // where one by one they would take nine.
+    let slow = Slow::new(prices(), 3);
+    let mut together = task::spawn(price_all_at_once::<Cents, _>(&slow, "AAC"));
+    for _ in 0..3 {
+        assert_pending!(together.poll());
+    }
+    assert_eq!(Ok(Cents(7000)), assert_ready!(together.poll()));
+    let mut apart = task::spawn(price_one_by_one::<Cents, _>(&slow, "AAC"));
+    for _ in 0..6 {
+        assert_pending!(apart.poll());
+    }
+}
+
+#[test]
+fn the_first_answer_wins_even_when_it_fails() {
+    let fast = Slow::new(InMemoryPriceSource::new().failing_for('A'), 0);
+    let slow = Slow::new(prices(), 5);
+    assert!(matches!(block_on(first_answer::<Cents, _, _>(&fast, &slow, 'A')),
+                     Err(SourceError::Transport(_))));
+    let fast = Slow::new(InMemoryPriceSource::new().with_price('A', Cents(1900)), 0);
+    assert_eq!(Ok(Some(Cents(1900))), block_on(first_answer(&slow, &fast, 'A')));
+}
+
+#[test]
+fn the_first_success_wins_over_failures() {
+    let sources = vec![
+        Slow::new(InMemoryPriceSource::new().failing_for('A'), 0),
+        Slow::new(prices(), 4),
+        Slow::new(InMemoryPriceSource::new().with_price('A', Cents(2100)), 8)
+    ];
+    assert_eq!(Ok(Some(Cents(2000))), block_on(first_success(&sources, 'A')));
+    let none: Vec<Slow<InMemoryPriceSource>> = Vec::new();
+    assert!(block_on(first_success::<Cents, _>(&none, 'A')).is_err());
+}
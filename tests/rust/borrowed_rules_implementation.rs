@@ -0,0 +1,26 @@
+/// The lifetime-parameterized Supermarket should price carts exactly like
+/// its owned counterpart, so it runs the shared suite from `common`.
+mod common;
+
+use rust_for_java_devs::borrowed_rules_implementation::Supermarket;
+use rust_for_java_devs::money::OverflowPolicy;
+
+market_suite!(Supermarket::<Cents>::demo());
+
+#[test]
+fn honors_the_overflow_policy() {
+    let s = Supermarket::demo().with_overflow_policy(OverflowPolicy::Saturating);
+    assert_eq!(Ok(Cents(24000)), s.try_checkout("ABBACBBAB".to_string()))
+}
+
+/// A function that holds a Supermarket must name its lifetime, unlike one
+/// holding the owned `reasonable_implementation::Supermarket`.
+fn total_with<'s>(s: &Supermarket<'s>, items: &str) -> Cents {
+    s.checkout(items).unwrap()
+}
+
+#[test]
+fn lifetime_appears_in_signatures_that_hold_it() {
+    let s = Supermarket::demo();
+    assert_eq!(Cents(10000), total_with(&s, "AXBC"))
+}
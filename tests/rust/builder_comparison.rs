@@ -0,0 +1,43 @@
+/// Both builders should make a Supermarket with exactly the rules and
+/// settings they were given.
+use rust_for_java_devs::builder_comparison::TypestateBuilder;
+use rust_for_java_devs::money::{Cents, OverflowPolicy};
+use rust_for_java_devs::reasonable_implementation::{BundlePrice, CartLimits, CheckoutError,
+                                                    FlatPrice, Market, Supermarket};
+
+#[test]
+fn builders_apply_every_rule_in_order() {
+    let market: Supermarket = Supermarket::builder()
+        .rule(FlatPrice::new('A', Cents(2000)).unwrap())
+        .rule(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap())
+        .build();
+    assert_eq!(Ok(Cents(17000)), market.checkout("ABBBBB"));
+    assert_eq!(2, market.rules().count());
+    assert_eq!(Ok(Cents(0)), Supermarket::<Cents>::builder().build().checkout("A"));
+}
+
+#[test]
+fn builders_apply_their_settings() {
+    let limits = CartLimits { max_bytes: 10, max_items: 2 };
+    let market = Supermarket::builder()
+        .rule(FlatPrice::new('A', Cents(i64::MAX)).unwrap())
+        .overflow_policy(OverflowPolicy::Saturating)
+        .cart_limits(limits)
+        .build();
+    assert_eq!(limits, market.cart_limits());
+    assert_eq!(Ok(Cents(i64::MAX)), market.try_checkout("AA".to_string()));
+    assert!(matches!(market.try_checkout("AAA".to_string()), Err(CheckoutError::CartTooLarge { .. })));
+}
+
+#[test]
+fn typestate_builders_build_once_they_have_a_rule() {
+    let limits = CartLimits { max_bytes: 10, max_items: 2 };
+    let market = TypestateBuilder::new()
+        .cart_limits(limits)
+        .rule(FlatPrice::new('C', Cents(3000)).unwrap())
+        .overflow_policy(OverflowPolicy::Checked)
+        .rule(FlatPrice::new('A', Cents(2000)).unwrap())
+        .build();
+    assert_eq!(limits, market.cart_limits());
+    assert_eq!(Ok(Cents(5000)), market.try_checkout("AC".to_string()));
+}
@@ -0,0 +1,70 @@
+/// Copies should leave their originals alone: deep clones by owning every
+/// rule, and shallow ones by sharing rules nobody can change.
+use rust_for_java_devs::clone_and_copy::*;
+use rust_for_java_devs::conversions::Sku;
+use rust_for_java_devs::core::rules::{BestOfferPrice, BundlePrice, FlatPrice};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::receipt::Receipt;
+
+fn deep() -> DeepMarket {
+    DeepMarket::new()
+        .with_rule(FlatPrice::new('A', Cents(2000)).unwrap())
+        .with_rule(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap())
+        .with_rule(BestOfferPrice::new('C', Cents(3000)).unwrap().with_offer(3, Cents(8000)).unwrap())
+}
+
+fn shallow() -> ShallowMarket {
+    ShallowMarket::new()
+        .with_rule(FlatPrice::new('A', Cents(2000)).unwrap())
+        .with_rule(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap())
+        .with_rule(BestOfferPrice::new('C', Cents(3000)).unwrap().with_offer(3, Cents(8000)).unwrap())
+}
+
+#[test]
+fn copies_leave_the_original_usable() {
+    let tag = PriceTag { sku: Sku('A'), price: Cents(2000) };
+    let mut marked_down = tag;
+    marked_down.price = Cents(1500);
+    assert_eq!(Cents(2000), tag.price);
+    assert_eq!(Sku('A'), marked_down.sku);
+}
+
+#[test]
+fn clones_own_their_data() {
+    let receipt = Receipt { lines: Vec::new(), subtotal: Cents(0), adjustments: Vec::new(), total: Cents(0) };
+    let mut copy = receipt.clone();
+    copy.total = Cents(100);
+    assert_ne!(receipt, copy);
+}
+
+#[test]
+fn deep_clones_are_independent() {
+    let original = deep();
+    let mut copy = original.clone();
+    assert!(!copy.shares_rules_with(&original));
+    assert!(original.shares_rules_with(&original));
+    assert_eq!(original.try_checkout("ABBACBBAB"), copy.try_checkout("ABBACBBAB"));
+    assert_eq!(Ok(Cents(8000)), copy.try_checkout("CCC"));
+    copy.reprice('A', FlatPrice::new('A', Cents(1000)).unwrap());
+    assert_eq!(Ok(Cents(1000)), copy.try_checkout("A"));
+    assert_eq!(Ok(Cents(2000)), original.try_checkout("A"));
+}
+
+#[test]
+fn shallow_clones_share_until_repriced() {
+    let original = shallow();
+    let mut copy = original.clone();
+    assert!(copy.shares_rules_with(&original));
+    copy.reprice('A', FlatPrice::new('A', Cents(1000)).unwrap());
+    assert_eq!(Ok(Cents(1000)), copy.try_checkout("A"));
+    assert_eq!(Ok(Cents(2000)), original.try_checkout("A"));
+    // The rules for B and C are still the same rules.
+    assert!(copy.shares_rules_with(&original));
+    assert_eq!(original.try_checkout("BBBBBCCC"), copy.try_checkout("BBBBBCCC"));
+}
+
+#[test]
+fn empty_markets_share_nothing() {
+    assert!(!DeepMarket::<Cents>::default().shares_rules_with(&DeepMarket::default()));
+    assert!(!ShallowMarket::<Cents>::default().shares_rules_with(&ShallowMarket::default()));
+}
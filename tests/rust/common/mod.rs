@@ -0,0 +1,88 @@
+/// Shared helpers for the integration tests of the parallel implementations.
+///
+/// Each file in `tests/rust` is compiled as its own crate, so code shared
+/// between them lives in a module that each one pulls in with `mod common;`.
+/// Placing it in `common/mod.rs` rather than `common.rs` stops cargo from
+/// treating it as a test crate of its own.
+use rust_for_java_devs::core::counting::Counts;
+
+pub const NUM_TEST_ITERATIONS: usize = 1000;
+pub const MAX_ITEMS_STRING_SIZE: usize = 1000;
+
+/// The demo catalog's prices, in cents, computed the dumbest possible way.
+pub fn simple_expected_cents(counts: &Counts) -> i64 {
+    let count_of = |c| counts.get(&c).cloned().unwrap_or(0) as i64;
+    let b = count_of('B');
+    count_of('A') * 2000 + (b / 5) * 15000 + (b % 5) * 5000 + count_of('C') * 3000
+}
+
+/// Generates the same suite of tests for any implementation of the demo
+/// catalog. The argument is an expression producing a fresh market that
+/// implements `Market<Cents>`.
+///
+/// `macro_rules!` macros expand to code at compile time, so each test file
+/// invoking this gets its own copy of every `#[test]` function below. It is
+/// the Rust counterpart of a JUnit abstract test class with one concrete
+/// subclass per implementation.
+#[macro_export]
+macro_rules! market_suite {
+    ($market:expr) => {
+        use rust_for_java_devs::reasonable_implementation::Market;
+        use rust_for_java_devs::money::Cents;
+        use rust_for_java_devs::assert_price;
+
+        #[test]
+        fn canonical_input() {
+            let s = $market;
+            assert_price!(s, "ABBACBBAB" => 240)
+        }
+
+        #[test]
+        fn empty_input() {
+            let s = $market;
+            assert_price!(s, "" => 0)
+        }
+
+        #[test]
+        fn ignores_unrelated_items() {
+            let s = $market;
+            assert_price!(s, "XKD" => 0)
+        }
+
+        #[test]
+        fn mixes_standard_and_unregistered_items_prices() {
+            let s = $market;
+            assert_price!(s, "AXBC" => 100)
+        }
+
+        #[test]
+        fn single_bundle_gets_combo_price() {
+            let s = $market;
+            assert_price!(s, "BBBBB" => 150)
+        }
+
+        #[test]
+        fn single_bundle_with_leftovers_gives_deal_price_plus_individual() {
+            let s = $market;
+            assert_price!(s, "BBBBB B" => 200)
+        }
+
+        #[test]
+        fn multiple_bundles_each_get_deal_price_plus_leftovers() {
+            let s = $market;
+            assert_price!(s, "BBBBB BBBBB BB" => 400)
+        }
+
+        #[test]
+        fn correctly_sums_random_sequence_of_valid_codes() {
+            use rust_for_java_devs::testkit::{seeded_rng, generate_mixed_char_sequence};
+            let s = $market;
+            let mut rng = seeded_rng(0);
+            for _i in 0..common::NUM_TEST_ITERATIONS {
+                let (items, counts) = generate_mixed_char_sequence(&mut rng, &['A', 'B', 'C'],
+                                                                   0..common::MAX_ITEMS_STRING_SIZE);
+                assert_eq!(Ok(Cents(common::simple_expected_cents(&counts))), s.checkout(&items));
+            }
+        }
+    };
+}
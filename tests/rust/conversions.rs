@@ -0,0 +1,69 @@
+/// `From`, `Into` and `TryFrom` should convert between products, carts and
+/// amounts losslessly, and refuse text that is not a cart.
+use proptest::prelude::*;
+use rust_for_java_devs::conversions::{NotACart, Sku};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::ownership_and_borrowing::Cart;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+#[test]
+fn chars_and_skus_convert_both_ways() {
+    let sku: Sku = 'A'.into();
+    assert_eq!(Sku('A'), sku);
+    assert_eq!('A', char::from(sku));
+    assert_eq!("A", sku.to_string());
+}
+
+#[test]
+fn carts_take_skus_and_chars() {
+    let mut cart = Cart::new();
+    cart.add(Sku('A'));
+    cart.add('B');
+    assert!(cart.remove(Sku('A')));
+    assert!(!cart.remove('C'));
+    assert_eq!("B", cart.items());
+}
+
+#[test]
+fn text_without_gaps_is_a_cart() {
+    let cart = Cart::try_from("ABBA").unwrap();
+    assert_eq!("ABBA", cart.items());
+    let empty: Cart = "".try_into().unwrap();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn gaps_are_not_products() {
+    assert_eq!(Err(NotACart { found: ' ', position: 2 }), Cart::try_from("AB BA"));
+    assert_eq!(Err(NotACart { found: '\n', position: 4 }), Cart::try_from("ABBA\n"));
+    assert_eq!("'\\n' at position 4 is not a product",
+               Cart::try_from("ABBA\n").unwrap_err().to_string());
+}
+
+#[test]
+fn markets_price_carts_and_strs() {
+    let market: Supermarket = Supermarket::demo();
+    let cart = Cart::try_from("ABBACBBAB").unwrap();
+    assert_eq!(Ok(Cents(24000)), market.try_checkout("ABBACBBAB"));
+    assert_eq!(Ok(Cents(24000)), market.try_checkout(cart));
+}
+
+#[test]
+fn receipts_convert_to_their_totals() {
+    let market: Supermarket = Supermarket::demo();
+    let total: Cents = market.receipt("ABBACBBAB").unwrap().into();
+    assert_eq!(Cents(24000), total);
+}
+
+proptest! {
+    #[test]
+    fn carts_hold_the_text_they_came_from(items in "[A-Z]{0,20}") {
+        let cart = Cart::try_from(items.as_str()).unwrap();
+        prop_assert_eq!(items.clone(), String::from(cart));
+    }
+
+    #[test]
+    fn skus_round_trip_through_chars(code in any::<char>()) {
+        prop_assert_eq!(code, char::from(Sku::from(code)));
+    }
+}
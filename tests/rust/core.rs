@@ -0,0 +1,289 @@
+/// The portable `core` module can price carts on its own, without any of
+/// the `std`-only Supermarkets.
+use rust_for_java_devs::core::counting::{count_items, check_items, AsciiCounts, ItemCounts,
+                                         InvalidItem};
+use rust_for_java_devs::core::money::{Cents, OverflowPolicy, Overflow};
+use rust_for_java_devs::core::rules::{total, Describable, PricingRule, FlatPrice, BundlePrice, BestOfferPrice,
+                                      RuleError, SubstitutePrice};
+
+fn demo_rules() -> Vec<Box<dyn PricingRule<Cents> + Send + Sync>> {
+    vec![
+        Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+        Box::new(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap()),
+        Box::new(FlatPrice::new('C', Cents(3000)).unwrap()),
+    ]
+}
+
+#[test]
+fn totals_the_canonical_input() {
+    let counts = count_items::<u64>("ABBACBBAB");
+    assert_eq!(Ok(Cents(24000)), total(&demo_rules(), &counts, OverflowPolicy::Checked))
+}
+
+#[test]
+fn totals_an_array_of_unboxed_rules() {
+    let rules = [FlatPrice::new('A', Cents(2000)).unwrap(),
+                 FlatPrice::new('C', Cents(3000)).unwrap()];
+    let counts = count_items::<u64>("AAC");
+    assert_eq!(Ok(Cents(7000)), total(&rules, &counts, OverflowPolicy::Checked))
+}
+
+#[test]
+fn total_reports_overflow_under_checked_policy() {
+    let rules = [FlatPrice::new('A', Cents(i64::MAX)).unwrap()];
+    let counts = count_items::<u64>("AA");
+    assert_eq!(Err(Overflow), total(&rules, &counts, OverflowPolicy::Checked))
+}
+
+#[test]
+fn rules_describe_themselves() {
+    let descriptions: Vec<String> = demo_rules().iter().map(|r| r.describe()).collect();
+    assert_eq!(vec!["A: $20.00 each",
+                    "B: $50.00 each or 5 for $150.00",
+                    "C: $30.00 each"], descriptions)
+}
+
+#[test]
+fn list_prices_ignore_offers() {
+    let counts = count_items::<u64>("ABBACBBAB");
+    let list_prices: Vec<Result<Cents, Overflow>> = demo_rules().iter()
+        .map(|r| r.list_price(&counts, OverflowPolicy::Checked))
+        .collect();
+    assert_eq!(vec![Ok(Cents(6000)), Ok(Cents(25000)), Ok(Cents(3000))], list_prices);
+    let best = best_offer(5000, &[(3, 13000)]);
+    assert_eq!(Ok(Cents(30000)), best.list_price(&count_items::<u64>("BBBBBB"), OverflowPolicy::Checked))
+}
+
+#[test]
+fn rule_constructors_refuse_negative_costs() {
+    assert_eq!(Some(RuleError::NegativeCost), FlatPrice::new('A', Cents(-1)).err());
+    assert_eq!(Some(RuleError::NegativeCost),
+               BundlePrice::new('B', Cents(5000), 5, Cents(-1)).err())
+}
+
+#[test]
+fn rule_constructors_refuse_empty_bundles() {
+    assert_eq!(Some(RuleError::EmptyBundle),
+               BundlePrice::new('B', Cents(5000), 0, Cents(15000)).err())
+}
+
+#[test]
+fn substitutes_are_priced_as_their_originals() {
+    let b = BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap();
+    assert_eq!(Some(RuleError::UnpricedOriginal), SubstitutePrice::new('D', 'A', b.clone()).err());
+    let d = SubstitutePrice::new('D', 'B', b).unwrap();
+    let counts = count_items::<u64>("BDDDDDDB");
+    assert_eq!(Ok(Cents(20000)), d.price(&counts, OverflowPolicy::Checked));
+    assert_eq!(Ok(Cents(30000)), d.list_price(&counts, OverflowPolicy::Checked));
+    assert_eq!("D: priced as B (B: $50.00 each or 5 for $150.00)", d.describe());
+    assert_eq!(vec!('D'), d.products());
+    assert!(d.applies_to('D') && !d.applies_to('B'));
+    let plan = d.plan(&counts, OverflowPolicy::Checked).unwrap();
+    assert!(plan.offers.iter().all(|offer| offer.product == 'D'));
+}
+
+#[test]
+fn free_items_are_allowed() {
+    assert!(FlatPrice::new('A', Cents(0)).is_ok())
+}
+
+#[test]
+fn ascii_counts_match_map_counts() {
+    let items = "ABBACBBAB XKD";
+    let map = count_items::<u64>(items);
+    let array = AsciiCounts::from_items(items).unwrap();
+    for c in "ABCDXK ".chars() {
+        assert_eq!(map.count_of(c), array.count_of(c));
+    }
+}
+
+#[test]
+fn ascii_counts_reject_non_ascii_carts() {
+    assert!(AsciiCounts::from_items("A\u{e9}").is_none())
+}
+
+#[test]
+fn single_code_point_emoji_are_one_item_each() {
+    let counts = count_items::<u64>("\u{1f34e}\u{1f34e}\u{1f34c}");
+    assert_eq!(2, counts.count_of('\u{1f34e}'));
+    assert_eq!(1, counts.count_of('\u{1f34c}'))
+}
+
+#[test]
+fn ascii_carts_always_pass_the_item_check() {
+    assert_eq!(Ok(()), check_items("ABBACBBAB, XKD"))
+}
+
+#[test]
+fn emoji_skus_pass_the_item_check() {
+    assert_eq!(Ok(()), check_items("A\u{1f34e}\u{1f34c}\u{e9}"));
+    // A Windows line break is two separators, not one item.
+    assert_eq!(Ok(()), check_items("A\r\nB"))
+}
+
+#[test]
+fn multi_code_point_graphemes_fail_the_item_check() {
+    // A thumbs up with a medium skin tone modifier.
+    assert_eq!(Err(InvalidItem { position: 1, item: "\u{1f44d}\u{1f3fd}".to_string() }),
+               check_items("A\u{1f44d}\u{1f3fd}B"));
+    // An `e` followed by a combining acute accent.
+    assert_eq!(Err(InvalidItem { position: 0, item: "e\u{301}".to_string() }),
+               check_items("e\u{301}"));
+    // A flag: two regional indicators, here U and S.
+    assert_eq!(Err(InvalidItem { position: 0, item: "\u{1f1fa}\u{1f1f8}".to_string() }),
+               check_items("\u{1f1fa}\u{1f1f8}A"));
+    // A woman, a zero-width joiner and a laptop: a technologist.
+    assert_eq!(Err(InvalidItem { position: 1, item: "\u{1f469}\u{200d}\u{1f4bb}".to_string() }),
+               check_items("B\u{1f469}\u{200d}\u{1f4bb}"))
+}
+
+/// The cheapest way to buy exactly `count` items, found by trying every
+/// number of each bundle in turn. `bundles` lists `(size, cents)` offers;
+/// whatever they leave over is bought at `lone` cents apiece.
+fn brute_force_cents(lone: i64, bundles: &[(u64, i64)], count: u64) -> i64 {
+    match bundles.split_first() {
+        None => lone * count as i64,
+        Some((&(size, cost), others)) => (0..=count / size)
+            .map(|k| k as i64 * cost + brute_force_cents(lone, others, count - k * size))
+            .min()
+            .unwrap()
+    }
+}
+
+fn best_offer(lone: i64, bundles: &[(u64, i64)]) -> BestOfferPrice<Cents> {
+    bundles.iter().fold(BestOfferPrice::new('B', Cents(lone)).unwrap(), |rule, &(size, cost)| {
+        rule.with_offer(size, Cents(cost)).unwrap()
+    })
+}
+
+#[test]
+fn best_offers_match_brute_force_on_small_counts() {
+    let catalogs: [(i64, &[(u64, i64)]); 4] = [
+        (5000, &[(3, 13000), (10, 40000)]),
+        (5000, &[(2, 9000), (5, 20000), (7, 27000)]),
+        (5000, &[(4, 25000)]),
+        (700, &[(6, 3900), (4, 2700), (9, 5800)]),
+    ];
+    for &(lone, bundles) in catalogs.iter() {
+        let rule = best_offer(lone, bundles);
+        for count in 0..=60 {
+            assert_eq!(Ok(Cents(brute_force_cents(lone, bundles, count))),
+                       rule.best_price(count, OverflowPolicy::Checked),
+                       "{} items from {:?}", count, bundles);
+        }
+    }
+}
+
+#[test]
+fn best_offers_price_huge_counts_without_a_huge_table() {
+    let rule = best_offer(5000, &[(3, 13000), (10, 40000)]);
+    // Ten for $400 is the best value, and a billion is a multiple of ten.
+    assert_eq!(Ok(Cents(4_000_000_000_000)),
+               rule.best_price(1_000_000_000, OverflowPolicy::Checked));
+    // Three extra items are cheapest as one more bundle of three.
+    assert_eq!(Ok(Cents(4_000_000_013_000)),
+               rule.best_price(1_000_000_003, OverflowPolicy::Checked))
+}
+
+#[test]
+fn best_offers_answer_alike_however_often_asked() {
+    let rule = best_offer(700, &[(6, 3900), (4, 2700), (9, 5800)]);
+    let unused = rule.clone();
+    // Descending, so later counts are answered from a table built for more.
+    for count in (0..=60).rev() {
+        assert_eq!(rule.clone().best_lots(count, OverflowPolicy::Checked),
+                   rule.best_lots(count, OverflowPolicy::Checked), "{} items", count);
+    }
+    // The remembered table takes no part in comparing rules.
+    assert_eq!(unused, rule);
+}
+
+#[test]
+fn adding_an_offer_forgets_the_remembered_table() {
+    let rule = best_offer(5000, &[(3, 13000)]);
+    assert_eq!(Ok(Cents(26000)), rule.best_price(6, OverflowPolicy::Checked));
+    let rule = rule.with_offer(6, Cents(20000)).unwrap();
+    assert_eq!(Ok(Cents(20000)), rule.best_price(6, OverflowPolicy::Checked))
+}
+
+#[test]
+fn best_offers_overflow_alike_under_every_policy_order() {
+    let rule = best_offer(i64::MAX / 2, &[(3, i64::MAX - 1)]);
+    assert!(rule.best_price(4, OverflowPolicy::Saturating).is_ok());
+    assert_eq!(Err(Overflow), rule.best_price(4, OverflowPolicy::Checked));
+    assert!(rule.best_price(4, OverflowPolicy::Saturating).is_ok())
+}
+
+#[test]
+fn best_offers_describe_every_offer() {
+    let rule = best_offer(5000, &[(3, 13000), (10, 40000)]);
+    assert_eq!("B: $50.00 each, or 3 for $130.00, or 10 for $400.00", rule.describe())
+}
+
+#[test]
+fn best_offers_refuse_empty_bundles() {
+    let rule = BestOfferPrice::new('B', Cents(5000)).unwrap();
+    assert_eq!(Some(RuleError::EmptyBundle), rule.with_offer(0, Cents(1)).err())
+}
+
+/// `proptest!` turns each function inside into a test run against many
+/// generated inputs. The `in` clauses name the strategy for each argument.
+mod properties {
+    use proptest::prelude::*;
+    use rust_for_java_devs::core::arbitrary::{catalog, cart, AnyRule, PRODUCTS};
+    use rust_for_java_devs::core::counting::{count_items, CountStrategy};
+    use rust_for_java_devs::core::lint::LintWarning;
+    use rust_for_java_devs::core::money::{Cents, OverflowPolicy};
+    use rust_for_java_devs::core::rules::{total, Describable, PricingRule};
+
+    proptest! {
+        #[test]
+        fn offers_never_cost_more_than_the_list_price(rules in catalog::<Cents>(),
+                                                      items in cart(&PRODUCTS, 0..200)) {
+            let counts = count_items::<u64>(&items);
+            for rule in rules.iter() {
+                let price = rule.price(&counts, OverflowPolicy::Checked).unwrap();
+                let list_price = rule.list_price(&counts, OverflowPolicy::Checked).unwrap();
+                prop_assert!(price <= list_price, "{} charged {} over {}",
+                             rule.describe(), price, list_price);
+            }
+        }
+
+        /// Free items are realistic enough, but nothing else should be
+        /// flagged.
+        #[test]
+        fn generated_rules_lint_clean(rule in any::<AnyRule<Cents>>()) {
+            let warnings: Vec<LintWarning> = rule.lint().into_iter()
+                .filter(|warning| !matches!(warning, LintWarning::NonPositivePrice { .. }))
+                .collect();
+            prop_assert!(warnings.is_empty(), "{} provoked {:?}", rule.describe(), warnings);
+        }
+
+        /// The core rules override `applies_to`, which should still agree
+        /// with the provided version, built on `products`.
+        #[test]
+        fn rules_apply_to_their_products(rule in any::<AnyRule<Cents>>(), product in "[A-Z]") {
+            let product = product.chars().next().unwrap();
+            prop_assert_eq!(rule.products().contains(&product), rule.applies_to(product));
+        }
+
+        /// A regular expression is a strategy for the strings it matches;
+        /// this one mixes ASCII codes with arbitrary characters, in carts
+        /// either side of `ARRAY_THRESHOLD`.
+        #[test]
+        fn every_count_strategy_counts_alike(items in "([A-E]|.){0,100}") {
+            let expected = count_items::<u64>(&items);
+            for strategy in [CountStrategy::Tree, CountStrategy::Array, CountStrategy::Hash] {
+                prop_assert_eq!(&expected, &strategy.count_items(&items), "{:?}", strategy);
+            }
+        }
+
+        #[test]
+        fn item_order_never_matters(rules in catalog::<Cents>(),
+                                    items in cart(&['A', 'B', 'X'], 0..100)) {
+            let reversed: String = items.chars().rev().collect();
+            prop_assert_eq!(total(&rules, &count_items::<u64>(&items), OverflowPolicy::Checked),
+                            total(&rules, &count_items::<u64>(&reversed), OverflowPolicy::Checked));
+        }
+    }
+}
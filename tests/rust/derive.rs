@@ -0,0 +1,81 @@
+/// Rules written with `#[derive(FlatRule)]` and `#[derive(BundleRule)]`
+/// should behave exactly like the core rules they imitate.
+use rust_for_java_devs::{FlatRule, BundleRule};
+use rust_for_java_devs::core::counting::count_items;
+use rust_for_java_devs::core::lint::LintWarning;
+use rust_for_java_devs::core::rules::{Describable, PricingRule, FlatPrice, BundlePrice};
+use rust_for_java_devs::money::{Cents, Money, OverflowPolicy, Overflow};
+
+#[derive(FlatRule)]
+struct Apples {
+    #[rule(product)]
+    code: char,
+    #[rule(cost)]
+    each: Cents
+}
+
+/// Generic over the money backend, as the core rules are; the derive copies
+/// the struct's generics onto the impl.
+#[derive(FlatRule)]
+struct Pears<M: Money> {
+    #[rule(product)]
+    code: char,
+    #[rule(cost)]
+    each: M,
+    /// Unmarked fields are left alone.
+    #[allow(dead_code)]
+    aisle: u8
+}
+
+#[derive(BundleRule)]
+struct Bananas {
+    #[rule(product)]
+    code: char,
+    #[rule(lone_cost)]
+    each: Cents,
+    #[rule(bundle_size)]
+    bunch: u64,
+    #[rule(bundle_cost)]
+    per_bunch: Cents
+}
+
+#[test]
+fn derived_rules_price_like_the_core_rules() {
+    let apples = Apples { code: 'A', each: Cents(2000) };
+    let pears = Pears { code: 'A', each: Cents(2000), aisle: 3 };
+    let bananas = Bananas { code: 'B', each: Cents(5000), bunch: 5, per_bunch: Cents(15000) };
+    let flat = FlatPrice::new('A', Cents(2000)).unwrap();
+    let bundle = BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap();
+    for cart in ["", "ABBACBBAB", "BBBBBBBBBBBB", "XYZ"] {
+        let counts = count_items::<u64>(cart);
+        let policy = OverflowPolicy::Checked;
+        assert_eq!(flat.price(&counts, policy), apples.price(&counts, policy));
+        assert_eq!(flat.price(&counts, policy), pears.price(&counts, policy));
+        assert_eq!(bundle.price(&counts, policy), bananas.price(&counts, policy));
+        assert_eq!(bundle.list_price(&counts, policy), bananas.list_price(&counts, policy));
+    }
+    assert_eq!(flat.describe(), apples.describe());
+    assert_eq!(bundle.describe(), bananas.describe());
+    assert_eq!(vec!['B'], bananas.products());
+}
+
+#[test]
+fn derived_rules_lint_like_the_core_rules() {
+    let free = Apples { code: 'A', each: Cents(0) };
+    assert_eq!(vec![LintWarning::NonPositivePrice { product: 'A' }], free.lint());
+    let dear = Bananas { code: 'B', each: Cents(100), bunch: 2, per_bunch: Cents(300) };
+    assert_eq!(vec![LintWarning::BundleCostsMore { product: 'B' }], dear.lint());
+}
+
+#[test]
+fn derived_rules_report_overflow() {
+    let gold = Apples { code: 'G', each: Cents(i64::MAX) };
+    assert_eq!(Err(Overflow), gold.price(&count_items::<u64>("GG"), OverflowPolicy::Checked));
+}
+
+#[test]
+fn empty_derived_bundles_price_every_item_singly() {
+    let bananas = Bananas { code: 'B', each: Cents(5000), bunch: 0, per_bunch: Cents(15000) };
+    assert_eq!(Ok(Cents(15000)),
+               bananas.price(&count_items::<u64>("BBB"), OverflowPolicy::Checked));
+}
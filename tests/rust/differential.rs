@@ -0,0 +1,81 @@
+/// Differential test against the Java solution in `java/`: both versions
+/// price the same random carts, and must agree on every total.
+///
+/// This needs a JDK, so it only runs when the `JAVA_DIFFERENTIAL`
+/// environment variable is set:
+///
+/// ```text
+/// JAVA_DIFFERENTIAL=1 cargo test --test differential
+/// ```
+///
+/// The carts are drawn from a fixed seed, or from `JAVA_DIFFERENTIAL_SEED`
+/// if it is set, so that any disagreement can be reproduced.
+use rust_for_java_devs::reasonable_implementation::{Market, Supermarket};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::testkit::{seeded_rng, generate_mixed_char_sequence};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const NUM_CARTS: usize = 1000;
+const MAX_ITEMS_STRING_SIZE: usize = 1000;
+
+/// Runs a command to completion, panicking with its output if it fails.
+///
+/// `Command` is Rust's `ProcessBuilder`. Piping standard input lets us
+/// write the carts to the child, like `Process.getOutputStream()`.
+fn run(command: &mut Command, input: &str) -> String {
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|e| panic!("cannot start {:?}: {}", command, e));
+    // `take` moves the pipe out of the child, so that it is closed, and the
+    // child sees the end of its input, as soon as we are done writing.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(input.as_bytes()).expect("the child reads its input");
+    drop(stdin);
+    let output = child.wait_with_output().expect("the child runs to completion");
+    assert!(output.status.success(), "{:?} failed:\n{}",
+            command, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).expect("the child prints UTF-8")
+}
+
+#[test]
+fn java_and_rust_agree_on_random_carts() {
+    if std::env::var_os("JAVA_DIFFERENTIAL").is_none() {
+        eprintln!("skipped: set JAVA_DIFFERENTIAL=1 to compare with the Java solution");
+        return;
+    }
+    let seed = std::env::var("JAVA_DIFFERENTIAL_SEED")
+        .map(|seed| seed.parse().expect("JAVA_DIFFERENTIAL_SEED is a number"))
+        .unwrap_or(0);
+
+    // `CARGO_TARGET_TMPDIR` is a scratch directory cargo provides to
+    // integration tests, inside `target`.
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let classes = Path::new(env!("CARGO_TARGET_TMPDIR")).join("java-differential");
+    let mut javac = Command::new("javac");
+    javac.arg("-d").arg(&classes).arg(root.join("tests/java/PriceCarts.java"));
+    for rule in ["Market", "PricingRule", "FlatPrice", "BundlePrice", "Supermarket"] {
+        javac.arg(root.join("java/reasonable_implementation").join(format!("{}.java", rule)));
+    }
+    run(&mut javac, "");
+
+    // Only ASCII item codes: a Java `char` is a UTF-16 code unit rather
+    // than a whole character, so the two versions count emoji differently.
+    let mut rng = seeded_rng(seed);
+    let carts: Vec<String> = (0..NUM_CARTS)
+        .map(|_| generate_mixed_char_sequence(&mut rng, &['A', 'B', 'C', 'X', ' '],
+                                              0..MAX_ITEMS_STRING_SIZE).0)
+        .collect();
+    let java_totals = run(Command::new("java").arg("-cp").arg(&classes).arg("PriceCarts"),
+                          &(carts.join("\n") + "\n"));
+
+    let s: Supermarket = Supermarket::demo();
+    let java_totals: Vec<&str> = java_totals.lines().collect();
+    assert_eq!(carts.len(), java_totals.len());
+    for (cart, java_total) in carts.into_iter().zip(java_totals) {
+        // The Java version counts whole dollars in an `int`.
+        let java_cents = Cents(java_total.parse::<i64>().expect("Java prints integers") * 100);
+        assert_eq!(Ok(java_cents), s.checkout(&cart), "pricing {:?} with seed {}", cart, seed);
+    }
+}
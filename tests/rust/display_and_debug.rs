@@ -0,0 +1,101 @@
+/// `Display` should give what a user reads, and `Debug` what a developer
+/// needs, for rules, receipts, markets and errors alike.
+use rust_for_java_devs::core::checkout::CheckoutError;
+use rust_for_java_devs::core::rules::{BundlePrice, Describable, FlatPrice};
+use rust_for_java_devs::display_and_debug::{summary, LoyaltyCard};
+use rust_for_java_devs::money::{Cents, Overflow};
+use rust_for_java_devs::reasonable_implementation::pipeline::Adjustment;
+use rust_for_java_devs::reasonable_implementation::{BoxedRule, Supermarket};
+use rust_for_java_devs::registry::RuleRegistry;
+use rust_for_java_devs::visitor_pattern::{walk, TextRenderer};
+
+#[test]
+fn rules_display_as_their_descriptions() {
+    let flat = FlatPrice::new('A', Cents(2000)).unwrap();
+    assert_eq!("A: $20.00 each", flat.to_string());
+    assert_eq!(flat.describe(), flat.to_string());
+    let boxed: BoxedRule<Cents> = Box::new(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap());
+    assert_eq!("B: $50.00 each or 5 for $150.00", format!("{}", boxed));
+}
+
+#[test]
+fn amounts_display_as_dollars_and_cents() {
+    assert_eq!("$20.00", Cents(2000).to_string());
+    assert_eq!("-$0.05", Cents(-5).to_string());
+    assert_eq!("-$92233720368547758.08", Cents(i64::MIN).to_string());
+}
+
+#[test]
+fn rules_honor_width_and_alignment() {
+    let flat = FlatPrice::new('A', Cents(2000)).unwrap();
+    assert_eq!("[A: $20.00 each    ]", format!("[{:<18}]", flat));
+    assert_eq!("[    A: $20.00 each]", format!("[{:>18}]", flat));
+}
+
+#[test]
+fn market_rules_display_through_the_trait_object() {
+    let market: Supermarket = Supermarket::demo();
+    let rules: Vec<String> = market.rules().map(|rule| rule.to_string()).collect();
+    assert_eq!(vec!["A: $20.00 each", "B: $50.00 each or 5 for $150.00", "C: $30.00 each"], rules);
+}
+
+#[test]
+fn receipts_display_as_the_text_renderer_renders_them() {
+    let market: Supermarket = Supermarket::demo();
+    let mut receipt = market.receipt("ABBBBBC").unwrap();
+    receipt.adjustments.push(Adjustment { label: "coupon".to_string(), amount: Cents(-500) });
+    receipt.total = Cents(19500);
+    assert_eq!("\
+A x1  $20.00
+B x5  $150.00  (5 for $150.00)
+C x1  $30.00
+subtotal  $200.00
+coupon  -$5.00
+total  $195.00
+", receipt.to_string());
+    assert_eq!(walk(&receipt, TextRenderer::default()), receipt.to_string());
+    assert_eq!("B x5  $150.00  (5 for $150.00)", receipt.lines[1].to_string());
+}
+
+#[test]
+fn summaries_fit_in_one_line() {
+    let market: Supermarket = Supermarket::demo();
+    assert_eq!("7 items: $200.00", summary(&market.receipt("ABBBBBC").unwrap()).to_string());
+    assert_eq!("1 item: $20.00", summary(&market.receipt("A").unwrap()).to_string());
+    assert_eq!("  0 items: $0.00", format!("{:>16}", summary(&market.receipt("").unwrap())));
+}
+
+#[test]
+fn markets_debug_their_rules_and_settings() {
+    let market: Supermarket = Supermarket::demo();
+    let debug = format!("{:?}", market);
+    assert!(debug.starts_with("Supermarket { rules: [\"A: $20.00 each\""), "{}", debug);
+    assert!(debug.contains("pipeline: [\"normalize\", \"count\", \"promotions\", \"cart rules\", \"tax\", \"rounding\"]"),
+            "{}", debug);
+    // `{:#?}` puts every field on a line of its own.
+    let pretty = format!("{:#?}", market);
+    assert!(pretty.contains("\n    overflow_policy: "), "{}", pretty);
+}
+
+#[test]
+fn registries_debug_their_kinds() {
+    let registry: RuleRegistry = RuleRegistry::with_builtins();
+    let kinds: Vec<&str> = registry.kinds().collect();
+    assert_eq!(format!("RuleRegistry {{ kinds: {:?} }}", kinds), format!("{:?}", registry));
+    assert_eq!("RuleRegistry { kinds: [] }", format!("{:?}", RuleRegistry::<Cents>::new()));
+}
+
+#[test]
+fn loyalty_cards_hide_their_numbers() {
+    let card = LoyaltyCard { holder: "Ada".to_string(), number: "4929123456781234".to_string() };
+    assert_eq!("LoyaltyCard { holder: \"Ada\", number: \"****1234\" }", format!("{:?}", card));
+    let short = LoyaltyCard { holder: "Bo".to_string(), number: "12".to_string() };
+    assert!(format!("{:?}", short).contains("\"****12\""));
+}
+
+#[test]
+fn errors_display_for_users_and_debug_for_developers() {
+    let error = CheckoutError::Overflow(Overflow);
+    assert_eq!("Overflow(Overflow)", format!("{:?}", error));
+    assert_ne!(format!("{:?}", error), error.to_string());
+}
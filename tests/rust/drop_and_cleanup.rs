@@ -0,0 +1,84 @@
+/// Sessions should write exactly one audit record each, at the moment the
+/// language says they are dropped.
+use std::panic;
+use rust_for_java_devs::drop_and_cleanup::*;
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+/// The sessions named in `log`, in the order they ended.
+fn ended(log: &AuditLog) -> Vec<String> {
+    log.records().into_iter().map(|record| record.session).collect()
+}
+
+#[test]
+fn sessions_write_their_record_when_dropped() {
+    let market: Supermarket = Supermarket::demo();
+    let log = AuditLog::new();
+    {
+        let mut session = CheckoutSession::open(&market, "till 1", &log);
+        session.scan("ABBA").unwrap();
+        session.scan("C").unwrap();
+        assert!(session.scan(&"B".repeat(10)).is_ok());
+        assert!(log.records().is_empty());
+    }
+    assert_eq!(vec![AuditRecord { session: "till 1".to_string(), carts: 3, total: Cents(47000), closed: false }],
+               log.records());
+}
+
+#[test]
+fn locals_are_dropped_last_to_first() {
+    let market: Supermarket = Supermarket::demo();
+    let log = AuditLog::new();
+    {
+        let _first = CheckoutSession::open(&market, "first", &log);
+        let second = CheckoutSession::open(&market, "second", &log);
+        let _third = CheckoutSession::open(&market, "third", &log);
+        // Dropped early, by handing it to a function that takes ownership.
+        drop(second);
+        assert_eq!(vec!["second"], ended(&log));
+    }
+    assert_eq!(vec!["second", "third", "first"], ended(&log));
+}
+
+#[test]
+fn collections_drop_their_elements_front_to_back() {
+    let market: Supermarket = Supermarket::demo();
+    let log = AuditLog::new();
+    let tills: Vec<_> = ["a", "b", "c"].iter().map(|name| CheckoutSession::open(&market, *name, &log)).collect();
+    drop(tills);
+    assert_eq!(vec!["a", "b", "c"], ended(&log));
+}
+
+#[test]
+fn closed_sessions_write_only_once() {
+    let market: Supermarket = Supermarket::demo();
+    let log = AuditLog::new();
+    let mut session = CheckoutSession::open(&market, "till 2", &log);
+    session.scan("A").unwrap();
+    let record = session.close();
+    assert!(record.closed);
+    assert_eq!(vec![record], log.records());
+}
+
+#[test]
+fn panics_still_drop_the_session() {
+    let market: Supermarket = Supermarket::demo();
+    let log = AuditLog::new();
+    // The closure only reads the market and appends to the log, so nothing
+    // it touches is left half changed by the panic.
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let mut session = CheckoutSession::open(&market, "till 3", &log);
+        session.scan("AA").unwrap();
+        panic!("the till caught fire");
+    }));
+    assert!(result.is_err());
+    assert_eq!(Cents(4000), log.records()[0].total);
+}
+
+#[test]
+fn forgotten_sessions_write_nothing() {
+    let market: Supermarket = Supermarket::demo();
+    let log = AuditLog::new();
+    std::mem::forget(CheckoutSession::open(&market, "lost", &log));
+    assert!(log.records().is_empty());
+}
@@ -0,0 +1,49 @@
+/// The enum-dispatch Supermarket runs the same suite as every other
+/// implementation of the demo catalog.
+mod common;
+
+use rust_for_java_devs::enum_implementation::{PricingRule, Supermarket};
+use rust_for_java_devs::money::{OverflowPolicy, Overflow};
+use std::collections::BTreeMap;
+
+market_suite!(Supermarket::<Cents>::demo());
+
+#[test]
+fn honors_the_overflow_policy() {
+    let s = Supermarket::demo().with_overflow_policy(OverflowPolicy::Saturating);
+    assert_eq!(Ok(Cents(24000)), s.try_checkout("ABBACBBAB".to_string()))
+}
+
+#[test]
+fn rules_can_be_priced_on_their_own() {
+    let rule = PricingRule::Bundle {
+        product: 'B',
+        lone_cost: Cents(5000),
+        bundle_size: 5,
+        bundle_cost: Cents(15000)
+    };
+    let mut counts = BTreeMap::new();
+    counts.insert('B', 6);
+    assert_eq!(Ok(Cents(20000)), rule.price(&counts, OverflowPolicy::Checked))
+}
+
+#[test]
+fn checked_rules_report_overflow() {
+    let rule = PricingRule::Flat { product: 'A', cost: Cents(i64::MAX) };
+    let mut counts = BTreeMap::new();
+    counts.insert('A', 2);
+    assert_eq!(Err(Overflow), rule.price(&counts, OverflowPolicy::Checked))
+}
+
+#[test]
+fn empty_bundles_price_items_individually() {
+    let rule = PricingRule::Bundle {
+        product: 'B',
+        lone_cost: Cents(5000),
+        bundle_size: 0,
+        bundle_cost: Cents(15000)
+    };
+    let mut counts = BTreeMap::new();
+    counts.insert('B', 2);
+    assert_eq!(Ok(Cents(10000)), rule.price(&counts, OverflowPolicy::Checked))
+}
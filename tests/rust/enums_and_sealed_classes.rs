@@ -0,0 +1,115 @@
+/// Promotions as an enum and as trait objects, and what each makes easy to
+/// change from outside the crate.
+use rust_for_java_devs::core::counting::Counts;
+use rust_for_java_devs::enums_and_sealed_classes::*;
+use rust_for_java_devs::money::{Cents, OverflowPolicy, Overflow};
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+/// The demo catalog, with `discounts` taken off after the rules.
+fn market<D: Discount + 'static>(discounts: Vec<D>) -> Supermarket {
+    let mut market = Supermarket::demo();
+    market.pipeline_mut().insert_after("promotions", Discounts::new(discounts)).unwrap();
+    market
+}
+
+fn promotions() -> Vec<Promotion> {
+    vec!(
+        Promotion::MultiBuy(MultiBuy { product: 'A', every: 3, cost: Cents(2000) }),
+        Promotion::SpendAndSave(SpendAndSave { threshold: Cents(20000), saving: Cents(1500) }),
+        Promotion::Coupon(Coupon { code: "WELCOME".to_string(), saving: Cents(500) })
+    )
+}
+
+fn labels(market: &Supermarket, items: &str) -> Vec<String> {
+    market.receipt(items).unwrap().adjustments.into_iter().map(|adjustment| adjustment.label).collect()
+}
+
+#[test]
+fn enum_promotions_come_off_the_total() {
+    let market = market(promotions());
+    // $240.00, less a free A, $15.00 for spending over $200.00 and $5.00 for
+    // the coupon.
+    assert_eq!(Ok(Cents(20000)), market.try_checkout("ABBACBBAB"));
+    assert_eq!(vec!("every 3rd A free", "$15.00 off over $200.00", "coupon WELCOME"),
+               labels(&market, "ABBACBBAB"));
+}
+
+#[test]
+fn promotions_that_do_not_apply_leave_no_adjustment() {
+    let market = market(promotions());
+    assert_eq!(Ok(Cents(4500)), market.try_checkout("AC"));
+    assert_eq!(vec!("coupon WELCOME"), labels(&market, "AC"));
+}
+
+#[test]
+fn trait_objects_price_the_same() {
+    let discounts: Vec<Box<dyn Discount>> = vec!(
+        Box::new(MultiBuy { product: 'A', every: 3, cost: Cents(2000) }),
+        Box::new(SpendAndSave { threshold: Cents(20000), saving: Cents(1500) }),
+        Box::new(Coupon { code: "WELCOME".to_string(), saving: Cents(500) })
+    );
+    assert_eq!(Ok(Cents(20000)), market(discounts).try_checkout("ABBACBBAB"));
+}
+
+/// A kind of discount the crate knows nothing about. With trait objects,
+/// adding one takes a struct and an impl, here, and nothing in the crate
+/// changes.
+struct HappyHour;
+
+impl Discount for HappyHour {
+    fn label(&self) -> String {
+        "happy hour".to_string()
+    }
+
+    fn saving(&self, counts: &Counts, _subtotal: Cents, policy: OverflowPolicy) -> Result<Cents, Overflow> {
+        policy.times(Cents(100), counts.values().sum())
+    }
+}
+
+#[test]
+fn a_new_kind_of_discount_can_be_added_downstream() {
+    let discounts: Vec<Box<dyn Discount>> = vec!(Box::new(HappyHour), Box::new(promotions().remove(2)));
+    // $70.00 for three items, less $1.00 each and $5.00 for the coupon.
+    assert_eq!(Ok(Cents(6200)), market(discounts).try_checkout("AAC"));
+}
+
+/// A new operation on promotions. With the enum, this is a function with a
+/// `match`, written here, and nothing in the crate changes. Because
+/// `Promotion` is `#[non_exhaustive]`, the `_` arm is required: a variant
+/// added to the crate later would land there, rather than break this code.
+fn fixed_saving(promotion: &Promotion) -> Option<Cents> {
+    match promotion {
+        Promotion::SpendAndSave(SpendAndSave { saving, .. }) | Promotion::Coupon(Coupon { saving, .. }) => {
+            Some(*saving)
+        },
+        Promotion::MultiBuy(_) => None,
+        _ => None
+    }
+}
+
+#[test]
+fn a_new_operation_can_be_added_downstream() {
+    let savings: Vec<Option<Cents>> = promotions().iter().map(fixed_saving).collect();
+    assert_eq!(vec!(None, Some(Cents(1500)), Some(Cents(500))), savings);
+}
+
+#[test]
+fn the_crate_adds_operations_the_same_way() {
+    let depends: Vec<bool> = promotions().iter().map(Promotion::depends_on_items).collect();
+    assert_eq!(vec!(true, false, false), depends);
+}
+
+#[test]
+fn a_multibuy_of_every_zeroth_item_frees_nothing() {
+    let never = MultiBuy { product: 'A', every: 0, cost: Cents(2000) };
+    let counts = Counts::from([('A', 4)]);
+    assert_eq!(Ok(Cents(0)), never.saving(&counts, Cents(8000), OverflowPolicy::Checked));
+}
+
+#[test]
+fn labels_count_in_ordinals() {
+    let label = |every| MultiBuy { product: 'B', every, cost: Cents(5000) }.label();
+    assert_eq!("every 2nd B free", label(2));
+    assert_eq!("every 11th B free", label(11));
+    assert_eq!("every 21st B free", label(21));
+}
@@ -0,0 +1,172 @@
+/// Catalog and receipt types should work as `HashMap` and `BTreeMap` keys,
+/// and the hand-written impls should keep the contracts Java's `equals`,
+/// `hashCode` and `compareTo` have.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use proptest::prelude::*;
+use rust_for_java_devs::catalog::{self, PricePoint};
+use rust_for_java_devs::equality_and_hashing::ProductCode;
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::receipt::ReceiptLine;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn line(product: Option<char>, quantity: u64, amount: i64) -> ReceiptLine {
+    ReceiptLine {
+        product,
+        rule: String::from("rule"),
+        quantity,
+        amount: Cents(amount),
+        promotions: Vec::new()
+    }
+}
+
+#[test]
+fn equal_receipts_find_each_other_in_a_hash_set() {
+    let market: Supermarket = Supermarket::demo();
+    let mut seen = HashSet::new();
+    // The same cart scanned in another order prices to an equal receipt,
+    // which the set recognizes, as a `HashSet` of Java records would.
+    assert!(seen.insert(market.receipt("ABBC").unwrap()));
+    assert!(!seen.insert(market.receipt("CBBA").unwrap()));
+    assert!(seen.insert(market.receipt("ABC").unwrap()));
+    assert_eq!(2, seen.len());
+}
+
+#[test]
+fn catalog_rows_key_a_hash_map() {
+    let market: Supermarket = Supermarket::demo();
+    let table = catalog::table(&market);
+    let mut products = HashMap::new();
+    for row in table.rows.iter() {
+        products.insert(row.clone(), row.product);
+    }
+    // A row built again from the same market is a different value, not the
+    // same object, and still finds its entry.
+    for row in catalog::table(&market).rows {
+        assert_eq!(Some(&row.product), products.get(&row));
+    }
+}
+
+#[test]
+fn price_points_sort_by_quantity_then_total() {
+    let points: BTreeSet<PricePoint> = [
+        PricePoint { quantity: 5, total: Cents(15000) },
+        PricePoint { quantity: 1, total: Cents(5000) },
+        PricePoint { quantity: 5, total: Cents(10000) },
+        PricePoint { quantity: 1, total: Cents(5000) }
+    ].into_iter().collect();
+    assert_eq!(vec![
+        PricePoint { quantity: 1, total: Cents(5000) },
+        PricePoint { quantity: 5, total: Cents(10000) },
+        PricePoint { quantity: 5, total: Cents(15000) }
+    ], points.into_iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn lines_without_a_product_sort_last() {
+    let mut lines = [line(None, 0, 100), line(Some('C'), 1, 3000), line(Some('A'), 2, 4000)];
+    lines.sort();
+    assert_eq!(vec![Some('A'), Some('C'), None],
+               lines.iter().map(|line| line.product).collect::<Vec<_>>());
+}
+
+#[test]
+fn receipt_lines_key_a_btree_map() {
+    let market: Supermarket = Supermarket::demo();
+    let mut totals = BTreeMap::new();
+    for cart in ["CBA", "ABBBBBC", "AA"] {
+        for line in market.receipt(cart).unwrap().lines {
+            *totals.entry(line).or_insert(0) += 1;
+        }
+    }
+    // "CBA" and "ABBBBBC" share the lines for one A and one C.
+    assert_eq!(Some(&2), totals.get(&line_for(&market, "A")));
+    assert_eq!(Some(&2), totals.get(&line_for(&market, "C")));
+    assert_eq!(5, totals.len());
+}
+
+fn line_for(market: &Supermarket, cart: &str) -> ReceiptLine {
+    market.receipt(cart).unwrap().lines.remove(0)
+}
+
+#[test]
+fn product_codes_ignore_case_in_hash_maps() {
+    let mut stock = HashMap::new();
+    stock.insert(ProductCode('a'), 3);
+    // Equal keys must hash the same, or this lookup would miss.
+    assert_eq!(Some(&3), stock.get(&ProductCode('A')));
+    stock.insert(ProductCode('A'), 4);
+    assert_eq!(1, stock.len());
+    // The map keeps the first key it was given; only the value is replaced.
+    assert_eq!("a", stock.keys().next().unwrap().to_string());
+}
+
+#[test]
+fn product_codes_ignore_case_in_btree_maps() {
+    let codes: BTreeSet<ProductCode> = "bAcaB".chars().map(ProductCode).collect();
+    // Which of two equal codes the set keeps is up to the set, so only the
+    // normalized codes are checked.
+    assert_eq!("ABC", codes.iter().map(|code| code.normalized()).collect::<String>());
+}
+
+/// The pitfall the contract guards against: equality that ignores case,
+/// next to the hash a `derive` would have written, which does not.
+#[derive(Debug)]
+struct CarelessCode(char);
+
+impl PartialEq for CarelessCode {
+    fn eq(&self, other: &CarelessCode) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl Eq for CarelessCode {}
+
+impl Hash for CarelessCode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+#[test]
+fn inconsistent_hashes_lose_keys() {
+    assert_eq!(CarelessCode('a'), CarelessCode('A'));
+    assert_ne!(hash_of(&CarelessCode('a')), hash_of(&CarelessCode('A')));
+    let mut stock = HashMap::new();
+    stock.insert(CarelessCode('a'), 3);
+    // As in Java, the lookup goes to the wrong bucket and finds nothing,
+    // although an equal key is in the map.
+    assert_eq!(None, stock.get(&CarelessCode('A')));
+}
+
+proptest! {
+    #[test]
+    fn equal_codes_hash_and_compare_equal(a in "[a-zA-Z]", b in "[a-zA-Z]") {
+        let a = ProductCode(a.chars().next().unwrap());
+        let b = ProductCode(b.chars().next().unwrap());
+        if a == b {
+            prop_assert_eq!(hash_of(&a), hash_of(&b));
+        }
+        prop_assert_eq!(a == b, a.cmp(&b).is_eq());
+    }
+
+    #[test]
+    fn line_order_agrees_with_equality(a in 0..3u64, b in 0..3u64, x in 0..3i64, y in 0..3i64,
+                                       p in prop::option::of(prop::char::range('A', 'C')),
+                                       q in prop::option::of(prop::char::range('A', 'C'))) {
+        let first = line(p, a, x);
+        let second = line(q, b, y);
+        prop_assert_eq!(first == second, first.cmp(&second).is_eq());
+        prop_assert_eq!(first.cmp(&second), second.cmp(&first).reverse());
+        if first == second {
+            prop_assert_eq!(hash_of(&first), hash_of(&second));
+        }
+    }
+}
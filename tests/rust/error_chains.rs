@@ -0,0 +1,114 @@
+/// One failure to load settings, reported three ways, and the chain of
+/// causes each renders.
+use std::fs;
+use std::path::PathBuf;
+use rust_for_java_devs::error_chains::{bare, chain, derived, with_anyhow, MarketConfig};
+use rust_for_java_devs::market::CartLimits;
+use rust_for_java_devs::money::{Cents, OverflowPolicy};
+use rust_for_java_devs::prelude::Supermarket;
+
+const EXPRESS: &str = "# Limits for the express lane.\nmax_items = 10\n\nmax_bytes = 64\noverflow = saturating\n";
+const BAD_NUMBER: &str = "max_bytes = 64\nmax_items = ten\n";
+
+/// A file in the temporary directory holding `text`, named for this test
+/// run so that parallel runs cannot collide.
+fn settings_file(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rust_for_java_devs-{}-{}", std::process::id(), name));
+    fs::write(&path, text).unwrap();
+    path
+}
+
+fn missing_file() -> PathBuf {
+    std::env::temp_dir().join(format!("rust_for_java_devs-{}-missing.cfg", std::process::id()))
+}
+
+#[test]
+fn all_three_load_the_same_settings() {
+    let expected = MarketConfig {
+        cart_limits: CartLimits { max_items: 10, max_bytes: 64 },
+        overflow_policy: OverflowPolicy::Saturating
+    };
+    let path = settings_file("express.cfg", EXPRESS);
+    assert_eq!(expected, bare::load(&path).unwrap());
+    assert_eq!(expected, derived::load(&path).unwrap());
+    assert_eq!(expected, with_anyhow::load(&path).unwrap());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn settings_apply_to_a_market() {
+    let config = derived::parse("max_items = 2").unwrap();
+    let market = config.apply(Supermarket::<Cents>::new());
+    assert_eq!(2, market.cart_limits().max_items);
+}
+
+#[test]
+fn a_bare_enum_chains_to_the_cause_it_was_given() {
+    let error = bare::parse(BAD_NUMBER).unwrap_err();
+    assert!(matches!(error, bare::ConfigError::BadNumber { line: 2, .. }));
+    assert_eq!(vec!("line 2: max_items is not a number", "invalid digit found in string"), chain(&error));
+}
+
+/// `?` converted the `io::Error` with no word of the file: the chain says
+/// what failed, but not where.
+#[test]
+fn a_bare_enum_forgets_which_file() {
+    let error = bare::load(&missing_file()).unwrap_err();
+    let messages = chain(&error);
+    assert_eq!(2, messages.len());
+    assert_eq!("could not read the settings", messages[0]);
+    assert!(!messages.concat().contains("missing.cfg"), "{:?}", messages);
+}
+
+#[test]
+fn a_derived_enum_names_the_file() {
+    let path = missing_file();
+    let error = derived::load(&path).unwrap_err();
+    let messages = chain(&error);
+    assert_eq!(format!("could not read {}", path.display()), messages[0]);
+    assert_eq!(2, messages.len());
+    match error {
+        derived::ConfigError::Read { source, .. } => assert_eq!(std::io::ErrorKind::NotFound, source.kind()),
+        other => panic!("expected a read error, got {:?}", other)
+    }
+}
+
+#[test]
+fn derived_and_bare_messages_agree() {
+    let cases = ["max_items", "max_items = 1\ncolour = red", "overflow = sideways", BAD_NUMBER];
+    for text in cases {
+        let bare = bare::parse(text).unwrap_err();
+        let derived = derived::parse(text).unwrap_err();
+        assert_eq!(chain(&bare), chain(&derived), "{:?}", text);
+    }
+}
+
+#[test]
+fn anyhow_renders_the_whole_chain() {
+    let path = settings_file("bad-number.cfg", BAD_NUMBER);
+    let error = with_anyhow::load(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+
+    let load = format!("could not load {}", path.display());
+    assert_eq!(vec!(load.as_str(), "line 2: max_items is not a number", "invalid digit found in string"),
+               error.chain().map(|cause| cause.to_string()).collect::<Vec<_>>());
+    // `{}` is the outermost message alone, `{:#}` every message, joined
+    // with colons, and `{:?}` the `Caused by:` list Java prints.
+    assert_eq!(load, format!("{}", error));
+    assert_eq!(format!("{}: line 2: max_items is not a number: invalid digit found in string", load),
+               format!("{:#}", error));
+    assert!(format!("{:?}", error).contains("Caused by:\n    0: line 2: max_items is not a number\n"));
+}
+
+#[test]
+fn anyhow_errors_still_chain_as_std_errors() {
+    let error = with_anyhow::parse("overflow = sideways").unwrap_err();
+    assert_eq!(vec!("line 1: no overflow policy called \"sideways\""), chain(error.as_ref()));
+}
+
+#[test]
+fn anyhow_errors_can_be_downcast_to_their_cause() {
+    let error = with_anyhow::load(&missing_file()).unwrap_err();
+    let cause = error.downcast_ref::<std::io::Error>().expect("an io::Error at the root");
+    assert_eq!(std::io::ErrorKind::NotFound, cause.kind());
+}
@@ -0,0 +1,40 @@
+/// Both checkouts should agree whenever they succeed, and fail on the same
+/// carts, each in its own way.
+use rust_for_java_devs::error_handling_comparison::{with_panics, with_result};
+use rust_for_java_devs::error_handling_comparison::with_result::OrderError;
+use rust_for_java_devs::money::Cents;
+
+const PRICES: [(char, Cents); 3] = [('A', Cents(2000)), ('B', Cents(5000)), ('C', Cents(3000))];
+
+#[test]
+fn both_styles_agree_on_good_carts() {
+    for cart in ["", "A", "ABC", "CCCBA"] {
+        assert_eq!(Ok(with_panics::checkout(&PRICES, cart)), with_result::checkout(&PRICES, cart));
+    }
+    assert_eq!(Ok(Cents(10000)), with_result::checkout(&PRICES, "ABC"));
+}
+
+#[test]
+fn results_say_exactly_what_went_wrong() {
+    assert_eq!(Err(OrderError::UnknownProduct { product: 'X', position: 2 }),
+               with_result::checkout(&PRICES, "ABXC"));
+    let huge = [('H', Cents(i64::MAX))];
+    assert_eq!(Err(OrderError::Overflow), with_result::checkout(&huge, "HH"));
+    assert_eq!("no price for 'X' at position 2",
+               with_result::checkout(&PRICES, "ABXC").unwrap_err().to_string());
+}
+
+/// `should_panic` is JUnit's `assertThrows`, for a whole test.
+#[test]
+#[should_panic(expected = "no price for 'X'")]
+fn panics_end_the_checkout() {
+    with_panics::checkout(&PRICES, "ABXC");
+}
+
+#[test]
+fn caught_panics_leave_only_a_message() {
+    assert_eq!(Ok(Cents(7000)), with_panics::try_checkout(&PRICES, "AB"));
+    assert_eq!(Err("no price for 'X'".to_string()), with_panics::try_checkout(&PRICES, "ABXC"));
+    let huge = [('H', Cents(i64::MAX))];
+    assert_eq!(Err("the total is too large".to_string()), with_panics::try_checkout(&huge, "HH"));
+}
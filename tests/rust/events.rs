@@ -0,0 +1,75 @@
+/// Every priced cart should be published once, as a `CheckoutCompleted`
+/// that survives the trip through JSON.
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use rust_for_java_devs::events::{self, cart_hash, CheckoutCompleted, EventError, NdjsonPublisher};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::{CartLimits, CheckoutError, Supermarket};
+
+#[test]
+fn cart_hashes_are_fnv_1a() {
+    assert_eq!("cbf29ce484222325", cart_hash(""));
+    assert_eq!("af63dc4c8601ec8c", cart_hash("a"));
+    assert_ne!(cart_hash("AB"), cart_hash("BA"));
+}
+
+#[test]
+fn checkouts_publish_one_event_each() {
+    let published = Mutex::new(Vec::new());
+    let publisher = |event: &CheckoutCompleted| {
+        published.lock().unwrap().push(event.clone());
+        Ok(())
+    };
+    let s = Supermarket::demo();
+    let event = events::checkout(&s, "store-7", "ABBACBBAB", &publisher).unwrap();
+    assert_eq!("store-7", event.store_id);
+    assert_eq!(cart_hash("ABBACBBAB"), event.cart_hash);
+    assert_eq!(Cents(24000), event.receipt.total);
+    assert_eq!(vec!(event), published.into_inner().unwrap());
+}
+
+#[test]
+fn unpriceable_carts_publish_nothing() {
+    let publisher = |_event: &CheckoutCompleted| -> io::Result<()> { panic!("published") };
+    let s = Supermarket::demo().with_cart_limits(CartLimits { max_items: 2, ..CartLimits::UNLIMITED });
+    assert!(matches!(events::checkout(&s, "store-7", "AAA", &publisher),
+                     Err(EventError::Checkout(CheckoutError::CartTooLarge { .. }))));
+}
+
+#[test]
+fn publishing_failures_are_reported() {
+    let publisher = |_event: &CheckoutCompleted| Err(io::Error::other("broker is down"));
+    let s = Supermarket::demo();
+    match events::checkout(&s, "store-7", "A", &publisher) {
+        Err(EventError::Publish(e)) => assert_eq!("broker is down", e.to_string()),
+        other => panic!("expected a publishing failure, found {:?}", other)
+    }
+}
+
+#[test]
+fn events_are_written_as_one_json_object_per_line() {
+    let publisher = NdjsonPublisher::new(Vec::new());
+    let s = Supermarket::demo();
+    let first = events::checkout(&s, "store-7", "ABBACBBAB", &publisher).unwrap();
+    let second = events::checkout(&s, "store-7", "AXBC", &publisher).unwrap();
+    let output = String::from_utf8(publisher.into_inner()).unwrap();
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(2, lines.len());
+    let json: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!("store-7", json["storeId"]);
+    assert_eq!(24000, json["receipt"]["total"]);
+    assert_eq!("5 for $150.00", json["receipt"]["lines"][1]["promotions"][0]);
+    // Reading an event back gives the one that was published.
+    assert_eq!(first, serde_json::from_str::<CheckoutCompleted>(lines[0]).unwrap());
+    assert_eq!(second, serde_json::from_str::<CheckoutCompleted>(lines[1]).unwrap());
+}
+
+#[test]
+fn timestamps_count_milliseconds_since_the_epoch() {
+    let receipt = Supermarket::<Cents>::demo().receipt("A").unwrap();
+    let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+    let event = CheckoutCompleted::new("store-7", "A", receipt, at);
+    assert_eq!(1_700_000_000_123, event.timestamp);
+}
@@ -0,0 +1,35 @@
+/// The statically dispatched Supermarket runs the same suite as every other
+/// implementation of the demo catalog.
+mod common;
+
+use rust_for_java_devs::generic_implementation::{Supermarket, FlatPrice, BundlePrice};
+use rust_for_java_devs::money::OverflowPolicy;
+
+market_suite!(Supermarket::<_, Cents>::demo());
+
+#[test]
+fn honors_the_overflow_policy() {
+    let s = Supermarket::demo().with_overflow_policy(OverflowPolicy::Saturating);
+    assert_eq!(Ok(Cents(24000)), s.try_checkout_str("ABBACBBAB"))
+}
+
+#[test]
+fn prices_non_ascii_carts_through_the_fallback_path() {
+    let s: Supermarket<_> = Supermarket::demo();
+    assert_eq!(Ok(Cents(4000)), s.try_checkout_str("A\u{e9}A"))
+}
+
+#[test]
+fn any_tuple_of_rules_forms_a_market() {
+    let s = Supermarket::new((
+        FlatPrice::new('X', Cents(100)).unwrap(),
+        BundlePrice::new('Y', Cents(300), 2, Cents(500)).unwrap()
+    ));
+    assert_eq!(Ok(Cents(900)), s.try_checkout_str("XYYY"))
+}
+
+#[test]
+fn the_unit_tuple_is_an_empty_catalog() {
+    let s: Supermarket<(), Cents> = Supermarket::new(());
+    assert_eq!(Ok(Cents(0)), s.try_checkout_str("ABBACBBAB"))
+}
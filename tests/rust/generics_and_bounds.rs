@@ -0,0 +1,67 @@
+/// A market generic over its item type should price `char` carts like every
+/// other implementation, and price carts of any other hashable item too.
+mod common;
+
+use rust_for_java_devs::conversions::Sku;
+use rust_for_java_devs::generics_and_bounds::{cheapest, count, GenericMarket, MultiBuy, UnitPrice};
+use rust_for_java_devs::money::Overflow;
+
+fn demo() -> GenericMarket<char> {
+    GenericMarket::new()
+        .with_rules(vec![UnitPrice { item: 'A', cost: Cents(2000) },
+                         UnitPrice { item: 'C', cost: Cents(3000) }])
+        .with_rule(MultiBuy { item: 'B', each: Cents(5000), size: 5, cost: Cents(15000) })
+}
+
+market_suite!(demo());
+
+#[test]
+fn numeric_codes_are_items_too() {
+    let market: GenericMarket<u32> = GenericMarket::new()
+        .with_rule(UnitPrice { item: 4011, cost: Cents(25) })
+        .with_rule(MultiBuy { item: 4225, each: Cents(150), size: 3, cost: Cents(400) });
+    assert_eq!(Ok(Cents(475)), market.try_checkout(vec![4011, 4225, 4225, 4225, 4011, 4011, 9999]));
+}
+
+#[test]
+fn barcodes_and_skus_are_items_too() {
+    let barcodes: GenericMarket<&str> = GenericMarket::new()
+        .with_rule(UnitPrice { item: "5000112637922", cost: Cents(120) });
+    assert_eq!(Ok(Cents(240)), barcodes.try_checkout(["5000112637922", "5000112637922"]));
+    let skus: GenericMarket<Sku> = GenericMarket::new()
+        .with_rule(UnitPrice { item: Sku('A'), cost: Cents(2000) });
+    assert_eq!(Ok(Cents(4000)), skus.try_checkout("ABA".chars().map(Sku::from)));
+}
+
+#[test]
+fn each_item_is_priced_on_its_own() {
+    let mut prices = Vec::new();
+    demo().each("ABX".chars(), |item, price| prices.push((*item, price))).unwrap();
+    assert_eq!(vec![('A', Cents(2000)), ('B', Cents(5000)), ('X', Cents(0))], prices);
+}
+
+#[test]
+fn checkouts_report_overflow() {
+    let market: GenericMarket<char> = GenericMarket::new()
+        .with_rule(UnitPrice { item: 'G', cost: Cents(i64::MAX) });
+    assert_eq!(Err(Overflow), market.try_checkout("GG".chars()));
+}
+
+#[test]
+fn items_are_counted_by_value() {
+    let counts = count(vec!["apple", "pear", "apple"]);
+    assert_eq!(Some(&2), counts.get("apple"));
+    assert_eq!(Some(&1), counts.get("pear"));
+    assert_eq!(None, counts.get("plum"));
+}
+
+#[test]
+fn cheapest_finds_the_least_total() {
+    let market = demo();
+    let totals = ["ABBBBB", "CC", "BBBBB"].iter().map(|cart| market.try_checkout(cart.chars()).unwrap());
+    assert_eq!(Some(Cents(6000)), cheapest(totals));
+    assert_eq!(None, cheapest(Vec::<Cents>::new()));
+    // The bound is `PartialOrd`, so floats qualify, and a `NaN` is passed
+    // over rather than taken for the least.
+    assert_eq!(Some(1.5), cheapest(vec![2.0, f64::NAN, 1.5]));
+}
@@ -0,0 +1,70 @@
+/// Golden-master regression test: every implementation prices the recorded
+/// carts in `tests/golden/carts.txt`, and the totals must match those
+/// recorded in `tests/golden/demo_catalog.txt` exactly.
+///
+/// A change to the catalog or to the engine that alters any historical
+/// total fails here, listing the carts affected. See `testkit::assert_golden`
+/// for how to accept an intended change.
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::testkit::{golden_totals, assert_golden, explain_changes};
+use rust_for_java_devs::reasonable_implementation::{BoxedRule, BundlePrice, FlatPrice, Supermarket};
+use rust_for_java_devs::{reasonable_implementation, borrowed_rules_implementation,
+                         enum_implementation, generic_implementation};
+
+/// `include_str!` embeds the file's contents in the test binary at compile
+/// time, and `concat!` and `env!` build its path, also at compile time.
+const CORPUS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/carts.txt"));
+const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/demo_catalog.txt");
+
+#[test]
+fn reasonable_implementation_matches_the_recorded_totals() {
+    let s: reasonable_implementation::Supermarket = reasonable_implementation::Supermarket::demo();
+    assert_golden(SNAPSHOT, &golden_totals(&s, CORPUS));
+}
+
+#[test]
+fn borrowed_rules_implementation_matches_the_recorded_totals() {
+    let s = borrowed_rules_implementation::Supermarket::<Cents>::demo();
+    assert_golden(SNAPSHOT, &golden_totals(&s, CORPUS));
+}
+
+#[test]
+fn enum_implementation_matches_the_recorded_totals() {
+    let s = enum_implementation::Supermarket::<Cents>::demo();
+    assert_golden(SNAPSHOT, &golden_totals(&s, CORPUS));
+}
+
+#[test]
+fn generic_implementation_matches_the_recorded_totals() {
+    let s = generic_implementation::Supermarket::<_, Cents>::demo();
+    assert_golden(SNAPSHOT, &golden_totals(&s, CORPUS));
+}
+
+/// The demo catalog, but with C at $35.00.
+fn dearer_c() -> Supermarket {
+    let rules: Vec<BoxedRule<Cents>> = vec![
+        Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+        Box::new(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap()),
+        Box::new(FlatPrice::new('C', Cents(3500)).unwrap()),
+    ];
+    Supermarket::with_rules(rules)
+}
+
+#[test]
+fn price_changes_are_explained_cart_by_cart() {
+    let report = explain_changes(&Supermarket::demo(), &dearer_c(), CORPUS);
+    assert!(report.starts_with("\
+ABBACBBAB
+  C: 1 for $30.00 -> $35.00 (+$5.00)
+  total: $240.00 -> $245.00 (+$5.00)
+"), "{}", report);
+    // Only the carts holding a C have moved.
+    let explained: Vec<&str> = report.lines().filter(|line| !line.starts_with("  ")).collect();
+    let with_c: Vec<&str> = CORPUS.lines().filter(|cart| !cart.starts_with('#') && cart.contains('C')).collect();
+    assert_eq!(with_c, explained);
+}
+
+#[test]
+fn unchanged_catalogs_explain_nothing() {
+    assert_eq!("", explain_changes(&Supermarket::<Cents>::demo(), &Supermarket::demo(), CORPUS));
+}
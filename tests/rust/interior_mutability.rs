@@ -0,0 +1,67 @@
+/// Rules counting their uses through `&self`, in `Cell`s on one thread and
+/// in atomics on many.
+use std::thread;
+use rust_for_java_devs::core::rules::{BundlePrice, FlatPrice};
+use rust_for_java_devs::interior_mutability::*;
+use rust_for_java_devs::lifetimes_explained::SliceMarket;
+use rust_for_java_devs::prelude::*;
+
+#[test]
+fn cells_count_on_a_single_thread() {
+    let rules = [
+        CellCountedRule::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+        CellCountedRule::new(FlatPrice::new('C', Cents(3000)).unwrap())
+    ];
+    let market = SliceMarket::new(&rules);
+    for cart in ["AAC", "A", "B", ""] {
+        market.try_checkout::<Cents>(cart).unwrap();
+    }
+    assert_eq!(Usage { carts: 2, items: 3 }, rules[0].usage());
+    assert_eq!(Usage { carts: 1, items: 1 }, rules[1].usage());
+}
+
+#[test]
+fn counting_does_not_change_prices() {
+    let rule = CountedRule::new(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap());
+    let counter = rule.counter();
+    let market: Supermarket = Supermarket::with_rules(vec!(Box::new(rule)));
+    assert_eq!(Ok(Cents(20000)), market.try_checkout("BBBBBB"));
+    assert_eq!(Usage { carts: 1, items: 6 }, counter.usage());
+}
+
+#[test]
+fn receipts_explain_without_counting_twice() {
+    let rule = CountedRule::new(FlatPrice::new('A', Cents(2000)).unwrap());
+    let counter = rule.counter();
+    let market: Supermarket = Supermarket::with_rules(vec!(Box::new(rule)));
+    market.receipt("AA").unwrap();
+    assert_eq!(Usage { carts: 1, items: 2 }, counter.usage());
+}
+
+/// Eight threads price a thousand carts each through one market. With a
+/// plain `u64` behind the counts, as a Java `long` field would be, some
+/// increments would be lost; with atomics, none are.
+#[test]
+fn atomics_count_across_threads() {
+    let rule = CountedRule::new(FlatPrice::new('A', Cents(2000)).unwrap());
+    let counter = rule.counter();
+    let market: Supermarket = Supermarket::with_rules(vec!(Box::new(rule)));
+    thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for _ in 0..1000 {
+                    assert_eq!(Ok(Cents(6000)), market.try_checkout("AAA"));
+                }
+            });
+        }
+    });
+    assert_eq!(Usage { carts: 8000, items: 24000 }, counter.usage());
+}
+
+#[test]
+fn wrappers_describe_and_lint_as_the_rule_does() {
+    let bundle = BundlePrice::new('B', Cents(5000), 5, Cents(30000)).unwrap();
+    let counted = CountedRule::new(bundle.clone());
+    assert_eq!(bundle.describe(), counted.describe());
+    assert_eq!(PricingRule::<Cents>::lint(&bundle), PricingRule::<Cents>::lint(&counted));
+}
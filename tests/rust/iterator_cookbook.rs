@@ -0,0 +1,55 @@
+/// Every iterator recipe should price a cart as the plain loop does, and the
+/// last, with the full rules, as the demo `Supermarket` does.
+use proptest::prelude::*;
+use rust_for_java_devs::core::counting::{count_items, Counts};
+use rust_for_java_devs::iterator_cookbook::*;
+use rust_for_java_devs::money::{Cents, Overflow};
+use rust_for_java_devs::reasonable_implementation::{FlatPrice, Supermarket};
+
+const PRICES: [(char, Cents); 3] = [('A', Cents(2000)), ('B', Cents(5000)), ('C', Cents(2500))];
+
+/// Checks every unit price recipe against `with_loop` for one cart.
+fn all_agree(cart: &str) {
+    let expected = with_loop(&PRICES, cart);
+    assert_eq!(expected, with_filter_map(&PRICES, cart), "filter_map");
+    assert_eq!(expected, with_fold(&PRICES, cart), "fold");
+    assert_eq!(Ok(expected), with_try_fold(&PRICES, cart), "try_fold");
+    assert_eq!(Ok(expected), with_groups(&PRICES, cart), "groups");
+}
+
+#[test]
+fn unknown_products_cost_nothing() {
+    all_agree("");
+    all_agree("XYZ");
+    assert_eq!(Cents(4500), with_fold(&PRICES, "AXC"));
+}
+
+#[test]
+fn grouping_counts_each_product() {
+    assert_eq!(Counts::from([('A', 2), ('B', 1), ('X', 1)]), group_counts("ABXA"));
+    assert_eq!(Counts::new(), group_counts(""));
+}
+
+#[test]
+fn overflows_stop_the_fold() {
+    let prices = [('A', Cents(i64::MAX))];
+    assert_eq!(Err(Overflow), with_try_fold(&prices, "AA"));
+    assert_eq!(Cents(i64::MAX), with_fold(&prices, "AA"));
+    assert_eq!(Err(Overflow), with_groups(&prices, "AA"));
+    let rules = [FlatPrice::new('A', Cents(i64::MAX)).unwrap()];
+    assert_eq!(Err(Overflow), with_rules(&rules, "AA"));
+}
+
+proptest! {
+    #[test]
+    fn every_recipe_prices_like_the_loop(cart in "[ABCX]{0,50}") {
+        all_agree(&cart);
+        prop_assert_eq!(group_counts(&cart), count_items::<u64>(&cart));
+    }
+
+    #[test]
+    fn rules_price_like_the_supermarket(cart in "[ABC]{0,50}") {
+        let market: Supermarket = Supermarket::demo();
+        prop_assert_eq!(market.try_checkout(cart.clone()).ok(), with_rules(market.rules(), &cart).ok());
+    }
+}
@@ -0,0 +1,105 @@
+/// The hand-written iterators of `iterators_from_scratch`, driven by the
+/// standard adapters.
+use rust_for_java_devs::core::rules::BundlePrice;
+use rust_for_java_devs::iterators_from_scratch::*;
+use rust_for_java_devs::money::{Cents, OverflowPolicy};
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+use rust_for_java_devs::receipt::Receipt;
+use rust_for_java_devs::rules::PricingRule;
+
+fn receipt() -> Receipt {
+    let market: Supermarket = Supermarket::demo();
+    market.receipt("ABBACBBAB").unwrap()
+}
+
+#[test]
+fn receipts_iterate_over_their_lines() {
+    let receipt = receipt();
+    let mut products = Vec::new();
+    for line in &receipt {
+        products.extend(line.product);
+    }
+    assert_eq!(vec!('A', 'B', 'C'), products);
+}
+
+#[test]
+fn receipt_lines_work_with_adapters() {
+    let receipt = receipt();
+    let promoted: Vec<char> = receipt.iter()
+        .filter(|line| !line.promotions.is_empty())
+        .filter_map(|line| line.product)
+        .collect();
+    assert_eq!(vec!('B'), promoted);
+    let items: u64 = receipt.iter().map(|line| line.quantity).sum();
+    assert_eq!(9, items);
+    let dearest = receipt.iter().max_by_key(|line| line.amount).and_then(|line| line.product);
+    assert_eq!(Some('B'), dearest);
+}
+
+#[test]
+fn receipt_lines_run_from_either_end() {
+    let receipt = receipt();
+    let backwards: Vec<char> = receipt.iter().rev().filter_map(|line| line.product).collect();
+    assert_eq!(vec!('C', 'B', 'A'), backwards);
+
+    let mut lines = receipt.iter();
+    assert_eq!(3, lines.len());
+    assert_eq!(Some('A'), lines.next().and_then(|line| line.product));
+    assert_eq!(Some('C'), lines.next_back().and_then(|line| line.product));
+    assert_eq!(1, lines.len());
+    assert_eq!(Some('B'), lines.next().and_then(|line| line.product));
+    assert!(lines.next().is_none());
+    assert!(lines.next_back().is_none());
+}
+
+#[test]
+fn bundle_chunks_cover_every_item() {
+    let cases = [
+        // count, size, chunks
+        (0, 5, vec!()),
+        (4, 5, vec!(4)),
+        (5, 5, vec!(5)),
+        (12, 5, vec!(5, 5, 2)),
+        (3, 1, vec!(1, 1, 1)),
+        (7, 0, vec!(7)),
+    ];
+    for (count, size, chunks) in cases {
+        let found: Vec<u64> = BundleChunks::new(count, size).collect();
+        assert_eq!(chunks, found, "BundleChunks::new({}, {})", count, size);
+        assert_eq!(count, BundleChunks::new(count, size).sum::<u64>());
+    }
+}
+
+#[test]
+fn bundle_chunks_know_their_size() {
+    let chunks = BundleChunks::new(12, 5);
+    assert_eq!((3, Some(3)), chunks.size_hint());
+    assert_eq!(3, chunks.count());
+    assert_eq!(Some(2), BundleChunks::new(12, 5).last());
+    assert_eq!(vec!((0, 5), (1, 5), (2, 2)), BundleChunks::new(12, 5).enumerate().collect::<Vec<_>>());
+}
+
+/// A billion bundles are skipped without stepping through them, or this
+/// test would take seconds.
+#[test]
+fn bundle_chunks_skip_ahead() {
+    let mut chunks = BundleChunks::new(5_000_000_003, 5);
+    assert_eq!(Some(5), chunks.nth(999_999_999));
+    assert_eq!(Some(3), chunks.next());
+    assert_eq!(None, chunks.next());
+    let rest: Vec<u64> = BundleChunks::new(5_000_000_003, 5).skip(999_999_999).collect();
+    assert_eq!(vec!(5, 3), rest);
+    assert_eq!(None, BundleChunks::new(13, 5).nth(3));
+    assert_eq!(1_000_000_001, BundleChunks::new(5_000_000_003, 5).count());
+}
+
+#[test]
+fn chunk_prices_match_the_bundle_rule() {
+    let rule = BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap();
+    for count in 0..=23 {
+        let counts = [('B', count)].into_iter().collect();
+        assert_eq!(rule.price(&counts, OverflowPolicy::Checked),
+                   price_by_chunks(count, Cents(5000), 5, Cents(15000), OverflowPolicy::Checked),
+                   "{} items", count);
+    }
+}
@@ -1,16 +1,33 @@
-/// `extern` is necessary to specify an explicit dependency on another crate.
-/// In this case, the crate happens to be a locally-available one.
+/// Integration tests are compiled as a separate crate that depends on the
+/// library, much like a consumer of a published jar would.
+///
+/// Every crate listed in `Cargo.toml`, including the library under test, is
+/// available by name without further declaration, so `use` statements are
+/// all we need.
 ///
-/// One way to think of it would be a manner of specifying in-code what other
-/// jar/artifacts should be used for further namespace/module resolution.
-extern crate rust_for_java_devs;
-
 /// Import the public structure that is the entry point for the library
-use rust_for_java_devs::reasonable_implementation::{Market, Supermarket};
-use std::rand;
-use std::rand::Rng;
-use std::collections::hash_map::{HashMap, Entry};
-use std::num::ToPrimitive;
+use rust_for_java_devs::reasonable_implementation::{Market, Supermarket, BoxedRule,
+                                                     FlatPrice, BundlePrice, BestOfferPrice,
+                                                     Describable, LintWarning,
+                                                     ScanPolicy, CartLimits, CartDimension,
+                                                     CheckoutError, OfferNode, CheckoutObserver,
+                                                     PricingRule, CountStrategy, PromotionBudget,
+                                                     CustomerContext, PriceTier, QuantityLimitPolicy,
+                                                     SubstitutePrice, SpendThresholds, Voucher,
+                                                     RuleError, CartRule, PriceQueryError};
+use rust_for_java_devs::market::NegativeTotalPolicy;
+use proptest::strategy::{Just, Strategy};
+use rust_for_java_devs::money::{Cents, OverflowPolicy, Overflow};
+use rust_for_java_devs::reasonable_implementation::pipeline::{Stage, BoxedStage, Ticket,
+                                                               UnknownStage, Adjustment};
+use rust_for_java_devs::receipt::{self, ReceiptLine, Change};
+use rust_for_java_devs::core::counting::{Counts, InvalidItem};
+use rust_for_java_devs::conversions::Sku;
+use rust_for_java_devs::newtypes_and_units::Quantity;
+use rust_for_java_devs::assert_price;
+use rust_for_java_devs::const_catalog::{self, ConstRule};
+use rust_for_java_devs::test_support::{FakeMarket, FakeRule};
+use rust_for_java_devs::testkit::{seeded_rng, generate_char_sequence, generate_mixed_char_sequence};
 
 /// `static` variables are essentially static constants available to
 /// everything in the module.
@@ -22,152 +39,1199 @@ static MAX_ITEMS_STRING_SIZE:usize = 1000;
 /// should be used in the test suite.
 #[test]
 fn canonical_input() {
-    let s = Supermarket::new(); 
+    let s = Supermarket::demo(); 
     let items = "ABBACBBAB".to_string();
-    // The presence of `i32` after the number clarifies which size of int
-    // we are using here.
-    assert_eq!(240i32, s.checkout(items))
+    // Prices come back in cents by default, so $240 is 24000 cents.
+    assert_eq!(Ok(Cents(24000)), s.checkout(&items))
 }
 
 #[test]
 fn empty_input() {
-    let s = Supermarket::new();
+    let s = Supermarket::demo();
     let items = "".to_string();
-    assert_eq!(0i32, s.checkout(items))
+    assert_eq!(Ok(Cents(0)), s.checkout(&items))
 }
 
 #[test]
 fn ignores_unrelated_items() {
-    let s = Supermarket::new();
+    let s = Supermarket::demo();
     let items = "XKD".to_string();
-    assert_eq!(0i32, s.checkout(items))
+    assert_eq!(Ok(Cents(0)), s.checkout(&items))
 }
 
 #[test]
 fn mixes_standard_and_unregistered_items_prices() {
-    let s = Supermarket::new();
+    let s = Supermarket::demo();
     let items = "AXBC".to_string();
-    assert_eq!(100i32, s.checkout(items))
+    assert_eq!(Ok(Cents(10000)), s.checkout(&items))
 }
 
 #[test]
 fn single_bundle_gets_combo_price() {
-    let s = Supermarket::new();
+    let s = Supermarket::demo();
     let items = "BBBBB".to_string();
-    assert_eq!(150i32, s.checkout(items))
+    assert_eq!(Ok(Cents(15000)), s.checkout(&items))
 }
 
 #[test]
 fn single_bundle_with_leftovers_gives_deal_price_plus_individual() {
-    let s = Supermarket::new();
+    let s = Supermarket::demo();
     let items = "BBBBB B".to_string();
-    assert_eq!(200i32, s.checkout(items))
+    assert_eq!(Ok(Cents(20000)), s.checkout(&items))
 }
 
 #[test]
 fn multiple_bundles_each_get_deal_price() {
-    let s = Supermarket::new();
+    let s = Supermarket::demo();
     let items = "BBBBB BBBBB".to_string();
-    assert_eq!(300i32, s.checkout(items))
+    assert_eq!(Ok(Cents(30000)), s.checkout(&items))
 }
 
 #[test]
 fn multiple_bundles_each_get_deal_price_plus_leftovers() {
-    let s = Supermarket::new();
+    let s = Supermarket::demo();
     let items = "BBBBB BBBBB BB".to_string();
-    assert_eq!(400i32, s.checkout(items))
+    assert_eq!(Ok(Cents(40000)), s.checkout(&items))
+}
+
+#[test]
+fn new_supermarkets_start_with_an_empty_catalog() {
+    let s: Supermarket = Supermarket::new();
+    assert_price!(s, "ABBACBBAB" => 0)
+}
+
+#[test]
+fn supermarkets_can_be_built_from_a_list_of_rules() {
+    let rules: Vec<BoxedRule<Cents>> = vec!(
+        Box::new(FlatPrice::new('X', Cents(100)).unwrap()),
+        Box::new(BundlePrice::new('Y', Cents(300), 2, Cents(500)).unwrap())
+    );
+    let s = Supermarket::with_rules(rules);
+    assert_price!(s, "XYYY" => 9)
+}
+
+#[test]
+fn rules_can_be_collected_into_a_supermarket() {
+    let s: Supermarket = ['X', 'Y', 'Z'].iter()
+        .map(|&product| Box::new(FlatPrice::new(product, Cents(100)).unwrap()) as BoxedRule<Cents>)
+        .collect();
+    assert_price!(s, "XYZZA" => 4)
+}
+
+#[test]
+fn catalogs_can_be_extended() {
+    let mut s = Supermarket::demo();
+    s.extend(vec!(Box::new(FlatPrice::new('D', Cents(1500)).unwrap()) as BoxedRule<Cents>));
+    assert_price!(s, "AD" => 35)
+}
+
+#[test]
+fn demo_catalog_lints_clean() {
+    assert_eq!(Vec::<LintWarning>::new(), Supermarket::<Cents>::demo().lint_stock("ABC"))
+}
+
+#[test]
+fn lint_finds_gaps_and_conflicts() {
+    let mut s = Supermarket::demo();
+    s.extend(vec!(Box::new(FlatPrice::new('A', Cents(1900)).unwrap()) as BoxedRule<Cents>));
+    assert_eq!(vec!(LintWarning::Conflicting { product: 'A', rules: 2 },
+                    LintWarning::Unpriced { product: 'D' }),
+               s.lint_stock("ABCD"))
+}
+
+#[test]
+fn lint_finds_pathological_rules() {
+    let s = Supermarket::with_rules(vec!(
+        Box::new(FlatPrice::new('A', Cents(0)).unwrap()) as BoxedRule<Cents>,
+        Box::new(BundlePrice::new('B', Cents(5000), 5, Cents(30000)).unwrap())
+    ));
+    assert_eq!(vec!(LintWarning::NonPositivePrice { product: 'A' },
+                    LintWarning::BundleCostsMore { product: 'B' }),
+               s.lint())
+}
+
+#[test]
+fn strict_scanning_is_the_default() {
+    let s: Supermarket = Supermarket::demo();
+    assert_price!(s, "a, B b\nAC" => 100)
+}
+
+#[test]
+fn lenient_scanning_ignores_case_and_separators() {
+    let s = Supermarket::demo().with_scan_policy(ScanPolicy::LENIENT);
+    assert_eq!(Ok(Cents(24000)), s.try_checkout("a,b b a\nc bba b".to_string()));
+    assert_price!(s, "ABBACBBAB" => 240)
+}
+
+#[test]
+fn scan_policy_settings_are_independent() {
+    let case_only = ScanPolicy { ignore_case: true, skip_separators: false };
+    assert_eq!(Some(','), case_only.scan(','));
+    assert_eq!(Some('B'), case_only.scan('b'));
+    let separators_only = ScanPolicy { ignore_case: false, skip_separators: true };
+    assert_eq!(None, separators_only.scan('\t'));
+    assert_eq!(Some('b'), separators_only.scan('b'))
+}
+
+#[test]
+fn carts_beyond_the_byte_limit_are_refused() {
+    let s = Supermarket::<Cents>::demo().with_cart_limits(CartLimits { max_bytes: 8, max_items: 100 });
+    assert_eq!(Ok(Cents(29000)), s.try_checkout("ABBACBBA".to_string()));
+    assert_eq!(Err(CheckoutError::CartTooLarge { dimension: CartDimension::Bytes, limit: 8 }),
+               s.try_checkout("ABBACBBAB".to_string()))
+}
+
+#[test]
+fn item_limit_ignores_skipped_separators() {
+    let s = Supermarket::<Cents>::demo()
+        .with_scan_policy(ScanPolicy::LENIENT)
+        .with_cart_limits(CartLimits { max_bytes: 100, max_items: 3 });
+    assert_eq!(Ok(Cents(10000)), s.try_checkout("A, B, C".to_string()));
+    assert_eq!(Err(CheckoutError::CartTooLarge { dimension: CartDimension::Items, limit: 3 }),
+               s.try_checkout("A, B, C, C".to_string()))
+}
+
+#[test]
+fn checkout_reports_overflow_as_a_checkout_error() {
+    let s = Supermarket::with_rules(vec!(
+        Box::new(FlatPrice::new('A', Cents(i64::MAX)).unwrap()) as BoxedRule<Cents>
+    ));
+    assert_eq!(Err(CheckoutError::Overflow(Overflow)), s.try_checkout("AA".to_string()))
+}
+
+#[test]
+fn emoji_skus_are_priced_one_item_each() {
+    let s = Supermarket::with_rules(vec!(
+        Box::new(FlatPrice::new('\u{1f34e}', Cents(150)).unwrap()) as BoxedRule<Cents>,
+        Box::new(FlatPrice::new('A', Cents(2000)).unwrap())
+    ));
+    assert_eq!(Ok(Cents(2300)), s.try_checkout("\u{1f34e}A\u{1f34e}"));
+    assert_eq!(Some(2), s.receipt("\u{1f34e}\u{1f34e}").unwrap().lines.iter()
+                          .find(|line| line.product == Some('\u{1f34e}')).map(|line| line.quantity));
+}
+
+#[test]
+fn carts_with_multi_code_point_items_are_refused() {
+    let s: Supermarket = Supermarket::demo().with_age_restricted([Sku('C')]);
+    // A thumbs up with a medium skin tone would otherwise be two items.
+    let thumbs_up = CheckoutError::InvalidItem(InvalidItem { position: 1, item: "\u{1f44d}\u{1f3fd}".to_string() });
+    assert_eq!(Err(thumbs_up.clone()), s.try_checkout("A\u{1f44d}\u{1f3fd}B"));
+    assert_eq!(Err(thumbs_up.clone()), s.receipt("A\u{1f44d}\u{1f3fd}B").map(|receipt| receipt.total));
+    assert_eq!(Err(thumbs_up), s.plan("A\u{1f44d}\u{1f3fd}B").map(|_| ()));
+    // An `e` and a combining acute accent, refused before the C is noticed.
+    let accented = s.checkout_strict("Ce\u{301}", &CustomerContext::default());
+    assert_eq!(Err(CheckoutError::InvalidItem(InvalidItem { position: 1, item: "e\u{301}".to_string() })),
+               accented);
+    let error = accented.unwrap_err();
+    assert_eq!("\"e\\u{301}\" at byte 1 is not a single item code", error.to_string());
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn several_offers_on_one_product_give_the_cheapest_combination() {
+    let b = BestOfferPrice::new('B', Cents(5000)).unwrap()
+        .with_offer(3, Cents(13000)).unwrap()
+        .with_offer(10, Cents(40000)).unwrap();
+    let s = Supermarket::with_rules(vec!(Box::new(b) as BoxedRule<Cents>));
+    assert_price!(s, "BBBBBB" => 260);
+    assert_price!(s, "BBBBBBBBBBBBB" => 530);
+    assert!(s.lint().is_empty())
+}
+
+#[test]
+fn plans_break_the_total_down_by_rule_and_offer() {
+    let plan = Supermarket::<Cents>::demo().plan("ABBACBBAB").unwrap();
+    assert_eq!(Cents(24000), plan.total);
+    let amounts: Vec<Cents> = plan.rules.iter().map(|rule| rule.amount).collect();
+    assert_eq!(vec!(Cents(6000), Cents(15000), Cents(3000)), amounts);
+    assert_eq!("B: $50.00 each or 5 for $150.00", plan.rules[1].description);
+    assert_eq!(vec!(OfferNode { product: 'B', lot_size: 5, lots: 1,
+                                lot_cost: Cents(15000), amount: Cents(15000) }),
+               plan.rules[1].offers)
+}
+
+#[test]
+fn plans_show_which_offers_were_chosen() {
+    let b = BestOfferPrice::new('B', Cents(5000)).unwrap()
+        .with_offer(3, Cents(13000)).unwrap()
+        .with_offer(10, Cents(40000)).unwrap();
+    let s = Supermarket::with_rules(vec!(Box::new(b) as BoxedRule<Cents>));
+    let plan = s.plan("BBBBBBBBBBBBB").unwrap();
+    let offers: Vec<(u64, u64)> = plan.rules[0].offers.iter()
+        .map(|offer| (offer.lot_size, offer.lots))
+        .collect();
+    assert_eq!(vec!((3, 1), (10, 1)), offers);
+    assert_eq!(Cents(53000), plan.total)
+}
+
+#[test]
+fn plan_totals_always_match_checkout() {
+    let s: Supermarket = Supermarket::demo();
+    let mut rng = seeded_rng(1);
+    for _i in 0..NUM_TEST_ITERATIONS {
+        let (items, _) = generate_mixed_char_sequence(&mut rng, &['A', 'B', 'C', 'X'],
+                                                      0..MAX_ITEMS_STRING_SIZE);
+        let plan = s.plan(&items).unwrap();
+        let items_planned: u64 = plan.rules.iter()
+            .flat_map(|rule| rule.offers.iter())
+            .map(|offer| offer.items())
+            .sum();
+        assert_eq!(items.chars().filter(|&c| c != 'X').count() as u64, items_planned);
+        assert_eq!(s.checkout(&items), Ok(plan.total));
+    }
+}
+
+/// Records every callback as a line of text. The `Mutex` makes the log safe
+/// to append to through the `&self` each callback receives.
+#[derive(Default)]
+struct Recorder {
+    log: std::sync::Mutex<Vec<String>>
+}
+
+impl CheckoutObserver<Cents> for Recorder {
+    fn on_item_counted(&self, item: char, count: u64) {
+        self.log.lock().unwrap().push(format!("{} x{}", item, count));
+    }
+
+    fn on_rule_applied(&self, rule: &dyn PricingRule<Cents>, amount: Cents) {
+        self.log.lock().unwrap().push(format!("{} -> {}", rule.describe(), amount));
+    }
+
+    fn on_total(&self, total: Cents) {
+        self.log.lock().unwrap().push(format!("total {}", total));
+    }
+}
+
+#[test]
+fn observers_follow_every_step_of_checkout() {
+    use std::sync::Arc;
+
+    let recorder = Arc::new(Recorder::default());
+    let s = Supermarket::demo().with_observer(Arc::clone(&recorder));
+    assert_price!(s, "AXBC" => 100);
+    assert_eq!(vec!("A x1", "B x1", "C x1", "X x1",
+                    "A: $20.00 each -> $20.00",
+                    "B: $50.00 each or 5 for $150.00 -> $50.00",
+                    "C: $30.00 each -> $30.00",
+                    "total $100.00"),
+               *recorder.log.lock().unwrap());
+}
+
+/// Only counts totals, relying on the default, empty bodies of the other
+/// callbacks.
+#[derive(Default)]
+struct TotalCounter {
+    totals: std::sync::atomic::AtomicU64
+}
+
+impl CheckoutObserver<Cents> for TotalCounter {
+    fn on_total(&self, _total: Cents) {
+        self.totals.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn observers_need_only_implement_the_callbacks_they_use() {
+    use std::sync::Arc;
+
+    let counter = Arc::new(TotalCounter::default());
+    let s = Supermarket::demo().with_observer(Arc::clone(&counter));
+    s.checkout("AB").unwrap();
+    s.checkout("C").unwrap();
+    assert_eq!(2, counter.totals.load(std::sync::atomic::Ordering::Relaxed))
+}
+
+#[test]
+fn the_standard_pipeline_has_six_stages() {
+    let mut s: Supermarket = Supermarket::demo();
+    assert_eq!(vec!("normalize", "count", "promotions", "cart rules", "tax", "rounding"),
+               s.pipeline_mut().names())
+}
+
+/// Ten percent off everything, for staff.
+struct EmployeeDiscount;
+
+impl Stage<Cents> for EmployeeDiscount {
+    fn name(&self) -> &str {
+        "employee discount"
+    }
+
+    fn run(&self, _market: &Supermarket, ticket: &mut Ticket<Cents>) -> Result<(), CheckoutError> {
+        let Cents(total) = ticket.total;
+        Ok(ticket.adjust(self.name(), Cents(-total / 10))?)
+    }
+}
+
+/// A flat 8% sales tax, rounded down to the cent.
+struct SalesTax;
+
+impl Stage<Cents> for SalesTax {
+    fn name(&self) -> &str {
+        "tax"
+    }
+
+    fn run(&self, _market: &Supermarket, ticket: &mut Ticket<Cents>) -> Result<(), CheckoutError> {
+        let Cents(total) = ticket.total;
+        Ok(ticket.adjust("sales tax", Cents(total * 8 / 100))?)
+    }
+}
+
+#[test]
+fn custom_stages_can_be_inserted_and_replaced() {
+    let mut s = Supermarket::demo();
+    s.pipeline_mut().insert_after("promotions", EmployeeDiscount).unwrap();
+    s.pipeline_mut().replace("tax", SalesTax).unwrap();
+    assert_eq!(vec!("normalize", "count", "promotions", "employee discount", "cart rules", "tax", "rounding"),
+               s.pipeline_mut().names());
+    // $240.00, less $24.00, plus 8% of $216.00.
+    assert_price!(s, "ABBACBBAB" => 233.28)
+}
+
+/// Charges a $1.00 fee for every stage it wraps, after the stage has run.
+struct Surcharge(BoxedStage<Cents>);
+
+impl Stage<Cents> for Surcharge {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn run(&self, market: &Supermarket, ticket: &mut Ticket<Cents>) -> Result<(), CheckoutError> {
+        self.0.run(market, ticket)?;
+        Ok(ticket.adjust("surcharge", Cents(100))?)
+    }
+}
+
+#[test]
+fn stages_can_be_wrapped() {
+    let mut s = Supermarket::demo();
+    s.pipeline_mut().wrap("promotions", |inner| Box::new(Surcharge(inner))).unwrap();
+    assert_price!(s, "ABBACBBAB" => 241)
+}
+
+#[test]
+fn promotions_beyond_the_budget_are_charged_back() {
+    let s: Supermarket = Supermarket::demo().with_promotion_budget(PromotionBudget::Absolute(Cents(6000)));
+    assert_price!(s, {
+        "BBBBB" => 190,
+        "BBBBBBBBBB" => 440,
+        "ABC" => 100,
+    });
+    assert!(s.receipt("ABC").unwrap().adjustments.is_empty());
+    let unlimited: Supermarket = Supermarket::demo().with_promotion_budget(PromotionBudget::Unlimited);
+    assert_price!(unlimited, "BBBBB" => 150);
+}
+
+#[test]
+fn the_rules_applied_last_are_trimmed_first() {
+    // Five Bs and three Ds list at $400.00 and sell for $270.00. A budget of
+    // 20% of the list price allows $80.00 of the $130.00 given away, so D's
+    // whole $30.00 is trimmed, then $20.00 of B's.
+    let s = Supermarket::builder()
+        .rule(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap())
+        .rule(BundlePrice::new('D', Cents(5000), 3, Cents(12000)).unwrap())
+        .promotion_budget(PromotionBudget::Percent(20))
+        .build();
+    let receipt = s.receipt("BBBBBDDD").unwrap();
+    let trims: Vec<String> = receipt.adjustments.iter().map(|a| a.to_string()).collect();
+    assert_eq!(vec!("promotion budget: B: $50.00 each or 5 for $150.00  $20.00",
+                    "promotion budget: D: $50.00 each or 3 for $120.00  $30.00"), trims);
+    assert_eq!((Cents(27000), Cents(32000)), (receipt.subtotal, receipt.total));
+    assert_eq!(PromotionBudget::Percent(20), s.promotion_budget());
+    assert_eq!(Some(Cents(99)), PromotionBudget::Percent(10).cap(Cents(999)));
+}
+
+#[test]
+fn receipts_itemize_the_cart_by_product() {
+    let mut s = Supermarket::demo();
+    s.pipeline_mut().replace("tax", SalesTax).unwrap();
+    let receipt = s.receipt("ABBACBBABX").unwrap();
+    let line = |product, rule: &str, quantity, cents, promotions: &[&str]| ReceiptLine {
+        product: Some(product),
+        rule: rule.to_string(),
+        quantity,
+        amount: Cents(cents),
+        promotions: promotions.iter().map(|p| p.to_string()).collect()
+    };
+    assert_eq!(vec!(line('A', "A: $20.00 each", 3, 6000, &[]),
+                    line('B', "B: $50.00 each or 5 for $150.00", 5, 15000, &["5 for $150.00"]),
+                    line('C', "C: $30.00 each", 1, 3000, &[])),
+               receipt.lines);
+    assert_eq!(vec!(Adjustment { label: "sales tax".to_string(), amount: Cents(1920) }),
+               receipt.adjustments);
+    assert_eq!((Cents(24000), Cents(25920)), (receipt.subtotal, receipt.total));
+}
+
+#[test]
+fn receipts_list_each_offer_a_product_was_bought_under() {
+    let b = BestOfferPrice::new('B', Cents(5000)).unwrap()
+        .with_offer(3, Cents(13000)).unwrap()
+        .with_offer(10, Cents(40000)).unwrap();
+    let s = Supermarket::with_rules(vec!(Box::new(b) as BoxedRule<Cents>));
+    let receipt = s.receipt("BBBBBBBBBBBBBB").unwrap();
+    assert_eq!(1, receipt.lines.len());
+    assert_eq!((14, Cents(58000)), (receipt.lines[0].quantity, receipt.lines[0].amount));
+    assert_eq!(vec!("3 for $130.00", "10 for $400.00"), receipt.lines[0].promotions);
+    assert_eq!(Cents(58000), receipt.total);
+}
+
+#[test]
+fn receipt_diffs_pair_lines_by_product() {
+    let before = Supermarket::demo().receipt("ABBBBBBC").unwrap();
+    let mut s = Supermarket::with_rules(vec!(
+        Box::new(FlatPrice::new('B', Cents(4000)).unwrap()) as BoxedRule<Cents>,
+        Box::new(FlatPrice::new('D', Cents(100)).unwrap())
+    ));
+    s.pipeline_mut().replace("tax", SalesTax).unwrap();
+    let after = s.receipt("ABBBBBBCD").unwrap();
+    let changes = receipt::diff(&before, &after);
+
+    let deltas: Vec<(Option<char>, Option<Cents>)> = changes.lines.iter()
+        .map(|change| (change.after().or(change.before()).unwrap().product, change.delta()))
+        .collect();
+    assert_eq!(vec!((Some('A'), Some(Cents(-2000))), (Some('B'), Some(Cents(4000))),
+                    (Some('C'), Some(Cents(-3000))), (Some('D'), Some(Cents(100)))),
+               deltas);
+    assert!(matches!(changes.lines[0], Change::Removed(_)));
+    assert_eq!(vec!("5 for $150.00"), changes.lines[1].promotions_lost());
+    assert!(changes.lines[1].promotions_gained().is_empty());
+    assert!(matches!(changes.lines[3], Change::Added(_)));
+    assert_eq!(Some(Cents(1928)), changes.adjustments[0].delta());
+    assert_eq!(Some(Cents(1028)), changes.total_delta());
+}
+
+#[test]
+fn identical_receipts_have_no_diff() {
+    let s: Supermarket = Supermarket::demo();
+    let changes = receipt::diff(&s.receipt("ABBACBBAB").unwrap(), &s.receipt("BACBBBABA").unwrap());
+    assert!(changes.is_empty());
+    assert_eq!("total: $240.00 -> $240.00 ($0.00)\n", changes.to_string());
+}
+
+#[test]
+fn one_catalog_serves_every_price_tier() {
+    let s: Supermarket = Supermarket::demo()
+        .with_tier_rules(PriceTier::Member, vec![Box::new(FlatPrice::new('B', Cents(4000)).unwrap())])
+        .with_tier_rules(PriceTier::Employee, vec![
+            Box::new(BundlePrice::new('B', Cents(4000), 5, Cents(10000)).unwrap()),
+            Box::new(FlatPrice::new('C', Cents(2500)).unwrap()),
+        ]);
+    let at = |tier| s.checkout_as("ABBACBBAB", &CustomerContext::new(tier));
+    assert_eq!(Ok(Cents(24000)), at(PriceTier::Retail));
+    assert_eq!(Ok(Cents(29000)), at(PriceTier::Member));
+    assert_eq!(Ok(Cents(18500)), at(PriceTier::Employee));
+    // A tier without rules of its own pays retail prices, and so does
+    // anybody checking out through the `Market` trait.
+    assert_eq!(Ok(Cents(24000)), at(PriceTier::Wholesale));
+    assert_eq!(Ok(Cents(24000)), s.checkout("ABBACBBAB"));
+    assert_eq!(vec![PriceTier::Member, PriceTier::Employee], s.tiers().collect::<Vec<_>>());
+
+    let rules: Vec<String> = s.rules_for(PriceTier::Employee).map(|rule| rule.describe()).collect();
+    assert_eq!(vec!["A: $20.00 each", "B: $40.00 each or 5 for $100.00", "C: $25.00 each"], rules);
+    let receipt = s.receipt_as("BBBBB", &CustomerContext::new(PriceTier::Employee)).unwrap();
+    assert_eq!("B: $40.00 each or 5 for $100.00", receipt.lines[0].rule);
+    assert_eq!(Cents(10000), receipt.total);
+}
+
+#[test]
+fn builders_take_tier_rules() {
+    let s: Supermarket = Supermarket::builder()
+        .rule(FlatPrice::new('A', Cents(2000)).unwrap())
+        .tier_rule(PriceTier::Wholesale, FlatPrice::new('A', Cents(1200)).unwrap())
+        .build();
+    assert_eq!(Ok(Cents(12000)), s.checkout_as("AAAAAAAAAA", &CustomerContext::new(PriceTier::Wholesale)));
+    assert_eq!(Ok(Cents(20000)), s.checkout_as("AAAAAAAAAA", &CustomerContext::default()));
+    assert_eq!("wholesale", PriceTier::Wholesale.to_string());
+}
+
+#[test]
+fn strict_checkouts_refuse_restricted_products_without_verification() {
+    let s: Supermarket = Supermarket::demo()
+        .with_scan_policy(ScanPolicy::LENIENT)
+        .with_age_restricted([Sku('C'), Sku('A')]);
+    let shopper = CustomerContext::new(PriceTier::Member);
+    assert_eq!(Err(CheckoutError::AgeVerificationRequired(vec![Sku('A'), Sku('C')])),
+               s.checkout_strict("c b a c", &shopper));
+    assert_eq!("verify the customer's age to sell A C",
+               s.checkout_strict("CA", &shopper).unwrap_err().to_string());
+    assert_eq!(Ok(Cents(5000)), s.checkout_strict("B", &shopper));
+    assert_eq!(Ok(Cents(13000)), s.checkout_strict("cbac", &shopper.with_verified_age()));
+    // Checking out through the `Market` trait does not check.
+    assert_eq!(Ok(Cents(3000)), s.checkout("C"));
+    assert!(s.is_age_restricted(Sku('A')) && !s.is_age_restricted(Sku('B')));
+}
+
+#[test]
+fn carts_over_a_quantity_limit_are_refused_by_default() {
+    let s: Supermarket = Supermarket::demo().with_quantity_limit(Sku('B'), 2);
+    assert_eq!(Err(CheckoutError::QuantityLimitExceeded { product: Sku('B'), limit: 2, quantity: 5 }),
+               s.checkout("ABBACBBAB"));
+    assert_eq!("cart holds 5 of B, which is limited to 2 per cart",
+               s.checkout("BBBBB").unwrap_err().to_string());
+    assert_price!(s, "ABBAC" => 170);
+    assert!(s.plan("BBB").is_err());
+    assert_eq!(Some(2), s.quantity_limit(Sku('B')));
+    assert_eq!(None, s.quantity_limit(Sku('A')));
+}
+
+#[test]
+fn carts_over_a_quantity_limit_may_be_trimmed_instead() {
+    let s: Supermarket = Supermarket::builder()
+        .rule(FlatPrice::new('A', Cents(1000)).unwrap())
+        .rule(FlatPrice::new('B', Cents(1000)).unwrap())
+        .rule(FlatPrice::new('C', Cents(1000)).unwrap())
+        .quantity_limit(Sku('B'), 2)
+        .quantity_limit(Sku('C'), 0)
+        .quantity_limit_policy(QuantityLimitPolicy::Trim)
+        .build();
+    let receipt = s.receipt("ABBACBBAB").unwrap();
+    assert_eq!(Cents(5000), receipt.total);
+    let notes: Vec<String> = receipt.adjustments.iter().map(|a| a.to_string()).collect();
+    assert_eq!(vec!("B: limited to 2 per cart, 3 left out  $0.00",
+                    "C: limited to 0 per cart, 1 left out  $0.00"), notes);
+    assert_eq!("AAABB", receipt.items());
+    // Asked the price of seven, the market will not answer with that of two.
+    let refused = CheckoutError::QuantityLimitExceeded { product: Sku('B'), limit: 2, quantity: 7 };
+    assert_eq!(Err(PriceQueryError::Checkout(refused)), s.price_of(Sku('B'), Quantity(7)));
+    assert_eq!(Ok(Cents(2000)), s.price_of(Sku('B'), Quantity(2)));
+    let table = s.unit_price_table(3);
+    assert!(table.rows[1].prices[2].is_err());
+    assert_eq!(None, table.rows[1].first_saving());
+}
+
+#[test]
+fn competitor_prices_are_matched_when_lower() {
+    let s: Supermarket = Supermarket::demo();
+    let shopper = CustomerContext::new(PriceTier::Retail)
+        .with_competitor_price(Sku('A'), Cents(1500))
+        .with_competitor_price(Sku('B'), Cents(4500))
+        .with_competitor_price(Sku('D'), Cents(1));
+    let receipt = s.receipt_as("ABBACBBAB", &shopper).unwrap();
+    // Five B at $150.00 beat $45.00 each; three A at $15.00 save $15.00.
+    assert_eq!(Cents(22500), receipt.total);
+    let notes: Vec<String> = receipt.adjustments.iter().map(|a| a.to_string()).collect();
+    assert_eq!(vec!("price match: A at $15.00 each  -$15.00"), notes);
+    assert_eq!(Ok(Cents(13500)), s.checkout_as("BBB", &shopper));
+    assert_eq!(Ok(Cents(24000)), s.checkout_as("ABBACBBAB", &CustomerContext::default()));
+}
+
+#[test]
+fn substitutes_sell_at_the_price_of_what_they_replace() {
+    let b = BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap();
+    let s: Supermarket = Supermarket::builder()
+        .rule(b.clone())
+        .rule(SubstitutePrice::new('D', 'B', b).unwrap())
+        .build();
+    assert_price!(s, "BBBBBDD" => 250);
+    assert_price!(s, "DDDDD" => 150);
+}
+
+#[test]
+fn spend_thresholds_take_the_highest_percentage_passed() {
+    let thresholds = SpendThresholds::new([(Cents(20000), 10), (Cents(10000), 5)]).unwrap();
+    assert_eq!("5% off over $100.00, 10% off over $200.00", thresholds.describe());
+    let s: Supermarket = Supermarket::demo().with_cart_rule(thresholds);
+    // Over, not at: exactly $100.00 gets nothing.
+    assert_price!(s, "BB" => 100);
+    assert_price!(s, "BBA" => 114);
+    assert_price!(s, "BBBBBBBBBBA" => 288);
+    let receipt = s.receipt("ABBACBBAB").unwrap();
+    assert_eq!(Cents(21600), receipt.total);
+    assert_eq!("10% off over $200.00  -$24.00", receipt.adjustments[0].to_string());
+    assert_eq!(1, s.cart_rules().count());
+}
+
+#[test]
+fn spend_thresholds_apply_before_tax() {
+    let mut s: Supermarket = Supermarket::demo()
+        .with_cart_rule(SpendThresholds::new([(Cents(10000), 5)]).unwrap());
+    s.pipeline_mut().replace("tax", SalesTax).unwrap();
+    // $240.00, less 5%, plus 8% of $228.00.
+    assert_price!(s, "ABBACBBAB" => 246.24);
+}
+
+#[test]
+fn spend_thresholds_refuse_impossible_steps() {
+    assert_eq!(Some(RuleError::PercentOutOfRange), SpendThresholds::new([(Cents(10000), 101)]).err());
+    assert_eq!(Some(RuleError::NegativeCost), SpendThresholds::new([(Cents(-1), 5)]).err());
+}
+
+#[test]
+fn vouchers_take_a_fixed_amount_off_a_spend_over_their_minimum() {
+    let s: Supermarket = Supermarket::demo().with_cart_rule(Voucher::new(Cents(1000), Cents(5000)).unwrap());
+    assert_price!(s, "B" => 50);
+    assert_price!(s, "BA" => 60);
+    let receipt = s.receipt("ABBACBBAB").unwrap();
+    assert_eq!(Cents(23000), receipt.total);
+    assert_eq!(Cents(-1000), receipt.adjustments[0].amount);
+    assert_eq!("voucher: $10.00 off over $50.00", receipt.adjustments[0].label);
+}
+
+#[test]
+fn vouchers_never_take_off_more_than_the_total() {
+    // A voucher worth more than any cart it can apply to, stacked after a
+    // discount, leaves the cart free rather than owing the customer.
+    let s: Supermarket = Supermarket::builder()
+        .rule(FlatPrice::new('A', Cents(1000)).unwrap())
+        .cart_rule(Voucher::new(Cents(5000), Cents(0)).unwrap())
+        .cart_rule(Voucher::new(Cents(1000), Cents(0)).unwrap())
+        .build();
+    let receipt = s.receipt("AA").unwrap();
+    assert_eq!(Cents(0), receipt.total);
+    let amounts: Vec<Cents> = receipt.adjustments.iter().map(|a| a.amount).collect();
+    assert_eq!(vec!(Cents(-2000)), amounts);
+    assert_eq!(Some(RuleError::NegativeCost), Voucher::new(Cents(-1), Cents(5000)).err());
+}
+
+#[test]
+fn receipts_give_back_their_carts() {
+    let s: Supermarket = Supermarket::demo();
+    assert_eq!("AAABBBBBC", s.receipt("ABBACBBAB").unwrap().items());
+    assert_eq!("", s.receipt("").unwrap().items());
+}
+
+#[test]
+fn repricing_flags_the_receipts_whose_totals_change() {
+    let before: Supermarket = Supermarket::demo();
+    let stored: Vec<_> = ["ABBACBBAB", "AC", "BBB", ""].iter()
+        .map(|cart| before.receipt(cart).unwrap())
+        .collect();
+    let after: Supermarket = Supermarket::with_rules(vec![
+        Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+        Box::new(BundlePrice::new('B', Cents(5000), 3, Cents(12000)).unwrap()),
+        Box::new(FlatPrice::new('C', Cents(3000)).unwrap()),
+    ]);
+    let results = receipt::reprice(&stored, &after);
+    let changed: Vec<(&str, Option<Cents>)> = results.iter()
+        .filter(|result| result.changed())
+        .map(|result| (result.items.as_str(), result.delta()))
+        .collect();
+    assert_eq!(vec![("AAABBBBBC", Some(Cents(7000))), ("BBB", Some(Cents(-3000)))], changed);
+    assert_eq!(Cents(12000), results[2].after.as_ref().unwrap().total);
+
+    let strict = before.with_cart_limits(CartLimits { max_items: 3, ..CartLimits::default() });
+    let results = receipt::reprice(&stored, &strict);
+    assert!(results[0].changed());
+    assert_eq!(None, results[0].delta());
+    assert!(results[0].after.is_err());
+    assert!(!results[1].changed());
+}
+
+#[test]
+fn unknown_stages_are_reported() {
+    let mut s: Supermarket = Supermarket::demo();
+    assert_eq!(Err(UnknownStage("shipping".to_string())),
+               s.pipeline_mut().insert_before("shipping", SalesTax))
+}
+
+#[test]
+fn every_overflow_policy_agrees_on_ordinary_carts() {
+    let policies = [OverflowPolicy::Checked,
+                    OverflowPolicy::Saturating,
+                    OverflowPolicy::Wrapping];
+    for &policy in policies.iter() {
+        let s = Supermarket::demo().with_overflow_policy(policy);
+        let items = "ABBACBBAB".to_string();
+        assert_eq!(Ok(Cents(24000)), s.try_checkout(items))
+    }
+}
+
+#[test]
+fn checked_policy_reports_overflow() {
+    let huge = Cents(i64::MAX);
+    assert_eq!(Err(Overflow), OverflowPolicy::Checked.plus(huge, Cents(1)));
+    assert_eq!(Err(Overflow), OverflowPolicy::Checked.times(huge, 2))
+}
+
+#[test]
+fn saturating_policy_clamps_at_the_limits() {
+    let huge = Cents(i64::MAX);
+    assert_eq!(Ok(huge), OverflowPolicy::Saturating.plus(huge, Cents(1)));
+    assert_eq!(Ok(huge), OverflowPolicy::Saturating.times(huge, 2));
+    assert_eq!(Ok(Cents(i64::MIN)),
+               OverflowPolicy::Saturating.times(Cents(-1), u64::MAX))
+}
+
+#[test]
+fn wrapping_policy_wraps_like_java_ints() {
+    let huge = Cents(i64::MAX);
+    assert_eq!(Ok(Cents(i64::MIN)), OverflowPolicy::Wrapping.plus(huge, Cents(1)))
 }
 
-fn generate_char_sequence(c:char) -> (String, i32) {
-    let mut rng = rand::thread_rng();
-    let n = rng.gen_range(1, MAX_ITEMS_STRING_SIZE);
-    let mut seq = "".to_string();
-    // The underscore prefix is a hint that we do not intend to actually use
-    // the value stored in this variable.
-    for _i in range(0, n) {
-        seq.push(c);
+#[test]
+fn default_overflow_policy_is_checked() {
+    assert_eq!(OverflowPolicy::Checked, Default::default())
+}
+
+#[test]
+fn supermarket_can_be_shared_between_threads() {
+    use std::sync::Arc;
+    use std::thread;
+
+    // `Arc` is an atomically reference-counted pointer, letting several
+    // threads own the same Supermarket. This only compiles because every
+    // rule inside it is `Send + Sync`.
+    let s: Arc<Supermarket> = Arc::new(Supermarket::demo());
+    let handles: Vec<_> = (0..4).map(|_| {
+        let s = Arc::clone(&s);
+        thread::spawn(move || s.checkout("ABBACBBAB"))
+    }).collect();
+    for handle in handles {
+        assert_eq!(Ok(Cents(24000)), handle.join().unwrap());
     }
-    (seq, n.to_i32().unwrap())
 }
 
 #[test]
 fn correctly_sums_sequences_of_many_sizes_of_as() {
-    let s = Supermarket::new();
-    for _i in range(1, NUM_TEST_ITERATIONS) {
-        let (items, len) = generate_char_sequence('A');
-        assert_eq!(len * 20, s.checkout(items)); 
+    let s = Supermarket::demo();
+    let mut rng = seeded_rng(2);
+    for _i in 1..NUM_TEST_ITERATIONS {
+        let (items, len) = generate_char_sequence(&mut rng, 'A', 1..MAX_ITEMS_STRING_SIZE);
+        assert_eq!(Ok(Cents(len as i64 * 2000)), s.checkout(&items)); 
     }
 }
 
 #[test]
 fn correctly_sums_sequences_of_many_sizes_of_bs() {
-    let s = Supermarket::new();
-    for _i in range(1, NUM_TEST_ITERATIONS) {
-        let (items, len) = generate_char_sequence('B');
-        assert_eq!(((len / 5) * 150) + ((len % 5) * 50), s.checkout(items)); 
+    let s = Supermarket::demo();
+    let mut rng = seeded_rng(3);
+    for _i in 1..NUM_TEST_ITERATIONS {
+        let (items, len) = generate_char_sequence(&mut rng, 'B', 1..MAX_ITEMS_STRING_SIZE);
+        assert_eq!(Ok(Cents((((len / 5) * 15000) + ((len % 5) * 5000)) as i64)),
+                   s.checkout(&items)); 
     }
 }
 
 #[test]
 fn correctly_sums_sequences_of_many_sizes_of_cs() {
-    let s = Supermarket::new();
-    for _i in range(1, NUM_TEST_ITERATIONS) {
-        let (items, len) = generate_char_sequence('C');
-        assert_eq!(len * 30, s.checkout(items)); 
-    }
-}
-
-
-fn generate_mixed_char_sequence(chars:&[char]) -> (String, HashMap<char, i32>) {
-    let mut rng = rand::thread_rng();
-    let n:usize = rng.gen_range(0, MAX_ITEMS_STRING_SIZE);
-    let mut count: HashMap<char, i32> = HashMap::new();
-    let mut s = "".to_string();
-    for _i in range(0, n) {
-        match rng.choose(chars) {
-            Some(&c) => {
-                s.push(c);
-                match count.entry(c) {
-                    Entry::Vacant(slot) => {slot.insert(1); },
-                    Entry::Occupied(mut slot) => {*slot.get_mut() += 1;}
-                }
+    let s = Supermarket::demo();
+    let mut rng = seeded_rng(4);
+    for _i in 1..NUM_TEST_ITERATIONS {
+        let (items, len) = generate_char_sequence(&mut rng, 'C', 1..MAX_ITEMS_STRING_SIZE);
+        assert_eq!(Ok(Cents(len as i64 * 3000)), s.checkout(&items)); 
+    }
+}
+
+
+#[test]
+fn testkit_carts_are_the_same_for_the_same_seed() {
+    let cart = |seed| generate_mixed_char_sequence(&mut seeded_rng(seed), &['A', 'B'], 1..100);
+    assert_eq!(cart(7), cart(7));
+    let (items, counts) = cart(7);
+    assert_eq!(items.len() as u64, counts.values().sum::<u64>())
+}
+
+/// `should_panic` passes only if the test panics, and `expected` checks
+/// the panic message, like JUnit's `assertThrows`.
+#[test]
+#[should_panic(expected = "pricing the cart \"BBBBB\"")]
+fn assert_price_tables_report_the_failing_cart() {
+    let s: Supermarket = Supermarket::demo();
+    assert_price!(s, {
+        "A" => 20,
+        "BBBBB" => 250,
+    });
+}
+
+/// A stand-in for application code built on the crate: it only needs some
+/// `Market`, so a `FakeMarket` can take the place of a real one.
+fn receipt_line(market: &impl Market, cart: &str) -> String {
+    format!("{} items: {}", cart.len(), market.checkout(cart).unwrap())
+}
+
+#[test]
+fn fake_markets_answer_with_scripted_totals() {
+    let market = FakeMarket::returning(Cents(100)).with_total("AB", Cents(250));
+    assert_eq!("2 items: $2.50", receipt_line(&market, "AB"));
+    assert_eq!("1 items: $1.00", receipt_line(&market, "C"));
+    assert_eq!(vec!["AB".to_string(), "C".to_string()], market.carts())
+}
+
+#[test]
+fn markets_may_be_injected_as_trait_objects() {
+    use std::sync::Arc;
+    use rust_for_java_devs::enum_implementation;
+
+    let markets: Vec<Box<dyn Market>> = vec!(
+        Box::new(Supermarket::demo()),
+        Box::new(enum_implementation::Supermarket::demo()),
+        Box::new(FakeMarket::returning(Cents(24000))),
+    );
+    for market in &markets {
+        assert_eq!(Ok(Cents(24000)), market.checkout("ABBACBBAB"));
+        // `&Box<dyn Market>` is a `Market` too, through the blanket impls.
+        assert_eq!("9 items: $240.00", receipt_line(&market, "ABBACBBAB"));
+    }
+    let shared: Arc<dyn Market + Send + Sync> = Arc::new(Supermarket::demo());
+    assert_eq!("1 items: $20.00", receipt_line(&shared, "A"));
+    let overflowing: Box<dyn Market> = Box::new(Supermarket::with_rules(vec!(
+        Box::new(FlatPrice::new('A', Cents(i64::MAX)).unwrap()) as BoxedRule<Cents>)));
+    assert_eq!(Err(CheckoutError::Overflow(Overflow)), overflowing.checkout("AA"));
+}
+
+#[test]
+fn closures_stand_in_for_markets() {
+    use rust_for_java_devs::market::FnMarket;
+
+    let flat = FnMarket(|items: &str| Cents(250 * items.len() as i64));
+    assert_eq!("2 items: $5.00", receipt_line(&flat, "AB"));
+    // Closures capture their surroundings, so a stub may share a real
+    // market, or record what it was asked.
+    let real: Supermarket = Supermarket::demo();
+    let asked = std::cell::RefCell::new(Vec::new());
+    let spy = FnMarket(|items: &str| {
+        asked.borrow_mut().push(items.to_string());
+        real.try_checkout(items).unwrap_or(Cents(0))
+    });
+    let markets: [&dyn Market; 2] = [&flat, &spy];
+    assert_eq!(Ok(Cents(24000)), markets[1].checkout("ABBACBBAB"));
+    assert_eq!(vec!["ABBACBBAB".to_string()], *asked.borrow());
+}
+
+#[test]
+fn fake_rules_stand_in_for_real_ones() {
+    let s = Supermarket::with_rules(vec!(
+        Box::new(FakeRule::charging(Cents(500)).for_products(&['Z'])) as BoxedRule<Cents>,
+        Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+    ));
+    assert_price!(s, "AZ" => 25);
+    assert_eq!(Vec::<LintWarning>::new(), s.lint_stock("AZ"));
+
+    let s = Supermarket::with_rules(vec!(Box::new(FakeRule::overflowing()) as BoxedRule<Cents>));
+    assert_eq!(Err(CheckoutError::Overflow(Overflow)), s.try_checkout("A".to_string()))
+}
+
+#[test]
+fn fake_rules_count_their_calls() {
+    let rule = FakeRule::charging(Cents(1)).describing("a penny for anything");
+    let counts = rust_for_java_devs::core::counting::count_items::<u64>("ABC");
+    assert_eq!(Ok(Cents(1)), rule.price(&counts, OverflowPolicy::Checked));
+    assert_eq!(Ok(Cents(1)), rule.price(&counts, OverflowPolicy::Checked));
+    assert_eq!(2, rule.calls());
+    assert_eq!("a penny for anything", rule.describe())
+}
+
+#[test]
+fn rules_apply_to_the_products_they_claim() {
+    // `FakeRule` leaves `applies_to` to the trait's provided version.
+    let rule = FakeRule::charging(Cents(500)).for_products(&['Y', 'Z']);
+    assert!(rule.applies_to('Z') && !rule.applies_to('A'));
+    let boxed: BoxedRule<Cents> = Box::new(FlatPrice::new('A', Cents(2000)).unwrap());
+    assert!(boxed.applies_to('A') && !boxed.applies_to('Z'));
+}
+
+proptest::proptest! {
+    /// Generated catalogs go into a Supermarket like any others, after
+    /// boxing each rule.
+    #[test]
+    fn plans_add_up_to_the_checkout_total(
+        rules in rust_for_java_devs::core::arbitrary::catalog::<Cents>(),
+        items in rust_for_java_devs::core::arbitrary::cart(&['A', 'C', 'E', 'X'], 0..200)
+    ) {
+        let s: Supermarket = rules.into_iter()
+            .map(|rule| Box::new(rule) as BoxedRule<Cents>)
+            .collect();
+        proptest::prop_assert_eq!(Ok(s.plan(&items).unwrap().total), s.checkout(&items));
+    }
+}
+
+/// Takes a fixed amount off every cart, however little it costs: a cart
+/// rule written carelessly, as the standard ones are not.
+struct Markdown(Cents);
+
+impl Describable for Markdown {
+    fn describe(&self) -> String {
+        format!("markdown: {}", self.0)
+    }
+}
+
+impl CartRule<Cents> for Markdown {
+    fn adjust(&self, _total: Cents) -> Result<Option<Adjustment<Cents>>, Overflow> {
+        Ok(Some(Adjustment { label: self.describe(), amount: Cents(-self.0.0) }))
+    }
+}
+
+#[test]
+fn totals_below_zero_are_clamped_by_default() {
+    let s: Supermarket = Supermarket::demo().with_cart_rule(Markdown(Cents(5000)));
+    let receipt = s.receipt("A").unwrap();
+    assert_eq!(Cents(0), receipt.total);
+    let notes: Vec<String> = receipt.adjustments.iter().map(|a| a.to_string()).collect();
+    assert_eq!(vec!("markdown: $50.00  -$50.00", "discount not used  $30.00"), notes);
+    assert_price!(s, "ABBACBBAB" => 190);
+    assert_eq!(Ok(Cents(-3000)),
+               s.with_negative_total_policy(NegativeTotalPolicy::Allow).try_checkout("A"));
+}
+
+proptest::proptest! {
+    /// No catalog, however discounted, makes a cart cost less than nothing
+    /// under the default policy.
+    #[test]
+    fn totals_are_never_negative_by_default(
+        rules in rust_for_java_devs::core::arbitrary::catalog::<Cents>(),
+        items in rust_for_java_devs::core::arbitrary::cart(&['A', 'B', 'C', 'D', 'X'], 0..50),
+        markdowns in proptest::collection::vec(rust_for_java_devs::core::arbitrary::money::<Cents>(), 0..3),
+        vouchers in proptest::collection::vec(
+            (rust_for_java_devs::core::arbitrary::money::<Cents>(), rust_for_java_devs::core::arbitrary::money::<Cents>()),
+            0..3),
+        percent in 0u32..=100,
+        budget in rust_for_java_devs::core::arbitrary::money::<Cents>()
+    ) {
+        let mut s: Supermarket = rules.into_iter()
+            .map(|rule| Box::new(rule) as BoxedRule<Cents>)
+            .collect();
+        s = s.with_promotion_budget(PromotionBudget::Absolute(budget))
+            .with_cart_rule(SpendThresholds::new([(Cents(0), percent)]).unwrap());
+        for (value, minimum) in vouchers {
+            s = s.with_cart_rule(Voucher::new(value, minimum).unwrap());
+        }
+        for amount in markdowns {
+            s = s.with_cart_rule(Markdown(amount));
+        }
+        s.pipeline_mut().insert_after("promotions", EmployeeDiscount).unwrap();
+        let receipt = s.receipt(&items).unwrap();
+        proptest::prop_assert!(receipt.total >= Cents(0), "{:?}", receipt);
+        proptest::prop_assert_eq!(Ok(receipt.total), s.try_checkout(items));
+    }
+}
+
+proptest::proptest! {
+    /// Rules registered in any order price every cart alike. A shuffled
+    /// copy of each generated catalog is checked against the original,
+    /// with the settings that read the rules, a budget and quantity limits,
+    /// switched on.
+    #[test]
+    fn totals_do_not_depend_on_registration_order(
+        (rules, shuffled) in rust_for_java_devs::core::arbitrary::catalog::<Cents>()
+            .prop_flat_map(|rules| (Just(rules.clone()), Just(rules).prop_shuffle())),
+        items in rust_for_java_devs::core::arbitrary::cart(&['A', 'B', 'C', 'D', 'E', 'F', 'X'], 0..100),
+        policy in proptest::arbitrary::any::<OverflowPolicy>(),
+        percent in 0u32..=100
+    ) {
+        let market = |rules: Vec<_>| -> Supermarket {
+            rules.into_iter()
+                .map(|rule| Box::new(rule) as BoxedRule<Cents>)
+                .collect::<Supermarket>()
+                .with_overflow_policy(policy)
+                .with_promotion_budget(PromotionBudget::Percent(percent))
+                .with_quantity_limit(Sku('B'), 7)
+                .with_quantity_limit_policy(QuantityLimitPolicy::Trim)
+        };
+        let (original, shuffled) = (market(rules), market(shuffled));
+        proptest::prop_assert_eq!(original.try_checkout(items.as_str()), shuffled.try_checkout(items.as_str()));
+        proptest::prop_assert_eq!(original.plan(&items).map(|plan| plan.total),
+                                  shuffled.plan(&items).map(|plan| plan.total));
+        proptest::prop_assert_eq!(original.receipt(&items).map(|receipt| receipt.total),
+                                  shuffled.receipt(&items).map(|receipt| receipt.total));
+    }
+}
+
+#[test]
+fn demo_rules_price_alike_in_every_order() {
+    let rules = || -> Vec<BoxedRule<Cents>> {
+        vec!(Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+             Box::new(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap()),
+             Box::new(FlatPrice::new('C', Cents(3000)).unwrap()))
+    };
+    // Three rotations, each forwards and backwards, are all six orders.
+    for rotation in 0..3 {
+        for reversed in [false, true] {
+            let mut ordered = rules();
+            ordered.rotate_left(rotation);
+            if reversed {
+                ordered.reverse();
             }
-            None => ()
+            let s = Supermarket::with_rules(ordered);
+            assert_price!(s, { "ABBACBBAB" => 240, "BBBBBB" => 200, "" => 0, "CCA" => 80 });
         }
     }
-    (s, count)
+}
+
+#[test]
+fn every_count_strategy_prices_alike() {
+    for strategy in [CountStrategy::Tree, CountStrategy::Array, CountStrategy::Hash] {
+        let s: Supermarket = Supermarket::demo().with_count_strategy(strategy);
+        assert_price!(s, {
+            "ABBACBBAB" => 240,
+            "ABBACBBABABBACBBABABBACBBABABBACBBAB" => 960,
+            "A\u{e9}BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB" => 1220,
+        });
+    }
 }
 
 /// Rigid, inflexible, and basically correct scoring of initial
 /// products based on simple item counts.
-fn simple_expected_price(counts: HashMap<char, i32>) -> i32 {
+fn simple_expected_price(counts: Counts) -> Cents {
     let a_cost = match counts.get(&'A') {
         None => 0,
-        Some(&count) => count*20
+        Some(&count) => count*2000
     };
     
     let b_cost = match counts.get(&'B') {
         None => 0,
         Some(&count) => {
-           ((count / 5) * 150) + ((count % 5) * 50)
+           ((count / 5) * 15000) + ((count % 5) * 5000)
         }
     };
 
     let c_cost = match counts.get(&'C') {
         None => 0,
-        Some(&count) => count*30
+        Some(&count) => count*3000
     };
     
-    a_cost + b_cost + c_cost
+    Cents((a_cost + b_cost + c_cost) as i64)
 }
 
 #[test]
 fn correctly_sums_random_sequence_of_valid_codes() {
     let standard_codes = ['A', 'B', 'C'];
-    let s = Supermarket::new();
-    for _i in range(0, NUM_TEST_ITERATIONS) {
-        let (items, counts) = generate_mixed_char_sequence(&standard_codes);
-        assert_eq!(simple_expected_price(counts), s.checkout(items));
+    let s = Supermarket::demo();
+    let mut rng = seeded_rng(5);
+    for _i in 0..NUM_TEST_ITERATIONS {
+        let (items, counts) = generate_mixed_char_sequence(&mut rng, &standard_codes,
+                                                           0..MAX_ITEMS_STRING_SIZE);
+        assert_eq!(Ok(simple_expected_price(counts)), s.checkout(&items));
     }
 }
 
+#[test]
+fn const_catalog_prices_like_the_runtime_demo() {
+    let runtime = const_catalog::supermarket(&const_catalog::DEMO);
+    let mut rng = seeded_rng(6);
+    for _i in 0..NUM_TEST_ITERATIONS {
+        let (items, _) = generate_mixed_char_sequence(&mut rng, &['A', 'B', 'C', 'X', '\u{e9}'],
+                                                      0..MAX_ITEMS_STRING_SIZE);
+        assert_eq!(runtime.checkout(&items).map_err(|_| Overflow),
+                   const_catalog::checkout(&const_catalog::DEMO, &items),
+                   "pricing the cart {:?}", items);
+    }
+}
+
+#[test]
+fn const_catalog_reports_overflow() {
+    let rules = [ConstRule::flat('A', i64::MAX)];
+    assert_eq!(Err(Overflow), const_catalog::checkout(&rules, "AA"))
+}
+
+/// Tests may be feature-gated just like library code. This one only runs
+/// under `cargo test --features decimal`.
+#[cfg(feature = "decimal")]
+#[test]
+fn decimal_backend_prices_canonical_input() {
+    use rust_for_java_devs::money::Decimal;
+    let s: Supermarket<Decimal> = Supermarket::demo();
+    let items = "ABBACBBAB".to_string();
+    assert_eq!(Ok(Decimal::new(24000, 2)), s.checkout(&items))
+}
+
+
+/// Collects log records as text. Only one logger may be installed per
+/// process, so a single test uses it.
+#[cfg(feature = "logging")]
+struct CapturingLogger {
+    records: std::sync::Mutex<Vec<String>>
+}
+
+#[cfg(feature = "logging")]
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.target().starts_with("rust_for_java_devs") {
+            self.records.lock().unwrap().push(format!("{} {}", record.level(), record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(feature = "logging")]
+#[test]
+fn logging_traces_every_rule_and_the_total() {
+    static LOGGER: CapturingLogger = CapturingLogger { records: std::sync::Mutex::new(Vec::new()) };
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let s: Supermarket = Supermarket::demo();
+    s.checkout("AXBC").unwrap();
+    let records = LOGGER.records.lock().unwrap();
+    assert!(records.contains(&"TRACE rule \"B: $50.00 each or 5 for $150.00\" charged $50.00".to_string()));
+    assert!(records.contains(&"DEBUG priced 4 items at $100.00 ($100.00 before adjustments)".to_string()));
+}
+
+/// Everything the Supermarket recorded while running `body`, keyed by each
+/// metric's name and labels.
+#[cfg(feature = "metrics")]
+fn recorded_metrics(body: impl FnOnce())
+    -> std::collections::HashMap<String, metrics_util::debugging::DebugValue> {
+    use metrics_util::debugging::DebuggingRecorder;
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    // A local recorder applies to this thread only, so tests running in
+    // parallel cannot see each other's metrics.
+    metrics::with_local_recorder(&recorder, body);
+    snapshotter.snapshot().into_vec().into_iter()
+        .map(|(key, _unit, _description, value)| {
+            let key = key.key();
+            let labels: Vec<String> = key.labels()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect();
+            (format!("{}{:?}", key.name(), labels), value)
+        })
+        .collect()
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn metrics_count_checkouts_unknown_items_and_discounts() {
+    use metrics_util::debugging::DebugValue;
+    use rust_for_java_devs::reasonable_implementation::telemetry;
+
+    let s: Supermarket = Supermarket::demo().with_cart_limits(CartLimits { max_bytes: 8, max_items: 100 });
+    let metrics = recorded_metrics(|| {
+        // Six B's cost $200.00 rather than $300.00 apiece, and X is unknown.
+        assert_eq!(Ok(Cents(20000)), s.try_checkout("BBBBBBXX".to_string()));
+        assert!(s.try_checkout("AAAAAAAAA".to_string()).is_err());
+    });
+
+    let ok = format!("{}{:?}", telemetry::CHECKOUTS, ["outcome=ok"]);
+    let error = format!("{}{:?}", telemetry::CHECKOUTS, ["outcome=error"]);
+    let unknown = format!("{}[]", telemetry::UNKNOWN_ITEMS);
+    let discount = format!("{}[]", telemetry::DISCOUNT);
+    let seconds = format!("{}[]", telemetry::CHECKOUT_SECONDS);
+    assert_eq!(Some(&DebugValue::Counter(1)), metrics.get(&ok));
+    assert_eq!(Some(&DebugValue::Counter(1)), metrics.get(&error));
+    assert_eq!(Some(&DebugValue::Counter(2)), metrics.get(&unknown));
+    match metrics.get(&discount) {
+        Some(DebugValue::Histogram(values)) => {
+            assert_eq!(vec![100.0], values.iter().map(|v| v.0).collect::<Vec<f64>>());
+        },
+        other => panic!("expected a discount histogram, found {:?}", other)
+    }
+    match metrics.get(&seconds) {
+        Some(DebugValue::Histogram(values)) => assert_eq!(2, values.len()),
+        other => panic!("expected a latency histogram, found {:?}", other)
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn metrics_record_discounts_net_of_the_promotion_budget() {
+    use metrics_util::debugging::DebugValue;
+    use rust_for_java_devs::reasonable_implementation::telemetry;
+
+    let s: Supermarket = Supermarket::demo().with_promotion_budget(PromotionBudget::Absolute(Cents(6000)));
+    let metrics = recorded_metrics(|| {
+        // Ten B's list at $500.00 and would cost $300.00, but only $60.00
+        // of the $200.00 off is within the budget.
+        assert_eq!(Ok(Cents(44000)), s.try_checkout("BBBBBBBBBB".to_string()));
+    });
+
+    match metrics.get(&format!("{}[]", telemetry::DISCOUNT)) {
+        Some(DebugValue::Histogram(values)) => {
+            assert_eq!(vec![60.0], values.iter().map(|v| v.0).collect::<Vec<f64>>());
+        },
+        other => panic!("expected a discount histogram, found {:?}", other)
+    }
+}
@@ -0,0 +1,60 @@
+/// Markets borrowing their rules, or rules borrowing their prices, should
+/// price like the owned demo market for as long as the borrows last.
+mod common;
+
+use std::collections::BTreeMap;
+use rust_for_java_devs::borrowed_rules_implementation;
+use rust_for_java_devs::core::rules::{BundlePrice, Describable, FlatPrice, PricingRule};
+use rust_for_java_devs::lifetimes_explained::*;
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::BoxedRule;
+
+fn demo_rules() -> Vec<BoxedRule<Cents>> {
+    vec![
+        Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+        Box::new(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap()),
+        Box::new(FlatPrice::new('C', Cents(3000)).unwrap()),
+    ]
+}
+
+mod slice_market {
+    use super::*;
+
+    // The rules must outlive every market the suite builds, so they are
+    // leaked into a `'static` slice, which is fine in a test.
+    market_suite!(SliceMarket::new(Box::leak(demo_rules().into_boxed_slice())));
+}
+
+#[test]
+fn rules_outlive_the_market_borrowing_them() {
+    let rules = demo_rules();
+    let found = {
+        let market = SliceMarket::new(&rules);
+        assert_eq!(3, market.rules().len());
+        market.rule_for::<Cents>('B')
+    };
+    // The market is gone, but the rule it found is borrowed from `rules`.
+    assert_eq!("B: $50.00 each or 5 for $150.00", found.unwrap().describe());
+    let market = SliceMarket::new(&rules);
+    assert!(market.rule_for::<Cents>('D').is_none());
+}
+
+#[test]
+fn borrowing_rules_price_from_their_table() {
+    let prices = BTreeMap::from([('A', Cents(2000)), ('C', Cents(3000))]);
+    let table = PriceTable::new(&prices);
+    assert_eq!(vec!['A', 'C'], PricingRule::<Cents>::products(&table));
+    assert_eq!("table: A $20.00, C $30.00", table.describe());
+    let market: borrowed_rules_implementation::Supermarket = borrowed_rules_implementation::Supermarket::new()
+        .with_rule(Box::new(table))
+        .with_rule(Box::new(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap()));
+    assert_eq!(Ok(Cents(24000)), market.try_checkout("ABBACBBAB".to_string()));
+}
+
+#[test]
+fn the_dearer_rule_is_returned() {
+    let cheap = FlatPrice::new('A', Cents(1000)).unwrap();
+    let dear = FlatPrice::new('A', Cents(2000)).unwrap();
+    assert_eq!("A: $20.00 each", dearer::<Cents>(&cheap, &dear, 'A').describe());
+    assert_eq!("A: $10.00 each", dearer::<Cents>(&cheap, &cheap.clone(), 'A').describe());
+}
@@ -0,0 +1,60 @@
+/// `price_rules!` should build the rules its table describes, and both it
+/// and the derive macros should turn mistakes into clear compile errors.
+mod common;
+
+use rust_for_java_devs::core::rules::RuleError;
+use rust_for_java_devs::price_rules;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+market_suite!(Supermarket::with_rules(price_rules! {
+    'A' => Cents(2000);
+    'B' => Cents(5000), 5 for Cents(15000);
+    'C' => Cents(3000)
+}.unwrap()));
+
+#[test]
+fn rows_become_the_matching_rules() {
+    let rules = price_rules! {
+        'A' => Cents(2000);
+        'B' => Cents(5000), 5 for Cents(15000);
+        'C' => Cents(3000), 3 for Cents(8000), 10 for Cents(25000);
+    }.unwrap();
+    let descriptions: Vec<String> = rules.iter().map(|rule| rule.describe()).collect();
+    assert_eq!(vec!["A: $20.00 each",
+                    "B: $50.00 each or 5 for $150.00",
+                    "C: $30.00 each, or 3 for $80.00, or 10 for $250.00"], descriptions);
+}
+
+#[test]
+fn several_offers_give_the_best_price() {
+    let market = Supermarket::with_rules(price_rules! {
+        'C' => Cents(3000), 3 for Cents(8000), 10 for Cents(25000)
+    }.unwrap());
+    // Ten for $250 and three for $80.
+    assert_eq!(Ok(Cents(33000)), market.try_checkout("CCCCCCCCCCCCC"));
+}
+
+#[test]
+fn invalid_prices_are_refused() {
+    assert_eq!(Some(RuleError::NegativeCost),
+               price_rules! { 'A' => Cents(2000); 'B' => Cents(-1) }.err());
+    assert_eq!(Some(RuleError::EmptyBundle),
+               price_rules! { 'B' => Cents(5000), 0 for Cents(0) }.err());
+}
+
+#[test]
+fn an_empty_table_has_no_rules() {
+    let rules: Vec<_> = price_rules! {}.unwrap();
+    assert!(rules.is_empty());
+    let market: Supermarket = Supermarket::with_rules(rules);
+    assert_eq!(Ok(Cents(0)), market.try_checkout("ABC"));
+}
+
+/// Each file in `tests/ui` must fail to compile, with exactly the errors in
+/// the `.stderr` file next to it. Set `TRYBUILD=overwrite` to rewrite those
+/// files after changing a message on purpose.
+#[test]
+fn mistakes_are_compile_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
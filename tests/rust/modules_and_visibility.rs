@@ -0,0 +1,54 @@
+/// The facades and the prelude should offer the very same items as the
+/// modules defining them, and a till should balance only when it should.
+use rust_for_java_devs::modules_and_visibility::{close, float_of, register::Till};
+use rust_for_java_devs::prelude::*;
+use rust_for_java_devs::{market, rules};
+
+#[test]
+fn the_prelude_is_enough_to_check_out() {
+    let market: Supermarket = Supermarket::with_rules(vec![
+        Box::new(FlatPrice::new('A', Cents(2000)).unwrap()),
+        Box::new(BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap()),
+        Box::new(FlatPrice::new('C', Cents(3000)).unwrap()),
+    ]);
+    assert_eq!(Ok(Cents(24000)), market.checkout("ABBACBBAB"));
+    assert_eq!("A: $20.00 each", FlatPrice::new('A', Cents(2000)).unwrap().describe());
+}
+
+#[test]
+fn the_prelude_fills_a_cart_and_reports_bad_rules() {
+    let market: Supermarket = Supermarket::with_rules(vec![Box::new(FlatPrice::new('A', Cents(2000)).unwrap())]);
+    let mut cart = Cart::new();
+    cart.add('A');
+    cart.add('B');
+    let receipt: Receipt = market.receipt(cart.items()).unwrap();
+    assert_eq!(Cents(2000), receipt.total);
+    let error: RuleError = BundlePrice::new('B', Cents(5000), 0, Cents(15000)).unwrap_err();
+    assert!(!error.to_string().is_empty());
+}
+
+#[test]
+fn facades_re_export_the_same_types() {
+    // A re-export is the same type under another path, so values move
+    // between the paths with no conversion.
+    let market: market::Supermarket = rust_for_java_devs::reasonable_implementation::Supermarket::new();
+    let error: rust_for_java_devs::core::checkout::CheckoutError =
+        market::CheckoutError::PriceSourceUnavailable { product: 'A', attempts: 1 };
+    assert_eq!(Ok(Cents(0)), market.try_checkout("A"));
+    assert_eq!("no price for A after 1 attempts", error.to_string());
+    let rule: market::BoxedRule<Cents> = Box::new(rules::FlatPrice::new('A', Cents(2000)).unwrap());
+    assert_eq!(vec!['A'], rule.products());
+}
+
+#[test]
+fn tills_balance_against_their_float_and_takings() {
+    let mut till = Till::open("Leeds", Cents(5000));
+    till.ring_up(Cents(2000));
+    till.ring_up(Cents(3000));
+    assert_eq!(Cents(5000), float_of(&till));
+    assert_eq!("Leeds", till.store);
+    assert_eq!(Ok(Cents(5000)), close(till, Cents(10000)));
+    let mut short = Till::open("York", Cents(5000));
+    short.ring_up(Cents(2000));
+    assert_eq!(Err(Cents(-500)), close(short, Cents(6500)));
+}
@@ -0,0 +1,100 @@
+/// `+`, `+=`, `*` and `sum` on `Cents` should agree with the checked
+/// `Money` methods wherever those succeed, and panic wherever they fail;
+/// charges should sum under their policy.
+use proptest::prelude::*;
+use rust_for_java_devs::money::{Cents, Money, Overflow, OverflowPolicy};
+
+#[test]
+fn operators_add_and_multiply() {
+    assert_eq!(Cents(7000), Cents(2000) + Cents(5000));
+    assert_eq!(Cents(15000), Cents(5000) * 3);
+    assert_eq!(Cents(-500), Cents(-100) * 5);
+}
+
+#[test]
+fn add_assign_adds_in_place() {
+    let mut total = Cents(2000);
+    for price in [Cents(5000), Cents(3000)] {
+        total += price;
+    }
+    assert_eq!(Cents(10000), total);
+}
+
+#[test]
+#[should_panic(expected = "overflowed")]
+fn add_assign_panics_on_overflow() {
+    let mut total = Cents(i64::MAX);
+    total += Cents(1);
+}
+
+#[test]
+fn charges_sum_under_their_policy() {
+    let prices = [Cents(i64::MAX), Cents(2)];
+    let sum = |policy: OverflowPolicy| -> Result<Cents, Overflow> {
+        prices.iter().map(|&price| policy.charge(Ok(price))).sum()
+    };
+    assert_eq!(Err(Overflow), sum(OverflowPolicy::Checked));
+    assert_eq!(Ok(Cents(i64::MAX)), sum(OverflowPolicy::Saturating));
+    assert_eq!(Ok(Cents(i64::MIN + 1)), sum(OverflowPolicy::Wrapping));
+    let none: Result<Cents, Overflow> = std::iter::empty().map(|price| OverflowPolicy::Checked.charge(Ok(price))).sum();
+    assert_eq!(Ok(Cents(0)), none);
+}
+
+#[test]
+fn charges_stop_at_the_first_overflow() {
+    let mut seen = 0;
+    let total: Result<Cents, Overflow> = [Ok(Cents(100)), Err(Overflow), Ok(Cents(200))].into_iter()
+        .map(|price| {
+            seen += 1;
+            OverflowPolicy::Saturating.charge(price)
+        })
+        .sum();
+    assert_eq!(Err(Overflow), total);
+    assert_eq!(2, seen);
+}
+
+#[test]
+fn sums_start_from_zero() {
+    let prices = [Cents(2000), Cents(5000), Cents(3000)];
+    assert_eq!(Cents(10000), prices.iter().sum::<Cents>());
+    assert_eq!(Cents(10000), prices.into_iter().sum::<Cents>());
+    assert_eq!(Cents(0), std::iter::empty::<Cents>().sum::<Cents>());
+}
+
+#[test]
+fn amounts_order_like_their_cents() {
+    let mut prices = vec![Cents(3000), Cents(-100), Cents(2000)];
+    prices.sort();
+    assert_eq!(vec![Cents(-100), Cents(2000), Cents(3000)], prices);
+    assert_eq!(Some(Cents(3000)), prices.into_iter().max());
+}
+
+#[test]
+#[should_panic(expected = "overflowed")]
+fn additions_panic_on_overflow() {
+    let _ = Cents(i64::MAX) + Cents(1);
+}
+
+#[test]
+#[should_panic(expected = "overflowed")]
+fn multiplications_panic_on_overflow() {
+    let _ = Cents(i64::MAX / 2) * 3;
+}
+
+#[test]
+#[should_panic(expected = "overflowed")]
+fn sums_panic_on_overflow() {
+    let _: Cents = [Cents(i64::MAX), Cents(i64::MAX)].iter().sum();
+}
+
+proptest! {
+    #[test]
+    fn operators_agree_with_checked_arithmetic(a in any::<i64>(), b in any::<i64>(), n in any::<u32>()) {
+        if let Some(total) = Cents(a).checked_plus(Cents(b)) {
+            prop_assert_eq!(total, Cents(a) + Cents(b));
+        }
+        if let Some(total) = Cents(a).checked_times(u64::from(n)) {
+            prop_assert_eq!(total, Cents(a) * n);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+/// The newtypes of `newtypes_and_units` at the engine's typed entry points.
+/// Their misuse is covered by the compile-fail cases in `tests/ui`.
+use rust_for_java_devs::conversions::Sku;
+use rust_for_java_devs::newtypes_and_units::Quantity;
+use rust_for_java_devs::ownership_and_borrowing::Cart;
+use rust_for_java_devs::prelude::*;
+use rust_for_java_devs::market::{CartDimension, CartLimits, PriceQueryError};
+use rust_for_java_devs::money::Overflow;
+
+#[test]
+fn prices_a_quantity_of_one_product() {
+    let market: Supermarket = Supermarket::demo();
+    assert_eq!(Ok(Cents(6000)), market.price_of(Sku('A'), Quantity(3)));
+    assert_eq!(Ok(Cents(15000)), market.price_of(Sku('B'), Quantity(5)));
+    assert_eq!(Ok(Cents(20000)), market.price_of(Sku('B'), Quantity(6)));
+}
+
+#[test]
+fn agrees_with_checkout() {
+    let market: Supermarket = Supermarket::demo();
+    assert_eq!(market.try_checkout("BBBBBBB").map_err(PriceQueryError::Checkout),
+               market.price_of(Sku('B'), Quantity(7)));
+}
+
+#[test]
+fn refuses_to_price_an_unknown_product() {
+    let market: Supermarket = Supermarket::demo();
+    assert_eq!(Err(PriceQueryError::UnknownProduct(Sku('X'))), market.price_of(Sku('X'), Quantity(2)));
+    assert_eq!("no rule prices X", market.price_of(Sku('X'), Quantity::ONE).unwrap_err().to_string());
+    // A cart is priced all the same, with the unknown item free.
+    assert_eq!(Ok(Cents(2000)), market.try_checkout("AX"));
+    assert_eq!(Ok(Cents(0)), market.price_of(Sku('A'), Quantity(0)));
+}
+
+#[test]
+fn reports_overflow_as_a_checkout_would() {
+    let market: Supermarket = Supermarket::with_rules(vec!(
+        Box::new(FlatPrice::new('A', Cents(i64::MAX)).unwrap()) as BoxedRule<Cents>));
+    let error = market.price_of(Sku('A'), Quantity(2)).unwrap_err();
+    assert_eq!(PriceQueryError::Checkout(CheckoutError::Overflow(Overflow)), error);
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn respects_the_item_limit() {
+    let market: Supermarket = Supermarket::demo()
+        .with_cart_limits(CartLimits { max_items: 10, ..CartLimits::UNLIMITED });
+    assert_eq!(Err(PriceQueryError::Checkout(CheckoutError::CartTooLarge { dimension: CartDimension::Items, limit: 10 })),
+               market.price_of(Sku('C'), Quantity(11)));
+}
+
+#[test]
+fn fills_and_counts_a_cart() {
+    let mut cart = Cart::new();
+    cart.add_many(Sku('A'), Quantity(2));
+    cart.add('B');
+    cart.add_many(Sku('A'), Quantity::ONE);
+    assert_eq!("AABA", cart.items());
+    assert_eq!(Quantity(3), cart.quantity_of(Sku('A')));
+    assert_eq!(Quantity(0), cart.quantity_of(Sku('C')));
+}
+
+#[test]
+fn money_times_a_quantity_is_money() {
+    assert_eq!(Cents(6000), Cents(2000) * Quantity(3));
+}
+
+#[test]
+fn quantities_add_without_overflowing() {
+    assert_eq!(Some(Quantity(5)), Quantity(2).checked_add(Quantity(3)));
+    assert_eq!(None, Quantity(u32::MAX).checked_add(Quantity::ONE));
+    assert_eq!(7, u64::from(Quantity(7)));
+    assert_eq!("7", Quantity(7).to_string());
+}
@@ -0,0 +1,51 @@
+/// All five ways of handling the `Option` from `Counts::get` should price
+/// exactly as `FlatPrice` does, overflow included.
+use proptest::prelude::*;
+use rust_for_java_devs::core::counting::{count_items, Counts};
+use rust_for_java_devs::money::{Cents, Overflow, OverflowPolicy};
+use rust_for_java_devs::option_patterns::*;
+use rust_for_java_devs::reasonable_implementation::{FlatPrice, PricingRule};
+
+type Pattern = fn(&Counts, char, Cents, OverflowPolicy) -> Result<Cents, Overflow>;
+
+const PATTERNS: [(&str, Pattern); 5] = [
+    ("match", with_match),
+    ("if let", with_if_let),
+    ("unwrap_or", with_unwrap_or),
+    ("map_or", with_map_or),
+    ("combinators", with_combinators),
+];
+
+/// Checks every pattern against `FlatPrice` for one cart.
+fn all_agree(counts: &Counts, product: char, cost: Cents, policy: OverflowPolicy) {
+    let expected = FlatPrice::new(product, cost).unwrap().price(counts, policy);
+    for (name, pattern) in PATTERNS {
+        assert_eq!(expected, pattern(counts, product, cost, policy), "{}", name);
+    }
+}
+
+#[test]
+fn missing_and_empty_products_cost_nothing() {
+    all_agree(&Counts::new(), 'A', Cents(2000), OverflowPolicy::Checked);
+    all_agree(&Counts::from([('A', 0)]), 'A', Cents(2000), OverflowPolicy::Checked);
+    assert_eq!(Ok(Cents(0)), with_combinators(&Counts::from([('A', 0)]), 'A', Cents(2000),
+                                             OverflowPolicy::Checked));
+}
+
+#[test]
+fn overflows_are_reported_by_every_pattern() {
+    let counts = Counts::from([('A', u64::MAX)]);
+    all_agree(&counts, 'A', Cents(2), OverflowPolicy::Checked);
+    assert_eq!(Err(Overflow), with_combinators(&counts, 'A', Cents(2), OverflowPolicy::Checked));
+    all_agree(&counts, 'A', Cents(2), OverflowPolicy::Saturating);
+}
+
+proptest! {
+    #[test]
+    fn every_pattern_prices_like_flat_price(items in "[ABC]{0,50}", cost in 0i64..100_000) {
+        let counts = count_items::<u64>(&items);
+        for product in ['A', 'B', 'X'] {
+            all_agree(&counts, product, Cents(cost), OverflowPolicy::Checked);
+        }
+    }
+}
@@ -0,0 +1,36 @@
+/// A cart filled through borrows and paid for by moving it should price as
+/// the same items handed straight to the market.
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::ownership_and_borrowing::*;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+
+#[test]
+fn carts_change_through_mutable_borrows() {
+    let mut cart = Cart::new();
+    fill(&mut cart, "ABBA");
+    assert_eq!("ABBA", cart.items());
+    assert!(cart.remove('A'));
+    assert!(!cart.remove('C'));
+    assert_eq!("ABB", cart.items());
+    assert_eq!(3, cart.len());
+    assert_eq!("ABB", cart.into_items());
+}
+
+#[test]
+fn subtotals_leave_the_cart_and_paying_uses_it_up() {
+    let market: Supermarket = Supermarket::demo();
+    let mut cart = Cart::new();
+    fill(&mut cart, "BBBB");
+    assert_eq!(Ok(Cents(20000)), subtotal(&market, &cart));
+    cart.add('B');
+    assert_eq!(Ok(Cents(15000)), pay(&market, cart.clone()));
+    assert_eq!(Ok(Cents(15000)), pay(&market, cart));
+}
+
+#[test]
+fn shopping_trips_price_before_and_after_putting_back() {
+    let market: Supermarket = Supermarket::demo();
+    // Putting one B back breaks the bundle of five, so the total goes up.
+    assert_eq!(Ok((Cents(20000), Cents(25000))), shopping_trip(&market, "ABBBBBC", 'B'));
+    assert_eq!(Ok((Cents(2000), Cents(2000))), shopping_trip(&market, "A", 'X'));
+}
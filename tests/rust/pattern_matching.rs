@@ -0,0 +1,81 @@
+/// Each pattern should pick out the events, lines and rules it is meant
+/// for, and no others.
+use rust_for_java_devs::core::checkout::{CartDimension, CheckoutError};
+use rust_for_java_devs::enum_implementation::PricingRule;
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::pattern_matching::*;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+use rust_for_java_devs::receipt::{Receipt, ReceiptLine};
+
+fn receipt(items: &str) -> Receipt {
+    let market: Supermarket = Supermarket::demo();
+    market.receipt(items).unwrap()
+}
+
+fn paid(items: &str, tendered: Cents) -> Event {
+    Event::Paid { receipt: receipt(items), tendered }
+}
+
+#[test]
+fn lines_are_described_by_quantity_and_deals() {
+    let lines: Vec<String> = receipt("ABBBBBBCC").lines.iter().map(describe_line).collect();
+    assert_eq!(vec!["1 of A", "6 of B, 1 deal", "2 of C"], lines);
+    let remote = ReceiptLine { product: None, rule: "remote prices".to_string(), quantity: 3,
+                               amount: Cents(100), promotions: Vec::new() };
+    assert_eq!("charged by remote prices", describe_line(&remote));
+}
+
+#[test]
+fn lanes_bind_the_count() {
+    assert_eq!("empty", lane(0));
+    assert_eq!("express, 1 item", lane(1));
+    assert_eq!("express, 10 items", lane(10));
+    assert_eq!("staffed, 11 items", lane(11));
+}
+
+#[test]
+fn headlines_reach_into_receipts() {
+    assert_eq!("register opened", headline::<Cents>(&Event::Opened));
+    assert_eq!("scanned A", headline::<Cents>(&Event::Scanned('A')));
+    assert_eq!("voided B", headline::<Cents>(&Event::Voided('B')));
+    assert_eq!("unknown product 'é'", headline::<Cents>(&Event::Voided('é')));
+    assert_eq!("paid $5.00 for nothing", headline(&paid("", Cents(500))));
+    assert_eq!("paid $40.00 for 2 of A", headline(&paid("AA", Cents(5000))));
+    assert_eq!("paid $200.00 for 3 lines, 1 of A to 1 of C",
+               headline(&paid("ABBBBBC", Cents(20000))));
+}
+
+#[test]
+fn refusals_match_nested_errors() {
+    let too_many = CheckoutError::CartTooLarge { dimension: CartDimension::Items, limit: 50 };
+    assert_eq!("refused, over 50 items", headline::<Cents>(&Event::Refused(too_many)));
+    let too_long = CheckoutError::CartTooLarge { dimension: CartDimension::Bytes, limit: 64 };
+    assert_eq!("refused, cart is longer than 64 bytes", headline::<Cents>(&Event::Refused(too_long)));
+}
+
+#[test]
+fn change_is_only_due_on_sufficient_payments() {
+    assert_eq!(Some(Cents(1000)), change_due(&paid("AA", Cents(5000))));
+    assert_eq!(Some(Cents(0)), change_due(&paid("AA", Cents(4000))));
+    assert_eq!(None, change_due(&paid("AA", Cents(3999))));
+    assert_eq!(None, change_due::<Cents>(&Event::Scanned('A')));
+}
+
+#[test]
+fn replays_rebuild_the_cart() {
+    let events: Vec<Event> = vec![Event::Scanned('C'), Event::Opened, Event::Scanned('A'),
+                                  Event::Scanned('B'), Event::Scanned('A'), Event::Voided('A'),
+                                  Event::Refused(CheckoutError::PriceSourceUnavailable { product: 'C', attempts: 1 })];
+    assert_eq!(Ok("AB".to_string()), replay(&events));
+    assert_eq!(Err("Voided('C') with no C in the cart".to_string()),
+               replay::<Cents>(&[Event::Scanned('A'), Event::Voided('C')]));
+}
+
+#[test]
+fn every_rule_has_a_kind() {
+    assert_eq!("flat", rule_kind(&PricingRule::Flat { product: 'A', cost: Cents(2000) }));
+    let bundle = |bundle_size| PricingRule::Bundle { product: 'B', lone_cost: Cents(5000), bundle_size,
+                                                     bundle_cost: Cents(15000) };
+    assert_eq!("flat, as a bundle", rule_kind(&bundle(1)));
+    assert_eq!("bundle", rule_kind(&bundle(5)));
+}
@@ -0,0 +1,117 @@
+/// Rules handed over through the plugin ABI should behave exactly like the
+/// rules they wrap, whether they come from this process or from a library.
+use std::path::PathBuf;
+use std::process::Command;
+use rust_for_java_devs::core::counting::{count_items, Counts};
+use rust_for_java_devs::core::rules::{BestOfferPrice, FlatPrice};
+use rust_for_java_devs::money::{Cents, OverflowPolicy, Overflow};
+use rust_for_java_devs::plugins::{self, Plugin, PluginRule, PluginError, PLUGIN_ABI_VERSION};
+use rust_for_java_devs::reasonable_implementation::{BoxedRule, Describable, Market, PricingRule, Supermarket};
+
+/// Sends `rule` through the C ABI and back, without a library in between.
+fn round_trip<R: PricingRule<Cents> + Send + Sync + 'static>(rule: R) -> PluginRule {
+    // SAFETY: `export` keeps every promise `from_raw` asks for.
+    unsafe { PluginRule::from_raw(plugins::export(rule)) }.unwrap()
+}
+
+#[test]
+fn exported_rules_price_like_the_original() {
+    let original = BestOfferPrice::new('B', Cents(5000)).unwrap()
+        .with_offer(3, Cents(13000)).unwrap()
+        .with_offer(10, Cents(40000)).unwrap();
+    let plugged = round_trip(original.clone());
+    for cart in ["", "BBBBBBBBBBBBB", "ABX"] {
+        let counts = count_items::<u64>(cart);
+        assert_eq!(original.price(&counts, OverflowPolicy::Checked),
+                   plugged.price(&counts, OverflowPolicy::Checked));
+    }
+    // Longer than the first buffer the host offers, so it asks twice.
+    assert_eq!(original.describe(), plugged.describe());
+    assert_eq!(vec!['B'], plugged.products());
+}
+
+#[test]
+fn exported_rules_report_overflow_under_each_policy() {
+    let plugged = round_trip(FlatPrice::new('G', Cents(i64::MAX)).unwrap());
+    let counts = count_items::<u64>("GG");
+    assert_eq!(Err(Overflow), plugged.price(&counts, OverflowPolicy::Checked));
+    assert_eq!(Ok(Cents(i64::MAX)), plugged.price(&counts, OverflowPolicy::Saturating));
+}
+
+/// A rule that panics, which must not unwind through `extern "C"` code.
+struct Panicking;
+
+impl Describable for Panicking {
+    fn describe(&self) -> String {
+        panic!("a bug in the plugin")
+    }
+}
+
+impl PricingRule<Cents> for Panicking {
+    fn price(&self, _counts: &Counts, _policy: OverflowPolicy) -> Result<Cents, Overflow> {
+        panic!("a bug in the plugin")
+    }
+
+    fn products(&self) -> Vec<char> {
+        vec!['P']
+    }
+}
+
+#[test]
+fn panics_stay_on_the_plugin_side() {
+    let plugged = round_trip(Panicking);
+    assert_eq!("", plugged.describe());
+    assert_eq!(vec!['P'], plugged.products());
+}
+
+#[test]
+#[should_panic(expected = "a plugin rule failed")]
+fn failed_plugin_rules_panic_in_the_host() {
+    let _ = round_trip(Panicking).price(&Counts::new(), OverflowPolicy::Checked);
+}
+
+#[test]
+fn rules_for_other_abi_versions_are_refused() {
+    let mut raw = plugins::export(FlatPrice::new('A', Cents(1)).unwrap());
+    raw.abi_version = PLUGIN_ABI_VERSION + 1;
+    match unsafe { PluginRule::from_raw(raw) } {
+        Err(PluginError::AbiVersion { expected, found }) => {
+            assert_eq!((PLUGIN_ABI_VERSION, PLUGIN_ABI_VERSION + 1), (expected, found))
+        },
+        _ => panic!("expected the ABI version to be refused")
+    }
+}
+
+#[test]
+fn missing_libraries_fail_to_load() {
+    let loaded = unsafe { Plugin::load("no/such/plugin.so") };
+    assert!(matches!(loaded, Err(PluginError::Load(_))));
+}
+
+/// Builds `pricing-plugin-sample` into a target directory of its own, so
+/// as not to wait on the build that is running this test.
+fn build_sample_plugin() -> PathBuf {
+    let target = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("plugins");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "-p", "pricing-plugin-sample", "--manifest-path"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .arg("--target-dir").arg(&target)
+        .status()
+        .expect("cargo runs");
+    assert!(status.success(), "building the sample plugin failed");
+    target.join("debug").join(libloading::library_filename("pricing_plugin_sample"))
+}
+
+#[test]
+fn the_sample_plugin_prices_inside_a_supermarket() {
+    let path = build_sample_plugin();
+    // SAFETY: the sample plugin is built from this very workspace.
+    let plugin = unsafe { Plugin::load(&path) }.unwrap();
+    let third_free = Box::new(plugin.rule().unwrap()) as BoxedRule<Cents>;
+    assert_eq!("D: $10.00 each, every third free", third_free.describe());
+    let market: Supermarket = Supermarket::with_rules(vec![third_free]);
+    assert_eq!(Ok(Cents(4000)), market.checkout("DDDDDD"));
+    // The rule keeps the library loaded after the `Plugin` is gone.
+    drop(plugin);
+    assert_eq!(Ok(Cents(2000)), market.checkout("DDD"));
+}
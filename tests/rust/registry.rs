@@ -0,0 +1,270 @@
+/// A catalog described as data should price exactly like the same catalog
+/// written in code, and two such catalogs should compare rule by rule.
+use rust_for_java_devs::core::rules::{BestOfferPrice, RuleError};
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::assert_price;
+use rust_for_java_devs::registry::{RuleRegistry, RuleSpec, RegistryError};
+use rust_for_java_devs::catalog::{self, CatalogEntry, file};
+use rust_for_java_devs::receipt::Change;
+use rust_for_java_devs::reasonable_implementation::{BoxedRule, CartDimension, CartLimits, CheckoutError,
+                                                     PriceQueryError, Supermarket};
+use rust_for_java_devs::remote::{InMemoryPriceSource, RemotePrice};
+
+fn demo_specs() -> Vec<RuleSpec> {
+    vec![
+        RuleSpec::new("flat", 'A').with("cost", 2000),
+        RuleSpec::new("bundle", 'B').with("cost", 5000).with("size", 5).with("bundle_cost", 15000),
+        RuleSpec::new("flat", 'C').with("cost", 3000),
+    ]
+}
+
+#[test]
+fn builtin_kinds_build_the_demo_catalog() {
+    let registry: RuleRegistry = RuleRegistry::default();
+    let market = registry.supermarket(&demo_specs()).unwrap();
+    assert_price!(market, {
+        "ABBACBBAB" => 240,
+        "AXBC" => 100,
+    });
+}
+
+#[test]
+fn bogo_gives_every_second_item_away() {
+    let registry: RuleRegistry = RuleRegistry::with_builtins();
+    let market = registry.supermarket(&[RuleSpec::new("bogo", 'D').with("cost", 1000)]).unwrap();
+    assert_price!(market, {
+        "D" => 10,
+        "DD" => 10,
+        "DDDDD" => 30,
+    });
+}
+
+#[test]
+fn downstream_kinds_are_built_like_builtin_ones() {
+    let mut registry: RuleRegistry = RuleRegistry::with_builtins();
+    registry.register("three_for_two", |spec| {
+        let cost: Cents = spec.cost("cost")?;
+        Ok(Box::new(BestOfferPrice::new(spec.product, cost)?.with_offer(3, Cents(cost.0 * 2))?))
+    });
+    assert_eq!(vec!["bogo", "bundle", "flat", "three_for_two"], registry.kinds().collect::<Vec<_>>());
+    let market = registry.supermarket(&[RuleSpec::new("three_for_two", 'E').with("cost", 500)])
+        .unwrap();
+    assert_price!(market, "EEEE" => 15);
+}
+
+#[test]
+fn specs_are_checked() {
+    let registry: RuleRegistry = RuleRegistry::with_builtins();
+    let error = |spec: RuleSpec| registry.build(&spec).err();
+    assert_eq!(Some(RegistryError::UnknownKind("bogof".to_string())),
+               error(RuleSpec::new("bogof", 'A').with("cost", 1)));
+    assert_eq!(Some(RegistryError::MissingParameter { kind: "bundle".to_string(),
+                                                      name: "size".to_string() }),
+               error(RuleSpec::new("bundle", 'B').with("cost", 1).with("bundle_cost", 1)));
+    assert_eq!(Some(RegistryError::InvalidParameter { name: "size".to_string(), value: -5 }),
+               error(RuleSpec::new("bundle", 'B').with("cost", 1).with("size", -5)
+                         .with("bundle_cost", 1)));
+    assert_eq!(Some(RegistryError::Rule(RuleError::NegativeCost)),
+               error(RuleSpec::new("flat", 'A').with("cost", -1)));
+}
+
+#[test]
+fn empty_registries_know_no_kinds() {
+    let registry: RuleRegistry = RuleRegistry::new();
+    assert_eq!(0, registry.kinds().count());
+    assert!(registry.supermarket(&demo_specs()).is_err());
+}
+
+#[test]
+fn identical_catalogs_have_no_diff() {
+    let registry: RuleRegistry = RuleRegistry::default();
+    let old = registry.supermarket(&demo_specs()).unwrap();
+    let new = registry.supermarket(&demo_specs()).unwrap();
+    let changes = catalog::diff(&old, &new);
+    assert!(changes.is_empty());
+    assert_eq!("", changes.to_string());
+}
+
+#[test]
+fn catalog_diffs_list_each_rule_that_changed() {
+    let registry: RuleRegistry = RuleRegistry::default();
+    let old = registry.supermarket(&demo_specs()).unwrap();
+    let mut specs = demo_specs();
+    specs[0] = RuleSpec::new("bogo", 'A').with("cost", 2200);
+    specs.remove(2);
+    let new = registry.supermarket(&specs).unwrap();
+
+    let changes = catalog::diff(&old, &new);
+    assert_eq!(2, changes.rules.len());
+    match changes.rules[0] {
+        Change::Changed { ref before, ref after } => {
+            assert_eq!(vec![('A', Cents(2000))], before.unit_prices);
+            assert_eq!(vec![('A', Cents(2200))], after.unit_prices);
+        },
+        ref other => panic!("expected A to change, found {:?}", other)
+    }
+    assert!(matches!(changes.rules[1], Change::Removed(CatalogEntry { ref products, .. }) if products == &['C']));
+    assert_eq!("\
+~ A: $20.00 each
+  -> A: $22.00 each or 2 for $22.00
+  one A: $20.00 -> $22.00 (+$2.00)
+- C: $30.00 each
+", changes.to_string());
+}
+
+#[test]
+fn rules_without_products_are_paired_by_description() {
+    let remote = || Box::new(RemotePrice::new(InMemoryPriceSource::new())) as BoxedRule<Cents>;
+    let old = Supermarket::with_rules(vec![remote()]);
+    let new = Supermarket::with_rules(vec![remote()]);
+    assert!(catalog::diff(&old, &new).is_empty());
+    let entries = catalog::entries(&new);
+    assert!(entries[0].products.is_empty() && entries[0].unit_prices.is_empty());
+}
+
+#[test]
+fn catalog_tables_show_prices_in_and_past_each_bundle() {
+    let market: Supermarket = Supermarket::demo();
+    assert_eq!("\
+product  rule                             unit price
+A        A: $20.00 each                   1: $20.00
+B        B: $50.00 each or 5 for $150.00  1: $50.00 5: $30.00 6: $33.33
+C        C: $30.00 each                   1: $30.00
+", catalog::table(&market).to_string());
+
+    let offers = BestOfferPrice::new('D', Cents(1000)).unwrap()
+        .with_offer(3, Cents(2500)).unwrap()
+        .with_offer(2, Cents(1800)).unwrap();
+    let market = Supermarket::with_rules(vec![Box::new(offers) as BoxedRule<Cents>]);
+    let row = &catalog::table(&market).rows[0];
+    let quantities: Vec<u64> = row.prices.iter().map(|point| point.quantity).collect();
+    assert_eq!(vec![1, 2, 3, 4], quantities);
+    assert_eq!(Cents(3500), row.prices[3].total);
+}
+
+#[test]
+fn unit_price_tables_show_where_offers_start_to_save() {
+    let market: Supermarket = Supermarket::demo();
+    assert_eq!("\
+product  unit price by quantity
+A        1: $20.00 2: $20.00 3: $20.00
+B        1: $50.00 2: $50.00 3: $50.00
+C        1: $30.00 2: $30.00 3: $30.00
+", market.unit_price_table(3).to_string());
+
+    let table = market.unit_price_table(10);
+    let b = &table.rows[1];
+    assert_eq!(10, b.prices.len());
+    assert_eq!(Some(5), b.first_saving().map(|point| point.quantity));
+    assert_eq!(Ok(Cents(20000)), b.prices[5].as_ref().map(|point| point.total));
+    assert_eq!(Ok(30.0), b.prices[9].as_ref().map(|point| point.each()));
+    assert_eq!(None, table.rows[0].first_saving());
+
+    // With two offers, the better one breaks even first.
+    let offers = BestOfferPrice::new('D', Cents(1000)).unwrap()
+        .with_offer(3, Cents(2500)).unwrap()
+        .with_offer(2, Cents(1800)).unwrap();
+    let market = Supermarket::with_rules(vec![Box::new(offers) as BoxedRule<Cents>]);
+    let row = &market.unit_price_table(4).rows[0];
+    assert_eq!(Some(2), row.first_saving().map(|point| point.quantity));
+    assert_eq!(Ok(Cents(3500)), row.prices[3].as_ref().map(|point| point.total));
+    assert!(market.unit_price_table(0).rows[0].prices.is_empty());
+}
+
+#[test]
+fn unit_price_tables_keep_refused_quantities_in_place() {
+    let market: Supermarket = Supermarket::demo().with_cart_limits(CartLimits { max_bytes: 100, max_items: 3 });
+    let table = market.unit_price_table(5);
+    let b = &table.rows[1];
+    assert_eq!(5, b.prices.len());
+    assert_eq!(Err(PriceQueryError::Checkout(CheckoutError::CartTooLarge { dimension: CartDimension::Items, limit: 3 })),
+               b.prices[3]);
+    assert_eq!(None, b.first_saving());
+    assert!(table.to_string().contains("B        1: $50.00 2: $50.00 3: $50.00 4: refused 5: refused\n"),
+            "{}", table);
+}
+
+/// The demo catalog as a file, shipped with the command line register.
+const DEMO_FILE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/pricing-cli/prices.toml"));
+
+fn problems(source: &str) -> Vec<(usize, String)> {
+    file::validate(&RuleRegistry::<Cents>::default(), source).into_iter()
+        .map(|d| (d.line, d.message))
+        .collect()
+}
+
+#[test]
+fn catalog_files_load_into_supermarkets() {
+    let market: Supermarket = file::load(&RuleRegistry::default(), DEMO_FILE).unwrap();
+    assert_price!(market, "ABBACBBAB" => 240);
+    let specs = file::parse(DEMO_FILE).unwrap();
+    assert_eq!(vec![4, 9, 16], specs.iter().map(|s| s.line).collect::<Vec<_>>());
+    assert_eq!(demo_specs(), specs.into_iter().map(|s| s.spec).collect::<Vec<_>>());
+    assert!(problems(DEMO_FILE).is_empty());
+}
+
+#[test]
+fn syntax_errors_are_reported_at_their_line() {
+    let found = problems("[[rule]]\nkind = \"flat\"\nproduct = A\n");
+    assert_eq!(1, found.len());
+    assert_eq!(3, found[0].0);
+    assert_eq!(vec![(1, "unknown field `rules`, expected `rule`".to_string())],
+               problems("[[rules]]\nkind = \"flat\"\n"));
+}
+
+#[test]
+fn every_malformed_rule_is_reported() {
+    let source = "\
+[[rule]]
+product = \"AB\"
+cost = 1
+
+[[rule]]
+kind = \"flat\"
+product = \"C\"
+cost = \"ten\"
+";
+    assert_eq!(vec![(1, "the rule has no `kind`".to_string()),
+                    (2, "`product` must be a string of one character".to_string()),
+                    (8, "`cost` must be a whole number".to_string())],
+               problems(source));
+}
+
+#[test]
+fn rules_the_registry_refuses_are_reported_at_their_line() {
+    let source = "\
+[[rule]]
+kind = \"flat\"
+product = \"A\"
+cost = 2000
+
+[[rule]]
+kind = \"bundle\"
+product = \"B\"
+cost = 5000
+size = -5
+bundle_cost = 15000
+
+[[rule]]
+kind = \"discount\"
+product = \"C\"
+";
+    assert_eq!(vec![(10, "-5 is not a valid \"size\"".to_string()),
+                    (14, "no rule kind is named \"discount\"".to_string())],
+               problems(source));
+}
+
+#[test]
+fn lint_warnings_are_reported_at_the_rule_concerned() {
+    let source = format!("{}
+[[rule]]
+kind = \"bundle\"
+product = \"A\"
+cost = 1000
+size = 2
+bundle_cost = 3000
+", DEMO_FILE);
+    assert_eq!(vec![(21, "A: the bundle costs more than its items bought singly".to_string()),
+                    (21, "A: priced by 2 rules".to_string())],
+               problems(&source));
+}
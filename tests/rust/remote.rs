@@ -0,0 +1,399 @@
+/// `RemotePrice` should price unknown products from its source, and the
+/// HTTP sources should read the service's answers correctly.
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use std::task::{Context, Poll, Waker};
+use rust_for_java_devs::core::counting::count_items;
+use rust_for_java_devs::core::rules::{FlatPrice, PricingRule};
+use rust_for_java_devs::money::{Cents, OverflowPolicy};
+use rust_for_java_devs::assert_price;
+use rust_for_java_devs::reasonable_implementation::{BoxedRule, Supermarket};
+use rust_for_java_devs::remote::{RemotePrice, InMemoryPriceSource, Prefetched, PriceSource,
+                                 LookupFailure, SourceError, CachedPriceSource, RetryPolicy,
+                                 RetryingPriceSource};
+use rust_for_java_devs::reasonable_implementation::CheckoutError;
+
+fn prices() -> InMemoryPriceSource {
+    InMemoryPriceSource::new().with_price('D', Cents(1000)).with_price('E', Cents(250))
+}
+
+/// A market with a flat price for A, and everything else looked up.
+fn market<S: PriceSource + 'static>(remote: RemotePrice<S>) -> Supermarket {
+    let flat = Box::new(FlatPrice::new('A', Cents(2000)).unwrap()) as BoxedRule<Cents>;
+    Supermarket::with_rules(vec![flat, Box::new(remote.excluding(&['A']))])
+}
+
+/// Runs a future that never waits on anything to completion. A real
+/// program would hand it to a runtime such as Tokio.
+fn ready<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("the future was not ready")
+    }
+}
+
+#[test]
+fn unknown_products_are_priced_remotely() {
+    let s = market(RemotePrice::new(prices()));
+    assert_price!(s, {
+        "ADDE" => 42.5,
+        "XYZ" => 0,
+        "" => 0,
+    });
+}
+
+#[test]
+fn excluded_products_are_never_looked_up() {
+    let remote = RemotePrice::new(prices()).excluding(&['A']);
+    let s: Supermarket = Supermarket::with_rules(vec![Box::new(remote)]);
+    assert_price!(s, "AAAD" => 10);
+}
+
+#[test]
+fn failed_lookups_are_ignored_by_default() {
+    let s = market(RemotePrice::new(prices().failing_for('E')));
+    assert_price!(s, "ADE" => 30);
+}
+
+#[test]
+#[should_panic(expected = "cannot price E")]
+fn failed_lookups_may_abandon_the_checkout() {
+    let s = market(RemotePrice::new(prices().failing_for('E')).on_failure(LookupFailure::Panic));
+    assert_price!(s, "ADE" => 30);
+}
+
+#[test]
+fn each_product_is_looked_up_once_per_pricing() {
+    let source = Arc::new(prices());
+    let remote = RemotePrice::new(Arc::clone(&source)).excluding(&['A']);
+    let counts = count_items::<u64>("ADDDDDEEEEX");
+    assert_eq!(Ok(Cents(6000)), remote.price(&counts, OverflowPolicy::Checked));
+    // D, E and X, but never A, which has a rule of its own.
+    assert_eq!(3, source.lookups());
+}
+
+#[test]
+fn prefetched_prices_serve_the_checkout() {
+    let source = prices();
+    let fetched: Prefetched = ready(Prefetched::fetch(&source, ['D', 'X'])).unwrap();
+    assert_eq!(2, source.lookups());
+    assert_eq!(Ok(Some(Cents(1000))), fetched.price_of('D'));
+    assert_eq!(Ok(None), fetched.price_of('X'));
+    assert_eq!(Err(SourceError::NotFetched('E')), fetched.price_of('E'));
+    let s = market(RemotePrice::new(fetched));
+    assert_price!(s, "ADX" => 30);
+}
+
+#[test]
+fn prefetching_stops_at_the_first_failure() {
+    let source = prices().failing_for('D');
+    let fetched: Result<Prefetched, _> = ready(Prefetched::fetch(&source, ['D', 'E']));
+    assert!(matches!(fetched, Err(SourceError::Transport(_))));
+    assert_eq!(1, source.lookups());
+}
+
+/// A clock that only moves when told to, shared between a test and the
+/// cache it is testing.
+#[derive(Clone)]
+struct FakeClock(Arc<Mutex<Instant>>);
+
+impl FakeClock {
+    fn new() -> FakeClock {
+        FakeClock(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    fn now(&self) -> Instant {
+        *self.0.lock().unwrap()
+    }
+
+    fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+/// A cache of `prices()` trusting answers for a minute, on a fake clock.
+fn cached(max_entries: usize)
+    -> (CachedPriceSource<Arc<InMemoryPriceSource>>, Arc<InMemoryPriceSource>, FakeClock) {
+    let upstream = Arc::new(prices().failing_for('Q'));
+    let clock = FakeClock::new();
+    let ticking = clock.clone();
+    let cache = CachedPriceSource::new(Arc::clone(&upstream), Duration::from_secs(60), max_entries)
+        .with_clock(move || ticking.now());
+    (cache, upstream, clock)
+}
+
+#[test]
+fn cached_answers_spare_the_upstream_source() {
+    let (cache, upstream, _clock) = cached(10);
+    for _i in 0..3 {
+        assert_eq!(Ok(Some(Cents(1000))), cache.price_of('D'));
+        // Knowing there is no price is worth remembering too.
+        assert_eq!(Ok(None), cache.price_of('X'));
+    }
+    assert_eq!(2, upstream.lookups());
+    assert_eq!((4, 2), (cache.hits(), cache.misses()));
+}
+
+#[test]
+fn cached_answers_expire_after_their_ttl() {
+    let (cache, upstream, clock) = cached(10);
+    cache.price_of('D').unwrap();
+    clock.advance(Duration::from_secs(59));
+    cache.price_of('D').unwrap();
+    assert_eq!(1, upstream.lookups());
+    clock.advance(Duration::from_secs(1));
+    cache.price_of('D').unwrap();
+    assert_eq!(2, upstream.lookups());
+}
+
+#[test]
+fn failures_are_never_cached() {
+    let (cache, upstream, _clock) = cached(10);
+    assert!(cache.price_of('Q').is_err());
+    assert!(cache.price_of('Q').is_err());
+    assert_eq!(2, upstream.lookups());
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn full_caches_drop_the_answer_expiring_soonest() {
+    let (cache, upstream, clock) = cached(2);
+    for product in ['D', 'E', 'X'] {
+        cache.price_of(product).unwrap();
+        clock.advance(Duration::from_secs(1));
+    }
+    assert_eq!(2, cache.len());
+    // D was fetched first, so it made way for X.
+    cache.price_of('E').unwrap();
+    assert_eq!(3, upstream.lookups());
+    cache.price_of('D').unwrap();
+    assert_eq!(4, upstream.lookups());
+}
+
+#[test]
+fn cleared_caches_ask_again() {
+    let (cache, upstream, _clock) = cached(10);
+    cache.price_of('D').unwrap();
+    cache.clear();
+    cache.price_of('D').unwrap();
+    assert_eq!(2, upstream.lookups());
+}
+
+/// A source failing its first `failures` lookups with `error`, then
+/// pricing everything at $1, after `delay`.
+struct Flaky {
+    failures: u32,
+    error: SourceError,
+    delay: Duration,
+    calls: AtomicU32
+}
+
+impl Flaky {
+    fn new(failures: u32, error: SourceError) -> Flaky {
+        Flaky { failures, error, delay: Duration::ZERO, calls: AtomicU32::new(0) }
+    }
+
+    fn calls(&self) -> u32 {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl PriceSource for Flaky {
+    fn price_of(&self, _product: char) -> Result<Option<Cents>, SourceError> {
+        std::thread::sleep(self.delay);
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Err(self.error.clone());
+        }
+        Ok(Some(Cents(100)))
+    }
+}
+
+fn down() -> SourceError {
+    SourceError::Transport("connection refused".to_string())
+}
+
+/// Retries quickly, so that the tests do not wait long.
+fn quickly() -> RetryPolicy {
+    RetryPolicy::default().with_backoff(Duration::from_millis(1))
+}
+
+#[test]
+fn transient_failures_are_retried() {
+    let source = RetryingPriceSource::new(Flaky::new(2, down()), quickly());
+    assert_eq!(Ok(Some(Cents(100))), source.price_of('D'));
+    assert_eq!(3, source.source().calls());
+}
+
+#[test]
+fn retries_give_up_after_the_last_attempt() {
+    let source = RetryingPriceSource::new(Flaky::new(5, down()), quickly().with_attempts(4));
+    assert_eq!(Err(SourceError::Exhausted { attempts: 4, last: Box::new(down()) }),
+               source.price_of('D'));
+    assert_eq!(4, source.source().calls());
+}
+
+#[test]
+fn lasting_failures_are_not_retried() {
+    for error in [SourceError::Status(404), SourceError::BadResponse("?".to_string())] {
+        let source = RetryingPriceSource::new(Flaky::new(1, error.clone()), quickly());
+        assert_eq!(Err(error), source.price_of('D'));
+        assert_eq!(1, source.source().calls());
+    }
+    assert!(SourceError::Status(503).is_transient());
+}
+
+#[test]
+fn slow_lookups_time_out() {
+    let mut slow = Flaky::new(0, down());
+    slow.delay = Duration::from_secs(5);
+    let policy = quickly().with_attempts(2).with_timeout(Some(Duration::from_millis(20)));
+    let source = RetryingPriceSource::new(slow, policy);
+    let started = Instant::now();
+    assert_eq!(Err(SourceError::Exhausted {
+                   attempts: 2,
+                   last: Box::new(SourceError::TimedOut(Duration::from_millis(20)))
+               }),
+               source.price_of('D'));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn backoff_doubles_up_to_its_limit() {
+    let policy = RetryPolicy::default()
+        .with_backoff(Duration::from_millis(100))
+        .with_max_backoff(Duration::from_millis(500));
+    let delays: Vec<u128> = (1..=5).map(|failed| policy.delay(failed).as_millis()).collect();
+    assert_eq!(vec![100, 200, 400, 500, 500], delays);
+    assert_eq!(Duration::from_millis(500), policy.delay(u32::MAX));
+}
+
+/// The demo market, with every other product priced remotely by a stage.
+fn remote_stage<S: PriceSource + 'static>(source: S) -> Supermarket {
+    let mut s = Supermarket::demo();
+    let remote = RemotePrice::new(source).excluding(&['A', 'B', 'C']);
+    s.pipeline_mut().insert_after("promotions", remote).unwrap();
+    s
+}
+
+#[test]
+fn a_remote_stage_adds_to_the_subtotal() {
+    let s = remote_stage(prices());
+    assert_price!(s, {
+        "ADE" => 32.5,
+        "X" => 0,
+    });
+}
+
+#[test]
+fn exhausted_retries_stop_the_checkout() {
+    let source = RetryingPriceSource::new(prices().failing_for('E'), quickly());
+    let s = remote_stage(source);
+    assert_eq!(Err(CheckoutError::PriceSourceUnavailable { product: 'E', attempts: 3 }),
+               s.try_checkout("ADE".to_string()));
+    // Without retries, a failure is one attempt.
+    let s = remote_stage(prices().failing_for('E'));
+    assert_eq!(Err(CheckoutError::PriceSourceUnavailable { product: 'E', attempts: 1 }),
+               s.try_checkout("ADE".to_string()));
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn cache_lookups_are_counted_in_the_metrics() {
+    use metrics_util::debugging::{DebuggingRecorder, DebugValue};
+    use rust_for_java_devs::reasonable_implementation::telemetry;
+
+    let (cache, _upstream, _clock) = cached(10);
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        for _i in 0..3 {
+            cache.price_of('D').unwrap();
+        }
+    });
+    let mut counts: Vec<(String, u64)> = snapshotter.snapshot().into_vec().into_iter()
+        .filter(|(key, ..)| key.key().name() == telemetry::PRICE_CACHE)
+        .map(|(key, _, _, value)| match value {
+            DebugValue::Counter(count) => (key.key().labels().next().unwrap().value().to_string(), count),
+            other => panic!("expected a counter, found {:?}", other)
+        })
+        .collect();
+    counts.sort();
+    assert_eq!(vec![("hit".to_string(), 2), ("miss".to_string(), 1)], counts);
+}
+
+#[cfg(feature = "http")]
+mod http {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+    use rust_for_java_devs::money::Cents;
+    use rust_for_java_devs::remote::{HttpPriceSource, AsyncHttpPriceSource, AsyncPriceSource,
+                                     PriceSource, SourceError};
+
+    /// Serves a stand-in pricing service on a free port, returning its base
+    /// URL: D costs $10.00, `%C3%A9` (é) costs $1.99, Z answers garbage,
+    /// and Q fails.
+    fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                reader.read_line(&mut request_line).unwrap();
+                // Skip the headers, up to the blank line ending them.
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or("");
+                let (status, body) = match path {
+                    "/prices/D" => ("200 OK", "1000"),
+                    "/prices/%C3%A9" => ("200 OK", "199\n"),
+                    "/prices/Z" => ("200 OK", "ten dollars"),
+                    "/prices/Q" => ("503 Service Unavailable", ""),
+                    _ => ("404 Not Found", "")
+                };
+                write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                       status, body.len(), body).unwrap();
+            }
+        });
+        base
+    }
+
+    fn answers<S: Fn(char) -> Result<Option<Cents>, SourceError>>(price_of: S) {
+        assert_eq!(Ok(Some(Cents(1000))), price_of('D'));
+        assert_eq!(Ok(Some(Cents(199))), price_of('\u{e9}'));
+        assert_eq!(Ok(None), price_of('X'));
+        assert_eq!(Err(SourceError::BadResponse("ten dollars".to_string())), price_of('Z'));
+        assert_eq!(Err(SourceError::Status(503)), price_of('Q'));
+    }
+
+    #[test]
+    fn the_blocking_source_reads_the_services_answers() {
+        let source = HttpPriceSource::new(serve(), Duration::from_secs(5)).unwrap();
+        answers(|product| source.price_of(product));
+    }
+
+    #[test]
+    fn the_async_source_reads_the_services_answers() {
+        let source = AsyncHttpPriceSource::new(serve(), Duration::from_secs(5)).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        answers(|product| runtime.block_on(source.price_of(product)));
+    }
+
+    #[test]
+    fn unreachable_services_are_transport_errors() {
+        // Nothing listens on the port once the listener is dropped.
+        let base = format!("http://{}", TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap());
+        let source = HttpPriceSource::new(base, Duration::from_secs(5)).unwrap();
+        let price: Result<Option<Cents>, _> = source.price_of('D');
+        assert!(matches!(price, Err(SourceError::Transport(_))));
+    }
+}
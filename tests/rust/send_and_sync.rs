@@ -0,0 +1,40 @@
+/// Markets shared across threads, borrowed by scoped threads and through
+/// an `Arc` by spawned ones.
+use std::sync::Arc;
+use rust_for_java_devs::send_and_sync::*;
+use rust_for_java_devs::shared_rules_implementation::SyncSupermarket;
+use rust_for_java_devs::prelude::*;
+
+fn carts() -> Vec<String> {
+    ["ABBACBBAB", "A", "BBBBB", "", "CC", "BBBBBB", "AC", "B"].iter().map(|cart| cart.to_string()).collect()
+}
+
+#[test]
+fn scoped_threads_match_a_single_thread() {
+    let market: Supermarket = Supermarket::demo();
+    let serial: Vec<Result<Cents, CheckoutError>> = carts().iter().map(|cart| market.checkout(cart)).collect();
+    assert_eq!(serial, checkout_in_scope(&market, &carts()));
+    assert_eq!(Ok(Cents(24000)), serial[0]);
+}
+
+#[test]
+fn spawned_threads_keep_the_carts_order() {
+    let market: Arc<Supermarket> = Arc::new(Supermarket::demo());
+    let totals: Vec<Cents> = checkout_on_threads(market, carts()).into_iter().map(Result::unwrap).collect();
+    assert_eq!(vec!(Cents(24000), Cents(2000), Cents(15000), Cents(0), Cents(6000), Cents(20000),
+                    Cents(5000), Cents(5000)), totals);
+}
+
+#[test]
+fn takings_shared_behind_a_mutex_miss_no_cart() {
+    let (mut markets, takings) = SyncSupermarket::<Cents>::demo_chain(1);
+    let market = Arc::new(markets.remove(0));
+    let mut expected = Cents(0);
+    for _ in 0..8 {
+        let totals = checkout_on_threads(Arc::clone(&market), carts());
+        expected = totals.into_iter().fold(expected, |sum, total| sum + total.unwrap());
+    }
+    let takings = *takings.lock().unwrap();
+    assert_eq!(8 * carts().len() as u64, takings.carts);
+    assert_eq!(expected, takings.total);
+}
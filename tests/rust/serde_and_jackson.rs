@@ -0,0 +1,85 @@
+/// Price lists should survive a round trip through JSON, read documents of
+/// either version, and price carts as the demo catalog does.
+use rust_for_java_devs::catalog::CatalogEntry;
+use rust_for_java_devs::core::rules::RuleError;
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::{BundlePrice, Supermarket};
+use rust_for_java_devs::serde_and_jackson::*;
+use serde_json::json;
+
+fn demo_list() -> PriceList {
+    PriceList::new(vec![
+        PriceListItem { product: 'A', each: Cents(2000), offers: Vec::new() },
+        PriceListItem { product: 'B', each: Cents(5000), offers: vec![Offer { size: 5, cost: Cents(15000) }] },
+        PriceListItem { product: 'C', each: Cents(3000), offers: Vec::new() }
+    ])
+}
+
+#[test]
+fn price_lists_are_written_as_version_two() {
+    let written = serde_json::to_value(demo_list()).unwrap();
+    assert_eq!(json!({
+        "version": 2,
+        "items": [
+            {"product": "A", "each": "$20.00"},
+            {"product": "B", "each": "$50.00", "offers": [{"size": 5, "cost": "$150.00"}]},
+            {"product": "C", "each": "$30.00"}
+        ]
+    }), written);
+    assert_eq!(demo_list(), serde_json::from_value::<PriceList>(written).unwrap());
+}
+
+#[test]
+fn version_one_documents_are_still_read() {
+    let old: PriceList = serde_json::from_str(r#"{"items": [{"product": "A", "price": 2000}]}"#).unwrap();
+    assert_eq!(1, old.version);
+    assert_eq!(vec![PriceListItem { product: 'A', each: Cents(2000), offers: Vec::new() }], old.items);
+}
+
+#[test]
+fn mistakes_are_refused() {
+    let misspelt = r#"{"items": [{"product": "B", "each": "$50.00", "ofers": []}]}"#;
+    assert!(serde_json::from_str::<PriceList>(misspelt).unwrap_err().to_string().contains("unknown field `ofers`"));
+    for each in ["\"20.00\"", "\"$20.0\"", "\"$20\"", "\"$-20.00\"", "20.5", "true"] {
+        let item = format!(r#"{{"items": [{{"product": "A", "each": {}}}]}}"#, each);
+        assert!(serde_json::from_str::<PriceList>(&item).is_err(), "{} was read", each);
+    }
+    let two_letters = r#"{"items": [{"product": "AB", "each": 2000}]}"#;
+    assert!(serde_json::from_str::<PriceList>(two_letters).is_err());
+}
+
+#[test]
+fn dollars_read_either_form() {
+    assert_eq!(Dollars(Cents(-150)), serde_json::from_str("\"-$1.50\"").unwrap());
+    assert_eq!(Dollars(Cents(2000)), serde_json::from_str("2000").unwrap());
+    assert_eq!("\"$20.00\"", serde_json::to_string(&Dollars(Cents(2000))).unwrap());
+    // `Cents` on its own is still just a number.
+    assert_eq!("2000", serde_json::to_string(&Cents(2000)).unwrap());
+}
+
+#[test]
+fn price_lists_price_like_the_demo() {
+    let market: Supermarket = Supermarket::with_rules(demo_list().to_rules().unwrap());
+    let demo: Supermarket = Supermarket::demo();
+    for cart in ["", "ABBACBBAB", "BBBBBBBBBBB", "CCA"] {
+        assert_eq!(demo.try_checkout(cart), market.try_checkout(cart), "{}", cart);
+    }
+    let mut several = demo_list();
+    several.items[2].offers = vec![Offer { size: 3, cost: Cents(8000) }, Offer { size: 10, cost: Cents(25000) }];
+    let market: Supermarket = Supermarket::with_rules(several.to_rules().unwrap());
+    assert_eq!(Ok(Cents(25000)), market.try_checkout("CCCCCCCCCC"));
+    several.items[0].offers = vec![Offer { size: 0, cost: Cents(0) }];
+    assert_eq!(RuleError::EmptyBundle, several.to_rules().err().unwrap());
+}
+
+#[test]
+fn catalog_entries_derive_their_mapping() {
+    let entry: CatalogEntry = CatalogEntry::of(&BundlePrice::new('B', Cents(5000), 5, Cents(15000)).unwrap());
+    let written = serde_json::to_value(&entry).unwrap();
+    assert_eq!(json!({
+        "description": "B: $50.00 each or 5 for $150.00",
+        "products": ["B"],
+        "unit_prices": [["B", 5000]]
+    }), written);
+    assert_eq!(entry, serde_json::from_value(written).unwrap());
+}
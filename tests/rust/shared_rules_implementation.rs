@@ -0,0 +1,50 @@
+/// Markets sharing their rules should price carts exactly like the owned
+/// Supermarket, so they run the shared suite from `common`, and should add
+/// every cart they price to the one tally they share.
+mod common;
+
+use std::rc::Rc;
+use std::thread;
+use rust_for_java_devs::shared_rules_implementation::{Supermarket, SyncSupermarket, Takings};
+
+market_suite!(Supermarket::<Cents>::demo_chain(1).0.remove(0));
+
+mod sync {
+    use super::*;
+    market_suite!(SyncSupermarket::<Cents>::demo_chain(1).0.remove(0));
+}
+
+#[test]
+fn chains_share_one_copy_of_each_rule() {
+    let (markets, _) = Supermarket::<Cents>::demo_chain(3);
+    for (first, other) in markets[0].rules().iter().zip(markets[2].rules()) {
+        assert!(Rc::ptr_eq(first, other));
+        // One count for each market; the list the chain was built from
+        // has already been dropped.
+        assert_eq!(3, Rc::strong_count(first));
+    }
+}
+
+#[test]
+fn takings_add_up_across_the_chain() {
+    let (markets, takings) = Supermarket::<Cents>::demo_chain(2);
+    markets[0].checkout("ABBACBBAB").unwrap();
+    markets[1].checkout("A").unwrap();
+    assert_eq!(Takings { carts: 2, total: Cents(26000) }, markets[0].takings());
+    assert_eq!(markets[1].takings(), *takings.borrow());
+}
+
+#[test]
+fn takings_add_up_across_threads() {
+    let (markets, takings) = SyncSupermarket::<Cents>::demo_chain(4);
+    thread::scope(|scope| {
+        for market in &markets {
+            scope.spawn(move || {
+                for _ in 0..100 {
+                    market.checkout("BBBBB").unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(Takings { carts: 400, total: Cents(6_000_000) }, *takings.lock().unwrap());
+}
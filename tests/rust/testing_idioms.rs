@@ -0,0 +1,72 @@
+/// Each idiom from `testing_idioms`, set to work on the demo catalog.
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+use rust_for_java_devs::receipt::Receipt;
+use rust_for_java_devs::testing_idioms::*;
+
+/// A fixture, in place of `@BeforeEach`.
+fn receipt(items: &str) -> Receipt {
+    let market: Supermarket = Supermarket::demo();
+    market.receipt(items).unwrap()
+}
+
+/// A table of cases, JUnit's `@ParameterizedTest` with a `@CsvSource`. The
+/// message names the failing case, as a parameterized test's display name
+/// would; unlike JUnit, the first failure stops the rest.
+#[test]
+fn splits_into_bundles_and_singles() {
+    let cases = [
+        // count, size, bundles, singles
+        (0, 5, 0, 0),
+        (4, 5, 0, 4),
+        (5, 5, 1, 0),
+        (11, 5, 2, 1),
+        (7, 1, 7, 0),
+    ];
+    for (count, size, bundles, singles) in cases {
+        assert_eq!(Split { bundles, singles }, split(count, size), "split({}, {})", count, size);
+    }
+}
+
+/// `assertThrows`, for the whole test.
+#[test]
+#[should_panic(expected = "bundles must hold at least one item")]
+fn refuses_empty_bundles() {
+    split(3, 0);
+}
+
+/// Without `#[should_panic]`, a panic can be caught and checked like any
+/// other value, closer to `assertThrows` returning the exception.
+#[test]
+fn empty_bundles_panic_with_a_message() {
+    let panic = std::panic::catch_unwind(|| split(3, 0)).unwrap_err();
+    assert_eq!(Some(&"bundles must hold at least one item"), panic.downcast_ref::<&str>());
+}
+
+#[test]
+fn receipts_read_fluently() {
+    assert_that(&receipt("ABBBBBBC"))
+        .has_total(Cents(25000))
+        .has_line('A', 1)
+        .has_line('B', 6)
+        .has_promotion_for('B')
+        .has_no_line_for('D');
+    assert_that(&empty_receipt()).has_total(Cents(0)).has_no_line_for('A');
+}
+
+#[test]
+#[should_panic(expected = "expected B x5, but found x6, in the receipt:\nA x1")]
+fn failed_assertions_show_the_receipt() {
+    assert_that(&receipt("ABBBBBB")).has_line('B', 5);
+}
+
+/// `#[ignore]` is `@Disabled`: skipped unless `cargo test -- --ignored`
+/// asks for it, as slow or flaky tests often are.
+#[test]
+#[ignore = "prices a million carts; run with --ignored"]
+fn prices_many_carts() {
+    let market: Supermarket = Supermarket::demo();
+    for _ in 0..1_000_000 {
+        assert_eq!(Ok(Cents(24000)), market.try_checkout("ABBACBBAB"));
+    }
+}
@@ -0,0 +1,44 @@
+/// Pricing a batch of carts on several threads should give exactly the
+/// totals, errors included, that pricing them one by one does, in the same
+/// order, whatever the number of threads.
+use std::sync::Arc;
+use proptest::prelude::*;
+use rust_for_java_devs::reasonable_implementation::{CartLimits, Supermarket};
+use rust_for_java_devs::threads_and_channels::*;
+
+/// The demo market, refusing carts of more than 20 items, so that some
+/// carts in a batch fail.
+fn market() -> Supermarket {
+    Supermarket::demo().with_cart_limits(CartLimits { max_bytes: 1000, max_items: 20 })
+}
+
+/// Checks both concurrent versions against `serial` for one batch.
+fn all_agree(carts: &[String], workers: usize) {
+    let market = market();
+    let expected = serial(&market, carts);
+    assert_eq!(expected, with_scoped_threads(&market, carts, workers), "scoped threads");
+    assert_eq!(expected, with_channels(Arc::new(market), carts.to_vec(), workers), "channels");
+}
+
+#[test]
+fn empty_batches_and_idle_workers() {
+    all_agree(&[], 4);
+    all_agree(&["ABBA".to_string()], 0);
+    all_agree(&["ABBA".to_string(), "C".to_string()], 8);
+}
+
+#[test]
+fn failures_stay_with_their_carts() {
+    let carts = vec!["A".to_string(), "B".repeat(21), "BBBBB".to_string()];
+    let totals = serial(&market(), &carts);
+    assert!(totals[0].is_ok() && totals[1].is_err() && totals[2].is_ok());
+    all_agree(&carts, 2);
+}
+
+proptest! {
+    #[test]
+    fn threads_price_like_the_serial_path(carts in prop::collection::vec("[ABCX]{0,25}", 0..40),
+                                          workers in 0usize..6) {
+        all_agree(&carts, workers);
+    }
+}
@@ -0,0 +1,52 @@
+/// A cart going from open to priced to paid, at the demo market. The steps
+/// that must not compile are in `tests/ui`.
+use rust_for_java_devs::prelude::*;
+use rust_for_java_devs::market::{CartDimension, CartLimits};
+use rust_for_java_devs::typestate_checkout::Cart;
+
+fn market() -> Supermarket {
+    Supermarket::demo()
+}
+
+#[test]
+fn carts_are_filled_priced_and_paid_for() {
+    let cart = "ABBACBBAB".chars().fold(Cart::new(), Cart::put);
+    let priced = cart.price(&market()).unwrap();
+    assert_eq!(Cents(24000), priced.total());
+    let paid = priced.pay(Cents(25000)).unwrap();
+    assert_eq!(Cents(24000), paid.total());
+    assert_eq!(Cents(1000), paid.change());
+    assert_eq!("ABBACBBAB", paid.items());
+    assert_eq!(Cents(24000), paid.into_receipt().total);
+}
+
+#[test]
+fn exact_money_leaves_no_change() {
+    let paid = Cart::new().put('A').price(&market()).unwrap().pay(Cents(2000)).unwrap();
+    assert_eq!(Cents(0), paid.change());
+}
+
+#[test]
+fn too_little_money_gives_the_priced_cart_back() {
+    let priced = Cart::new().put('C').price(&market()).unwrap();
+    let priced = priced.pay(Cents(2999)).unwrap_err();
+    assert_eq!(Cents(3000), priced.total());
+    assert!(priced.pay(Cents(3000)).is_ok());
+}
+
+#[test]
+fn reopened_carts_are_priced_afresh() {
+    let priced = Cart::new().put('A').price(&market()).unwrap();
+    assert_eq!(1, priced.receipt().lines.len());
+    let priced = priced.reopen().put('C').price(&market()).unwrap();
+    assert_eq!(Cents(5000), priced.total());
+    assert_eq!("AC", priced.items());
+}
+
+#[test]
+fn carts_the_market_refuses_are_not_priced() {
+    let market = market().with_cart_limits(CartLimits { max_items: 2, ..CartLimits::UNLIMITED });
+    let cart = Cart::new().put('A').put('A').put('A');
+    assert_eq!(Err(CheckoutError::CartTooLarge { dimension: CartDimension::Items, limit: 2 }),
+               cart.price(&market));
+}
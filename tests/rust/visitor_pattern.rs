@@ -0,0 +1,48 @@
+/// Each visitor should see every part of a receipt in order, and the `match`
+/// version should render exactly what the text visitor does.
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::reasonable_implementation::Supermarket;
+use rust_for_java_devs::reasonable_implementation::pipeline::Adjustment;
+use rust_for_java_devs::receipt::Receipt;
+use rust_for_java_devs::visitor_pattern::*;
+
+/// The demo receipt for "ABBBBB", with a made-up discount on it.
+fn discounted() -> Receipt {
+    let market: Supermarket = Supermarket::demo();
+    let mut receipt = market.receipt("ABBBBB").unwrap();
+    receipt.adjustments.push(Adjustment { label: "staff \"discount\"".to_string(), amount: Cents(-1700) });
+    receipt.total = Cents(15300);
+    receipt
+}
+
+#[test]
+fn text_renderers_list_every_part() {
+    let text = walk(&discounted(), TextRenderer::default());
+    assert_eq!("\
+A x1  $20.00
+B x5  $150.00  (5 for $150.00)
+subtotal  $170.00
+staff \"discount\"  -$17.00
+total  $153.00
+", text);
+    assert_eq!(text, render_text_with_match(&discounted()));
+}
+
+#[test]
+fn json_renderers_escape_and_separate() {
+    let market: Supermarket = Supermarket::demo();
+    assert_eq!(r#"{"lines":[],"subtotal":"$0.00","adjustments":[],"total":"$0.00"}"#,
+               walk(&market.receipt("").unwrap(), JsonRenderer::default()));
+    assert_eq!(concat!(
+        r#"{"lines":[{"product":"A","rule":"A: $20.00 each","quantity":1,"amount":"$20.00","promotions":[]},"#,
+        r#"{"product":"B","rule":"B: $50.00 each or 5 for $150.00","quantity":5,"amount":"$150.00","#,
+        r#""promotions":["5 for $150.00"]}],"subtotal":"$170.00","#,
+        r#""adjustments":[{"label":"staff \"discount\"","amount":"-$17.00"}],"total":"$153.00"}"#),
+        walk(&discounted(), JsonRenderer::default()));
+}
+
+#[test]
+fn total_extractors_find_the_total() {
+    assert_eq!(Cents(15300), walk(&discounted(), TotalExtractor::default()));
+    assert_eq!(5, parts(&discounted()).count());
+}
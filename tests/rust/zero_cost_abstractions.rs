@@ -0,0 +1,56 @@
+/// Each abstraction in `zero_cost_abstractions` gives the same answers as
+/// what it abstracts over, and takes no more memory.
+use std::mem::size_of;
+use std::marker::PhantomData;
+use rust_for_java_devs::core::counting::Counts;
+use rust_for_java_devs::core::rules::{FlatPrice, PricingRule};
+use rust_for_java_devs::money::{Cents, Overflow};
+use rust_for_java_devs::newtypes_and_units::Quantity;
+use rust_for_java_devs::reasonable_implementation::BoxedRule;
+use rust_for_java_devs::zero_cost_abstractions::*;
+
+#[test]
+fn indexes_and_iterators_sum_alike() {
+    let prices = [Cents(2000), Cents(5000), Cents(3000)];
+    let cases: [&[u32]; 4] = [&[], &[1], &[3, 5, 1], &[1, 1, 1, 1]];
+    for quantities in cases {
+        assert_eq!(subtotal_by_index(&prices, quantities), subtotal_by_iterator(&prices, quantities),
+                   "{:?}", quantities);
+    }
+    assert_eq!(Cents(34000), subtotal_by_iterator(&prices, &[3, 5, 1]));
+}
+
+#[test]
+#[should_panic(expected = "overflowed")]
+fn both_sums_check_for_overflow() {
+    subtotal_by_iterator(&[Cents(i64::MAX)], &[2]);
+}
+
+#[test]
+fn static_and_dynamic_dispatch_price_alike() {
+    let flat = [FlatPrice::new('A', Cents(2000)).unwrap(), FlatPrice::new('C', Cents(3000)).unwrap()];
+    let boxed: Vec<BoxedRule<Cents>> = flat.iter().map(|rule| Box::new(rule.clone()) as BoxedRule<Cents>).collect();
+    let counts = Counts::from([('A', 3), ('B', 1), ('C', 1)]);
+    assert_eq!(Ok(Cents(9000)), total_static(&flat, &counts));
+    assert_eq!(total_static(&flat, &counts), total_dynamic(&boxed, &counts));
+
+    let huge = Counts::from([('A', u64::MAX)]);
+    assert_eq!(Err(Overflow), total_static(&flat, &huge));
+    assert_eq!(Err(Overflow), total_dynamic(&boxed, &huge));
+}
+
+#[test]
+fn newtypes_and_markers_take_no_extra_memory() {
+    assert_eq!(size_of::<i64>(), size_of::<Cents>());
+    assert_eq!(size_of::<u32>(), size_of::<Quantity>());
+    assert_eq!(size_of::<[i64; 8]>(), size_of::<[Cents; 8]>());
+    assert_eq!(0, size_of::<PhantomData<Cents>>());
+    assert_eq!(size_of::<Box<FlatPrice<Cents>>>(), size_of::<Option<Box<FlatPrice<Cents>>>>());
+}
+
+#[test]
+fn trait_objects_are_reached_through_fat_pointers() {
+    assert_eq!(size_of::<usize>(), size_of::<&FlatPrice<Cents>>());
+    assert_eq!(2 * size_of::<usize>(), size_of::<&dyn PricingRule<Cents>>());
+    assert_eq!(2 * size_of::<usize>(), size_of::<BoxedRule<Cents>>());
+}
@@ -0,0 +1,10 @@
+// A `Supermarket` may price carts on several threads at once, so every rule
+// it holds must be `Sync`, and a rule counting in `Cell`s is not.
+use rust_for_java_devs::core::rules::FlatPrice;
+use rust_for_java_devs::interior_mutability::CellCountedRule;
+use rust_for_java_devs::prelude::*;
+
+fn main() {
+    let rule = CellCountedRule::new(FlatPrice::new('A', Cents(2000)).unwrap());
+    let _market: Supermarket = Supermarket::with_rules(vec!(Box::new(rule)));
+}
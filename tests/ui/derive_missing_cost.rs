@@ -0,0 +1,11 @@
+use rust_for_java_devs::FlatRule;
+use rust_for_java_devs::money::Cents;
+
+#[derive(FlatRule)]
+struct Apples {
+    #[rule(product)]
+    code: char,
+    each: Cents
+}
+
+fn main() {}
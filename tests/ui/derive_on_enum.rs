@@ -0,0 +1,9 @@
+use rust_for_java_devs::BundleRule;
+
+#[derive(BundleRule)]
+enum Fruit {
+    Apple,
+    Pear
+}
+
+fn main() {}
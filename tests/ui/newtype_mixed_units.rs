@@ -0,0 +1,8 @@
+// Money and a number of items do not add up, nor does money multiply money.
+use rust_for_java_devs::newtypes_and_units::Quantity;
+use rust_for_java_devs::money::Cents;
+
+fn main() {
+    let _ = Cents(2000) + Quantity(3);
+    let _ = Cents(2000) * Cents(3);
+}
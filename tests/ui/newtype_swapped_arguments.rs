@@ -0,0 +1,10 @@
+// Java would accept `priceOf(3, 'A')` for `priceOf(char, int)`, widening
+// both. A `Quantity` is no `Sku`, nor the other way round.
+use rust_for_java_devs::conversions::Sku;
+use rust_for_java_devs::newtypes_and_units::Quantity;
+use rust_for_java_devs::prelude::*;
+
+fn main() {
+    let market: Supermarket = Supermarket::new();
+    let _ = market.price_of(Quantity(3), Sku('A'));
+}
@@ -0,0 +1,5 @@
+use rust_for_java_devs::price_rules;
+
+fn main() {
+    let _rules = price_rules! { 'A' costs Cents(2000) };
+}
@@ -0,0 +1,14 @@
+// `Promotion` is `#[non_exhaustive]`, so outside its crate a `match` naming
+// every variant is still not exhaustive: a `_` arm is required.
+use rust_for_java_devs::enums_and_sealed_classes::Promotion;
+
+fn main() {
+    let promotion: Option<Promotion> = None;
+    if let Some(promotion) = promotion {
+        let _ = match promotion {
+            Promotion::MultiBuy(_) => "multibuy",
+            Promotion::SpendAndSave(_) => "spend and save",
+            Promotion::Coupon(_) => "coupon"
+        };
+    }
+}
@@ -0,0 +1,11 @@
+// Pricing moves the open cart into the priced one, so the open cart cannot
+// be filled any further, as a Java reference to it could be.
+use rust_for_java_devs::prelude::*;
+use rust_for_java_devs::typestate_checkout::Cart;
+
+fn main() {
+    let market: Supermarket = Supermarket::new();
+    let cart = Cart::new().put('A');
+    let _priced = cart.price(&market).unwrap();
+    let _ = cart.put('B');
+}
@@ -0,0 +1,7 @@
+// An open cart has no `pay` method: it must be priced first.
+use rust_for_java_devs::money::Cents;
+use rust_for_java_devs::typestate_checkout::Cart;
+
+fn main() {
+    let _ = Cart::new().put('A').pay(Cents(2000));
+}
@@ -0,0 +1,9 @@
+// A priced cart has no `put`: it must be reopened, dropping its price.
+use rust_for_java_devs::prelude::*;
+use rust_for_java_devs::typestate_checkout::Cart;
+
+fn main() {
+    let market: Supermarket = Supermarket::new();
+    let priced = Cart::new().put('A').price(&market).unwrap();
+    let _ = priced.put('C');
+}
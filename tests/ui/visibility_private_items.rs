@@ -0,0 +1,10 @@
+// A user of the crate cannot reach what is only `pub(crate)`, `pub(super)`
+// or private, however it is spelt.
+use rust_for_java_devs::modules_and_visibility::register::{self, Till};
+use rust_for_java_devs::money::Cents;
+
+fn main() {
+    let till = Till::open("Leeds", Cents(5000));
+    let _ = till.float;
+    let _ = register::balances(&till, Cents(5000));
+}